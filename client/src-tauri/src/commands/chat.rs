@@ -15,6 +15,24 @@ pub enum ChannelType {
     Dm,
 }
 
+/// End-to-end encryption state of a channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelEncryption {
+    pub channel_id: String,
+    pub encrypted: bool,
+    pub epoch: i64,
+    /// Users whose devices need our Megolm session key.
+    pub members: Vec<String>,
+}
+
+/// Disappearing message timer of a channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisappearingMessages {
+    pub channel_id: String,
+    /// Lifetime of new messages in seconds (`None` = off).
+    pub message_ttl_seconds: Option<u32>,
+}
+
 /// Channel from server.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Channel {
@@ -25,6 +43,12 @@ pub struct Channel {
     pub topic: Option<String>,
     pub user_limit: Option<u32>,
     pub position: i32,
+    #[serde(default)]
+    pub encrypted: bool,
+    #[serde(default)]
+    pub encryption_epoch: i64,
+    #[serde(default)]
+    pub message_ttl_seconds: Option<u32>,
     pub created_at: String,
 }
 
@@ -64,6 +88,8 @@ pub struct Message {
     pub thread_last_reply_at: Option<String>,
     pub edited_at: Option<String>,
     pub created_at: String,
+    #[serde(default)]
+    pub expires_at: Option<String>,
 }
 
 /// Get all channels.
@@ -169,6 +195,9 @@ pub async fn send_message(
     state: State<'_, AppState>,
     channel_id: String,
     content: String,
+    encrypted: Option<bool>,
+    nonce: Option<String>,
+    encryption_epoch: Option<i64>,
 ) -> Result<Message, String> {
     let (server_url, token) = {
         let auth = state.auth.read().await;
@@ -186,7 +215,9 @@ pub async fn send_message(
         .header("Authorization", format!("Bearer {token}"))
         .json(&serde_json::json!({
             "content": content,
-            "encrypted": false
+            "encrypted": encrypted.unwrap_or(false),
+            "nonce": nonce,
+            "encryption_epoch": encryption_epoch,
         }))
         .send()
         .await
@@ -199,6 +230,11 @@ pub async fn send_message(
         let status = response.status();
         let body = response.text().await.unwrap_or_default();
         error!("Failed to send message: {} - {}", status, body);
+        // The channel's membership epoch moved on; the caller must rotate its
+        // Megolm session and retry.
+        if status == reqwest::StatusCode::CONFLICT {
+            return Err("ENCRYPTION_EPOCH_STALE".to_string());
+        }
         return Err(format!("Failed to send message: {status}"));
     }
 
@@ -409,3 +445,110 @@ pub async fn mark_thread_read(state: State<'_, AppState>, parent_id: String) ->
     debug!("Thread {} marked as read", parent_id);
     Ok(())
 }
+
+/// Get a channel's encryption state and the users who hold its keys.
+#[command]
+pub async fn get_channel_encryption(
+    state: State<'_, AppState>,
+    channel_id: String,
+) -> Result<ChannelEncryption, String> {
+    channel_encryption_request(&state, reqwest::Method::GET, &channel_id).await
+}
+
+/// Turn on end-to-end encryption for a DM or private channel. Can't be undone.
+#[command]
+pub async fn enable_channel_encryption(
+    state: State<'_, AppState>,
+    channel_id: String,
+) -> Result<ChannelEncryption, String> {
+    channel_encryption_request(&state, reqwest::Method::PUT, &channel_id).await
+}
+
+async fn channel_encryption_request(
+    state: &AppState,
+    method: reqwest::Method,
+    channel_id: &str,
+) -> Result<ChannelEncryption, String> {
+    let (server_url, token) = {
+        let auth = state.auth.read().await;
+        (auth.server_url.clone(), auth.access_token.clone())
+    };
+
+    let server_url = server_url.ok_or("Not authenticated")?;
+    let token = token.ok_or("Not authenticated")?;
+
+    debug!("{} encryption for channel {}", method, channel_id);
+
+    let response = state
+        .http
+        .request(
+            method,
+            format!("{server_url}/api/channels/{channel_id}/encryption"),
+        )
+        .header("Authorization", format!("Bearer {token}"))
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to request channel encryption: {}", e);
+            format!("Connection failed: {e}")
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        error!(
+            "Failed to request channel encryption: {} - {}",
+            status, body
+        );
+        return Err(format!("Failed to request channel encryption: {status}"));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response: {e}"))
+}
+
+/// Set a channel's disappearing message timer; `None` turns it off.
+#[command]
+pub async fn set_disappearing_messages(
+    state: State<'_, AppState>,
+    channel_id: String,
+    message_ttl_seconds: Option<u32>,
+) -> Result<DisappearingMessages, String> {
+    let (server_url, token) = {
+        let auth = state.auth.read().await;
+        (auth.server_url.clone(), auth.access_token.clone())
+    };
+
+    let server_url = server_url.ok_or("Not authenticated")?;
+    let token = token.ok_or("Not authenticated")?;
+
+    debug!("Setting disappearing messages for channel {}", channel_id);
+
+    let response = state
+        .http
+        .put(format!(
+            "{server_url}/api/channels/{channel_id}/disappearing-messages"
+        ))
+        .header("Authorization", format!("Bearer {token}"))
+        .json(&serde_json::json!({ "message_ttl_seconds": message_ttl_seconds }))
+        .send()
+        .await
+        .map_err(|e| {
+            error!("Failed to set disappearing messages: {}", e);
+            format!("Connection failed: {e}")
+        })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        error!("Failed to set disappearing messages: {} - {}", status, body);
+        return Err(format!("Failed to set disappearing messages: {status}"));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response: {e}"))
+}
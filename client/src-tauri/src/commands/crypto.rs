@@ -11,10 +11,20 @@ use sha2::{Digest, Sha256};
 use tauri::{command, Manager, State};
 use tracing::{error, info, warn};
 use uuid::Uuid;
-use vc_crypto::olm::EncryptedMessage;
-use vc_crypto::{EncryptedBackup, RecoveryKey};
-
-use crate::crypto::{ClaimedPrekey, CryptoManager, PrekeyForUpload, PrekeyInfo};
+use vc_crypto::olm::{EncryptedMessage, SignedFallbackKey};
+use vc_crypto::recovery::MEGOLM_BACKUP_ALGORITHM;
+use vc_crypto::verification::{CancelCode, VerificationMessage, VERIFICATION_EVENT_TYPE};
+use vc_crypto::{
+    CrossSigningPublicKeys, EncryptedBackup, EncryptedFile, ForwardedRoomKey, KeyBackupAuthData,
+    RecoveryKey, RoomKeyRequest,
+};
+
+use crate::crypto::manager::BackedUpRoomKey;
+use crate::crypto::store::{MegolmShare, VerifiedKey};
+use crate::crypto::{
+    ClaimedPrekey, CryptoManager, DeviceKeys, GroupCiphertext, MessageCache, PrekeyForUpload,
+    PrekeyInfo, VerificationPeer, VerificationSignature, VerificationStatus,
+};
 use crate::AppState;
 
 /// Recovery key formatted for display (4-char chunks).
@@ -87,6 +97,8 @@ pub struct InitE2EEResponse {
     pub identity_key_curve25519: String,
     /// One-time prekeys for upload to server.
     pub prekeys: Vec<PrekeyData>,
+    /// Signed fallback key for upload to server, if not yet published.
+    pub fallback_key: Option<SignedFallbackKey>,
 }
 
 /// Prekey data for upload to server.
@@ -120,6 +132,9 @@ pub struct ClaimedPrekeyInput {
     pub identity_key_curve25519: String,
     /// One-time prekey (if available).
     pub one_time_prekey: Option<PrekeyInput>,
+    /// Signed fallback key, if the one-time prekeys ran out.
+    #[serde(default)]
+    pub fallback_key: Option<SignedFallbackKey>,
 }
 
 /// One-time prekey input.
@@ -549,7 +564,9 @@ pub async fn init_e2ee(
     // Derive encryption key from input using Argon2id (or SHA-256 for legacy stores)
     let key = derive_encryption_key(&encryption_key, &data_dir)?;
 
-    // Initialize crypto manager
+    // Initialize crypto manager and the message cache next to it
+    let message_cache = MessageCache::open(&data_dir.join("messages.db"), key)
+        .map_err(|e| format!("Failed to open message cache: {e}"))?;
     let manager =
         CryptoManager::init(data_dir, user_id, key).map_err(|e| format!("Init failed: {e}"))?;
 
@@ -565,12 +582,16 @@ pub async fn init_e2ee(
         .into_iter()
         .map(PrekeyData::from)
         .collect();
+    let fallback_key = manager
+        .get_unpublished_fallback_key()
+        .map_err(|e| format!("Failed to get fallback key: {e}"))?;
 
     let device_id = manager.device_id().to_string();
 
     // Store manager in state
     let mut crypto = state.crypto.lock().await;
     *crypto = Some(manager);
+    *state.message_cache.lock().await = Some(message_cache);
 
     info!(
         device_id = %device_id,
@@ -583,6 +604,7 @@ pub async fn init_e2ee(
         identity_key_ed25519: identity.ed25519,
         identity_key_curve25519: identity.curve25519,
         prekeys,
+        fallback_key,
     })
 }
 
@@ -635,6 +657,7 @@ pub async fn encrypt_message(
                 key_id: p.key_id,
                 public_key: p.public_key,
             }),
+            fallback_key: recipient.fallback_key,
         };
 
         // Encrypt for this device
@@ -756,6 +779,61 @@ pub async fn needs_prekey_upload(state: State<'_, AppState>) -> Result<bool, Str
         .map_err(|e| format!("Failed to check key upload status: {e}"))
 }
 
+/// Prepare a key upload after the server reports this device as low on keys.
+///
+/// Generates `prekey_count` one-time prekeys and, if requested, replaces the
+/// used fallback key. Returns everything still unpublished for upload; call
+/// `mark_prekeys_published` afterwards.
+#[command]
+pub async fn replenish_keys(
+    state: State<'_, AppState>,
+    prekey_count: usize,
+    rotate_fallback_key: bool,
+) -> Result<InitE2EEResponse, String> {
+    if prekey_count > MAX_PREKEY_COUNT {
+        return Err(format!(
+            "Prekey count exceeds maximum of {MAX_PREKEY_COUNT}"
+        ));
+    }
+
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+
+    let prekeys: Vec<PrekeyData> = manager
+        .generate_prekeys(prekey_count)
+        .map_err(|e| format!("Failed to generate prekeys: {e}"))?
+        .into_iter()
+        .map(PrekeyData::from)
+        .collect();
+    let fallback_key = if rotate_fallback_key {
+        Some(
+            manager
+                .rotate_fallback_key()
+                .map_err(|e| format!("Failed to rotate fallback key: {e}"))?,
+        )
+    } else {
+        manager
+            .get_unpublished_fallback_key()
+            .map_err(|e| format!("Failed to get fallback key: {e}"))?
+    };
+    let identity = manager
+        .get_identity_keys()
+        .map_err(|e| format!("Failed to get identity keys: {e}"))?;
+
+    info!(
+        prekey_count = prekeys.len(),
+        rotated_fallback_key = rotate_fallback_key,
+        "Prepared key replenishment"
+    );
+    Ok(InitE2EEResponse {
+        device_id: manager.device_id().to_string(),
+        identity_key_ed25519: identity.ed25519,
+        identity_key_curve25519: identity.curve25519,
+        prekeys,
+        fallback_key,
+    })
+}
+
 /// Get our Curve25519 public key (base64).
 ///
 /// This is needed for looking up our ciphertext in encrypted messages.
@@ -776,6 +854,10 @@ pub async fn get_our_curve25519_key(state: State<'_, AppState>) -> Result<String
 /// Create a new Megolm outbound session for a group/channel.
 /// Returns the exportable session key (base64) that should be shared with other members
 /// via 1:1 Olm encrypted messages.
+///
+/// # Arguments
+///
+/// * `room_id` - The channel or group room ID
 #[command]
 pub async fn create_megolm_session(
     state: State<'_, AppState>,
@@ -789,13 +871,41 @@ pub async fn create_megolm_session(
         .map_err(|e| format!("Failed to create Megolm session: {e}"))
 }
 
+/// Record the devices the current Megolm session of a room was shared with.
+/// Those devices may later ask for the session again.
+///
+/// # Arguments
+///
+/// * `room_id` - The channel or group room ID
+/// * `devices` - The devices the session key was encrypted for
+#[command]
+pub async fn record_megolm_session_shares(
+    state: State<'_, AppState>,
+    room_id: String,
+    devices: Vec<MegolmShare>,
+) -> Result<(), String> {
+    if devices.len() > MAX_RECIPIENTS {
+        return Err(format!("Too many devices (max {MAX_RECIPIENTS})"));
+    }
+
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+
+    manager
+        .record_group_session_shares(&room_id, &devices)
+        .map_err(|e| format!("Failed to record Megolm session shares: {e}"))
+}
+
 /// Encrypt a message for a group using Megolm.
+///
+/// Returns the ciphertext together with the session ID to put in the
+/// message envelope.
 #[command]
 pub async fn encrypt_group_message(
     state: State<'_, AppState>,
     room_id: String,
     plaintext: String,
-) -> Result<String, String> {
+) -> Result<GroupCiphertext, String> {
     if plaintext.len() > MAX_PLAINTEXT_LEN {
         return Err(format!(
             "Plaintext exceeds maximum size of {} KB",
@@ -828,11 +938,15 @@ pub async fn add_inbound_group_session(
 }
 
 /// Decrypt a Megolm group message.
+///
+/// `session_id` comes from the message envelope; messages sent before it was
+/// included are decrypted with the sender's current session.
 #[command]
 pub async fn decrypt_group_message(
     state: State<'_, AppState>,
     room_id: String,
     sender_key: String,
+    session_id: Option<String>,
     ciphertext: String,
 ) -> Result<String, String> {
     if ciphertext.len() > MAX_CIPHERTEXT_LEN {
@@ -846,10 +960,927 @@ pub async fn decrypt_group_message(
     let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
 
     manager
-        .decrypt_group_message(&room_id, &sender_key, &ciphertext)
+        .decrypt_group_message(&room_id, &sender_key, session_id.as_deref(), &ciphertext)
         .map_err(|e| format!("Failed to decrypt group message: {e}"))
 }
 
+// =============================================================================
+// Room Key Request Commands
+// =============================================================================
+
+/// Ask for a Megolm session this device is missing.
+///
+/// Returns the signed request to Olm-encrypt and send as a
+/// `kaiku.room_key_request` to-device message to our other devices and the
+/// session's sender, or `None` if we have the session or asked for it
+/// recently.
+///
+/// # Arguments
+///
+/// * `device_id` - This device's server device ID
+#[command]
+pub async fn request_room_key(
+    state: State<'_, AppState>,
+    device_id: String,
+    room_id: String,
+    sender_key: String,
+    session_id: String,
+) -> Result<Option<RoomKeyRequest>, String> {
+    let device_id = parse_device_id(&device_id)?;
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    manager
+        .request_room_key(device_id, &room_id, &sender_key, &session_id)
+        .map_err(|e| format!("Failed to request room key: {e}"))
+}
+
+/// Answer a decrypted room key request from another device.
+///
+/// Returns the session to Olm-encrypt and send back as a
+/// `kaiku.forwarded_room_key` to-device message, or `None` if the request is
+/// refused.
+///
+/// # Arguments
+///
+/// * `sender_user_id` - The requesting user
+/// * `sender_device` - The requesting device, with its published keys
+/// * `sender_cross_signing` - The requesting user's published cross-signing keys
+/// * `request` - The decrypted request
+#[command]
+pub async fn handle_room_key_request(
+    state: State<'_, AppState>,
+    sender_user_id: String,
+    sender_device: DeviceKeys,
+    sender_cross_signing: Option<CrossSigningPublicKeys>,
+    request: RoomKeyRequest,
+) -> Result<Option<ForwardedRoomKey>, String> {
+    let sender_user_id =
+        Uuid::parse_str(&sender_user_id).map_err(|e| format!("Invalid user ID: {e}"))?;
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    let forwarded = manager
+        .respond_to_room_key_request(
+            sender_user_id,
+            &sender_device,
+            sender_cross_signing.as_ref(),
+            &request,
+        )
+        .map_err(|e| format!("Invalid room key request: {e}"))?;
+
+    if forwarded.is_none() {
+        info!(
+            sender_device_id = %sender_device.device_id,
+            "Refused room key request"
+        );
+    }
+    Ok(forwarded)
+}
+
+/// Import a decrypted forwarded room key from another device.
+///
+/// Returns whether the session was imported; messages in its room should
+/// then be decrypted again.
+///
+/// # Arguments
+///
+/// * `sender_user_id` - The forwarding user
+/// * `sender_device` - The forwarding device, with its published keys
+/// * `forwarded` - The decrypted forwarded key
+#[command]
+pub async fn add_forwarded_room_key(
+    state: State<'_, AppState>,
+    sender_user_id: String,
+    sender_device: DeviceKeys,
+    forwarded: ForwardedRoomKey,
+) -> Result<bool, String> {
+    let sender_user_id =
+        Uuid::parse_str(&sender_user_id).map_err(|e| format!("Invalid user ID: {e}"))?;
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    manager
+        .add_forwarded_room_key(sender_user_id, &sender_device, &forwarded)
+        .map_err(|e| format!("Failed to add forwarded room key: {e}"))
+}
+
+// =============================================================================
+// Encrypted Attachment Commands
+// =============================================================================
+
+/// A file encrypted for upload to an end-to-end encrypted conversation.
+#[derive(Debug, Serialize)]
+pub struct EncryptedAttachment {
+    /// Ciphertext to upload (base64).
+    pub ciphertext: String,
+    /// Key material to embed in the encrypted message body.
+    pub file: EncryptedFile,
+}
+
+/// Encrypt a file (base64) under a fresh attachment key.
+#[command]
+pub async fn encrypt_attachment(data: String) -> Result<EncryptedAttachment, String> {
+    let data = STANDARD
+        .decode(data)
+        .map_err(|e| format!("Invalid file data: {e}"))?;
+    let (ciphertext, file) =
+        tauri::async_runtime::spawn_blocking(move || vc_crypto::encrypt_attachment(&data))
+            .await
+            .map_err(|e| format!("Encryption task failed: {e}"))?;
+
+    Ok(EncryptedAttachment {
+        ciphertext: STANDARD.encode(ciphertext),
+        file,
+    })
+}
+
+/// Download an encrypted attachment (or its encrypted thumbnail) and decrypt
+/// it with the key from the message body. Returns the raw file bytes.
+#[command]
+pub async fn download_encrypted_attachment(
+    state: State<'_, AppState>,
+    attachment_id: String,
+    variant: Option<String>,
+    file: EncryptedFile,
+) -> Result<tauri::ipc::Response, String> {
+    let (server_url, token) = {
+        let auth = state.auth.read().await;
+        (auth.server_url.clone(), auth.access_token.clone())
+    };
+    let server_url = server_url.ok_or("Not connected")?;
+    let token = token.ok_or("Not authenticated")?;
+
+    let mut url = format!("{server_url}/api/messages/attachments/{attachment_id}/download");
+    if let Some(ref variant) = variant {
+        let encoded: String = form_urlencoded::byte_serialize(variant.as_bytes()).collect();
+        url.push_str("?variant=");
+        url.push_str(&encoded);
+    }
+    let response = state
+        .http
+        .get(url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Server error: {}", response.status()));
+    }
+
+    let ciphertext = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Download failed: {e}"))?;
+    let plaintext = tauri::async_runtime::spawn_blocking(move || {
+        vc_crypto::decrypt_attachment(&ciphertext, &file)
+    })
+    .await
+    .map_err(|e| format!("Decryption task failed: {e}"))?
+    .map_err(|e| format!("Failed to decrypt attachment: {e}"))?;
+
+    Ok(tauri::ipc::Response::new(plaintext.to_vec()))
+}
+
+// =============================================================================
+// Megolm Key Backup Commands
+// =============================================================================
+
+/// Sessions uploaded per `sync_key_backup` round.
+const KEY_BACKUP_BATCH: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct CreateBackupVersionResponse {
+    version: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct BackupVersionResponse {
+    version: i64,
+    algorithm: String,
+    auth_data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct BackupSessionsResponse {
+    sessions: Vec<BackedUpRoomKey>,
+    #[serde(default)]
+    has_more: bool,
+}
+
+/// Sessions requested per page when restoring a key backup.
+const BACKUP_SESSIONS_PAGE_SIZE: usize = 500;
+
+/// Start a new server-side key backup version for `recovery_key` and queue
+/// all inbound Megolm sessions for upload.
+///
+/// Replaces any existing backup version. Returns the new version.
+#[command]
+pub async fn enable_key_backup(
+    state: State<'_, AppState>,
+    recovery_key: String,
+) -> Result<i64, String> {
+    if recovery_key.len() > MAX_RECOVERY_KEY_LEN {
+        return Err(format!(
+            "Recovery key exceeds maximum length of {MAX_RECOVERY_KEY_LEN} bytes"
+        ));
+    }
+    let key = RecoveryKey::from_formatted_string(&recovery_key)
+        .map_err(|e| format!("Invalid recovery key: {e}"))?;
+    let (auth_data, backup_key) = KeyBackupAuthData::create(&key);
+
+    let version = {
+        let auth = state.auth.read().await;
+        let server_url = auth.server_url.as_ref().ok_or("Not connected")?;
+        let token = auth.access_token.as_ref().ok_or("Not authenticated")?;
+
+        let response = state
+            .http
+            .post(format!("{server_url}/api/keys/backup/version"))
+            .bearer_auth(token)
+            .json(&serde_json::json!({
+                "algorithm": MEGOLM_BACKUP_ALGORITHM,
+                "auth_data": auth_data,
+            }))
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {e}"))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Server error: {}", response.status()));
+        }
+
+        response
+            .json::<CreateBackupVersionResponse>()
+            .await
+            .map_err(|e| format!("Parse error: {e}"))?
+            .version
+    };
+
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    manager
+        .enable_key_backup(version, &backup_key)
+        .map_err(|e| format!("Failed to enable key backup: {e}"))?;
+
+    info!(version, "Key backup enabled");
+    Ok(version)
+}
+
+/// Upload inbound Megolm sessions that aren't in the key backup yet.
+///
+/// Does nothing if key backup isn't enabled on this device. If the backup
+/// version was replaced from another device, backup is disabled here until
+/// it is restored or enabled again. Returns the number of uploaded sessions.
+#[command]
+pub async fn sync_key_backup(state: State<'_, AppState>) -> Result<usize, String> {
+    let (server_url, token) = {
+        let auth = state.auth.read().await;
+        (
+            auth.server_url.clone().ok_or("Not connected")?,
+            auth.access_token.clone().ok_or("Not authenticated")?,
+        )
+    };
+
+    let mut uploaded = 0;
+    loop {
+        let (version, keys) = {
+            let crypto = state.crypto.lock().await;
+            let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+            if manager
+                .key_backup_version()
+                .map_err(|e| format!("Failed to read key backup: {e}"))?
+                .is_none()
+            {
+                return Ok(uploaded);
+            }
+            manager
+                .pending_key_backups(KEY_BACKUP_BATCH)
+                .map_err(|e| format!("Failed to prepare key backup: {e}"))?
+        };
+        if keys.is_empty() {
+            break;
+        }
+
+        for key in keys {
+            let mut url =
+                url::Url::parse(&server_url).map_err(|e| format!("Invalid server URL: {e}"))?;
+            // Session IDs are base64 and may contain '/'
+            url.path_segments_mut()
+                .map_err(|()| "Invalid server URL: cannot be a base")?
+                .extend(&[
+                    "api",
+                    "keys",
+                    "backup",
+                    &version.to_string(),
+                    "rooms",
+                    &key.room_id,
+                    "sessions",
+                    &key.session_id,
+                ]);
+
+            let response = state
+                .http
+                .put(url)
+                .bearer_auth(&token)
+                .json(&serde_json::json!({
+                    "first_message_index": key.first_message_index,
+                    "nonce": key.key.nonce,
+                    "ciphertext": key.key.ciphertext,
+                }))
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {e}"))?;
+
+            if response.status() == reqwest::StatusCode::NOT_FOUND {
+                warn!(version, "Key backup version is gone, disabling key backup");
+                let crypto = state.crypto.lock().await;
+                if let Some(manager) = crypto.as_ref() {
+                    manager
+                        .disable_key_backup()
+                        .map_err(|e| format!("Failed to disable key backup: {e}"))?;
+                }
+                return Err("Key backup was replaced on another device".to_string());
+            }
+            if !response.status().is_success() {
+                return Err(format!("Server error: {}", response.status()));
+            }
+
+            let crypto = state.crypto.lock().await;
+            let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+            manager
+                .mark_key_backed_up(&key.room_id, &key.session_id)
+                .map_err(|e| format!("Failed to update key backup: {e}"))?;
+            uploaded += 1;
+        }
+    }
+
+    if uploaded > 0 {
+        info!(uploaded, "Key backup synced");
+    }
+    Ok(uploaded)
+}
+
+/// Download the current key backup with `recovery_key`, import its Megolm
+/// sessions and keep backing up new sessions to it.
+///
+/// Returns the number of imported sessions.
+#[command]
+pub async fn restore_key_backup(
+    state: State<'_, AppState>,
+    recovery_key: String,
+) -> Result<usize, String> {
+    if recovery_key.len() > MAX_RECOVERY_KEY_LEN {
+        return Err(format!(
+            "Recovery key exceeds maximum length of {MAX_RECOVERY_KEY_LEN} bytes"
+        ));
+    }
+    let key = RecoveryKey::from_formatted_string(&recovery_key)
+        .map_err(|e| format!("Invalid recovery key: {e}"))?;
+
+    let version = {
+        let auth = state.auth.read().await;
+        let server_url = auth.server_url.as_ref().ok_or("Not connected")?;
+        let token = auth.access_token.as_ref().ok_or("Not authenticated")?;
+
+        let response = state
+            .http
+            .get(format!("{server_url}/api/keys/backup/version"))
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {e}"))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err("No key backup found".to_string());
+        }
+        if !response.status().is_success() {
+            return Err(format!("Server error: {}", response.status()));
+        }
+        let version: BackupVersionResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Parse error: {e}"))?;
+        if version.algorithm != MEGOLM_BACKUP_ALGORITHM {
+            return Err(format!(
+                "Unsupported key backup algorithm: {}",
+                version.algorithm
+            ));
+        }
+
+        version
+    };
+
+    let auth_data: KeyBackupAuthData = serde_json::from_value(version.auth_data)
+        .map_err(|e| format!("Invalid key backup: {e}"))?;
+    let backup_key = auth_data
+        .backup_key(&key)
+        .map_err(|e| format!("Invalid recovery key: {e}"))?;
+
+    // Import page by page so large backups aren't held in memory at once
+    let mut restored = 0;
+    let mut after: Option<(String, String)> = None;
+    loop {
+        let page = {
+            let auth = state.auth.read().await;
+            let server_url = auth.server_url.as_ref().ok_or("Not connected")?;
+            let token = auth.access_token.as_ref().ok_or("Not authenticated")?;
+
+            let mut url = url::Url::parse(&format!(
+                "{server_url}/api/keys/backup/{}/sessions",
+                version.version
+            ))
+            .map_err(|e| format!("Invalid server URL: {e}"))?;
+            {
+                let mut query = url.query_pairs_mut();
+                query.append_pair("limit", &BACKUP_SESSIONS_PAGE_SIZE.to_string());
+                if let Some((room_id, session_id)) = &after {
+                    query.append_pair("after_room_id", room_id);
+                    query.append_pair("after_session_id", session_id);
+                }
+            }
+            let response = state
+                .http
+                .get(url)
+                .bearer_auth(token)
+                .send()
+                .await
+                .map_err(|e| format!("Request failed: {e}"))?;
+
+            if !response.status().is_success() {
+                return Err(format!("Server error: {}", response.status()));
+            }
+            response
+                .json::<BackupSessionsResponse>()
+                .await
+                .map_err(|e| format!("Parse error: {e}"))?
+        };
+
+        {
+            let crypto = state.crypto.lock().await;
+            let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+            restored += manager
+                .restore_key_backup(version.version, &backup_key, &page.sessions)
+                .map_err(|e| format!("Failed to restore key backup: {e}"))?;
+        }
+
+        match page.sessions.last() {
+            Some(last) if page.has_more => {
+                after = Some((last.room_id.clone(), last.session_id.clone()));
+            }
+            _ => break,
+        }
+    }
+
+    info!(version = version.version, restored, "Key backup restored");
+    Ok(restored)
+}
+
+// =============================================================================
+// Cross-Signing & Verification Commands
+// =============================================================================
+
+/// Result of a verification step.
+#[derive(Debug, Serialize)]
+pub struct VerificationUpdate {
+    /// To-device event type for `messages`.
+    pub event_type: &'static str,
+    /// Messages to Olm-encrypt and send to the peer device, in order.
+    pub messages: Vec<VerificationMessage>,
+    /// State after this step.
+    pub status: VerificationStatus,
+}
+
+/// Send a JSON body to the server with the current session.
+async fn send_json(
+    state: &State<'_, AppState>,
+    method: reqwest::Method,
+    path: &str,
+    body: &serde_json::Value,
+) -> Result<(), String> {
+    let auth = state.auth.read().await;
+    let server_url = auth.server_url.as_ref().ok_or("Not connected")?;
+    let token = auth.access_token.as_ref().ok_or("Not authenticated")?;
+
+    let response = state
+        .http
+        .request(method, format!("{server_url}{path}"))
+        .bearer_auth(token)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Server error: {}", response.status()));
+    }
+    Ok(())
+}
+
+fn parse_device_id(device_id: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(device_id).map_err(|e| format!("Invalid device ID: {e}"))
+}
+
+/// Build the update for `transaction_id`, publishing a cross-signing
+/// signature if the verification just finished.
+async fn verification_update(
+    state: &State<'_, AppState>,
+    transaction_id: &str,
+    messages: Vec<VerificationMessage>,
+) -> Result<VerificationUpdate, String> {
+    let (status, signature) = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        let status = manager
+            .verification_status(transaction_id)
+            .map_err(|e| format!("Verification failed: {e}"))?;
+        let signature = manager
+            .verification_signature(transaction_id)
+            .map_err(|e| format!("Failed to sign verified keys: {e}"))?;
+        (status, signature)
+    };
+
+    if let Some(signature) = signature {
+        let body = match signature {
+            VerificationSignature::Device {
+                device_id,
+                signature,
+            } => {
+                serde_json::json!({ "devices": [{ "device_id": device_id, "signature": signature }] })
+            }
+            VerificationSignature::User { user_id, signature } => {
+                serde_json::json!({ "users": [{ "user_id": user_id, "signature": signature }] })
+            }
+        };
+        // The local verification stands even if publishing fails; it can be retried later
+        if let Err(e) = send_json(state, reqwest::Method::POST, "/api/keys/signatures", &body).await
+        {
+            warn!(transaction_id = %transaction_id, "Failed to publish verification signature: {e}");
+        }
+    }
+
+    Ok(VerificationUpdate {
+        event_type: VERIFICATION_EVENT_TYPE,
+        messages,
+        status,
+    })
+}
+
+/// Create (or, with `reset`, replace) cross-signing keys and publish them.
+///
+/// Also signs this device with the new self-signing key. New keys are only
+/// kept once the server has accepted them. Replacing published keys requires
+/// the account password (local accounts) and an MFA code (when MFA is enabled).
+///
+/// # Arguments
+///
+/// * `device_id` - This device's server device ID
+/// * `reset` - Replace existing keys, invalidating all previous signatures
+/// * `password` - Current account password, to replace published keys
+/// * `mfa_code` - TOTP or backup code, to replace published keys
+#[command]
+pub async fn bootstrap_cross_signing(
+    state: State<'_, AppState>,
+    device_id: String,
+    reset: bool,
+    password: Option<String>,
+    mfa_code: Option<String>,
+) -> Result<CrossSigningPublicKeys, String> {
+    let device_id = parse_device_id(&device_id)?;
+
+    let (identity, signature) = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        let identity = manager
+            .bootstrap_cross_signing(reset)
+            .map_err(|e| format!("Failed to create cross-signing keys: {e}"))?;
+        let device = manager
+            .get_identity_keys()
+            .map_err(|e| format!("Failed to get identity keys: {e}"))?;
+        let signature =
+            identity.sign_device(&device_id.to_string(), &device.ed25519, &device.curve25519);
+        (identity, signature)
+    };
+    let keys = identity.public_keys();
+
+    let mut body = serde_json::to_value(&keys).map_err(|e| format!("Serialization error: {e}"))?;
+    body["password"] = serde_json::json!(password);
+    body["mfa_code"] = serde_json::json!(mfa_code);
+    send_json(
+        &state,
+        reqwest::Method::PUT,
+        "/api/keys/cross-signing",
+        &body,
+    )
+    .await?;
+
+    {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .save_cross_signing(&identity)
+            .map_err(|e| format!("Failed to save cross-signing keys: {e}"))?;
+    }
+
+    send_json(
+        &state,
+        reqwest::Method::POST,
+        "/api/keys/signatures",
+        &serde_json::json!({ "devices": [{ "device_id": device_id, "signature": signature }] }),
+    )
+    .await?;
+
+    info!(reset = reset, "Cross-signing keys published");
+    Ok(keys)
+}
+
+/// Start verifying another device.
+///
+/// # Arguments
+///
+/// * `device_id` - This device's server device ID
+/// * `peer` - The device to verify, with its published keys
+#[command]
+pub async fn request_device_verification(
+    state: State<'_, AppState>,
+    device_id: String,
+    peer: VerificationPeer,
+) -> Result<VerificationUpdate, String> {
+    let device_id = parse_device_id(&device_id)?;
+    let request = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .request_verification(device_id, peer)
+            .map_err(|e| format!("Failed to request verification: {e}"))?
+    };
+    let transaction_id = request.transaction_id().to_string();
+    verification_update(&state, &transaction_id, vec![request]).await
+}
+
+/// Process a decrypted verification message from another device.
+///
+/// # Arguments
+///
+/// * `device_id` - This device's server device ID
+/// * `peer` - The sending device, with its published keys
+/// * `message` - The decrypted verification message
+#[command]
+pub async fn handle_verification_message(
+    state: State<'_, AppState>,
+    device_id: String,
+    peer: VerificationPeer,
+    message: VerificationMessage,
+) -> Result<VerificationUpdate, String> {
+    let device_id = parse_device_id(&device_id)?;
+    let replies = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .receive_verification(device_id, peer, &message)
+            .map_err(|e| format!("Verification failed: {e}"))?
+    };
+    verification_update(&state, message.transaction_id(), replies).await
+}
+
+/// Accept an incoming verification request.
+#[command]
+pub async fn accept_device_verification(
+    state: State<'_, AppState>,
+    transaction_id: String,
+) -> Result<VerificationUpdate, String> {
+    let ready = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .accept_verification(&transaction_id)
+            .map_err(|e| format!("Verification failed: {e}"))?
+    };
+    verification_update(&state, &transaction_id, vec![ready]).await
+}
+
+/// Begin emoji/decimal (SAS) comparison.
+#[command]
+pub async fn start_sas_verification(
+    state: State<'_, AppState>,
+    transaction_id: String,
+) -> Result<VerificationUpdate, String> {
+    let start = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .start_sas(&transaction_id)
+            .map_err(|e| format!("Verification failed: {e}"))?
+    };
+    verification_update(&state, &transaction_id, vec![start]).await
+}
+
+/// Confirm that the emoji shown on both devices match.
+#[command]
+pub async fn confirm_sas_verification(
+    state: State<'_, AppState>,
+    transaction_id: String,
+) -> Result<VerificationUpdate, String> {
+    let messages = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .confirm_sas(&transaction_id)
+            .map_err(|e| format!("Verification failed: {e}"))?
+    };
+    verification_update(&state, &transaction_id, messages).await
+}
+
+/// Cancel a verification, e.g. because the emoji didn't match.
+#[command]
+pub async fn cancel_device_verification(
+    state: State<'_, AppState>,
+    transaction_id: String,
+) -> Result<VerificationUpdate, String> {
+    let cancel = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .cancel_verification(&transaction_id, CancelCode::User)
+            .map_err(|e| format!("Verification failed: {e}"))?
+    };
+    verification_update(&state, &transaction_id, vec![cancel]).await
+}
+
+/// Get the QR code for the peer device to scan.
+#[command]
+pub async fn get_verification_qr_code(
+    state: State<'_, AppState>,
+    transaction_id: String,
+) -> Result<String, String> {
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    manager
+        .verification_qr_code(&transaction_id)
+        .map_err(|e| format!("Failed to create QR code: {e}"))
+}
+
+/// Check a QR code scanned from the peer device.
+#[command]
+pub async fn scan_verification_qr_code(
+    state: State<'_, AppState>,
+    transaction_id: String,
+    code: String,
+) -> Result<VerificationUpdate, String> {
+    let reply = {
+        let crypto = state.crypto.lock().await;
+        let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+        manager
+            .scan_verification_qr(&transaction_id, &code)
+            .map_err(|e| format!("Verification failed: {e}"))?
+    };
+    verification_update(&state, &transaction_id, vec![reply]).await
+}
+
+/// Get the current state of a verification.
+#[command]
+pub async fn get_verification_status(
+    state: State<'_, AppState>,
+    transaction_id: String,
+) -> Result<VerificationStatus, String> {
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    manager
+        .verification_status(&transaction_id)
+        .map_err(|e| format!("Verification failed: {e}"))
+}
+
+/// Get the keys of a user this device has verified.
+#[command]
+pub async fn get_verified_keys(
+    state: State<'_, AppState>,
+    user_id: String,
+) -> Result<Vec<VerifiedKey>, String> {
+    let user_id = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid user ID: {e}"))?;
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    manager
+        .verified_keys(user_id)
+        .map_err(|e| format!("Failed to load verified keys: {e}"))
+}
+
+// =============================================================================
+// Device Management
+// =============================================================================
+
+/// One of the current user's registered devices.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OwnDevice {
+    pub device_id: String,
+    pub device_name: Option<String>,
+    pub identity_key_ed25519: String,
+    pub identity_key_curve25519: String,
+    pub cross_signing_signature: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OwnDevicesResponse {
+    devices: Vec<OwnDevice>,
+}
+
+/// List the current user's registered devices, most recently seen first.
+#[command]
+pub async fn get_own_devices(state: State<'_, AppState>) -> Result<Vec<OwnDevice>, String> {
+    let auth = state.auth.read().await;
+    let server_url = auth.server_url.as_ref().ok_or("Not connected")?;
+    let token = auth.access_token.as_ref().ok_or("Not authenticated")?;
+
+    let response = state
+        .http
+        .get(format!("{server_url}/api/keys/devices"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Server error: {}", response.status()));
+    }
+
+    response
+        .json::<OwnDevicesResponse>()
+        .await
+        .map(|r| r.devices)
+        .map_err(|e| format!("Parse error: {e}"))
+}
+
+/// Rename one of the current user's devices.
+///
+/// # Arguments
+///
+/// * `device_id` - Server device ID
+/// * `device_name` - New name, or `None` to clear it
+#[command]
+pub async fn rename_device(
+    state: State<'_, AppState>,
+    device_id: String,
+    device_name: Option<String>,
+) -> Result<(), String> {
+    let device_id = parse_device_id(&device_id)?;
+    send_json(
+        &state,
+        reqwest::Method::PATCH,
+        &format!("/api/keys/devices/{device_id}"),
+        &serde_json::json!({ "device_name": device_name }),
+    )
+    .await
+}
+
+/// Delete one of the current user's devices and log out its session.
+///
+/// The server refuses to delete the device of the current session.
+#[command]
+pub async fn delete_device(state: State<'_, AppState>, device_id: String) -> Result<(), String> {
+    let device_id = parse_device_id(&device_id)?;
+
+    let auth = state.auth.read().await;
+    let server_url = auth.server_url.as_ref().ok_or("Not connected")?;
+    let token = auth.access_token.as_ref().ok_or("Not authenticated")?;
+
+    let mut request = state
+        .http
+        .delete(format!("{server_url}/api/keys/devices/{device_id}"))
+        .bearer_auth(token);
+    // Lets the server recognise the device of this session
+    if let Some(refresh_token) = auth.refresh_token.as_ref() {
+        request = request.header("X-Refresh-Token", refresh_token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Server error: {}", response.status()));
+    }
+
+    info!(device_id = %device_id, "Device deleted");
+    Ok(())
+}
+
+/// Handle a `device_removed` WebSocket event.
+///
+/// Drops the Olm session with the removed device and discards all Megolm
+/// outbound sessions, so the next message in each room goes out under a
+/// fresh session (see `create_megolm_session`).
+#[command]
+pub async fn handle_device_removed(
+    state: State<'_, AppState>,
+    user_id: String,
+    identity_key_curve25519: String,
+) -> Result<(), String> {
+    let user_id = Uuid::parse_str(&user_id).map_err(|e| format!("Invalid user ID: {e}"))?;
+    let crypto = state.crypto.lock().await;
+    let manager = crypto.as_ref().ok_or("E2EE not initialized")?;
+    manager
+        .forget_device(user_id, &identity_key_curve25519)
+        .map_err(|e| format!("Failed to forget device: {e}"))?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use tempfile::tempdir;
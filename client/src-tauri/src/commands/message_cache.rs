@@ -0,0 +1,170 @@
+//! Message Cache Commands
+//!
+//! Offline history and local search over decrypted messages.
+
+use tauri::{command, State};
+use tracing::debug;
+use uuid::Uuid;
+
+use crate::crypto::message_cache::{CachedMessage, CachedMessagePage, RetentionPolicy};
+use crate::AppState;
+
+/// Default page size, matching the server's.
+const DEFAULT_PAGE_LIMIT: u32 = 50;
+
+/// Largest page or search result the cache returns.
+const MAX_LIMIT: u32 = 200;
+
+fn parse_id(id: &str) -> Result<Uuid, String> {
+    Uuid::parse_str(id).map_err(|e| format!("Invalid ID: {e}"))
+}
+
+/// Cache decrypted messages, e.g. new or edited ones from the WebSocket.
+/// Undecryptable messages are skipped.
+#[command]
+pub async fn cache_messages(
+    state: State<'_, AppState>,
+    messages: Vec<CachedMessage>,
+) -> Result<(), String> {
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    cache
+        .store_messages(&messages)
+        .map_err(|e| format!("Failed to cache messages: {e}"))
+}
+
+/// Reconcile the cache with a page of messages fetched from the server.
+///
+/// Caches the page, drops cached messages the server no longer returns, and
+/// returns the page with undecryptable messages replaced by cached copies.
+///
+/// # Arguments
+///
+/// * `channel_id` - The channel the page belongs to
+/// * `before` - The `before` cursor the page was fetched with
+/// * `messages` - The page, newest first, as decrypted by the client
+/// * `has_more` - Whether the server has older messages
+#[command]
+pub async fn sync_cached_messages(
+    state: State<'_, AppState>,
+    channel_id: String,
+    before: Option<String>,
+    messages: Vec<CachedMessage>,
+    has_more: bool,
+) -> Result<Vec<CachedMessage>, String> {
+    let channel_id = parse_id(&channel_id)?;
+    let before = before.as_deref().map(parse_id).transpose()?;
+
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    cache
+        .reconcile(channel_id, before, messages, has_more)
+        .map_err(|e| format!("Failed to sync message cache: {e}"))
+}
+
+/// Get cached messages of a channel, newest first, for offline use.
+#[command]
+pub async fn get_cached_messages(
+    state: State<'_, AppState>,
+    channel_id: String,
+    before: Option<String>,
+    limit: Option<u32>,
+) -> Result<CachedMessagePage, String> {
+    let channel_id = parse_id(&channel_id)?;
+    let before = before.as_deref().map(parse_id).transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_LIMIT);
+
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    cache
+        .message_page(channel_id, before, limit)
+        .map_err(|e| format!("Failed to read message cache: {e}"))
+}
+
+/// Drop a deleted message from the cache.
+#[command]
+pub async fn remove_cached_message(
+    state: State<'_, AppState>,
+    message_id: String,
+) -> Result<bool, String> {
+    let message_id = parse_id(&message_id)?;
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    cache
+        .remove_message(message_id)
+        .map_err(|e| format!("Failed to update message cache: {e}"))
+}
+
+/// Search cached messages for all words of `query`, newest first.
+///
+/// # Arguments
+///
+/// * `query` - Words to find; matched whole and case-insensitively
+/// * `channel_ids` - Only search these channels
+/// * `limit` - Maximum number of results
+#[command]
+pub async fn search_cached_messages(
+    state: State<'_, AppState>,
+    query: String,
+    channel_ids: Option<Vec<String>>,
+    limit: Option<u32>,
+) -> Result<Vec<CachedMessage>, String> {
+    let channel_ids = channel_ids
+        .map(|ids| {
+            ids.iter()
+                .map(String::as_str)
+                .map(parse_id)
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_LIMIT);
+
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    let results = cache
+        .search(&query, channel_ids.as_deref(), limit)
+        .map_err(|e| format!("Failed to search message cache: {e}"))?;
+    debug!(results = results.len(), "Searched message cache");
+    Ok(results)
+}
+
+/// Get the message cache retention policy.
+#[command]
+pub async fn get_message_cache_retention(
+    state: State<'_, AppState>,
+) -> Result<RetentionPolicy, String> {
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    cache
+        .retention()
+        .map_err(|e| format!("Failed to read retention policy: {e}"))
+}
+
+/// Change the message cache retention policy.
+/// Returns the number of messages dropped under the new policy.
+#[command]
+pub async fn set_message_cache_retention(
+    state: State<'_, AppState>,
+    policy: RetentionPolicy,
+) -> Result<usize, String> {
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    cache
+        .set_retention(policy)
+        .map_err(|e| format!("Failed to set retention policy: {e}"))
+}
+
+/// Clear the cache of one channel, or all of it.
+/// Returns the number of removed messages.
+#[command]
+pub async fn clear_message_cache(
+    state: State<'_, AppState>,
+    channel_id: Option<String>,
+) -> Result<usize, String> {
+    let channel_id = channel_id.as_deref().map(parse_id).transpose()?;
+    let cache = state.message_cache.lock().await;
+    let cache = cache.as_ref().ok_or("Message cache not initialized")?;
+    cache
+        .clear(channel_id)
+        .map_err(|e| format!("Failed to clear message cache: {e}"))
+}
@@ -10,6 +10,7 @@ pub mod chat;
 pub mod clipboard;
 pub mod crypto;
 pub mod favorites;
+pub mod message_cache;
 pub mod pages;
 pub mod pins;
 pub mod preferences;
@@ -5,18 +5,34 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(feature = "megolm")]
+use std::time::{Duration, Instant};
 
+#[cfg(feature = "megolm")]
+use base64::engine::general_purpose::STANDARD;
+#[cfg(feature = "megolm")]
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
 #[cfg(feature = "megolm")]
 use vc_crypto::megolm::{MegolmInboundSession, MegolmOutboundSession};
-use vc_crypto::olm::{EncryptedMessage, IdentityKeyPair, OlmAccount};
+use vc_crypto::olm::{EncryptedMessage, IdentityKeyPair, OlmAccount, SignedFallbackKey};
+#[cfg(feature = "megolm")]
+use vc_crypto::recovery::BackupKey;
 use vc_crypto::types::{Curve25519PublicKey, KeyId};
+use vc_crypto::verification::{
+    CancelCode, SasEmoji, Verification, VerificationMessage, VerificationParty, VerificationState,
+};
+use vc_crypto::{CrossSigningIdentity, CrossSigningPublicKeys};
+#[cfg(feature = "megolm")]
+use vc_crypto::{EncryptedRoomKey, ForwardedRoomKey, RoomKeyRequest};
+#[cfg(feature = "megolm")]
+use zeroize::Zeroizing;
 
 #[cfg(feature = "megolm")]
-use super::store::MegolmInboundKey;
-use super::store::{KeyStoreMetadata, LocalKeyStore, SessionKey};
+use super::store::{KeyBackupState, MegolmInboundKey, MegolmShare};
+use super::store::{KeyStoreMetadata, LocalKeyStore, SessionKey, VerifiedKey};
 
 /// Crypto manager errors.
 #[derive(Debug, Error)]
@@ -49,6 +65,18 @@ pub enum CryptoManagerError {
 
     #[error("Key store lock poisoned: {0}")]
     LockPoisoned(String),
+
+    /// Cross-signing keys have not been created or restored on this device.
+    #[error("Cross-signing is not set up on this device")]
+    CrossSigningNotSetUp,
+
+    /// No verification with this transaction ID.
+    #[error("Unknown verification: {0}")]
+    VerificationNotFound(String),
+
+    /// This device doesn't upload to a key backup.
+    #[error("Key backup is not enabled on this device")]
+    KeyBackupNotEnabled,
 }
 
 /// Crypto manager result type.
@@ -66,6 +94,9 @@ pub struct DeviceKeys {
     pub identity_key_ed25519: String,
     /// Curve25519 identity key (base64).
     pub identity_key_curve25519: String,
+    /// Self-signing key signature over this device, if cross-signed.
+    #[serde(default)]
+    pub cross_signing_signature: Option<String>,
 }
 
 /// Claimed prekey from server.
@@ -79,6 +110,9 @@ pub struct ClaimedPrekey {
     pub identity_key_curve25519: String,
     /// One-time prekey (if available).
     pub one_time_prekey: Option<PrekeyInfo>,
+    /// Signed fallback key, handed out once the one-time prekeys run out.
+    #[serde(default)]
+    pub fallback_key: Option<SignedFallbackKey>,
 }
 
 /// One-time prekey info.
@@ -109,6 +143,122 @@ pub struct PrekeyForUpload {
     pub public_key: String,
 }
 
+/// Megolm session encrypted for the server-side key backup.
+#[cfg(feature = "megolm")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackedUpRoomKey {
+    /// The channel or group room ID.
+    pub room_id: String,
+    /// Megolm session ID.
+    pub session_id: String,
+    /// First message index the session can decrypt.
+    pub first_message_index: u32,
+    /// The encrypted [`RoomKeyBackupData`].
+    #[serde(flatten)]
+    pub key: EncryptedRoomKey,
+}
+
+/// Plaintext of a [`BackedUpRoomKey`].
+#[cfg(feature = "megolm")]
+#[derive(Serialize, Deserialize)]
+struct RoomKeyBackupData {
+    /// The sender's device Curve25519 public key (base64).
+    sender_key: String,
+    /// Session exported at its first known index (base64).
+    session_key: String,
+}
+
+/// Megolm ciphertext together with the session that encrypted it.
+#[cfg(feature = "megolm")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupCiphertext {
+    /// Megolm ciphertext (base64).
+    pub ciphertext: String,
+    /// Megolm session ID, needed by recipients to find or request the session.
+    pub session_id: String,
+}
+
+/// Ask for the same missing session again after this long without an answer.
+#[cfg(feature = "megolm")]
+const ROOM_KEY_REQUEST_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Maximum number of sessions forwarded to one device per
+/// [`FORWARDED_KEY_WINDOW`].
+#[cfg(feature = "megolm")]
+const MAX_FORWARDED_KEYS_PER_DEVICE: usize = 100;
+
+/// Window for [`MAX_FORWARDED_KEYS_PER_DEVICE`].
+#[cfg(feature = "megolm")]
+const FORWARDED_KEY_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Room key requests in flight and recently answered.
+#[cfg(feature = "megolm")]
+#[derive(Default)]
+struct RoomKeyRequests {
+    /// Sessions we asked for, by session ID, with when we last asked.
+    outgoing: HashMap<String, Instant>,
+    /// When we forwarded sessions to each requesting device.
+    forwarded: HashMap<Uuid, Vec<Instant>>,
+}
+
+/// The other side of a verification, as published by the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationPeer {
+    /// User ID.
+    pub user_id: Uuid,
+    /// Server device ID.
+    pub device_id: Uuid,
+    /// Ed25519 identity key (base64).
+    pub identity_key_ed25519: String,
+    /// Curve25519 identity key (base64).
+    pub identity_key_curve25519: String,
+    /// Cross-signing master key (base64), if the user has one.
+    pub master_key: Option<String>,
+}
+
+impl VerificationPeer {
+    fn party(&self) -> VerificationParty {
+        VerificationParty {
+            user_id: self.user_id.to_string(),
+            device_id: self.device_id.to_string(),
+            device_key: self.identity_key_ed25519.clone(),
+            master_key: self.master_key.clone(),
+        }
+    }
+}
+
+/// Snapshot of a verification for the UI.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationStatus {
+    /// Transaction ID.
+    pub transaction_id: String,
+    /// The device being verified.
+    pub peer: VerificationPeer,
+    /// Current progress.
+    pub state: VerificationState,
+    /// Methods both devices support.
+    pub methods: Vec<String>,
+    /// SAS emoji, once keys are exchanged.
+    pub emojis: Option<Vec<SasEmoji>>,
+    /// SAS decimals, once keys are exchanged.
+    pub decimals: Option<(u16, u16, u16)>,
+}
+
+/// Cross-signing signature to publish after a successful verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationSignature {
+    /// Self-signing signature over one of our own devices.
+    Device { device_id: Uuid, signature: String },
+    /// User-signing signature over another user's master key.
+    User { user_id: Uuid, signature: String },
+}
+
+/// A verification in progress together with the peer it was started for.
+struct ActiveVerification {
+    verification: Verification,
+    peer: VerificationPeer,
+}
+
 /// Manages E2EE cryptographic operations.
 ///
 /// Uses `Mutex` instead of `RwLock` because `rusqlite::Connection` is `Send` but not `Sync`.
@@ -117,6 +267,13 @@ pub struct CryptoManager {
     store: Arc<Mutex<LocalKeyStore>>,
     user_id: Uuid,
     device_id: Uuid,
+    /// Interactive verifications by transaction ID. Kept in memory only: SAS
+    /// state can't be resumed after a restart, so those are simply restarted.
+    verifications: Mutex<HashMap<String, ActiveVerification>>,
+    /// Room key requests, kept in memory only: missing sessions are asked
+    /// for again when their messages are next shown.
+    #[cfg(feature = "megolm")]
+    key_requests: Mutex<RoomKeyRequests>,
 }
 
 impl CryptoManager {
@@ -126,6 +283,19 @@ impl CryptoManager {
             .map_err(|e| CryptoManagerError::LockPoisoned(e.to_string()))
     }
 
+    fn lock_verifications(&self) -> Result<MutexGuard<'_, HashMap<String, ActiveVerification>>> {
+        self.verifications
+            .lock()
+            .map_err(|e| CryptoManagerError::LockPoisoned(e.to_string()))
+    }
+
+    #[cfg(feature = "megolm")]
+    fn lock_key_requests(&self) -> Result<MutexGuard<'_, RoomKeyRequests>> {
+        self.key_requests
+            .lock()
+            .map_err(|e| CryptoManagerError::LockPoisoned(e.to_string()))
+    }
+
     /// Initialize the crypto manager.
     ///
     /// Creates a new Olm account if one doesn't exist, otherwise loads the existing one.
@@ -155,9 +325,10 @@ impl CryptoManager {
             let account = OlmAccount::new();
             store.save_account(&account)?;
 
-            // Generate initial one-time keys
+            // Generate initial one-time keys and a fallback key
             let mut account = store.load_account()?;
             account.generate_one_time_keys(50);
+            account.generate_fallback_key();
             store.save_account(&account)?;
 
             // Create and save metadata
@@ -176,12 +347,16 @@ impl CryptoManager {
             store: Arc::new(Mutex::new(store)),
             user_id,
             device_id,
+            verifications: Mutex::new(HashMap::new()),
+            #[cfg(feature = "megolm")]
+            key_requests: Mutex::new(RoomKeyRequests::default()),
         })
     }
 
     /// Check if keys need to be uploaded to the server.
     ///
-    /// Returns true if there are unpublished one-time keys.
+    /// Returns true if there are unpublished one-time keys or an unpublished
+    /// fallback key.
     ///
     /// # Errors
     ///
@@ -191,7 +366,7 @@ impl CryptoManager {
     pub fn needs_key_upload(&self) -> Result<bool> {
         let store = self.lock_store()?;
         let account = store.load_account()?;
-        Ok(!account.one_time_keys().is_empty())
+        Ok(!account.one_time_keys().is_empty() || account.fallback_key().is_some())
     }
 
     /// Get our identity keys.
@@ -306,9 +481,46 @@ impl CryptoManager {
         Ok(prekeys)
     }
 
+    /// Get the unpublished fallback key for upload, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the account cannot be loaded.
+    ///
+    /// Returns `CryptoManagerError::LockPoisoned` if the internal lock is poisoned.
+    pub fn get_unpublished_fallback_key(&self) -> Result<Option<SignedFallbackKey>> {
+        let store = self.lock_store()?;
+        let account = store.load_account()?;
+        Ok(account.fallback_key())
+    }
+
+    /// Replace our fallback key after the server reports it as used.
+    ///
+    /// The key it replaces stays usable for sessions already being set up;
+    /// the one before that is forgotten. Call `mark_keys_published` once the
+    /// returned key has been uploaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the account cannot be loaded or saved.
+    ///
+    /// Returns `CryptoManagerError::LockPoisoned` if the internal lock is poisoned.
+    pub fn rotate_fallback_key(&self) -> Result<SignedFallbackKey> {
+        let store = self.lock_store()?;
+        let mut account = store.load_account()?;
+        account.forget_fallback_key();
+        account.generate_fallback_key();
+        let fallback_key = account
+            .fallback_key()
+            .ok_or(CryptoManagerError::NotInitialized)?;
+        store.save_account(&account)?;
+        Ok(fallback_key)
+    }
+
     /// Encrypt a message for a specific device.
     ///
-    /// Creates a new session if one doesn't exist using the claimed prekey.
+    /// Creates a new session if one doesn't exist using the claimed prekey,
+    /// or the device's fallback key once its one-time prekeys have run out.
     ///
     /// # Arguments
     ///
@@ -346,11 +558,19 @@ impl CryptoManager {
             // Need to create a new outbound session
             let mut account = store.load_account()?;
 
-            // A one-time prekey is required to establish a secure session.
-            // Without it, Olm cannot produce a decryptable pre-key message.
+            // A one-time prekey (or the signed fallback key) is required to
+            // establish a secure session. Without it, Olm cannot produce a
+            // decryptable pre-key message.
             let one_time_key = if let Some(ref prekey) = claimed.one_time_prekey {
                 Curve25519PublicKey::from_base64(&prekey.public_key)
                     .map_err(|e| CryptoManagerError::InvalidKey(e.to_string()))?
+            } else if let Some(ref fallback_key) = claimed.fallback_key {
+                fallback_key.verify(
+                    &claimed.identity_key_ed25519,
+                    &claimed.identity_key_curve25519,
+                )?;
+                Curve25519PublicKey::from_base64(&fallback_key.public_key)
+                    .map_err(|e| CryptoManagerError::InvalidKey(e.to_string()))?
             } else {
                 return Err(CryptoManagerError::NoOneTimePrekey {
                     device_key: claimed.identity_key_curve25519.clone(),
@@ -457,6 +677,31 @@ impl CryptoManager {
         Ok(store.load_session(&session_key)?.is_some())
     }
 
+    /// Forget a device that was removed from its owner's account.
+    ///
+    /// Drops our Olm session with it and, so that it can't read anything sent
+    /// from now on, discards every Megolm outbound session; callers create
+    /// fresh ones with `create_outbound_group_session` before the next send.
+    ///
+    /// Returns whether an Olm session with the device existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store write fails.
+    ///
+    /// Returns `CryptoManagerError::LockPoisoned` if the internal lock is poisoned.
+    pub fn forget_device(&self, user_id: Uuid, device_curve25519: &str) -> Result<bool> {
+        let store = self.lock_store()?;
+        let session_key = SessionKey {
+            user_id,
+            device_curve25519: device_curve25519.to_string(),
+        };
+        let had_session = store.remove_session(&session_key)?;
+        #[cfg(feature = "megolm")]
+        store.clear_megolm_outbound_sessions()?;
+        Ok(had_session)
+    }
+
     // =========================================================================
     // Megolm Group Encryption Methods
     // =========================================================================
@@ -475,9 +720,34 @@ impl CryptoManager {
         Ok(session_key)
     }
 
+    /// Record the devices the current outbound session of a room was shared
+    /// with. They may later ask us for the session again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the room has no outbound session or the key
+    /// store write fails.
+    #[cfg(feature = "megolm")]
+    pub fn record_group_session_shares(
+        &self,
+        room_id: &str,
+        devices: &[MegolmShare],
+    ) -> Result<()> {
+        let store = self.lock_store()?;
+        let session = store
+            .load_megolm_outbound_session(room_id)?
+            .ok_or_else(|| {
+                CryptoManagerError::InvalidKey(
+                    "No outbound group session for this room".to_string(),
+                )
+            })?;
+        store.save_megolm_shares(room_id, &session.session_id(), devices)?;
+        Ok(())
+    }
+
     /// Encrypt a message for a group channel using the current Megolm outbound session.
     #[cfg(feature = "megolm")]
-    pub fn encrypt_group_message(&self, room_id: &str, plaintext: &str) -> Result<String> {
+    pub fn encrypt_group_message(&self, room_id: &str, plaintext: &str) -> Result<GroupCiphertext> {
         let store = self.lock_store()?;
 
         let mut session = store
@@ -492,7 +762,10 @@ impl CryptoManager {
 
         // Save the updated session (ratchet advanced)
         store.save_megolm_outbound_session(room_id, &session)?;
-        Ok(ciphertext)
+        Ok(GroupCiphertext {
+            ciphertext,
+            session_id: session.session_id(),
+        })
     }
 
     /// Store a Megolm session key received from another user (via an Olm 1:1 message).
@@ -519,11 +792,16 @@ impl CryptoManager {
     }
 
     /// Decrypt a Megolm group message using a stored inbound session.
+    ///
+    /// Messages carrying their `session_id` are decrypted with that session,
+    /// even if the sender has rotated since; messages without one use the
+    /// sender's current session.
     #[cfg(feature = "megolm")]
     pub fn decrypt_group_message(
         &self,
         room_id: &str,
         sender_key: &str,
+        session_id: Option<&str>,
         ciphertext: &str,
     ) -> Result<String> {
         let store = self.lock_store()?;
@@ -533,7 +811,11 @@ impl CryptoManager {
             sender_key: sender_key.to_string(),
         };
 
-        let mut session = store.load_megolm_inbound_session(&key)?.ok_or_else(|| {
+        let session = match session_id {
+            Some(session_id) => store.load_megolm_inbound_session_by_id(&key, session_id)?,
+            None => store.load_megolm_inbound_session(&key)?,
+        };
+        let mut session = session.ok_or_else(|| {
             CryptoManagerError::InvalidKey("No inbound group session found".to_string())
         })?;
 
@@ -541,11 +823,814 @@ impl CryptoManager {
             CryptoManagerError::InvalidKey("Group message decryption failed".to_string())
         })?;
 
-        // Save the updated session (message index advanced for replay protection)
-        store.save_megolm_inbound_session(&key, &session)?;
+        // Save the updated session (message index advanced for replay protection),
+        // keeping the sender's current session in place
+        let is_current = session_id.is_none()
+            || store
+                .load_megolm_inbound_session(&key)?
+                .is_some_and(|current| current.session_id() == session.session_id());
+        if is_current {
+            store.save_megolm_inbound_session(&key, &session)?;
+        } else {
+            store.save_older_megolm_inbound_session(&key, &session)?;
+        }
 
         Ok(plaintext)
     }
+
+    // =========================================================================
+    // Room Key Request Methods
+    // =========================================================================
+
+    /// Ask for a Megolm session this device is missing.
+    ///
+    /// Returns the signed request to send to our other devices and the
+    /// session's sender, or `None` if we already have the session or asked
+    /// for it within the last [`ROOM_KEY_REQUEST_INTERVAL`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store can't be read.
+    #[cfg(feature = "megolm")]
+    pub fn request_room_key(
+        &self,
+        own_device_id: Uuid,
+        room_id: &str,
+        sender_key: &str,
+        session_id: &str,
+    ) -> Result<Option<RoomKeyRequest>> {
+        let store = self.lock_store()?;
+        let key = MegolmInboundKey {
+            room_id: room_id.to_string(),
+            sender_key: sender_key.to_string(),
+        };
+        if store
+            .load_megolm_inbound_session_by_id(&key, session_id)?
+            .is_some()
+        {
+            return Ok(None);
+        }
+
+        let mut requests = self.lock_key_requests()?;
+        if requests
+            .outgoing
+            .get(session_id)
+            .is_some_and(|asked| asked.elapsed() < ROOM_KEY_REQUEST_INTERVAL)
+        {
+            return Ok(None);
+        }
+
+        let account = store.load_account()?;
+        let request = RoomKeyRequest::new(
+            &account,
+            &self.user_id.to_string(),
+            &own_device_id.to_string(),
+            room_id,
+            sender_key,
+            session_id,
+        );
+        requests
+            .outgoing
+            .insert(session_id.to_string(), Instant::now());
+        Ok(Some(request))
+    }
+
+    /// Whether `device` of `user_id` is signed by that user's self-signing
+    /// key, under a master key we trust: our own, or one we verified.
+    #[cfg(feature = "megolm")]
+    fn is_cross_signed(
+        &self,
+        store: &LocalKeyStore,
+        user_id: Uuid,
+        device: &DeviceKeys,
+        cross_signing: Option<&CrossSigningPublicKeys>,
+    ) -> Result<bool> {
+        let (Some(keys), Some(signature)) = (cross_signing, &device.cross_signing_signature) else {
+            return Ok(false);
+        };
+        let trusted_master = if user_id == self.user_id {
+            match Self::cross_signing_identity(store, self.user_id) {
+                Ok(identity) => identity.master_key() == keys.master_key,
+                Err(CryptoManagerError::CrossSigningNotSetUp) => false,
+                Err(e) => return Err(e),
+            }
+        } else {
+            store.is_key_verified(user_id, &keys.master_key)?
+        };
+
+        let user_id = user_id.to_string();
+        Ok(trusted_master
+            && keys.verify(&user_id).is_ok()
+            && keys
+                .verify_device(
+                    &user_id,
+                    &device.device_id.to_string(),
+                    &device.identity_key_ed25519,
+                    &device.identity_key_curve25519,
+                    signature,
+                )
+                .is_ok())
+    }
+
+    /// Answer a room key request from `requester`, a device of
+    /// `requester_user_id` as published by the server together with the
+    /// user's `requester_cross_signing` keys.
+    ///
+    /// A device is trusted if we verified it interactively or it is
+    /// cross-signed under a master key we trust. Sessions are only forwarded
+    /// to our own trusted devices, or, for sessions we created, to the exact
+    /// devices we shared them with and to trusted devices of the same users.
+    /// At most [`MAX_FORWARDED_KEYS_PER_DEVICE`] sessions are forwarded to a
+    /// device per [`FORWARDED_KEY_WINDOW`]. Returns `None` if the request is
+    /// refused or we don't have the session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request wasn't signed by `requester`, or the
+    /// key store can't be read.
+    #[cfg(feature = "megolm")]
+    pub fn respond_to_room_key_request(
+        &self,
+        requester_user_id: Uuid,
+        requester: &DeviceKeys,
+        requester_cross_signing: Option<&CrossSigningPublicKeys>,
+        request: &RoomKeyRequest,
+    ) -> Result<Option<ForwardedRoomKey>> {
+        if request.requesting_user_id != requester_user_id.to_string()
+            || request.requesting_device_id != requester.device_id.to_string()
+        {
+            return Err(CryptoManagerError::InvalidKey(
+                "Room key request doesn't match the sending device".to_string(),
+            ));
+        }
+        request.verify(&requester.identity_key_ed25519)?;
+
+        let store = self.lock_store()?;
+        let account = store.load_account()?;
+        let our_keys = account.identity_keys();
+        if requester.identity_key_ed25519 == our_keys.ed25519 {
+            return Ok(None);
+        }
+
+        let key = MegolmInboundKey {
+            room_id: request.room_id.clone(),
+            sender_key: request.sender_key.clone(),
+        };
+        let Some(session) = store.load_megolm_inbound_session_by_id(&key, &request.session_id)?
+        else {
+            return Ok(None);
+        };
+
+        let trusted_device = store
+            .is_key_verified(requester_user_id, &requester.identity_key_ed25519)?
+            || self.is_cross_signed(
+                &store,
+                requester_user_id,
+                requester,
+                requester_cross_signing,
+            )?;
+        let own_trusted_device = requester_user_id == self.user_id && trusted_device;
+        let shared_by_us = request.sender_key == our_keys.curve25519
+            && (store.was_megolm_session_shared(
+                &request.room_id,
+                &request.session_id,
+                &MegolmShare {
+                    user_id: requester_user_id,
+                    device_id: requester.device_id,
+                    identity_key_curve25519: requester.identity_key_curve25519.clone(),
+                },
+            )? || (trusted_device
+                && store.was_megolm_session_shared_with_user(
+                    &request.room_id,
+                    &request.session_id,
+                    requester_user_id,
+                )?));
+        if !own_trusted_device && !shared_by_us {
+            return Ok(None);
+        }
+
+        let mut requests = self.lock_key_requests()?;
+        let forwarded = requests.forwarded.entry(requester.device_id).or_default();
+        forwarded.retain(|at| at.elapsed() < FORWARDED_KEY_WINDOW);
+        if forwarded.len() >= MAX_FORWARDED_KEYS_PER_DEVICE {
+            return Ok(None);
+        }
+        forwarded.push(Instant::now());
+
+        Ok(Some(ForwardedRoomKey {
+            request_id: request.request_id.clone(),
+            room_id: request.room_id.clone(),
+            sender_key: request.sender_key.clone(),
+            session_id: request.session_id.clone(),
+            session_key: session.export(),
+        }))
+    }
+
+    /// Import a session forwarded by `forwarder`, a device of
+    /// `forwarder_user_id` as published by the server.
+    ///
+    /// Only sessions we asked for are accepted, and only from one of our own
+    /// verified devices or from the device that created the session. A
+    /// session we already have is only replaced if the forwarded copy can
+    /// decrypt earlier messages. Returns whether the session was imported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the forwarded session is malformed or the key
+    /// store can't be read or written.
+    #[cfg(feature = "megolm")]
+    pub fn add_forwarded_room_key(
+        &self,
+        forwarder_user_id: Uuid,
+        forwarder: &DeviceKeys,
+        forwarded: &ForwardedRoomKey,
+    ) -> Result<bool> {
+        let store = self.lock_store()?;
+        let mut requests = self.lock_key_requests()?;
+        if !requests.outgoing.contains_key(&forwarded.session_id) {
+            return Ok(false);
+        }
+
+        let own_verified_device = forwarder_user_id == self.user_id
+            && store.is_key_verified(self.user_id, &forwarder.identity_key_ed25519)?;
+        let original_sender = forwarder.identity_key_curve25519 == forwarded.sender_key;
+        if !own_verified_device && !original_sender {
+            return Ok(false);
+        }
+
+        let session = MegolmInboundSession::import(&forwarded.session_key)?;
+        if session.session_id() != forwarded.session_id {
+            return Err(CryptoManagerError::InvalidKey(
+                "Forwarded session doesn't match its session ID".to_string(),
+            ));
+        }
+        requests.outgoing.remove(&forwarded.session_id);
+
+        let key = MegolmInboundKey {
+            room_id: forwarded.room_id.clone(),
+            sender_key: forwarded.sender_key.clone(),
+        };
+        if store
+            .load_megolm_inbound_session_by_id(&key, &forwarded.session_id)?
+            .is_some_and(|known| known.first_known_index() <= session.first_known_index())
+        {
+            return Ok(false);
+        }
+
+        match store.load_megolm_inbound_session(&key)? {
+            Some(current) if current.session_id() != forwarded.session_id => {
+                store.save_older_megolm_inbound_session(&key, &session)?;
+            }
+            _ => store.save_megolm_inbound_session(&key, &session)?,
+        }
+        Ok(true)
+    }
+
+    // =========================================================================
+    // Megolm Key Backup Methods
+    // =========================================================================
+
+    #[cfg(feature = "megolm")]
+    fn backup_key(state: &KeyBackupState) -> Result<BackupKey> {
+        let bytes = Zeroizing::new(
+            STANDARD
+                .decode(&state.backup_key)
+                .map_err(|_| CryptoManagerError::InvalidKey("Invalid backup key".to_string()))?,
+        );
+        let bytes: [u8; 32] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| CryptoManagerError::InvalidKey("Invalid backup key".to_string()))?;
+        Ok(BackupKey::from_bytes(bytes))
+    }
+
+    /// Start uploading inbound Megolm sessions to backup `version`.
+    ///
+    /// Every known session is queued again, since a new version starts empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store write fails.
+    #[cfg(feature = "megolm")]
+    pub fn enable_key_backup(&self, version: i64, backup_key: &BackupKey) -> Result<()> {
+        let store = self.lock_store()?;
+        store.save_key_backup(&KeyBackupState {
+            version,
+            backup_key: STANDARD.encode(backup_key),
+        })?;
+        store.reset_megolm_backups()?;
+        Ok(())
+    }
+
+    /// Stop uploading to the key backup, e.g. after it was replaced by
+    /// another device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store write fails.
+    pub fn disable_key_backup(&self) -> Result<()> {
+        Ok(self.lock_store()?.clear_key_backup()?)
+    }
+
+    /// The key backup version this device uploads to, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store can't be read.
+    pub fn key_backup_version(&self) -> Result<Option<i64>> {
+        Ok(self.lock_store()?.load_key_backup()?.map(|b| b.version))
+    }
+
+    /// Encrypt up to `limit` inbound Megolm sessions that haven't been
+    /// uploaded to the key backup yet.
+    ///
+    /// Returns the backup version together with the encrypted sessions; call
+    /// [`Self::mark_key_backed_up`] once each has been uploaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoManagerError::KeyBackupNotEnabled` if no backup is
+    /// enabled, or an error if the key store can't be read.
+    #[cfg(feature = "megolm")]
+    pub fn pending_key_backups(&self, limit: usize) -> Result<(i64, Vec<BackedUpRoomKey>)> {
+        let store = self.lock_store()?;
+        let state = store
+            .load_key_backup()?
+            .ok_or(CryptoManagerError::KeyBackupNotEnabled)?;
+        let backup_key = Self::backup_key(&state)?;
+
+        let mut keys = Vec::new();
+        loop {
+            let batch = store.pending_megolm_backups(limit)?;
+            if batch.is_empty() {
+                break;
+            }
+            for pending in batch {
+                let key = MegolmInboundKey {
+                    room_id: pending.room_id.clone(),
+                    sender_key: pending.sender_key.clone(),
+                };
+                let session =
+                    match store.load_megolm_inbound_session_by_id(&key, &pending.session_id)? {
+                        Some(session) => session,
+                        // Saved before sessions were kept by ID, and since replaced
+                        None => {
+                            store.mark_megolm_backed_up(&pending.room_id, &pending.session_id)?;
+                            continue;
+                        }
+                    };
+
+                let data = Zeroizing::new(
+                    serde_json::to_vec(&RoomKeyBackupData {
+                        sender_key: pending.sender_key,
+                        session_key: session.export(),
+                    })
+                    .map_err(super::store::KeyStoreError::from)?,
+                );
+                keys.push(BackedUpRoomKey {
+                    key: backup_key.encrypt_room_key(&pending.room_id, &pending.session_id, &data),
+                    first_message_index: session.first_known_index(),
+                    room_id: pending.room_id,
+                    session_id: pending.session_id,
+                });
+            }
+            if !keys.is_empty() {
+                break;
+            }
+        }
+        Ok((state.version, keys))
+    }
+
+    /// Record that a session returned by [`Self::pending_key_backups`] has
+    /// been uploaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store write fails.
+    #[cfg(feature = "megolm")]
+    pub fn mark_key_backed_up(&self, room_id: &str, session_id: &str) -> Result<()> {
+        Ok(self
+            .lock_store()?
+            .mark_megolm_backed_up(room_id, session_id)?)
+    }
+
+    /// Import sessions downloaded from backup `version` and keep uploading
+    /// new sessions to it.
+    ///
+    /// Entries that don't decrypt under `backup_key` are skipped, and sessions
+    /// this device already has are kept. Restored sessions don't replace a
+    /// sender's current session. Returns the number of sessions imported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store can't be read or written.
+    #[cfg(feature = "megolm")]
+    pub fn restore_key_backup(
+        &self,
+        version: i64,
+        backup_key: &BackupKey,
+        keys: &[BackedUpRoomKey],
+    ) -> Result<usize> {
+        let store = self.lock_store()?;
+        if store.load_key_backup()?.map(|b| b.version) != Some(version) {
+            store.reset_megolm_backups()?;
+        }
+
+        let mut restored = 0;
+        for entry in keys {
+            let Ok(data) =
+                backup_key.decrypt_room_key(&entry.room_id, &entry.session_id, &entry.key)
+            else {
+                continue;
+            };
+            let Ok(data) = serde_json::from_slice::<RoomKeyBackupData>(&data) else {
+                continue;
+            };
+            let Ok(session) = MegolmInboundSession::import(&data.session_key) else {
+                continue;
+            };
+            if session.session_id() != entry.session_id {
+                continue;
+            }
+
+            let key = MegolmInboundKey {
+                room_id: entry.room_id.clone(),
+                sender_key: data.sender_key,
+            };
+            if store
+                .load_megolm_inbound_session_by_id(&key, &entry.session_id)?
+                .is_some()
+            {
+                continue;
+            }
+            if store.load_megolm_inbound_session(&key)?.is_some() {
+                store.save_older_megolm_inbound_session(&key, &session)?;
+            } else {
+                store.save_megolm_inbound_session(&key, &session)?;
+            }
+            store.mark_megolm_backed_up(&entry.room_id, &entry.session_id)?;
+            restored += 1;
+        }
+
+        store.save_key_backup(&KeyBackupState {
+            version,
+            backup_key: STANDARD.encode(backup_key),
+        })?;
+        Ok(restored)
+    }
+
+    // =========================================================================
+    // Cross-Signing Methods
+    // =========================================================================
+
+    fn cross_signing_identity(
+        store: &LocalKeyStore,
+        user_id: Uuid,
+    ) -> Result<CrossSigningIdentity> {
+        let secrets = store
+            .load_cross_signing_secrets()?
+            .ok_or(CryptoManagerError::CrossSigningNotSetUp)?;
+        Ok(CrossSigningIdentity::from_secrets(
+            &user_id.to_string(),
+            &secrets,
+        )?)
+    }
+
+    /// Our cross-signing identity, or a new one if there is none or `reset` is set.
+    ///
+    /// A new identity is not saved: call [`Self::save_cross_signing`] once the
+    /// server has accepted its public keys, so a rejected replacement leaves the
+    /// current keys in place.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored keys can't be read.
+    pub fn bootstrap_cross_signing(&self, reset: bool) -> Result<CrossSigningIdentity> {
+        let store = self.lock_store()?;
+        if !reset {
+            if let Some(secrets) = store.load_cross_signing_secrets()? {
+                return Ok(CrossSigningIdentity::from_secrets(
+                    &self.user_id.to_string(),
+                    &secrets,
+                )?);
+            }
+        }
+        Ok(CrossSigningIdentity::new(&self.user_id.to_string()))
+    }
+
+    /// Save a cross-signing identity, replacing any previous one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the keys can't be saved.
+    pub fn save_cross_signing(&self, identity: &CrossSigningIdentity) -> Result<()> {
+        let store = self.lock_store()?;
+        store.save_cross_signing_secrets(&identity.secrets())?;
+        Ok(())
+    }
+
+    /// Our cross-signing public keys, if this device holds the private keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored keys can't be read.
+    pub fn cross_signing_keys(&self) -> Result<Option<CrossSigningPublicKeys>> {
+        let store = self.lock_store()?;
+        match Self::cross_signing_identity(&store, self.user_id) {
+            Ok(identity) => Ok(Some(identity.public_keys())),
+            Err(CryptoManagerError::CrossSigningNotSetUp) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sign one of our own devices with the self-signing key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoManagerError::CrossSigningNotSetUp` if there are no cross-signing keys.
+    pub fn sign_own_device(
+        &self,
+        device_id: Uuid,
+        identity_key_ed25519: &str,
+        identity_key_curve25519: &str,
+    ) -> Result<String> {
+        let store = self.lock_store()?;
+        let identity = Self::cross_signing_identity(&store, self.user_id)?;
+        Ok(identity.sign_device(
+            &device_id.to_string(),
+            identity_key_ed25519,
+            identity_key_curve25519,
+        ))
+    }
+
+    /// Whether a key has been verified interactively.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store query fails.
+    pub fn is_key_verified(&self, user_id: Uuid, key: &str) -> Result<bool> {
+        Ok(self.lock_store()?.is_key_verified(user_id, key)?)
+    }
+
+    /// All verified keys of a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key store query fails.
+    pub fn verified_keys(&self, user_id: Uuid) -> Result<Vec<VerifiedKey>> {
+        Ok(self.lock_store()?.verified_keys(user_id)?)
+    }
+
+    // =========================================================================
+    // Interactive Verification Methods
+    // =========================================================================
+
+    /// Our side of a verification. `device_id` is our server device ID.
+    fn own_party(&self, device_id: Uuid) -> Result<VerificationParty> {
+        let store = self.lock_store()?;
+        let account = store.load_account()?;
+        let master_key = store
+            .load_cross_signing_secrets()?
+            .map(|secrets| {
+                CrossSigningIdentity::from_secrets(&self.user_id.to_string(), &secrets)
+                    .map(|identity| identity.master_key())
+            })
+            .transpose()?;
+
+        Ok(VerificationParty {
+            user_id: self.user_id.to_string(),
+            device_id: device_id.to_string(),
+            device_key: account.identity_keys().ed25519,
+            master_key,
+        })
+    }
+
+    /// Start verifying another device.
+    ///
+    /// Returns the `request` message to send to the peer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if our identity keys can't be loaded.
+    pub fn request_verification(
+        &self,
+        own_device_id: Uuid,
+        peer: VerificationPeer,
+    ) -> Result<VerificationMessage> {
+        let (verification, message) =
+            Verification::request(self.own_party(own_device_id)?, peer.party())?;
+        self.lock_verifications()?.insert(
+            verification.transaction_id().to_string(),
+            ActiveVerification { verification, peer },
+        );
+        Ok(message)
+    }
+
+    /// Process a verification message received from `peer`.
+    ///
+    /// Incoming requests are held until accepted. Returns the messages to
+    /// send back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message belongs to an unknown verification or
+    /// comes from a different device than the one being verified.
+    pub fn receive_verification(
+        &self,
+        own_device_id: Uuid,
+        peer: VerificationPeer,
+        message: &VerificationMessage,
+    ) -> Result<Vec<VerificationMessage>> {
+        let transaction_id = message.transaction_id().to_string();
+        if matches!(message, VerificationMessage::Request { .. }) {
+            let verification =
+                Verification::from_request(self.own_party(own_device_id)?, peer.party(), message)?;
+            self.lock_verifications()?
+                .insert(transaction_id, ActiveVerification { verification, peer });
+            return Ok(Vec::new());
+        }
+
+        let mut verifications = self.lock_verifications()?;
+        let active = verifications
+            .get_mut(&transaction_id)
+            .ok_or_else(|| CryptoManagerError::VerificationNotFound(transaction_id.clone()))?;
+        if active.peer.user_id != peer.user_id || active.peer.device_id != peer.device_id {
+            return Err(CryptoManagerError::VerificationNotFound(transaction_id));
+        }
+
+        let reply = active.verification.receive(message)?;
+        self.record_if_done(active)?;
+        Ok(reply.into_iter().collect())
+    }
+
+    /// Apply `action` to an active verification, recording verified keys if it finished.
+    fn with_verification<T>(
+        &self,
+        transaction_id: &str,
+        action: impl FnOnce(&mut Verification) -> Result<T>,
+    ) -> Result<T> {
+        let mut verifications = self.lock_verifications()?;
+        let active = verifications
+            .get_mut(transaction_id)
+            .ok_or_else(|| CryptoManagerError::VerificationNotFound(transaction_id.to_string()))?;
+        let result = action(&mut active.verification)?;
+        self.record_if_done(active)?;
+        Ok(result)
+    }
+
+    /// Accept an incoming verification request.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown or not an incoming request.
+    pub fn accept_verification(&self, transaction_id: &str) -> Result<VerificationMessage> {
+        self.with_verification(transaction_id, |v| Ok(v.accept()?))
+    }
+
+    /// Begin SAS once both devices are ready.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown or not ready.
+    pub fn start_sas(&self, transaction_id: &str) -> Result<VerificationMessage> {
+        self.with_verification(transaction_id, |v| Ok(v.start_sas()?))
+    }
+
+    /// Confirm the SAS emoji match.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown or keys aren't exchanged yet.
+    pub fn confirm_sas(&self, transaction_id: &str) -> Result<Vec<VerificationMessage>> {
+        self.with_verification(transaction_id, |v| Ok(v.confirm()?))
+    }
+
+    /// Cancel a verification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown.
+    pub fn cancel_verification(
+        &self,
+        transaction_id: &str,
+        code: CancelCode,
+    ) -> Result<VerificationMessage> {
+        self.with_verification(transaction_id, |v| Ok(v.cancel(code)))
+    }
+
+    /// Encode a QR code for the peer to scan.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown or not ready.
+    pub fn verification_qr_code(&self, transaction_id: &str) -> Result<String> {
+        self.with_verification(transaction_id, |v| Ok(v.qr_code()?))
+    }
+
+    /// Check a QR code scanned from the peer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown or not ready.
+    pub fn scan_verification_qr(
+        &self,
+        transaction_id: &str,
+        code: &str,
+    ) -> Result<VerificationMessage> {
+        self.with_verification(transaction_id, |v| Ok(v.scan_qr(code)?))
+    }
+
+    /// Current state of a verification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown.
+    pub fn verification_status(&self, transaction_id: &str) -> Result<VerificationStatus> {
+        let verifications = self.lock_verifications()?;
+        let active = verifications
+            .get(transaction_id)
+            .ok_or_else(|| CryptoManagerError::VerificationNotFound(transaction_id.to_string()))?;
+        let verification = &active.verification;
+
+        Ok(VerificationStatus {
+            transaction_id: transaction_id.to_string(),
+            peer: active.peer.clone(),
+            state: verification.state(),
+            methods: verification.methods().to_vec(),
+            emojis: verification.emojis(),
+            decimals: verification.decimals(),
+        })
+    }
+
+    /// The cross-signing signature to publish for a finished verification.
+    ///
+    /// Our own devices are signed with the self-signing key; other users'
+    /// verified master keys with the user-signing key. Returns `None` if
+    /// there is nothing to sign or this device has no cross-signing keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the verification is unknown.
+    pub fn verification_signature(
+        &self,
+        transaction_id: &str,
+    ) -> Result<Option<VerificationSignature>> {
+        let verifications = self.lock_verifications()?;
+        let active = verifications
+            .get(transaction_id)
+            .ok_or_else(|| CryptoManagerError::VerificationNotFound(transaction_id.to_string()))?;
+        if active.verification.state() != VerificationState::Done {
+            return Ok(None);
+        }
+
+        let store = self.lock_store()?;
+        let identity = match Self::cross_signing_identity(&store, self.user_id) {
+            Ok(identity) => identity,
+            Err(CryptoManagerError::CrossSigningNotSetUp) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        let peer = &active.peer;
+        let verified = active.verification.verified_keys();
+        if peer.user_id == self.user_id {
+            let device_key_id = format!("ed25519:{}", peer.device_id);
+            return Ok(verified.contains_key(&device_key_id).then(|| {
+                VerificationSignature::Device {
+                    device_id: peer.device_id,
+                    signature: identity.sign_device(
+                        &peer.device_id.to_string(),
+                        &peer.identity_key_ed25519,
+                        &peer.identity_key_curve25519,
+                    ),
+                }
+            }));
+        }
+
+        Ok(verified
+            .get("master")
+            .map(|master_key| VerificationSignature::User {
+                user_id: peer.user_id,
+                signature: identity.sign_user(&peer.user_id.to_string(), master_key),
+            }))
+    }
+
+    /// Persist the keys proven by a finished verification.
+    fn record_if_done(&self, active: &ActiveVerification) -> Result<()> {
+        if active.verification.state() != VerificationState::Done {
+            return Ok(());
+        }
+        let store = self.lock_store()?;
+        let verified_at = chrono::Utc::now().timestamp();
+        for (key_id, key) in active.verification.verified_keys() {
+            store.save_verified_key(&VerifiedKey {
+                user_id: active.peer.user_id,
+                key_id: key_id.clone(),
+                key: key.clone(),
+                verified_at,
+            })?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -650,6 +1735,7 @@ mod tests {
                 key_id: bob_prekey.key_id.clone(),
                 public_key: bob_prekey.public_key.clone(),
             }),
+            fallback_key: None,
         };
 
         let plaintext = "Hello, Bob!";
@@ -674,6 +1760,7 @@ mod tests {
             identity_key_ed25519: alice_identity.ed25519.clone(),
             identity_key_curve25519: alice_identity.curve25519.clone(),
             one_time_prekey: None, // Bob already has session, doesn't need prekey
+            fallback_key: None,
         };
 
         let reply = "Hello, Alice!";
@@ -734,6 +1821,7 @@ mod tests {
                     key_id: bob_prekey.key_id.clone(),
                     public_key: bob_prekey.public_key.clone(),
                 }),
+                fallback_key: None,
             };
 
             first_ciphertext = alice
@@ -765,6 +1853,7 @@ mod tests {
                 identity_key_ed25519: bob_identity.ed25519.clone(),
                 identity_key_curve25519: bob_identity.curve25519.clone(),
                 one_time_prekey: None, // Don't need prekey - we have existing session
+                fallback_key: None,
             };
 
             let second_ciphertext = alice
@@ -812,6 +1901,7 @@ mod tests {
             identity_key_ed25519: bob_identity.ed25519.clone(),
             identity_key_curve25519: bob_identity.curve25519.clone(),
             one_time_prekey: None,
+            fallback_key: None,
         };
 
         let result = alice.encrypt_for_device(bob_user_id, &claimed, "Hello!");
@@ -825,6 +1915,329 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_crypto_manager_fallback_key_session() {
+        let dir = tempdir().unwrap();
+        let encryption_key = [0u8; 32];
+
+        let alice_dir = dir.path().join("alice");
+        std::fs::create_dir(&alice_dir).unwrap();
+        let alice_user_id = Uuid::now_v7();
+        let alice = CryptoManager::init(alice_dir, alice_user_id, encryption_key).unwrap();
+
+        let bob_dir = dir.path().join("bob");
+        std::fs::create_dir(&bob_dir).unwrap();
+        let bob_user_id = Uuid::now_v7();
+        let bob = CryptoManager::init(bob_dir, bob_user_id, encryption_key).unwrap();
+
+        // Bob publishes everything, then his one-time prekeys run out
+        let bob_identity = bob.get_identity_keys().unwrap();
+        let fallback_key = bob.get_unpublished_fallback_key().unwrap().unwrap();
+        bob.mark_keys_published().unwrap();
+        assert!(!bob.needs_key_upload().unwrap());
+
+        // A fallback key signed by someone else is rejected
+        let mut claimed = ClaimedPrekey {
+            device_id: bob.device_id(),
+            identity_key_ed25519: alice.get_identity_keys().unwrap().ed25519,
+            identity_key_curve25519: bob_identity.curve25519.clone(),
+            one_time_prekey: None,
+            fallback_key: Some(fallback_key.clone()),
+        };
+        assert!(alice
+            .encrypt_for_device(bob_user_id, &claimed, "Hello!")
+            .is_err());
+
+        claimed.identity_key_ed25519 = bob_identity.ed25519.clone();
+        let ciphertext = alice
+            .encrypt_for_device(bob_user_id, &claimed, "Hello!")
+            .unwrap();
+        let alice_curve25519 = alice.our_curve25519_key().unwrap();
+        let decrypted = bob
+            .decrypt_message(alice_user_id, &alice_curve25519, &ciphertext)
+            .unwrap();
+        assert_eq!(decrypted, "Hello!");
+
+        // Once used, Bob rotates it and has a new key to upload
+        let rotated = bob.rotate_fallback_key().unwrap();
+        assert_ne!(rotated.public_key, fallback_key.public_key);
+        assert!(bob.needs_key_upload().unwrap());
+        bob.mark_keys_published().unwrap();
+        assert!(bob.get_unpublished_fallback_key().unwrap().is_none());
+    }
+
+    #[cfg(feature = "megolm")]
+    #[test]
+    fn test_crypto_manager_key_backup_roundtrip() {
+        use vc_crypto::{KeyBackupAuthData, RecoveryKey};
+
+        let dir = tempdir().unwrap();
+        let encryption_key = [0u8; 32];
+        let bob_user_id = Uuid::now_v7();
+        let room_id = "room-1";
+
+        let alice_dir = dir.path().join("alice");
+        std::fs::create_dir(&alice_dir).unwrap();
+        let alice = CryptoManager::init(alice_dir, Uuid::now_v7(), encryption_key).unwrap();
+        let alice_curve25519 = alice.our_curve25519_key().unwrap();
+
+        let bob_dir = dir.path().join("bob");
+        std::fs::create_dir(&bob_dir).unwrap();
+        let bob = CryptoManager::init(bob_dir, bob_user_id, encryption_key).unwrap();
+        assert!(matches!(
+            bob.pending_key_backups(10),
+            Err(CryptoManagerError::KeyBackupNotEnabled)
+        ));
+
+        let recovery_key = RecoveryKey::generate();
+        let (auth_data, backup_key) = KeyBackupAuthData::create(&recovery_key);
+        bob.enable_key_backup(7, &backup_key).unwrap();
+
+        let session_key = alice.create_outbound_group_session(room_id).unwrap();
+        bob.add_inbound_group_session(room_id, &alice_curve25519, &session_key)
+            .unwrap();
+        let message = alice.encrypt_group_message(room_id, "Hello!").unwrap();
+        assert_eq!(
+            bob.decrypt_group_message(
+                room_id,
+                &alice_curve25519,
+                Some(&message.session_id),
+                &message.ciphertext
+            )
+            .unwrap(),
+            "Hello!"
+        );
+
+        // The session is queued once, even though decrypting saved it again
+        let (version, keys) = bob.pending_key_backups(10).unwrap();
+        assert_eq!(version, 7);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].room_id, room_id);
+        assert_eq!(keys[0].first_message_index, 0);
+        bob.mark_key_backed_up(&keys[0].room_id, &keys[0].session_id)
+            .unwrap();
+        assert!(bob.pending_key_backups(10).unwrap().1.is_empty());
+
+        // A new device restores the session with the recovery key
+        let device_dir = dir.path().join("bob-new");
+        std::fs::create_dir(&device_dir).unwrap();
+        let new_device = CryptoManager::init(device_dir, bob_user_id, [1u8; 32]).unwrap();
+        let wrong_key = KeyBackupAuthData::create(&RecoveryKey::generate()).1;
+        assert_eq!(
+            new_device.restore_key_backup(7, &wrong_key, &keys).unwrap(),
+            0
+        );
+
+        let restored_key = auth_data.backup_key(&recovery_key).unwrap();
+        assert_eq!(
+            new_device
+                .restore_key_backup(7, &restored_key, &keys)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            new_device
+                .decrypt_group_message(room_id, &alice_curve25519, None, &message.ciphertext)
+                .unwrap(),
+            "Hello!"
+        );
+        assert_eq!(new_device.key_backup_version().unwrap(), Some(7));
+        assert!(new_device.pending_key_backups(10).unwrap().1.is_empty());
+
+        // A new version starts empty, so everything is uploaded again
+        bob.enable_key_backup(8, &backup_key).unwrap();
+        assert_eq!(bob.pending_key_backups(10).unwrap().1.len(), 1);
+    }
+
+    #[cfg(feature = "megolm")]
+    #[test]
+    fn test_room_key_request_forwarding() {
+        let dir = tempdir().unwrap();
+        let room_id = "room-1";
+        let init = |name: &str, user_id: Uuid| {
+            let path = dir.path().join(name);
+            std::fs::create_dir(&path).unwrap();
+            CryptoManager::init(path, user_id, [0u8; 32]).unwrap()
+        };
+        let device_keys = |manager: &CryptoManager| {
+            let identity = manager.get_identity_keys().unwrap();
+            DeviceKeys {
+                device_id: Uuid::now_v7(),
+                device_name: None,
+                identity_key_ed25519: identity.ed25519,
+                identity_key_curve25519: identity.curve25519,
+                cross_signing_signature: None,
+            }
+        };
+
+        let alice_id = Uuid::now_v7();
+        let bob_id = Uuid::now_v7();
+        let carol_id = Uuid::now_v7();
+        let alice = init("alice", alice_id);
+        let bob = init("bob", bob_id);
+        let bob_new = init("bob-new", bob_id);
+        let carol = init("carol", carol_id);
+        let carol_new = init("carol-new", carol_id);
+        let alice_device = device_keys(&alice);
+        let bob_device = device_keys(&bob);
+        let bob_new_device = device_keys(&bob_new);
+        let carol_device = device_keys(&carol);
+        let carol_new_device = device_keys(&carol_new);
+        let share = |user_id: Uuid, device: &DeviceKeys| MegolmShare {
+            user_id,
+            device_id: device.device_id,
+            identity_key_curve25519: device.identity_key_curve25519.clone(),
+        };
+
+        // Alice shares a session with Bob and Carol before their new devices exist
+        let alice_curve25519 = alice.our_curve25519_key().unwrap();
+        let session_key = alice.create_outbound_group_session(room_id).unwrap();
+        alice
+            .record_group_session_shares(
+                room_id,
+                &[share(bob_id, &bob_device), share(carol_id, &carol_device)],
+            )
+            .unwrap();
+        alice
+            .add_inbound_group_session(room_id, &alice_curve25519, &session_key)
+            .unwrap();
+        bob.add_inbound_group_session(room_id, &alice_curve25519, &session_key)
+            .unwrap();
+        let message = alice.encrypt_group_message(room_id, "Hello!").unwrap();
+
+        // The new device asks for the session, once
+        let decrypt = |manager: &CryptoManager| {
+            manager.decrypt_group_message(
+                room_id,
+                &alice_curve25519,
+                Some(&message.session_id),
+                &message.ciphertext,
+            )
+        };
+        assert!(decrypt(&bob_new).is_err());
+        let request = bob_new
+            .request_room_key(
+                bob_new_device.device_id,
+                room_id,
+                &alice_curve25519,
+                &message.session_id,
+            )
+            .unwrap()
+            .expect("request");
+        assert!(bob_new
+            .request_room_key(
+                bob_new_device.device_id,
+                room_id,
+                &alice_curve25519,
+                &message.session_id,
+            )
+            .unwrap()
+            .is_none());
+
+        // Bob's other device only answers once it has verified the new one
+        assert!(bob
+            .respond_to_room_key_request(bob_id, &bob_new_device, None, &request)
+            .unwrap()
+            .is_none());
+        bob.lock_store()
+            .unwrap()
+            .save_verified_key(&VerifiedKey {
+                user_id: bob_id,
+                key_id: format!("ed25519:{}", bob_new_device.device_id),
+                key: bob_new_device.identity_key_ed25519.clone(),
+                verified_at: 0,
+            })
+            .unwrap();
+        let from_bob = bob
+            .respond_to_room_key_request(bob_id, &bob_new_device, None, &request)
+            .unwrap()
+            .expect("forwarded by own device");
+        assert_eq!(from_bob.request_id, request.request_id);
+
+        // Requests must be signed by the device they claim to come from
+        let mut forged = request.clone();
+        forged.session_id = "other-session".to_string();
+        assert!(bob
+            .respond_to_room_key_request(bob_id, &bob_new_device, None, &forged)
+            .is_err());
+        assert!(bob
+            .respond_to_room_key_request(bob_id, &carol_device, None, &request)
+            .is_err());
+
+        // The sender answers the exact devices it shared the session with
+        let request_from = |manager: &CryptoManager, device: &DeviceKeys| {
+            manager
+                .request_room_key(
+                    device.device_id,
+                    room_id,
+                    &alice_curve25519,
+                    &message.session_id,
+                )
+                .unwrap()
+                .expect("request")
+        };
+        let carol_request = request_from(&carol, &carol_device);
+        assert!(alice
+            .respond_to_room_key_request(carol_id, &carol_device, None, &carol_request)
+            .unwrap()
+            .is_some());
+
+        // Other devices of those users must be cross-signed under a master
+        // key the sender verified
+        let carol_new_request = request_from(&carol_new, &carol_new_device);
+        assert!(alice
+            .respond_to_room_key_request(carol_id, &carol_new_device, None, &carol_new_request)
+            .unwrap()
+            .is_none());
+
+        let bob_identity = bob.bootstrap_cross_signing(false).unwrap();
+        bob.save_cross_signing(&bob_identity).unwrap();
+        let bob_keys = bob_identity.public_keys();
+        let signed_bob_new_device = DeviceKeys {
+            cross_signing_signature: Some(
+                bob.sign_own_device(
+                    bob_new_device.device_id,
+                    &bob_new_device.identity_key_ed25519,
+                    &bob_new_device.identity_key_curve25519,
+                )
+                .unwrap(),
+            ),
+            ..bob_new_device.clone()
+        };
+        assert!(alice
+            .respond_to_room_key_request(bob_id, &signed_bob_new_device, Some(&bob_keys), &request)
+            .unwrap()
+            .is_none());
+        alice
+            .lock_store()
+            .unwrap()
+            .save_verified_key(&VerifiedKey {
+                user_id: bob_id,
+                key_id: "master".to_string(),
+                key: bob_keys.master_key.clone(),
+                verified_at: 0,
+            })
+            .unwrap();
+        let from_alice = alice
+            .respond_to_room_key_request(bob_id, &signed_bob_new_device, Some(&bob_keys), &request)
+            .unwrap()
+            .expect("forwarded by sender");
+
+        // Forwarded sessions are only taken from trusted devices
+        assert!(!bob_new
+            .add_forwarded_room_key(carol_id, &carol_device, &from_alice)
+            .unwrap());
+        assert!(bob_new
+            .add_forwarded_room_key(alice_id, &alice_device, &from_alice)
+            .unwrap());
+        assert_eq!(decrypt(&bob_new).unwrap(), "Hello!");
+
+        // Answers to requests that were already answered are ignored
+        assert!(!bob_new
+            .add_forwarded_room_key(alice_id, &alice_device, &from_alice)
+            .unwrap());
+    }
+
     #[test]
     fn test_crypto_manager_decrypt_wrong_sender() {
         // Test that decryption fails when the wrong sender key is provided.
@@ -864,6 +2277,7 @@ mod tests {
                 key_id: bob_prekey.key_id.clone(),
                 public_key: bob_prekey.public_key.clone(),
             }),
+            fallback_key: None,
         };
 
         let plaintext = "Secret message";
@@ -892,4 +2306,111 @@ mod tests {
         // or a crypto error (for prekey messages where the key doesn't match)
         assert!(result.is_err());
     }
+
+    fn verification_peer(manager: &CryptoManager, device_id: Uuid) -> VerificationPeer {
+        let identity = manager.get_identity_keys().unwrap();
+        VerificationPeer {
+            user_id: manager.user_id(),
+            device_id,
+            identity_key_ed25519: identity.ed25519,
+            identity_key_curve25519: identity.curve25519,
+            master_key: manager
+                .cross_signing_keys()
+                .unwrap()
+                .map(|keys| keys.master_key),
+        }
+    }
+
+    /// Deliver each message to `to`, collecting its replies.
+    fn deliver(
+        to: &CryptoManager,
+        to_device: Uuid,
+        from: &VerificationPeer,
+        messages: &[VerificationMessage],
+    ) -> Vec<VerificationMessage> {
+        messages
+            .iter()
+            .flat_map(|m| to.receive_verification(to_device, from.clone(), m).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_sas_verification_cross_signs_own_device() {
+        let dir = tempdir().unwrap();
+        let user_id = Uuid::now_v7();
+        let (laptop_dir, phone_dir) = (dir.path().join("laptop"), dir.path().join("phone"));
+        std::fs::create_dir(&laptop_dir).unwrap();
+        std::fs::create_dir(&phone_dir).unwrap();
+        let laptop = CryptoManager::init(laptop_dir, user_id, [0u8; 32]).unwrap();
+        let phone = CryptoManager::init(phone_dir, user_id, [1u8; 32]).unwrap();
+        let (laptop_id, phone_id) = (Uuid::now_v7(), Uuid::now_v7());
+
+        let identity = laptop.bootstrap_cross_signing(false).unwrap();
+        assert!(laptop.cross_signing_keys().unwrap().is_none());
+        laptop.save_cross_signing(&identity).unwrap();
+        let keys = identity.public_keys();
+        assert_eq!(
+            laptop.bootstrap_cross_signing(false).unwrap().public_keys(),
+            keys
+        );
+        assert!(phone.cross_signing_keys().unwrap().is_none());
+
+        let laptop_peer = verification_peer(&laptop, laptop_id);
+        let phone_peer = verification_peer(&phone, phone_id);
+
+        let request = laptop
+            .request_verification(laptop_id, phone_peer.clone())
+            .unwrap();
+        let tx = request.transaction_id().to_string();
+        assert!(deliver(&phone, phone_id, &laptop_peer, &[request]).is_empty());
+
+        let ready = phone.accept_verification(&tx).unwrap();
+        assert!(deliver(&laptop, laptop_id, &phone_peer, &[ready]).is_empty());
+        let start = laptop.start_sas(&tx).unwrap();
+        let accept = deliver(&phone, phone_id, &laptop_peer, &[start]);
+        let laptop_key = deliver(&laptop, laptop_id, &phone_peer, &accept);
+        let phone_key = deliver(&phone, phone_id, &laptop_peer, &laptop_key);
+        assert!(deliver(&laptop, laptop_id, &phone_peer, &phone_key).is_empty());
+
+        let laptop_status = laptop.verification_status(&tx).unwrap();
+        assert_eq!(laptop_status.state, VerificationState::KeysExchanged);
+        assert_eq!(
+            laptop_status.emojis,
+            phone.verification_status(&tx).unwrap().emojis
+        );
+
+        let laptop_mac = laptop.confirm_sas(&tx).unwrap();
+        assert!(deliver(&phone, phone_id, &laptop_peer, &laptop_mac).is_empty());
+        let phone_messages = phone.confirm_sas(&tx).unwrap();
+        deliver(&laptop, laptop_id, &phone_peer, &phone_messages);
+
+        assert_eq!(
+            laptop.verification_status(&tx).unwrap().state,
+            VerificationState::Done
+        );
+        assert!(laptop
+            .is_key_verified(user_id, &phone_peer.identity_key_ed25519)
+            .unwrap());
+        assert!(phone.is_key_verified(user_id, &keys.master_key).unwrap());
+
+        // The laptop holds the cross-signing keys, so it can vouch for the phone
+        let Some(VerificationSignature::Device {
+            device_id,
+            signature,
+        }) = laptop.verification_signature(&tx).unwrap()
+        else {
+            panic!("expected a device signature");
+        };
+        assert_eq!(device_id, phone_id);
+        assert!(keys
+            .verify_device(
+                &user_id.to_string(),
+                &phone_id.to_string(),
+                &phone_peer.identity_key_ed25519,
+                &phone_peer.identity_key_curve25519,
+                &signature,
+            )
+            .is_ok());
+        assert_eq!(phone.verification_signature(&tx).unwrap(), None);
+    }
 }
@@ -0,0 +1,951 @@
+//! Encrypted Message Cache
+//!
+//! Decrypted message history kept on disk, so channels open offline and
+//! restarts don't have to decrypt everything again, and so end-to-end
+//! encrypted messages can be searched locally (the server can't index them).
+//!
+//! Messages are stored AES-256-GCM encrypted under a key derived from the key
+//! store key. Message and channel IDs are stored as keyed hashes, and the
+//! search index holds keyed hashes of normalized words: matching needs the
+//! key, but how often a word occurs in the cache is visible on disk. Only
+//! message timestamps are stored in plaintext, for ordering and retention.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+use zeroize::Zeroizing;
+
+use super::store::Result;
+
+/// Longest word kept in the search index; longer words are truncated.
+const MAX_TERM_CHARS: usize = 64;
+
+/// A decrypted message as shown by the client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedMessage {
+    /// Message ID.
+    pub id: Uuid,
+    /// Channel the message was posted in.
+    pub channel_id: Uuid,
+    /// Decrypted text, indexed for search.
+    pub content: String,
+    /// When the message was posted.
+    pub created_at: DateTime<Utc>,
+    /// When the message was last edited.
+    #[serde(default)]
+    pub edited_at: Option<DateTime<Utc>>,
+    /// When a disappearing message expires; it is dropped from the cache then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Set by the client when the message couldn't be decrypted; such
+    /// messages are never cached.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub undecryptable: bool,
+    /// Everything else the client shows (author, attachments, reactions...).
+    #[serde(flatten)]
+    pub rest: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A page of cached messages, newest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMessagePage {
+    /// Messages, newest first.
+    pub items: Vec<CachedMessage>,
+    /// Whether older messages are cached.
+    pub has_more: bool,
+}
+
+/// How much history the cache keeps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Drop messages posted more than this many days ago; `None` keeps them.
+    pub max_age_days: Option<u32>,
+    /// Keep at most this many of the newest messages per channel; `None`
+    /// keeps all of them.
+    pub max_messages_per_channel: Option<u32>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: Some(90),
+            max_messages_per_channel: Some(5000),
+        }
+    }
+}
+
+/// Local encrypted message cache.
+pub struct MessageCache {
+    conn: Connection,
+    cipher_key: Zeroizing<[u8; 32]>,
+    index_key: Zeroizing<[u8; 32]>,
+}
+
+impl MessageCache {
+    const CIPHER_DOMAIN: &'static [u8] = b"vc-client:message_cache:v1";
+    const INDEX_DOMAIN: &'static [u8] = b"vc-client:message_cache_index:v1";
+    const RETENTION_KEY: &'static str = "retention";
+
+    /// Create or open a message cache at the given path, and drop messages
+    /// outside the retention policy.
+    ///
+    /// The cache keys are derived from `encryption_key`, the key store key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or the schema cannot
+    /// be initialized.
+    pub fn open(path: &Path, encryption_key: [u8; 32]) -> Result<Self> {
+        let encryption_key = Zeroizing::new(encryption_key);
+        let cache = Self {
+            conn: Connection::open(path)?,
+            cipher_key: derive_key(&encryption_key, Self::CIPHER_DOMAIN),
+            index_key: derive_key(&encryption_key, Self::INDEX_DOMAIN),
+        };
+        cache.init_schema()?;
+        cache.prune()?;
+        Ok(cache)
+    }
+
+    fn init_schema(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS cached_messages (
+                lookup TEXT PRIMARY KEY,
+                channel TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                cached_at INTEGER NOT NULL,
+                expires_at INTEGER,
+                value TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_cached_messages_channel
+                ON cached_messages(channel, created_at);
+            CREATE INDEX IF NOT EXISTS idx_cached_messages_created
+                ON cached_messages(created_at);
+            CREATE INDEX IF NOT EXISTS idx_cached_messages_expires
+                ON cached_messages(expires_at) WHERE expires_at IS NOT NULL;
+            CREATE TABLE IF NOT EXISTS cached_message_terms (
+                term TEXT NOT NULL,
+                lookup TEXT NOT NULL,
+                PRIMARY KEY (term, lookup)
+            );
+            CREATE INDEX IF NOT EXISTS idx_cached_message_terms_lookup
+                ON cached_message_terms(lookup);
+            ",
+        )?;
+        Ok(())
+    }
+
+    fn keyed_hash(&self, domain: &str, value: &str) -> String {
+        let mut mac = match <Hmac<Sha256> as Mac>::new_from_slice(self.index_key.as_ref()) {
+            Ok(mac) => mac,
+            Err(_) => unreachable!("HMAC-SHA256 accepts keys of any length"),
+        };
+        mac.update(domain.as_bytes());
+        mac.update(&[0u8]);
+        mac.update(value.as_bytes());
+        STANDARD.encode(mac.finalize().into_bytes())
+    }
+
+    fn message_lookup(&self, message_id: Uuid) -> String {
+        self.keyed_hash("cache:message", &message_id.to_string())
+    }
+
+    fn channel_lookup(&self, channel_id: Uuid) -> String {
+        self.keyed_hash("cache:channel", &channel_id.to_string())
+    }
+
+    fn term_lookup(&self, term: &str) -> String {
+        self.keyed_hash("cache:term", term)
+    }
+
+    /// Encrypt a message, bound to its row so rows can't be swapped.
+    fn seal(&self, lookup: &str, message: &CachedMessage) -> Result<String> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(message)?);
+        let cipher = match Aes256Gcm::new_from_slice(self.cipher_key.as_ref()) {
+            Ok(cipher) => cipher,
+            Err(_) => unreachable!("derived key size matches AES-256 key size"),
+        };
+
+        let mut nonce_bytes = [0u8; 12];
+        getrandom::getrandom(&mut nonce_bytes).map_err(|e| {
+            vc_crypto::CryptoError::InvalidKey(format!("Nonce generation failed: {e}"))
+        })?;
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce_bytes),
+                Payload {
+                    msg: &plaintext,
+                    aad: lookup.as_bytes(),
+                },
+            )
+            .map_err(|e| {
+                vc_crypto::CryptoError::InvalidKey(format!("Message encryption failed: {e}"))
+            })?;
+
+        let mut combined = Vec::with_capacity(nonce_bytes.len() + ciphertext.len());
+        combined.extend_from_slice(&nonce_bytes);
+        combined.extend_from_slice(&ciphertext);
+        Ok(STANDARD.encode(combined))
+    }
+
+    fn unseal(&self, lookup: &str, stored: &str) -> Result<CachedMessage> {
+        let invalid = || vc_crypto::CryptoError::DecryptionFailed("Corrupt cached message".into());
+        let combined = STANDARD.decode(stored).map_err(|_| invalid())?;
+        if combined.len() <= 12 {
+            return Err(invalid().into());
+        }
+        let (nonce_bytes, ciphertext) = combined.split_at(12);
+        let cipher = match Aes256Gcm::new_from_slice(self.cipher_key.as_ref()) {
+            Ok(cipher) => cipher,
+            Err(_) => unreachable!("derived key size matches AES-256 key size"),
+        };
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    Nonce::from_slice(nonce_bytes),
+                    Payload {
+                        msg: ciphertext,
+                        aad: lookup.as_bytes(),
+                    },
+                )
+                .map_err(|_| invalid())?,
+        );
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// Cache decrypted messages, replacing earlier copies (e.g. after an
+    /// edit). Undecryptable messages are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encryption or the database write fails.
+    pub fn store_messages(&self, messages: &[CachedMessage]) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut channels = HashSet::new();
+        for message in messages.iter().filter(|m| !m.undecryptable) {
+            self.write_message(message)?;
+            channels.insert(message.channel_id);
+        }
+        for channel_id in channels {
+            self.prune_channel(&self.channel_lookup(channel_id))?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn write_message(&self, message: &CachedMessage) -> Result<()> {
+        let lookup = self.message_lookup(message.id);
+        let value = self.seal(&lookup, message)?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO cached_messages
+                 (lookup, channel, created_at, cached_at, expires_at, value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                lookup,
+                self.channel_lookup(message.channel_id),
+                message.created_at.timestamp_millis(),
+                Utc::now().timestamp(),
+                message.expires_at.map(|at| at.timestamp_millis()),
+                value
+            ],
+        )?;
+
+        self.conn.execute(
+            "DELETE FROM cached_message_terms WHERE lookup = ?1",
+            params![lookup],
+        )?;
+        let mut insert = self.conn.prepare_cached(
+            "INSERT OR IGNORE INTO cached_message_terms (term, lookup) VALUES (?1, ?2)",
+        )?;
+        for term in search_terms(&message.content) {
+            insert.execute(params![self.term_lookup(&term), lookup])?;
+        }
+        Ok(())
+    }
+
+    fn load_message(&self, lookup: &str) -> Result<Option<CachedMessage>> {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM cached_messages WHERE lookup = ?1",
+                params![lookup],
+                |row| row.get(0),
+            )
+            .optional()?;
+        value.map(|value| self.unseal(lookup, &value)).transpose()
+    }
+
+    /// Remove a message, e.g. after it was deleted on the server.
+    ///
+    /// Returns whether the message was cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    pub fn remove_message(&self, message_id: Uuid) -> Result<bool> {
+        let lookup = self.message_lookup(message_id);
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM cached_message_terms WHERE lookup = ?1",
+            params![lookup],
+        )?;
+        let removed = tx.execute(
+            "DELETE FROM cached_messages WHERE lookup = ?1",
+            params![lookup],
+        )?;
+        tx.commit()?;
+        Ok(removed > 0)
+    }
+
+    /// Get cached messages of a channel, newest first, posted before the
+    /// cached message `before` (or the newest ones).
+    ///
+    /// Returns an empty page if `before` isn't cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query or decryption fails.
+    pub fn message_page(
+        &self,
+        channel_id: Uuid,
+        before: Option<Uuid>,
+        limit: u32,
+    ) -> Result<CachedMessagePage> {
+        self.drop_expired()?;
+        let channel = self.channel_lookup(channel_id);
+        let (before_at, before_lookup) = match before {
+            Some(before) => {
+                let lookup = self.message_lookup(before);
+                match self.created_at(&lookup)? {
+                    Some(created_at) => (created_at, lookup),
+                    None => {
+                        return Ok(CachedMessagePage {
+                            items: Vec::new(),
+                            has_more: false,
+                        })
+                    }
+                }
+            }
+            None => (i64::MAX, String::new()),
+        };
+
+        let mut stmt = self.conn.prepare(
+            "SELECT lookup, value FROM cached_messages
+             WHERE channel = ?1
+               AND (created_at < ?2 OR (created_at = ?2 AND lookup < ?3))
+             ORDER BY created_at DESC, lookup DESC
+             LIMIT ?4",
+        )?;
+        let rows = stmt
+            .query_map(
+                params![channel, before_at, before_lookup, i64::from(limit) + 1],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let has_more = rows.len() > limit as usize;
+        let items = rows
+            .iter()
+            .take(limit as usize)
+            .map(|(lookup, value)| self.unseal(lookup, value))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(CachedMessagePage { items, has_more })
+    }
+
+    fn created_at(&self, lookup: &str) -> Result<Option<i64>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT created_at FROM cached_messages WHERE lookup = ?1",
+                params![lookup],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Reconcile the cache with a page of messages fetched from the server.
+    ///
+    /// `page` is the server's answer for messages of `channel_id` before
+    /// `before`, newest first, with `has_more` telling whether older ones
+    /// exist. Decrypted messages are cached, replacing stale copies; cached
+    /// messages in the time span the page covers that the server no longer
+    /// returns were deleted and are removed. Returns the page with
+    /// undecryptable messages replaced by their cached copies where the
+    /// message hasn't been edited since.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query, write or encryption fails.
+    pub fn reconcile(
+        &self,
+        channel_id: Uuid,
+        before: Option<Uuid>,
+        page: Vec<CachedMessage>,
+        has_more: bool,
+    ) -> Result<Vec<CachedMessage>> {
+        let channel = self.channel_lookup(channel_id);
+        let tx = self.conn.unchecked_transaction()?;
+
+        // Span covered by the page; bounds touching other pages are exclusive
+        let newest = page.iter().map(|m| m.created_at.timestamp_millis()).max();
+        let oldest = page.iter().map(|m| m.created_at.timestamp_millis()).min();
+        let upper = match before {
+            None => Some(i64::MAX),
+            Some(before) => self
+                .created_at(&self.message_lookup(before))?
+                .or(newest.map(|newest| newest + 1)),
+        };
+        let lower = if has_more { oldest } else { Some(i64::MIN) };
+
+        if let (Some(upper), Some(lower)) = (upper, lower) {
+            let returned: HashSet<String> =
+                page.iter().map(|m| self.message_lookup(m.id)).collect();
+            let mut stmt = self.conn.prepare(
+                "SELECT lookup FROM cached_messages
+                 WHERE channel = ?1 AND created_at > ?2 AND created_at < ?3",
+            )?;
+            let deleted = stmt
+                .query_map(params![channel, lower, upper], |row| {
+                    row.get::<_, String>(0)
+                })?
+                .filter(|lookup| !matches!(lookup, Ok(lookup) if returned.contains(lookup)))
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            for lookup in deleted {
+                self.conn.execute(
+                    "DELETE FROM cached_message_terms WHERE lookup = ?1",
+                    params![lookup],
+                )?;
+                self.conn.execute(
+                    "DELETE FROM cached_messages WHERE lookup = ?1",
+                    params![lookup],
+                )?;
+            }
+        }
+
+        let mut merged = Vec::with_capacity(page.len());
+        for message in page {
+            if message.undecryptable {
+                let cached = self.load_message(&self.message_lookup(message.id))?;
+                match cached {
+                    // Keep the server's reactions, pins and thread counts
+                    Some(cached) if cached.edited_at == message.edited_at => {
+                        let mut rest = message.rest;
+                        rest.remove("ciphertext");
+                        if let Some(attachments) = cached.rest.get("attachments") {
+                            rest.insert("attachments".to_string(), attachments.clone());
+                        }
+                        merged.push(CachedMessage {
+                            content: cached.content,
+                            undecryptable: false,
+                            rest,
+                            ..message
+                        });
+                    }
+                    _ => merged.push(message),
+                }
+            } else {
+                self.write_message(&message)?;
+                merged.push(message);
+            }
+        }
+        self.prune_channel(&channel)?;
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    /// Search cached messages for all words of `query`, newest first.
+    ///
+    /// Words are matched whole and case-insensitively. Pass `channel_ids` to
+    /// only search those channels.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query or decryption fails.
+    pub fn search(
+        &self,
+        query: &str,
+        channel_ids: Option<&[Uuid]>,
+        limit: u32,
+    ) -> Result<Vec<CachedMessage>> {
+        let terms: Vec<String> = search_terms(query)
+            .iter()
+            .map(|term| self.term_lookup(term))
+            .collect();
+        if terms.is_empty() || channel_ids.is_some_and(<[Uuid]>::is_empty) {
+            return Ok(Vec::new());
+        }
+        self.drop_expired()?;
+
+        let term_params = vec!["?"; terms.len()].join(", ");
+        let mut sql = format!(
+            "SELECT lookup, value FROM cached_messages
+             WHERE lookup IN (
+                 SELECT lookup FROM cached_message_terms WHERE term IN ({term_params})
+                 GROUP BY lookup HAVING COUNT(*) = {}
+             )",
+            terms.len()
+        );
+        let mut values = terms;
+        if let Some(channel_ids) = channel_ids {
+            sql.push_str(&format!(
+                " AND channel IN ({})",
+                vec!["?"; channel_ids.len()].join(", ")
+            ));
+            values.extend(channel_ids.iter().map(|id| self.channel_lookup(*id)));
+        }
+        sql.push_str(&format!(
+            " ORDER BY created_at DESC, lookup DESC LIMIT {limit}"
+        ));
+
+        let mut stmt = self.conn.prepare(&sql)?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        rows.iter()
+            .map(|(lookup, value)| self.unseal(lookup, value))
+            .collect()
+    }
+
+    /// Remove cached messages of one channel, or of all channels.
+    ///
+    /// Returns the number of removed messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    pub fn clear(&self, channel_id: Option<Uuid>) -> Result<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let removed = match channel_id {
+            Some(channel_id) => {
+                let channel = self.channel_lookup(channel_id);
+                tx.execute(
+                    "DELETE FROM cached_message_terms WHERE lookup IN
+                     (SELECT lookup FROM cached_messages WHERE channel = ?1)",
+                    params![channel],
+                )?;
+                tx.execute(
+                    "DELETE FROM cached_messages WHERE channel = ?1",
+                    params![channel],
+                )?
+            }
+            None => {
+                tx.execute("DELETE FROM cached_message_terms", [])?;
+                tx.execute("DELETE FROM cached_messages", [])?
+            }
+        };
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// The current retention policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query or deserialization fails.
+    pub fn retention(&self) -> Result<RetentionPolicy> {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                params![Self::RETENTION_KEY],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(match value {
+            Some(value) => serde_json::from_str(&value)?,
+            None => RetentionPolicy::default(),
+        })
+    }
+
+    /// Change the retention policy and drop messages outside it.
+    ///
+    /// Returns the number of removed messages.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or the database write fails.
+    pub fn set_retention(&self, policy: RetentionPolicy) -> Result<usize> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+            params![Self::RETENTION_KEY, serde_json::to_string(&policy)?],
+        )?;
+        self.prune()
+    }
+
+    /// Drop disappearing messages that have expired.
+    fn drop_expired(&self) -> Result<usize> {
+        let now = Utc::now().timestamp_millis();
+        self.conn.execute(
+            "DELETE FROM cached_message_terms WHERE lookup IN
+             (SELECT lookup FROM cached_messages WHERE expires_at <= ?1)",
+            params![now],
+        )?;
+        Ok(self.conn.execute(
+            "DELETE FROM cached_messages WHERE expires_at <= ?1",
+            params![now],
+        )?)
+    }
+
+    /// Drop expired messages and messages outside the retention policy in
+    /// all channels.
+    fn prune(&self) -> Result<usize> {
+        let policy = self.retention()?;
+        let mut removed = self.drop_expired()?;
+        let tx = self.conn.unchecked_transaction()?;
+        if let Some(days) = policy.max_age_days {
+            let cutoff = Utc::now() - chrono::Duration::days(i64::from(days));
+            removed += tx.execute(
+                "DELETE FROM cached_messages WHERE created_at < ?1",
+                params![cutoff.timestamp_millis()],
+            )?;
+        }
+        if let Some(max) = policy.max_messages_per_channel {
+            removed += tx.execute(
+                "DELETE FROM cached_messages WHERE lookup IN (
+                     SELECT lookup FROM (
+                         SELECT lookup, ROW_NUMBER() OVER (
+                             PARTITION BY channel ORDER BY created_at DESC, lookup DESC
+                         ) AS position
+                         FROM cached_messages
+                     ) WHERE position > ?1
+                 )",
+                params![max],
+            )?;
+        }
+        if removed > 0 {
+            tx.execute(
+                "DELETE FROM cached_message_terms
+                 WHERE lookup NOT IN (SELECT lookup FROM cached_messages)",
+                [],
+            )?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// Drop the oldest messages of a channel beyond the retention policy.
+    fn prune_channel(&self, channel: &str) -> Result<()> {
+        let Some(max) = self.retention()?.max_messages_per_channel else {
+            return Ok(());
+        };
+        self.conn.execute(
+            "DELETE FROM cached_message_terms WHERE lookup IN (
+                 SELECT lookup FROM cached_messages WHERE channel = ?1
+                 ORDER BY created_at DESC, lookup DESC LIMIT -1 OFFSET ?2
+             )",
+            params![channel, max],
+        )?;
+        self.conn.execute(
+            "DELETE FROM cached_messages WHERE lookup IN (
+                 SELECT lookup FROM cached_messages WHERE channel = ?1
+                 ORDER BY created_at DESC, lookup DESC LIMIT -1 OFFSET ?2
+             )",
+            params![channel, max],
+        )?;
+        Ok(())
+    }
+}
+
+fn derive_key(key: &[u8; 32], domain: &[u8]) -> Zeroizing<[u8; 32]> {
+    let mut mac = match <Hmac<Sha256> as Mac>::new_from_slice(key) {
+        Ok(mac) => mac,
+        Err(_) => unreachable!("HMAC-SHA256 accepts keys of any length"),
+    };
+    mac.update(domain);
+    let mut derived = Zeroizing::new([0u8; 32]);
+    derived.copy_from_slice(&mac.finalize().into_bytes());
+    derived
+}
+
+/// Normalized, distinct words of a text, as indexed for search.
+fn search_terms(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            word.to_lowercase()
+                .chars()
+                .take(MAX_TERM_CHARS)
+                .collect::<String>()
+        })
+        .filter(|term| seen.insert(term.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn message(channel_id: Uuid, minute: u32, content: &str) -> CachedMessage {
+        CachedMessage {
+            id: Uuid::new_v4(),
+            channel_id,
+            content: content.to_string(),
+            created_at: Utc::now() - chrono::Duration::hours(1)
+                + chrono::Duration::minutes(i64::from(minute)),
+            edited_at: None,
+            expires_at: None,
+            undecryptable: false,
+            rest: serde_json::json!({ "encrypted": true, "author": { "id": "a" } })
+                .as_object()
+                .cloned()
+                .unwrap(),
+        }
+    }
+
+    fn open(dir: &Path) -> MessageCache {
+        MessageCache::open(&dir.join("messages.db"), [7u8; 32]).unwrap()
+    }
+
+    #[test]
+    fn test_message_cache_pages_and_encryption() {
+        let dir = tempdir().unwrap();
+        let cache = open(dir.path());
+        let channel = Uuid::new_v4();
+        let messages: Vec<_> = (0..5)
+            .map(|i| message(channel, i, &format!("secret number {i}")))
+            .collect();
+        cache.store_messages(&messages).unwrap();
+        cache
+            .store_messages(&[message(Uuid::new_v4(), 0, "other channel")])
+            .unwrap();
+
+        let page = cache.message_page(channel, None, 3).unwrap();
+        assert!(page.has_more);
+        assert_eq!(
+            page.items.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![messages[4].id, messages[3].id, messages[2].id]
+        );
+        assert_eq!(page.items[0], messages[4]);
+
+        let page = cache
+            .message_page(channel, Some(messages[2].id), 3)
+            .unwrap();
+        assert!(!page.has_more);
+        assert_eq!(page.items.len(), 2);
+
+        // Nothing readable on disk
+        drop(cache);
+        let raw = std::fs::read(dir.path().join("messages.db")).unwrap();
+        let raw = String::from_utf8_lossy(&raw);
+        assert!(!raw.contains("secret"));
+        assert!(!raw.contains(&channel.to_string()));
+
+        // The wrong key can't read the cache
+        let wrong = MessageCache::open(&dir.path().join("messages.db"), [8u8; 32]).unwrap();
+        assert!(wrong
+            .message_page(channel, None, 3)
+            .unwrap()
+            .items
+            .is_empty());
+    }
+
+    #[test]
+    fn test_message_cache_reconcile() {
+        let dir = tempdir().unwrap();
+        let cache = open(dir.path());
+        let channel = Uuid::new_v4();
+        let mut messages: Vec<_> = (0..4)
+            .map(|i| message(channel, i, &format!("message {i}")))
+            .collect();
+        cache.store_messages(&messages).unwrap();
+
+        // The server no longer has message 1, message 2 was edited and
+        // message 3 can't be decrypted right now
+        let edited_at = Utc.timestamp_opt(1_900_000_000, 0).unwrap();
+        messages[2].content = "edited".to_string();
+        messages[2].edited_at = Some(edited_at);
+        let mut undecryptable = messages[3].clone();
+        undecryptable.content = "[Unable to decrypt]".to_string();
+        undecryptable.undecryptable = true;
+        let page = vec![undecryptable, messages[2].clone(), messages[0].clone()];
+
+        let merged = cache.reconcile(channel, None, page, false).unwrap();
+        assert_eq!(merged[0], messages[3]);
+        assert_eq!(merged[1].content, "edited");
+
+        let cached = cache.message_page(channel, None, 10).unwrap().items;
+        assert_eq!(
+            cached.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![messages[3].id, messages[2].id, messages[0].id]
+        );
+        assert_eq!(cache.search("message", None, 10).unwrap().len(), 2);
+        assert_eq!(cache.search("edited", None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_message_cache_reconcile_keeps_messages_outside_page() {
+        let dir = tempdir().unwrap();
+        let cache = open(dir.path());
+        let channel = Uuid::new_v4();
+        let messages: Vec<_> = (0..6).map(|i| message(channel, i, "hi")).collect();
+        cache.store_messages(&messages).unwrap();
+
+        // A middle page; older and newer messages live in other pages
+        let page = vec![messages[3].clone(), messages[2].clone()];
+        cache
+            .reconcile(channel, Some(messages[4].id), page, true)
+            .unwrap();
+        assert_eq!(
+            cache.message_page(channel, None, 10).unwrap().items.len(),
+            6
+        );
+
+        // Message 2 was deleted
+        let page = vec![messages[3].clone(), messages[1].clone()];
+        cache
+            .reconcile(channel, Some(messages[4].id), page, true)
+            .unwrap();
+        assert_eq!(
+            cache.message_page(channel, None, 10).unwrap().items.len(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_message_cache_search() {
+        let dir = tempdir().unwrap();
+        let cache = open(dir.path());
+        let first = Uuid::new_v4();
+        let second = Uuid::new_v4();
+        let hello = message(first, 0, "Hello, World!");
+        let meeting = message(first, 1, "Meeting moved to Friday");
+        let elsewhere = message(second, 2, "hello from elsewhere");
+        cache
+            .store_messages(&[hello.clone(), meeting.clone(), elsewhere.clone()])
+            .unwrap();
+
+        let ids = |results: Vec<CachedMessage>| results.iter().map(|m| m.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(cache.search("HELLO", None, 10).unwrap()),
+            vec![elsewhere.id, hello.id]
+        );
+        assert_eq!(
+            ids(cache.search("hello world", None, 10).unwrap()),
+            vec![hello.id]
+        );
+        assert_eq!(
+            ids(cache.search("hello", Some(&[first]), 10).unwrap()),
+            vec![hello.id]
+        );
+        assert!(cache.search("hel", None, 10).unwrap().is_empty());
+        assert!(cache.search("  ", None, 10).unwrap().is_empty());
+
+        assert!(cache.remove_message(hello.id).unwrap());
+        assert!(!cache.remove_message(hello.id).unwrap());
+        assert_eq!(
+            ids(cache.search("hello", None, 10).unwrap()),
+            vec![elsewhere.id]
+        );
+        assert_eq!(cache.clear(Some(second)).unwrap(), 1);
+        assert!(cache.search("hello", None, 10).unwrap().is_empty());
+        assert_eq!(
+            ids(cache.search("friday", None, 10).unwrap()),
+            vec![meeting.id]
+        );
+    }
+
+    #[test]
+    fn test_message_cache_retention() {
+        let dir = tempdir().unwrap();
+        let cache = open(dir.path());
+        assert_eq!(cache.retention().unwrap(), RetentionPolicy::default());
+
+        let channel = Uuid::new_v4();
+        let mut old = message(channel, 0, "old news");
+        old.created_at = Utc::now() - chrono::Duration::days(30);
+        let recent: Vec<_> = (0..3).map(|i| message(channel, i, "recent")).collect();
+        cache.store_messages(&[old.clone()]).unwrap();
+        cache.store_messages(&recent).unwrap();
+
+        let removed = cache
+            .set_retention(RetentionPolicy {
+                max_age_days: Some(7),
+                max_messages_per_channel: Some(2),
+            })
+            .unwrap();
+        assert_eq!(removed, 2);
+        let items = cache.message_page(channel, None, 10).unwrap().items;
+        assert_eq!(
+            items.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![recent[2].id, recent[1].id]
+        );
+        assert!(cache.search("old", None, 10).unwrap().is_empty());
+
+        // The per-channel limit also applies to new messages
+        cache
+            .store_messages(&[message(channel, 5, "newest")])
+            .unwrap();
+        assert_eq!(
+            cache.message_page(channel, None, 10).unwrap().items.len(),
+            2
+        );
+
+        // The policy survives reopening
+        drop(cache);
+        assert_eq!(
+            open(dir.path())
+                .retention()
+                .unwrap()
+                .max_messages_per_channel,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn test_expired_messages_are_dropped() {
+        let dir = tempdir().unwrap();
+        let cache = open(dir.path());
+        let channel = Uuid::new_v4();
+        let mut expired = message(channel, 0, "gone soon");
+        expired.expires_at = Some(Utc::now() - chrono::Duration::seconds(1));
+        let mut pending = message(channel, 1, "still here");
+        pending.expires_at = Some(Utc::now() + chrono::Duration::hours(1));
+        cache.store_messages(&[expired, pending.clone()]).unwrap();
+
+        let items = cache.message_page(channel, None, 10).unwrap().items;
+        assert_eq!(
+            items.iter().map(|m| m.id).collect::<Vec<_>>(),
+            vec![pending.id]
+        );
+        assert!(cache.search("gone", None, 10).unwrap().is_empty());
+        assert_eq!(cache.search("still", None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_cached_message_keeps_client_fields() {
+        let json = serde_json::json!({
+            "id": Uuid::new_v4(),
+            "channel_id": Uuid::new_v4(),
+            "content": "hi",
+            "created_at": "2026-01-01T00:00:00Z",
+            "edited_at": null,
+            "author": { "id": "a", "username": "alice" },
+            "attachments": [],
+            "pinned": false,
+        });
+        let message: CachedMessage = serde_json::from_value(json.clone()).unwrap();
+        assert!(!message.undecryptable);
+        assert_eq!(serde_json::to_value(&message).unwrap(), json);
+    }
+}
@@ -3,6 +3,13 @@
 //! Local storage and management of cryptographic keys for E2EE messaging.
 
 pub mod manager;
+pub mod message_cache;
 pub mod store;
 
-pub use manager::{ClaimedPrekey, CryptoManager, PrekeyForUpload, PrekeyInfo};
+#[cfg(feature = "megolm")]
+pub use manager::GroupCiphertext;
+pub use manager::{
+    ClaimedPrekey, CryptoManager, DeviceKeys, PrekeyForUpload, PrekeyInfo, VerificationPeer,
+    VerificationSignature, VerificationStatus,
+};
+pub use message_cache::MessageCache;
@@ -15,6 +15,7 @@ use sha2::{Digest, Sha256};
 use thiserror::Error;
 use uuid::Uuid;
 #[cfg(feature = "megolm")]
+use vc_crypto::cross_signing::CrossSigningSecrets;
 use vc_crypto::megolm::{MegolmInboundSession, MegolmOutboundSession};
 use vc_crypto::olm::{OlmAccount, OlmSession};
 use zeroize::Zeroizing;
@@ -56,6 +57,17 @@ pub struct MegolmInboundKey {
     pub sender_key: String,
 }
 
+/// A device one of our outbound Megolm sessions was shared with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MegolmShare {
+    /// Owner of the device.
+    pub user_id: Uuid,
+    /// Server device ID.
+    pub device_id: Uuid,
+    /// The device's Curve25519 identity key (base64).
+    pub identity_key_curve25519: String,
+}
+
 /// Metadata about the local key store.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyStoreMetadata {
@@ -67,6 +79,39 @@ pub struct KeyStoreMetadata {
     pub created_at: i64,
 }
 
+/// A key of another device or user that was verified interactively.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifiedKey {
+    /// Owner of the key.
+    pub user_id: Uuid,
+    /// `ed25519:<device_id>` for a device key, `master` for a master key.
+    pub key_id: String,
+    /// The verified public key (base64).
+    pub key: String,
+    /// Unix timestamp of the verification.
+    pub verified_at: i64,
+}
+
+/// Megolm key backup this device uploads to.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeyBackupState {
+    /// Server-side backup version.
+    pub version: i64,
+    /// The version's backup key (base64).
+    pub backup_key: String,
+}
+
+/// Inbound Megolm session queued for the key backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingMegolmBackup {
+    /// The channel or group room ID.
+    pub room_id: String,
+    /// The sender's device Curve25519 public key (base64).
+    pub sender_key: String,
+    /// Megolm session ID.
+    pub session_id: String,
+}
+
 /// Local encrypted key store.
 ///
 /// Stores Olm accounts and sessions in `SQLite`, encrypted with the provided key.
@@ -133,6 +178,33 @@ impl LocalKeyStore {
                 updated_at INTEGER NOT NULL,
                 PRIMARY KEY (room_id, sender_key)
             );
+            CREATE TABLE IF NOT EXISTS cross_signing (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                serialized TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS verified_keys (
+                lookup TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                value TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_verified_keys_user ON verified_keys(user_id);
+            CREATE TABLE IF NOT EXISTS key_backup (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                serialized TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS megolm_backup_queue (
+                lookup TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                backed_up INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS megolm_inbound_sessions_by_id (
+                lookup TEXT PRIMARY KEY,
+                serialized TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS megolm_outbound_shares (
+                lookup TEXT PRIMARY KEY
+            );
             ",
         )?;
         Ok(())
@@ -289,6 +361,34 @@ impl LocalKeyStore {
         }
     }
 
+    /// Delete the session with a device, e.g. after the device was removed.
+    ///
+    /// Returns whether a session existed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    pub fn remove_session(&self, key: &SessionKey) -> Result<bool> {
+        let user_id = key.user_id.to_string();
+        let mut removed = 0;
+        for (hashed_user_id, hashed_device_key) in [
+            (
+                self.keyed_hash("session:user_id", &user_id),
+                self.keyed_hash("session:device_key", &key.device_curve25519),
+            ),
+            (
+                self.keyed_hash_legacy("session:user_id", &user_id),
+                self.keyed_hash_legacy("session:device_key", &key.device_curve25519),
+            ),
+        ] {
+            removed += self.conn.execute(
+                "DELETE FROM sessions WHERE user_id = ?1 AND device_key = ?2",
+                params![hashed_user_id, hashed_device_key],
+            )?;
+        }
+        Ok(removed > 0)
+    }
+
     /// Save a Megolm outbound group session.
     #[cfg(feature = "megolm")]
     pub fn save_megolm_outbound_session(
@@ -368,12 +468,47 @@ impl LocalKeyStore {
         }
     }
 
-    /// Save a Megolm inbound group session.
+    /// Discard all Megolm outbound group sessions so the next message in every
+    /// room starts a fresh session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    #[cfg(feature = "megolm")]
+    pub fn clear_megolm_outbound_sessions(&self) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("DELETE FROM megolm_outbound_sessions", [])?)
+    }
+
+    /// Save a Megolm inbound group session as the sender's current session
+    /// for the room.
     #[cfg(feature = "megolm")]
     pub fn save_megolm_inbound_session(
         &self,
         key: &MegolmInboundKey,
         session: &MegolmInboundSession,
+    ) -> Result<()> {
+        self.write_megolm_inbound_session(key, session, true)
+    }
+
+    /// Save a Megolm inbound group session without replacing the sender's
+    /// current session, e.g. an older session forwarded on request.
+    #[cfg(feature = "megolm")]
+    pub fn save_older_megolm_inbound_session(
+        &self,
+        key: &MegolmInboundKey,
+        session: &MegolmInboundSession,
+    ) -> Result<()> {
+        self.write_megolm_inbound_session(key, session, false)
+    }
+
+    #[cfg(feature = "megolm")]
+    fn write_megolm_inbound_session(
+        &self,
+        key: &MegolmInboundKey,
+        session: &MegolmInboundSession,
+        current: bool,
     ) -> Result<()> {
         let serialized = session.serialize(&self.encryption_key)?;
         let encrypted = self.encrypt_metadata_value(&serialized)?;
@@ -381,14 +516,75 @@ impl LocalKeyStore {
         let hashed_room_id = self.keyed_hash("megolm:room_inbound", &key.room_id);
         let hashed_sender = self.keyed_hash("megolm:sender", &key.sender_key);
 
-        self.conn.execute(
-            "INSERT OR REPLACE INTO megolm_inbound_sessions (room_id, sender_key, session_id, serialized, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![hashed_room_id, hashed_sender, session.session_id(), encrypted, now],
+        let session_id = session.session_id();
+        let pending = PendingMegolmBackup {
+            room_id: key.room_id.clone(),
+            sender_key: key.sender_key.clone(),
+            session_id: session_id.clone(),
+        };
+        let backup_lookup = self.megolm_backup_lookup(&key.room_id, &session_id);
+        let backup_value = self.encrypt_metadata_value(&serde_json::to_string(&pending)?)?;
+
+        // Sessions are also kept by ID, so messages from a sender's earlier
+        // sessions stay readable after the sender rotates. New sessions are
+        // queued for the key backup; saving an advanced ratchet of a known
+        // session doesn't queue it again
+        let tx = self.conn.unchecked_transaction()?;
+        if current {
+            tx.execute(
+                "INSERT OR REPLACE INTO megolm_inbound_sessions (room_id, sender_key, session_id, serialized, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![hashed_room_id, hashed_sender, session_id, encrypted, now],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO megolm_inbound_sessions_by_id (lookup, serialized, updated_at)
+             VALUES (?1, ?2, ?3)",
+            params![self.megolm_session_lookup(key, &session_id), encrypted, now],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO megolm_backup_queue (lookup, value) VALUES (?1, ?2)",
+            params![backup_lookup, backup_value],
         )?;
+        tx.commit()?;
         Ok(())
     }
 
+    /// Load a Megolm inbound group session by its session ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query or deserialization fails.
+    #[cfg(feature = "megolm")]
+    pub fn load_megolm_inbound_session_by_id(
+        &self,
+        key: &MegolmInboundKey,
+        session_id: &str,
+    ) -> Result<Option<MegolmInboundSession>> {
+        let result: std::result::Result<String, _> = self.conn.query_row(
+            "SELECT serialized FROM megolm_inbound_sessions_by_id WHERE lookup = ?1",
+            params![self.megolm_session_lookup(key, session_id)],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(serialized) => {
+                let json = self
+                    .decrypt_metadata_value(&serialized)
+                    .unwrap_or(serialized);
+                Ok(Some(MegolmInboundSession::deserialize(
+                    &json,
+                    &self.encryption_key,
+                )?))
+            }
+            // Sessions saved before they were also kept by ID
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(self
+                .load_megolm_inbound_session(key)?
+                .filter(|session| session.session_id() == session_id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Load a Megolm inbound group session.
     #[cfg(feature = "megolm")]
     pub fn load_megolm_inbound_session(
@@ -451,6 +647,213 @@ impl LocalKeyStore {
         }
     }
 
+    #[cfg(feature = "megolm")]
+    fn megolm_backup_lookup(&self, room_id: &str, session_id: &str) -> String {
+        self.keyed_hash("megolm:backup", &format!("{room_id}|{session_id}"))
+    }
+
+    #[cfg(feature = "megolm")]
+    fn megolm_session_lookup(&self, key: &MegolmInboundKey, session_id: &str) -> String {
+        self.keyed_hash(
+            "megolm:session",
+            &format!("{}|{}|{session_id}", key.room_id, key.sender_key),
+        )
+    }
+
+    #[cfg(feature = "megolm")]
+    fn megolm_share_lookup(&self, room_id: &str, session_id: &str, share: &MegolmShare) -> String {
+        self.keyed_hash(
+            "megolm:share:device",
+            &format!(
+                "{room_id}|{session_id}|{}|{}|{}",
+                share.user_id, share.device_id, share.identity_key_curve25519
+            ),
+        )
+    }
+
+    #[cfg(feature = "megolm")]
+    fn megolm_share_user_lookup(&self, room_id: &str, session_id: &str, user_id: Uuid) -> String {
+        self.keyed_hash(
+            "megolm:share:user",
+            &format!("{room_id}|{session_id}|{user_id}"),
+        )
+    }
+
+    #[cfg(feature = "megolm")]
+    fn has_megolm_share(&self, lookup: &str) -> Result<bool> {
+        Ok(self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM megolm_outbound_shares WHERE lookup = ?1)",
+            params![lookup],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Record the devices an outbound Megolm session was shared with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    #[cfg(feature = "megolm")]
+    pub fn save_megolm_shares(
+        &self,
+        room_id: &str,
+        session_id: &str,
+        shares: &[MegolmShare],
+    ) -> Result<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for share in shares {
+            for lookup in [
+                self.megolm_share_lookup(room_id, session_id, share),
+                self.megolm_share_user_lookup(room_id, session_id, share.user_id),
+            ] {
+                tx.execute(
+                    "INSERT OR IGNORE INTO megolm_outbound_shares (lookup) VALUES (?1)",
+                    params![lookup],
+                )?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Whether one of our outbound Megolm sessions was shared with exactly
+    /// this device, identity key included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    #[cfg(feature = "megolm")]
+    pub fn was_megolm_session_shared(
+        &self,
+        room_id: &str,
+        session_id: &str,
+        share: &MegolmShare,
+    ) -> Result<bool> {
+        self.has_megolm_share(&self.megolm_share_lookup(room_id, session_id, share))
+    }
+
+    /// Whether one of our outbound Megolm sessions was shared with any device
+    /// of `user_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    #[cfg(feature = "megolm")]
+    pub fn was_megolm_session_shared_with_user(
+        &self,
+        room_id: &str,
+        session_id: &str,
+        user_id: Uuid,
+    ) -> Result<bool> {
+        self.has_megolm_share(&self.megolm_share_user_lookup(room_id, session_id, user_id))
+    }
+
+    /// Inbound Megolm sessions not yet uploaded to the key backup.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query or deserialization fails.
+    #[cfg(feature = "megolm")]
+    pub fn pending_megolm_backups(&self, limit: usize) -> Result<Vec<PendingMegolmBackup>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT value FROM megolm_backup_queue WHERE backed_up = 0 LIMIT ?1")?;
+        let rows = stmt
+            .query_map(params![i64::try_from(limit).unwrap_or(i64::MAX)], |row| {
+                row.get::<_, String>(0)
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut pending = Vec::with_capacity(rows.len());
+        for stored in rows {
+            // Entries written under a different store key can't be read and are skipped
+            if let Some(json) = self.decrypt_metadata_value(&stored) {
+                pending.push(serde_json::from_str(&json)?);
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Mark an inbound Megolm session as uploaded to the key backup.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    #[cfg(feature = "megolm")]
+    pub fn mark_megolm_backed_up(&self, room_id: &str, session_id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE megolm_backup_queue SET backed_up = 1 WHERE lookup = ?1",
+            params![self.megolm_backup_lookup(room_id, session_id)],
+        )?;
+        Ok(())
+    }
+
+    /// Queue every known inbound Megolm session for upload again, e.g. after
+    /// switching to a new backup version.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    #[cfg(feature = "megolm")]
+    pub fn reset_megolm_backups(&self) -> Result<usize> {
+        Ok(self
+            .conn
+            .execute("UPDATE megolm_backup_queue SET backed_up = 0", [])?)
+    }
+
+    /// Save the key backup this device uploads to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization, encryption or the database write fails.
+    pub fn save_key_backup(&self, backup: &KeyBackupState) -> Result<()> {
+        let json = Zeroizing::new(serde_json::to_string(backup)?);
+        let encrypted = self.encrypt_metadata_value(&json)?;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO key_backup (id, serialized) VALUES (1, ?1)",
+            params![encrypted],
+        )?;
+        Ok(())
+    }
+
+    /// Load the key backup this device uploads to, if enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored state can't be decrypted or parsed.
+    pub fn load_key_backup(&self) -> Result<Option<KeyBackupState>> {
+        let result: std::result::Result<String, _> = self.conn.query_row(
+            "SELECT serialized FROM key_backup WHERE id = 1",
+            [],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(stored) => {
+                let json =
+                    Zeroizing::new(self.decrypt_metadata_value(&stored).ok_or_else(|| {
+                        vc_crypto::CryptoError::DecryptionFailed(
+                            "Key backup state could not be decrypted".to_string(),
+                        )
+                    })?);
+                Ok(Some(serde_json::from_str(&json)?))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Stop uploading to the key backup.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    pub fn clear_key_backup(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM key_backup", [])?;
+        Ok(())
+    }
+
     /// Save metadata.
     ///
     /// # Errors
@@ -496,6 +899,119 @@ impl LocalKeyStore {
         }
     }
 
+    /// Save our cross-signing private keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization, encryption or the database write fails.
+    pub fn save_cross_signing_secrets(&self, secrets: &CrossSigningSecrets) -> Result<()> {
+        let json = Zeroizing::new(serde_json::to_string(secrets)?);
+        let encrypted = self.encrypt_metadata_value(&json)?;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO cross_signing (id, serialized) VALUES (1, ?1)",
+            params![encrypted],
+        )?;
+        Ok(())
+    }
+
+    /// Load our cross-signing private keys, if this device holds them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stored keys can't be decrypted or parsed.
+    pub fn load_cross_signing_secrets(&self) -> Result<Option<CrossSigningSecrets>> {
+        let result: std::result::Result<String, _> = self.conn.query_row(
+            "SELECT serialized FROM cross_signing WHERE id = 1",
+            [],
+            |row| row.get(0),
+        );
+
+        match result {
+            Ok(stored) => {
+                let json =
+                    Zeroizing::new(self.decrypt_metadata_value(&stored).ok_or_else(|| {
+                        vc_crypto::CryptoError::DecryptionFailed(
+                            "Cross-signing keys could not be decrypted".to_string(),
+                        )
+                    })?);
+                Ok(Some(serde_json::from_str(&json)?))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Record a key verified through SAS or QR verification.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization, encryption or the database write fails.
+    pub fn save_verified_key(&self, verified: &VerifiedKey) -> Result<()> {
+        let user_id = verified.user_id.to_string();
+        let lookup = self.keyed_hash("trust:key", &format!("{user_id}|{}", verified.key));
+        let hashed_user = self.keyed_hash("trust:user_id", &user_id);
+        let encrypted = self.encrypt_metadata_value(&serde_json::to_string(verified)?)?;
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO verified_keys (lookup, user_id, value) VALUES (?1, ?2, ?3)",
+            params![lookup, hashed_user, encrypted],
+        )?;
+        Ok(())
+    }
+
+    /// Whether `key` has been verified for `user_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query fails.
+    pub fn is_key_verified(&self, user_id: Uuid, key: &str) -> Result<bool> {
+        let lookup = self.keyed_hash("trust:key", &format!("{user_id}|{key}"));
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM verified_keys WHERE lookup = ?1",
+            params![lookup],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// All verified keys of a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database query or deserialization fails.
+    pub fn verified_keys(&self, user_id: Uuid) -> Result<Vec<VerifiedKey>> {
+        let hashed_user = self.keyed_hash("trust:user_id", &user_id.to_string());
+        let mut stmt = self
+            .conn
+            .prepare("SELECT value FROM verified_keys WHERE user_id = ?1")?;
+        let rows = stmt
+            .query_map(params![hashed_user], |row| row.get::<_, String>(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut keys = Vec::with_capacity(rows.len());
+        for stored in rows {
+            // Entries written under a different store key can't be read and are skipped
+            if let Some(json) = self.decrypt_metadata_value(&stored) {
+                keys.push(serde_json::from_str(&json)?);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Forget all verified keys of a user, e.g. after their identity was reset.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database write fails.
+    pub fn remove_verified_keys(&self, user_id: Uuid) -> Result<usize> {
+        let hashed_user = self.keyed_hash("trust:user_id", &user_id.to_string());
+        Ok(self.conn.execute(
+            "DELETE FROM verified_keys WHERE user_id = ?1",
+            params![hashed_user],
+        )?)
+    }
+
     fn encrypt_metadata_value(&self, plaintext: &str) -> Result<String> {
         let key = self.derive_metadata_encryption_key();
 
@@ -659,6 +1175,32 @@ mod tests {
         assert_eq!(loaded.session_id(), session_id);
     }
 
+    #[test]
+    fn test_store_session_remove() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let key = [0u8; 32];
+
+        let store = LocalKeyStore::open(&path, key).unwrap();
+
+        let mut alice = OlmAccount::new();
+        let mut bob = OlmAccount::new();
+        bob.generate_one_time_keys(1);
+        let bob_otk = bob.one_time_keys().pop().unwrap().1;
+        let bob_otk_key = Curve25519PublicKey::from_base64(&bob_otk).unwrap();
+        let session = alice.create_outbound_session(&bob.curve25519_key(), &bob_otk_key);
+
+        let session_key = SessionKey {
+            user_id: Uuid::new_v4(),
+            device_curve25519: bob_otk,
+        };
+        store.save_session(&session_key, &session).unwrap();
+
+        assert!(store.remove_session(&session_key).unwrap());
+        assert!(store.load_session(&session_key).unwrap().is_none());
+        assert!(!store.remove_session(&session_key).unwrap());
+    }
+
     #[test]
     fn test_store_session_not_found() {
         let dir = tempdir().unwrap();
@@ -902,4 +1444,190 @@ mod tests {
             .unwrap();
         assert_eq!(count, 1, "Should have exactly one session after overwrite");
     }
+
+    #[test]
+    fn test_store_cross_signing_secrets() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        let store = LocalKeyStore::open(&path, [0u8; 32]).unwrap();
+        assert!(store.load_cross_signing_secrets().unwrap().is_none());
+
+        let identity = vc_crypto::CrossSigningIdentity::new("alice");
+        store
+            .save_cross_signing_secrets(&identity.secrets())
+            .unwrap();
+
+        let loaded = store.load_cross_signing_secrets().unwrap().unwrap();
+        assert_eq!(loaded.master, identity.secrets().master);
+
+        // Secrets are not stored in plaintext
+        let raw: String = store
+            .conn
+            .query_row("SELECT serialized FROM cross_signing", [], |row| row.get(0))
+            .unwrap();
+        assert!(!raw.contains(&loaded.master));
+
+        let other = LocalKeyStore::open(&path, [1u8; 32]).unwrap();
+        assert!(other.load_cross_signing_secrets().is_err());
+    }
+
+    #[cfg(feature = "megolm")]
+    #[test]
+    fn test_store_megolm_backup_queue() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        let store = LocalKeyStore::open(&path, [0u8; 32]).unwrap();
+        assert!(store.load_key_backup().unwrap().is_none());
+
+        let outbound = MegolmOutboundSession::new();
+        let session = MegolmInboundSession::new(&outbound.session_key()).unwrap();
+        let key = MegolmInboundKey {
+            room_id: "room-1".to_string(),
+            sender_key: "sender-key".to_string(),
+        };
+        store.save_megolm_inbound_session(&key, &session).unwrap();
+        store.save_megolm_inbound_session(&key, &session).unwrap();
+
+        let expected = PendingMegolmBackup {
+            room_id: key.room_id.clone(),
+            sender_key: key.sender_key.clone(),
+            session_id: session.session_id(),
+        };
+        assert_eq!(store.pending_megolm_backups(10).unwrap(), vec![expected]);
+
+        store
+            .mark_megolm_backed_up(&key.room_id, &session.session_id())
+            .unwrap();
+        assert!(store.pending_megolm_backups(10).unwrap().is_empty());
+
+        // Saving a known session again doesn't queue it
+        store.save_megolm_inbound_session(&key, &session).unwrap();
+        assert!(store.pending_megolm_backups(10).unwrap().is_empty());
+
+        assert_eq!(store.reset_megolm_backups().unwrap(), 1);
+        assert_eq!(store.pending_megolm_backups(10).unwrap().len(), 1);
+
+        store
+            .save_key_backup(&KeyBackupState {
+                version: 3,
+                backup_key: "c2VjcmV0".to_string(),
+            })
+            .unwrap();
+        assert_eq!(store.load_key_backup().unwrap().unwrap().version, 3);
+        store.clear_key_backup().unwrap();
+        assert!(store.load_key_backup().unwrap().is_none());
+    }
+
+    #[cfg(feature = "megolm")]
+    #[test]
+    fn test_store_megolm_sessions_by_id() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+        let store = LocalKeyStore::open(&path, [0u8; 32]).unwrap();
+
+        let key = MegolmInboundKey {
+            room_id: "room-1".to_string(),
+            sender_key: "sender-key".to_string(),
+        };
+        let first = MegolmInboundSession::new(&MegolmOutboundSession::new().session_key()).unwrap();
+        let second =
+            MegolmInboundSession::new(&MegolmOutboundSession::new().session_key()).unwrap();
+        store.save_megolm_inbound_session(&key, &first).unwrap();
+        store.save_megolm_inbound_session(&key, &second).unwrap();
+
+        // The sender rotated, but the first session is still there by ID
+        let current = store.load_megolm_inbound_session(&key).unwrap().unwrap();
+        assert_eq!(current.session_id(), second.session_id());
+        let by_id = store
+            .load_megolm_inbound_session_by_id(&key, &first.session_id())
+            .unwrap()
+            .unwrap();
+        assert_eq!(by_id.session_id(), first.session_id());
+
+        // Older sessions don't replace the current one
+        let older = MegolmInboundSession::new(&MegolmOutboundSession::new().session_key()).unwrap();
+        store
+            .save_older_megolm_inbound_session(&key, &older)
+            .unwrap();
+        let current = store.load_megolm_inbound_session(&key).unwrap().unwrap();
+        assert_eq!(current.session_id(), second.session_id());
+        assert!(store
+            .load_megolm_inbound_session_by_id(&key, &older.session_id())
+            .unwrap()
+            .is_some());
+        assert!(store
+            .load_megolm_inbound_session_by_id(&key, "unknown")
+            .unwrap()
+            .is_none());
+
+        let bob_laptop = MegolmShare {
+            user_id: Uuid::new_v4(),
+            device_id: Uuid::new_v4(),
+            identity_key_curve25519: "laptop-curve".to_string(),
+        };
+        let bob_phone = MegolmShare {
+            user_id: bob_laptop.user_id,
+            device_id: Uuid::new_v4(),
+            identity_key_curve25519: "phone-curve".to_string(),
+        };
+        assert!(!store
+            .was_megolm_session_shared("room-1", "s1", &bob_laptop)
+            .unwrap());
+        store
+            .save_megolm_shares("room-1", "s1", std::slice::from_ref(&bob_laptop))
+            .unwrap();
+        assert!(store
+            .was_megolm_session_shared("room-1", "s1", &bob_laptop)
+            .unwrap());
+        assert!(!store
+            .was_megolm_session_shared("room-1", "s2", &bob_laptop)
+            .unwrap());
+
+        // Other devices of the user, or a new key under the same device ID,
+        // only match the user-level record
+        let replaced_key = MegolmShare {
+            user_id: bob_laptop.user_id,
+            device_id: bob_laptop.device_id,
+            identity_key_curve25519: "other-curve".to_string(),
+        };
+        assert!(!store
+            .was_megolm_session_shared("room-1", "s1", &bob_phone)
+            .unwrap());
+        assert!(!store
+            .was_megolm_session_shared("room-1", "s1", &replaced_key)
+            .unwrap());
+        assert!(store
+            .was_megolm_session_shared_with_user("room-1", "s1", bob_laptop.user_id)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_store_verified_keys() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.db");
+
+        let store = LocalKeyStore::open(&path, [0u8; 32]).unwrap();
+        let alice = Uuid::new_v4();
+        let bob = Uuid::new_v4();
+        let verified = VerifiedKey {
+            user_id: alice,
+            key_id: "master".to_string(),
+            key: "alice-master".to_string(),
+            verified_at: 1,
+        };
+
+        assert!(!store.is_key_verified(alice, "alice-master").unwrap());
+        store.save_verified_key(&verified).unwrap();
+        assert!(store.is_key_verified(alice, "alice-master").unwrap());
+        // Trust is per user
+        assert!(!store.is_key_verified(bob, "alice-master").unwrap());
+
+        assert_eq!(store.verified_keys(alice).unwrap(), vec![verified]);
+        assert!(store.verified_keys(bob).unwrap().is_empty());
+
+        assert_eq!(store.remove_verified_keys(alice).unwrap(), 1);
+        assert!(!store.is_key_verified(alice, "alice-master").unwrap());
+    }
 }
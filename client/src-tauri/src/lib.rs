@@ -96,6 +96,9 @@ pub fn run() {
             commands::chat::get_channels,
             commands::chat::get_messages,
             commands::chat::send_message,
+            commands::chat::get_channel_encryption,
+            commands::chat::enable_channel_encryption,
+            commands::chat::set_disappearing_messages,
             commands::chat::edit_message,
             commands::chat::get_thread_replies,
             commands::chat::send_thread_reply,
@@ -210,12 +213,48 @@ pub fn run() {
             commands::crypto::mark_prekeys_published,
             commands::crypto::generate_prekeys,
             commands::crypto::needs_prekey_upload,
+            commands::crypto::replenish_keys,
             commands::crypto::get_our_curve25519_key,
             // Megolm commands
             commands::crypto::create_megolm_session,
+            commands::crypto::record_megolm_session_shares,
             commands::crypto::encrypt_group_message,
             commands::crypto::add_inbound_group_session,
             commands::crypto::decrypt_group_message,
+            commands::crypto::request_room_key,
+            commands::crypto::handle_room_key_request,
+            commands::crypto::add_forwarded_room_key,
+            commands::crypto::encrypt_attachment,
+            commands::crypto::download_encrypted_attachment,
+            commands::crypto::enable_key_backup,
+            commands::crypto::sync_key_backup,
+            commands::crypto::restore_key_backup,
+            // Cross-signing & verification commands
+            commands::crypto::bootstrap_cross_signing,
+            commands::crypto::request_device_verification,
+            commands::crypto::handle_verification_message,
+            commands::crypto::accept_device_verification,
+            commands::crypto::start_sas_verification,
+            commands::crypto::confirm_sas_verification,
+            commands::crypto::cancel_device_verification,
+            commands::crypto::get_verification_qr_code,
+            commands::crypto::scan_verification_qr_code,
+            commands::crypto::get_verification_status,
+            commands::crypto::get_verified_keys,
+            // Device management commands
+            commands::crypto::get_own_devices,
+            commands::crypto::rename_device,
+            commands::crypto::delete_device,
+            commands::crypto::handle_device_removed,
+            // Message cache commands
+            commands::message_cache::cache_messages,
+            commands::message_cache::sync_cached_messages,
+            commands::message_cache::get_cached_messages,
+            commands::message_cache::remove_cached_message,
+            commands::message_cache::search_cached_messages,
+            commands::message_cache::get_message_cache_retention,
+            commands::message_cache::set_message_cache_retention,
+            commands::message_cache::clear_message_cache,
             // Presence commands
             commands::presence::scan_processes,
             commands::presence::scan_all_processes,
@@ -339,6 +378,8 @@ pub struct AppState {
     /// E2EE crypto manager.
     /// Uses `Mutex` instead of `RwLock` because `rusqlite::Connection` is `Send` but not `Sync`.
     pub crypto: Arc<Mutex<Option<crypto::CryptoManager>>>,
+    /// Encrypted cache of decrypted messages, opened together with the crypto manager.
+    pub message_cache: Arc<Mutex<Option<crypto::MessageCache>>>,
     /// Cached UI state (category collapse). Lazy-loaded from disk on first access.
     pub ui_state: Arc<Mutex<Option<UiState>>>,
 }
@@ -356,6 +397,7 @@ impl AppState {
             websocket: Arc::new(RwLock::new(None)),
             voice: Arc::new(RwLock::new(None)),
             crypto: Arc::new(Mutex::new(None)),
+            message_cache: Arc::new(Mutex::new(None)),
             ui_state: Arc::new(Mutex::new(None)),
         }
     }
@@ -64,6 +64,8 @@ pub enum ClientEvent {
 pub enum ServerEvent {
     Ready {
         user_id: String,
+        #[serde(default)]
+        low_prekey_devices: Vec<serde_json::Value>,
     },
     Pong,
     Subscribed {
@@ -180,6 +182,16 @@ pub enum ServerEvent {
         channel_id: String,
         name: String,
     },
+    ChannelEncryptionEnabled {
+        channel_id: String,
+        epoch: i64,
+        enabled_by: String,
+    },
+    DisappearingMessagesUpdated {
+        channel_id: String,
+        message_ttl_seconds: Option<u32>,
+        updated_by: String,
+    },
     // Screen share events
     ScreenShareStarted {
         channel_id: String,
@@ -340,6 +352,22 @@ pub enum ServerEvent {
         entity_id: String,
         diff: serde_json::Value,
     },
+    // E2EE key supply
+    PrekeysLow {
+        device_id: String,
+        identity_key_curve25519: String,
+        one_time_prekeys: i64,
+        needs_fallback_key: bool,
+    },
+    ToDeviceMessage {
+        id: String,
+        sender_user_id: String,
+        sender_device_id: String,
+        recipient_device_id: String,
+        event_type: String,
+        ciphertext: String,
+        created_at: String,
+    },
 }
 
 /// Connection status.
@@ -582,6 +610,10 @@ fn handle_server_message(app: &AppHandle, text: &str) {
                 ServerEvent::ChannelRead { .. } => "ws:channel_read",
                 ServerEvent::DmRead { .. } => "ws:dm_read",
                 ServerEvent::DmNameUpdated { .. } => "ws:dm_name_updated",
+                ServerEvent::ChannelEncryptionEnabled { .. } => "ws:channel_encryption_enabled",
+                ServerEvent::DisappearingMessagesUpdated { .. } => {
+                    "ws:disappearing_messages_updated"
+                }
                 // Screen share events
                 ServerEvent::ScreenShareStarted { .. } => "ws:screen_share_started",
                 ServerEvent::ScreenShareStopped { .. } => "ws:screen_share_stopped",
@@ -624,6 +656,9 @@ fn handle_server_message(app: &AppHandle, text: &str) {
                 ServerEvent::PreferencesUpdated { .. } => "ws:preferences_updated",
                 // State sync
                 ServerEvent::Patch { .. } => "ws:patch",
+                // E2EE key supply
+                ServerEvent::PrekeysLow { .. } => "ws:prekeys_low",
+                ServerEvent::ToDeviceMessage { .. } => "ws:to_device_message",
             };
 
             if let Err(e) = app.emit(event_name, &event) {
@@ -4,13 +4,16 @@
 
 import { Component, createSignal, Show } from "solid-js";
 import { Portal } from "solid-js/web";
-import { X, Hash, Settings, Shield, Check, Bell, BellOff } from "lucide-solid";
-import { channelsState } from "@/stores/channels";
+import { X, Hash, Settings, Shield, Check, Bell, BellOff, Lock, Timer } from "lucide-solid";
+import { channelsState, handleChannelEncryptionEnabled } from "@/stores/channels";
+import { enableChannelEncryption } from "@/lib/tauri";
+import { showToast } from "@/components/ui/Toast";
 import { memberHasPermission } from "@/stores/permissions";
 import { authState } from "@/stores/auth";
 import { isGuildOwner } from "@/stores/guilds";
 import { PermissionBits } from "@/lib/permissionConstants";
 import ChannelPermissions from "./ChannelPermissions";
+import DisappearingMessagesSelect from "./DisappearingMessagesSelect";
 import {
   getChannelNotificationLevel,
   setChannelNotificationLevel,
@@ -42,6 +45,25 @@ const ChannelSettingsModal: Component<ChannelSettingsModalProps> = (props) => {
       PermissionBits.MANAGE_CHANNELS,
     );
 
+  const [isEnablingEncryption, setIsEnablingEncryption] = createSignal(false);
+
+  const handleEnableEncryption = async () => {
+    setIsEnablingEncryption(true);
+    try {
+      const state = await enableChannelEncryption(props.channelId);
+      handleChannelEncryptionEnabled(props.channelId, state.epoch);
+    } catch (err) {
+      showToast({
+        type: "error",
+        title: "Could not enable encryption",
+        message: err instanceof Error ? err.message : String(err),
+        duration: 8000,
+      });
+    } finally {
+      setIsEnablingEncryption(false);
+    }
+  };
+
   const handleBackdropClick = (e: MouseEvent) => {
     if (e.target === e.currentTarget) {
       props.onClose();
@@ -127,6 +149,60 @@ const ChannelSettingsModal: Component<ChannelSettingsModalProps> = (props) => {
                   </div>
                 </div>
 
+                {/* End-to-end encryption */}
+                <Show when={channel()?.channel_type === "text" && (channel()?.encrypted || canManageChannel())}>
+                  <div>
+                    <div class="flex items-center gap-2 mb-3">
+                      <Lock class="w-4 h-4 text-text-secondary" />
+                      <h3 class="text-base font-medium text-text-primary">
+                        End-to-end encryption
+                      </h3>
+                    </div>
+                    <Show
+                      when={!channel()?.encrypted}
+                      fallback={
+                        <p class="text-sm text-text-secondary">
+                          Messages in this channel are end-to-end encrypted.
+                        </p>
+                      }
+                    >
+                      <p class="text-sm text-text-secondary mb-3">
+                        Only for private channels hidden from @everyone. Once on, encryption
+                        can't be turned off, bots can't post and the server can't search new
+                        messages.
+                      </p>
+                      <button
+                        onClick={handleEnableEncryption}
+                        disabled={isEnablingEncryption()}
+                        class="px-4 py-2 rounded-lg bg-accent-primary text-white text-sm font-medium hover:bg-accent-primary/90 disabled:opacity-50 transition-colors"
+                      >
+                        {isEnablingEncryption() ? "Enabling..." : "Enable encryption"}
+                      </button>
+                    </Show>
+                  </div>
+                </Show>
+
+                {/* Disappearing messages */}
+                <Show when={channel()?.channel_type === "text" && canManageChannel()}>
+                  <div>
+                    <div class="flex items-center gap-2 mb-3">
+                      <Timer class="w-4 h-4 text-text-secondary" />
+                      <h3 class="text-base font-medium text-text-primary">
+                        Disappearing messages
+                      </h3>
+                    </div>
+                    <p class="text-sm text-text-secondary mb-3">
+                      New messages and their attachments are deleted for everyone once
+                      the timer runs out. Earlier messages aren't affected.
+                    </p>
+                    <DisappearingMessagesSelect
+                      channelId={props.channelId}
+                      value={channel()?.message_ttl_seconds}
+                      class="w-full"
+                    />
+                  </div>
+                </Show>
+
                 {/* Notification Settings */}
                 <div>
                   <div class="flex items-center gap-2 mb-3">
@@ -0,0 +1,68 @@
+/**
+ * DisappearingMessagesSelect - Picks the disappearing-message timer of a
+ * channel or DM.
+ */
+
+import { Component, For, createSignal } from "solid-js";
+import { setDisappearingMessages } from "@/lib/tauri";
+import { handleDisappearingMessagesUpdated } from "@/stores/channels";
+import { handleDMDisappearingMessagesUpdated } from "@/stores/dms";
+import { showToast } from "@/components/ui/Toast";
+
+/** Timer choices; the server accepts 5 minutes to 4 weeks. */
+export const DISAPPEARING_TIMERS: { seconds: number | null; label: string }[] = [
+  { seconds: null, label: "Off" },
+  { seconds: 5 * 60, label: "5 minutes" },
+  { seconds: 60 * 60, label: "1 hour" },
+  { seconds: 24 * 60 * 60, label: "1 day" },
+  { seconds: 7 * 24 * 60 * 60, label: "1 week" },
+  { seconds: 28 * 24 * 60 * 60, label: "4 weeks" },
+];
+
+interface DisappearingMessagesSelectProps {
+  channelId: string;
+  value: number | null | undefined;
+  class?: string;
+}
+
+const DisappearingMessagesSelect: Component<DisappearingMessagesSelectProps> = (props) => {
+  const [isSaving, setIsSaving] = createSignal(false);
+
+  const handleChange = async (value: string) => {
+    const seconds = value === "" ? null : Number(value);
+    setIsSaving(true);
+    try {
+      const state = await setDisappearingMessages(props.channelId, seconds);
+      handleDisappearingMessagesUpdated(state.channel_id, state.message_ttl_seconds);
+      handleDMDisappearingMessagesUpdated(state.channel_id, state.message_ttl_seconds);
+    } catch (err) {
+      showToast({
+        type: "error",
+        title: "Could not change disappearing messages",
+        message: err instanceof Error ? err.message : String(err),
+        duration: 8000,
+      });
+    } finally {
+      setIsSaving(false);
+    }
+  };
+
+  return (
+    <select
+      value={props.value?.toString() ?? ""}
+      onChange={(e) => handleChange(e.currentTarget.value)}
+      disabled={isSaving()}
+      aria-label="Disappearing messages"
+      class={`px-3 py-2 rounded-lg border border-white/10 text-text-primary disabled:opacity-50 ${props.class ?? ""}`}
+      style="background-color: var(--color-surface-layer2)"
+    >
+      <For each={DISAPPEARING_TIMERS}>
+        {(timer) => (
+          <option value={timer.seconds?.toString() ?? ""}>{timer.label}</option>
+        )}
+      </For>
+    </select>
+  );
+};
+
+export default DisappearingMessagesSelect;
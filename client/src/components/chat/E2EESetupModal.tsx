@@ -151,6 +151,7 @@ const E2EESetupModal: Component<E2EESetupModalProps> = (props) => {
         initResponse.identity_key_ed25519,
         initResponse.identity_key_curve25519,
         initResponse.prekeys,
+        initResponse.fallback_key,
       );
 
       // 3. Mark prekeys as published
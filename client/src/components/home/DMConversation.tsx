@@ -11,7 +11,7 @@ import {
   createEffect,
   createSignal,
 } from "solid-js";
-import { Phone, Lock, Unlock, Check, X } from "lucide-solid";
+import { Phone, Lock, Unlock, Check, X, Timer } from "lucide-solid";
 import { e2eeStatus } from "@/stores/e2ee";
 import { getSelectedDM, markDMAsRead, updateDMIconUrl } from "@/stores/dms";
 import { currentUser } from "@/stores/auth";
@@ -20,6 +20,7 @@ import MessageInput from "@/components/messages/MessageInput";
 import TypingIndicator from "@/components/messages/TypingIndicator";
 import { showToast } from "@/components/ui/Toast";
 import { CallBanner } from "@/components/call";
+import DisappearingMessagesSelect from "@/components/channels/DisappearingMessagesSelect";
 import { callState, startCall, endCall, isInCallForChannel } from "@/stores/call";
 import {
   startDMCall,
@@ -345,6 +346,22 @@ const DMConversation: Component = () => {
           {/* Spacer */}
           <div class="flex-1" />
 
+          {/* Disappearing messages, open to every participant */}
+          <div class="flex items-center gap-1.5" title="Disappearing messages">
+            <Timer
+              class="w-4 h-4"
+              classList={{
+                "text-accent-primary": !!dm()?.message_ttl_seconds,
+                "text-text-secondary": !dm()?.message_ttl_seconds,
+              }}
+            />
+            <DisappearingMessagesSelect
+              channelId={dm()!.id}
+              value={dm()?.message_ttl_seconds}
+              class="py-1 text-sm"
+            />
+          </div>
+
           {/* Call Button */}
           <button
             onClick={handleStartCall}
@@ -1,13 +1,14 @@
 import { Component, createSignal, Show, For, onCleanup, createEffect, createMemo } from "solid-js";
 import { PlusCircle, Send, Smile, UploadCloud, X, File as FileIcon, Bold, Italic, Code, EyeOff } from "lucide-solid";
-import { sendMessage, messagesState, addMessage } from "@/stores/messages";
+import { sendMessage, sendEncryptedFile, messagesState, addMessage } from "@/stores/messages";
 import { stopTyping, sendTyping } from "@/stores/websocket";
 import { uploadMessageWithFile, validateFileSize, getUploadLimitText } from "@/lib/tauri";
 import { showToast } from "@/components/ui/Toast";
 import { getDraft, saveDraft, clearDraft } from "@/stores/drafts";
 import AutocompletePopup from "./AutocompletePopup";
 import { guildsState } from "@/stores/guilds";
-import { channelsState } from "@/stores/channels";
+import { channelsState, getChannel } from "@/stores/channels";
+import { getDM } from "@/stores/dms";
 import { listGuildCommands, type GuildCommand } from "@/lib/api/bots";
 import PositionedEmojiPicker from "@/components/emoji/PositionedEmojiPicker";
 
@@ -348,6 +349,12 @@ const MessageInput: Component<MessageInputProps> = (props) => {
         // Upload files one at a time (first file gets the text, rest are separate)
         for (let i = 0; i < files.length; i++) {
           const messageText = i === 0 ? text || undefined : undefined;
+          // Encrypted channels never receive plaintext files
+          if (getChannel(props.channelId)?.encrypted || getDM(props.channelId)?.encrypted) {
+            const message = await sendEncryptedFile(props.channelId, files[i].file, messageText);
+            if (!message) return;
+            continue;
+          }
           const message = await uploadMessageWithFile(props.channelId, files[i].file, messageText);
           await addMessage(message);
         }
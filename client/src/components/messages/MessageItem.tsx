@@ -23,7 +23,8 @@ import {
   Pencil,
   Pin,
 } from "lucide-solid";
-import type { Message } from "@/lib/types";
+import type { Attachment, Message } from "@/lib/types";
+import { decryptedAttachmentUrl } from "@/lib/encryptedFiles";
 import { formatTimestamp } from "@/lib/utils";
 import Avatar from "@/components/ui/Avatar";
 import CodeBlock from "@/components/ui/CodeBlock";
@@ -201,6 +202,35 @@ async function fetchSignedUrl(
   return `/api/messages/attachments/${attachmentId}/download${qs ? `?${qs}` : ""}`;
 }
 
+/**
+ * URL to display an attachment. Encrypted attachments are downloaded and
+ * decrypted locally into a blob URL.
+ */
+async function attachmentUrl(
+  attachment: Attachment,
+  variant?: "thumbnail",
+): Promise<string> {
+  if (attachment.encrypted) {
+    return decryptedAttachmentUrl(attachment, variant);
+  }
+  return fetchSignedUrl(attachment.id, variant);
+}
+
+/**
+ * Open an attachment in a new tab, or save it if it had to be decrypted.
+ */
+async function openAttachment(attachment: Attachment): Promise<void> {
+  const url = await attachmentUrl(attachment);
+  if (!attachment.encrypted) {
+    window.open(url, "_blank");
+    return;
+  }
+  const link = document.createElement("a");
+  link.href = url;
+  link.download = attachment.filename;
+  link.click();
+}
+
 
 // ---- Module-level lightbox state ----
 const [lightboxSrc, setLightboxSrc] = createSignal<string | null>(null);
@@ -706,6 +736,8 @@ const MessageItem: Component<MessageItemProps> = (props) => {
           <div
             ref={contentRef}
             class="text-text-primary break-words leading-relaxed prose prose-invert max-w-none"
+            classList={{ "italic opacity-70": !!props.message.undecryptable }}
+            title={props.message.undecryptable ? "Will decrypt automatically once the sender's keys arrive" : undefined}
           >
             <For each={contentBlocks()}>
               {(block) => (
@@ -743,8 +775,7 @@ const MessageItem: Component<MessageItemProps> = (props) => {
                       class="flex items-center gap-3 px-4 py-3 bg-surface-layer2 rounded-xl hover:bg-surface-highlight transition-all duration-200 border border-white/5 max-w-sm cursor-pointer text-left"
                       onClick={async () => {
                         try {
-                          const url = await fetchSignedUrl(attachment.id);
-                          window.open(url, "_blank");
+                          await openAttachment(attachment);
                         } catch (err) {
                           console.error("Failed to get signed URL:", err);
                           showToast({
@@ -772,14 +803,16 @@ const MessageItem: Component<MessageItemProps> = (props) => {
                   }
                 >
                   {(() => {
-                    const variant = attachment.thumbnail_url
-                      ? "thumbnail"
-                      : undefined;
+                    const variant =
+                      attachment.thumbnail_url &&
+                      (!attachment.encrypted || attachment.thumbnail_file)
+                        ? "thumbnail"
+                        : undefined;
                     const [imgSrc] = createResource(
                       () => attachment.id,
                       async (id) => {
                         try {
-                          return await fetchSignedUrl(id, variant);
+                          return await attachmentUrl(attachment, variant);
                         } catch (err) {
                           console.error("Failed to load image attachment:", id, err);
                           throw err;
@@ -832,7 +865,7 @@ const MessageItem: Component<MessageItemProps> = (props) => {
                               );
                             }}
                             onClick={async () => {
-                              const url = await fetchSignedUrl(attachment.id);
+                              const url = await attachmentUrl(attachment);
                               setLightboxSrc(url);
                             }}
                             style={{ cursor: "pointer" }}
@@ -844,8 +877,7 @@ const MessageItem: Component<MessageItemProps> = (props) => {
                           title="Download original"
                           onClick={async () => {
                             try {
-                              const url = await fetchSignedUrl(attachment.id);
-                              window.open(url, "_blank");
+                              await openAttachment(attachment);
                             } catch (err) {
                               console.error("Failed to get signed URL:", err);
                               showToast({
@@ -38,7 +38,10 @@ import {
   hasMore,
 } from "@/stores/search";
 import { getActiveGuild } from "@/stores/guilds";
+import { getChannel } from "@/stores/channels";
+import { getDM } from "@/stores/dms";
 import type {
+  Message,
   SearchFilters,
   SearchResult,
   GlobalSearchResult,
@@ -176,6 +179,18 @@ const SearchPanel: Component<SearchPanelProps> = (props) => {
     props.onClose();
   };
 
+  const handleLocalResultClick = (message: Message) => {
+    const guildId = getDM(message.channel_id)
+      ? null
+      : getChannel(message.channel_id)?.guild_id;
+    if (guildId) {
+      navigate(`/guilds/${guildId}/channels/${message.channel_id}?highlight=${message.id}`);
+    } else {
+      navigate(`/home/dm/${message.channel_id}?highlight=${message.id}`);
+    }
+    props.onClose();
+  };
+
   // Sanitize headline HTML (only allow <mark> tags from ts_headline)
   const sanitizeHeadline = (html: string): string => {
     return DOMPurify.sanitize(html, {
@@ -407,6 +422,45 @@ const SearchPanel: Component<SearchPanelProps> = (props) => {
         </span>
       </Show>
 
+      <Show when={searchState.query.length >= 2 && searchState.encryptedChannelIds.length > 0}>
+        <Show
+          when={searchState.localResults.length > 0}
+          fallback={
+            <p class="mx-3 mt-1 text-xs text-text-secondary">
+              Encrypted messages in {searchState.encryptedChannelIds.length} channel
+              {searchState.encryptedChannelIds.length !== 1 ? "s" : ""} are only searched
+              on this device.
+            </p>
+          }
+        >
+          {/* Encrypted matches from the local message cache */}
+          <div data-testid="local-search-results" class="mt-1 max-h-48 overflow-y-auto border-b border-white/10">
+            <p class="mx-3 mb-1 text-xs text-text-secondary">
+              {searchState.localResults.length} encrypted match
+              {searchState.localResults.length !== 1 ? "es" : ""} on this device
+            </p>
+            <For each={searchState.localResults}>
+              {(message) => (
+                <button
+                  onClick={() => handleLocalResultClick(message)}
+                  class="w-full px-3 py-2 text-left hover:bg-white/5 transition-colors"
+                >
+                  <div class="flex items-center gap-2 mb-1">
+                    <span class="text-sm font-medium text-text-primary">
+                      {message.author.display_name}
+                    </span>
+                    <span class="text-xs text-text-secondary">
+                      {formatTimestamp(message.created_at)}
+                    </span>
+                  </div>
+                  <p class="text-sm text-text-secondary line-clamp-2">{message.content}</p>
+                </button>
+              )}
+            </For>
+          </div>
+        </Show>
+      </Show>
+
       {/* Results */}
       <div ref={resultsContainerRef} data-testid="search-results" class="flex-1 overflow-y-auto">
         {/* Loading State */}
@@ -423,7 +477,8 @@ const SearchPanel: Component<SearchPanelProps> = (props) => {
           when={
             !searchState.isSearching &&
             searchState.query.length >= 2 &&
-            searchState.results.length === 0
+            searchState.results.length === 0 &&
+            searchState.localResults.length === 0
           }
         >
           <div class="flex flex-col items-center justify-center py-8 text-text-secondary">
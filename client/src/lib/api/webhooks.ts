@@ -10,7 +10,19 @@ export type WebhookEventType =
   | "message.created"
   | "member.joined"
   | "member.left"
-  | "command.invoked";
+  | "command.invoked"
+  | "message.updated"
+  | "message.deleted"
+  | "reaction.added"
+  | "reaction.removed"
+  | "voice.state_updated"
+  | "channel.created"
+  | "channel.updated"
+  | "channel.deleted"
+  | "role.created"
+  | "role.updated"
+  | "role.deleted"
+  | "presence.updated";
 
 export interface Webhook {
   id: string;
@@ -0,0 +1,100 @@
+/**
+ * Encrypted File Helpers
+ *
+ * The server can't look inside files shared in end-to-end encrypted
+ * conversations, so the client produces what the server would otherwise
+ * generate (dimensions, blurhash, thumbnail) before encrypting, and decrypts
+ * downloads into local blob URLs for display.
+ */
+
+import { encode } from "blurhash";
+import type { Attachment } from "@/lib/types";
+import { downloadEncryptedAttachment } from "@/lib/tauri";
+
+/** Longest side of client-generated thumbnails (matches the server's 256px variant). */
+const THUMBNAIL_SIZE = 256;
+
+/** Longest side of the image the blurhash is computed from. */
+const BLURHASH_SIZE = 32;
+
+export interface ImagePreview {
+  width: number;
+  height: number;
+  blurhash: string;
+  /** WebP thumbnail, only for images larger than the thumbnail size. */
+  thumbnail?: Blob;
+}
+
+function drawScaled(bitmap: ImageBitmap, maxSide: number): HTMLCanvasElement {
+  const scale = Math.min(1, maxSide / Math.max(bitmap.width, bitmap.height));
+  const canvas = document.createElement("canvas");
+  canvas.width = Math.max(1, Math.round(bitmap.width * scale));
+  canvas.height = Math.max(1, Math.round(bitmap.height * scale));
+  canvas.getContext("2d")!.drawImage(bitmap, 0, 0, canvas.width, canvas.height);
+  return canvas;
+}
+
+/**
+ * Compute dimensions, blurhash and thumbnail of an image before it is
+ * encrypted. Returns null for files that aren't images the browser can decode.
+ */
+export async function createImagePreview(file: File): Promise<ImagePreview | null> {
+  if (!file.type.startsWith("image/")) {
+    return null;
+  }
+
+  let bitmap: ImageBitmap;
+  try {
+    bitmap = await createImageBitmap(file);
+  } catch {
+    return null;
+  }
+
+  try {
+    const small = drawScaled(bitmap, BLURHASH_SIZE);
+    const pixels = small.getContext("2d")!.getImageData(0, 0, small.width, small.height);
+    const blurhash = encode(pixels.data, small.width, small.height, 4, 3);
+
+    let thumbnail: Blob | undefined;
+    if (Math.max(bitmap.width, bitmap.height) > THUMBNAIL_SIZE) {
+      const canvas = drawScaled(bitmap, THUMBNAIL_SIZE);
+      thumbnail = await new Promise<Blob | undefined>((resolve) =>
+        canvas.toBlob((blob) => resolve(blob ?? undefined), "image/webp", 0.8),
+      );
+    }
+
+    return { width: bitmap.width, height: bitmap.height, blurhash, thumbnail };
+  } finally {
+    bitmap.close();
+  }
+}
+
+// Decrypted attachments as blob URLs, keyed by `${attachmentId}:${variant}`
+const decryptedUrls = new Map<string, Promise<string>>();
+
+/**
+ * Download and decrypt an encrypted attachment (or its thumbnail) into a
+ * blob URL. Results are cached for the session.
+ */
+export function decryptedAttachmentUrl(
+  attachment: Attachment,
+  variant?: "thumbnail",
+): Promise<string> {
+  const key = variant ? attachment.thumbnail_file : attachment.file;
+  if (!key) {
+    return Promise.reject(new Error("Missing key for encrypted attachment"));
+  }
+
+  const cacheKey = `${attachment.id}:${variant ?? "original"}`;
+  let url = decryptedUrls.get(cacheKey);
+  if (!url) {
+    url = downloadEncryptedAttachment(attachment.id, key, variant).then((data) =>
+      URL.createObjectURL(
+        new Blob([data], { type: variant ? "image/webp" : attachment.mime_type }),
+      ),
+    );
+    url.catch(() => decryptedUrls.delete(cacheKey));
+    decryptedUrls.set(cacheKey, url);
+  }
+  return url;
+}
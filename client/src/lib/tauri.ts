@@ -25,6 +25,10 @@ import type {
   GuildRole,
   GuildEmoji,
   ChannelOverride,
+  ChannelEncryption,
+  DisappearingMessages,
+  EncryptedAttachment,
+  EncryptedFile,
   CreateRoleRequest,
   UpdateRoleRequest,
   SetChannelOverrideRequest,
@@ -47,8 +51,17 @@ import type {
   E2EEStatus,
   InitE2EEResponse,
   PrekeyData,
+  SignedFallbackKey,
   E2EEContent,
   ClaimedPrekeyInput,
+  CrossSigningKeys,
+  DeviceKeys,
+  MegolmShare,
+  GroupCiphertext,
+  RoomKeyRequest,
+  ForwardedRoomKey,
+  ToDeviceMessage,
+  MessageCacheRetention,
   UserKeysResponse,
   ClaimedPrekeyResponse,
   SearchResponse,
@@ -108,6 +121,10 @@ export type {
   GuildRole,
   GuildEmoji,
   ChannelOverride,
+  ChannelEncryption,
+  DisappearingMessages,
+  EncryptedAttachment,
+  EncryptedFile,
   CreateRoleRequest,
   UpdateRoleRequest,
   SetChannelOverrideRequest,
@@ -130,8 +147,17 @@ export type {
   E2EEStatus,
   InitE2EEResponse,
   PrekeyData,
+  SignedFallbackKey,
   E2EEContent,
   ClaimedPrekeyInput,
+  CrossSigningKeys,
+  DeviceKeys,
+  MegolmShare,
+  GroupCiphertext,
+  RoomKeyRequest,
+  ForwardedRoomKey,
+  ToDeviceMessage,
+  MessageCacheRetention,
   UserKeysResponse,
   ClaimedPrekeyResponse,
   SearchResponse,
@@ -166,6 +192,7 @@ export interface ChannelUnread {
   channel_id: string;
   channel_name: string;
   unread_count: number;
+  mention_count: number;
 }
 
 export interface GuildUnreadSummary {
@@ -1201,7 +1228,7 @@ export async function getMessages(
 export async function sendMessage(
   channelId: string,
   content: string,
-  options?: { encrypted?: boolean; nonce?: string },
+  options?: { encrypted?: boolean; nonce?: string; encryptionEpoch?: number },
 ): Promise<Message> {
   const result = await sendMessageWithStatus(channelId, content, options);
   return result.message;
@@ -1215,7 +1242,7 @@ export interface SendMessageResult {
 export async function sendMessageWithStatus(
   channelId: string,
   content: string,
-  options?: { encrypted?: boolean; nonce?: string },
+  options?: { encrypted?: boolean; nonce?: string; encryptionEpoch?: number },
 ): Promise<SendMessageResult> {
   if (isTauri) {
     const { invoke } = await import("@tauri-apps/api/core");
@@ -1224,6 +1251,7 @@ export async function sendMessageWithStatus(
       content,
       encrypted: options?.encrypted,
       nonce: options?.nonce,
+      encryptionEpoch: options?.encryptionEpoch,
     });
 
     // Tauri command interface currently does not expose HTTP status.
@@ -1247,6 +1275,7 @@ export async function sendMessageWithStatus(
       content,
       encrypted: options?.encrypted ?? false,
       nonce: options?.nonce,
+      encryption_epoch: options?.encryptionEpoch,
     }),
   });
 
@@ -1256,7 +1285,11 @@ export async function sendMessageWithStatus(
 
     try {
       const errorBody = rawErrorBody ? JSON.parse(rawErrorBody) : null;
-      errorMessage = errorBody.message || errorBody.error || errorMessage;
+      // Keep the code so callers can rotate their Megolm session and retry
+      errorMessage =
+        errorBody.error === "ENCRYPTION_EPOCH_STALE"
+          ? errorBody.error
+          : errorBody.message || errorBody.error || errorMessage;
     } catch (_parseError) {
       if (rawErrorBody.length > 0 && rawErrorBody.length < 500) {
         errorMessage = rawErrorBody;
@@ -1390,8 +1423,16 @@ export async function uploadFile(messageId: string, file: File): Promise<any> {
  */
 export async function uploadMessageWithFile(
   channelId: string,
-  file: File,
+  file: File | Blob,
   content?: string,
+  options?: {
+    /** `file` and `content` are client-encrypted. */
+    encrypted?: boolean;
+    nonce?: string;
+    encryptionEpoch?: number;
+    /** Client-encrypted thumbnail (encrypted uploads only). */
+    thumbnail?: Blob;
+  },
 ): Promise<Message> {
   // Frontend validation
   const error = validateFileSize(file, "attachment");
@@ -1408,7 +1449,20 @@ export async function uploadMessageWithFile(
   }
 
   const formData = new FormData();
-  formData.append("file", file);
+  if (options?.encrypted) {
+    // The real name and type are inside the encrypted content
+    formData.append("file", file, "encrypted.bin");
+    formData.append("encrypted", "true");
+    if (options.nonce) formData.append("nonce", options.nonce);
+    if (options.encryptionEpoch !== undefined) {
+      formData.append("encryption_epoch", String(options.encryptionEpoch));
+    }
+    if (options.thumbnail) {
+      formData.append("thumbnail", options.thumbnail, "thumbnail.bin");
+    }
+  } else {
+    formData.append("file", file);
+  }
   if (content) {
     formData.append("content", content);
   }
@@ -1427,7 +1481,11 @@ export async function uploadMessageWithFile(
 
     try {
       const errorBody = await response.json();
-      errorMessage = errorBody.message || errorBody.error || errorMessage;
+      // Keep the code so callers can rotate their Megolm session and retry
+      errorMessage =
+        errorBody.error === "ENCRYPTION_EPOCH_STALE"
+          ? errorBody.error
+          : errorBody.message || errorBody.error || errorMessage;
     } catch (parseError) {
       console.warn(
         "[uploadMessageWithFile] Failed to parse error response:",
@@ -1441,7 +1499,7 @@ export async function uploadMessageWithFile(
       error: errorMessage,
       channelId,
       fileSize: file.size,
-      fileName: file.name,
+      fileName: file instanceof File ? file.name : undefined,
     });
 
     throw new Error(errorMessage);
@@ -3419,6 +3477,59 @@ export async function getChannelOverrides(
   );
 }
 
+/**
+ * Get a channel's end-to-end encryption state and the users who hold its keys.
+ */
+export async function getChannelEncryption(
+  channelId: string,
+): Promise<ChannelEncryption> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke("get_channel_encryption", { channelId });
+  }
+
+  return httpRequest<ChannelEncryption>(
+    "GET",
+    `/api/channels/${channelId}/encryption`,
+  );
+}
+
+/**
+ * Turn on end-to-end encryption for a DM or private channel. Can't be undone.
+ */
+export async function enableChannelEncryption(
+  channelId: string,
+): Promise<ChannelEncryption> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke("enable_channel_encryption", { channelId });
+  }
+
+  return httpRequest<ChannelEncryption>(
+    "PUT",
+    `/api/channels/${channelId}/encryption`,
+  );
+}
+
+/**
+ * Set or clear the disappearing-message timer of a channel or DM.
+ */
+export async function setDisappearingMessages(
+  channelId: string,
+  messageTtlSeconds: number | null,
+): Promise<DisappearingMessages> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke("set_disappearing_messages", { channelId, messageTtlSeconds });
+  }
+
+  return httpRequest<DisappearingMessages>(
+    "PUT",
+    `/api/channels/${channelId}/disappearing-messages`,
+    { message_ttl_seconds: messageTtlSeconds },
+  );
+}
+
 /**
  * Set a permission override for a role in a channel.
  */
@@ -4009,13 +4120,32 @@ export async function createMegolmSession(roomId: string): Promise<string> {
   throw new Error("E2EE requires the native Tauri app");
 }
 
+/**
+ * Record the devices the current Megolm session of a room was shared with.
+ * Those devices may later ask for the session again.
+ */
+export async function recordMegolmSessionShares(
+  roomId: string,
+  devices: MegolmShare[],
+): Promise<void> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke("record_megolm_session_shares", { roomId, devices });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
 /**
  * Encrypt a message for a group using Megolm.
  */
-export async function encryptGroupMessage(roomId: string, plaintext: string): Promise<string> {
+export async function encryptGroupMessage(
+  roomId: string,
+  plaintext: string,
+): Promise<GroupCiphertext> {
   if (isTauri) {
     const { invoke } = await import("@tauri-apps/api/core");
-    return invoke<string>("encrypt_group_message", { roomId, plaintext });
+    return invoke<GroupCiphertext>("encrypt_group_message", { roomId, plaintext });
   }
 
   throw new Error("E2EE requires the native Tauri app");
@@ -4043,16 +4173,287 @@ export async function addInboundGroupSession(
 export async function decryptGroupMessage(
   roomId: string,
   senderKey: string,
+  sessionId: string | undefined,
   ciphertext: string
 ): Promise<string> {
   if (isTauri) {
     const { invoke } = await import("@tauri-apps/api/core");
-    return invoke<string>("decrypt_group_message", { roomId, senderKey, ciphertext });
+    return invoke<string>("decrypt_group_message", {
+      roomId,
+      senderKey,
+      sessionId,
+      ciphertext,
+    });
   }
 
   throw new Error("E2EE requires the native Tauri app");
 }
 
+/**
+ * Build a signed request for a missing Megolm session, or null if this device
+ * has the session or asked for it recently.
+ */
+export async function requestRoomKey(
+  deviceId: string,
+  roomId: string,
+  senderKey: string,
+  sessionId: string,
+): Promise<RoomKeyRequest | null> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<RoomKeyRequest | null>("request_room_key", {
+      deviceId,
+      roomId,
+      senderKey,
+      sessionId,
+    });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+/**
+ * Answer a room key request from another device. Returns the session to
+ * forward, or null if the request is refused.
+ */
+export async function handleRoomKeyRequest(
+  senderUserId: string,
+  senderDevice: DeviceKeys,
+  senderCrossSigning: CrossSigningKeys | null,
+  request: RoomKeyRequest,
+): Promise<ForwardedRoomKey | null> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<ForwardedRoomKey | null>("handle_room_key_request", {
+      senderUserId,
+      senderDevice,
+      senderCrossSigning,
+      request,
+    });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+/**
+ * Import a forwarded room key. Returns whether the session was imported.
+ */
+export async function addForwardedRoomKey(
+  senderUserId: string,
+  senderDevice: DeviceKeys,
+  forwarded: ForwardedRoomKey,
+): Promise<boolean> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<boolean>("add_forwarded_room_key", {
+      senderUserId,
+      senderDevice,
+      forwarded,
+    });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+/**
+ * Encrypt a file for an end-to-end encrypted conversation under a fresh key.
+ */
+export async function encryptAttachment(data: Uint8Array): Promise<EncryptedAttachment> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    let binary = "";
+    for (let i = 0; i < data.length; i += 0x8000) {
+      binary += String.fromCharCode(...data.subarray(i, i + 0x8000));
+    }
+    return invoke<EncryptedAttachment>("encrypt_attachment", { data: btoa(binary) });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+/**
+ * Download an encrypted attachment (or its thumbnail variant) and decrypt it
+ * with the key from the message body.
+ */
+export async function downloadEncryptedAttachment(
+  attachmentId: string,
+  file: EncryptedFile,
+  variant?: "thumbnail",
+): Promise<ArrayBuffer> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<ArrayBuffer>("download_encrypted_attachment", {
+      attachmentId,
+      variant,
+      file,
+    });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+/**
+ * Start a new server-side Megolm key backup for the recovery key, replacing
+ * any existing one. Returns the new backup version.
+ */
+export async function enableKeyBackup(recoveryKey: string): Promise<number> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<number>("enable_key_backup", { recoveryKey });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+/**
+ * Upload Megolm sessions that aren't in the key backup yet.
+ * Returns the number of uploaded sessions.
+ */
+export async function syncKeyBackup(): Promise<number> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<number>("sync_key_backup");
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+/**
+ * Import Megolm sessions from the key backup using the recovery key.
+ * Returns the number of imported sessions.
+ */
+export async function restoreKeyBackup(recoveryKey: string): Promise<number> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<number>("restore_key_backup", { recoveryKey });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
+// =============================================================================
+// Message Cache Commands
+// =============================================================================
+
+/**
+ * Cache decrypted messages on this device. Undecryptable messages are skipped.
+ */
+export async function cacheMessages(messages: Message[]): Promise<void> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<void>("cache_messages", { messages });
+  }
+}
+
+/**
+ * Reconcile the message cache with a page fetched from the server. Returns
+ * the page with undecryptable messages replaced by cached copies.
+ */
+export async function syncCachedMessages(
+  channelId: string,
+  before: string | undefined,
+  messages: Message[],
+  hasMore: boolean,
+): Promise<Message[]> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<Message[]>("sync_cached_messages", {
+      channelId,
+      before,
+      messages,
+      hasMore,
+    });
+  }
+
+  return messages;
+}
+
+/**
+ * Get cached messages of a channel, newest first, for offline use.
+ */
+export async function getCachedMessages(
+  channelId: string,
+  before?: string,
+  limit?: number,
+): Promise<PaginatedMessages> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    const page = await invoke<{ items: Message[]; has_more: boolean }>(
+      "get_cached_messages",
+      { channelId, before, limit },
+    );
+    return { ...page, next_cursor: null };
+  }
+
+  return { items: [], has_more: false, next_cursor: null };
+}
+
+/**
+ * Drop a deleted message from the cache.
+ */
+export async function removeCachedMessage(messageId: string): Promise<void> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    await invoke<boolean>("remove_cached_message", { messageId });
+  }
+}
+
+/**
+ * Search decrypted messages cached on this device for all words of `query`.
+ */
+export async function searchCachedMessages(
+  query: string,
+  channelIds?: string[],
+  limit?: number,
+): Promise<Message[]> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<Message[]>("search_cached_messages", { query, channelIds, limit });
+  }
+
+  return [];
+}
+
+/**
+ * Get how much history the message cache keeps.
+ */
+export async function getMessageCacheRetention(): Promise<MessageCacheRetention> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<MessageCacheRetention>("get_message_cache_retention");
+  }
+
+  throw new Error("The message cache requires the native Tauri app");
+}
+
+/**
+ * Change how much history the message cache keeps.
+ * Returns the number of messages dropped.
+ */
+export async function setMessageCacheRetention(
+  policy: MessageCacheRetention,
+): Promise<number> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<number>("set_message_cache_retention", { policy });
+  }
+
+  throw new Error("The message cache requires the native Tauri app");
+}
+
+/**
+ * Clear the message cache of one channel, or all of it.
+ * Returns the number of removed messages.
+ */
+export async function clearMessageCache(channelId?: string): Promise<number> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<number>("clear_message_cache", { channelId });
+  }
+
+  return 0;
+}
+
 /**
  * Mark prekeys as published after uploading them to the server.
  * Note: E2EE commands require Tauri - they are not available in browser mode.
@@ -4079,6 +4480,26 @@ export async function generatePrekeys(count: number): Promise<PrekeyData[]> {
   throw new Error("E2EE requires the native Tauri app");
 }
 
+/**
+ * Generate new prekeys and optionally replace a used fallback key.
+ * Returns the identity keys and all unpublished keys for upload.
+ * Note: E2EE commands require Tauri - they are not available in browser mode.
+ */
+export async function replenishKeys(
+  prekeyCount: number,
+  rotateFallbackKey: boolean,
+): Promise<InitE2EEResponse> {
+  if (isTauri) {
+    const { invoke } = await import("@tauri-apps/api/core");
+    return invoke<InitE2EEResponse>("replenish_keys", {
+      prekeyCount,
+      rotateFallbackKey,
+    });
+  }
+
+  throw new Error("E2EE requires the native Tauri app");
+}
+
 /**
  * Check if the device needs to upload more prekeys to the server.
  * Note: E2EE commands require Tauri - they are not available in browser mode.
@@ -4135,6 +4556,43 @@ export async function claimPrekey(
   );
 }
 
+/**
+ * Queue Olm-encrypted messages for specific devices.
+ * `messages` maps user ID to device ID to ciphertext.
+ */
+export async function sendToDevice(
+  senderDeviceId: string,
+  eventType: string,
+  messages: Record<string, Record<string, string>>,
+): Promise<void> {
+  await httpRequest<void>("PUT", "/api/keys/send-to-device", {
+    sender_device_id: senderDeviceId,
+    event_type: eventType,
+    messages,
+  });
+}
+
+/**
+ * Fetch to-device messages queued for one of our devices, oldest first.
+ */
+export async function getPendingToDeviceMessages(
+  deviceId: string,
+): Promise<ToDeviceMessage[]> {
+  return httpRequest<ToDeviceMessage[]>("GET", `/api/keys/to-device/${deviceId}`);
+}
+
+/**
+ * Delete handled to-device messages from the server queue.
+ */
+export async function ackToDeviceMessages(
+  deviceId: string,
+  messageIds: string[],
+): Promise<void> {
+  await httpRequest<void>("POST", `/api/keys/to-device/${deviceId}/ack`, {
+    message_ids: messageIds,
+  });
+}
+
 /**
  * Upload identity keys and prekeys to the server.
  * Creates or updates the device record.
@@ -4144,6 +4602,7 @@ export async function uploadKeys(
   identityKeyEd25519: string,
   identityKeyCurve25519: string,
   oneTimePrekeys: PrekeyData[],
+  fallbackKey: SignedFallbackKey | null = null,
 ): Promise<{
   device_id: string;
   prekeys_uploaded: number;
@@ -4161,6 +4620,7 @@ export async function uploadKeys(
       key_id: pk.key_id,
       public_key: pk.public_key,
     })),
+    fallback_key: fallbackKey,
   });
 }
 
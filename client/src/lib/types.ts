@@ -210,9 +210,30 @@ export interface Channel {
   icon_url: string | null;
   user_limit: number | null;
   position: number;
+  /** Messages must be end-to-end encrypted (can't be turned off). */
+  encrypted?: boolean;
+  /** Membership epoch; outbound Megolm sessions are rotated when it changes. */
+  encryption_epoch?: number;
+  /** Lifetime of new messages in seconds, or null if they don't disappear. */
+  message_ttl_seconds?: number | null;
   created_at: string;
 }
 
+/** Disappearing-message timer of a channel (PUT /api/channels/:id/disappearing-messages). */
+export interface DisappearingMessages {
+  channel_id: string;
+  message_ttl_seconds: number | null;
+}
+
+/** Encryption state of a channel (GET/PUT /api/channels/:id/encryption). */
+export interface ChannelEncryption {
+  channel_id: string;
+  encrypted: boolean;
+  epoch: number;
+  /** Users whose devices need our Megolm session key. */
+  members: string[];
+}
+
 /** Channel with unread message count (returned from guild channel list). */
 export interface ChannelWithUnread extends Channel {
   /** Number of unread messages (only for text channels). */
@@ -247,6 +268,12 @@ export interface Attachment {
   blurhash?: string;
   thumbnail_url?: string;
   medium_url?: string;
+  /** Client-encrypted ciphertext; the key is in the encrypted message body. */
+  encrypted?: boolean;
+  /** Key of an encrypted attachment (client-only, from the decrypted message). */
+  file?: EncryptedFile;
+  /** Key of the encrypted thumbnail (client-only, from the decrypted message). */
+  thumbnail_file?: EncryptedFile;
 }
 
 export interface Reaction {
@@ -279,11 +306,17 @@ export interface Message {
   thread_last_reply_at: string | null;
   edited_at: string | null;
   created_at: string;
+  /** When a disappearing message is deleted by the server. */
+  expires_at?: string | null;
   mention_type: "direct" | "everyone" | "here" | null;
   reactions?: Reaction[];
   thread_info?: ThreadInfo;
   pinned: boolean;
   message_type: string; // "user" | "system"
+  /** Client-only: the Megolm session for this message hasn't arrived yet. */
+  undecryptable?: boolean;
+  /** Client-only: original ciphertext of an undecryptable message, kept for retries. */
+  ciphertext?: string;
 }
 
 export interface ChannelPin {
@@ -333,6 +366,8 @@ export interface SearchResponse {
   total: number;
   limit: number;
   offset: number;
+  /** Searched channels that are end-to-end encrypted; their encrypted messages aren't searchable. */
+  encrypted_channel_ids: string[];
 }
 
 export interface SearchFilters {
@@ -361,6 +396,8 @@ export interface GlobalSearchResponse {
   total: number;
   limit: number;
   offset: number;
+  /** Searched channels that are end-to-end encrypted; their encrypted messages aren't searchable. */
+  encrypted_channel_ids: string[];
 }
 
 // Voice Types
@@ -466,7 +503,9 @@ export type ClientEvent =
   | { type: "admin_unsubscribe" };
 
 export type ServerEvent =
-  | { type: "ready"; user_id: string }
+  | { type: "ready"; user_id: string; low_prekey_devices: DevicePrekeyStatus[] }
+  | ({ type: "prekeys_low" } & DevicePrekeyStatus)
+  | ({ type: "to_device_message" } & ToDeviceMessage)
   | { type: "pong" }
   | { type: "subscribed"; channel_id: string }
   | { type: "unsubscribed"; channel_id: string }
@@ -649,6 +688,18 @@ export type ServerEvent =
       name: string;
       updated_by: string;
     }
+  | {
+      type: "channel_encryption_enabled";
+      channel_id: string;
+      epoch: number;
+      enabled_by: string;
+    }
+  | {
+      type: "disappearing_messages_updated";
+      channel_id: string;
+      message_ttl_seconds: number | null;
+      updated_by: string;
+    }
   // Block events
   | { type: "user_blocked"; user_id: string }
   | { type: "user_unblocked"; user_id: string }
@@ -917,6 +968,9 @@ export interface DMListItem {
   icon_url: string | null;
   user_limit: number | null;
   position: number;
+  encrypted?: boolean;
+  encryption_epoch?: number;
+  message_ttl_seconds?: number | null;
   created_at: string;
   participants: DMParticipant[];
   last_message: LastMessagePreview | null;
@@ -1073,6 +1127,7 @@ export interface GuildRole {
   permissions: number;
   position: number;
   is_default: boolean;
+  mentionable: boolean;
   created_at: string;
 }
 
@@ -1080,6 +1135,7 @@ export interface CreateRoleRequest {
   name: string;
   color?: string;
   permissions?: number;
+  mentionable?: boolean;
 }
 
 export interface UpdateRoleRequest {
@@ -1087,6 +1143,7 @@ export interface UpdateRoleRequest {
   color?: string;
   permissions?: number;
   position?: number;
+  mentionable?: boolean;
 }
 
 export interface AssignRoleResponse {
@@ -1325,6 +1382,8 @@ export interface InitE2EEResponse {
   identity_key_ed25519: string;
   identity_key_curve25519: string;
   prekeys: PrekeyData[];
+  /** Signed fallback key, if not yet published */
+  fallback_key: SignedFallbackKey | null;
 }
 
 export interface PrekeyData {
@@ -1332,15 +1391,51 @@ export interface PrekeyData {
   public_key: string;
 }
 
+/** Fallback key signed by its device, used once one-time prekeys run out. */
+export interface SignedFallbackKey {
+  key_id: string;
+  public_key: string;
+  signature: string;
+}
+
+/** Prekey supply of one of our devices, reported when it runs low. */
+export interface DevicePrekeyStatus {
+  device_id: string;
+  identity_key_curve25519: string;
+  one_time_prekeys: number;
+  needs_fallback_key: boolean;
+}
+
 export interface DeviceKeys {
   device_id: string;
   device_name: string | null;
   identity_key_ed25519: string;
   identity_key_curve25519: string;
+  /** Self-signing key signature over this device, if cross-signed. */
+  cross_signing_signature?: string | null;
+}
+
+/** A user's published cross-signing keys. */
+export interface CrossSigningKeys {
+  master_key: string;
+  self_signing_key: string;
+  user_signing_key: string;
+  self_signing_signature: string;
+  user_signing_signature: string;
 }
 
 export interface UserKeysResponse {
   devices: DeviceKeys[];
+  cross_signing?: CrossSigningKeys | null;
+  /** Our user-signing signature over this user's master key, if verified. */
+  user_signature?: string | null;
+}
+
+/** A device an outbound Megolm session was shared with. */
+export interface MegolmShare {
+  user_id: string;
+  device_id: string;
+  identity_key_curve25519: string;
 }
 
 export interface ClaimedPrekeyResponse {
@@ -1351,6 +1446,7 @@ export interface ClaimedPrekeyResponse {
     key_id: string;
     public_key: string;
   } | null;
+  fallback_key: SignedFallbackKey | null;
 }
 
 export interface E2EEContent {
@@ -1371,6 +1467,96 @@ export interface MegolmE2EEContent {
   room_id: string;
   /** Megolm ciphertext (base64) */
   megolm_ciphertext: string;
+  /** Megolm session ID; missing on messages sent before it was included */
+  session_id?: string;
+}
+
+/** A Megolm ciphertext and the session it was encrypted with. */
+export interface GroupCiphertext {
+  ciphertext: string;
+  session_id: string;
+}
+
+/** Signed request for a Megolm session this device is missing. */
+export interface RoomKeyRequest {
+  request_id: string;
+  room_id: string;
+  sender_key: string;
+  session_id: string;
+  requesting_user_id: string;
+  requesting_device_id: string;
+  signature: string;
+}
+
+/** A Megolm session shared in answer to a `RoomKeyRequest`. */
+export interface ForwardedRoomKey {
+  request_id: string;
+  room_id: string;
+  sender_key: string;
+  session_id: string;
+  session_key: string;
+}
+
+/** Olm-encrypted message addressed to one of this user's devices. */
+export interface ToDeviceMessage {
+  id: string;
+  sender_user_id: string;
+  sender_device_id: string;
+  recipient_device_id: string;
+  event_type: string;
+  /** Olm-encrypted payload, see `ToDeviceCiphertext` */
+  ciphertext: string;
+  created_at: string;
+}
+
+/** Layout of a to-device ciphertext produced by this client. */
+export interface ToDeviceCiphertext {
+  /** Sender's Curve25519 public key (base64) */
+  sender_key: string;
+  message_type: number;
+  ciphertext: string;
+}
+
+/** How much history the Tauri app's encrypted message cache keeps. */
+export interface MessageCacheRetention {
+  /** Drop messages posted more than this many days ago; null keeps them */
+  max_age_days: number | null;
+  /** Keep at most this many of the newest messages per channel; null keeps all */
+  max_messages_per_channel: number | null;
+}
+
+/** Key material of an encrypted attachment (AES-256-GCM, base64 fields). */
+export interface EncryptedFile {
+  v: string;
+  key: string;
+  iv: string;
+  sha256: string;
+}
+
+/** A file encrypted by the native app, ready for upload. */
+export interface EncryptedAttachment {
+  /** Ciphertext (base64) */
+  ciphertext: string;
+  file: EncryptedFile;
+}
+
+/**
+ * Plaintext of an encrypted message that carries a file. The server only sees
+ * the ciphertext; name, type, dimensions, blurhash and keys travel in here.
+ */
+export interface EncryptedFileContent {
+  type: "__encrypted_file__";
+  body: string;
+  file: {
+    name: string;
+    mimetype: string;
+    size: number;
+    width?: number;
+    height?: number;
+    blurhash?: string;
+    key: EncryptedFile;
+    thumbnail?: EncryptedFile;
+  };
 }
 
 export interface ClaimedPrekeyInput {
@@ -1382,6 +1568,7 @@ export interface ClaimedPrekeyInput {
     key_id: string;
     public_key: string;
   } | null;
+  fallback_key: SignedFallbackKey | null;
 }
 
 // ============================================================================
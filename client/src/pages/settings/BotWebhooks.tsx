@@ -35,6 +35,18 @@ const ALL_EVENT_TYPES: WebhookEventType[] = [
   "member.joined",
   "member.left",
   "command.invoked",
+  "message.updated",
+  "message.deleted",
+  "reaction.added",
+  "reaction.removed",
+  "voice.state_updated",
+  "channel.created",
+  "channel.updated",
+  "channel.deleted",
+  "role.created",
+  "role.updated",
+  "role.deleted",
+  "presence.updated",
 ];
 
 const BotWebhooks: Component = () => {
@@ -25,6 +25,7 @@ vi.mock("@/stores/presence", () => ({
 vi.mock("@/stores/messages", () => ({
   addMessage: vi.fn(),
   removeMessage: vi.fn(),
+  syncCachedEdit: vi.fn(),
   messagesState: { byChannel: {} },
   setMessagesState: vi.fn(),
 }));
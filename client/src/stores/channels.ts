@@ -310,6 +310,29 @@ export function handleChannelReadEvent(channelId: string): void {
   }
 }
 
+/**
+ * Handle channel_encryption_enabled event from WebSocket.
+ */
+export function handleChannelEncryptionEnabled(channelId: string, epoch: number): void {
+  const idx = channelsState.channels.findIndex((c) => c.id === channelId);
+  if (idx !== -1) {
+    setChannelsState("channels", idx, { encrypted: true, encryption_epoch: epoch });
+  }
+}
+
+/**
+ * Handle disappearing_messages_updated event from WebSocket.
+ */
+export function handleDisappearingMessagesUpdated(
+  channelId: string,
+  messageTtlSeconds: number | null,
+): void {
+  const idx = channelsState.channels.findIndex((c) => c.id === channelId);
+  if (idx !== -1) {
+    setChannelsState("channels", idx, "message_ttl_seconds", messageTtlSeconds);
+  }
+}
+
 /**
  * Create a new channel in a guild.
  */
@@ -189,6 +189,36 @@ export function handleDMNameUpdated(channelId: string, name: string): void {
   }
 }
 
+/**
+ * Handle channel_encryption_enabled event for a DM.
+ */
+export function handleDMEncryptionEnabled(channelId: string, epoch: number): void {
+  const dmIndex = dmsState.dms.findIndex((d) => d.id === channelId);
+  if (dmIndex !== -1) {
+    setDmsState("dms", dmIndex, { encrypted: true, encryption_epoch: epoch });
+  }
+}
+
+/**
+ * Handle disappearing_messages_updated event for a DM.
+ */
+export function handleDMDisappearingMessagesUpdated(
+  channelId: string,
+  messageTtlSeconds: number | null,
+): void {
+  const dmIndex = dmsState.dms.findIndex((d) => d.id === channelId);
+  if (dmIndex !== -1) {
+    setDmsState("dms", dmIndex, "message_ttl_seconds", messageTtlSeconds);
+  }
+}
+
+/**
+ * Find a DM by channel ID.
+ */
+export function getDM(channelId: string): DMListItem | undefined {
+  return dmsState.dms.find((d) => d.id === channelId);
+}
+
 /**
  * Update a DM channel's icon URL.
  */
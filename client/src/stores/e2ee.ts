@@ -14,19 +14,30 @@ import {
   markPrekeysPublished,
   generatePrekeys,
   needsPrekeyUpload,
+  replenishKeys,
+  uploadKeys,
+  getOurCurve25519Key,
   createMegolmSession,
   encryptGroupMessage,
   addInboundGroupSession,
   decryptGroupMessage,
+  enableKeyBackup,
+  syncKeyBackup,
+  restoreKeyBackup,
 } from "@/lib/tauri";
 import type {
   E2EEStatus,
   InitE2EEResponse,
   ClaimedPrekeyInput,
   E2EEContent,
+  GroupCiphertext,
   PrekeyData,
+  DevicePrekeyStatus,
 } from "@/lib/types";
 
+/** One-time prekeys a device keeps on the server. */
+const PREKEY_TARGET = 50;
+
 // Reactive state
 const [status, setStatus] = createSignal<E2EEStatus>({
   initialized: false,
@@ -158,6 +169,37 @@ async function markAsPublished(): Promise<void> {
   }
 }
 
+/**
+ * Top up this device's keys on the server if it is among the given devices.
+ * Called with the `ready` payload and on `prekeys_low` events.
+ */
+async function replenishIfLow(devices: DevicePrekeyStatus[]): Promise<void> {
+  if (!status().initialized || devices.length === 0) {
+    return;
+  }
+  try {
+    const ourKey = await getOurCurve25519Key();
+    const own = devices.find((d) => d.identity_key_curve25519 === ourKey);
+    if (!own) {
+      return;
+    }
+    const keys = await replenishKeys(
+      Math.max(PREKEY_TARGET - own.one_time_prekeys, 0),
+      own.needs_fallback_key,
+    );
+    await uploadKeys(
+      null,
+      keys.identity_key_ed25519,
+      keys.identity_key_curve25519,
+      keys.prekeys,
+      keys.fallback_key,
+    );
+    await markPrekeysPublished();
+  } catch (e) {
+    setError(String(e));
+  }
+}
+
 /**
  * Clear any E2EE errors.
  */
@@ -189,7 +231,7 @@ async function createGroupSession(roomId: string): Promise<string> {
  * Encrypt a message for a group using the current Megolm outbound session.
  * The outbound session must have been created via `createGroupSession()` first.
  */
-async function encryptGroup(roomId: string, plaintext: string): Promise<string> {
+async function encryptGroup(roomId: string, plaintext: string): Promise<GroupCiphertext> {
   if (!status().initialized) {
     throw new Error("E2EE not initialized");
   }
@@ -207,13 +249,14 @@ async function encryptGroup(roomId: string, plaintext: string): Promise<string>
 async function decryptGroup(
   roomId: string,
   senderKey: string,
+  sessionId: string | undefined,
   ciphertext: string
 ): Promise<string> {
   if (!status().initialized) {
     throw new Error("E2EE not initialized");
   }
   try {
-    return await decryptGroupMessage(roomId, senderKey, ciphertext);
+    return await decryptGroupMessage(roomId, senderKey, sessionId, ciphertext);
   } catch (e) {
     setError(String(e));
     throw e;
@@ -238,6 +281,53 @@ async function addInboundSession(
     setError(String(e));
     throw e;
   }
+  void backupKeys();
+}
+
+/**
+ * Upload new Megolm sessions to the server-side key backup, if enabled.
+ * Failures are logged and retried with the next new session.
+ */
+async function backupKeys(): Promise<void> {
+  try {
+    await syncKeyBackup();
+  } catch (e) {
+    console.warn("[E2EE] Key backup sync failed:", e);
+  }
+}
+
+/**
+ * Start a new key backup for the recovery key and upload all sessions.
+ */
+async function enableBackup(recoveryKey: string): Promise<number> {
+  if (!status().initialized) {
+    throw new Error("E2EE not initialized");
+  }
+  try {
+    const version = await enableKeyBackup(recoveryKey);
+    await syncKeyBackup();
+    return version;
+  } catch (e) {
+    setError(String(e));
+    throw e;
+  }
+}
+
+/**
+ * Import Megolm sessions from the key backup. Returns the number imported.
+ */
+async function restoreBackup(recoveryKey: string): Promise<number> {
+  if (!status().initialized) {
+    throw new Error("E2EE not initialized");
+  }
+  try {
+    const restored = await restoreKeyBackup(recoveryKey);
+    void backupKeys();
+    return restored;
+  } catch (e) {
+    setError(String(e));
+    throw e;
+  }
 }
 
 // Export the store
@@ -255,6 +345,7 @@ export const e2eeStore = {
   generateMorePrekeys,
   checkNeedsPrekeyUpload,
   markAsPublished,
+  replenishIfLow,
   clearError,
 
   // Megolm Group Functions
@@ -262,6 +353,11 @@ export const e2eeStore = {
   encryptGroup,
   decryptGroup,
   addInboundSession,
+
+  // Key Backup Functions
+  backupKeys,
+  enableBackup,
+  restoreBackup,
 };
 
 // Also export individual signals and functions for direct access
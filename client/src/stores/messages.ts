@@ -7,11 +7,23 @@
 
 import { createSignal } from "solid-js";
 import { createStore } from "solid-js/store";
-import type { Message, ClaimedPrekeyInput, DMListItem, E2EEContent, MegolmE2EEContent } from "@/lib/types";
+import type {
+  Message,
+  ClaimedPrekeyInput,
+  DMListItem,
+  E2EEContent,
+  EncryptedFileContent,
+  MegolmE2EEContent,
+  PaginatedMessages,
+} from "@/lib/types";
 import * as tauri from "@/lib/tauri";
+import { createImagePreview } from "@/lib/encryptedFiles";
 import { e2eeStore } from "@/stores/e2ee";
 import { showToast } from "@/components/ui/Toast";
 import { currentUser } from "@/stores/auth";
+import { getChannel } from "@/stores/channels";
+import { getDM } from "@/stores/dms";
+import { requestRoomKey } from "@/stores/roomKeys";
 
 // ============================================================================
 // E2EE Decryption Helpers
@@ -75,12 +87,33 @@ async function decryptMessageIfNeeded(message: Message): Promise<Message> {
     // Megolm group message — identified by `megolm_ciphertext` key
     if ("megolm_ciphertext" in parsed) {
       const megolm = parsed as MegolmE2EEContent;
-      const plaintext = await e2eeStore.decryptGroup(
-        megolm.room_id,
-        megolm.sender_key,
-        megolm.megolm_ciphertext
-      );
-      return { ...message, content: plaintext };
+      try {
+        const plaintext = await e2eeStore.decryptGroup(
+          megolm.room_id,
+          megolm.sender_key,
+          megolm.session_id,
+          megolm.megolm_ciphertext
+        );
+        return withDecryptedContent(message, plaintext);
+      } catch (err) {
+        // Usually the session key hasn't reached us yet; retried when it does.
+        // Devices that joined later ask our other devices or the sender for it.
+        console.warn("[E2EE/Megolm] No session for message yet:", err);
+        if (megolm.session_id) {
+          void requestRoomKey(
+            megolm.room_id,
+            megolm.sender_key,
+            megolm.session_id,
+            message.author.id,
+          );
+        }
+        return {
+          ...message,
+          content: "[Unable to decrypt - waiting for the sender's keys]",
+          undecryptable: true,
+          ciphertext: message.content,
+        };
+      }
     }
 
     // 1:1 Olm message — identified by `recipients` key
@@ -125,13 +158,54 @@ async function decryptMessageIfNeeded(message: Message): Promise<Message> {
       return { ...message, content: "[Encryption key exchange]" };
     }
 
-    return { ...message, content: plaintext };
+    return withDecryptedContent(message, plaintext);
   } catch (err) {
     console.error("[E2EE] Decryption failed:", err);
     return { ...message, content: "[Unable to decrypt message]" };
   }
 }
 
+/**
+ * Build the decrypted message. Messages that carry an encrypted file hold the
+ * file's name, type, preview metadata and keys in their plaintext; these are
+ * merged into the opaque attachment the server returned.
+ */
+function withDecryptedContent(message: Message, plaintext: string): Message {
+  let payload: EncryptedFileContent | null = null;
+  try {
+    const parsed = JSON.parse(plaintext);
+    if (parsed?.type === "__encrypted_file__" && parsed.file) {
+      payload = parsed as EncryptedFileContent;
+    }
+  } catch {
+    // Regular text message
+  }
+  if (!payload) {
+    return { ...message, content: plaintext };
+  }
+
+  const { file } = payload;
+  return {
+    ...message,
+    content: payload.body,
+    attachments: (message.attachments ?? []).map((attachment) =>
+      attachment.encrypted
+        ? {
+            ...attachment,
+            filename: file.name,
+            mime_type: file.mimetype,
+            size: file.size,
+            width: file.width,
+            height: file.height,
+            blurhash: file.blurhash,
+            file: file.key,
+            thumbnail_file: file.thumbnail,
+          }
+        : attachment,
+    ),
+  };
+}
+
 /**
  * Check if a decrypted Olm message contains a Megolm session key payload.
  * If so, automatically store it as an inbound group session.
@@ -159,6 +233,7 @@ async function processMegolmKeyIfPresent(
     // Store the inbound session
     await e2eeStore.addInboundSession(roomId, senderKey, sessionKey);
     console.log(`[E2EE/Megolm] Stored inbound session key for room ${roomId} from ${senderKey.slice(0, 8)}...`);
+    void retryUndecryptableMessages(roomId);
     return true;
   } catch {
     // Not JSON or not a key payload — that's fine, it's a regular message
@@ -166,6 +241,28 @@ async function processMegolmKeyIfPresent(
   }
 }
 
+/**
+ * Retry decrypting messages in a channel that arrived before their Megolm
+ * session key did.
+ */
+export async function retryUndecryptableMessages(channelId: string): Promise<void> {
+  const pending = (messagesState.byChannel[channelId] || []).filter(
+    (m) => m.undecryptable && m.ciphertext,
+  );
+  for (const message of pending) {
+    const retried = await decryptMessageIfNeeded({
+      ...message,
+      content: message.ciphertext!,
+      undecryptable: undefined,
+      ciphertext: undefined,
+    });
+    if (!retried.undecryptable) {
+      updateMessage(retried);
+      cacheDecrypted([retried]);
+    }
+  }
+}
+
 /**
  * Decrypt multiple messages in parallel.
  */
@@ -173,6 +270,54 @@ async function decryptMessages(messages: Message[]): Promise<Message[]> {
   return Promise.all(messages.map(decryptMessageIfNeeded));
 }
 
+/**
+ * Store decrypted messages in the local encrypted cache.
+ * Best-effort: failures only affect offline history and local search.
+ */
+function cacheDecrypted(messages: Message[]): void {
+  if (!e2eeStore.status().initialized) return;
+  const cacheable = messages.filter((m) => !m.undecryptable && !m.id.startsWith("pending:"));
+  if (cacheable.length === 0) return;
+  tauri.cacheMessages(cacheable).catch((err) => {
+    console.warn("Failed to cache messages:", err);
+  });
+}
+
+/**
+ * Reconcile the local cache with a page fetched from the server.
+ * Returns the page with undecryptable messages filled in from the cache.
+ */
+async function syncMessageCache(
+  channelId: string,
+  before: string | undefined,
+  messages: Message[],
+  hasMore: boolean,
+): Promise<Message[]> {
+  if (!e2eeStore.status().initialized) return messages;
+  try {
+    return await tauri.syncCachedMessages(channelId, before, messages, hasMore);
+  } catch (err) {
+    console.warn("Failed to sync message cache:", err);
+    return messages;
+  }
+}
+
+/**
+ * Load a page from the local cache when the server can't be reached.
+ */
+async function loadCachedPage(
+  channelId: string,
+  before: string | undefined,
+): Promise<PaginatedMessages | null> {
+  if (!e2eeStore.status().initialized) return null;
+  try {
+    const page = await tauri.getCachedMessages(channelId, before, MESSAGE_LIMIT);
+    return page.items.length > 0 ? page : null;
+  } catch {
+    return null;
+  }
+}
+
 // Messages state interface
 interface MessagesState {
   // Messages indexed by channel ID
@@ -216,11 +361,24 @@ export async function loadMessages(channelId: string): Promise<void> {
     const existing = messagesState.byChannel[channelId] || [];
     const before = existing.length > 0 ? existing[0].id : undefined;
 
-    const response = await tauri.getMessages(channelId, before, MESSAGE_LIMIT);
-    const messageList = response.items;
-
-    // Decrypt encrypted messages
-    const decryptedMessages = await decryptMessages(messageList);
+    let response: PaginatedMessages;
+    let decryptedMessages: Message[];
+    try {
+      response = await tauri.getMessages(channelId, before, MESSAGE_LIMIT);
+      // Decrypt encrypted messages, then fill gaps from the local cache
+      decryptedMessages = await syncMessageCache(
+        channelId,
+        before,
+        await decryptMessages(response.items),
+        response.has_more,
+      );
+    } catch (err) {
+      // Offline: serve history from the local cache, already decrypted
+      const cached = await loadCachedPage(channelId, before);
+      if (!cached) throw err;
+      response = cached;
+      decryptedMessages = cached.items;
+    }
 
     // Initialize channel if needed
     if (!messagesState.byChannel[channelId]) {
@@ -279,6 +437,11 @@ export async function sendMessage(
     return null;
   }
 
+  // Encrypted channels refuse plaintext; never fall back to it
+  if (getChannel(channelId)?.encrypted || getDM(channelId)?.encrypted) {
+    return sendEncryptedChannelMessage(channelId, trimmedContent);
+  }
+
   setMessagesState({ error: null });
 
   // Build an optimistic (pending) message so the UI updates instantly
@@ -362,6 +525,7 @@ export async function addMessage(message: Message): Promise<void> {
 
   // Decrypt if needed (async — store may change during this await)
   const processedMessage = await decryptMessageIfNeeded(message);
+  cacheDecrypted([processedMessage]);
 
   // Re-read store after await to avoid losing concurrent prepends from loadMessages
   const existing = messagesState.byChannel[channelId] || [];
@@ -396,6 +560,26 @@ export function updateMessage(message: Message): void {
       setMessagesState("byChannel", channelId, index, message);
     }
   }
+  // Edits of encrypted messages come back as ciphertext
+  if (!message.encrypted) {
+    cacheDecrypted([message]);
+  }
+}
+
+/**
+ * Bring the cached copy of a message in line with an edit applied to the store.
+ * Encrypted edits arrive as ciphertext, so their stale copy is dropped instead.
+ */
+export function syncCachedEdit(channelId: string, messageId: string): void {
+  const message = messagesState.byChannel[channelId]?.find((m) => m.id === messageId);
+  if (!message || !e2eeStore.status().initialized) return;
+  if (message.encrypted) {
+    tauri.removeCachedMessage(messageId).catch((err) => {
+      console.warn("Failed to remove cached message:", err);
+    });
+  } else {
+    cacheDecrypted([message]);
+  }
 }
 
 /**
@@ -406,6 +590,11 @@ export function removeMessage(channelId: string, messageId: string): void {
   if (existing) {
     setMessagesState("byChannel", channelId, existing.filter((m) => m.id !== messageId));
   }
+  if (e2eeStore.status().initialized && !messageId.startsWith("pending:")) {
+    tauri.removeCachedMessage(messageId).catch((err) => {
+      console.warn("Failed to remove cached message:", err);
+    });
+  }
 }
 
 /**
@@ -470,6 +659,7 @@ async function claimPrekeysForRecipients(
             identity_key_ed25519: claimed.identity_key_ed25519,
             identity_key_curve25519: claimed.identity_key_curve25519,
             one_time_prekey: claimed.one_time_prekey,
+            fallback_key: claimed.fallback_key,
           });
         } catch (err) {
           console.warn(`[E2EE] Failed to claim prekey for device ${device.device_id}:`, err);
@@ -577,6 +767,9 @@ export async function sendDMMessage(
       .filter((id) => id !== currentUserId);
 
     if (recipientUserIds.length > 0) {
+      if (dm.encrypted) {
+        return sendEncryptedChannelMessage(channelId, content);
+      }
       try {
         // Group DMs (3+ participants including self) use Megolm
         if (dm.participants.length > 2) {
@@ -609,6 +802,8 @@ interface MegolmSessionState {
   messageCount: number;
   /** Sorted participant IDs at the time the session was created. */
   participantHash: string;
+  /** Channel membership epoch the session was shared for (encrypted channels). */
+  epoch?: number;
 }
 
 /** Rotate the outbound session after this many messages (Matrix uses 100). */
@@ -625,6 +820,135 @@ function participantHash(recipientUserIds: string[]): string {
   return [...recipientUserIds].sort().join(",");
 }
 
+/** Options for sending an already encrypted message body. */
+interface EncryptedSendOptions {
+  encrypted: true;
+  nonce: string;
+  encryptionEpoch?: number;
+}
+
+/**
+ * Delivers an encrypted message body to the server: a plain message by
+ * default, or an upload for messages that carry an encrypted file.
+ */
+type EncryptedTransport = (
+  channelId: string,
+  content: string,
+  options: EncryptedSendOptions,
+) => Promise<Message>;
+
+const sendEncryptedContent: EncryptedTransport = (channelId, content, options) =>
+  tauri.sendMessage(channelId, content, options);
+
+/**
+ * Whether a send failed because the channel's membership epoch moved on.
+ */
+function isStaleEpochError(err: unknown): boolean {
+  const message = err instanceof Error ? err.message : String(err);
+  return message.includes("ENCRYPTION_EPOCH_STALE");
+}
+
+/**
+ * Send a message to an end-to-end encrypted channel or DM.
+ *
+ * Fetches the channel's current epoch and members, then sends with Megolm.
+ * If the members changed while sending, the server rejects the stale epoch
+ * and the message is sent once more with a fresh session.
+ */
+export async function sendEncryptedChannelMessage(
+  channelId: string,
+  content: string,
+  retryOnStaleEpoch = true,
+  transport: EncryptedTransport = sendEncryptedContent,
+): Promise<Message | null> {
+  const status = e2eeStore.status();
+  if (!status.initialized) {
+    showToast({ type: "error", title: "Send Failed", message: "This channel is end-to-end encrypted. Set up encryption to send messages.", duration: 8000 });
+    return null;
+  }
+
+  try {
+    const encryption = await tauri.getChannelEncryption(channelId);
+    const me = currentUser()?.id;
+    const recipients = encryption.members.filter((id) => id !== me);
+    return await sendEncryptedGroupDM(channelId, content, recipients, encryption.epoch, transport);
+  } catch (err) {
+    if (retryOnStaleEpoch && isStaleEpochError(err)) {
+      megolmSessionCache.delete(channelId);
+      return sendEncryptedChannelMessage(channelId, content, false, transport);
+    }
+    const error = err instanceof Error ? err.message : String(err);
+    console.error("Failed to send encrypted channel message:", error);
+    showToast({ type: "error", title: "Send Failed", message: "Could not send encrypted message. Please try again.", duration: 8000 });
+    setMessagesState({ error });
+    return null;
+  }
+}
+
+/**
+ * Share a file in an end-to-end encrypted channel or DM.
+ *
+ * The file (and a thumbnail for images) is encrypted under a fresh key in
+ * the native app. The key, the real name and type, and the image dimensions
+ * and blurhash go into the Megolm-encrypted message body, which is uploaded
+ * together with the ciphertext.
+ */
+export async function sendEncryptedFile(
+  channelId: string,
+  file: File,
+  content?: string,
+): Promise<Message | null> {
+  if (!e2eeStore.status().initialized) {
+    showToast({ type: "error", title: "Send Failed", message: "This channel is end-to-end encrypted. Set up encryption to send files.", duration: 8000 });
+    return null;
+  }
+
+  const [preview, encrypted] = await Promise.all([
+    createImagePreview(file),
+    file.arrayBuffer().then((data) => tauri.encryptAttachment(new Uint8Array(data))),
+  ]);
+  const thumbnail = preview?.thumbnail
+    ? await tauri.encryptAttachment(new Uint8Array(await preview.thumbnail.arrayBuffer()))
+    : undefined;
+
+  const body: EncryptedFileContent = {
+    type: "__encrypted_file__",
+    body: content ?? "",
+    file: {
+      name: file.name,
+      mimetype: file.type || "application/octet-stream",
+      size: file.size,
+      width: preview?.width,
+      height: preview?.height,
+      blurhash: preview?.blurhash,
+      key: encrypted.file,
+      thumbnail: thumbnail?.file,
+    },
+  };
+
+  const ciphertext = base64ToBlob(encrypted.ciphertext);
+  const thumbnailCiphertext = thumbnail ? base64ToBlob(thumbnail.ciphertext) : undefined;
+  return sendEncryptedChannelMessage(
+    channelId,
+    JSON.stringify(body),
+    true,
+    (target, encryptedContent, options) =>
+      tauri.uploadMessageWithFile(target, ciphertext, encryptedContent, {
+        ...options,
+        thumbnail: thumbnailCiphertext,
+      }),
+  );
+}
+
+function base64ToBlob(data: string): Blob {
+  const binary = atob(data);
+  const bytes = new Uint8Array(binary.length);
+  for (let i = 0; i < binary.length; i++) {
+    bytes[i] = binary.charCodeAt(i);
+  }
+  return new Blob([bytes], { type: "application/octet-stream" });
+}
+
 /**
  * Send an encrypted group message using Megolm.
  *
@@ -637,12 +961,15 @@ function participantHash(recipientUserIds: string[]): string {
  * @param channelId - The channel/room ID
  * @param content - The plaintext message content
  * @param recipientUserIds - The user IDs of the members (excluding self)
+ * @param transport - Sends the encrypted body (a plain message by default)
  * @returns The sent message or null on failure
  */
 export async function sendEncryptedGroupDM(
   channelId: string,
   content: string,
-  recipientUserIds: string[]
+  recipientUserIds: string[],
+  encryptionEpoch?: number,
+  transport: EncryptedTransport = sendEncryptedContent,
 ): Promise<Message | null> {
   if (!content.trim()) {
     return null;
@@ -662,26 +989,28 @@ export async function sendEncryptedGroupDM(
     const needsNewSession =
       !cached ||
       cached.messageCount >= MEGOLM_ROTATION_LIMIT ||
-      cached.participantHash !== currentHash;
+      cached.participantHash !== currentHash ||
+      cached.epoch !== encryptionEpoch;
 
     if (needsNewSession) {
       // Create a new outbound Megolm session
       const sessionKey = await e2eeStore.createGroupSession(channelId);
 
       // Distribute the session key to all group members via 1:1 Olm messages
-      await distributeGroupSessionKey(channelId, sessionKey, recipientUserIds);
+      await distributeGroupSessionKey(channelId, sessionKey, recipientUserIds, encryptionEpoch);
 
       // Cache the new session state
       megolmSessionCache.set(channelId, {
         messageCount: 0,
         participantHash: currentHash,
+        epoch: encryptionEpoch,
       });
 
       console.log(`[E2EE/Megolm] Created new outbound session for room ${channelId}`);
     }
 
     // Step 2: Encrypt the actual message with Megolm.
-    const megolmCiphertext = await e2eeStore.encryptGroup(channelId, content.trim());
+    const encrypted = await e2eeStore.encryptGroup(channelId, content.trim());
 
     // Increment message counter
     const state = megolmSessionCache.get(channelId)!;
@@ -697,23 +1026,31 @@ export async function sendEncryptedGroupDM(
     const megolmContent: MegolmE2EEContent = {
       sender_key: senderKey,
       room_id: channelId,
-      megolm_ciphertext: megolmCiphertext,
+      megolm_ciphertext: encrypted.ciphertext,
+      session_id: encrypted.session_id,
     };
 
     // Step 4: Send the encrypted message.
     const encryptedContent = JSON.stringify(megolmContent);
-    const message = await tauri.sendMessage(channelId, encryptedContent, {
+    const message = await transport(channelId, encryptedContent, {
       encrypted: true,
+      nonce: crypto.randomUUID(),
+      encryptionEpoch,
     });
 
-    // Add to local store
+    // Add to local store, decrypted with our own inbound session
+    const shown = await decryptMessageIfNeeded(message);
     const prev = messagesState.byChannel[channelId] || [];
-    if (!prev.some((m) => m.id === message.id)) {
-      setMessagesState("byChannel", channelId, [...prev, message]);
+    if (!prev.some((m) => m.id === shown.id)) {
+      setMessagesState("byChannel", channelId, [...prev, shown]);
     }
 
-    return message;
+    return shown;
   } catch (err) {
+    // Encrypted channels rotate the session and retry in the caller
+    if (encryptionEpoch !== undefined && isStaleEpochError(err)) {
+      throw err;
+    }
     const error = err instanceof Error ? err.message : String(err);
     console.error("Failed to send encrypted group message:", error);
     showToast({ type: "error", title: "Send Failed", message: "Could not send encrypted group message. Please try again.", duration: 8000 });
@@ -733,7 +1070,8 @@ export async function sendEncryptedGroupDM(
 async function distributeGroupSessionKey(
   roomId: string,
   sessionKey: string,
-  recipientUserIds: string[]
+  recipientUserIds: string[],
+  encryptionEpoch?: number
 ): Promise<void> {
   // Build the key-sharing payload
   const keyPayload = JSON.stringify({
@@ -757,7 +1095,21 @@ async function distributeGroupSessionKey(
   // This ensures recipients receive it via WebSocket `message_new` events
   // and can automatically process it in `decryptMessageIfNeeded()`.
   const encryptedContent = JSON.stringify(encrypted);
-  await tauri.sendMessage(roomId, encryptedContent, { encrypted: true });
+  await tauri.sendMessage(roomId, encryptedContent, {
+    encrypted: true,
+    nonce: crypto.randomUUID(),
+    encryptionEpoch,
+  });
+
+  // Only these exact devices may later ask us for the session again
+  await tauri.recordMegolmSessionShares(
+    roomId,
+    recipients.map((r) => ({
+      user_id: r.user_id,
+      device_id: r.device_id,
+      identity_key_curve25519: r.identity_key_curve25519,
+    })),
+  );
 
   // Also store the session key as an inbound session for ourselves
   // so we can decrypt our own Megolm messages (e.g., on other devices)
@@ -0,0 +1,251 @@
+/**
+ * Room Key Requests
+ *
+ * Asks this user's other devices and the original sender for Megolm sessions
+ * this device is missing, and answers such requests from other devices.
+ * Requests and forwarded keys are Olm-encrypted and travel over the server's
+ * to-device queue. Which requests get answered is decided by the native
+ * crypto manager.
+ */
+
+import * as tauri from "@/lib/tauri";
+import type {
+  ClaimedPrekeyInput,
+  DeviceKeys,
+  ForwardedRoomKey,
+  RoomKeyRequest,
+  ToDeviceCiphertext,
+  ToDeviceMessage,
+} from "@/lib/types";
+import { currentUser } from "@/stores/auth";
+import { e2eeStore } from "@/stores/e2ee";
+import { retryUndecryptableMessages } from "@/stores/messages";
+
+/** To-device event types, matching `vc_crypto::room_key`. */
+const ROOM_KEY_REQUEST_EVENT_TYPE = "kaiku.room_key_request";
+const FORWARDED_ROOM_KEY_EVENT_TYPE = "kaiku.forwarded_room_key";
+
+interface Target {
+  userId: string;
+  device: DeviceKeys;
+}
+
+// Server device ID of this device, cached per identity key
+let ownDevice: { curveKey: string; deviceId: string } | null = null;
+
+/**
+ * Find this device's server device ID by its Curve25519 identity key.
+ */
+async function getOwnDeviceId(): Promise<string | null> {
+  const user = currentUser();
+  const curveKey = await tauri.getOurCurve25519Key();
+  if (!user || !curveKey) {
+    return null;
+  }
+  if (ownDevice?.curveKey === curveKey) {
+    return ownDevice.deviceId;
+  }
+
+  const { devices } = await tauri.getUserKeys(user.id);
+  const own = devices.find((d) => d.identity_key_curve25519 === curveKey);
+  ownDevice = own ? { curveKey, deviceId: own.device_id } : null;
+  return ownDevice?.deviceId ?? null;
+}
+
+/**
+ * Olm-encrypt `payload` for each target device and queue it on the server.
+ */
+async function sendEncrypted(
+  ownDeviceId: string,
+  eventType: string,
+  payload: RoomKeyRequest | ForwardedRoomKey,
+  targets: Target[],
+): Promise<void> {
+  const claimed: ClaimedPrekeyInput[] = [];
+  for (const { userId, device } of targets) {
+    try {
+      const prekey = await tauri.claimPrekey(userId, device.device_id);
+      claimed.push({ user_id: userId, ...prekey });
+    } catch (err) {
+      console.warn(`[E2EE/Keys] Failed to claim prekey for device ${device.device_id}:`, err);
+    }
+  }
+  if (claimed.length === 0) {
+    return;
+  }
+
+  const encrypted = await e2eeStore.encrypt(JSON.stringify(payload), claimed);
+  const messages: Record<string, Record<string, string>> = {};
+  for (const recipient of claimed) {
+    const message =
+      encrypted.recipients[recipient.user_id]?.[recipient.identity_key_curve25519];
+    if (!message) {
+      continue;
+    }
+    const ciphertext: ToDeviceCiphertext = {
+      sender_key: encrypted.sender_key,
+      message_type: message.message_type,
+      ciphertext: message.ciphertext,
+    };
+    (messages[recipient.user_id] ??= {})[recipient.device_id] = JSON.stringify(ciphertext);
+  }
+
+  await tauri.sendToDevice(ownDeviceId, eventType, messages);
+}
+
+/**
+ * Ask for a Megolm session after a message couldn't be decrypted.
+ *
+ * The request goes to our other devices and to the sender's device that owns
+ * `senderKey`. Sessions we asked for recently are not requested again.
+ */
+export async function requestRoomKey(
+  roomId: string,
+  senderKey: string,
+  sessionId: string,
+  senderUserId: string,
+): Promise<void> {
+  const user = currentUser();
+  if (!user || !e2eeStore.status().initialized) {
+    return;
+  }
+
+  try {
+    const ownDeviceId = await getOwnDeviceId();
+    if (!ownDeviceId) {
+      return;
+    }
+    const request = await tauri.requestRoomKey(ownDeviceId, roomId, senderKey, sessionId);
+    if (!request) {
+      return;
+    }
+
+    const targets: Target[] = (await tauri.getUserKeys(user.id)).devices
+      .filter((device) => device.device_id !== ownDeviceId)
+      .map((device) => ({ userId: user.id, device }));
+    if (senderUserId !== user.id) {
+      const sender = (await tauri.getUserKeys(senderUserId)).devices.find(
+        (device) => device.identity_key_curve25519 === senderKey,
+      );
+      if (sender) {
+        targets.push({ userId: senderUserId, device: sender });
+      }
+    }
+
+    await sendEncrypted(ownDeviceId, ROOM_KEY_REQUEST_EVENT_TYPE, request, targets);
+    console.log(
+      `[E2EE/Keys] Requested session ${sessionId.slice(0, 8)}... from ${targets.length} device(s)`,
+    );
+  } catch (err) {
+    console.warn("[E2EE/Keys] Room key request failed:", err);
+  }
+}
+
+/**
+ * Decrypt and act on a room key to-device message.
+ *
+ * @returns false if the message is of another type and was left alone,
+ *   true once it was handled and can be acknowledged
+ */
+async function processMessage(message: ToDeviceMessage): Promise<boolean> {
+  if (
+    message.event_type !== ROOM_KEY_REQUEST_EVENT_TYPE &&
+    message.event_type !== FORWARDED_ROOM_KEY_EVENT_TYPE
+  ) {
+    return false;
+  }
+
+  try {
+    const encrypted = JSON.parse(message.ciphertext) as ToDeviceCiphertext;
+
+    // The payload is only trusted as coming from the device whose identity
+    // key the Olm session was established with.
+    const senderKeys = await tauri.getUserKeys(message.sender_user_id);
+    const sender = senderKeys.devices.find(
+      (device) =>
+        device.device_id === message.sender_device_id &&
+        device.identity_key_curve25519 === encrypted.sender_key,
+    );
+    if (!sender) {
+      console.warn("[E2EE/Keys] Dropping to-device message from unknown device");
+      return true;
+    }
+
+    const plaintext = await e2eeStore.decrypt(
+      message.sender_user_id,
+      encrypted.sender_key,
+      encrypted.message_type,
+      encrypted.ciphertext,
+    );
+
+    if (message.event_type === ROOM_KEY_REQUEST_EVENT_TYPE) {
+      const request = JSON.parse(plaintext) as RoomKeyRequest;
+      const forwarded = await tauri.handleRoomKeyRequest(
+        message.sender_user_id,
+        sender,
+        senderKeys.cross_signing ?? null,
+        request,
+      );
+      if (forwarded) {
+        await sendEncrypted(
+          message.recipient_device_id,
+          FORWARDED_ROOM_KEY_EVENT_TYPE,
+          forwarded,
+          [{ userId: message.sender_user_id, device: sender }],
+        );
+      }
+    } else {
+      const forwarded = JSON.parse(plaintext) as ForwardedRoomKey;
+      if (await tauri.addForwardedRoomKey(message.sender_user_id, sender, forwarded)) {
+        void e2eeStore.backupKeys();
+        await retryUndecryptableMessages(forwarded.room_id);
+      }
+    }
+  } catch (err) {
+    console.warn(`[E2EE/Keys] Failed to handle ${message.event_type}:`, err);
+  }
+  return true;
+}
+
+/**
+ * Handle a to-device message pushed over the WebSocket.
+ */
+export async function handleToDeviceMessage(message: ToDeviceMessage): Promise<void> {
+  if (!e2eeStore.status().initialized) {
+    return;
+  }
+  if (await processMessage(message)) {
+    await tauri
+      .ackToDeviceMessages(message.recipient_device_id, [message.id])
+      .catch((err) => console.warn("[E2EE/Keys] Failed to acknowledge message:", err));
+  }
+}
+
+/**
+ * Handle to-device messages that were queued while this device was offline.
+ * Called after (re)connecting.
+ */
+export async function processPendingToDeviceMessages(): Promise<void> {
+  if (!e2eeStore.status().initialized) {
+    return;
+  }
+
+  try {
+    const ownDeviceId = await getOwnDeviceId();
+    if (!ownDeviceId) {
+      return;
+    }
+    const pending = await tauri.getPendingToDeviceMessages(ownDeviceId);
+    const handled: string[] = [];
+    for (const message of pending) {
+      if (await processMessage(message)) {
+        handled.push(message.id);
+      }
+    }
+    if (handled.length > 0) {
+      await tauri.ackToDeviceMessages(ownDeviceId, handled);
+    }
+  } catch (err) {
+    console.warn("[E2EE/Keys] Failed to process pending to-device messages:", err);
+  }
+}
@@ -8,6 +8,7 @@
 import { createSignal } from "solid-js";
 import { createStore } from "solid-js/store";
 import type {
+  Message,
   SearchResult,
   SearchFilters,
   GlobalSearchResult,
@@ -16,7 +17,9 @@ import {
   searchGuildMessages,
   searchDMMessages,
   searchGlobalMessages,
+  searchCachedMessages,
 } from "@/lib/tauri";
+import { e2eeStore } from "@/stores/e2ee";
 
 // ============================================================================
 // Types
@@ -43,6 +46,10 @@ interface SearchState {
   context: "guild" | "dm" | "global";
   /** Active search filters */
   filters: SearchFilters;
+  /** Searched channels whose encrypted messages the server can't search */
+  encryptedChannelIds: string[];
+  /** Matches for the query among locally cached messages of those channels */
+  localResults: Message[];
 }
 
 // ============================================================================
@@ -60,6 +67,8 @@ const [searchState, setSearchState] = createStore<SearchState>({
   guildId: null,
   context: "guild",
   filters: {},
+  encryptedChannelIds: [],
+  localResults: [],
 });
 
 // Global search visibility signal
@@ -69,6 +78,26 @@ const [showGlobalSearch, setShowGlobalSearch] = createSignal(false);
 // Actions
 // ============================================================================
 
+/**
+ * Search the local message cache for encrypted channels the server skipped.
+ */
+async function searchLocalCache(query: string, channelIds: string[]): Promise<void> {
+  if (channelIds.length === 0 || !e2eeStore.status().initialized) {
+    setSearchState("localResults", []);
+    return;
+  }
+  try {
+    const results = await searchCachedMessages(query, channelIds);
+    // Ignore results for a query that has since changed
+    if (searchState.query === query) {
+      setSearchState("localResults", results);
+    }
+  } catch (err) {
+    console.warn("Local search failed:", err);
+    setSearchState("localResults", []);
+  }
+}
+
 /**
  * Search messages in a guild.
  */
@@ -86,6 +115,8 @@ export async function search(
       offset: 0,
       error: null,
       isSearching: false,
+      encryptedChannelIds: [],
+      localResults: [],
       guildId,
       context: "guild",
       filters,
@@ -117,7 +148,9 @@ export async function search(
       total: response.total,
       offset: 0,
       isSearching: false,
+      encryptedChannelIds: response.encrypted_channel_ids,
     });
+    void searchLocalCache(query.trim(), response.encrypted_channel_ids);
   } catch (err) {
     console.error("Search failed:", err);
     setSearchState({
@@ -145,6 +178,8 @@ export async function searchDMs(
       offset: 0,
       error: null,
       isSearching: false,
+      encryptedChannelIds: [],
+      localResults: [],
       guildId: null,
       context: "dm",
       filters,
@@ -175,7 +210,9 @@ export async function searchDMs(
       total: response.total,
       offset: 0,
       isSearching: false,
+      encryptedChannelIds: response.encrypted_channel_ids,
     });
+    void searchLocalCache(query.trim(), response.encrypted_channel_ids);
   } catch (err) {
     console.error("DM search failed:", err);
     setSearchState({
@@ -210,6 +247,8 @@ export async function searchGlobal(
       offset: 0,
       error: null,
       isSearching: false,
+      encryptedChannelIds: [],
+      localResults: [],
       guildId: null,
       context: "global",
       filters: effectiveFilters,
@@ -240,7 +279,9 @@ export async function searchGlobal(
       total: response.total,
       offset: 0,
       isSearching: false,
+      encryptedChannelIds: response.encrypted_channel_ids,
     });
+    void searchLocalCache(query.trim(), response.encrypted_channel_ids);
   } catch (err) {
     console.error("Global search failed:", err);
     setSearchState({
@@ -324,6 +365,8 @@ export function clearSearch(): void {
     guildId: null,
     context: "guild",
     filters: {},
+    encryptedChannelIds: [],
+    localResults: [],
   });
 }
 
@@ -9,9 +9,11 @@ import * as tauri from "@/lib/tauri";
 import type {
   Activity,
   CustomStatus,
+  DevicePrekeyStatus,
   Message,
   ServerEvent,
   ThreadInfo,
+  ToDeviceMessage,
   UserStatus,
 } from "@/lib/types";
 import {
@@ -22,6 +24,7 @@ import {
 import {
   addMessage,
   removeMessage,
+  syncCachedEdit,
   messagesState,
   setMessagesState,
 } from "./messages";
@@ -36,6 +39,8 @@ import {
   threadsState,
 } from "./threads";
 import { handlePreferencesUpdated } from "./preferences";
+import { e2eeStore } from "./e2ee";
+import { handleToDeviceMessage, processPendingToDeviceMessages } from "./roomKeys";
 import {
   receiveIncomingCall,
   callConnected,
@@ -57,6 +62,8 @@ import {
   getChannel,
   channelsState,
   handleChannelReadEvent,
+  handleChannelEncryptionEnabled,
+  handleDisappearingMessagesUpdated,
   incrementUnreadCount,
 } from "./channels";
 import { currentUser } from "./auth";
@@ -70,6 +77,8 @@ import {
   dmsState,
   handleDMReadEvent,
   handleDMNameUpdated,
+  handleDMEncryptionEnabled,
+  handleDMDisappearingMessagesUpdated,
   updateDMLastMessage,
 } from "./dms";
 import { handlePinAdded, handlePinRemoved } from "./channelPins";
@@ -284,6 +293,7 @@ export async function initWebSocket(): Promise<void> {
               "edited_at",
               edited_at,
             );
+            syncCachedEdit(channel_id, message_id);
           }
         }
       }),
@@ -564,6 +574,27 @@ export async function initWebSocket(): Promise<void> {
       }),
     );
 
+    pending.push(
+      listen<{ channel_id: string; epoch: number }>(
+        "ws:channel_encryption_enabled",
+        (event) => {
+          handleChannelEncryptionEnabled(event.payload.channel_id, event.payload.epoch);
+          handleDMEncryptionEnabled(event.payload.channel_id, event.payload.epoch);
+        },
+      ),
+    );
+
+    pending.push(
+      listen<{ channel_id: string; message_ttl_seconds: number | null }>(
+        "ws:disappearing_messages_updated",
+        (event) => {
+          const { channel_id, message_ttl_seconds } = event.payload;
+          handleDisappearingMessagesUpdated(channel_id, message_ttl_seconds);
+          handleDMDisappearingMessagesUpdated(channel_id, message_ttl_seconds);
+        },
+      ),
+    );
+
     // Call events (Tauri → complete call support)
     // Note: These were partially implemented in earlier commits
     // This completes the full call event coverage
@@ -830,6 +861,27 @@ export async function initWebSocket(): Promise<void> {
       }),
     );
 
+    // E2EE key supply
+    pending.push(
+      listen<{ user_id: string; low_prekey_devices: DevicePrekeyStatus[] }>(
+        "ws:ready",
+        async (event) => {
+          await e2eeStore.replenishIfLow(event.payload.low_prekey_devices);
+          await processPendingToDeviceMessages();
+        },
+      ),
+    );
+    pending.push(
+      listen<DevicePrekeyStatus>("ws:prekeys_low", async (event) => {
+        await e2eeStore.replenishIfLow([event.payload]);
+      }),
+    );
+    pending.push(
+      listen<ToDeviceMessage>("ws:to_device_message", async (event) => {
+        await handleToDeviceMessage(event.payload);
+      }),
+    );
+
     // Bot command response events
     pending.push(
       listen<{
@@ -1003,6 +1055,7 @@ async function handleServerEvent(event: ServerEvent): Promise<void> {
             "edited_at",
             event.edited_at,
           );
+          syncCachedEdit(event.channel_id, event.message_id);
         }
       }
       break;
@@ -1224,6 +1277,18 @@ async function handleServerEvent(event: ServerEvent): Promise<void> {
       handleDMNameUpdated(event.channel_id, event.name);
       break;
 
+    // End-to-end encryption turned on for a channel or DM
+    case "channel_encryption_enabled":
+      handleChannelEncryptionEnabled(event.channel_id, event.epoch);
+      handleDMEncryptionEnabled(event.channel_id, event.epoch);
+      break;
+
+    // Disappearing-message timer changed for a channel or DM
+    case "disappearing_messages_updated":
+      handleDisappearingMessagesUpdated(event.channel_id, event.message_ttl_seconds);
+      handleDMDisappearingMessagesUpdated(event.channel_id, event.message_ttl_seconds);
+      break;
+
     // Guild channel read sync event
     case "channel_read":
       handleChannelReadEvent(event.channel_id);
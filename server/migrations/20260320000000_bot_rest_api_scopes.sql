@@ -0,0 +1,8 @@
+-- Bot REST API scopes
+--
+-- Each bot application declares the REST API scopes it may use with its bot
+-- token (e.g. `messages.read`, `roles.write`). Routes under `/api/bot` reject
+-- requests whose application lacks the required scope.
+
+ALTER TABLE bot_applications
+    ADD COLUMN IF NOT EXISTS scopes TEXT[] NOT NULL DEFAULT '{}';
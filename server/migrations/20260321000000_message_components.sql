@@ -0,0 +1,10 @@
+-- Interactive message components
+--
+-- Bots can attach action rows of buttons and select menus to messages.
+-- Clicks are relayed to the owning bot as `component.interaction` events.
+
+ALTER TABLE messages ADD COLUMN components JSONB;
+
+COMMENT ON COLUMN messages.components IS 'Action rows of bot message components (buttons, select menus)';
+
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'component.interaction';
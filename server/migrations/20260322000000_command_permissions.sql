@@ -0,0 +1,24 @@
+-- Per-guild slash command permissions
+--
+-- Bots declare the permissions a member needs by default; guild admins can
+-- disable commands or restrict them to specific roles and channels.
+
+ALTER TABLE slash_commands ADD COLUMN default_member_permissions BIGINT;
+
+COMMENT ON COLUMN slash_commands.default_member_permissions IS 'Guild permission bits a member needs to use the command (NULL = everyone)';
+
+-- Keyed by command name rather than ID because re-registering commands replaces their rows
+CREATE TABLE guild_command_permissions (
+    guild_id UUID NOT NULL REFERENCES guilds(id) ON DELETE CASCADE,
+    application_id UUID NOT NULL REFERENCES bot_applications(id) ON DELETE CASCADE,
+    command_name TEXT NOT NULL CHECK (char_length(command_name) >= 1 AND char_length(command_name) <= 32),
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    role_ids UUID[] NOT NULL DEFAULT '{}',
+    channel_ids UUID[] NOT NULL DEFAULT '{}',
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, application_id, command_name)
+);
+
+COMMENT ON COLUMN guild_command_permissions.role_ids IS 'Roles allowed to use the command (empty = default member permissions apply)';
+COMMENT ON COLUMN guild_command_permissions.channel_ids IS 'Channels the command can be used in (empty = all channels)';
@@ -0,0 +1,43 @@
+-- Additional bot gateway events and privileged intents
+--
+-- New event types for message edits/deletes, reactions, voice state, channel
+-- and role lifecycle, and presence. The `message_content` and `presence`
+-- intents are privileged: a guild administrator must approve them per
+-- installation before the bot receives message content or presence updates.
+
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'message.updated';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'message.deleted';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'reaction.added';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'reaction.removed';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'voice.state_updated';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'channel.created';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'channel.updated';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'channel.deleted';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'role.created';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'role.updated';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'role.deleted';
+ALTER TYPE webhook_event_type ADD VALUE IF NOT EXISTS 'presence.updated';
+
+ALTER TABLE guild_bot_installations
+    ADD COLUMN approved_intents TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN guild_bot_installations.approved_intents IS 'Privileged gateway intents approved by a guild administrator';
+
+-- Bots already receiving message content keep it: declare the new intent for
+-- applications using the messages intent or message webhooks, and approve it
+-- for their existing installations.
+UPDATE bot_applications ba
+SET gateway_intents = array_append(ba.gateway_intents, 'message_content')
+WHERE NOT ('message_content' = ANY(ba.gateway_intents))
+  AND ('messages' = ANY(ba.gateway_intents)
+       OR EXISTS (
+           SELECT 1 FROM webhooks w
+           WHERE w.application_id = ba.id
+             AND 'message.created' = ANY(w.subscribed_events::text[])
+       ));
+
+UPDATE guild_bot_installations gbi
+SET approved_intents = ARRAY['message_content']
+FROM bot_applications ba
+WHERE ba.id = gbi.application_id
+  AND 'message_content' = ANY(ba.gateway_intents);
@@ -0,0 +1,26 @@
+-- Server-enforced notification settings.
+--
+-- One row per (user, scope). Scopes nest thread -> channel -> category -> guild;
+-- the most specific row with a level wins, and a mute at any scope silences
+-- everything below it.
+
+CREATE TYPE notification_scope AS ENUM ('guild', 'category', 'channel', 'thread');
+CREATE TYPE notification_level AS ENUM ('all', 'mentions', 'nothing');
+
+CREATE TABLE notification_settings (
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    scope notification_scope NOT NULL,
+    scope_id UUID NOT NULL,
+    -- NULL inherits the level from the enclosing scope
+    level notification_level,
+    muted BOOLEAN NOT NULL DEFAULT FALSE,
+    -- NULL with muted = TRUE mutes indefinitely
+    muted_until TIMESTAMPTZ,
+    -- Guild scope only
+    suppress_everyone BOOLEAN NOT NULL DEFAULT FALSE,
+    suppress_roles BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, scope, scope_id)
+);
+
+CREATE INDEX idx_notification_settings_scope ON notification_settings(scope, scope_id);
@@ -0,0 +1,24 @@
+-- Persistent mentions index.
+--
+-- One row per (message, mentioned user), written when a message is created or
+-- edited. @everyone, @here and role mentions are fanned out to every member
+-- who can see the channel, so "recent mentions" and unread mention counts are
+-- plain index lookups.
+
+CREATE TYPE message_mention_kind AS ENUM ('user', 'role', 'everyone', 'here');
+
+CREATE TABLE message_mentions (
+    message_id UUID NOT NULL REFERENCES messages(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    channel_id UUID NOT NULL REFERENCES channels(id) ON DELETE CASCADE,
+    guild_id UUID REFERENCES guilds(id) ON DELETE CASCADE,
+    -- Most direct way the user was mentioned: user > role > everyone > here
+    kind message_mention_kind NOT NULL,
+    role_id UUID REFERENCES guild_roles(id) ON DELETE SET NULL,
+    -- Copied from the message so the inbox can paginate without a join
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (message_id, user_id)
+);
+
+CREATE INDEX idx_message_mentions_user ON message_mentions(user_id, created_at DESC, message_id DESC);
+CREATE INDEX idx_message_mentions_user_guild ON message_mentions(user_id, guild_id, created_at DESC);
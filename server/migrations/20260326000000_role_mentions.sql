@@ -0,0 +1,4 @@
+-- Role mentions: `<@&role_id>` pings every member holding the role.
+-- Only mentionable roles can be pinged by members without MENTION_EVERYONE.
+
+ALTER TABLE guild_roles ADD COLUMN mentionable BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,27 @@
+-- Push notification registrations.
+--
+-- One registration per auth session, so logging out (or revoking the session)
+-- stops pushes to that device. Token refresh moves the registration to the
+-- rotated session.
+
+CREATE TYPE push_provider AS ENUM ('web_push', 'unified_push');
+
+CREATE TABLE push_registrations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    session_id UUID NOT NULL UNIQUE REFERENCES sessions(id) ON DELETE CASCADE,
+    provider push_provider NOT NULL,
+    endpoint TEXT NOT NULL,
+    -- RFC 8291 subscription keys (base64url). Required for Web Push; optional
+    -- for UnifiedPush distributors that accept plaintext payloads.
+    p256dh TEXT,
+    auth_secret TEXT,
+    device_name VARCHAR(128),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_delivered_at TIMESTAMPTZ,
+    CONSTRAINT push_registrations_web_push_keys
+        CHECK (provider <> 'web_push' OR (p256dh IS NOT NULL AND auth_secret IS NOT NULL))
+);
+
+CREATE INDEX idx_push_registrations_user ON push_registrations(user_id);
@@ -0,0 +1,20 @@
+-- Opt-in email digests of missed mentions and DMs.
+--
+-- No row means digests are off. `window_start` marks where the next digest
+-- picks up: it advances every time a user is processed, whether or not an
+-- email went out, so nothing is reported twice.
+
+CREATE TYPE digest_frequency AS ENUM ('off', 'daily', 'weekly');
+
+CREATE TABLE email_digest_settings (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    frequency digest_frequency NOT NULL DEFAULT 'off',
+    window_start TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_email_digest_settings_due
+    ON email_digest_settings(window_start)
+    WHERE frequency <> 'off';
@@ -0,0 +1,35 @@
+-- Polls attached to messages.
+--
+-- A message carries at most one poll. Each user has a single ballot per poll
+-- (the primary key of message_poll_votes), listing the option indexes they
+-- picked. When a poll closes its tallies are frozen into final_counts and
+-- final_voters, so later ballot removals (e.g. account deletion) don't change
+-- the published result.
+
+CREATE TABLE message_polls (
+    message_id UUID PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    question TEXT NOT NULL CHECK (char_length(question) BETWEEN 1 AND 300),
+    options TEXT[] NOT NULL CHECK (cardinality(options) BETWEEN 2 AND 10),
+    allow_multiple BOOLEAN NOT NULL DEFAULT FALSE,
+    anonymous BOOLEAN NOT NULL DEFAULT FALSE,
+    expires_at TIMESTAMPTZ,
+    closed_at TIMESTAMPTZ,
+    -- Votes per option (same order as options), set when the poll closes
+    final_counts BIGINT[],
+    final_voters BIGINT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_message_polls_expiring ON message_polls(expires_at)
+    WHERE closed_at IS NULL AND expires_at IS NOT NULL;
+
+CREATE TABLE message_poll_votes (
+    message_id UUID NOT NULL REFERENCES message_polls(message_id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Indexes into message_polls.options
+    options SMALLINT[] NOT NULL CHECK (cardinality(options) >= 1),
+    voted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (message_id, user_id)
+);
+
+CREATE INDEX idx_message_poll_votes_user ON message_poll_votes(user_id, voted_at);
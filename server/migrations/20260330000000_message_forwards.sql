@@ -0,0 +1,23 @@
+-- Forwarded messages.
+--
+-- A forward is a new message in the target channel (holding the forwarder's
+-- optional comment) plus a snapshot of the original. The source columns are
+-- the link back; they are cleared when the original, its channel or its
+-- author go away, but the snapshot stays with the copy. content is NULL when
+-- the client re-encrypted the original into the new message itself.
+
+CREATE TABLE message_forwards (
+    message_id UUID PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    source_message_id UUID REFERENCES messages(id) ON DELETE SET NULL,
+    source_channel_id UUID REFERENCES channels(id) ON DELETE SET NULL,
+    source_author_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    content TEXT,
+    source_created_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_message_forwards_source ON message_forwards(source_message_id);
+
+-- Forwarded attachments share the original's S3 objects, so object cleanup
+-- has to look up other references by key.
+CREATE INDEX idx_attachments_s3_key ON file_attachments(s3_key);
@@ -0,0 +1,13 @@
+-- Slow mode: the minimum number of seconds a member must wait between
+-- messages. Set per channel, and optionally overridden per thread. 0 is off;
+-- the cap is six hours.
+
+ALTER TABLE channels
+    ADD COLUMN slowmode_seconds INTEGER NOT NULL DEFAULT 0
+        CHECK (slowmode_seconds BETWEEN 0 AND 21600);
+
+CREATE TABLE thread_slowmode (
+    parent_id UUID PRIMARY KEY REFERENCES messages(id) ON DELETE CASCADE,
+    slowmode_seconds INTEGER NOT NULL CHECK (slowmode_seconds BETWEEN 0 AND 21600),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,26 @@
+-- Guild stickers.
+--
+-- Larger images than custom emojis, sent as a message component that
+-- points at the stored image instead of an uploaded attachment. preview_key
+-- holds a static, downscaled WebP of the first frame for pickers and
+-- reduced-motion clients.
+
+CREATE TABLE guild_stickers (
+    id UUID PRIMARY KEY,
+    guild_id UUID NOT NULL REFERENCES guilds(id) ON DELETE CASCADE,
+    name VARCHAR(30) NOT NULL,
+    description VARCHAR(100),
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    format VARCHAR(8) NOT NULL CHECK (format IN ('png', 'apng', 'webp', 'gif')),
+    animated BOOLEAN NOT NULL DEFAULT FALSE,
+    s3_key TEXT NOT NULL,
+    preview_key TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    blurhash TEXT,
+    uploaded_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (guild_id, name)
+);
+
+CREATE INDEX idx_guild_stickers_guild ON guild_stickers(guild_id);
@@ -0,0 +1,24 @@
+-- To-device message queue.
+--
+-- Olm-encrypted payloads addressed to one device of one user, used to share
+-- Megolm room keys and verification messages. Rows are delivered over the
+-- WebSocket and kept until the recipient device acknowledges them or they
+-- expire, so devices that were offline still receive them on reconnect.
+
+CREATE TABLE to_device_messages (
+    id UUID PRIMARY KEY,
+    recipient_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    recipient_device_id UUID NOT NULL REFERENCES user_devices(id) ON DELETE CASCADE,
+    sender_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    sender_device_id UUID NOT NULL REFERENCES user_devices(id) ON DELETE CASCADE,
+    event_type VARCHAR(64) NOT NULL,
+    ciphertext TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_to_device_messages_recipient
+    ON to_device_messages(recipient_device_id, created_at);
+CREATE INDEX idx_to_device_messages_recipient_sender
+    ON to_device_messages(recipient_device_id, sender_user_id);
+CREATE INDEX idx_to_device_messages_expires ON to_device_messages(expires_at);
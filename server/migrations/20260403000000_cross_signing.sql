@@ -0,0 +1,46 @@
+-- Cross-Signing
+--
+-- Each user publishes a master key plus self-signing and user-signing keys
+-- signed by it. The self-signing key signs the user's own devices; the
+-- user-signing key signs other users' master keys after verification.
+
+CREATE TABLE cross_signing_keys (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    master_key TEXT NOT NULL,
+    self_signing_key TEXT NOT NULL,
+    user_signing_key TEXT NOT NULL,
+    self_signing_signature TEXT NOT NULL,
+    user_signing_signature TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT cross_signing_key_length CHECK (
+        length(master_key) <= 64
+        AND length(self_signing_key) <= 64
+        AND length(user_signing_key) <= 64
+    ),
+    CONSTRAINT cross_signing_signature_length CHECK (
+        length(self_signing_signature) <= 128
+        AND length(user_signing_signature) <= 128
+    )
+);
+
+-- Self-signing key signature over the device's identity keys.
+-- A signed device is marked verified.
+ALTER TABLE user_devices
+    ADD COLUMN cross_signing_signature TEXT,
+    ADD CONSTRAINT device_cross_signing_signature_length
+        CHECK (cross_signing_signature IS NULL OR length(cross_signing_signature) <= 128);
+
+-- User-signing key signatures over other users' master keys.
+-- Only visible to the signer, so verification status doesn't leak.
+CREATE TABLE user_signatures (
+    signer_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    target_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    master_key TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (signer_id, target_id),
+    CONSTRAINT user_signature_not_self CHECK (signer_id <> target_id),
+    CONSTRAINT user_signature_length CHECK (length(master_key) <= 64 AND length(signature) <= 128)
+);
+
+CREATE INDEX idx_user_signatures_target ON user_signatures(target_id);
@@ -0,0 +1,10 @@
+-- E2EE Device Management
+--
+-- Link each E2EE device to the auth session that last uploaded its keys, so
+-- deleting a lost device also logs that session out.
+
+ALTER TABLE user_devices
+    ADD COLUMN session_id UUID REFERENCES sessions(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_user_devices_session ON user_devices(session_id)
+    WHERE session_id IS NOT NULL;
@@ -0,0 +1,20 @@
+-- E2EE Fallback Keys
+--
+-- Each device publishes one signed fallback key next to its one-time
+-- prekeys. It is handed out when the prekey pool is empty, so sessions can
+-- still be established with offline devices, and replaced by the device once
+-- `used_at` is set.
+
+CREATE TABLE fallback_keys (
+    device_id UUID PRIMARY KEY REFERENCES user_devices(id) ON DELETE CASCADE,
+    key_id TEXT NOT NULL,
+    public_key TEXT NOT NULL,
+    signature TEXT NOT NULL,
+    used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CONSTRAINT fallback_key_length CHECK (
+        length(key_id) <= 64
+        AND length(public_key) <= 64
+        AND length(signature) <= 128
+    )
+);
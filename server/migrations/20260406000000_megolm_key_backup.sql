@@ -0,0 +1,38 @@
+-- Per-session Megolm Key Backup
+--
+-- Each user has at most one backup version. The client publishes the
+-- version's `auth_data` (salt and key check for deriving the backup key from
+-- the recovery key) and then uploads every inbound Megolm session as its own
+-- encrypted entry, so new sessions are backed up incrementally. Creating a new
+-- version (after rotating the recovery key) drops the previous one and all of
+-- its sessions. Version numbers come from a sequence so a stale client can
+-- never write into a newer version. `etag` changes whenever an entry is added
+-- or replaced.
+
+CREATE SEQUENCE megolm_backup_version_seq;
+
+CREATE TABLE megolm_backup_versions (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    version BIGINT NOT NULL DEFAULT nextval('megolm_backup_version_seq'),
+    algorithm TEXT NOT NULL,
+    auth_data JSONB NOT NULL,
+    etag BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (user_id, version)
+);
+
+CREATE TABLE megolm_backup_sessions (
+    user_id UUID NOT NULL,
+    version BIGINT NOT NULL,
+    room_id TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    first_message_index BIGINT NOT NULL,
+    forwarded_count INT NOT NULL DEFAULT 0,
+    is_verified BOOLEAN NOT NULL DEFAULT FALSE,
+    nonce TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, version, room_id, session_id),
+    FOREIGN KEY (user_id, version)
+        REFERENCES megolm_backup_versions(user_id, version) ON DELETE CASCADE
+);
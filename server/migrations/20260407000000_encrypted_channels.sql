@@ -0,0 +1,74 @@
+-- End-to-end encrypted channels
+--
+-- Group DMs and private guild channels can be switched to Megolm encryption.
+-- Once on, encryption can't be turned off again and the server only accepts
+-- encrypted messages in the channel. `encryption_epoch` is bumped whenever
+-- the set of people who can read the channel may have changed; senders must
+-- name the current epoch, so a session shared before a membership change is
+-- rotated before it is used again.
+
+ALTER TABLE channels
+    ADD COLUMN encrypted BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN encryption_epoch BIGINT NOT NULL DEFAULT 0;
+
+CREATE OR REPLACE FUNCTION prevent_channel_decryption() RETURNS trigger AS $$
+BEGIN
+  IF OLD.encrypted AND NOT NEW.encrypted THEN
+    RAISE EXCEPTION 'encryption cannot be disabled for channel %', OLD.id;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_channel_encryption_one_way
+  BEFORE UPDATE OF encrypted ON channels
+  FOR EACH ROW EXECUTE FUNCTION prevent_channel_decryption();
+
+-- DM participants joining or leaving
+CREATE OR REPLACE FUNCTION bump_dm_encryption_epoch() RETURNS trigger AS $$
+BEGIN
+  UPDATE channels SET encryption_epoch = encryption_epoch + 1
+  WHERE id = COALESCE(NEW.channel_id, OLD.channel_id) AND encrypted;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_dm_participants_encryption_epoch
+  AFTER INSERT OR DELETE ON dm_participants
+  FOR EACH ROW EXECUTE FUNCTION bump_dm_encryption_epoch();
+
+-- Guild members joining or leaving, role changes and role permission edits
+-- can all change who sees a private channel
+CREATE OR REPLACE FUNCTION bump_guild_encryption_epoch() RETURNS trigger AS $$
+BEGIN
+  UPDATE channels SET encryption_epoch = encryption_epoch + 1
+  WHERE guild_id = COALESCE(NEW.guild_id, OLD.guild_id) AND encrypted;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_guild_members_encryption_epoch
+  AFTER INSERT OR DELETE ON guild_members
+  FOR EACH ROW EXECUTE FUNCTION bump_guild_encryption_epoch();
+
+CREATE TRIGGER trg_guild_member_roles_encryption_epoch
+  AFTER INSERT OR DELETE ON guild_member_roles
+  FOR EACH ROW EXECUTE FUNCTION bump_guild_encryption_epoch();
+
+CREATE TRIGGER trg_guild_roles_encryption_epoch
+  AFTER UPDATE OF permissions ON guild_roles
+  FOR EACH ROW WHEN (OLD.permissions IS DISTINCT FROM NEW.permissions)
+  EXECUTE FUNCTION bump_guild_encryption_epoch();
+
+-- Channel permission overrides
+CREATE OR REPLACE FUNCTION bump_channel_encryption_epoch() RETURNS trigger AS $$
+BEGIN
+  UPDATE channels SET encryption_epoch = encryption_epoch + 1
+  WHERE id = COALESCE(NEW.channel_id, OLD.channel_id) AND encrypted;
+  RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_channel_overrides_encryption_epoch
+  AFTER INSERT OR UPDATE OR DELETE ON channel_overrides
+  FOR EACH ROW EXECUTE FUNCTION bump_channel_encryption_epoch();
@@ -0,0 +1,9 @@
+-- Encrypted attachments
+--
+-- Files shared in end-to-end encrypted conversations are encrypted on the
+-- client. The server stores the ciphertext as-is (no MIME sniffing, no
+-- thumbnails); an encrypted thumbnail uploaded by the client may sit in
+-- `thumbnail_s3_key`.
+
+ALTER TABLE file_attachments
+    ADD COLUMN encrypted BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,8 @@
+-- Room key requests
+--
+-- Devices missing a Megolm session ask other devices for it with
+-- `kaiku.room_key_request` to-device messages. Each sending device may only
+-- have a limited number of these waiting, counted with this index.
+
+CREATE INDEX idx_to_device_messages_sender
+    ON to_device_messages(sender_device_id, event_type);
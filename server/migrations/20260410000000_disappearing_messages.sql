@@ -0,0 +1,32 @@
+-- Disappearing messages
+--
+-- A channel may set a lifetime for new messages, between 5 minutes and
+-- 4 weeks. Messages posted while it is set get an `expires_at`, stamped by
+-- a trigger so every insert path is covered; changing the timer only
+-- affects later messages. The expiry worker hard-deletes messages once
+-- `expires_at` has passed, together with their attachments.
+
+ALTER TABLE channels
+    ADD COLUMN message_ttl_seconds INTEGER
+        CHECK (message_ttl_seconds BETWEEN 300 AND 2419200);
+
+ALTER TABLE messages ADD COLUMN expires_at TIMESTAMPTZ;
+
+CREATE INDEX idx_messages_expires_at ON messages(expires_at)
+    WHERE expires_at IS NOT NULL;
+
+CREATE OR REPLACE FUNCTION set_message_expiry() RETURNS trigger AS $$
+BEGIN
+  IF NEW.expires_at IS NULL THEN
+    SELECT NEW.created_at + make_interval(secs => message_ttl_seconds)
+      INTO NEW.expires_at
+      FROM channels
+     WHERE id = NEW.channel_id AND message_ttl_seconds IS NOT NULL;
+  END IF;
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_messages_expiry
+  BEFORE INSERT ON messages
+  FOR EACH ROW EXECUTE FUNCTION set_message_expiry();
@@ -0,0 +1,20 @@
+-- Per-guild approval of bot REST API scopes
+--
+-- An application's scopes only apply in a guild once approved for that
+-- installation. Installing a bot approves the scopes it requests at that
+-- time; scopes added later must be approved by a guild administrator.
+-- Bots can also hold roles, which set their rank for role management.
+
+ALTER TABLE guild_bot_installations
+    ADD COLUMN approved_scopes TEXT[] NOT NULL DEFAULT '{}';
+
+COMMENT ON COLUMN guild_bot_installations.approved_scopes IS 'Bot REST API scopes approved for this guild';
+
+-- Existing installations keep their scopes, except `roles.write`, which may
+-- have been granted after install and must be approved again.
+UPDATE guild_bot_installations gbi
+SET approved_scopes = ARRAY(
+    SELECT s FROM unnest(ba.scopes) AS s WHERE s <> 'roles.write'
+)
+FROM bot_applications ba
+WHERE ba.id = gbi.application_id;
@@ -26,6 +26,7 @@ struct ApplicationRow {
     bot_user_id: Option<Uuid>,
     public: bool,
     gateway_intents: Vec<String>,
+    scopes: Vec<String>,
     created_at: DateTime<Utc>,
 }
 
@@ -38,6 +39,7 @@ impl From<ApplicationRow> for ApplicationResponse {
             bot_user_id: r.bot_user_id,
             public: r.public,
             gateway_intents: r.gateway_intents,
+            scopes: r.scopes,
             created_at: r.created_at.to_rfc3339(),
         }
     }
@@ -105,6 +107,8 @@ pub struct ApplicationResponse {
     pub public: bool,
     /// Gateway intents for event filtering.
     pub gateway_intents: Vec<String>,
+    /// REST API scopes granted to the bot token.
+    pub scopes: Vec<String>,
     /// When the application was created.
     pub created_at: String,
 }
@@ -154,7 +158,7 @@ pub async fn create_application(
         r"
         INSERT INTO bot_applications (owner_id, name, description)
         VALUES ($1, $2, $3)
-        RETURNING id, name, description, bot_user_id, public, gateway_intents, created_at
+        RETURNING id, name, description, bot_user_id, public, gateway_intents, scopes, created_at
         ",
     )
     .bind(claims.id)
@@ -184,7 +188,7 @@ pub async fn list_applications(
 ) -> Result<Json<Vec<ApplicationResponse>>, (StatusCode, String)> {
     let apps: Vec<ApplicationRow> = sqlx::query_as(
         r"
-        SELECT id, name, description, bot_user_id, public, gateway_intents, created_at
+        SELECT id, name, description, bot_user_id, public, gateway_intents, scopes, created_at
         FROM bot_applications
         WHERE owner_id = $1
         ORDER BY created_at DESC
@@ -225,13 +229,14 @@ pub async fn get_application(
         bot_user_id: Option<Uuid>,
         public: bool,
         gateway_intents: Vec<String>,
+        scopes: Vec<String>,
         created_at: DateTime<Utc>,
         owner_id: Uuid,
     }
 
     let app: AppWithOwner = sqlx::query_as(
         r"
-        SELECT id, name, description, bot_user_id, public, gateway_intents, created_at, owner_id
+        SELECT id, name, description, bot_user_id, public, gateway_intents, scopes, created_at, owner_id
         FROM bot_applications
         WHERE id = $1
         ",
@@ -254,6 +259,7 @@ pub async fn get_application(
         bot_user_id: app.bot_user_id,
         public: app.public,
         gateway_intents: app.gateway_intents,
+        scopes: app.scopes,
         created_at: app.created_at.to_rfc3339(),
     }))
 }
@@ -560,7 +566,7 @@ pub async fn update_gateway_intents(
         UPDATE bot_applications
         SET gateway_intents = $1, updated_at = NOW()
         WHERE id = $2
-        RETURNING id, name, description, bot_user_id, public, gateway_intents, created_at
+        RETURNING id, name, description, bot_user_id, public, gateway_intents, scopes, created_at
         ",
     )
     .bind(&req.intents)
@@ -571,3 +577,83 @@ pub async fn update_gateway_intents(
 
     Ok(Json(updated.into()))
 }
+
+/// Request to update bot REST API scopes.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct UpdateScopesRequest {
+    /// List of scope names (e.g., `["messages.read", "messages.write"]`).
+    pub scopes: Vec<String>,
+}
+
+/// Update the REST API scopes granted to an application's bot token.
+///
+/// Guilds that already installed the bot must approve added scopes before
+/// they apply there.
+/// PUT /api/applications/{id}/scopes
+#[utoipa::path(
+    put,
+    path = "/api/applications/{id}/scopes",
+    tag = "bots",
+    params(
+        ("id" = Uuid, Path, description = "Application ID"),
+    ),
+    request_body = UpdateScopesRequest,
+    responses(
+        (status = 200, body = ApplicationResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[instrument(skip(pool, claims))]
+pub async fn update_bot_scopes(
+    State(pool): State<PgPool>,
+    Path(app_id): Path<Uuid>,
+    claims: AuthUser,
+    Json(req): Json<UpdateScopesRequest>,
+) -> Result<Json<ApplicationResponse>, (StatusCode, String)> {
+    // Validate scope names
+    for scope in &req.scopes {
+        if crate::bot_api::scopes::BotScope::parse_str(scope).is_none() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Invalid scope: '{}'. Valid scopes: {}",
+                    scope,
+                    crate::bot_api::scopes::BotScope::ALL.join(", ")
+                ),
+            ));
+        }
+    }
+
+    // Check ownership
+    let row: Option<(Uuid,)> =
+        sqlx::query_as("SELECT owner_id FROM bot_applications WHERE id = $1")
+            .bind(app_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(BotError::Database)?;
+
+    let (owner_id,) = row.ok_or_else(|| BotError::NotFound)?;
+    if owner_id != claims.id {
+        return Err(BotError::Forbidden.into());
+    }
+
+    let mut scopes = req.scopes;
+    scopes.sort();
+    scopes.dedup();
+
+    let updated: ApplicationRow = sqlx::query_as(
+        r"
+        UPDATE bot_applications
+        SET scopes = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING id, name, description, bot_user_id, public, gateway_intents, scopes, created_at
+        ",
+    )
+    .bind(&scopes)
+    .bind(app_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(BotError::Database)?;
+
+    Ok(Json(updated.into()))
+}
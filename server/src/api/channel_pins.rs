@@ -147,7 +147,8 @@ pub async fn list_channel_pins(
 
     // Fetch all pinned messages
     let messages = sqlx::query_as::<_, db::Message>(
-        "SELECT * FROM messages WHERE id = ANY($1) AND deleted_at IS NULL",
+        "SELECT * FROM messages WHERE id = ANY($1) AND deleted_at IS NULL
+           AND (expires_at IS NULL OR expires_at > NOW())",
     )
     .bind(&message_ids)
     .fetch_all(&state.db)
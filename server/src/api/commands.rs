@@ -14,6 +14,7 @@ use tracing::instrument;
 use uuid::Uuid;
 
 use crate::auth::AuthUser;
+use crate::permissions::GuildPermissions;
 
 /// Errors that can occur during command operations.
 #[derive(Error, Debug)]
@@ -39,6 +40,9 @@ pub enum CommandError {
     /// Duplicate command name in a single registration batch.
     #[error("Duplicate command name in batch: {0}")]
     DuplicateName(String),
+    /// Invalid option schema.
+    #[error("Invalid command options: {0}")]
+    InvalidOptions(String),
 }
 
 impl From<CommandError> for (StatusCode, String) {
@@ -57,18 +61,30 @@ impl From<CommandError> for (StatusCode, String) {
             CommandError::InvalidName => (StatusCode::BAD_REQUEST, err.to_string()),
             CommandError::InvalidDescription => (StatusCode::BAD_REQUEST, err.to_string()),
             CommandError::DuplicateName(_) => (StatusCode::CONFLICT, err.to_string()),
+            CommandError::InvalidOptions(_) => (StatusCode::BAD_REQUEST, err.to_string()),
         }
     }
 }
 
+/// Maximum number of options per command, subcommand or subcommand group.
+pub const MAX_OPTIONS: usize = 25;
+
+/// Maximum number of static choices (or autocomplete suggestions) per option.
+pub const MAX_CHOICES: usize = 25;
+
+/// Maximum length of a string option value.
+const MAX_STRING_OPTION_LENGTH: u16 = 4000;
+
 /// Command option type.
-#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum CommandOptionType {
     /// String input.
     String,
     /// Integer input.
     Integer,
+    /// Floating point input.
+    Number,
     /// Boolean input.
     Boolean,
     /// User mention.
@@ -77,6 +93,57 @@ pub enum CommandOptionType {
     Channel,
     /// Role mention.
     Role,
+    /// File attachment reference.
+    Attachment,
+    /// Nested subcommand with its own options.
+    Subcommand,
+    /// Group of subcommands.
+    #[serde(rename = "subcommand_group")]
+    SubcommandGroup,
+}
+
+impl CommandOptionType {
+    /// Whether this option nests other options rather than taking a value.
+    #[must_use]
+    pub const fn is_subcommand(self) -> bool {
+        matches!(self, Self::Subcommand | Self::SubcommandGroup)
+    }
+
+    /// Whether options of this type may declare choices or use autocomplete.
+    const fn supports_choices(self) -> bool {
+        matches!(self, Self::String | Self::Integer | Self::Number)
+    }
+}
+
+/// Value of a predefined option choice.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(untagged)]
+pub enum CommandChoiceValue {
+    /// String value.
+    String(String),
+    /// Integer value.
+    Integer(i64),
+    /// Floating point value.
+    Number(f64),
+}
+
+impl CommandChoiceValue {
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Self::String(s) => serde_json::Value::from(s.as_str()),
+            Self::Integer(i) => serde_json::Value::from(*i),
+            Self::Number(n) => serde_json::Value::from(*n),
+        }
+    }
+}
+
+/// Predefined choice for a string, integer or number option.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CommandOptionChoice {
+    /// Display name (1-100 characters).
+    pub name: String,
+    /// Value sent to the bot when this choice is picked.
+    pub value: CommandChoiceValue,
 }
 
 /// Command option definition.
@@ -90,7 +157,30 @@ pub struct CommandOption {
     #[serde(rename = "type")]
     pub option_type: CommandOptionType,
     /// Whether this option is required.
+    #[serde(default)]
     pub required: bool,
+    /// Predefined choices (string, integer and number options only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<CommandOptionChoice>,
+    /// Minimum value (integer and number options only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<f64>,
+    /// Maximum value (integer and number options only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<f64>,
+    /// Minimum length (string options only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_length: Option<u16>,
+    /// Maximum length (string options only, at most 4000).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u16>,
+    /// Whether the bot suggests values while the user types.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub autocomplete: bool,
+    /// Nested options (subcommands and subcommand groups only).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(no_recursion)]
+    pub options: Vec<Self>,
 }
 
 /// Request body for registering commands.
@@ -110,6 +200,10 @@ pub struct RegisterCommandData {
     /// Command options/parameters.
     #[serde(default)]
     pub options: Vec<CommandOption>,
+    /// Guild permission bits a member needs to use the command by default
+    /// (omit to allow everyone). Guild admins can override this per guild.
+    #[serde(default)]
+    pub default_member_permissions: Option<u64>,
 }
 
 /// Response for a slash command.
@@ -127,6 +221,8 @@ pub struct CommandResponse {
     pub description: String,
     /// Command options.
     pub options: Vec<CommandOption>,
+    /// Guild permission bits a member needs to use the command by default.
+    pub default_member_permissions: Option<u64>,
     /// When the command was created.
     pub created_at: String,
 }
@@ -161,6 +257,413 @@ const fn validate_command_description(desc: &str) -> Result<(), CommandError> {
     Ok(())
 }
 
+/// Validate the option schema of a command.
+///
+/// A command either takes value options directly or is split into subcommands
+/// and subcommand groups (groups only at the top level, containing only
+/// subcommands). Required options must precede optional ones because
+/// invocations are parsed positionally.
+pub fn validate_command_options(options: &[CommandOption]) -> Result<(), CommandError> {
+    validate_option_level(options, 0)
+}
+
+fn validate_option_level(options: &[CommandOption], depth: usize) -> Result<(), CommandError> {
+    let invalid = |msg: String| Err(CommandError::InvalidOptions(msg));
+
+    if options.len() > MAX_OPTIONS {
+        return invalid(format!("at most {MAX_OPTIONS} options are allowed"));
+    }
+
+    let nested = options
+        .iter()
+        .filter(|o| o.option_type.is_subcommand())
+        .count();
+    if nested > 0 && nested != options.len() {
+        return invalid("subcommands cannot be mixed with value options".to_string());
+    }
+
+    let mut seen_names = HashSet::with_capacity(options.len());
+    let mut seen_optional = false;
+    for opt in options {
+        if validate_command_name(&opt.name).is_err() {
+            return invalid(format!("invalid option name '{}'", opt.name));
+        }
+        if validate_command_description(&opt.description).is_err() {
+            return invalid(format!(
+                "description of '{}' must be 1-100 characters",
+                opt.name
+            ));
+        }
+        if !seen_names.insert(opt.name.as_str()) {
+            return invalid(format!("duplicate option name '{}'", opt.name));
+        }
+
+        match opt.option_type {
+            CommandOptionType::SubcommandGroup | CommandOptionType::Subcommand => {
+                validate_subcommand(opt, depth)?;
+                continue;
+            }
+            _ if !opt.options.is_empty() => {
+                return invalid(format!("'{}' cannot have nested options", opt.name));
+            }
+            _ => {}
+        }
+
+        if opt.required && seen_optional {
+            return invalid(format!(
+                "required option '{}' must come before optional options",
+                opt.name
+            ));
+        }
+        seen_optional |= !opt.required;
+
+        validate_option_constraints(opt)?;
+    }
+
+    Ok(())
+}
+
+fn validate_subcommand(opt: &CommandOption, depth: usize) -> Result<(), CommandError> {
+    let invalid = |msg: String| Err(CommandError::InvalidOptions(msg));
+
+    let is_group = opt.option_type == CommandOptionType::SubcommandGroup;
+    // Groups only at the top level; subcommands at the top level or in a group
+    if (is_group && depth > 0) || depth > 1 {
+        return invalid(format!("'{}' is nested too deeply", opt.name));
+    }
+    if opt.required
+        || opt.autocomplete
+        || !opt.choices.is_empty()
+        || opt.min_value.is_some()
+        || opt.max_value.is_some()
+        || opt.min_length.is_some()
+        || opt.max_length.is_some()
+    {
+        return invalid(format!(
+            "'{}' is a subcommand and cannot be required or constrained",
+            opt.name
+        ));
+    }
+    if is_group {
+        if opt.options.is_empty()
+            || opt
+                .options
+                .iter()
+                .any(|o| o.option_type != CommandOptionType::Subcommand)
+        {
+            return invalid(format!(
+                "subcommand group '{}' must contain only subcommands",
+                opt.name
+            ));
+        }
+    } else if opt.options.iter().any(|o| o.option_type.is_subcommand()) {
+        return invalid(format!(
+            "subcommand '{}' cannot contain subcommands",
+            opt.name
+        ));
+    }
+
+    validate_option_level(&opt.options, depth + 1)
+}
+
+fn validate_option_constraints(opt: &CommandOption) -> Result<(), CommandError> {
+    let invalid = |msg: String| Err(CommandError::InvalidOptions(msg));
+    let name = &opt.name;
+    let kind = opt.option_type;
+
+    if (!opt.choices.is_empty() || opt.autocomplete) && !kind.supports_choices() {
+        return invalid(format!(
+            "'{name}' must be a string, integer or number option to use choices or autocomplete"
+        ));
+    }
+    if !opt.choices.is_empty() && opt.autocomplete {
+        return invalid(format!("'{name}' cannot use both choices and autocomplete"));
+    }
+    if opt.choices.len() > MAX_CHOICES {
+        return invalid(format!("'{name}' has more than {MAX_CHOICES} choices"));
+    }
+    for choice in &opt.choices {
+        validate_choice(choice, kind).map_err(CommandError::InvalidOptions)?;
+    }
+
+    let numeric = matches!(kind, CommandOptionType::Integer | CommandOptionType::Number);
+    if (opt.min_value.is_some() || opt.max_value.is_some()) && !numeric {
+        return invalid(format!(
+            "'{name}' must be an integer or number option to use min_value/max_value"
+        ));
+    }
+    if let (Some(min), Some(max)) = (opt.min_value, opt.max_value) {
+        if min > max {
+            return invalid(format!("'{name}' has min_value greater than max_value"));
+        }
+    }
+
+    if (opt.min_length.is_some() || opt.max_length.is_some()) && kind != CommandOptionType::String {
+        return invalid(format!(
+            "'{name}' must be a string option to use min_length/max_length"
+        ));
+    }
+    if opt.min_length.unwrap_or(0) > MAX_STRING_OPTION_LENGTH
+        || opt
+            .max_length
+            .is_some_and(|max| max == 0 || max > MAX_STRING_OPTION_LENGTH)
+    {
+        return invalid(format!(
+            "'{name}' length limits must be within 1-{MAX_STRING_OPTION_LENGTH}"
+        ));
+    }
+    if let (Some(min), Some(max)) = (opt.min_length, opt.max_length) {
+        if min > max {
+            return invalid(format!("'{name}' has min_length greater than max_length"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate a choice against the type of the option it belongs to.
+///
+/// Also used for autocomplete suggestions returned by bots.
+pub fn validate_choice(
+    choice: &CommandOptionChoice,
+    option_type: CommandOptionType,
+) -> Result<(), String> {
+    let name_len = choice.name.chars().count();
+    if name_len == 0 || name_len > 100 {
+        return Err("Choice names must be 1-100 characters".to_string());
+    }
+    let matches_type = match (&choice.value, option_type) {
+        (CommandChoiceValue::String(s), CommandOptionType::String) => {
+            !s.is_empty() && s.chars().count() <= usize::from(MAX_STRING_OPTION_LENGTH)
+        }
+        (
+            CommandChoiceValue::Integer(_),
+            CommandOptionType::Integer | CommandOptionType::Number,
+        ) => true,
+        (CommandChoiceValue::Number(n), CommandOptionType::Number) => n.is_finite(),
+        _ => false,
+    };
+    if !matches_type {
+        return Err(format!(
+            "Choice '{}' has a value that does not match the option type",
+            choice.name
+        ));
+    }
+    Ok(())
+}
+
+/// Options parsed from a slash command invocation.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ResolvedInvocation {
+    /// Invoked subcommand group, if the command has groups.
+    pub subcommand_group: Option<String>,
+    /// Invoked subcommand, if the command has subcommands.
+    pub subcommand: Option<String>,
+    /// Option values keyed by option name, converted to their declared types.
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Find the options that apply to an invocation path.
+///
+/// Returns the value options of the selected subcommand (or of the command
+/// itself when it has no subcommands).
+pub fn select_subcommand<'a>(
+    options: &'a [CommandOption],
+    group: Option<&str>,
+    subcommand: Option<&str>,
+) -> Option<&'a [CommandOption]> {
+    let find = |level: &'a [CommandOption], name: Option<&str>, kind: CommandOptionType| {
+        level
+            .iter()
+            .find(|o| o.option_type == kind && Some(o.name.as_str()) == name)
+    };
+
+    let mut level = options;
+    if level
+        .iter()
+        .any(|o| o.option_type == CommandOptionType::SubcommandGroup)
+    {
+        if let Some(group) = find(level, group, CommandOptionType::SubcommandGroup) {
+            level = &group.options;
+        } else if group.is_some() {
+            return None;
+        }
+    } else if group.is_some() {
+        return None;
+    }
+
+    if level.iter().any(|o| o.option_type.is_subcommand()) {
+        return find(level, subcommand, CommandOptionType::Subcommand)
+            .map(|o| o.options.as_slice());
+    }
+    subcommand.is_none().then_some(level)
+}
+
+/// Resolve the arguments typed after a command name against its registered options.
+///
+/// Leading arguments select the subcommand group and subcommand, the remaining
+/// ones are assigned positionally to value options. The last string option
+/// takes the rest of the input. Commands registered without options accept
+/// (and ignore) free-form trailing text.
+pub fn resolve_invocation(
+    options: &[CommandOption],
+    args: &[&str],
+) -> Result<ResolvedInvocation, String> {
+    let mut resolved = ResolvedInvocation::default();
+    let mut level = options;
+    let mut rest = args;
+
+    for kind in [
+        CommandOptionType::SubcommandGroup,
+        CommandOptionType::Subcommand,
+    ] {
+        if !level.iter().any(|o| o.option_type == kind) {
+            continue;
+        }
+        let available = || {
+            level
+                .iter()
+                .map(|o| o.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let Some((first, tail)) = rest.split_first() else {
+            return Err(format!(
+                "Missing subcommand (expected one of: {})",
+                available()
+            ));
+        };
+        let name = first.to_lowercase();
+        let selected = level
+            .iter()
+            .find(|o| o.option_type.is_subcommand() && o.name == name)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown subcommand '{name}' (expected one of: {})",
+                    available()
+                )
+            })?;
+        if selected.option_type == CommandOptionType::SubcommandGroup {
+            resolved.subcommand_group = Some(name);
+        } else {
+            resolved.subcommand = Some(name);
+        }
+        level = &selected.options;
+        rest = tail;
+    }
+
+    if level.is_empty() {
+        return Ok(resolved);
+    }
+
+    for (idx, opt) in level.iter().enumerate() {
+        let Some(arg) = rest.get(idx) else {
+            if opt.required {
+                return Err(format!("Missing required option '{}'", opt.name));
+            }
+            continue;
+        };
+        let raw = if opt.option_type == CommandOptionType::String && idx + 1 == level.len() {
+            rest[idx..].join(" ")
+        } else {
+            (*arg).to_string()
+        };
+        let value = convert_option_value(opt, &raw)?;
+        resolved.options.insert(opt.name.clone(), value);
+    }
+
+    let absorbs_rest = level
+        .last()
+        .is_some_and(|o| o.option_type == CommandOptionType::String);
+    if rest.len() > level.len() && !absorbs_rest {
+        return Err(format!(
+            "Too many arguments (expected at most {})",
+            level.len()
+        ));
+    }
+
+    Ok(resolved)
+}
+
+/// Convert a raw argument to the option's type and check its constraints.
+fn convert_option_value(opt: &CommandOption, raw: &str) -> Result<serde_json::Value, String> {
+    let name = &opt.name;
+
+    if !opt.choices.is_empty() {
+        // Accept either the choice value or its display name
+        return opt
+            .choices
+            .iter()
+            .find(|c| {
+                c.name.eq_ignore_ascii_case(raw)
+                    || match &c.value {
+                        CommandChoiceValue::String(s) => s == raw,
+                        CommandChoiceValue::Integer(i) => raw.parse::<i64>().ok() == Some(*i),
+                        CommandChoiceValue::Number(n) => raw.parse::<f64>().ok() == Some(*n),
+                    }
+            })
+            .map(|c| c.value.to_json())
+            .ok_or_else(|| {
+                let names: Vec<&str> = opt.choices.iter().map(|c| c.name.as_str()).collect();
+                format!(
+                    "Invalid value for '{name}' (expected one of: {})",
+                    names.join(", ")
+                )
+            });
+    }
+
+    let check_range = |value: f64| {
+        if opt.min_value.is_some_and(|min| value < min)
+            || opt.max_value.is_some_and(|max| value > max)
+        {
+            return Err(format!("Value for '{name}' is out of range"));
+        }
+        Ok(())
+    };
+
+    match opt.option_type {
+        CommandOptionType::String => {
+            let len = raw.chars().count();
+            if opt.min_length.is_some_and(|min| len < usize::from(min))
+                || len > usize::from(opt.max_length.unwrap_or(MAX_STRING_OPTION_LENGTH))
+            {
+                return Err(format!("Value for '{name}' has an invalid length"));
+            }
+            Ok(serde_json::Value::from(raw))
+        }
+        CommandOptionType::Integer => {
+            let value: i64 = raw
+                .parse()
+                .map_err(|_| format!("Option '{name}' must be an integer"))?;
+            #[allow(clippy::cast_precision_loss)]
+            check_range(value as f64)?;
+            Ok(serde_json::Value::from(value))
+        }
+        CommandOptionType::Number => {
+            let value: f64 = raw
+                .parse()
+                .ok()
+                .filter(|v: &f64| v.is_finite())
+                .ok_or_else(|| format!("Option '{name}' must be a number"))?;
+            check_range(value)?;
+            Ok(serde_json::Value::from(value))
+        }
+        CommandOptionType::Boolean => match raw.to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Ok(serde_json::Value::Bool(true)),
+            "false" | "no" | "off" | "0" => Ok(serde_json::Value::Bool(false)),
+            _ => Err(format!("Option '{name}' must be true or false")),
+        },
+        // Mentions and attachment references are resolved by the bot
+        CommandOptionType::User
+        | CommandOptionType::Channel
+        | CommandOptionType::Role
+        | CommandOptionType::Attachment => Ok(serde_json::Value::from(raw)),
+        CommandOptionType::Subcommand | CommandOptionType::SubcommandGroup => {
+            Err(format!("'{name}' is not a value option"))
+        }
+    }
+}
+
 /// Register or update slash commands for an application.
 /// This will replace all existing commands for the scope (guild or global).
 #[utoipa::path(
@@ -207,6 +710,7 @@ pub async fn register_commands(
     for cmd in &req.commands {
         validate_command_name(&cmd.name)?;
         validate_command_description(&cmd.description)?;
+        validate_command_options(&cmd.options)?;
     }
 
     // Check for duplicate names within the batch
@@ -245,17 +749,23 @@ pub async fn register_commands(
             )
         })?;
 
+        let default_member_permissions = cmd
+            .default_member_permissions
+            .map(GuildPermissions::from_bits_truncate);
+
         let result = sqlx::query!(
             r#"
-            INSERT INTO slash_commands (application_id, guild_id, name, description, options)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO slash_commands
+                (application_id, guild_id, name, description, options, default_member_permissions)
+            VALUES ($1, $2, $3, $4, $5, $6)
             RETURNING id, created_at
             "#,
             app_id,
             query.guild_id,
             cmd.name,
             cmd.description,
-            options_json
+            options_json,
+            default_member_permissions.map(GuildPermissions::to_db)
         )
         .fetch_one(&mut *tx)
         .await
@@ -268,10 +778,15 @@ pub async fn register_commands(
             name: cmd.name,
             description: cmd.description,
             options: cmd.options,
+            default_member_permissions: default_member_permissions.map(|p| p.bits()),
             created_at: result.created_at.to_rfc3339(),
         });
     }
 
+    crate::permissions::prune_command_permission_overrides(&mut *tx, app_id)
+        .await
+        .map_err(CommandError::Database)?;
+
     tx.commit().await.map_err(CommandError::Database)?;
 
     Ok((StatusCode::OK, Json(responses)))
@@ -319,7 +834,8 @@ pub async fn list_commands(
     // Fetch commands
     let commands = sqlx::query!(
         r#"
-        SELECT id, application_id, guild_id, name, description, options, created_at
+        SELECT id, application_id, guild_id, name, description, options,
+               default_member_permissions, created_at
         FROM slash_commands
         WHERE application_id = $1
           AND (($2::uuid IS NULL AND guild_id IS NULL) OR guild_id = $2)
@@ -347,6 +863,9 @@ pub async fn list_commands(
                 name: cmd.name,
                 description: cmd.description,
                 options,
+                default_member_permissions: cmd
+                    .default_member_permissions
+                    .map(|p| GuildPermissions::from_db(p).bits()),
                 created_at: cmd.created_at.to_rfc3339(),
             }
         })
@@ -411,6 +930,10 @@ pub async fn delete_command(
         return Err(CommandError::NotFound.into());
     }
 
+    crate::permissions::prune_command_permission_overrides(&pool, app_id)
+        .await
+        .map_err(CommandError::Database)?;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -467,5 +990,182 @@ pub async fn delete_all_commands(
     .await
     .map_err(CommandError::Database)?;
 
+    crate::permissions::prune_command_permission_overrides(&pool, app_id)
+        .await
+        .map_err(CommandError::Database)?;
+
     Ok(StatusCode::NO_CONTENT)
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn options(value: serde_json::Value) -> Vec<CommandOption> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn schema() -> Vec<CommandOption> {
+        options(json!([
+            {
+                "name": "config",
+                "description": "Configuration",
+                "type": "subcommand_group",
+                "options": [{
+                    "name": "set",
+                    "description": "Set a value",
+                    "type": "subcommand",
+                    "options": [
+                        { "name": "volume", "description": "Volume", "type": "integer", "required": true, "min_value": 0, "max_value": 100 },
+                        { "name": "mode", "description": "Mode", "type": "string", "choices": [
+                            { "name": "Loud", "value": "loud" },
+                            { "name": "Quiet", "value": "quiet" }
+                        ]}
+                    ]
+                }]
+            },
+            {
+                "name": "say",
+                "description": "Say something",
+                "type": "subcommand",
+                "options": [
+                    { "name": "loud", "description": "Shout", "type": "boolean", "required": true },
+                    { "name": "text", "description": "Text", "type": "string", "required": true, "max_length": 20 }
+                ]
+            }
+        ]))
+    }
+
+    #[test]
+    fn test_valid_schema_passes() {
+        assert!(validate_command_options(&schema()).is_ok());
+        assert!(validate_command_options(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_legacy_options_deserialize() {
+        let opts = options(json!([
+            { "name": "target", "description": "Target", "type": "user", "required": false }
+        ]));
+        assert!(opts[0].choices.is_empty());
+        assert!(!opts[0].autocomplete);
+        assert!(validate_command_options(&opts).is_ok());
+
+        let serialized = serde_json::to_value(&opts).unwrap();
+        assert_eq!(
+            serialized,
+            json!([{ "name": "target", "description": "Target", "type": "user", "required": false }])
+        );
+    }
+
+    #[test]
+    fn test_invalid_schemas_rejected() {
+        let cases = [
+            // Mixed subcommands and value options
+            json!([
+                { "name": "a", "description": "A", "type": "subcommand" },
+                { "name": "b", "description": "B", "type": "string" }
+            ]),
+            // Group nested in a subcommand
+            json!([{ "name": "a", "description": "A", "type": "subcommand", "options": [
+                { "name": "b", "description": "B", "type": "subcommand_group", "options": [
+                    { "name": "c", "description": "C", "type": "subcommand" }
+                ]}
+            ]}]),
+            // Choices on a boolean
+            json!([{ "name": "a", "description": "A", "type": "boolean", "choices": [{ "name": "x", "value": "x" }] }]),
+            // Choice type mismatch
+            json!([{ "name": "a", "description": "A", "type": "integer", "choices": [{ "name": "x", "value": "x" }] }]),
+            // Choices combined with autocomplete
+            json!([{ "name": "a", "description": "A", "type": "string", "autocomplete": true, "choices": [{ "name": "x", "value": "x" }] }]),
+            // Length limits on an integer
+            json!([{ "name": "a", "description": "A", "type": "integer", "max_length": 5 }]),
+            // Inverted range
+            json!([{ "name": "a", "description": "A", "type": "number", "min_value": 5, "max_value": 1 }]),
+            // Required after optional
+            json!([
+                { "name": "a", "description": "A", "type": "string" },
+                { "name": "b", "description": "B", "type": "string", "required": true }
+            ]),
+            // Duplicate names
+            json!([
+                { "name": "a", "description": "A", "type": "string" },
+                { "name": "a", "description": "B", "type": "string" }
+            ]),
+            // Invalid option name
+            json!([{ "name": "Bad Name", "description": "A", "type": "string" }]),
+        ];
+        for case in cases {
+            assert!(
+                matches!(
+                    validate_command_options(&options(case.clone())),
+                    Err(CommandError::InvalidOptions(_))
+                ),
+                "expected rejection: {case}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_subcommands_and_types() {
+        let schema = schema();
+
+        let resolved = resolve_invocation(&schema, &["config", "set", "42", "Quiet"]).unwrap();
+        assert_eq!(resolved.subcommand_group.as_deref(), Some("config"));
+        assert_eq!(resolved.subcommand.as_deref(), Some("set"));
+        assert_eq!(resolved.options["volume"], json!(42));
+        assert_eq!(resolved.options["mode"], json!("quiet"));
+
+        let resolved = resolve_invocation(&schema, &["say", "yes", "hello", "there"]).unwrap();
+        assert_eq!(resolved.subcommand_group, None);
+        assert_eq!(resolved.subcommand.as_deref(), Some("say"));
+        assert_eq!(resolved.options["loud"], json!(true));
+        assert_eq!(resolved.options["text"], json!("hello there"));
+    }
+
+    #[test]
+    fn test_resolve_rejects_invalid_input() {
+        let schema = schema();
+        for args in [
+            &[][..],
+            &["unknown"][..],
+            &["config", "set"][..],
+            &["config", "set", "abc"][..],
+            &["config", "set", "101"][..],
+            &["config", "set", "5", "medium"][..],
+            &["config", "set", "5", "loud", "extra"][..],
+            &["say", "maybe", "hi"][..],
+            &["say", "no", "this text is much too long for the limit"][..],
+        ] {
+            assert!(
+                resolve_invocation(&schema, args).is_err(),
+                "expected error for {args:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_resolve_without_options_ignores_text() {
+        let resolved = resolve_invocation(&[], &["free", "form"]).unwrap();
+        assert_eq!(resolved, ResolvedInvocation::default());
+    }
+
+    #[test]
+    fn test_select_subcommand() {
+        let schema = schema();
+        let set = select_subcommand(&schema, Some("config"), Some("set")).unwrap();
+        assert_eq!(set.len(), 2);
+        assert!(select_subcommand(&schema, None, Some("say")).is_some());
+        assert!(select_subcommand(&schema, None, Some("set")).is_none());
+        assert!(select_subcommand(&schema, Some("config"), None).is_none());
+
+        let flat = options(json!([{ "name": "a", "description": "A", "type": "string" }]));
+        assert_eq!(
+            select_subcommand(&flat, None, None).map(<[_]>::len),
+            Some(1)
+        );
+        assert!(select_subcommand(&flat, None, Some("a")).is_none());
+    }
+}
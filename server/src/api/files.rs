@@ -38,14 +38,14 @@ fn content_type_from_key(key: &str) -> &'static str {
 }
 
 /// Serve a file from S3 storage.
-pub async fn serve(
-    State(state): State<AppState>,
-    Path(key): Path<String>,
-) -> impl IntoResponse {
+pub async fn serve(State(state): State<AppState>, Path(key): Path<String>) -> impl IntoResponse {
     let s3 = match &state.s3 {
         Some(s3) => s3,
         None => {
-            return (StatusCode::SERVICE_UNAVAILABLE, "File storage not configured")
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "File storage not configured",
+            )
                 .into_response()
         }
     };
@@ -114,6 +114,9 @@ pub struct GlobalSearchResponse {
     pub total: i64,
     pub limit: i64,
     pub offset: i64,
+    /// Searched channels and DMs that are end-to-end encrypted. Their
+    /// encrypted messages can't be searched by the server.
+    pub encrypted_channel_ids: Vec<Uuid>,
 }
 
 // ============================================================================
@@ -191,11 +194,12 @@ pub async fn search_all(
     let mut all_channel_ids: Vec<Uuid> = Vec::new();
     let mut channel_guild_map: std::collections::HashMap<Uuid, Uuid> =
         std::collections::HashMap::new();
+    let mut encrypted_channel_ids: Vec<Uuid> = Vec::new();
 
     if !guild_ids.is_empty() {
         let guild_channels: Vec<db::Channel> = sqlx::query_as(
             "SELECT id, name, channel_type, category_id, guild_id, topic, icon_url, \
-             user_limit, position, max_screen_shares, created_at, updated_at \
+             user_limit, position, max_screen_shares, slowmode_seconds, encrypted, encryption_epoch, message_ttl_seconds, created_at, updated_at \
              FROM channels WHERE guild_id = ANY($1) ORDER BY position ASC",
         )
         .bind(&guild_ids)
@@ -249,12 +253,14 @@ pub async fn search_all(
                 channel_guild_map.insert(channel.id, guild_id);
             }
         }
+        encrypted_channel_ids.extend(guild_channels.iter().filter(|c| c.encrypted).map(|c| c.id));
     }
 
     // 3. Get DM channel IDs
     let dm_channels = dm::list_user_dms(&state.db, auth.id).await?;
     let dm_channel_ids: Vec<Uuid> = dm_channels.iter().map(|c| c.id).collect();
     all_channel_ids.extend(&dm_channel_ids);
+    encrypted_channel_ids.extend(dm_channels.iter().filter(|c| c.encrypted).map(|c| c.id));
 
     // 3b. Apply channel_id filter if provided
     if let Some(channel_id) = query.channel_id {
@@ -272,9 +278,12 @@ pub async fn search_all(
             total: 0,
             limit: query.limit,
             offset: query.offset,
+            encrypted_channel_ids: vec![],
         }));
     }
 
+    encrypted_channel_ids.retain(|id| all_channel_ids.contains(id));
+
     // Build search filters
     let filters = db::SearchFilters {
         date_from: query.date_from,
@@ -401,5 +410,6 @@ pub async fn search_all(
         total,
         limit,
         offset,
+        encrypted_channel_ids,
     }))
 }
@@ -44,8 +44,8 @@ use crate::ratelimit::{
 };
 use crate::voice::{ScreenShareLimiter, SfuServer};
 use crate::{
-    admin, auth, chat, connectivity, crypto, discovery, governance, guild, moderation, pages,
-    social, voice, webhooks, workspaces, ws,
+    admin, auth, bot_api, chat, connectivity, crypto, digest, discovery, governance, guild,
+    moderation, notifications, pages, push, social, voice, webhooks, workspaces, ws,
 };
 
 /// Shared application state.
@@ -225,6 +225,9 @@ pub fn create_router(state: AppState) -> Router {
         )
         .nest("/api/me/connection", connectivity::router())
         .nest("/api/me/preferences", preferences::router())
+        .nest("/api/me/notification-settings", notifications::router())
+        .nest("/api/me/push", push::router())
+        .nest("/api/me/email-digest", digest::router())
         .route("/api/me/pins", get(pins::list_pins).post(pins::create_pin))
         .route("/api/me/pins/reorder", put(pins::reorder_pins))
         .route(
@@ -246,6 +249,11 @@ pub fn create_router(state: AppState) -> Router {
         )
         .nest("/api/me/workspaces", workspaces::router())
         .route("/api/me/unread", get(unread::get_unread_aggregate))
+        .route("/api/me/mentions", get(chat::mentions::list_mentions))
+        .route(
+            "/api/me/stickers",
+            get(guild::stickers::list_available_stickers),
+        )
         .route("/api/me/read-all", post(unread::mark_all_read))
         .nest("/api/keys", crypto::router())
         .nest("/api/users/{user_id}/keys", crypto::user_keys_router())
@@ -298,6 +306,11 @@ pub fn create_router(state: AppState) -> Router {
             "/api/applications/{id}/intents",
             put(bots::update_gateway_intents),
         )
+        // Bot REST API scopes
+        .route(
+            "/api/applications/{id}/scopes",
+            put(bots::update_bot_scopes),
+        )
         // Message reactions
         .route(
             "/api/channels/{channel_id}/messages/{message_id}/reactions",
@@ -330,6 +343,42 @@ pub fn create_router(state: AppState) -> Router {
         .layer(from_fn_with_state(state.clone(), rate_limit_by_user))
         .layer(from_fn(with_category(RateLimitCategory::Search)));
 
+    // Poll votes with dedicated PollVote rate limit category (20 req/60s)
+    let poll_vote_routes = Router::new()
+        .route(
+            "/api/messages/{id}/poll/votes",
+            put(chat::polls::vote).delete(chat::polls::retract_vote),
+        )
+        .layer(from_fn_with_state(state.clone(), rate_limit_by_user))
+        .layer(from_fn(with_category(RateLimitCategory::PollVote)));
+
+    // Bulk message delete with dedicated BulkDelete rate limit category (5 req/60s)
+    let bulk_delete_routes = Router::new()
+        .route(
+            "/api/messages/channel/{channel_id}/bulk-delete",
+            post(chat::bulk_delete::bulk_delete),
+        )
+        .layer(from_fn_with_state(state.clone(), rate_limit_by_user))
+        .layer(from_fn(with_category(RateLimitCategory::BulkDelete)));
+
+    // Component clicks with dedicated Interaction rate limit category (30 req/60s)
+    let interaction_routes = Router::new()
+        .route(
+            "/api/messages/{id}/interactions",
+            post(chat::components::interact),
+        )
+        .layer(from_fn_with_state(state.clone(), rate_limit_by_user))
+        .layer(from_fn(with_category(RateLimitCategory::Interaction)));
+
+    // Autocomplete runs per keystroke, so it has its own Autocomplete category (60 req/60s)
+    let autocomplete_routes = Router::new()
+        .route(
+            "/api/guilds/{id}/commands/autocomplete",
+            post(guild::handlers::command_autocomplete),
+        )
+        .layer(from_fn_with_state(state.clone(), rate_limit_by_user))
+        .layer(from_fn(with_category(RateLimitCategory::Autocomplete)));
+
     // Data governance routes with DataGovernance rate limit (2 req/60s for mutations)
     let governance_routes = Router::new()
         .route(
@@ -353,6 +402,10 @@ pub fn create_router(state: AppState) -> Router {
         .merge(governance_routes)
         .merge(discovery_join_routes)
         .merge(search_routes)
+        .merge(poll_vote_routes)
+        .merge(bulk_delete_routes)
+        .merge(interaction_routes)
+        .merge(autocomplete_routes)
         .nest("/api", social_routes)
         .route("/api/reports", post(moderation::handlers::create_report))
         .nest("/api/admin", admin_routes)
@@ -389,6 +442,13 @@ pub fn create_router(state: AppState) -> Router {
         .route("/api/files/{*key}", get(files::serve))
         // Public message routes (download handles its own auth via query param)
         .nest("/api/messages", chat::messages_public_router())
+        // Email digest unsubscribe (signed token in query, IP rate limited)
+        .nest(
+            "/api/email",
+            digest::public_router()
+                .layer(from_fn_with_state(state.clone(), rate_limit_by_ip))
+                .layer(from_fn(with_category(RateLimitCategory::AuthOther))),
+        )
         // WebSocket
         .route("/ws", get(ws::handler))
         // Bot Gateway WebSocket (uses bot token auth)
@@ -396,6 +456,8 @@ pub fn create_router(state: AppState) -> Router {
             "/api/gateway/bot",
             get(ws::bot_gateway::bot_gateway_handler),
         )
+        // Bot REST API (uses bot token auth, per-route rate limits)
+        .nest("/api/bot", bot_api::router(state.clone()))
         // API documentation
         .merge(api_docs(state.config.enable_api_docs))
         .layer(OtelInResponseLayer)
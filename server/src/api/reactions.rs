@@ -7,12 +7,13 @@ use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::Json;
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{FromRow, PgPool};
 use uuid::Uuid;
 
 use crate::api::AppState;
 use crate::auth::AuthUser;
 use crate::db;
+use crate::ws::bot_gateway::BotServerEvent;
 use crate::ws::{broadcast_to_channel, ServerEvent};
 
 // ============================================================================
@@ -120,7 +121,7 @@ pub async fn add_reaction(
     }
 
     // Check channel exists
-    let _ = db::find_channel_by_id(&state.db, channel_id)
+    let channel = db::find_channel_by_id(&state.db, channel_id)
         .await?
         .ok_or(ReactionsError::ChannelNotFound)?;
 
@@ -180,6 +181,21 @@ pub async fn add_reaction(
         tracing::warn!("Failed to broadcast reaction_add event: {}", e);
     }
 
+    if let Some(guild_id) = channel.guild_id {
+        crate::ws::bot_events::spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            BotServerEvent::ReactionAdded {
+                message_id,
+                channel_id,
+                guild_id,
+                user_id: auth_user.id,
+                emoji: req.emoji.clone(),
+            },
+        );
+    }
+
     Ok((
         StatusCode::CREATED,
         Json(ReactionResponse {
@@ -212,7 +228,7 @@ pub async fn remove_reaction(
     auth_user: AuthUser,
 ) -> Result<impl IntoResponse, ReactionsError> {
     // Check channel exists
-    let _ = db::find_channel_by_id(&state.db, channel_id)
+    let channel = db::find_channel_by_id(&state.db, channel_id)
         .await?
         .ok_or(ReactionsError::ChannelNotFound)?;
 
@@ -242,6 +258,21 @@ pub async fn remove_reaction(
     .execute(&state.db)
     .await?;
 
+    if let Some(guild_id) = channel.guild_id {
+        crate::ws::bot_events::spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            BotServerEvent::ReactionRemoved {
+                message_id,
+                channel_id,
+                guild_id,
+                user_id: auth_user.id,
+                emoji: emoji.clone(),
+            },
+        );
+    }
+
     // Broadcast reaction_removed event to channel subscribers
     if let Err(e) = broadcast_to_channel(
         &state.redis,
@@ -328,3 +359,67 @@ pub async fn get_reactions(
 
     Ok(Json(response))
 }
+
+/// Check that a reaction is a Unicode emoji or `:name:` of one of the guild's
+/// custom emojis.
+pub async fn is_valid_reaction_emoji(
+    pool: &PgPool,
+    guild_id: Option<Uuid>,
+    emoji: &str,
+) -> sqlx::Result<bool> {
+    if emoji.is_empty() || emoji.len() > 64 {
+        return Ok(false);
+    }
+
+    if let Some(name) = emoji
+        .strip_prefix(':')
+        .and_then(|rest| rest.strip_suffix(':'))
+    {
+        let Some(guild_id) = guild_id else {
+            return Ok(false);
+        };
+        return sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM guild_emojis WHERE guild_id = $1 AND name = $2)",
+        )
+        .bind(guild_id)
+        .bind(name)
+        .fetch_one(pool)
+        .await;
+    }
+
+    Ok(is_unicode_emoji(emoji))
+}
+
+/// Whether `s` looks like a Unicode emoji sequence.
+///
+/// Emoji are outside ASCII except for keycap bases (`0`-`9`, `#`, `*`), so
+/// text, whitespace and control characters are rejected.
+fn is_unicode_emoji(s: &str) -> bool {
+    !s.is_ascii()
+        && s.chars().all(|c| {
+            if c.is_ascii() {
+                c.is_ascii_digit() || c == '#' || c == '*'
+            } else {
+                !c.is_control() && !c.is_whitespace()
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicode_emoji_sequences_are_accepted() {
+        for emoji in ["👍", "❤️", "1️⃣", "👩‍👩‍👧", "🇩🇪"] {
+            assert!(is_unicode_emoji(emoji), "{emoji} should be accepted");
+        }
+    }
+
+    #[test]
+    fn text_is_not_an_emoji() {
+        for text in ["", "ok", "1", "👍 nice", "👍\n", "<b>👍</b>", "héllo"] {
+            assert!(!is_unicode_emoji(text), "{text:?} should be rejected");
+        }
+    }
+}
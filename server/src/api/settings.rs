@@ -73,6 +73,7 @@ pub struct InstanceLimitsResponse {
     pub max_channels_per_guild: i64,
     pub max_roles_per_guild: i64,
     pub max_emojis_per_guild: i64,
+    pub max_stickers_per_guild: i64,
     pub max_bots_per_guild: i64,
     pub max_webhooks_per_app: i64,
     pub max_workspaces_per_user: i64,
@@ -102,6 +103,7 @@ pub async fn get_instance_limits(State(state): State<AppState>) -> Json<Instance
         max_channels_per_guild: state.config.max_channels_per_guild,
         max_roles_per_guild: state.config.max_roles_per_guild,
         max_emojis_per_guild: state.config.max_emojis_per_guild,
+        max_stickers_per_guild: state.config.max_stickers_per_guild,
         max_bots_per_guild: state.config.max_bots_per_guild,
         max_webhooks_per_app: state.config.max_webhooks_per_app,
         max_workspaces_per_user: state.config.max_workspaces_per_user,
@@ -119,6 +121,8 @@ pub struct UploadLimitsResponse {
     pub max_avatar_size: usize,
     /// Maximum emoji size in bytes (guild custom emojis).
     pub max_emoji_size: usize,
+    /// Maximum sticker size in bytes (guild stickers).
+    pub max_sticker_size: usize,
     /// Maximum attachment size in bytes (message attachments).
     pub max_upload_size: usize,
 }
@@ -141,6 +145,7 @@ pub async fn get_upload_limits(State(state): State<AppState>) -> Json<UploadLimi
     Json(UploadLimitsResponse {
         max_avatar_size: state.config.max_avatar_size,
         max_emoji_size: state.config.max_emoji_size,
+        max_sticker_size: state.config.max_sticker_size,
         max_upload_size: state.config.max_upload_size,
     })
 }
@@ -73,6 +73,74 @@ fn should_return_refresh_token(headers: &HeaderMap) -> bool {
     !has_origin
 }
 
+/// Check a TOTP or unused backup code against the user's enabled MFA secret.
+///
+/// A matching backup code is marked as used.
+pub async fn verify_mfa_code(state: &AppState, user: &db::User, code: &str) -> AuthResult<()> {
+    let encrypted_secret = user
+        .mfa_secret
+        .as_ref()
+        .ok_or_else(|| AuthError::Validation("MFA not enabled".to_string()))?;
+
+    // Get encryption key from config
+    let encryption_key = state
+        .config
+        .mfa_encryption_key
+        .as_ref()
+        .ok_or_else(|| AuthError::Internal("MFA encryption not configured".to_string()))?;
+
+    // Decode encryption key from hex
+    let key_bytes = hex::decode(encryption_key)
+        .map_err(|_| AuthError::Internal("Invalid MFA encryption key".to_string()))?;
+
+    // Decrypt the secret
+    let secret_str = decrypt_mfa_secret(encrypted_secret, &key_bytes)
+        .map_err(|e| AuthError::Internal(format!("Failed to decrypt MFA secret: {e}")))?;
+
+    // Parse the secret and create TOTP instance
+    let secret = Secret::Encoded(secret_str);
+    let totp = TOTP::new(
+        Algorithm::SHA1,
+        6,
+        1,
+        30,
+        secret
+            .to_bytes()
+            .map_err(|_| AuthError::Internal("Invalid TOTP secret encoding".into()))?,
+        Some("Kaiku".to_string()),
+        user.username.clone(),
+    )
+    .map_err(|e| AuthError::Internal(format!("Failed to create TOTP: {e}")))?;
+
+    // Try TOTP code first
+    let totp_valid = totp
+        .check_current(code)
+        .map_err(|e| AuthError::Internal(format!("Failed to verify TOTP code: {e}")))?;
+    if totp_valid {
+        return Ok(());
+    }
+
+    // TOTP failed — try backup code
+    let backup_codes = get_unused_mfa_backup_codes(&state.db, user.id)
+        .await
+        .map_err(AuthError::Database)?;
+
+    let hashes: Vec<String> = backup_codes.iter().map(|c| c.code_hash.clone()).collect();
+    let matched_idx = find_matching_backup_code(code, &hashes).ok_or(AuthError::InvalidMfaCode)?;
+
+    // Mark backup code as used
+    let used_code_id = backup_codes[matched_idx].id;
+    mark_mfa_backup_code_used(&state.db, used_code_id)
+        .await
+        .map_err(AuthError::Database)?;
+    tracing::info!(
+        user_id = %user.id,
+        code_id = %used_code_id,
+        "MFA backup code used"
+    );
+    Ok(())
+}
+
 // ============================================================================
 // Request/Response Types
 // ============================================================================
@@ -330,7 +398,7 @@ use super::hash_token;
 /// Browser clients send the refresh token via an `HttpOnly` cookie.
 /// Tauri clients cannot use cookies for same-origin requests to the API, so they
 /// send the refresh token via the `X-Refresh-Token` header instead.
-fn extract_current_token_hash(headers: &HeaderMap, jar: &CookieJar) -> Option<String> {
+pub fn extract_current_token_hash(headers: &HeaderMap, jar: &CookieJar) -> Option<String> {
     // Try cookie first (browser clients)
     if let Some(cookie) = jar.get(cookies::REFRESH_COOKIE_NAME) {
         return Some(hash_token(cookie.value()));
@@ -719,68 +787,16 @@ pub async fn login(
     }
 
     // Check MFA if enabled
-    if let Some(ref encrypted_secret) = user.mfa_secret {
+    if user.mfa_secret.is_some() {
         // MFA is enabled - code is required
         let mfa_code = body.mfa_code.as_ref().ok_or(AuthError::MfaRequired)?;
 
-        // Get encryption key from config
-        let encryption_key = state
-            .config
-            .mfa_encryption_key
-            .as_ref()
-            .ok_or_else(|| AuthError::Internal("MFA encryption not configured".to_string()))?;
-
-        // Decode encryption key from hex
-        let key_bytes = hex::decode(encryption_key)
-            .map_err(|_| AuthError::Internal("Invalid MFA encryption key".to_string()))?;
-
-        // Decrypt the secret
-        let secret_str = decrypt_mfa_secret(encrypted_secret, &key_bytes)
-            .map_err(|e| AuthError::Internal(format!("Failed to decrypt MFA secret: {e}")))?;
-
-        // Parse the secret and create TOTP instance
-        let secret = Secret::Encoded(secret_str);
-        let totp = TOTP::new(
-            Algorithm::SHA1,
-            6,
-            1,
-            30,
-            secret
-                .to_bytes()
-                .map_err(|_| AuthError::Internal("Invalid TOTP secret encoding".into()))?,
-            Some("Kaiku".to_string()),
-            user.username.clone(),
-        )
-        .map_err(|e| AuthError::Internal(format!("Failed to create TOTP: {e}")))?;
-
-        // Try TOTP code first
-        let totp_valid = totp
-            .check_current(mfa_code)
-            .map_err(|e| AuthError::Internal(format!("Failed to verify TOTP code: {e}")))?;
-
-        if !totp_valid {
-            // TOTP failed — try backup code
-            let backup_codes = get_unused_mfa_backup_codes(&state.db, user.id)
-                .await
-                .map_err(AuthError::Database)?;
-
-            let hashes: Vec<String> = backup_codes.iter().map(|c| c.code_hash.clone()).collect();
-            if let Some(matched_idx) = find_matching_backup_code(mfa_code, &hashes) {
-                // Mark backup code as used
-                let used_code_id = backup_codes[matched_idx].id;
-                mark_mfa_backup_code_used(&state.db, used_code_id)
-                    .await
-                    .map_err(AuthError::Database)?;
-                tracing::info!(
-                    user_id = %user.id,
-                    code_id = %used_code_id,
-                    "MFA backup code used for login"
-                );
-            } else {
+        if let Err(e) = verify_mfa_code(&state, &user, mfa_code).await {
+            if matches!(e, AuthError::InvalidMfaCode) {
                 record_failed_auth!();
                 crate::observability::metrics::record_auth_login_attempt(false);
-                return Err(AuthError::InvalidMfaCode);
             }
+            return Err(e);
         }
     }
 
@@ -915,12 +931,6 @@ pub async fn refresh_token(
         .await?
         .ok_or(AuthError::UserNotFound)?;
 
-    // Delete old session within the transaction
-    sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
-        .bind(&token_hash)
-        .execute(&mut *tx)
-        .await?;
-
     // Generate new token pair
     let new_tokens = generate_token_pair(
         user_id,
@@ -939,10 +949,11 @@ pub async fn refresh_token(
     let city = geo.as_ref().and_then(|g| g.city.as_deref());
     let country = geo.as_ref().and_then(|g| g.country.as_deref());
 
-    sqlx::query(
+    let new_session_id: Uuid = sqlx::query_scalar(
         r"
         INSERT INTO sessions (user_id, token_hash, expires_at, ip_address, user_agent, city, country)
         VALUES ($1, $2, $3, $4::inet, $5, $6, $7)
+        RETURNING id
         ",
     )
     .bind(user_id)
@@ -952,9 +963,20 @@ pub async fn refresh_token(
     .bind(user_agent.as_deref())
     .bind(city)
     .bind(country)
-    .execute(&mut *tx)
+    .fetch_one(&mut *tx)
     .await?;
 
+    // Keep the device's push registration across rotation
+    crate::push::queries::move_session_registration(&mut *tx, session.id, new_session_id).await?;
+    // Keep E2EE devices linked to the session that uploaded their keys
+    crate::crypto::devices::move_session_devices(&mut *tx, session.id, new_session_id).await?;
+
+    // Delete old session within the transaction
+    sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+        .bind(&token_hash)
+        .execute(&mut *tx)
+        .await?;
+
     // Commit the transaction — this is the atomic point
     tx.commit().await?;
 
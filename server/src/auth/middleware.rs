@@ -1,15 +1,36 @@
 //! Authentication Middleware
 
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use argon2::password_hash::{PasswordHash, PasswordVerifier};
+use argon2::Argon2;
 use axum::extract::{Request, State};
 use axum::http::header::AUTHORIZATION;
 use axum::middleware::Next;
 use axum::response::Response;
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
 use uuid::Uuid;
 
 use super::error::AuthError;
 use super::jwt::validate_access_token;
 use crate::api::AppState;
 use crate::db::{find_user_by_id, User};
+use crate::ratelimit::NormalizedIp;
+
+/// How long a verified bot token skips Argon2 on later requests.
+const BOT_TOKEN_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Maximum number of cached bot token verifications.
+const BOT_TOKEN_CACHE_CAPACITY: usize = 10_000;
+
+/// Recently verified bot tokens, keyed by the token's SHA-256 digest, with the
+/// stored hash they matched and when. Resetting a token replaces the stored
+/// hash, so cached entries for the old token stop matching on every server.
+static VERIFIED_BOT_TOKENS: LazyLock<DashMap<[u8; 32], (String, Instant)>> =
+    LazyLock::new(DashMap::new);
 
 /// Authenticated user injected into request extensions.
 ///
@@ -96,6 +117,155 @@ pub async fn require_auth(
     Ok(next.run(request).await)
 }
 
+/// Authenticated bot injected into request extensions by [`require_bot_auth`].
+///
+/// Routes behind `require_bot_auth` also receive an [`AuthUser`] for the bot
+/// user, so per-user rate limiting keys on the bot account.
+#[derive(Debug, Clone)]
+pub struct BotAuth {
+    /// The bot's user ID.
+    pub bot_user_id: Uuid,
+    /// The bot application ID.
+    pub application_id: Uuid,
+    /// REST API scopes granted to the application.
+    pub scopes: Vec<String>,
+}
+
+impl BotAuth {
+    /// Whether the application has been granted the given scope.
+    #[must_use]
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Check a bot token against its Argon2 hash on the blocking thread pool.
+async fn verify_bot_token(token: &str, token_hash: &str) -> Result<(), AuthError> {
+    let token = token.to_owned();
+    let token_hash = token_hash.to_owned();
+    tokio::task::spawn_blocking(move || {
+        let parsed_hash = PasswordHash::new(&token_hash).map_err(|e| {
+            tracing::error!("Failed to parse bot token hash: {}", e);
+            AuthError::Internal("Invalid stored token hash".to_string())
+        })?;
+
+        // Verify the token hash (constant-time operation)
+        Argon2::default()
+            .verify_password(token.as_bytes(), &parsed_hash)
+            .map_err(|_| AuthError::InvalidToken)
+    })
+    .await
+    .map_err(|e| AuthError::Internal(format!("Bot token verification failed: {e}")))?
+}
+
+/// Verify a bot token and load the owning application.
+///
+/// Token format: `bot_user_id.secret` to enable indexed lookup. Successful
+/// verifications are cached for [`BOT_TOKEN_CACHE_TTL`] so busy bots don't
+/// pay for Argon2 on every request.
+pub async fn authenticate_bot_token(pool: &PgPool, token: &str) -> Result<BotAuth, AuthError> {
+    let (user_part, _secret) = token.split_once('.').ok_or(AuthError::InvalidToken)?;
+    let bot_user_id = Uuid::parse_str(user_part).map_err(|_| AuthError::InvalidToken)?;
+
+    // Look up the specific bot application (indexed query)
+    let app = sqlx::query!(
+        r#"
+        SELECT id, token_hash, scopes
+        FROM bot_applications
+        WHERE bot_user_id = $1 AND token_hash IS NOT NULL
+        "#,
+        bot_user_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(AuthError::InvalidToken)?;
+
+    let token_hash = app.token_hash.ok_or(AuthError::InvalidToken)?;
+    let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+    let cached = VERIFIED_BOT_TOKENS.get(&digest).is_some_and(|entry| {
+        let (verified_hash, verified_at) = entry.value();
+        *verified_hash == token_hash && verified_at.elapsed() < BOT_TOKEN_CACHE_TTL
+    });
+
+    if !cached {
+        verify_bot_token(token, &token_hash).await?;
+
+        if VERIFIED_BOT_TOKENS.len() >= BOT_TOKEN_CACHE_CAPACITY {
+            VERIFIED_BOT_TOKENS
+                .retain(|_, (_, verified_at)| verified_at.elapsed() < BOT_TOKEN_CACHE_TTL);
+            if VERIFIED_BOT_TOKENS.len() >= BOT_TOKEN_CACHE_CAPACITY {
+                VERIFIED_BOT_TOKENS.clear();
+            }
+        }
+        VERIFIED_BOT_TOKENS.insert(digest, (token_hash, Instant::now()));
+    }
+
+    Ok(BotAuth {
+        bot_user_id,
+        application_id: app.id,
+        scopes: app.scopes,
+    })
+}
+
+/// Middleware to require bot authentication.
+///
+/// Extracts a `Bot <token>` Authorization header, verifies it against the
+/// application's token hash, and injects both `BotAuth` and an `AuthUser`
+/// for the bot account into request extensions.
+///
+/// Run behind `check_ip_not_blocked` and an IP rate limit so guessing is
+/// throttled before any hashing; rejected tokens count as failed
+/// authentication for the client IP.
+pub async fn require_bot_auth(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AuthError> {
+    let auth_header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .ok_or(AuthError::MissingAuthHeader)?;
+
+    // Parse Bot token
+    let token = auth_header
+        .strip_prefix("Bot ")
+        .ok_or(AuthError::InvalidAuthHeader)?;
+
+    let bot = match authenticate_bot_token(&state.db, token).await {
+        Ok(bot) => bot,
+        Err(AuthError::InvalidToken) => {
+            let ip = request.extensions().get::<NormalizedIp>();
+            if let (Some(rate_limiter), Some(ip)) = (&state.rate_limiter, ip) {
+                if let Err(e) = rate_limiter.record_failed_auth(&ip.0).await {
+                    // Fail closed so a broken limiter can't be used to guess tokens freely
+                    tracing::error!(
+                        error = %e,
+                        ip = ?ip.0,
+                        "SECURITY: Failed to record failed bot authentication"
+                    );
+                    return Err(AuthError::Internal(
+                        "Authentication service temporarily unavailable. Please try again later."
+                            .to_string(),
+                    ));
+                }
+            }
+            return Err(AuthError::InvalidToken);
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Load the bot user so handlers and rate limiting see a regular AuthUser
+    let user = find_user_by_id(&state.db, bot.bot_user_id)
+        .await?
+        .ok_or(AuthError::UserNotFound)?;
+
+    request.extensions_mut().insert(AuthUser::from(user));
+    request.extensions_mut().insert(bot);
+
+    Ok(next.run(request).await)
+}
+
 /// Extractor for authenticated user in handlers.
 ///
 /// Use this to get the current user in protected endpoints:
@@ -136,3 +306,86 @@ where
         Ok(parts.extensions.get::<Self>().cloned())
     }
 }
+
+impl<S> axum::extract::FromRequestParts<S> for BotAuth
+where
+    S: Send + Sync,
+{
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<Self>()
+            .cloned()
+            .ok_or(AuthError::MissingAuthHeader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use argon2::password_hash::rand_core::OsRng;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+
+    use super::*;
+    use crate::db;
+
+    fn hash(token: &str) -> String {
+        Argon2::default()
+            .hash_password(token.as_bytes(), &SaltString::generate(&mut OsRng))
+            .expect("hash token")
+            .to_string()
+    }
+
+    #[sqlx::test]
+    async fn reset_bot_tokens_stop_working_despite_the_cache(pool: PgPool) {
+        let owner = db::create_user(&pool, "owner", "Owner", None, "hash")
+            .await
+            .expect("create owner");
+        let bot = db::create_user(&pool, "helper_bot", "Helper", None, "hash")
+            .await
+            .expect("create bot");
+        let token = format!("{}.first-secret", bot.id);
+        let application_id: Uuid = sqlx::query_scalar(
+            r"
+            INSERT INTO bot_applications (owner_id, name, bot_user_id, token_hash, scopes)
+            VALUES ($1, 'Helper', $2, $3, ARRAY['messages.read'])
+            RETURNING id
+            ",
+        )
+        .bind(owner.id)
+        .bind(bot.id)
+        .bind(hash(&token))
+        .fetch_one(&pool)
+        .await
+        .expect("create application");
+
+        let auth = authenticate_bot_token(&pool, &token).await.unwrap();
+        assert_eq!(auth.application_id, application_id);
+        assert!(auth.has_scope("messages.read"));
+        // Served from the cache the second time
+        authenticate_bot_token(&pool, &token).await.unwrap();
+
+        let wrong = format!("{}.guessed-secret", bot.id);
+        assert!(matches!(
+            authenticate_bot_token(&pool, &wrong).await,
+            Err(AuthError::InvalidToken)
+        ));
+
+        let new_token = format!("{}.second-secret", bot.id);
+        sqlx::query("UPDATE bot_applications SET token_hash = $1 WHERE id = $2")
+            .bind(hash(&new_token))
+            .bind(application_id)
+            .execute(&pool)
+            .await
+            .expect("reset token");
+        assert!(matches!(
+            authenticate_bot_token(&pool, &token).await,
+            Err(AuthError::InvalidToken)
+        ));
+        authenticate_bot_token(&pool, &new_token).await.unwrap();
+    }
+}
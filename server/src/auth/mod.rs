@@ -18,8 +18,9 @@ use axum::extract::DefaultBodyLimit;
 use axum::routing::{delete, get, post};
 use axum::{middleware as axum_middleware, Router};
 pub use error::{AuthError, AuthResult};
+pub(crate) use handlers::verify_mfa_code;
 pub use jwt::Claims;
-pub use middleware::{require_auth, AuthUser};
+pub use middleware::{authenticate_bot_token, require_auth, require_bot_auth, AuthUser, BotAuth};
 pub use password::{hash_password, verify_password};
 
 use crate::api::AppState;
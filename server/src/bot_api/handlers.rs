@@ -0,0 +1,573 @@
+//! Bot REST API Handlers
+//!
+//! Bots act with the guild's @everyone permissions in channels of guilds where
+//! their application is installed. Scopes only apply in guilds that approved
+//! them, and member roles are managed with the bot's own roles and rank.
+
+use axum::extract::{Multipart, Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use tracing::instrument;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::scopes::BotScope;
+use super::types::{BotApiError, BotGuildMember, BotReactionRequest, ListMembersQuery};
+use crate::api::reactions::{is_valid_reaction_emoji, ReactionResponse};
+use crate::api::AppState;
+use crate::auth::BotAuth;
+use crate::chat::messages::{
+    apply_message_edit, broadcast_message_removed, build_message_responses,
+    CursorPaginatedResponse, ListMessagesQuery, MessageResponse, UpdateMessageRequest,
+};
+use crate::chat::uploads::create_message_with_file;
+use crate::db;
+use crate::guild::roles::broadcast_member_roles;
+use crate::permissions::{
+    bot_guild_permission_context, can_manage_role, can_moderate_member,
+    get_member_permission_context, require_bot_channel_access, GuildPermissions,
+    MemberPermissionContext,
+};
+use crate::ws::bot_events::spawn_guild_event;
+use crate::ws::bot_gateway::BotServerEvent;
+use crate::ws::{broadcast_to_channel, ServerEvent};
+
+/// Load the channel access context for the authenticated bot.
+///
+/// In guild channels, `scope` must be approved for the bot's installation.
+async fn channel_access(
+    state: &AppState,
+    bot: &BotAuth,
+    scope: BotScope,
+    channel_id: Uuid,
+) -> Result<MemberPermissionContext, BotApiError> {
+    let ctx =
+        require_bot_channel_access(&state.db, bot.application_id, bot.bot_user_id, channel_id)
+            .await?;
+
+    let approved: Option<bool> = sqlx::query_scalar(
+        r"
+        SELECT $3 = ANY(gbi.approved_scopes)
+        FROM channels c
+        INNER JOIN guild_bot_installations gbi
+            ON gbi.guild_id = c.guild_id AND gbi.application_id = $2
+        WHERE c.id = $1
+        ",
+    )
+    .bind(channel_id)
+    .bind(bot.application_id)
+    .bind(scope.as_str())
+    .fetch_optional(&state.db)
+    .await?;
+    if approved == Some(false) {
+        return Err(BotApiError::ScopeNotApproved(scope));
+    }
+
+    Ok(ctx)
+}
+
+/// Require that `scope` is approved for the bot's installation in a guild.
+///
+/// Fails with `Forbidden` if the application is not installed there.
+async fn require_guild_scope(
+    state: &AppState,
+    bot: &BotAuth,
+    scope: BotScope,
+    guild_id: Uuid,
+) -> Result<(), BotApiError> {
+    let approved: Option<bool> = sqlx::query_scalar(
+        "SELECT $3 = ANY(approved_scopes) FROM guild_bot_installations WHERE guild_id = $1 AND application_id = $2",
+    )
+    .bind(guild_id)
+    .bind(bot.application_id)
+    .bind(scope.as_str())
+    .fetch_optional(&state.db)
+    .await?;
+
+    match approved {
+        Some(true) => Ok(()),
+        Some(false) => Err(BotApiError::ScopeNotApproved(scope)),
+        None => Err(BotApiError::Forbidden),
+    }
+}
+
+/// Check that the bot may change `user_id`'s assignment of a role.
+///
+/// The bot needs `MANAGE_ROLES` from @everyone or its own roles, must rank
+/// above the role, and must outrank the target member.
+async fn require_role_hierarchy(
+    state: &AppState,
+    bot: &BotAuth,
+    guild_id: Uuid,
+    user_id: Uuid,
+    role_position: i32,
+) -> Result<(), BotApiError> {
+    let ctx =
+        bot_guild_permission_context(&state.db, guild_id, bot.application_id, bot.bot_user_id)
+            .await?;
+    let actor_position = ctx.highest_role_position.unwrap_or(i32::MAX);
+    can_manage_role(
+        ctx.computed_permissions,
+        actor_position,
+        role_position,
+        None,
+    )?;
+
+    let target = get_member_permission_context(&state.db, guild_id, user_id)
+        .await?
+        .ok_or_else(|| BotApiError::Validation("User is not a member of this guild".to_string()))?;
+    can_moderate_member(
+        actor_position,
+        target.highest_role_position.unwrap_or(i32::MAX),
+        target.is_owner,
+    )?;
+
+    Ok(())
+}
+
+/// Fetch channel message history.
+/// `GET /api/bot/channels/{channel_id}/messages`
+#[utoipa::path(
+    get,
+    path = "/api/bot/channels/{channel_id}/messages",
+    tag = "bots",
+    params(
+        ("channel_id" = Uuid, Path, description = "Channel ID"),
+        ("before" = Option<Uuid>, Query, description = "Return messages older than this ID"),
+        ("limit" = Option<i64>, Query, description = "Page size (1-100, default 50)"),
+    ),
+    responses(
+        (status = 200, body = CursorPaginatedResponse<MessageResponse>),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn list_messages(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path(channel_id): Path<Uuid>,
+    Query(query): Query<ListMessagesQuery>,
+) -> Result<Json<CursorPaginatedResponse<MessageResponse>>, BotApiError> {
+    BotScope::MessagesRead.require(&bot)?;
+    channel_access(&state, &bot, BotScope::MessagesRead, channel_id).await?;
+
+    let limit = query.limit.clamp(1, 100);
+    let mut messages = db::list_messages(&state.db, channel_id, query.before, limit + 1).await?;
+
+    let has_more = messages.len() as i64 > limit;
+    if has_more {
+        messages.pop();
+    }
+
+    let items = build_message_responses(&state.db, bot.bot_user_id, messages).await?;
+    let next_cursor = if has_more {
+        items.last().map(|m| m.id)
+    } else {
+        None
+    };
+
+    Ok(Json(CursorPaginatedResponse {
+        items,
+        has_more,
+        next_cursor,
+    }))
+}
+
+/// Edit a message sent by the bot.
+/// `PATCH /api/bot/messages/{id}`
+#[utoipa::path(
+    patch,
+    path = "/api/bot/messages/{id}",
+    tag = "bots",
+    params(("id" = Uuid, Path, description = "Message ID")),
+    request_body = UpdateMessageRequest,
+    responses(
+        (status = 200, body = MessageResponse),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot, body), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn edit_message(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path(id): Path<Uuid>,
+    Json(body): Json<UpdateMessageRequest>,
+) -> Result<Json<MessageResponse>, BotApiError> {
+    BotScope::MessagesWrite.require(&bot)?;
+    body.validate()
+        .map_err(|e| BotApiError::Validation(e.to_string()))?;
+
+    let message = db::find_message_by_id(&state.db, id)
+        .await?
+        .ok_or(BotApiError::NotFound("Message"))?;
+
+    // Bots may only edit their own messages
+    if message.user_id != Some(bot.bot_user_id) {
+        return Err(BotApiError::Forbidden);
+    }
+
+    channel_access(&state, &bot, BotScope::MessagesWrite, message.channel_id).await?;
+
+    let response = apply_message_edit(&state, bot.bot_user_id, &message, &body).await?;
+
+    Ok(Json(response))
+}
+
+/// Delete a message sent by the bot.
+/// `DELETE /api/bot/messages/{id}`
+#[utoipa::path(
+    delete,
+    path = "/api/bot/messages/{id}",
+    tag = "bots",
+    params(("id" = Uuid, Path, description = "Message ID")),
+    responses(
+        (status = 204, description = "Message deleted"),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn delete_message(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, BotApiError> {
+    BotScope::MessagesWrite.require(&bot)?;
+
+    let message = db::find_message_by_id(&state.db, id)
+        .await?
+        .ok_or(BotApiError::NotFound("Message"))?;
+
+    // Bots may only delete their own messages
+    if message.user_id != Some(bot.bot_user_id) {
+        return Err(BotApiError::Forbidden);
+    }
+
+    channel_access(&state, &bot, BotScope::MessagesWrite, message.channel_id).await?;
+
+    if !db::delete_message(&state.db, id, bot.bot_user_id).await? {
+        return Err(BotApiError::NotFound("Message"));
+    }
+
+    broadcast_message_removed(&state, message.channel_id, id, message.parent_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Add a reaction to a message.
+/// `PUT /api/bot/channels/{channel_id}/messages/{message_id}/reactions`
+#[utoipa::path(
+    put,
+    path = "/api/bot/channels/{channel_id}/messages/{message_id}/reactions",
+    tag = "bots",
+    params(
+        ("channel_id" = Uuid, Path, description = "Channel ID"),
+        ("message_id" = Uuid, Path, description = "Message ID"),
+    ),
+    request_body = BotReactionRequest,
+    responses(
+        (status = 201, body = ReactionResponse),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn add_reaction(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path((channel_id, message_id)): Path<(Uuid, Uuid)>,
+    Json(req): Json<BotReactionRequest>,
+) -> Result<(StatusCode, Json<ReactionResponse>), BotApiError> {
+    BotScope::ReactionsWrite.require(&bot)?;
+
+    let ctx = channel_access(&state, &bot, BotScope::ReactionsWrite, channel_id).await?;
+    ctx.require_permission(GuildPermissions::ADD_REACTIONS)?;
+
+    let channel = db::find_channel_by_id(&state.db, channel_id)
+        .await?
+        .ok_or(BotApiError::NotFound("Channel"))?;
+    if !is_valid_reaction_emoji(&state.db, channel.guild_id, &req.emoji).await? {
+        return Err(BotApiError::Validation("Invalid emoji".to_string()));
+    }
+
+    let message = db::find_message_by_id(&state.db, message_id)
+        .await?
+        .ok_or(BotApiError::NotFound("Message"))?;
+    if message.channel_id != channel_id {
+        return Err(BotApiError::NotFound("Message"));
+    }
+
+    sqlx::query(
+        r"
+        INSERT INTO message_reactions (message_id, user_id, emoji)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (message_id, user_id, emoji) DO NOTHING
+        ",
+    )
+    .bind(message_id)
+    .bind(bot.bot_user_id)
+    .bind(&req.emoji)
+    .execute(&state.db)
+    .await?;
+
+    let count: (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM message_reactions WHERE message_id = $1 AND emoji = $2",
+    )
+    .bind(message_id)
+    .bind(&req.emoji)
+    .fetch_one(&state.db)
+    .await?;
+
+    if let Err(e) = broadcast_to_channel(
+        &state.redis,
+        channel_id,
+        &ServerEvent::ReactionAdd {
+            channel_id,
+            message_id,
+            user_id: bot.bot_user_id,
+            emoji: req.emoji.clone(),
+        },
+    )
+    .await
+    {
+        tracing::warn!("Failed to broadcast reaction_add event: {}", e);
+    }
+
+    if let Some(guild_id) = channel.guild_id {
+        spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            BotServerEvent::ReactionAdded {
+                message_id,
+                channel_id,
+                guild_id,
+                user_id: bot.bot_user_id,
+                emoji: req.emoji.clone(),
+            },
+        );
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ReactionResponse {
+            emoji: req.emoji,
+            count: count.0,
+            me: true,
+        }),
+    ))
+}
+
+/// List members of a guild the bot is installed in.
+/// `GET /api/bot/guilds/{guild_id}/members`
+#[utoipa::path(
+    get,
+    path = "/api/bot/guilds/{guild_id}/members",
+    tag = "bots",
+    params(
+        ("guild_id" = Uuid, Path, description = "Guild ID"),
+        ListMembersQuery,
+    ),
+    responses(
+        (status = 200, body = Vec<BotGuildMember>),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn list_members(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path(guild_id): Path<Uuid>,
+    Query(query): Query<ListMembersQuery>,
+) -> Result<Json<Vec<BotGuildMember>>, BotApiError> {
+    BotScope::MembersRead.require(&bot)?;
+    require_guild_scope(&state, &bot, BotScope::MembersRead, guild_id).await?;
+
+    let limit = query.limit.clamp(1, 1000);
+
+    let members = sqlx::query_as::<_, BotGuildMember>(
+        r"
+        SELECT
+            u.id as user_id,
+            u.username,
+            u.display_name,
+            u.avatar_url,
+            gm.nickname,
+            u.is_bot,
+            gm.joined_at,
+            COALESCE(
+                array_agg(gmr.role_id) FILTER (WHERE gmr.role_id IS NOT NULL),
+                '{}'
+            ) as role_ids
+        FROM guild_members gm
+        INNER JOIN users u ON gm.user_id = u.id
+        LEFT JOIN guild_member_roles gmr
+            ON gmr.guild_id = gm.guild_id AND gmr.user_id = gm.user_id
+        WHERE gm.guild_id = $1 AND ($2::uuid IS NULL OR gm.user_id > $2)
+        GROUP BY u.id, gm.nickname, gm.joined_at
+        ORDER BY u.id
+        LIMIT $3
+        ",
+    )
+    .bind(guild_id)
+    .bind(query.after)
+    .bind(limit)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(members))
+}
+
+/// Assign a role to a guild member.
+/// `PUT /api/bot/guilds/{guild_id}/members/{user_id}/roles/{role_id}`
+#[utoipa::path(
+    put,
+    path = "/api/bot/guilds/{guild_id}/members/{user_id}/roles/{role_id}",
+    tag = "bots",
+    params(
+        ("guild_id" = Uuid, Path, description = "Guild ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("role_id" = Uuid, Path, description = "Role ID"),
+    ),
+    responses(
+        (status = 204, description = "Role assigned"),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn assign_role(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path((guild_id, user_id, role_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<StatusCode, BotApiError> {
+    BotScope::RolesWrite.require(&bot)?;
+    require_guild_scope(&state, &bot, BotScope::RolesWrite, guild_id).await?;
+
+    let role: Option<(i32, bool)> = sqlx::query_as(
+        "SELECT position, is_default FROM guild_roles WHERE id = $1 AND guild_id = $2",
+    )
+    .bind(role_id)
+    .bind(guild_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let (position, is_default) = role.ok_or(BotApiError::NotFound("Role"))?;
+
+    if is_default {
+        return Err(BotApiError::Validation(
+            "Cannot assign @everyone role".to_string(),
+        ));
+    }
+
+    require_role_hierarchy(&state, &bot, guild_id, user_id, position).await?;
+
+    sqlx::query(
+        r"
+        INSERT INTO guild_member_roles (guild_id, user_id, role_id, assigned_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (guild_id, user_id, role_id) DO NOTHING
+        ",
+    )
+    .bind(guild_id)
+    .bind(user_id)
+    .bind(role_id)
+    .bind(bot.bot_user_id)
+    .execute(&state.db)
+    .await?;
+
+    broadcast_member_roles(&state, guild_id, user_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove a role from a guild member.
+/// `DELETE /api/bot/guilds/{guild_id}/members/{user_id}/roles/{role_id}`
+#[utoipa::path(
+    delete,
+    path = "/api/bot/guilds/{guild_id}/members/{user_id}/roles/{role_id}",
+    tag = "bots",
+    params(
+        ("guild_id" = Uuid, Path, description = "Guild ID"),
+        ("user_id" = Uuid, Path, description = "User ID"),
+        ("role_id" = Uuid, Path, description = "Role ID"),
+    ),
+    responses(
+        (status = 204, description = "Role removed"),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn remove_role(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path((guild_id, user_id, role_id)): Path<(Uuid, Uuid, Uuid)>,
+) -> Result<StatusCode, BotApiError> {
+    BotScope::RolesWrite.require(&bot)?;
+    require_guild_scope(&state, &bot, BotScope::RolesWrite, guild_id).await?;
+
+    let role: Option<(i32,)> =
+        sqlx::query_as("SELECT position FROM guild_roles WHERE id = $1 AND guild_id = $2")
+            .bind(role_id)
+            .bind(guild_id)
+            .fetch_optional(&state.db)
+            .await?;
+    let (position,) = role.ok_or(BotApiError::NotFound("Role"))?;
+
+    require_role_hierarchy(&state, &bot, guild_id, user_id, position).await?;
+
+    let result = sqlx::query(
+        "DELETE FROM guild_member_roles WHERE guild_id = $1 AND user_id = $2 AND role_id = $3",
+    )
+    .bind(guild_id)
+    .bind(user_id)
+    .bind(role_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(BotApiError::NotFound("Role assignment"));
+    }
+
+    broadcast_member_roles(&state, guild_id, user_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Upload a file and post it as a message from the bot.
+/// `POST /api/bot/channels/{channel_id}/attachments`
+///
+/// Expects the same multipart form as the user upload endpoint:
+/// - `file`: The file data (required)
+/// - `content`: Optional message text content
+#[utoipa::path(
+    post,
+    path = "/api/bot/channels/{channel_id}/attachments",
+    tag = "bots",
+    params(("channel_id" = Uuid, Path, description = "Channel ID")),
+    request_body(content = Vec<u8>, content_type = "multipart/form-data"),
+    responses(
+        (status = 201, body = MessageResponse),
+    ),
+    security(("bot_token" = [])),
+)]
+#[instrument(skip(state, bot, multipart), fields(bot_user_id = %bot.bot_user_id))]
+pub async fn upload_attachment(
+    State(state): State<AppState>,
+    bot: BotAuth,
+    Path(channel_id): Path<Uuid>,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<MessageResponse>), BotApiError> {
+    BotScope::AttachmentsWrite.require(&bot)?;
+
+    let ctx = channel_access(&state, &bot, BotScope::AttachmentsWrite, channel_id).await?;
+    ctx.require_permission(GuildPermissions::SEND_MESSAGES)?;
+    ctx.require_permission(GuildPermissions::ATTACH_FILES)?;
+
+    let channel = db::find_channel_by_id(&state.db, channel_id)
+        .await?
+        .ok_or(BotApiError::NotFound("Channel"))?;
+    if channel.encrypted {
+        return Err(BotApiError::Validation(
+            "Bots cannot post in end-to-end encrypted channels".to_string(),
+        ));
+    }
+
+    Ok(create_message_with_file(&state, bot.bot_user_id, &channel, multipart).await?)
+}
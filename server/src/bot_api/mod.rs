@@ -0,0 +1,76 @@
+//! Bot REST API
+//!
+//! HTTP endpoints authenticated with `Authorization: Bot <token>`. Each route
+//! requires an explicit application scope and has its own rate limit category.
+
+pub mod handlers;
+pub mod scopes;
+pub mod types;
+
+use axum::middleware::{from_fn, from_fn_with_state};
+use axum::routing::{get, patch, post, put};
+use axum::Router;
+
+use crate::api::AppState;
+use crate::auth::require_bot_auth;
+use crate::ratelimit::{
+    check_ip_not_blocked, rate_limit_by_ip, rate_limit_by_user, with_category, RateLimitCategory,
+};
+
+/// Create the bot REST API router (mounted at `/api/bot`).
+///
+/// Routes:
+/// - `GET /channels/{channel_id}/messages` - Fetch channel history (`messages.read`)
+/// - `PATCH /messages/{id}` - Edit own message (`messages.write`)
+/// - `DELETE /messages/{id}` - Delete own message (`messages.write`)
+/// - `PUT /channels/{channel_id}/messages/{message_id}/reactions` - Add reaction
+///   (`reactions.write`)
+/// - `GET /guilds/{guild_id}/members` - List guild members (`members.read`)
+/// - `PUT /guilds/{guild_id}/members/{user_id}/roles/{role_id}` - Assign role (`roles.write`)
+/// - `DELETE /guilds/{guild_id}/members/{user_id}/roles/{role_id}` - Remove role
+///   (`roles.write`)
+/// - `POST /channels/{channel_id}/attachments` - Upload attachment (`attachments.write`)
+///
+/// Blocked IPs are rejected and every IP is rate limited before the token is
+/// checked.
+pub fn router(state: AppState) -> Router<AppState> {
+    let limited = |routes: Router<AppState>, category: RateLimitCategory| {
+        routes
+            .layer(from_fn_with_state(state.clone(), rate_limit_by_user))
+            .layer(from_fn(with_category(category)))
+    };
+
+    let message_read = Router::new().route(
+        "/channels/{channel_id}/messages",
+        get(handlers::list_messages),
+    );
+    let message_write = Router::new().route(
+        "/messages/{id}",
+        patch(handlers::edit_message).delete(handlers::delete_message),
+    );
+    let reactions = Router::new().route(
+        "/channels/{channel_id}/messages/{message_id}/reactions",
+        put(handlers::add_reaction),
+    );
+    let members = Router::new().route("/guilds/{guild_id}/members", get(handlers::list_members));
+    let roles = Router::new().route(
+        "/guilds/{guild_id}/members/{user_id}/roles/{role_id}",
+        put(handlers::assign_role).delete(handlers::remove_role),
+    );
+    let uploads = Router::new().route(
+        "/channels/{channel_id}/attachments",
+        post(handlers::upload_attachment),
+    );
+
+    Router::new()
+        .merge(limited(message_read, RateLimitCategory::BotMessageRead))
+        .merge(limited(message_write, RateLimitCategory::BotMessageWrite))
+        .merge(limited(reactions, RateLimitCategory::BotReaction))
+        .merge(limited(members, RateLimitCategory::BotMemberRead))
+        .merge(limited(roles, RateLimitCategory::BotRoleManage))
+        .merge(limited(uploads, RateLimitCategory::BotUpload))
+        .layer(from_fn_with_state(state.clone(), require_bot_auth))
+        .layer(from_fn_with_state(state.clone(), rate_limit_by_ip))
+        .layer(from_fn(with_category(RateLimitCategory::BotAuth)))
+        .layer(from_fn_with_state(state, check_ip_not_blocked))
+}
@@ -0,0 +1,118 @@
+//! Bot REST API Scopes
+//!
+//! Scopes are stored as a string array in `bot_applications.scopes` and
+//! checked on every bot REST API request. In guilds, a scope also has to be
+//! approved for the installation (`guild_bot_installations.approved_scopes`).
+
+use serde::{Deserialize, Serialize};
+
+use super::types::BotApiError;
+use crate::auth::BotAuth;
+
+/// A permission a bot application can be granted for the REST API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BotScope {
+    /// Fetch channel message history.
+    #[serde(rename = "messages.read")]
+    MessagesRead,
+    /// Edit and delete the bot's own messages.
+    #[serde(rename = "messages.write")]
+    MessagesWrite,
+    /// Add reactions to messages.
+    #[serde(rename = "reactions.write")]
+    ReactionsWrite,
+    /// List guild members.
+    #[serde(rename = "members.read")]
+    MembersRead,
+    /// Assign and remove member roles.
+    #[serde(rename = "roles.write")]
+    RolesWrite,
+    /// Upload message attachments.
+    #[serde(rename = "attachments.write")]
+    AttachmentsWrite,
+}
+
+impl BotScope {
+    /// All valid scope names.
+    pub const ALL: &'static [&'static str] = &[
+        "messages.read",
+        "messages.write",
+        "reactions.write",
+        "members.read",
+        "roles.write",
+        "attachments.write",
+    ];
+
+    /// Parse from a string (e.g., `"messages.read"`).
+    pub fn parse_str(s: &str) -> Option<Self> {
+        match s {
+            "messages.read" => Some(Self::MessagesRead),
+            "messages.write" => Some(Self::MessagesWrite),
+            "reactions.write" => Some(Self::ReactionsWrite),
+            "members.read" => Some(Self::MembersRead),
+            "roles.write" => Some(Self::RolesWrite),
+            "attachments.write" => Some(Self::AttachmentsWrite),
+            _ => None,
+        }
+    }
+
+    /// Convert to string form.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::MessagesRead => "messages.read",
+            Self::MessagesWrite => "messages.write",
+            Self::ReactionsWrite => "reactions.write",
+            Self::MembersRead => "members.read",
+            Self::RolesWrite => "roles.write",
+            Self::AttachmentsWrite => "attachments.write",
+        }
+    }
+
+    /// Require that the authenticated bot was granted this scope.
+    pub fn require(self, bot: &BotAuth) -> Result<(), BotApiError> {
+        if bot.has_scope(self.as_str()) {
+            Ok(())
+        } else {
+            Err(BotApiError::MissingScope(self))
+        }
+    }
+}
+
+impl std::fmt::Display for BotScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_round_trip() {
+        for name in BotScope::ALL {
+            let scope = BotScope::parse_str(name).expect("listed scope should parse");
+            assert_eq!(scope.as_str(), *name);
+            assert_eq!(
+                serde_json::to_value(scope).unwrap(),
+                serde_json::Value::String((*name).to_string())
+            );
+        }
+        assert!(BotScope::parse_str("messages").is_none());
+        assert!(BotScope::parse_str("MESSAGES.READ").is_none());
+    }
+
+    #[test]
+    fn test_require_scope() {
+        let bot = BotAuth {
+            bot_user_id: uuid::Uuid::new_v4(),
+            application_id: uuid::Uuid::new_v4(),
+            scopes: vec!["messages.read".to_string()],
+        };
+        assert!(BotScope::MessagesRead.require(&bot).is_ok());
+        assert!(matches!(
+            BotScope::RolesWrite.require(&bot),
+            Err(BotApiError::MissingScope(BotScope::RolesWrite))
+        ));
+    }
+}
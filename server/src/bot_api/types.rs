@@ -0,0 +1,143 @@
+//! Bot REST API Types
+//!
+//! Error type and request/response payloads for the bot REST API.
+
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::scopes::BotScope;
+use crate::chat::messages::MessageError;
+use crate::chat::uploads::UploadError;
+use crate::permissions::PermissionError;
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+/// Errors returned by the bot REST API.
+#[derive(Debug, thiserror::Error)]
+pub enum BotApiError {
+    /// The application was not granted the scope this route requires.
+    #[error("Missing bot scope: {0}")]
+    MissingScope(BotScope),
+    /// The scope was not approved for the guild the route acts in.
+    #[error("Bot scope not approved in this guild: {0}")]
+    ScopeNotApproved(BotScope),
+    /// Target resource does not exist.
+    #[error("{0} not found")]
+    NotFound(&'static str),
+    /// The bot cannot access the target channel or guild.
+    #[error("Access denied")]
+    Forbidden,
+    /// Permission or role hierarchy check failed.
+    #[error("{0}")]
+    Permission(PermissionError),
+    /// Invalid request.
+    #[error("{0}")]
+    Validation(String),
+    /// Error from the shared message handlers.
+    #[error("Message operation failed")]
+    Message(MessageError),
+    /// Error from the shared upload handlers.
+    #[error("{0}")]
+    Upload(#[from] UploadError),
+    /// Database error.
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for BotApiError {
+    fn into_response(self) -> Response {
+        let message = self.to_string();
+        let (status, code, message) = match self {
+            // Shared handler errors keep their own response format
+            Self::Message(e) => return e.into_response(),
+            Self::Upload(e) => return e.into_response(),
+            Self::MissingScope(_) => (StatusCode::FORBIDDEN, "MISSING_SCOPE", message),
+            Self::ScopeNotApproved(_) => (StatusCode::FORBIDDEN, "SCOPE_NOT_APPROVED", message),
+            Self::NotFound(_) => (StatusCode::NOT_FOUND, "NOT_FOUND", message),
+            Self::Forbidden => (StatusCode::FORBIDDEN, "FORBIDDEN", message),
+            Self::Permission(PermissionError::RoleHierarchy { .. }) => {
+                (StatusCode::FORBIDDEN, "ROLE_HIERARCHY", message)
+            }
+            Self::Permission(_) => (StatusCode::FORBIDDEN, "MISSING_PERMISSION", message),
+            Self::Validation(_) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", message),
+            Self::Database(err) => {
+                tracing::error!("Database error: {}", err);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "Database error".to_string(),
+                )
+            }
+        };
+        (
+            status,
+            Json(serde_json::json!({ "error": code, "message": message })),
+        )
+            .into_response()
+    }
+}
+
+impl From<MessageError> for BotApiError {
+    fn from(err: MessageError) -> Self {
+        Self::Message(err)
+    }
+}
+
+impl From<PermissionError> for BotApiError {
+    fn from(err: PermissionError) -> Self {
+        match err {
+            PermissionError::NotFound => Self::NotFound("Channel"),
+            PermissionError::DatabaseError(msg) => {
+                tracing::error!("Permission check failed: {}", msg);
+                Self::Forbidden
+            }
+            PermissionError::NotGuildMember
+            | PermissionError::Forbidden
+            | PermissionError::InvalidChannel => Self::Forbidden,
+            other => Self::Permission(other),
+        }
+    }
+}
+
+// ============================================================================
+// Request/Response Types
+// ============================================================================
+
+/// Query parameters for listing guild members.
+#[derive(Debug, Deserialize, utoipa::ToSchema, utoipa::IntoParams)]
+pub struct ListMembersQuery {
+    /// Return members whose user ID sorts after this one.
+    pub after: Option<Uuid>,
+    /// Page size (1-1000, default 100).
+    #[serde(default = "default_member_limit")]
+    pub limit: i64,
+}
+
+const fn default_member_limit() -> i64 {
+    100
+}
+
+/// Guild member as seen by a bot.
+#[derive(Debug, Serialize, sqlx::FromRow, utoipa::ToSchema)]
+pub struct BotGuildMember {
+    pub user_id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    pub nickname: Option<String>,
+    pub is_bot: bool,
+    pub joined_at: chrono::DateTime<chrono::Utc>,
+    /// Assigned role IDs (excluding @everyone).
+    pub role_ids: Vec<Uuid>,
+}
+
+/// Request body for adding a reaction.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct BotReactionRequest {
+    pub emoji: String,
+}
@@ -0,0 +1,511 @@
+//! Bulk Message Delete
+//!
+//! Lets moderators with `MANAGE_MESSAGES` wipe many messages in a guild
+//! channel at once, either by explicit IDs or by filters. Deletions are
+//! announced to clients in one `MessageBulkDelete` event and to bots and
+//! webhooks as one `MessageDeleted` event per message, attachment objects are
+//! removed from S3 in the background and every run is written to the audit log.
+
+use std::collections::HashSet;
+
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::messages::MessageError;
+use super::S3Client;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db;
+use crate::permissions::GuildPermissions;
+use crate::ws::bot_events::spawn_guild_events;
+use crate::ws::bot_gateway::BotServerEvent;
+use crate::ws::{broadcast_to_channel, ServerEvent};
+
+/// Most messages a single bulk delete may remove.
+pub const MAX_BULK_DELETE: usize = 1000;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Select messages to delete, by ID or by filters.
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct BulkDeleteRequest {
+    /// Explicit messages to delete. Cannot be combined with filters.
+    pub message_ids: Option<Vec<Uuid>>,
+    /// Only messages by this user.
+    pub author_id: Option<Uuid>,
+    /// Only messages posted at or after this time.
+    pub after: Option<DateTime<Utc>>,
+    /// Only messages posted before this time.
+    pub before: Option<DateTime<Utc>>,
+    /// Only messages containing a link.
+    #[serde(default)]
+    pub contains_link: bool,
+    /// Delete at most this many of the newest matching messages.
+    pub count: Option<u32>,
+    /// Report what would be deleted without deleting anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+impl BulkDeleteRequest {
+    const fn has_filters(&self) -> bool {
+        self.author_id.is_some()
+            || self.after.is_some()
+            || self.before.is_some()
+            || self.contains_link
+            || self.count.is_some()
+    }
+}
+
+/// Messages deleted by a bulk delete (or matched, for a dry run).
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkDeleteResponse {
+    pub dry_run: bool,
+    /// Number of messages deleted, or that would be.
+    pub deleted: usize,
+    /// IDs of those messages, newest first.
+    pub message_ids: Vec<Uuid>,
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+/// Check a bulk delete request is bounded and unambiguous.
+fn validate_request(req: &BulkDeleteRequest) -> Result<(), String> {
+    if let Some(ids) = &req.message_ids {
+        if req.has_filters() {
+            return Err("Provide either message_ids or filters, not both".to_string());
+        }
+        if ids.is_empty() {
+            return Err("message_ids cannot be empty".to_string());
+        }
+        if ids.len() > MAX_BULK_DELETE {
+            return Err(format!(
+                "Cannot delete more than {MAX_BULK_DELETE} messages at once"
+            ));
+        }
+        return Ok(());
+    }
+
+    if !req.has_filters() {
+        return Err("Provide message_ids or at least one filter".to_string());
+    }
+    if let Some(count) = req.count {
+        if count == 0 || count as usize > MAX_BULK_DELETE {
+            return Err(format!("count must be between 1 and {MAX_BULK_DELETE}"));
+        }
+    }
+    if let (Some(after), Some(before)) = (req.after, req.before) {
+        if after >= before {
+            return Err("after must be earlier than before".to_string());
+        }
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Find the live messages in `channel_id` matching the request, newest first.
+async fn select_messages(
+    pool: &PgPool,
+    channel_id: Uuid,
+    req: &BulkDeleteRequest,
+) -> sqlx::Result<Vec<Uuid>> {
+    let limit = req.count.map_or(MAX_BULK_DELETE, |c| c as usize);
+    sqlx::query_scalar(
+        r"
+        SELECT id FROM messages
+        WHERE channel_id = $1
+          AND deleted_at IS NULL
+          AND ($2::uuid[] IS NULL OR id = ANY($2))
+          AND ($3::uuid IS NULL OR user_id = $3)
+          AND ($4::timestamptz IS NULL OR created_at >= $4)
+          AND ($5::timestamptz IS NULL OR created_at < $5)
+          AND (NOT $6 OR (NOT encrypted AND content ~* '(https?://|www\.)'))
+        ORDER BY created_at DESC, id DESC
+        LIMIT $7
+        ",
+    )
+    .bind(channel_id)
+    .bind(req.message_ids.as_deref())
+    .bind(req.author_id)
+    .bind(req.after)
+    .bind(req.before)
+    .bind(req.contains_link)
+    .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+    .fetch_all(pool)
+    .await
+}
+
+/// Soft-delete `ids` and drop their attachments and pins.
+///
+/// Thread counters of surviving parents are recomputed. Returns the messages
+/// actually deleted and the S3 keys no other attachment still references
+/// (forwarded copies share objects with the original).
+async fn delete_messages(pool: &PgPool, ids: &[Uuid]) -> sqlx::Result<(Vec<Uuid>, Vec<String>)> {
+    let mut tx = pool.begin().await?;
+
+    let deleted: Vec<(Uuid, Option<Uuid>)> = sqlx::query_as(
+        r"
+        UPDATE messages
+        SET deleted_at = NOW(), content = '[deleted]'
+        WHERE id = ANY($1) AND deleted_at IS NULL
+        RETURNING id, parent_id
+        ",
+    )
+    .bind(ids)
+    .fetch_all(&mut *tx)
+    .await?;
+    let deleted_ids: Vec<Uuid> = deleted.iter().map(|(id, _)| *id).collect();
+    let parent_ids: Vec<Uuid> = deleted
+        .iter()
+        .filter_map(|(_, parent)| *parent)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    sqlx::query("DELETE FROM channel_pins WHERE message_id = ANY($1)")
+        .bind(&deleted_ids)
+        .execute(&mut *tx)
+        .await?;
+
+    refresh_thread_counters(&mut tx, &parent_ids).await?;
+    let keys = detach_attachments(&mut tx, &deleted_ids).await?;
+
+    tx.commit().await?;
+    Ok((deleted_ids, keys))
+}
+
+/// Recompute reply counters of the surviving thread parents in `parent_ids`.
+pub(super) async fn refresh_thread_counters(
+    conn: &mut PgConnection,
+    parent_ids: &[Uuid],
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r"
+        UPDATE messages p
+        SET thread_reply_count = (
+                SELECT COUNT(*) FROM messages r
+                WHERE r.parent_id = p.id AND r.deleted_at IS NULL
+            ),
+            thread_last_reply_at = (
+                SELECT MAX(created_at) FROM messages r
+                WHERE r.parent_id = p.id AND r.deleted_at IS NULL
+            )
+        WHERE p.id = ANY($1) AND p.deleted_at IS NULL
+        ",
+    )
+    .bind(parent_ids)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Drop the attachment rows of `message_ids`.
+///
+/// Returns the S3 keys no other attachment still references (forwarded
+/// copies share objects with the original).
+pub(super) async fn detach_attachments(
+    conn: &mut PgConnection,
+    message_ids: &[Uuid],
+) -> sqlx::Result<Vec<String>> {
+    let removed: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        r"
+        DELETE FROM file_attachments
+        WHERE message_id = ANY($1)
+        RETURNING s3_key, thumbnail_s3_key, medium_s3_key
+        ",
+    )
+    .bind(message_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut keys: Vec<String> = removed
+        .into_iter()
+        .flat_map(|(key, thumb, medium)| std::iter::once(key).chain(thumb).chain(medium))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+
+    let shared: HashSet<String> = sqlx::query_scalar(
+        r"
+        SELECT s3_key FROM file_attachments WHERE s3_key = ANY($1)
+        UNION
+        SELECT thumbnail_s3_key FROM file_attachments WHERE thumbnail_s3_key = ANY($1)
+        UNION
+        SELECT medium_s3_key FROM file_attachments WHERE medium_s3_key = ANY($1)
+        ",
+    )
+    .bind(&keys)
+    .fetch_all(&mut *conn)
+    .await?
+    .into_iter()
+    .collect();
+    keys.retain(|key| !shared.contains(key));
+    Ok(keys)
+}
+
+/// Delete attachment objects in the background, logging failures.
+fn spawn_s3_cleanup(s3: S3Client, keys: Vec<String>) {
+    tokio::spawn(async move {
+        for key in keys {
+            if let Err(e) = s3.delete(&key).await {
+                warn!(s3_key = %key, error = %e, "Failed to delete bulk-deleted attachment");
+            }
+        }
+    });
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Delete many messages in a guild channel at once.
+///
+/// POST /api/messages/channel/{channel_id}/bulk-delete
+#[utoipa::path(
+    post,
+    path = "/api/messages/channel/{channel_id}/bulk-delete",
+    tag = "messages",
+    params(("channel_id" = Uuid, Path, description = "Channel ID")),
+    request_body = BulkDeleteRequest,
+    responses(
+        (status = 200, body = BulkDeleteResponse),
+        (status = 400, description = "Unbounded or invalid selection, or not a guild channel"),
+        (status = 403, description = "Missing MANAGE_MESSAGES"),
+        (status = 404, description = "Channel not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, body), fields(user_id = %auth_user.id, channel_id = %channel_id))]
+pub async fn bulk_delete(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(channel_id): Path<Uuid>,
+    Json(body): Json<BulkDeleteRequest>,
+) -> Result<Json<BulkDeleteResponse>, MessageError> {
+    let channel = db::find_channel_by_id(&state.db, channel_id)
+        .await?
+        .ok_or(MessageError::ChannelNotFound)?;
+    let Some(guild_id) = channel.guild_id else {
+        return Err(MessageError::Validation(
+            "Bulk delete is only available in guild channels".to_string(),
+        ));
+    };
+
+    let ctx = crate::permissions::require_channel_access(&state.db, auth_user.id, channel_id)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+    if !ctx.has_permission(GuildPermissions::MANAGE_MESSAGES) {
+        return Err(MessageError::Forbidden);
+    }
+
+    validate_request(&body).map_err(MessageError::Validation)?;
+    let matched = select_messages(&state.db, channel_id, &body).await?;
+    if body.dry_run {
+        return Ok(Json(BulkDeleteResponse {
+            dry_run: true,
+            deleted: matched.len(),
+            message_ids: matched,
+        }));
+    }
+
+    let (deleted, orphaned_keys) = delete_messages(&state.db, &matched).await?;
+    if deleted.is_empty() {
+        return Ok(Json(BulkDeleteResponse {
+            dry_run: false,
+            deleted: 0,
+            message_ids: deleted,
+        }));
+    }
+
+    if let Err(e) = broadcast_to_channel(
+        &state.redis,
+        channel_id,
+        &ServerEvent::MessageBulkDelete {
+            channel_id,
+            message_ids: deleted.clone(),
+        },
+    )
+    .await
+    {
+        warn!(channel_id = %channel_id, error = %e, "Failed to broadcast bulk delete event");
+    }
+    spawn_guild_events(
+        &state.db,
+        &state.redis,
+        guild_id,
+        deleted
+            .iter()
+            .map(|&message_id| BotServerEvent::MessageDeleted {
+                message_id,
+                channel_id,
+                guild_id: Some(guild_id),
+            })
+            .collect(),
+    );
+
+    if let Some(s3) = &state.s3 {
+        if !orphaned_keys.is_empty() {
+            spawn_s3_cleanup(s3.clone(), orphaned_keys);
+        }
+    }
+
+    crate::permissions::queries::write_audit_log(
+        &state.db,
+        auth_user.id,
+        "guild.messages.bulk_deleted",
+        Some("guild"),
+        Some(guild_id),
+        Some(serde_json::json!({
+            "channel_id": channel_id,
+            "count": deleted.len(),
+            "by_ids": body.message_ids.is_some(),
+            "author_id": body.author_id,
+            "after": body.after,
+            "before": body.before,
+            "contains_link": body.contains_link,
+        })),
+        None,
+    )
+    .await
+    .ok();
+
+    Ok(Json(BulkDeleteResponse {
+        dry_run: false,
+        deleted: deleted.len(),
+        message_ids: deleted,
+    }))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulk_delete_requests_are_bounded() {
+        assert!(validate_request(&BulkDeleteRequest::default()).is_err());
+        assert!(validate_request(&BulkDeleteRequest {
+            message_ids: Some(vec![]),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_request(&BulkDeleteRequest {
+            message_ids: Some(vec![Uuid::new_v4(); MAX_BULK_DELETE + 1]),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_request(&BulkDeleteRequest {
+            message_ids: Some(vec![Uuid::new_v4()]),
+            author_id: Some(Uuid::new_v4()),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(validate_request(&BulkDeleteRequest {
+            count: Some(0),
+            ..Default::default()
+        })
+        .is_err());
+
+        let now = Utc::now();
+        assert!(validate_request(&BulkDeleteRequest {
+            after: Some(now),
+            before: Some(now),
+            ..Default::default()
+        })
+        .is_err());
+
+        assert!(validate_request(&BulkDeleteRequest {
+            message_ids: Some(vec![Uuid::new_v4(); MAX_BULK_DELETE]),
+            ..Default::default()
+        })
+        .is_ok());
+        assert!(validate_request(&BulkDeleteRequest {
+            contains_link: true,
+            ..Default::default()
+        })
+        .is_ok());
+    }
+
+    #[sqlx::test]
+    async fn deletes_keep_objects_shared_with_forwards(pool: PgPool) {
+        let user = db::create_user(&pool, "mod", "Mod", None, "hash")
+            .await
+            .expect("create user");
+        let channel_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO channels (id, name, channel_type, guild_id, position) VALUES ($1, 'spam', 'dm', NULL, 0)",
+        )
+        .bind(channel_id)
+        .execute(&pool)
+        .await
+        .expect("create channel");
+
+        let mut ids = Vec::new();
+        for content in ["hi", "see https://spam.example", "www.spam.example"] {
+            let message =
+                db::create_message(&pool, channel_id, user.id, content, false, None, None)
+                    .await
+                    .expect("create message");
+            ids.push(message.id);
+        }
+        for (message_id, key) in [(ids[1], "a.png"), (ids[2], "b.png"), (ids[0], "b.png")] {
+            sqlx::query(
+                "INSERT INTO file_attachments (message_id, filename, mime_type, size_bytes, s3_key) VALUES ($1, 'f.png', 'image/png', 1, $2)",
+            )
+            .bind(message_id)
+            .bind(key)
+            .execute(&pool)
+            .await
+            .expect("insert attachment");
+        }
+
+        let links = select_messages(
+            &pool,
+            channel_id,
+            &BulkDeleteRequest {
+                contains_link: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("select");
+        assert_eq!(links, vec![ids[2], ids[1]]);
+
+        let newest = select_messages(
+            &pool,
+            channel_id,
+            &BulkDeleteRequest {
+                count: Some(1),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("select");
+        assert_eq!(newest, vec![ids[2]]);
+
+        // b.png is still used by the surviving message, so only a.png goes
+        let (deleted, keys) = delete_messages(&pool, &links).await.expect("delete");
+        assert_eq!(deleted.len(), 2);
+        assert_eq!(keys, vec!["a.png".to_string()]);
+
+        // Already-deleted messages are skipped
+        let (deleted, keys) = delete_messages(&pool, &links).await.expect("delete");
+        assert!(deleted.is_empty());
+        assert!(keys.is_empty());
+    }
+}
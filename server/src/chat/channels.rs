@@ -11,6 +11,7 @@ use validator::Validate;
 use crate::api::AppState;
 use crate::auth::AuthUser;
 use crate::db::{self, ChannelType};
+use crate::ws::bot_gateway::BotServerEvent;
 use crate::ws::{broadcast_to_user, ServerEvent};
 
 // ============================================================================
@@ -80,6 +81,14 @@ pub struct ChannelResponse {
     pub position: i32,
     /// Maximum concurrent screen shares (voice channels only).
     pub max_screen_shares: i32,
+    /// Seconds members must wait between messages (0 = off).
+    pub slowmode_seconds: i32,
+    /// Messages must be end-to-end encrypted.
+    pub encrypted: bool,
+    /// Current membership epoch of an encrypted channel.
+    pub encryption_epoch: i64,
+    /// Seconds until new messages disappear (`None` = off).
+    pub message_ttl_seconds: Option<i32>,
     pub icon_url: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
@@ -101,6 +110,10 @@ impl From<db::Channel> for ChannelResponse {
             user_limit: ch.user_limit,
             position: ch.position,
             max_screen_shares: ch.max_screen_shares,
+            slowmode_seconds: ch.slowmode_seconds,
+            encrypted: ch.encrypted,
+            encryption_epoch: ch.encryption_epoch,
+            message_ttl_seconds: ch.message_ttl_seconds,
             created_at: ch.created_at,
         }
     }
@@ -124,6 +137,13 @@ pub struct UpdateChannelRequest {
     pub topic: Option<String>,
     pub user_limit: Option<i32>,
     pub position: Option<i32>,
+    /// Slow mode interval in seconds; 0 turns it off.
+    #[validate(range(
+        min = 0,
+        max = super::slowmode::MAX_SLOWMODE_SECONDS,
+        message = "Slow mode must be between 0 and 21600 seconds"
+    ))]
+    pub slowmode_seconds: Option<i32>,
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
@@ -226,7 +246,7 @@ pub async fn create(
         let channel = sqlx::query_as::<_, db::Channel>(
             r"INSERT INTO channels (name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position)
               VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-              RETURNING id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, created_at, updated_at",
+              RETURNING id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, slowmode_seconds, encrypted, encryption_epoch, message_ttl_seconds, created_at, updated_at",
         )
         .bind(&body.name)
         .bind(&channel_type)
@@ -257,9 +277,44 @@ pub async fn create(
         .await?
     };
 
+    if let Some(guild_id) = channel.guild_id {
+        crate::ws::bot_events::spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            channel_event(&channel, guild_id, true),
+        );
+    }
+
     Ok((StatusCode::CREATED, Json(channel.into())))
 }
 
+/// Build a `ChannelCreated` (or `ChannelUpdated`) bot event for a guild channel.
+fn channel_event(channel: &db::Channel, guild_id: Uuid, created: bool) -> BotServerEvent {
+    let name = channel.name.clone();
+    let channel_type = match channel.channel_type {
+        ChannelType::Text => "text",
+        ChannelType::Voice => "voice",
+        ChannelType::Dm => "dm",
+    }
+    .to_string();
+    if created {
+        BotServerEvent::ChannelCreated {
+            guild_id,
+            channel_id: channel.id,
+            name,
+            channel_type,
+        }
+    } else {
+        BotServerEvent::ChannelUpdated {
+            guild_id,
+            channel_id: channel.id,
+            name,
+            channel_type,
+        }
+    }
+}
+
 /// Get a channel by ID.
 /// GET /api/channels/:id
 #[utoipa::path(
@@ -313,10 +368,16 @@ pub async fn update(
         .map_err(|e| ChannelError::Validation(e.to_string()))?;
 
     // Check channel exists
-    let _ = db::find_channel_by_id(&state.db, id)
+    let existing = db::find_channel_by_id(&state.db, id)
         .await?
         .ok_or(ChannelError::NotFound)?;
 
+    if body.slowmode_seconds.is_some() && existing.channel_type == ChannelType::Dm {
+        return Err(ChannelError::Validation(
+            "Slow mode is not available in DMs".to_string(),
+        ));
+    }
+
     // Check if user has VIEW_CHANNEL and MANAGE_CHANNELS permissions
     let ctx = crate::permissions::require_channel_access(&state.db, auth_user.id, id)
         .await
@@ -334,10 +395,20 @@ pub async fn update(
         None, // icon_url
         body.user_limit,
         body.position,
+        body.slowmode_seconds,
     )
     .await?
     .ok_or(ChannelError::NotFound)?;
 
+    if let Some(guild_id) = channel.guild_id {
+        crate::ws::bot_events::spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            channel_event(&channel, guild_id, false),
+        );
+    }
+
     Ok(Json(channel.into()))
 }
 
@@ -367,9 +438,24 @@ pub async fn delete(
         return Err(ChannelError::Forbidden);
     }
 
+    let guild_id = db::find_channel_by_id(&state.db, id)
+        .await?
+        .and_then(|channel| channel.guild_id);
+
     let deleted = db::delete_channel(&state.db, id).await?;
 
     if deleted {
+        if let Some(guild_id) = guild_id {
+            crate::ws::bot_events::spawn_guild_event(
+                &state.db,
+                &state.redis,
+                guild_id,
+                BotServerEvent::ChannelDeleted {
+                    guild_id,
+                    channel_id: id,
+                },
+            );
+        }
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err(ChannelError::NotFound)
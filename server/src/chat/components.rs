@@ -0,0 +1,653 @@
+//! Interactive Message Components
+//!
+//! Action rows of buttons and select menus that bots attach to messages and
+//! command responses. Clicking a component creates a short-lived interaction
+//! that is relayed to the owning bot via the gateway (`ComponentInteraction`)
+//! and signed webhook delivery (`component.interaction`).
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use fred::interfaces::{KeysInterface, PubsubInterface};
+use serde::{Deserialize, Serialize};
+use tracing::{error, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::messages::MessageError;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db;
+use crate::ws::bot_gateway::BotServerEvent;
+use crate::ws::{broadcast_to_user, ServerEvent};
+
+/// Maximum number of action rows per message.
+pub const MAX_ACTION_ROWS: usize = 5;
+/// Maximum number of buttons in a single action row.
+pub const MAX_BUTTONS_PER_ROW: usize = 5;
+/// Maximum number of options in a select menu.
+pub const MAX_SELECT_OPTIONS: usize = 25;
+/// Maximum length of a component custom ID.
+pub const MAX_CUSTOM_ID_LENGTH: usize = 100;
+/// Maximum length of a button or option label.
+pub const MAX_LABEL_LENGTH: usize = 80;
+/// Maximum length of select option values and descriptions.
+const MAX_OPTION_VALUE_LENGTH: usize = 100;
+/// Maximum length of a select menu placeholder.
+const MAX_PLACEHOLDER_LENGTH: usize = 150;
+/// Maximum length of a link button URL.
+const MAX_URL_LENGTH: usize = 512;
+
+/// How long a component interaction stays valid for a bot response (seconds).
+pub const INTERACTION_TTL_SECS: i64 = 300;
+/// How long the clicking user waits before a timeout is reported (seconds).
+const INTERACTION_TIMEOUT_SECS: u64 = 30;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A horizontal row of interactive components.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ActionRow {
+    /// Components in this row: up to 5 buttons or a single select menu.
+    pub components: Vec<Component>,
+}
+
+/// An interactive component attached to a message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Component {
+    /// A clickable button.
+    Button(Button),
+    /// A dropdown of predefined options.
+    SelectMenu(SelectMenu),
+    /// A guild sticker sent by a member. Added by the server from
+    /// `sticker_id`; bots cannot attach one directly.
+    Sticker(Sticker),
+}
+
+/// Visual style of a button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonStyle {
+    Primary,
+    Secondary,
+    Success,
+    Danger,
+    /// Opens `url` in the browser instead of creating an interaction.
+    Link,
+}
+
+/// A button component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Button {
+    pub style: ButtonStyle,
+    pub label: String,
+    /// Bot-defined identifier sent back on click (required unless `style` is `link`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_id: Option<String>,
+    /// Target URL (only for `link` buttons).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+/// A select menu component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SelectMenu {
+    /// Bot-defined identifier sent back on selection.
+    pub custom_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placeholder: Option<String>,
+    pub options: Vec<SelectOption>,
+    /// Minimum number of options that must be chosen.
+    #[serde(default = "default_min_values")]
+    pub min_values: u8,
+    /// Maximum number of options that can be chosen.
+    #[serde(default = "default_max_values")]
+    pub max_values: u8,
+    #[serde(default)]
+    pub disabled: bool,
+}
+
+const fn default_min_values() -> u8 {
+    1
+}
+
+const fn default_max_values() -> u8 {
+    1
+}
+
+/// Snapshot of a guild sticker, rendered from its stored image without an
+/// attachment upload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Sticker {
+    pub sticker_id: Uuid,
+    pub guild_id: Uuid,
+    pub name: String,
+    pub format: String,
+    pub url: String,
+    pub preview_url: String,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// A selectable option in a select menu.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SelectOption {
+    pub label: String,
+    pub value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Request body for interacting with a message component.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ComponentInteractionRequest {
+    /// Custom ID of the clicked button or select menu.
+    pub custom_id: String,
+    /// Selected option values (select menus only).
+    #[serde(default)]
+    pub values: Vec<String>,
+}
+
+/// Response for an accepted component interaction.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ComponentInteractionResponse {
+    /// Interaction ID the bot will respond to.
+    pub interaction_id: Uuid,
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+fn validate_custom_id(custom_id: &str, seen: &mut Vec<String>) -> Result<(), String> {
+    if custom_id.is_empty() || custom_id.chars().count() > MAX_CUSTOM_ID_LENGTH {
+        return Err(format!(
+            "Component custom_id must be 1-{MAX_CUSTOM_ID_LENGTH} characters"
+        ));
+    }
+    if custom_id.chars().any(char::is_control) {
+        return Err("Component custom_id must not contain control characters".to_string());
+    }
+    if seen.iter().any(|id| id == custom_id) {
+        return Err(format!("Duplicate component custom_id '{custom_id}'"));
+    }
+    seen.push(custom_id.to_string());
+    Ok(())
+}
+
+fn validate_text(field: &str, value: &str, max: usize) -> Result<(), String> {
+    if value.trim().is_empty() || value.chars().count() > max {
+        return Err(format!("Component {field} must be 1-{max} characters"));
+    }
+    Ok(())
+}
+
+fn validate_button(button: &Button, seen: &mut Vec<String>) -> Result<(), String> {
+    validate_text("label", &button.label, MAX_LABEL_LENGTH)?;
+
+    if button.style == ButtonStyle::Link {
+        if button.custom_id.is_some() {
+            return Err("Link buttons cannot have a custom_id".to_string());
+        }
+        let url = button
+            .url
+            .as_deref()
+            .ok_or_else(|| "Link buttons require a url".to_string())?;
+        if url.len() > MAX_URL_LENGTH
+            || !(url.starts_with("https://") || url.starts_with("http://"))
+        {
+            return Err("Link button url must be an http(s) URL".to_string());
+        }
+        return Ok(());
+    }
+
+    if button.url.is_some() {
+        return Err("Only link buttons can have a url".to_string());
+    }
+    let custom_id = button
+        .custom_id
+        .as_deref()
+        .ok_or_else(|| "Buttons require a custom_id".to_string())?;
+    validate_custom_id(custom_id, seen)
+}
+
+fn validate_select_menu(menu: &SelectMenu, seen: &mut Vec<String>) -> Result<(), String> {
+    validate_custom_id(&menu.custom_id, seen)?;
+
+    if let Some(placeholder) = &menu.placeholder {
+        validate_text("placeholder", placeholder, MAX_PLACEHOLDER_LENGTH)?;
+    }
+    if menu.options.is_empty() || menu.options.len() > MAX_SELECT_OPTIONS {
+        return Err(format!(
+            "Select menus must have 1-{MAX_SELECT_OPTIONS} options"
+        ));
+    }
+    if menu.min_values > menu.max_values || usize::from(menu.max_values) > menu.options.len() {
+        return Err(
+            "Select menu min_values/max_values must satisfy min <= max <= option count".to_string(),
+        );
+    }
+
+    let mut values: Vec<&str> = Vec::with_capacity(menu.options.len());
+    for option in &menu.options {
+        validate_text("option label", &option.label, MAX_LABEL_LENGTH)?;
+        validate_text("option value", &option.value, MAX_OPTION_VALUE_LENGTH)?;
+        if let Some(description) = &option.description {
+            validate_text("option description", description, MAX_OPTION_VALUE_LENGTH)?;
+        }
+        if values.contains(&option.value.as_str()) {
+            return Err(format!("Duplicate select option value '{}'", option.value));
+        }
+        values.push(&option.value);
+    }
+    Ok(())
+}
+
+/// Validate action rows before they are persisted on a message.
+///
+/// Enforces row/component limits, a single select menu per row, and
+/// custom IDs that are unique within the message.
+pub fn validate_components(rows: &[ActionRow]) -> Result<(), String> {
+    if rows.len() > MAX_ACTION_ROWS {
+        return Err(format!(
+            "Messages can have at most {MAX_ACTION_ROWS} action rows"
+        ));
+    }
+
+    let mut seen = Vec::new();
+    for row in rows {
+        let has_select = row
+            .components
+            .iter()
+            .any(|c| matches!(c, Component::SelectMenu(_)));
+
+        if row.components.is_empty() {
+            return Err("Action rows must contain at least one component".to_string());
+        }
+        if has_select && row.components.len() > 1 {
+            return Err("A select menu must be the only component in its row".to_string());
+        }
+        if row.components.len() > MAX_BUTTONS_PER_ROW {
+            return Err(format!(
+                "Action rows can have at most {MAX_BUTTONS_PER_ROW} buttons"
+            ));
+        }
+
+        for component in &row.components {
+            match component {
+                Component::Button(button) => validate_button(button, &mut seen)?,
+                Component::SelectMenu(menu) => validate_select_menu(menu, &mut seen)?,
+                Component::Sticker(_) => {
+                    return Err("Stickers cannot be attached as components".to_string())
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Serialize validated action rows for storage (`None` when there are none).
+pub fn to_stored(rows: Option<&[ActionRow]>) -> Option<serde_json::Value> {
+    rows.filter(|r| !r.is_empty())
+        .and_then(|r| serde_json::to_value(r).ok())
+}
+
+/// Parse action rows from the stored `messages.components` column.
+pub fn from_stored(value: Option<&serde_json::Value>) -> Option<Vec<ActionRow>> {
+    value
+        .and_then(|v| serde_json::from_value::<Vec<ActionRow>>(v.clone()).ok())
+        .filter(|rows| !rows.is_empty())
+}
+
+/// Find an interactive (non-link) component by custom ID.
+pub fn find_component<'a>(rows: &'a [ActionRow], custom_id: &str) -> Option<&'a Component> {
+    rows.iter()
+        .flat_map(|row| &row.components)
+        .find(|component| match component {
+            Component::Button(b) => b.custom_id.as_deref() == Some(custom_id),
+            Component::SelectMenu(m) => m.custom_id == custom_id,
+            Component::Sticker(_) => false,
+        })
+}
+
+/// Check submitted interaction values against the component definition.
+fn validate_interaction_values(component: &Component, values: &[String]) -> Result<(), String> {
+    match component {
+        Component::Button(button) => {
+            if button.disabled {
+                return Err("Component is disabled".to_string());
+            }
+            if !values.is_empty() {
+                return Err("Buttons do not accept values".to_string());
+            }
+        }
+        Component::SelectMenu(menu) => {
+            if menu.disabled {
+                return Err("Component is disabled".to_string());
+            }
+            if values.len() < usize::from(menu.min_values)
+                || values.len() > usize::from(menu.max_values)
+            {
+                return Err(format!(
+                    "Select between {} and {} options",
+                    menu.min_values, menu.max_values
+                ));
+            }
+            for (idx, value) in values.iter().enumerate() {
+                if !menu.options.iter().any(|o| &o.value == value) {
+                    return Err(format!("Unknown select option '{value}'"));
+                }
+                if values[..idx].contains(value) {
+                    return Err(format!("Duplicate select option '{value}'"));
+                }
+            }
+        }
+        Component::Sticker(_) => return Err("Stickers are not interactive".to_string()),
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Interact with a message component.
+///
+/// POST /api/messages/{id}/interactions
+///
+/// Relays the click to the bot that authored the message. The bot has
+/// `INTERACTION_TTL_SECS` to answer with a `component_response`; if nothing
+/// arrives within 30 seconds the user receives `component_interaction_timeout`.
+#[utoipa::path(
+    post,
+    path = "/api/messages/{id}/interactions",
+    tag = "messages",
+    params(("id" = Uuid, Path, description = "Message ID")),
+    request_body = ComponentInteractionRequest,
+    responses(
+        (status = 202, body = ComponentInteractionResponse),
+        (status = 400, description = "Unknown, disabled or invalid component interaction"),
+        (status = 403, description = "No access to channel"),
+        (status = 404, description = "Message not found"),
+        (status = 503, description = "Bot interaction routing unavailable"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, body), fields(user_id = %auth_user.id, message_id = %id))]
+pub async fn interact(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ComponentInteractionRequest>,
+) -> Result<(StatusCode, Json<ComponentInteractionResponse>), MessageError> {
+    let message = db::find_message_by_id(&state.db, id)
+        .await?
+        .ok_or(MessageError::NotFound)?;
+
+    crate::permissions::require_channel_access(&state.db, auth_user.id, message.channel_id)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+
+    let rows = from_stored(message.components.as_ref())
+        .ok_or_else(|| MessageError::Validation("Message has no components".to_string()))?;
+    let component = find_component(&rows, &body.custom_id)
+        .ok_or_else(|| MessageError::Validation("Unknown component".to_string()))?;
+    validate_interaction_values(component, &body.values).map_err(MessageError::Validation)?;
+
+    // Components are only interactive on messages authored by a bot application
+    let bot_user_id = message.user_id.ok_or(MessageError::NotFound)?;
+    let application_id: Uuid =
+        sqlx::query_scalar("SELECT id FROM bot_applications WHERE bot_user_id = $1")
+            .bind(bot_user_id)
+            .fetch_optional(&state.db)
+            .await?
+            .ok_or_else(|| MessageError::Validation("Message is not owned by a bot".to_string()))?;
+
+    let channel = db::find_channel_by_id(&state.db, message.channel_id)
+        .await?
+        .ok_or(MessageError::ChannelNotFound)?;
+
+    let interaction_id = Uuid::new_v4();
+    let component_type = match component {
+        Component::Button(_) => "button",
+        Component::SelectMenu(_) => "select_menu",
+        Component::Sticker(_) => "sticker",
+    };
+
+    let event = BotServerEvent::ComponentInteraction {
+        interaction_id,
+        message_id: message.id,
+        guild_id: channel.guild_id,
+        channel_id: channel.id,
+        user_id: auth_user.id,
+        custom_id: body.custom_id.clone(),
+        component_type: component_type.to_string(),
+        values: body.values.clone(),
+    };
+    let payload = serde_json::to_string(&event).map_err(|e| {
+        warn!(error = %e, "Failed to serialize component interaction payload");
+        MessageError::Validation("Invalid component interaction payload".to_string())
+    })?;
+
+    let routing_unavailable =
+        || MessageError::Unavailable("Bot interaction routing unavailable".to_string());
+
+    let owner_key = format!("interaction:{interaction_id}:owner");
+    state
+        .redis
+        .set::<(), _, _>(
+            &owner_key,
+            bot_user_id.to_string(),
+            Some(fred::types::Expiration::EX(INTERACTION_TTL_SECS)),
+            None,
+            false,
+        )
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to store component interaction owner");
+            routing_unavailable()
+        })?;
+
+    // Store interaction context for response delivery
+    let context_key = format!("interaction:{interaction_id}:context");
+    let context_data = serde_json::json!({
+        "kind": "component",
+        "user_id": auth_user.id,
+        "channel_id": channel.id,
+        "guild_id": channel.guild_id,
+        "message_id": message.id,
+        "custom_id": body.custom_id,
+    });
+    state
+        .redis
+        .set::<(), _, _>(
+            &context_key,
+            context_data.to_string(),
+            Some(fred::types::Expiration::EX(INTERACTION_TTL_SECS)),
+            None,
+            false,
+        )
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to store component interaction context");
+            routing_unavailable()
+        })?;
+
+    let pending = crate::ws::interactions::register(&state.redis, interaction_id).await;
+    state
+        .redis
+        .publish::<(), _, _>(format!("bot:{bot_user_id}"), payload)
+        .await
+        .map_err(|e| {
+            error!(error = %e, "Failed to publish component interaction");
+            routing_unavailable()
+        })?;
+
+    // Dispatch component.interaction to webhooks (non-blocking)
+    {
+        let wh_db = state.db.clone();
+        let wh_redis = state.redis.clone();
+        let wh_payload = serde_json::to_value(&event).unwrap_or_default();
+        tokio::spawn(async move {
+            crate::webhooks::dispatch::dispatch_app_event(
+                &wh_db,
+                &wh_redis,
+                application_id,
+                crate::webhooks::events::BotEventType::ComponentInteraction,
+                wh_payload,
+            )
+            .await;
+        });
+    }
+
+    // Report a timeout unless the bot answers in time
+    {
+        let timeout_redis = state.redis.clone();
+        let invoker_id = auth_user.id;
+        let message_id = message.id;
+        let channel_id = channel.id;
+        tokio::spawn(async move {
+            let timeout = std::time::Duration::from_secs(INTERACTION_TIMEOUT_SECS);
+            if pending.wait(&timeout_redis, timeout).await.is_none() {
+                let event = ServerEvent::ComponentInteractionTimeout {
+                    interaction_id,
+                    message_id,
+                    channel_id,
+                };
+                let _ = broadcast_to_user(&timeout_redis, invoker_id, &event).await;
+            }
+        });
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ComponentInteractionResponse { interaction_id }),
+    ))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn button(custom_id: &str) -> Component {
+        Component::Button(Button {
+            style: ButtonStyle::Primary,
+            label: "Click".to_string(),
+            custom_id: Some(custom_id.to_string()),
+            url: None,
+            disabled: false,
+        })
+    }
+
+    fn select(custom_id: &str, values: &[&str], max_values: u8) -> Component {
+        Component::SelectMenu(SelectMenu {
+            custom_id: custom_id.to_string(),
+            placeholder: None,
+            options: values
+                .iter()
+                .map(|v| SelectOption {
+                    label: v.to_string(),
+                    value: v.to_string(),
+                    description: None,
+                })
+                .collect(),
+            min_values: 1,
+            max_values,
+            disabled: false,
+        })
+    }
+
+    fn row(components: Vec<Component>) -> ActionRow {
+        ActionRow { components }
+    }
+
+    #[test]
+    fn test_valid_components_pass() {
+        let rows = vec![
+            row(vec![button("yes"), button("no")]),
+            row(vec![select("pick", &["a", "b", "c"], 2)]),
+        ];
+        assert!(validate_components(&rows).is_ok());
+    }
+
+    #[test]
+    fn test_deserialize_tagged_components() {
+        let json = serde_json::json!([{
+            "components": [
+                { "type": "button", "style": "link", "label": "Docs", "url": "https://example.com" },
+                { "type": "button", "style": "danger", "label": "Delete", "custom_id": "delete" }
+            ]
+        }]);
+        let rows: Vec<ActionRow> = serde_json::from_value(json).unwrap();
+        assert!(validate_components(&rows).is_ok());
+        assert!(find_component(&rows, "delete").is_some());
+    }
+
+    #[test]
+    fn test_custom_id_rules() {
+        let duplicate = vec![row(vec![button("same")]), row(vec![button("same")])];
+        assert!(validate_components(&duplicate).is_err());
+
+        let too_long = vec![row(vec![button(&"x".repeat(MAX_CUSTOM_ID_LENGTH + 1))])];
+        assert!(validate_components(&too_long).is_err());
+
+        let empty = vec![row(vec![button("")])];
+        assert!(validate_components(&empty).is_err());
+    }
+
+    #[test]
+    fn test_layout_limits() {
+        let too_many_rows: Vec<ActionRow> = (0..=MAX_ACTION_ROWS)
+            .map(|i| row(vec![button(&format!("b{i}"))]))
+            .collect();
+        assert!(validate_components(&too_many_rows).is_err());
+
+        let too_many_buttons = vec![row((0..=MAX_BUTTONS_PER_ROW)
+            .map(|i| button(&format!("b{i}")))
+            .collect())];
+        assert!(validate_components(&too_many_buttons).is_err());
+
+        let mixed = vec![row(vec![button("b"), select("s", &["a"], 1)])];
+        assert!(validate_components(&mixed).is_err());
+    }
+
+    #[test]
+    fn test_interaction_values() {
+        let menu = select("pick", &["a", "b", "c"], 2);
+        assert!(validate_interaction_values(&menu, &["a".to_string()]).is_ok());
+        assert!(validate_interaction_values(&menu, &[]).is_err());
+        assert!(validate_interaction_values(&menu, &["z".to_string()]).is_err());
+        assert!(validate_interaction_values(&menu, &["a".to_string(), "a".to_string()]).is_err());
+        assert!(validate_interaction_values(&button("ok"), &["a".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_stickers_are_server_attached_only() {
+        let sticker = Component::Sticker(Sticker {
+            sticker_id: Uuid::new_v4(),
+            guild_id: Uuid::new_v4(),
+            name: "wave".to_string(),
+            format: "png".to_string(),
+            url: "/api/files/stickers/wave.png".to_string(),
+            preview_url: "/api/files/stickers/wave_preview.webp".to_string(),
+            width: 160,
+            height: 160,
+        });
+        let rows = vec![row(vec![sticker.clone()])];
+        assert!(validate_components(&rows).is_err());
+        assert!(validate_interaction_values(&sticker, &[]).is_err());
+
+        // Stored sticker rows still round-trip for rendering
+        let stored = to_stored(Some(&rows));
+        assert_eq!(from_stored(stored.as_ref()), Some(rows));
+    }
+}
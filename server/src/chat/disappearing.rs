@@ -0,0 +1,485 @@
+//! Disappearing Messages
+//!
+//! A channel can give new messages a lifetime, from 5 minutes to 4 weeks.
+//! The timer lives on the channel row and a trigger stamps `expires_at` on
+//! every message posted while it is set (see the `disappearing_messages`
+//! migration), so it applies to plaintext and encrypted messages alike.
+//! The expiry sweep hard-deletes messages once they expire, removes their
+//! attachment objects from S3 and announces each one with `MessageDelete`.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::Json;
+use fred::prelude::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{debug, warn};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::bulk_delete::{detach_attachments, refresh_thread_counters};
+use super::channels::ChannelError;
+use super::S3Client;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db::{self, ChannelType};
+use crate::permissions::GuildPermissions;
+use crate::ws::{broadcast_to_channel, ServerEvent};
+
+/// Shortest message lifetime (5 minutes), matching the database constraint.
+pub const MIN_MESSAGE_TTL_SECONDS: i32 = 5 * 60;
+
+/// Longest message lifetime (4 weeks), matching the database constraint.
+pub const MAX_MESSAGE_TTL_SECONDS: i32 = 28 * 24 * 60 * 60;
+
+/// How often the sweep looks for expired messages.
+pub const EXPIRY_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Maximum number of expired messages deleted per batch.
+const EXPIRY_BATCH_SIZE: i64 = 500;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Set or turn off a channel's disappearing message timer.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct DisappearingMessagesRequest {
+    /// Lifetime of new messages in seconds; `null` turns the timer off.
+    #[validate(range(
+        min = MIN_MESSAGE_TTL_SECONDS,
+        max = MAX_MESSAGE_TTL_SECONDS,
+        message = "Timer must be between 300 seconds and 4 weeks"
+    ))]
+    pub message_ttl_seconds: Option<i32>,
+}
+
+/// Disappearing message timer of a channel.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DisappearingMessagesResponse {
+    pub channel_id: Uuid,
+    /// Lifetime of new messages in seconds (`null` = off).
+    pub message_ttl_seconds: Option<i32>,
+}
+
+/// A message removed by the expiry sweep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpiredMessage {
+    pub id: Uuid,
+    pub channel_id: Uuid,
+}
+
+/// Human-readable timer, e.g. "1 week" or "90 minutes".
+#[must_use]
+pub fn format_ttl(seconds: i32) -> String {
+    const UNITS: [(i32, &str); 4] = [
+        (7 * 24 * 60 * 60, "week"),
+        (24 * 60 * 60, "day"),
+        (60 * 60, "hour"),
+        (60, "minute"),
+    ];
+    let (count, unit) = UNITS
+        .iter()
+        .find(|(size, _)| seconds % size == 0)
+        .map_or((seconds, "second"), |(size, unit)| (seconds / size, *unit));
+    if count == 1 {
+        format!("1 {unit}")
+    } else {
+        format!("{count} {unit}s")
+    }
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Hard-delete up to `limit` expired messages, with the thread replies and
+/// attachments they take along.
+///
+/// Returns the deleted messages and the S3 keys no other attachment still
+/// references.
+pub async fn delete_expired(
+    pool: &PgPool,
+    limit: i64,
+) -> sqlx::Result<(Vec<ExpiredMessage>, Vec<String>)> {
+    let mut tx = pool.begin().await?;
+
+    // Replies are deleted with their parent (ON DELETE CASCADE), so collect
+    // them too, or their attachments and delete events would be lost
+    let ids: Vec<Uuid> = sqlx::query_scalar(
+        r"
+        WITH expired AS (
+            SELECT id FROM messages
+            WHERE expires_at <= NOW()
+            ORDER BY expires_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        SELECT id FROM expired
+        UNION
+        SELECT m.id FROM messages m INNER JOIN expired e ON m.parent_id = e.id
+        ",
+    )
+    .bind(limit)
+    .fetch_all(&mut *tx)
+    .await?;
+    if ids.is_empty() {
+        return Ok((Vec::new(), Vec::new()));
+    }
+
+    let keys = detach_attachments(&mut tx, &ids).await?;
+
+    let deleted: Vec<(Uuid, Uuid, Option<Uuid>)> = sqlx::query_as(
+        "DELETE FROM messages WHERE id = ANY($1) RETURNING id, channel_id, parent_id",
+    )
+    .bind(&ids)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let parent_ids: Vec<Uuid> = deleted
+        .iter()
+        .filter_map(|(_, _, parent)| *parent)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    refresh_thread_counters(&mut tx, &parent_ids).await?;
+
+    tx.commit().await?;
+    Ok((
+        deleted
+            .into_iter()
+            .map(|(id, channel_id, _)| ExpiredMessage { id, channel_id })
+            .collect(),
+        keys,
+    ))
+}
+
+// ============================================================================
+// Expiry Sweep
+// ============================================================================
+
+/// Spawn a background task that deletes expired messages every 30 seconds.
+pub fn spawn_expiry_sweep(
+    db: PgPool,
+    redis: Client,
+    s3: Option<S3Client>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            match sweep_expired(&db, &redis, s3.as_ref()).await {
+                Ok(deleted) if deleted > 0 => debug!(deleted, "Deleted expired messages"),
+                Err(e) => warn!(error = %e, "Message expiry sweep failed"),
+                _ => {}
+            }
+        }
+    })
+}
+
+/// Delete every expired message, batch by batch. Returns how many were deleted.
+pub async fn sweep_expired(
+    db: &PgPool,
+    redis: &Client,
+    s3: Option<&S3Client>,
+) -> sqlx::Result<usize> {
+    let mut total = 0;
+    loop {
+        let (deleted, keys) = delete_expired(db, EXPIRY_BATCH_SIZE).await?;
+        if deleted.is_empty() {
+            return Ok(total);
+        }
+        total += deleted.len();
+
+        for message in &deleted {
+            if let Err(e) = broadcast_to_channel(
+                redis,
+                message.channel_id,
+                &ServerEvent::MessageDelete {
+                    channel_id: message.channel_id,
+                    message_id: message.id,
+                },
+            )
+            .await
+            {
+                warn!(message_id = %message.id, error = %e, "Failed to broadcast expired message delete");
+            }
+        }
+
+        if let Some(s3) = s3 {
+            for key in keys {
+                if let Err(e) = s3.delete(&key).await {
+                    warn!(s3_key = %key, error = %e, "Failed to delete expired attachment");
+                }
+            }
+        }
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Set a channel's disappearing message timer.
+///
+/// Any DM participant may change it; guild text channels need
+/// `MANAGE_CHANNELS`. The timer applies to messages sent afterwards, and a
+/// system message announces every change.
+///
+/// PUT /api/channels/:id/disappearing-messages
+#[utoipa::path(
+    put,
+    path = "/api/channels/{id}/disappearing-messages",
+    tag = "channels",
+    params(("id" = Uuid, Path, description = "Channel ID")),
+    request_body = DisappearingMessagesRequest,
+    responses(
+        (status = 200, body = DisappearingMessagesResponse),
+        (status = 400, description = "Invalid timer or voice channel"),
+        (status = 403, description = "Missing MANAGE_CHANNELS"),
+        (status = 404, description = "Channel not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, body), fields(user_id = %auth_user.id, channel_id = %id))]
+pub async fn set_disappearing_messages(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(body): Json<DisappearingMessagesRequest>,
+) -> Result<Json<DisappearingMessagesResponse>, ChannelError> {
+    body.validate()
+        .map_err(|e| ChannelError::Validation(e.to_string()))?;
+
+    let ctx = crate::permissions::require_channel_access(&state.db, auth_user.id, id)
+        .await
+        .map_err(|_| ChannelError::Forbidden)?;
+
+    let channel = db::find_channel_by_id(&state.db, id)
+        .await?
+        .ok_or(ChannelError::NotFound)?;
+
+    match channel.channel_type {
+        ChannelType::Dm => {}
+        ChannelType::Text => {
+            if !ctx.has_permission(GuildPermissions::MANAGE_CHANNELS) {
+                return Err(ChannelError::Forbidden);
+            }
+        }
+        ChannelType::Voice => {
+            return Err(ChannelError::Validation(
+                "Voice channels can't have disappearing messages".to_string(),
+            ));
+        }
+    }
+
+    let ttl = body.message_ttl_seconds;
+    let changed = sqlx::query(
+        r"
+        UPDATE channels SET message_ttl_seconds = $2, updated_at = NOW()
+        WHERE id = $1 AND message_ttl_seconds IS DISTINCT FROM $2
+        ",
+    )
+    .bind(id)
+    .bind(ttl)
+    .execute(&state.db)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if changed {
+        tracing::info!(message_ttl_seconds = ?ttl, "Disappearing messages timer changed");
+        announce_change(&state, id, auth_user.id, ttl).await?;
+    }
+
+    Ok(Json(DisappearingMessagesResponse {
+        channel_id: id,
+        message_ttl_seconds: ttl,
+    }))
+}
+
+/// Post the system message for a timer change and broadcast the new timer.
+async fn announce_change(
+    state: &AppState,
+    channel_id: Uuid,
+    user_id: Uuid,
+    ttl: Option<i32>,
+) -> Result<(), ChannelError> {
+    let content = ttl.map_or_else(
+        || "turned off disappearing messages.".to_string(),
+        |seconds| format!("set disappearing messages to {}.", format_ttl(seconds)),
+    );
+    let sys_msg = sqlx::query_as::<_, db::Message>(
+        r"
+        INSERT INTO messages (channel_id, user_id, content, message_type)
+        VALUES ($1, $2, $3, 'system')
+        RETURNING *
+        ",
+    )
+    .bind(channel_id)
+    .bind(user_id)
+    .bind(content)
+    .fetch_one(&state.db)
+    .await?;
+
+    let sys_responses =
+        crate::chat::messages::build_message_responses(&state.db, user_id, vec![sys_msg])
+            .await
+            .unwrap_or_default();
+    if let Some(sys_response) = sys_responses.into_iter().next() {
+        let message_json = serde_json::to_value(&sys_response).unwrap_or_default();
+        if let Err(e) = broadcast_to_channel(
+            &state.redis,
+            channel_id,
+            &ServerEvent::MessageNew {
+                channel_id,
+                message: message_json,
+            },
+        )
+        .await
+        {
+            warn!(error = %e, "Failed to broadcast system message for timer change");
+        }
+    }
+
+    if let Err(e) = broadcast_to_channel(
+        &state.redis,
+        channel_id,
+        &ServerEvent::DisappearingMessagesUpdated {
+            channel_id,
+            message_ttl_seconds: ttl,
+            updated_by: user_id,
+        },
+    )
+    .await
+    {
+        warn!(error = %e, "Failed to broadcast DisappearingMessagesUpdated event");
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures;
+
+    #[test]
+    fn test_timer_bounds_and_labels() {
+        let request = |seconds| DisappearingMessagesRequest {
+            message_ttl_seconds: seconds,
+        };
+        assert!(request(None).validate().is_ok());
+        assert!(request(Some(MIN_MESSAGE_TTL_SECONDS)).validate().is_ok());
+        assert!(request(Some(MAX_MESSAGE_TTL_SECONDS)).validate().is_ok());
+        assert!(request(Some(MIN_MESSAGE_TTL_SECONDS - 1))
+            .validate()
+            .is_err());
+        assert!(request(Some(MAX_MESSAGE_TTL_SECONDS + 1))
+            .validate()
+            .is_err());
+
+        assert_eq!(format_ttl(300), "5 minutes");
+        assert_eq!(format_ttl(5400), "90 minutes");
+        assert_eq!(format_ttl(3600), "1 hour");
+        assert_eq!(format_ttl(86_400), "1 day");
+        assert_eq!(format_ttl(MAX_MESSAGE_TTL_SECONDS), "4 weeks");
+        assert_eq!(format_ttl(301), "301 seconds");
+    }
+
+    #[sqlx::test]
+    async fn expired_messages_are_deleted_with_replies_and_attachments(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let channel_id = fixtures::create_dm(&pool, "dm", &[]).await;
+
+        let kept = db::create_message(&pool, channel_id, user.id, "before", false, None, None)
+            .await
+            .expect("create message");
+        assert!(kept.expires_at.is_none());
+
+        sqlx::query("UPDATE channels SET message_ttl_seconds = 300 WHERE id = $1")
+            .bind(channel_id)
+            .execute(&pool)
+            .await
+            .expect("set timer");
+        let parent = db::create_message(&pool, channel_id, user.id, "secret", false, None, None)
+            .await
+            .expect("create message");
+        assert_eq!(
+            parent.expires_at,
+            Some(parent.created_at + chrono::Duration::seconds(300))
+        );
+        let reply = db::create_thread_reply(
+            &pool,
+            db::CreateThreadReplyParams {
+                parent_id: parent.id,
+                channel_id,
+                user_id: user.id,
+                content: "reply",
+                encrypted: false,
+                nonce: None,
+                reply_to: None,
+            },
+        )
+        .await
+        .expect("create reply");
+        for (message_id, key) in [
+            (parent.id, "a.png"),
+            (reply.id, "b.png"),
+            (kept.id, "b.png"),
+        ] {
+            sqlx::query(
+                "INSERT INTO file_attachments (message_id, filename, mime_type, size_bytes, s3_key) VALUES ($1, 'f.png', 'image/png', 1, $2)",
+            )
+            .bind(message_id)
+            .bind(key)
+            .execute(&pool)
+            .await
+            .expect("insert attachment");
+        }
+
+        // Nothing has expired yet
+        let (deleted, _) = delete_expired(&pool, 10).await.expect("delete");
+        assert!(deleted.is_empty());
+
+        sqlx::query("UPDATE messages SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+            .bind(parent.id)
+            .execute(&pool)
+            .await
+            .expect("expire message");
+        let (deleted, keys) = delete_expired(&pool, 10).await.expect("delete");
+        let mut deleted_ids: Vec<Uuid> = deleted.iter().map(|m| m.id).collect();
+        deleted_ids.sort_unstable();
+        let mut expected = vec![parent.id, reply.id];
+        expected.sort_unstable();
+        assert_eq!(deleted_ids, expected);
+        assert!(deleted.iter().all(|m| m.channel_id == channel_id));
+        // b.png is still used by the surviving message
+        assert_eq!(keys, vec!["a.png".to_string()]);
+
+        let remaining: Vec<Uuid> =
+            sqlx::query_scalar("SELECT id FROM messages WHERE channel_id = $1")
+                .bind(channel_id)
+                .fetch_all(&pool)
+                .await
+                .expect("list messages");
+        assert_eq!(remaining, vec![kept.id]);
+
+        // The timer must stay within bounds
+        assert!(
+            sqlx::query("UPDATE channels SET message_ttl_seconds = 60 WHERE id = $1")
+                .bind(channel_id)
+                .execute(&pool)
+                .await
+                .is_err()
+        );
+    }
+}
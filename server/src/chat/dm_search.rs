@@ -111,6 +111,9 @@ pub struct DmSearchResponse {
     pub total: i64,
     pub limit: i64,
     pub offset: i64,
+    /// Searched DMs that are end-to-end encrypted. Their encrypted messages
+    /// can't be searched by the server.
+    pub encrypted_channel_ids: Vec<Uuid>,
 }
 
 // ============================================================================
@@ -202,9 +205,16 @@ pub async fn search_dm_messages(
             total: 0,
             limit: query.limit,
             offset: query.offset,
+            encrypted_channel_ids: vec![],
         }));
     }
 
+    let encrypted_channel_ids: Vec<Uuid> = dm_channels
+        .iter()
+        .filter(|c| c.encrypted && dm_channel_ids.contains(&c.id))
+        .map(|c| c.id)
+        .collect();
+
     // Build search filters
     let filters = db::SearchFilters {
         date_from: query.date_from,
@@ -304,5 +314,6 @@ pub async fn search_dm_messages(
         total,
         limit,
         offset,
+        encrypted_channel_ids,
     }))
 }
@@ -0,0 +1,353 @@
+//! End-to-End Encrypted Channels
+//!
+//! Group DMs and private guild channels (hidden from `@everyone`) can be
+//! switched to Megolm encryption. The switch is one-way: afterwards the server
+//! refuses plaintext messages in the channel. Every change that may alter who
+//! can read the channel bumps its `encryption_epoch` (see the
+//! `encrypted_channels` migration), and messages must name the current epoch,
+//! so clients rotate their outbound session before sending to new members or
+//! after members left.
+
+use axum::extract::{Path, State};
+use axum::Json;
+use serde::Serialize;
+use sqlx::PgPool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::channels::ChannelError;
+use super::messages::MessageError;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db::{self, ChannelType};
+use crate::permissions::GuildPermissions;
+use crate::ws::{broadcast_to_channel, ServerEvent};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Encryption state of a channel.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ChannelEncryptionResponse {
+    pub channel_id: Uuid,
+    /// Messages must be end-to-end encrypted.
+    pub encrypted: bool,
+    /// Current membership epoch; Megolm sessions from older epochs must be
+    /// rotated before sending.
+    pub epoch: i64,
+    /// Users who can read the channel. Every device of these users needs the
+    /// sender's Megolm session key.
+    pub members: Vec<Uuid>,
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Whether a channel only accepts encrypted messages.
+pub async fn is_encrypted(pool: &PgPool, channel_id: Uuid) -> sqlx::Result<bool> {
+    Ok(
+        sqlx::query_scalar("SELECT encrypted FROM channels WHERE id = $1")
+            .bind(channel_id)
+            .fetch_optional(pool)
+            .await?
+            .unwrap_or(false),
+    )
+}
+
+/// Whether a guild channel is hidden from `@everyone`.
+async fn is_private_channel(pool: &PgPool, channel_id: Uuid) -> sqlx::Result<bool> {
+    sqlx::query_scalar(
+        r"
+        SELECT EXISTS(
+            SELECT 1 FROM channel_overrides o
+            INNER JOIN guild_roles r ON r.id = o.role_id
+            WHERE o.channel_id = $1 AND r.is_default AND (o.deny_permissions & $2) <> 0
+        )
+        ",
+    )
+    .bind(channel_id)
+    .bind(GuildPermissions::VIEW_CHANNEL.to_db())
+    .fetch_one(pool)
+    .await
+}
+
+/// Users who can read a channel: DM participants, or the guild members with
+/// `VIEW_CHANNEL`.
+pub async fn channel_readers(pool: &PgPool, channel: &db::Channel) -> sqlx::Result<Vec<Uuid>> {
+    let Some(guild_id) = channel.guild_id else {
+        return sqlx::query_scalar(
+            "SELECT user_id FROM dm_participants WHERE channel_id = $1 ORDER BY user_id",
+        )
+        .bind(channel.id)
+        .fetch_all(pool)
+        .await;
+    };
+
+    let members: Vec<Uuid> =
+        sqlx::query_scalar("SELECT user_id FROM guild_members WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_all(pool)
+            .await?;
+    let mut readers =
+        crate::permissions::filter_channel_viewers(pool, guild_id, channel.id, &members).await?;
+    readers.sort_unstable();
+    Ok(readers)
+}
+
+/// Check a new message against the channel's encryption requirements.
+///
+/// Encrypted channels only take encrypted messages that name the channel's
+/// current epoch.
+pub const fn check_message(
+    channel: &db::Channel,
+    encrypted: bool,
+    epoch: Option<i64>,
+) -> Result<(), MessageError> {
+    if !channel.encrypted {
+        return Ok(());
+    }
+    if !encrypted {
+        return Err(MessageError::EncryptionRequired);
+    }
+    match epoch {
+        Some(epoch) if epoch == channel.encryption_epoch => Ok(()),
+        _ => Err(MessageError::StaleEncryptionEpoch {
+            epoch: channel.encryption_epoch,
+        }),
+    }
+}
+
+/// Whether `channel` takes client-encrypted files the server can't inspect.
+///
+/// Only encrypted channels and DMs do; uploads anywhere else go through the
+/// usual type, signature and content checks whatever the client claims.
+pub const fn accepts_ciphertext(channel: &db::Channel) -> bool {
+    channel.encrypted || channel.guild_id.is_none()
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Get a channel's encryption state and the users who hold its keys.
+///
+/// GET /api/channels/:id/encryption
+#[utoipa::path(
+    get,
+    path = "/api/channels/{id}/encryption",
+    tag = "channels",
+    params(("id" = Uuid, Path, description = "Channel ID")),
+    responses(
+        (status = 200, body = ChannelEncryptionResponse),
+        (status = 403, description = "No access to the channel"),
+        (status = 404, description = "Channel not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id, channel_id = %id))]
+pub async fn get_encryption(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ChannelEncryptionResponse>, ChannelError> {
+    crate::permissions::require_channel_access(&state.db, auth_user.id, id)
+        .await
+        .map_err(|_| ChannelError::Forbidden)?;
+
+    let channel = db::find_channel_by_id(&state.db, id)
+        .await?
+        .ok_or(ChannelError::NotFound)?;
+
+    Ok(Json(ChannelEncryptionResponse {
+        channel_id: channel.id,
+        encrypted: channel.encrypted,
+        epoch: channel.encryption_epoch,
+        members: channel_readers(&state.db, &channel).await?,
+    }))
+}
+
+/// Turn on end-to-end encryption for a group DM or private guild channel.
+///
+/// Any DM participant may enable it; guild channels need `MANAGE_CHANNELS`
+/// and must be text channels hidden from `@everyone`. Encryption can't be
+/// turned off again.
+///
+/// PUT /api/channels/:id/encryption
+#[utoipa::path(
+    put,
+    path = "/api/channels/{id}/encryption",
+    tag = "channels",
+    params(("id" = Uuid, Path, description = "Channel ID")),
+    responses(
+        (status = 200, body = ChannelEncryptionResponse),
+        (status = 400, description = "Not a DM or private text channel"),
+        (status = 403, description = "Missing MANAGE_CHANNELS"),
+        (status = 404, description = "Channel not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id, channel_id = %id))]
+pub async fn enable_encryption(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ChannelEncryptionResponse>, ChannelError> {
+    let ctx = crate::permissions::require_channel_access(&state.db, auth_user.id, id)
+        .await
+        .map_err(|_| ChannelError::Forbidden)?;
+
+    let channel = db::find_channel_by_id(&state.db, id)
+        .await?
+        .ok_or(ChannelError::NotFound)?;
+
+    match channel.channel_type {
+        ChannelType::Dm => {}
+        ChannelType::Text => {
+            if !ctx.has_permission(GuildPermissions::MANAGE_CHANNELS) {
+                return Err(ChannelError::Forbidden);
+            }
+            if !is_private_channel(&state.db, id).await? {
+                return Err(ChannelError::Validation(
+                    "Only channels hidden from @everyone can be encrypted".to_string(),
+                ));
+            }
+        }
+        ChannelType::Voice => {
+            return Err(ChannelError::Validation(
+                "Voice channels can't be encrypted".to_string(),
+            ));
+        }
+    }
+
+    let enabled: Option<i64> = sqlx::query_scalar(
+        r"
+        UPDATE channels SET encrypted = TRUE, updated_at = NOW()
+        WHERE id = $1 AND NOT encrypted
+        RETURNING encryption_epoch
+        ",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    let epoch = enabled.unwrap_or(channel.encryption_epoch);
+    if enabled.is_some() {
+        tracing::info!(epoch, "Channel encryption enabled");
+        if let Err(e) = broadcast_to_channel(
+            &state.redis,
+            id,
+            &ServerEvent::ChannelEncryptionEnabled {
+                channel_id: id,
+                epoch,
+                enabled_by: auth_user.id,
+            },
+        )
+        .await
+        {
+            tracing::warn!(error = %e, "Failed to broadcast ChannelEncryptionEnabled event");
+        }
+    }
+
+    Ok(Json(ChannelEncryptionResponse {
+        channel_id: id,
+        encrypted: true,
+        epoch,
+        members: channel_readers(&state.db, &channel).await?,
+    }))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures;
+
+    #[sqlx::test]
+    async fn membership_changes_bump_the_epoch_of_encrypted_channels(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create user");
+        let carol = db::create_user(&pool, "carol", "Carol", None, "hash")
+            .await
+            .expect("create user");
+        let channel_id = fixtures::create_dm(&pool, "dm", &[alice.id, bob.id]).await;
+
+        // Plaintext channels don't track epochs
+        sqlx::query("INSERT INTO dm_participants (channel_id, user_id) VALUES ($1, $2)")
+            .bind(channel_id)
+            .bind(carol.id)
+            .execute(&pool)
+            .await
+            .expect("add participant");
+        assert_eq!(
+            fixtures::channel(&pool, channel_id).await.encryption_epoch,
+            0
+        );
+
+        sqlx::query("UPDATE channels SET encrypted = TRUE WHERE id = $1")
+            .bind(channel_id)
+            .execute(&pool)
+            .await
+            .expect("enable encryption");
+        sqlx::query("DELETE FROM dm_participants WHERE channel_id = $1 AND user_id = $2")
+            .bind(channel_id)
+            .bind(carol.id)
+            .execute(&pool)
+            .await
+            .expect("remove participant");
+
+        let encrypted = fixtures::channel(&pool, channel_id).await;
+        assert!(encrypted.encrypted);
+        assert_eq!(encrypted.encryption_epoch, 1);
+        let mut expected = vec![alice.id, bob.id];
+        expected.sort_unstable();
+        assert_eq!(
+            channel_readers(&pool, &encrypted).await.expect("readers"),
+            expected
+        );
+
+        // Encryption is one-way
+        assert!(
+            sqlx::query("UPDATE channels SET encrypted = FALSE WHERE id = $1")
+                .bind(channel_id)
+                .execute(&pool)
+                .await
+                .is_err()
+        );
+    }
+
+    #[sqlx::test]
+    async fn encrypted_channels_require_current_epoch(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let channel_id = fixtures::create_dm(&pool, "dm", &[alice.id]).await;
+        let mut channel = fixtures::channel(&pool, channel_id).await;
+
+        assert!(check_message(&channel, false, None).is_ok());
+
+        channel.encrypted = true;
+        channel.encryption_epoch = 2;
+        assert!(matches!(
+            check_message(&channel, false, Some(2)),
+            Err(MessageError::EncryptionRequired)
+        ));
+        assert!(matches!(
+            check_message(&channel, true, Some(1)),
+            Err(MessageError::StaleEncryptionEpoch { epoch: 2 })
+        ));
+        assert!(matches!(
+            check_message(&channel, true, None),
+            Err(MessageError::StaleEncryptionEpoch { epoch: 2 })
+        ));
+        assert!(check_message(&channel, true, Some(2)).is_ok());
+    }
+}
@@ -0,0 +1,475 @@
+//! Message Forwarding
+//!
+//! Forwarding copies a message, with its attachments, into another channel or
+//! DM the user can write to. The copy carries a snapshot of the original and
+//! a link back to it; the link only resolves for viewers who can still see
+//! the source channel. Encrypted originals are only forwarded when the client
+//! re-encrypts them for the target.
+
+use std::collections::HashMap;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::components;
+use super::mentions;
+use super::messages::{
+    check_mention_permissions, enforce_content_filter, require_send_access,
+    validate_message_content, AttachmentInfo, AuthorProfile, MessageError, MessageResponse,
+};
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db;
+use crate::ws::{broadcast_to_channel, ServerEvent};
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Forward a message to another channel.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ForwardMessageRequest {
+    /// Channel or DM to forward to.
+    pub channel_id: Uuid,
+    /// Optional comment shown with the forward. For encrypted forwards this
+    /// is the ciphertext of the re-encrypted original plus any comment.
+    #[serde(default)]
+    pub content: String,
+    /// Whether `content` was encrypted by the client for the target channel.
+    #[serde(default)]
+    pub encrypted: bool,
+    pub nonce: Option<String>,
+    /// Membership epoch of the target channel. Required when forwarding into
+    /// an end-to-end encrypted channel.
+    #[serde(default)]
+    pub encryption_epoch: Option<i64>,
+}
+
+/// Where a forwarded message came from.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ForwardedFrom {
+    /// Original message; only set when the viewer can see its channel.
+    pub message_id: Option<Uuid>,
+    /// Original channel; only set when the viewer can see it.
+    pub channel_id: Option<Uuid>,
+    pub author_id: Option<Uuid>,
+    /// Snapshot of the original content, or `None` when the client
+    /// re-encrypted it into the forward itself.
+    pub content: Option<String>,
+    /// When the original was posted.
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, FromRow)]
+struct ForwardRow {
+    message_id: Uuid,
+    source_message_id: Option<Uuid>,
+    source_channel_id: Option<Uuid>,
+    source_author_id: Option<Uuid>,
+    content: Option<String>,
+    source_created_at: DateTime<Utc>,
+    /// Whether the original message still exists.
+    source_live: bool,
+}
+
+impl ForwardRow {
+    fn into_response(self, link: bool) -> ForwardedFrom {
+        ForwardedFrom {
+            message_id: self.source_message_id.filter(|_| link && self.source_live),
+            channel_id: self.source_channel_id.filter(|_| link),
+            author_id: self.source_author_id,
+            content: self.content,
+            created_at: self.source_created_at,
+        }
+    }
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Load the forward snapshots of `message_ids`, resolving links for `viewer_id`.
+pub async fn load_forwards(
+    pool: &PgPool,
+    viewer_id: Uuid,
+    message_ids: &[Uuid],
+) -> Result<HashMap<Uuid, ForwardedFrom>, MessageError> {
+    let rows = sqlx::query_as::<_, ForwardRow>(
+        r"
+        SELECT f.message_id, f.source_message_id, f.source_channel_id, f.source_author_id,
+               f.content, f.source_created_at, (src.id IS NOT NULL) AS source_live
+        FROM message_forwards f
+        LEFT JOIN messages src ON src.id = f.source_message_id AND src.deleted_at IS NULL
+            AND (src.expires_at IS NULL OR src.expires_at > NOW())
+        WHERE f.message_id = ANY($1)
+        ",
+    )
+    .bind(message_ids)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut source_channels: Vec<Uuid> = rows.iter().filter_map(|r| r.source_channel_id).collect();
+    source_channels.sort_unstable();
+    source_channels.dedup();
+    let viewable = crate::permissions::filter_viewable_channels(pool, viewer_id, &source_channels)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let link = row
+                .source_channel_id
+                .is_some_and(|id| viewable.contains(&id));
+            (row.message_id, row.into_response(link))
+        })
+        .collect())
+}
+
+/// Load a single message's forward snapshot for `viewer_id`.
+pub async fn load_forward(
+    pool: &PgPool,
+    viewer_id: Uuid,
+    message_id: Uuid,
+) -> Result<Option<ForwardedFrom>, MessageError> {
+    Ok(load_forwards(pool, viewer_id, &[message_id])
+        .await?
+        .remove(&message_id))
+}
+
+/// The snapshot a forward of `source` should carry.
+///
+/// Forwarding a forward that has no comment of its own passes the inner
+/// snapshot on, so chains point at the original rather than an empty copy.
+async fn snapshot_source(pool: &PgPool, source: &db::Message) -> sqlx::Result<ForwardRow> {
+    let inner = sqlx::query_as::<_, ForwardRow>(
+        r"
+        SELECT message_id, source_message_id, source_channel_id, source_author_id,
+               content, source_created_at, TRUE AS source_live
+        FROM message_forwards
+        WHERE message_id = $1
+        ",
+    )
+    .bind(source.id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match inner {
+        Some(inner) if source.content.is_empty() => inner,
+        _ => ForwardRow {
+            message_id: source.id,
+            source_message_id: Some(source.id),
+            source_channel_id: Some(source.channel_id),
+            source_author_id: source.user_id,
+            content: Some(source.content.clone()),
+            source_created_at: source.created_at,
+            source_live: true,
+        },
+    })
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Forward a message, with its attachments, to another channel or DM.
+///
+/// POST /api/messages/{id}/forward
+#[utoipa::path(
+    post,
+    path = "/api/messages/{id}/forward",
+    tag = "messages",
+    params(("id" = Uuid, Path, description = "Message to forward")),
+    request_body = ForwardMessageRequest,
+    responses(
+        (status = 201, body = MessageResponse),
+        (status = 400, description = "Invalid comment, or an encrypted message that wasn't re-encrypted"),
+        (status = 403, description = "No access to the source channel, or cannot post in the target"),
+        (status = 404, description = "Message or target channel not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, body), fields(user_id = %auth_user.id, message_id = %id))]
+pub async fn forward(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(body): Json<ForwardMessageRequest>,
+) -> Result<(StatusCode, Json<MessageResponse>), MessageError> {
+    let source = db::find_message_by_id(&state.db, id)
+        .await?
+        .ok_or(MessageError::NotFound)?;
+    crate::permissions::require_channel_access(&state.db, auth_user.id, source.channel_id)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+    if source.message_type == "system" {
+        return Err(MessageError::Validation(
+            "System messages cannot be forwarded".to_string(),
+        ));
+    }
+
+    // The server can't read an encrypted original, so it can't snapshot it
+    // into a channel whose members may not hold the keys
+    if source.encrypted && !body.encrypted {
+        return Err(MessageError::Validation(
+            "Encrypted messages must be re-encrypted by the client before forwarding".to_string(),
+        ));
+    }
+    if body.encrypted {
+        if body.nonce.is_none() {
+            return Err(MessageError::Validation(
+                "Encrypted messages require a nonce".to_string(),
+            ));
+        }
+        validate_message_content(&body.content)
+            .map_err(|e| MessageError::Validation(e.to_string()))?;
+    } else if !body.content.is_empty() {
+        validate_message_content(&body.content)
+            .map_err(|e| MessageError::Validation(e.to_string()))?;
+    }
+
+    let channel = db::find_channel_by_id(&state.db, body.channel_id)
+        .await?
+        .ok_or(MessageError::ChannelNotFound)?;
+    let ctx = require_send_access(&state, auth_user.id, &channel).await?;
+    super::encryption::check_message(&channel, body.encrypted, body.encryption_epoch)?;
+
+    let mut snapshot = snapshot_source(&state.db, &source).await?;
+    if body.encrypted {
+        snapshot.content = None;
+    }
+
+    if let (Some(guild_id), false) = (channel.guild_id, body.encrypted) {
+        check_mention_permissions(&state, guild_id, auth_user.id, &body.content).await?;
+        let filter_text = match snapshot.content.as_deref() {
+            Some(original) if !body.content.is_empty() => {
+                format!("{}\n{original}", body.content)
+            }
+            Some(original) => original.to_string(),
+            None => body.content.clone(),
+        };
+        enforce_content_filter(&state, guild_id, auth_user.id, channel.id, &filter_text).await?;
+    }
+
+    let cooldown = super::slowmode::cooldown(&state, &channel, None, auth_user.id, &ctx)
+        .await
+        .map_err(|retry_after| MessageError::SlowMode { retry_after })?;
+
+    let inserted = async {
+        let mut tx = state.db.begin().await?;
+        let message = sqlx::query_as::<_, db::Message>(
+            r"
+            INSERT INTO messages (channel_id, user_id, content, encrypted, nonce)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            ",
+        )
+        .bind(channel.id)
+        .bind(auth_user.id)
+        .bind(&body.content)
+        .bind(body.encrypted)
+        .bind(body.nonce.as_deref())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r"
+            INSERT INTO message_forwards
+                (message_id, source_message_id, source_channel_id, source_author_id, content, source_created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ",
+        )
+        .bind(message.id)
+        .bind(snapshot.source_message_id)
+        .bind(snapshot.source_channel_id)
+        .bind(snapshot.source_author_id)
+        .bind(snapshot.content.as_deref())
+        .bind(snapshot.source_created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        // Copies share the original's S3 objects
+        let attachments = sqlx::query_as::<_, db::FileAttachment>(
+            r"
+            INSERT INTO file_attachments
+                (message_id, filename, mime_type, size_bytes, s3_key, width, height, blurhash,
+                 thumbnail_s3_key, medium_s3_key, processing_status)
+            SELECT $2, filename, mime_type, size_bytes, s3_key, width, height, blurhash,
+                   thumbnail_s3_key, medium_s3_key, processing_status
+            FROM file_attachments
+            WHERE message_id = $1
+            ORDER BY created_at ASC
+            RETURNING *
+            ",
+        )
+        .bind(source.id)
+        .bind(message.id)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok::<_, MessageError>((message, attachments))
+    }
+    .await;
+    let (message, attachments) = cooldown.settle(&state, inserted).await?;
+
+    // Index mentions in the comment and notify recipients
+    let recipients = mentions::record_mentions(&state.db, &message, channel.guild_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(message_id = %message.id, error = %e, "Failed to record message mentions");
+            Vec::new()
+        });
+    mentions::spawn_message_notifications(&state, &message, channel.guild_id, recipients);
+
+    let author = db::find_user_by_id(&state.db, auth_user.id)
+        .await?
+        .map(AuthorProfile::from)
+        .unwrap_or_else(|| AuthorProfile {
+            id: auth_user.id,
+            username: "unknown".to_string(),
+            display_name: "Unknown User".to_string(),
+            avatar_url: None,
+            status: "offline".to_string(),
+        });
+
+    let mut response = MessageResponse {
+        id: message.id,
+        channel_id: message.channel_id,
+        author,
+        content: message.content,
+        encrypted: message.encrypted,
+        attachments: attachments.iter().map(AttachmentInfo::from_db).collect(),
+        reply_to: None,
+        parent_id: None,
+        thread_reply_count: 0,
+        thread_last_reply_at: None,
+        edited_at: None,
+        created_at: message.created_at,
+        expires_at: message.expires_at,
+        mention_type: None,
+        reactions: None,
+        thread_info: None,
+        pinned: false,
+        message_type: message.message_type,
+        components: components::from_stored(message.components.as_ref()),
+        poll: None,
+        forwarded_from: Some(snapshot.into_response(true)),
+    };
+
+    // Channel members may not be able to see the source, so the broadcast
+    // carries no link; clients get it when they load the message
+    let link = response.forwarded_from.clone();
+    if let Some(forwarded_from) = &mut response.forwarded_from {
+        forwarded_from.message_id = None;
+        forwarded_from.channel_id = None;
+    }
+    let message_json = serde_json::to_value(&response).unwrap_or_default();
+    if let Err(e) = broadcast_to_channel(
+        &state.redis,
+        channel.id,
+        &ServerEvent::MessageNew {
+            channel_id: channel.id,
+            message: message_json,
+        },
+    )
+    .await
+    {
+        warn!(channel_id = %channel.id, error = %e, "Failed to broadcast forwarded message");
+    }
+
+    response.forwarded_from = link;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures;
+
+    #[sqlx::test]
+    async fn links_resolve_only_for_viewers_of_the_source(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create bob");
+        let guild_id = fixtures::create_guild(&pool, "Forwarding", alice.id, &[]).await;
+        let channels = [
+            fixtures::create_text_channel(&pool, guild_id, "private").await,
+            fixtures::create_text_channel(&pool, guild_id, "public").await,
+        ];
+        let [source_channel, target_channel] = channels;
+
+        let source =
+            db::create_message(&pool, source_channel, alice.id, "hello", false, None, None)
+                .await
+                .expect("create source");
+        let copy = db::create_message(&pool, target_channel, alice.id, "", false, None, None)
+            .await
+            .expect("create copy");
+        let snapshot = snapshot_source(&pool, &source).await.expect("snapshot");
+        sqlx::query(
+            "INSERT INTO message_forwards (message_id, source_message_id, source_channel_id, source_author_id, content, source_created_at) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(copy.id)
+        .bind(snapshot.source_message_id)
+        .bind(snapshot.source_channel_id)
+        .bind(snapshot.source_author_id)
+        .bind(snapshot.content.as_deref())
+        .bind(snapshot.source_created_at)
+        .execute(&pool)
+        .await
+        .expect("record forward");
+
+        let seen = load_forward(&pool, alice.id, copy.id)
+            .await
+            .expect("load")
+            .expect("forward");
+        assert_eq!(seen.message_id, Some(source.id));
+        assert_eq!(seen.channel_id, Some(source_channel));
+        assert_eq!(seen.content.as_deref(), Some("hello"));
+
+        // Bob isn't in the guild: the snapshot stays, the link doesn't
+        let hidden = load_forward(&pool, bob.id, copy.id)
+            .await
+            .expect("load")
+            .expect("forward");
+        assert_eq!(hidden.message_id, None);
+        assert_eq!(hidden.channel_id, None);
+        assert_eq!(hidden.author_id, Some(alice.id));
+        assert_eq!(hidden.content.as_deref(), Some("hello"));
+
+        // Forwarding the comment-less copy carries the original on
+        let chained = snapshot_source(&pool, &copy).await.expect("snapshot");
+        assert_eq!(chained.source_message_id, Some(source.id));
+        assert_eq!(chained.content.as_deref(), Some("hello"));
+
+        // Deleting the original drops the message link
+        sqlx::query("UPDATE messages SET deleted_at = NOW() WHERE id = $1")
+            .bind(source.id)
+            .execute(&pool)
+            .await
+            .expect("delete source");
+        let after_delete = load_forward(&pool, alice.id, copy.id)
+            .await
+            .expect("load")
+            .expect("forward");
+        assert_eq!(after_delete.message_id, None);
+        assert_eq!(after_delete.channel_id, Some(source_channel));
+    }
+}
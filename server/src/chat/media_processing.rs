@@ -86,19 +86,7 @@ pub fn process_image(
     // frame count easily). Animated WebP files will get static variants generated.
     let is_animated = matches!(format, ImageFormat::Gif);
 
-    // Use reader API to enforce dimension limits (prevents decompression bombs:
-    // a small compressed file can expand to enormous RGBA buffers)
-    let mut reader = ImageReader::with_format(Cursor::new(data), format);
-    let mut limits = Limits::default();
-    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
-    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
-    limits.max_alloc = Some(MAX_DECODE_ALLOC);
-    reader.limits(limits);
-
-    let img = reader
-        .decode()
-        .map_err(|e| ProcessingError::DecodeFailed(e.to_string()))?;
-
+    let img = decode(data, format)?;
     let (width, height) = img.dimensions();
 
     let blurhash = generate_blurhash(&img)?;
@@ -121,6 +109,81 @@ pub fn process_image(
     })
 }
 
+/// Render a static WebP preview of the first frame, at most `max_dim` on
+/// either side.
+///
+/// Unlike the attachment variants this is always produced, so animated and
+/// small images get one too. CPU-bound; call inside `spawn_blocking`.
+pub fn generate_preview(
+    data: &[u8],
+    mime_type: &str,
+    max_dim: u32,
+) -> Result<ProcessedVariant, ProcessingError> {
+    if data.len() > MAX_PROCESSABLE_SIZE {
+        return Err(ProcessingError::TooLarge(data.len()));
+    }
+    let img = decode(data, mime_to_format(mime_type)?)?;
+    let img = if img.width() > max_dim || img.height() > max_dim {
+        img.resize(max_dim, max_dim, FilterType::Lanczos3)
+    } else {
+        img
+    };
+    let (width, height) = img.dimensions();
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    img.write_to(&mut buf, ImageFormat::WebP)
+        .map_err(|e| ProcessingError::EncodeFailed(e.to_string()))?;
+
+    Ok(ProcessedVariant {
+        data: buf.into_inner(),
+        width,
+        height,
+        content_type: "image/webp".to_string(),
+    })
+}
+
+/// Whether an image has more than one frame: any GIF, a PNG carrying an
+/// `acTL` chunk (APNG), or a WebP whose `VP8X` header sets the animation flag.
+pub fn is_animated(data: &[u8], mime_type: &str) -> bool {
+    match mime_type {
+        "image/gif" => true,
+        "image/png" => {
+            // Walk chunks after the 8-byte signature; acTL must precede IDAT
+            let mut offset = 8;
+            while let Some(header) = data.get(offset..offset + 8) {
+                let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+                match &header[4..8] {
+                    b"acTL" => return true,
+                    b"IDAT" | b"IEND" => return false,
+                    _ => {}
+                }
+                offset += 12 + len as usize;
+            }
+            false
+        }
+        "image/webp" => {
+            data.get(12..16) == Some(b"VP8X".as_slice())
+                && data.get(20).is_some_and(|flags| flags & 0x02 != 0)
+        }
+        _ => false,
+    }
+}
+
+/// Decode with dimension and allocation limits (prevents decompression bombs:
+/// a small compressed file can expand to enormous RGBA buffers).
+fn decode(data: &[u8], format: ImageFormat) -> Result<DynamicImage, ProcessingError> {
+    let mut reader = ImageReader::with_format(Cursor::new(data), format);
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+    limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+    limits.max_alloc = Some(MAX_DECODE_ALLOC);
+    reader.limits(limits);
+
+    reader
+        .decode()
+        .map_err(|e| ProcessingError::DecodeFailed(e.to_string()))
+}
+
 /// Map MIME type to `image` crate format.
 fn mime_to_format(mime_type: &str) -> Result<ImageFormat, ProcessingError> {
     match mime_type {
@@ -267,6 +330,36 @@ mod tests {
         assert!(matches!(err, Err(ProcessingError::UnsupportedFormat(_))));
     }
 
+    #[test]
+    fn test_preview_is_always_generated_and_bounded() {
+        let small = generate_preview(&create_test_png(100, 50), "image/png", 160).unwrap();
+        assert_eq!((small.width, small.height), (100, 50));
+        assert_eq!(small.content_type, "image/webp");
+
+        let large = generate_preview(&create_test_gif(640, 320), "image/gif", 160).unwrap();
+        assert_eq!((large.width, large.height), (160, 80));
+    }
+
+    #[test]
+    fn test_animation_detection() {
+        let png = create_test_png(10, 10);
+        assert!(!is_animated(&png, "image/png"));
+        assert!(is_animated(&create_test_gif(10, 10), "image/gif"));
+
+        // Splice an acTL chunk in after IHDR (8-byte signature + 25-byte chunk)
+        let mut apng = png[..33].to_vec();
+        apng.extend_from_slice(&8u32.to_be_bytes());
+        apng.extend_from_slice(b"acTL");
+        apng.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        apng.extend_from_slice(&png[33..]);
+        assert!(is_animated(&apng, "image/png"));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0".to_vec();
+        assert!(!is_animated(&webp, "image/webp"));
+        webp[20] = 0x02;
+        assert!(is_animated(&webp, "image/webp"));
+    }
+
     #[test]
     fn test_medium_only_for_mid_size_image() {
         // Image bigger than thumbnail but smaller than medium
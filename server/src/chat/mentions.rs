@@ -0,0 +1,737 @@
+//! Mentions Index
+//!
+//! Parses mentions when a message is created or edited and stores one
+//! `message_mentions` row per mentioned user, so the "recent mentions" inbox
+//! and unread mention counts don't have to re-scan message content.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use axum::extract::{Query, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::messages::{AuthorProfile, CursorPaginatedResponse, MessageError};
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db;
+use crate::notifications::should_notify;
+use crate::permissions::{
+    filter_channel_viewers, filter_viewable_channels, get_member_permission_context,
+    GuildPermissions,
+};
+use crate::ws::{broadcast_to_user, ServerEvent};
+
+/// `@name` at the start or after a non-word character, so `bob@alice.com` isn't a mention.
+static USER_MENTION_REGEX: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"(?:^|\W)@(\w+)").expect("valid mention regex"));
+
+static ROLE_MENTION_REGEX: LazyLock<regex::Regex> = LazyLock::new(|| {
+    regex::Regex::new(r"<@&([0-9a-fA-F-]{36})>").expect("valid role mention regex")
+});
+
+/// How a user was mentioned, from most to least direct.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    sqlx::Type,
+    utoipa::ToSchema,
+)]
+#[sqlx(type_name = "message_mention_kind", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum MessageMentionKind {
+    User,
+    Role,
+    Everyone,
+    Here,
+}
+
+impl MessageMentionKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Role => "role",
+            Self::Everyone => "everyone",
+            Self::Here => "here",
+        }
+    }
+}
+
+impl From<MessageMentionKind> for crate::notifications::MentionKind {
+    fn from(kind: MessageMentionKind) -> Self {
+        match kind {
+            MessageMentionKind::User => Self::Direct,
+            MessageMentionKind::Role => Self::Role,
+            MessageMentionKind::Everyone | MessageMentionKind::Here => Self::Everyone,
+        }
+    }
+}
+
+/// Mentions found in message content.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ParsedMentions {
+    /// Lowercased usernames, deduplicated.
+    pub usernames: Vec<String>,
+    /// Roles mentioned as `<@&role_id>`, deduplicated.
+    pub role_ids: Vec<Uuid>,
+    pub everyone: bool,
+    pub here: bool,
+}
+
+/// Parse `@username`, `<@&role_id>`, `@everyone` and `@here` mentions from message content.
+#[must_use]
+pub fn parse_mentions(content: &str) -> ParsedMentions {
+    let mut parsed = ParsedMentions::default();
+    for cap in USER_MENTION_REGEX.captures_iter(content) {
+        let name = cap[1].to_lowercase();
+        match name.as_str() {
+            "everyone" => parsed.everyone = true,
+            "here" => parsed.here = true,
+            _ if !parsed.usernames.contains(&name) => parsed.usernames.push(name),
+            _ => {}
+        }
+    }
+    for cap in ROLE_MENTION_REGEX.captures_iter(content) {
+        if let Ok(role_id) = Uuid::parse_str(&cap[1]) {
+            if !parsed.role_ids.contains(&role_id) {
+                parsed.role_ids.push(role_id);
+            }
+        }
+    }
+    parsed
+}
+
+/// Check the role mentions of a new guild message.
+///
+/// Every mentioned role must belong to the guild and, unless the author holds
+/// `MENTION_EVERYONE`, be marked mentionable. Returns a user-facing message on
+/// failure.
+pub async fn validate_role_mentions(
+    pool: &PgPool,
+    guild_id: Uuid,
+    author_permissions: GuildPermissions,
+    content: &str,
+) -> sqlx::Result<Result<(), String>> {
+    let role_ids = parse_mentions(content).role_ids;
+    if role_ids.is_empty() {
+        return Ok(Ok(()));
+    }
+
+    let roles: Vec<(Uuid, String, bool)> = sqlx::query_as(
+        "SELECT id, name, mentionable FROM guild_roles WHERE guild_id = $1 AND id = ANY($2) AND NOT is_default",
+    )
+    .bind(guild_id)
+    .bind(&role_ids)
+    .fetch_all(pool)
+    .await?;
+
+    if roles.len() != role_ids.len() {
+        return Ok(Err("Message mentions an unknown role".to_string()));
+    }
+    if !author_permissions.has(GuildPermissions::MENTION_EVERYONE) {
+        if let Some((_, name, _)) = roles.iter().find(|(_, _, mentionable)| !mentionable) {
+            return Ok(Err(format!(
+                "You do not have permission to mention the {name} role"
+            )));
+        }
+    }
+    Ok(Ok(()))
+}
+
+/// Index the mentions of a newly created or edited message.
+///
+/// Replaces any rows stored for the message. `@everyone`, `@here` and roles
+/// that aren't mentionable only fan out when the author holds
+/// `MENTION_EVERYONE` in guild channels, and only users who can view the
+/// channel and haven't blocked (or been blocked by) the author are recorded.
+/// Encrypted messages are never indexed.
+///
+/// Returns the mentioned users with how they were mentioned.
+#[tracing::instrument(skip(pool, message), fields(message_id = %message.id))]
+pub async fn record_mentions(
+    pool: &PgPool,
+    message: &db::Message,
+    guild_id: Option<Uuid>,
+) -> sqlx::Result<Vec<(Uuid, MessageMentionKind)>> {
+    sqlx::query("DELETE FROM message_mentions WHERE message_id = $1")
+        .bind(message.id)
+        .execute(pool)
+        .await?;
+
+    let Some(author_id) = message.user_id else {
+        return Ok(Vec::new());
+    };
+    if message.encrypted {
+        return Ok(Vec::new());
+    }
+    let parsed = parse_mentions(&message.content);
+    if parsed == ParsedMentions::default() {
+        return Ok(Vec::new());
+    }
+
+    // Per user: the most direct mention kind, plus the role for role mentions
+    let mut mentioned: HashMap<Uuid, (MessageMentionKind, Option<Uuid>)> = HashMap::new();
+    let mut mention = |user_id: Uuid, kind: MessageMentionKind, role_id: Option<Uuid>| {
+        let entry = mentioned.entry(user_id).or_insert((kind, role_id));
+        if kind < entry.0 {
+            *entry = (kind, role_id);
+        }
+    };
+
+    if !parsed.usernames.is_empty() {
+        let ids: Vec<Uuid> = sqlx::query_scalar("SELECT id FROM users WHERE username = ANY($1)")
+            .bind(&parsed.usernames)
+            .fetch_all(pool)
+            .await?;
+        for id in ids {
+            mention(id, MessageMentionKind::User, None);
+        }
+    }
+
+    let may_mention_everyone = match guild_id {
+        Some(guild_id) if parsed.everyone || parsed.here || !parsed.role_ids.is_empty() => {
+            get_member_permission_context(pool, guild_id, author_id)
+                .await?
+                .is_some_and(|ctx| ctx.has_permission(GuildPermissions::MENTION_EVERYONE))
+        }
+        Some(_) => false,
+        None => true,
+    };
+
+    if let (Some(guild_id), false) = (guild_id, parsed.role_ids.is_empty()) {
+        let holders: Vec<(Uuid, Uuid)> = sqlx::query_as(
+            r"
+            SELECT gmr.user_id, gmr.role_id
+            FROM guild_member_roles gmr
+            INNER JOIN guild_roles r ON r.id = gmr.role_id
+            WHERE r.guild_id = $1
+              AND r.id = ANY($2)
+              AND NOT r.is_default
+              AND (r.mentionable OR $3)
+            ",
+        )
+        .bind(guild_id)
+        .bind(&parsed.role_ids)
+        .bind(may_mention_everyone)
+        .fetch_all(pool)
+        .await?;
+        for (user_id, role_id) in holders {
+            mention(user_id, MessageMentionKind::Role, Some(role_id));
+        }
+    }
+
+    if (parsed.everyone || parsed.here) && may_mention_everyone {
+        let members: Vec<(Uuid, bool)> = sqlx::query_as(
+            r"
+            SELECT u.id, u.status <> 'offline'
+            FROM users u
+            WHERE u.id IN (
+                SELECT user_id FROM guild_members WHERE guild_id = $1
+                UNION
+                SELECT user_id FROM dm_participants WHERE channel_id = $2 AND $1::uuid IS NULL
+            )
+            ",
+        )
+        .bind(guild_id)
+        .bind(message.channel_id)
+        .fetch_all(pool)
+        .await?;
+        for (id, online) in members {
+            if parsed.everyone {
+                mention(id, MessageMentionKind::Everyone, None);
+            } else if online {
+                mention(id, MessageMentionKind::Here, None);
+            }
+        }
+    }
+
+    let blocked: Vec<Uuid> = sqlx::query_scalar(
+        r"
+        SELECT CASE WHEN requester_id = $1 THEN addressee_id ELSE requester_id END
+        FROM friendships
+        WHERE status = 'blocked' AND (requester_id = $1 OR addressee_id = $1)
+        ",
+    )
+    .bind(author_id)
+    .fetch_all(pool)
+    .await?;
+    mentioned.remove(&author_id);
+    mentioned.retain(|id, _| !blocked.contains(id));
+
+    let candidates: Vec<Uuid> = mentioned.keys().copied().collect();
+    let viewers = if let Some(guild_id) = guild_id {
+        filter_channel_viewers(pool, guild_id, message.channel_id, &candidates).await?
+    } else {
+        sqlx::query_scalar(
+            "SELECT user_id FROM dm_participants WHERE channel_id = $1 AND user_id = ANY($2)",
+        )
+        .bind(message.channel_id)
+        .bind(&candidates)
+        .fetch_all(pool)
+        .await?
+    };
+    let recipients: Vec<(Uuid, MessageMentionKind, Option<Uuid>)> = viewers
+        .into_iter()
+        .filter_map(|id| {
+            mentioned
+                .get(&id)
+                .map(|&(kind, role_id)| (id, kind, role_id))
+        })
+        .collect();
+    if recipients.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let user_ids: Vec<Uuid> = recipients.iter().map(|r| r.0).collect();
+    let kinds: Vec<&str> = recipients.iter().map(|r| r.1.as_str()).collect();
+    let role_ids: Vec<Option<Uuid>> = recipients.iter().map(|r| r.2).collect();
+    sqlx::query(
+        r"
+        INSERT INTO message_mentions
+            (message_id, user_id, channel_id, guild_id, kind, role_id, created_at)
+        SELECT $1, r.user_id, $2, $3, r.kind::message_mention_kind, r.role_id, $4
+        FROM UNNEST($5::uuid[], $6::text[], $7::uuid[]) AS r(user_id, kind, role_id)
+        ",
+    )
+    .bind(message.id)
+    .bind(message.channel_id)
+    .bind(guild_id)
+    .bind(message.created_at)
+    .bind(&user_ids)
+    .bind(&kinds)
+    .bind(&role_ids)
+    .execute(pool)
+    .await?;
+
+    Ok(recipients
+        .into_iter()
+        .map(|(id, kind, _)| (id, kind))
+        .collect())
+}
+
+/// Send `MentionCreated` to each recipient of a new message whose
+/// notification settings allow it. Returns the users that were notified.
+pub async fn notify_mentions(
+    state: &AppState,
+    message: &db::Message,
+    guild_id: Option<Uuid>,
+    recipients: &[(Uuid, MessageMentionKind)],
+) -> Vec<Uuid> {
+    let mut notified = Vec::with_capacity(recipients.len());
+    for &(user_id, kind) in recipients {
+        match should_notify(
+            &state.db,
+            user_id,
+            message.channel_id,
+            message.parent_id,
+            Some(kind.into()),
+        )
+        .await
+        {
+            Ok(true) => notified.push(user_id),
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::warn!(%user_id, error = %e, "Failed to resolve notification settings");
+                continue;
+            }
+        }
+        let event = ServerEvent::MentionCreated {
+            message_id: message.id,
+            channel_id: message.channel_id,
+            guild_id,
+            parent_id: message.parent_id,
+            kind,
+        };
+        if let Err(e) = broadcast_to_user(&state.redis, user_id, &event).await {
+            tracing::warn!("Failed to broadcast MentionCreated event: {}", e);
+        }
+    }
+    notified
+}
+
+/// Notify mention recipients and queue pushes for a new message in the
+/// background.
+pub fn spawn_message_notifications(
+    state: &AppState,
+    message: &db::Message,
+    guild_id: Option<Uuid>,
+    recipients: Vec<(Uuid, MessageMentionKind)>,
+) {
+    // Guild messages only notify the mentioned; DMs notify every participant
+    if guild_id.is_some() && recipients.is_empty() {
+        return;
+    }
+    let state = state.clone();
+    let message = message.clone();
+    tokio::spawn(async move {
+        let notified = notify_mentions(&state, &message, guild_id, &recipients).await;
+        crate::push::dispatch::push_message(&state, &message, guild_id, &notified).await;
+    });
+}
+
+// ============================================================================
+// Recent Mentions Inbox
+// ============================================================================
+
+/// Query parameters for the mentions inbox.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct ListMentionsQuery {
+    /// Message ID cursor; returns mentions older than this one.
+    pub before: Option<Uuid>,
+    /// Only return mentions from this guild.
+    pub guild_id: Option<Uuid>,
+    #[serde(default = "default_limit")]
+    pub limit: i64,
+}
+
+const fn default_limit() -> i64 {
+    50
+}
+
+/// A message that mentioned the user.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct MentionResponse {
+    pub message_id: Uuid,
+    pub channel_id: Uuid,
+    pub channel_name: String,
+    pub guild_id: Option<Uuid>,
+    pub guild_name: Option<String>,
+    /// Thread root, if the mention is in a thread reply.
+    pub parent_id: Option<Uuid>,
+    pub kind: MessageMentionKind,
+    pub role_id: Option<Uuid>,
+    pub author: AuthorProfile,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct MentionRow {
+    message_id: Uuid,
+    channel_id: Uuid,
+    channel_name: String,
+    guild_id: Option<Uuid>,
+    guild_name: Option<String>,
+    parent_id: Option<Uuid>,
+    kind: MessageMentionKind,
+    role_id: Option<Uuid>,
+    content: String,
+    created_at: DateTime<Utc>,
+    author_id: Option<Uuid>,
+    author_username: Option<String>,
+    author_display_name: Option<String>,
+    author_avatar_url: Option<String>,
+    author_status: Option<String>,
+}
+
+/// List messages that mentioned the authenticated user, newest first.
+///
+/// `GET /api/me/mentions`
+#[utoipa::path(
+    get,
+    path = "/api/me/mentions",
+    tag = "messages",
+    params(ListMentionsQuery),
+    responses(
+        (status = 200, body = CursorPaginatedResponse<MentionResponse>),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id))]
+pub async fn list_mentions(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Query(query): Query<ListMentionsQuery>,
+) -> Result<Json<CursorPaginatedResponse<MentionResponse>>, MessageError> {
+    let limit = query.limit.clamp(1, 100);
+
+    // Resolve which mentioned channels the user can still view up front, so
+    // unviewable mentions are filtered before `LIMIT` and pages stay full.
+    let mentioned_channels: Vec<Uuid> = sqlx::query_scalar(
+        r"
+        SELECT DISTINCT channel_id FROM message_mentions
+        WHERE user_id = $1 AND ($2::uuid IS NULL OR guild_id = $2)
+        ",
+    )
+    .bind(auth_user.id)
+    .bind(query.guild_id)
+    .fetch_all(&state.db)
+    .await?;
+    let viewable: Vec<Uuid> =
+        filter_viewable_channels(&state.db, auth_user.id, &mentioned_channels)
+            .await
+            .map_err(|_| MessageError::Forbidden)?
+            .into_iter()
+            .collect();
+
+    let mut rows: Vec<MentionRow> = sqlx::query_as(
+        r"
+        SELECT mm.message_id, mm.channel_id, c.name AS channel_name,
+               mm.guild_id, g.name AS guild_name, m.parent_id,
+               mm.kind, mm.role_id, m.content, mm.created_at,
+               u.id AS author_id, u.username AS author_username,
+               u.display_name AS author_display_name, u.avatar_url AS author_avatar_url,
+               u.status::text AS author_status
+        FROM message_mentions mm
+        INNER JOIN messages m ON m.id = mm.message_id AND m.deleted_at IS NULL
+            AND (m.expires_at IS NULL OR m.expires_at > NOW())
+        INNER JOIN channels c ON c.id = mm.channel_id
+        LEFT JOIN guilds g ON g.id = mm.guild_id
+        LEFT JOIN users u ON u.id = m.user_id
+        WHERE mm.user_id = $1
+          AND mm.channel_id = ANY($2)
+          AND (
+              $3::uuid IS NULL
+              OR (mm.created_at, mm.message_id) < (
+                  SELECT created_at, message_id FROM message_mentions
+                  WHERE message_id = $3 AND user_id = $1
+              )
+          )
+        ORDER BY mm.created_at DESC, mm.message_id DESC
+        LIMIT $4
+        ",
+    )
+    .bind(auth_user.id)
+    .bind(&viewable)
+    .bind(query.before)
+    .bind(limit + 1)
+    .fetch_all(&state.db)
+    .await?;
+
+    let has_more = rows.len() as i64 > limit;
+    if has_more {
+        rows.pop();
+    }
+    let next_cursor = if has_more {
+        rows.last().map(|r| r.message_id)
+    } else {
+        None
+    };
+
+    let items = rows
+        .into_iter()
+        .map(|row| MentionResponse {
+            message_id: row.message_id,
+            channel_id: row.channel_id,
+            channel_name: row.channel_name,
+            guild_id: row.guild_id,
+            guild_name: row.guild_name,
+            parent_id: row.parent_id,
+            kind: row.kind,
+            role_id: row.role_id,
+            author: AuthorProfile {
+                id: row.author_id.unwrap_or(Uuid::nil()),
+                username: row.author_username.unwrap_or_else(|| "deleted".to_string()),
+                display_name: row
+                    .author_display_name
+                    .unwrap_or_else(|| "Deleted User".to_string()),
+                avatar_url: crate::api::files::maybe_file_url(row.author_avatar_url),
+                status: row.author_status.unwrap_or_else(|| "offline".to_string()),
+            },
+            content: row.content,
+            created_at: row.created_at,
+        })
+        .collect();
+
+    Ok(Json(CursorPaginatedResponse {
+        items,
+        has_more,
+        next_cursor,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures;
+
+    #[sqlx::test]
+    async fn record_mentions_fans_out_to_channel_viewers(pool: PgPool) {
+        let owner = db::create_user(&pool, "mentioner", "Mentioner", None, "hash")
+            .await
+            .expect("create owner");
+        let member = db::create_user(&pool, "member", "Member", None, "hash")
+            .await
+            .expect("create member");
+        let _outsider = db::create_user(&pool, "outsider", "Outsider", None, "hash")
+            .await
+            .expect("create outsider");
+        let (guild_id, channel_id) =
+            fixtures::guild_with_channel(&pool, "Mentions", owner.id, &[member.id]).await;
+
+        let message = db::create_message(
+            &pool,
+            channel_id,
+            owner.id,
+            "@member @outsider @mentioner @everyone",
+            false,
+            None,
+            None,
+        )
+        .await
+        .expect("create message");
+
+        let recipients = record_mentions(&pool, &message, Some(guild_id))
+            .await
+            .expect("record mentions");
+        assert_eq!(recipients, vec![(member.id, MessageMentionKind::User)]);
+
+        // Editing away the mention clears the index
+        let edited = db::Message {
+            content: "never mind".to_string(),
+            ..message
+        };
+        assert!(record_mentions(&pool, &edited, Some(guild_id))
+            .await
+            .expect("record mentions")
+            .is_empty());
+        let stored: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM message_mentions WHERE message_id = $1")
+                .bind(edited.id)
+                .fetch_one(&pool)
+                .await
+                .expect("count mentions");
+        assert_eq!(stored, 0);
+    }
+
+    #[sqlx::test]
+    async fn record_mentions_fans_out_to_mentionable_role_holders(pool: PgPool) {
+        let owner = db::create_user(&pool, "owner", "Owner", None, "hash")
+            .await
+            .expect("create owner");
+        let author = db::create_user(&pool, "author", "Author", None, "hash")
+            .await
+            .expect("create author");
+        let helper = db::create_user(&pool, "helper", "Helper", None, "hash")
+            .await
+            .expect("create helper");
+        let (guild_id, channel_id) =
+            fixtures::guild_with_channel(&pool, "Mentions", owner.id, &[author.id, helper.id])
+                .await;
+
+        let mut role_ids = Vec::new();
+        for (name, mentionable) in [("helpers", true), ("staff", false)] {
+            let role_id: Uuid = sqlx::query_scalar(
+                "INSERT INTO guild_roles (guild_id, name, permissions, position, mentionable) VALUES ($1, $2, 0, 1, $3) RETURNING id",
+            )
+            .bind(guild_id)
+            .bind(name)
+            .bind(mentionable)
+            .fetch_one(&pool)
+            .await
+            .expect("create role");
+            sqlx::query(
+                "INSERT INTO guild_member_roles (guild_id, user_id, role_id) VALUES ($1, $2, $3)",
+            )
+            .bind(guild_id)
+            .bind(helper.id)
+            .bind(role_id)
+            .execute(&pool)
+            .await
+            .expect("assign role");
+            role_ids.push(role_id);
+        }
+        let (helpers, staff) = (role_ids[0], role_ids[1]);
+
+        // Without MENTION_EVERYONE only mentionable roles fan out
+        let message = db::create_message(
+            &pool,
+            channel_id,
+            author.id,
+            &format!("<@&{staff}> and <@&{helpers}>"),
+            false,
+            None,
+            None,
+        )
+        .await
+        .expect("create message");
+        let recipients = record_mentions(&pool, &message, Some(guild_id))
+            .await
+            .expect("record mentions");
+        assert_eq!(recipients, vec![(helper.id, MessageMentionKind::Role)]);
+        let stored: Option<Uuid> =
+            sqlx::query_scalar("SELECT role_id FROM message_mentions WHERE message_id = $1")
+                .bind(message.id)
+                .fetch_one(&pool)
+                .await
+                .expect("stored mention");
+        assert_eq!(stored, Some(helpers));
+
+        let staff_only = db::Message {
+            content: format!("<@&{staff}>"),
+            ..message
+        };
+        assert!(record_mentions(&pool, &staff_only, Some(guild_id))
+            .await
+            .expect("record mentions")
+            .is_empty());
+
+        let perms = get_member_permission_context(&pool, guild_id, author.id)
+            .await
+            .expect("load permissions")
+            .expect("author is a member")
+            .computed_permissions;
+        assert_eq!(
+            validate_role_mentions(&pool, guild_id, perms, &staff_only.content)
+                .await
+                .expect("validate"),
+            Err("You do not have permission to mention the staff role".to_string())
+        );
+        assert!(validate_role_mentions(
+            &pool,
+            guild_id,
+            GuildPermissions::MENTION_EVERYONE,
+            &staff_only.content
+        )
+        .await
+        .expect("validate")
+        .is_ok());
+        assert!(
+            validate_role_mentions(&pool, guild_id, perms, &format!("<@&{}>", Uuid::new_v4()))
+                .await
+                .expect("validate")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_role_mentions() {
+        let role_id = Uuid::new_v4();
+        let parsed = parse_mentions(&format!("<@&{role_id}> ping <@&{role_id}> <@&not-a-role>"));
+        assert_eq!(parsed.role_ids, vec![role_id]);
+        assert!(parsed.usernames.is_empty());
+    }
+
+    #[test]
+    fn parses_user_and_broadcast_mentions() {
+        let parsed = parse_mentions("hey @Alice and @bob, @alice again @here");
+        assert_eq!(parsed.usernames, vec!["alice", "bob"]);
+        assert!(parsed.here);
+        assert!(!parsed.everyone);
+
+        assert_eq!(
+            parse_mentions("no mentions here"),
+            ParsedMentions::default()
+        );
+        assert!(parse_mentions("@everyone").everyone);
+        assert!(parse_mentions("mail bob@alice.com").usernames.is_empty());
+        assert_eq!(parse_mentions("(@bob)").usernames, vec!["bob"]);
+    }
+
+    #[test]
+    fn mention_kinds_order_by_directness() {
+        assert!(MessageMentionKind::User < MessageMentionKind::Role);
+        assert!(MessageMentionKind::Role < MessageMentionKind::Everyone);
+        assert!(MessageMentionKind::Everyone < MessageMentionKind::Here);
+    }
+}
@@ -15,6 +15,11 @@ use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
+use super::components::{self, ActionRow};
+use super::forwarding::{self, ForwardedFrom};
+use super::mentions;
+use super::polls::{self, CreatePollRequest, PollResponse};
+use super::slowmode;
 use crate::api::AppState;
 use crate::auth::AuthUser;
 use crate::db;
@@ -22,6 +27,7 @@ use crate::moderation::filter_queries;
 use crate::moderation::filter_types::FilterAction;
 use crate::permissions::{get_member_permission_context, GuildPermissions};
 use crate::social::block_cache;
+use crate::ws::bot_gateway::BotServerEvent;
 use crate::ws::{broadcast_admin_event, broadcast_to_channel, broadcast_to_user, ServerEvent};
 
 // ============================================================================
@@ -35,7 +41,22 @@ pub enum MessageError {
     Forbidden,
     Blocked,
     ContentFiltered,
+    /// Slash command disabled or restricted for the invoking member.
+    CommandForbidden(String),
+    /// Channel or thread slow mode is still cooling down for this member.
+    SlowMode {
+        retry_after: u64,
+    },
+    /// Plaintext message sent to an end-to-end encrypted channel.
+    EncryptionRequired,
+    /// Encrypted message sent with an outdated membership epoch; the sender
+    /// must rotate its Megolm session.
+    StaleEncryptionEpoch {
+        epoch: i64,
+    },
     Validation(String),
+    /// A backing service (e.g. Redis) failed; not the client's fault.
+    Unavailable(String),
     Database(#[allow(dead_code)] sqlx::Error),
 }
 
@@ -67,18 +88,49 @@ impl IntoResponse for MessageError {
                 "CONTENT_FILTERED",
                 "Your message was blocked by the server's content filter.".to_string(),
             ),
+            Self::CommandForbidden(msg) => {
+                (StatusCode::FORBIDDEN, "COMMAND_FORBIDDEN", msg.clone())
+            }
             Self::Validation(msg) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", msg.clone()),
+            Self::Unavailable(msg) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "SERVICE_UNAVAILABLE",
+                msg.clone(),
+            ),
+            Self::SlowMode { retry_after } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "SLOW_MODE",
+                format!("Slow mode is on. Wait {retry_after} seconds."),
+            ),
+            Self::EncryptionRequired => (
+                StatusCode::BAD_REQUEST,
+                "ENCRYPTION_REQUIRED",
+                "This channel is end-to-end encrypted".to_string(),
+            ),
+            Self::StaleEncryptionEpoch { .. } => (
+                StatusCode::CONFLICT,
+                "ENCRYPTION_EPOCH_STALE",
+                "Channel membership changed. Rotate the session and retry.".to_string(),
+            ),
             Self::Database(_) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "INTERNAL_ERROR",
                 "Database error".to_string(),
             ),
         };
-        (
-            status,
-            Json(serde_json::json!({ "error": code, "message": message })),
-        )
-            .into_response()
+        let mut body = serde_json::json!({ "error": code, "message": message });
+        if let Self::SlowMode { retry_after } = self {
+            body["retry_after"] = retry_after.into();
+            let mut response = (status, Json(body)).into_response();
+            response
+                .headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, retry_after.into());
+            return response;
+        }
+        if let Self::StaleEncryptionEpoch { epoch } = self {
+            body["epoch"] = epoch.into();
+        }
+        (status, Json(body)).into_response()
     }
 }
 
@@ -132,6 +184,9 @@ pub struct AttachmentInfo {
     pub thumbnail_url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub medium_url: Option<String>,
+    /// Client-encrypted ciphertext; decrypt with the key in the message body.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub encrypted: bool,
 }
 
 impl AttachmentInfo {
@@ -157,6 +212,7 @@ impl AttachmentInfo {
             blurhash: attachment.blurhash.clone(),
             thumbnail_url,
             medium_url,
+            encrypted: attachment.encrypted,
         }
     }
 }
@@ -202,6 +258,9 @@ pub struct MessageResponse {
     pub thread_last_reply_at: Option<DateTime<Utc>>,
     pub edited_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// When the message disappears, in channels with disappearing messages.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
     /// Type of mention in this message (for notification sounds).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub mention_type: Option<MentionType>,
@@ -216,6 +275,15 @@ pub struct MessageResponse {
     pub pinned: bool,
     /// Message type: "user" for normal messages, "system" for system events.
     pub message_type: String,
+    /// Interactive components (buttons, select menus) attached by a bot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub components: Option<Vec<ActionRow>>,
+    /// Poll attached to this message.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll: Option<PollResponse>,
+    /// Snapshot of the original when this message is a forward.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwarded_from: Option<ForwardedFrom>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
@@ -299,6 +367,14 @@ pub struct CreateMessageRequest {
     pub nonce: Option<String>,
     pub reply_to: Option<Uuid>,
     pub parent_id: Option<Uuid>,
+    /// Poll to attach. Content may be empty when a poll is attached.
+    pub poll: Option<CreatePollRequest>,
+    /// Guild sticker to send. Content may be empty when a sticker is sent.
+    pub sticker_id: Option<Uuid>,
+    /// Membership epoch the Megolm session was shared for. Required in
+    /// end-to-end encrypted channels.
+    #[serde(default)]
+    pub encryption_epoch: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
@@ -312,6 +388,14 @@ pub struct ListThreadRepliesQuery {
 pub struct UpdateMessageRequest {
     #[validate(custom(function = "validate_message_content"))]
     pub content: String,
+    /// Nonce of the re-encrypted content. Required when editing an encrypted
+    /// message.
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// Membership epoch the Megolm session was shared for. Required in
+    /// end-to-end encrypted channels.
+    #[serde(default)]
+    pub encryption_epoch: Option<i64>,
 }
 
 static CODE_BLOCK_RE: LazyLock<regex::Regex> =
@@ -357,6 +441,164 @@ pub fn validate_message_content(content: &str) -> Result<(), validator::Validati
 // Handlers
 // ============================================================================
 
+/// Check that `user_id` may post in `channel`: `VIEW_CHANNEL`, `SEND_MESSAGES`
+/// in guild channels, and no block between DM participants.
+pub async fn require_send_access(
+    state: &AppState,
+    user_id: Uuid,
+    channel: &db::Channel,
+) -> Result<crate::permissions::MemberPermissionContext, MessageError> {
+    // Check if user has VIEW_CHANNEL permission
+    let ctx = crate::permissions::require_channel_access(&state.db, user_id, channel.id)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+
+    // For guild channels, also check SEND_MESSAGES permission
+    if channel.guild_id.is_some() && !ctx.has_permission(GuildPermissions::SEND_MESSAGES) {
+        return Err(MessageError::Forbidden);
+    }
+
+    // For DM channels, check if any participant has blocked the other
+    if channel.channel_type == db::ChannelType::Dm {
+        let participants: Vec<Uuid> = sqlx::query_scalar!(
+            "SELECT user_id FROM dm_participants WHERE channel_id = $1",
+            channel.id
+        )
+        .fetch_all(&state.db)
+        .await
+        .map_err(MessageError::Database)?;
+
+        for &participant_id in &participants {
+            if participant_id != user_id {
+                match block_cache::is_blocked_either_direction(
+                    &state.redis,
+                    user_id,
+                    participant_id,
+                )
+                .await
+                {
+                    Ok(true) => return Err(MessageError::Blocked),
+                    Ok(false) => {}
+                    Err(e) => {
+                        warn!(
+                            error = %e,
+                            user_id = %user_id,
+                            target_id = %participant_id,
+                            fail_open = state.config.block_check_fail_open,
+                            "Redis block check failed, using failsafe policy"
+                        );
+                        if !state.config.block_check_fail_open {
+                            return Err(MessageError::Blocked);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ctx)
+}
+
+/// Reject `@everyone`/`@here` and role mentions the member may not use.
+pub async fn check_mention_permissions(
+    state: &AppState,
+    guild_id: Uuid,
+    user_id: Uuid,
+    content: &str,
+) -> Result<(), MessageError> {
+    let mentions_everyone = content.contains("@everyone") || content.contains("@here");
+    if !mentions_everyone && !content.contains("<@&") {
+        return Ok(());
+    }
+
+    // Load user's permissions in this guild
+    let Ok(Some(ctx)) = get_member_permission_context(&state.db, guild_id, user_id).await else {
+        // User is not a guild member, should not happen if channel access is correct
+        return Err(MessageError::Forbidden);
+    };
+    if mentions_everyone && !ctx.has_permission(GuildPermissions::MENTION_EVERYONE) {
+        return Err(MessageError::Validation(
+            "You do not have permission to mention @everyone or @here".to_string(),
+        ));
+    }
+    mentions::validate_role_mentions(&state.db, guild_id, ctx.computed_permissions, content)
+        .await?
+        .map_err(MessageError::Validation)
+}
+
+/// Run `text` through the guild's content filter, logging matches and
+/// notifying admins when it is blocked.
+pub async fn enforce_content_filter(
+    state: &AppState,
+    guild_id: Uuid,
+    user_id: Uuid,
+    channel_id: Uuid,
+    filter_text: &str,
+) -> Result<(), MessageError> {
+    let Ok(engine) = state.filter_cache.get_or_build(&state.db, guild_id).await else {
+        return Ok(());
+    };
+    let result = engine.check(filter_text);
+    if result.blocked {
+        // Log all matches to moderation_actions table
+        for m in &result.matches {
+            filter_queries::log_moderation_action(
+                &state.db,
+                &filter_queries::LogActionParams {
+                    guild_id,
+                    user_id,
+                    channel_id,
+                    action: m.action,
+                    category: Some(m.category),
+                    matched_pattern: &m.matched_pattern,
+                    original_content: filter_text,
+                    custom_pattern_id: m.custom_pattern_id,
+                },
+            )
+            .await
+            .ok();
+        }
+        // Notify admins
+        if let Some(first) = result.matches.first() {
+            broadcast_admin_event(
+                &state.redis,
+                &ServerEvent::AdminModerationBlocked {
+                    guild_id,
+                    user_id,
+                    channel_id,
+                    category: first.category.to_string(),
+                },
+            )
+            .await
+            .ok();
+        }
+        return Err(MessageError::ContentFiltered);
+    }
+    // For "log" and "warn" actions, still log but allow the message
+    for m in result
+        .matches
+        .iter()
+        .filter(|m| m.action == FilterAction::Log || m.action == FilterAction::Warn)
+    {
+        filter_queries::log_moderation_action(
+            &state.db,
+            &filter_queries::LogActionParams {
+                guild_id,
+                user_id,
+                channel_id,
+                action: m.action,
+                category: Some(m.category),
+                matched_pattern: &m.matched_pattern,
+                original_content: filter_text,
+                custom_pattern_id: m.custom_pattern_id,
+            },
+        )
+        .await
+        .ok();
+    }
+    Ok(())
+}
+
 /// List messages in a channel.
 /// GET /`api/messages/channel/:channel_id`
 ///
@@ -462,80 +704,51 @@ pub async fn create(
     Path(channel_id): Path<Uuid>,
     Json(body): Json<CreateMessageRequest>,
 ) -> Result<(StatusCode, Json<MessageResponse>), MessageError> {
-    // Validate input
-    body.validate()
-        .map_err(|e| MessageError::Validation(e.to_string()))?;
+    // Validate input (content may be empty when a poll or sticker is attached)
+    if (body.poll.is_none() && body.sticker_id.is_none()) || !body.content.is_empty() {
+        body.validate()
+            .map_err(|e| MessageError::Validation(e.to_string()))?;
+    }
+    if let Some(poll) = &body.poll {
+        if body.encrypted {
+            return Err(MessageError::Validation(
+                "Polls cannot be attached to encrypted messages".to_string(),
+            ));
+        }
+        polls::validate_poll(poll).map_err(MessageError::Validation)?;
+    }
+    if body.sticker_id.is_some() && (body.encrypted || body.poll.is_some()) {
+        return Err(MessageError::Validation(
+            "Stickers cannot be sent with a poll or in encrypted messages".to_string(),
+        ));
+    }
 
     // Check channel exists
     let channel = db::find_channel_by_id(&state.db, channel_id)
         .await?
         .ok_or(MessageError::ChannelNotFound)?;
 
-    // Check if user has VIEW_CHANNEL permission
-    let ctx = crate::permissions::require_channel_access(&state.db, auth_user.id, channel_id)
-        .await
-        .map_err(|_| MessageError::Forbidden)?;
-
-    // For guild channels, also check SEND_MESSAGES permission
-    if channel.guild_id.is_some() && !ctx.has_permission(GuildPermissions::SEND_MESSAGES) {
-        return Err(MessageError::Forbidden);
-    }
-
-    // For DM channels, check if any participant has blocked the other
-    if channel.channel_type == db::ChannelType::Dm {
-        let participants: Vec<Uuid> = sqlx::query_scalar!(
-            "SELECT user_id FROM dm_participants WHERE channel_id = $1",
-            channel_id
-        )
-        .fetch_all(&state.db)
-        .await
-        .map_err(MessageError::Database)?;
+    let ctx = require_send_access(&state, auth_user.id, &channel).await?;
+    super::encryption::check_message(&channel, body.encrypted, body.encryption_epoch)?;
 
-        for &participant_id in &participants {
-            if participant_id != auth_user.id {
-                match block_cache::is_blocked_either_direction(
-                    &state.redis,
-                    auth_user.id,
-                    participant_id,
-                )
-                .await
-                {
-                    Ok(true) => return Err(MessageError::Blocked),
-                    Ok(false) => {}
-                    Err(e) => {
-                        warn!(
-                            error = %e,
-                            user_id = %auth_user.id,
-                            target_id = %participant_id,
-                            fail_open = state.config.block_check_fail_open,
-                            "Redis block check failed, using failsafe policy"
-                        );
-                        if !state.config.block_check_fail_open {
-                            return Err(MessageError::Blocked);
-                        }
-                    }
-                }
+    // Stickers can come from any guild the sender belongs to
+    let sticker = match body.sticker_id {
+        Some(sticker_id) => {
+            if channel.guild_id.is_some() && !ctx.has_permission(GuildPermissions::USE_EMOJI) {
+                return Err(MessageError::Forbidden);
             }
+            let sticker =
+                crate::guild::stickers::message_component(&state.db, auth_user.id, sticker_id)
+                    .await?
+                    .ok_or_else(|| MessageError::Validation("Sticker not found".to_string()))?;
+            Some(sticker)
         }
-    }
+        None => None,
+    };
 
-    // Check for @everyone/@here mentions in guild channels
+    // Check for @everyone/@here and role mentions in guild channels
     if let Some(guild_id) = channel.guild_id {
-        if body.content.contains("@everyone") || body.content.contains("@here") {
-            // Load user's permissions in this guild
-            if let Ok(Some(ctx)) =
-                get_member_permission_context(&state.db, guild_id, auth_user.id).await
-            {
-                if !ctx.has_permission(GuildPermissions::MENTION_EVERYONE) {
-                    return Err(MessageError::Validation(
-                        "You do not have permission to mention @everyone or @here".to_string(),
-                    ));
-                }
-            } else {
-                // User is not a guild member, should not happen if channel access is correct
-                return Err(MessageError::Forbidden);
-            }
-        }
+        check_mention_permissions(&state, guild_id, auth_user.id, &body.content).await?;
     }
 
     // Validate encrypted messages have nonce
@@ -548,66 +761,9 @@ pub async fn create(
     // Content filtering: skip encrypted messages (can't inspect E2EE) and DMs (guild-scoped)
     if !body.encrypted {
         if let Some(guild_id) = channel.guild_id {
-            if let Ok(engine) = state.filter_cache.get_or_build(&state.db, guild_id).await {
-                let result = engine.check(&body.content);
-                if result.blocked {
-                    // Log all matches to moderation_actions table
-                    for m in &result.matches {
-                        filter_queries::log_moderation_action(
-                            &state.db,
-                            &filter_queries::LogActionParams {
-                                guild_id,
-                                user_id: auth_user.id,
-                                channel_id,
-                                action: m.action,
-                                category: Some(m.category),
-                                matched_pattern: &m.matched_pattern,
-                                original_content: &body.content,
-                                custom_pattern_id: m.custom_pattern_id,
-                            },
-                        )
-                        .await
-                        .ok();
-                    }
-                    // Notify admins
-                    if let Some(first) = result.matches.first() {
-                        broadcast_admin_event(
-                            &state.redis,
-                            &ServerEvent::AdminModerationBlocked {
-                                guild_id,
-                                user_id: auth_user.id,
-                                channel_id,
-                                category: first.category.to_string(),
-                            },
-                        )
-                        .await
-                        .ok();
-                    }
-                    return Err(MessageError::ContentFiltered);
-                }
-                // For "log" and "warn" actions, still log but allow the message
-                for m in result
-                    .matches
-                    .iter()
-                    .filter(|m| m.action == FilterAction::Log || m.action == FilterAction::Warn)
-                {
-                    filter_queries::log_moderation_action(
-                        &state.db,
-                        &filter_queries::LogActionParams {
-                            guild_id,
-                            user_id: auth_user.id,
-                            channel_id,
-                            action: m.action,
-                            category: Some(m.category),
-                            matched_pattern: &m.matched_pattern,
-                            original_content: &body.content,
-                            custom_pattern_id: m.custom_pattern_id,
-                        },
-                    )
-                    .await
-                    .ok();
-                }
-            }
+            let filter_text = polls::filterable_text(&body.content, body.poll.as_ref());
+            enforce_content_filter(&state, guild_id, auth_user.id, channel_id, &filter_text)
+                .await?;
         }
     }
 
@@ -623,7 +779,12 @@ pub async fn create(
 
     // Route slash command invocations to installed bots in guild channels.
     if let Some(guild_id) = channel.guild_id {
-        if let Some(command_input) = body.content.trim().strip_prefix('/') {
+        if let Some(command_input) = body
+            .content
+            .trim()
+            .strip_prefix('/')
+            .filter(|_| body.poll.is_none() && sticker.is_none())
+        {
             let mut parts = command_input.split_whitespace();
             if let Some(command_name) = parts.next() {
                 let command_name = command_name.to_lowercase();
@@ -644,11 +805,11 @@ pub async fn create(
                     let latency_ms = start.elapsed().as_millis();
                     let content = format!("Pong! (latency: {latency_ms}ms)");
 
-                    let msg: (Uuid, DateTime<Utc>) = sqlx::query_as(
+                    let msg: (Uuid, DateTime<Utc>, Option<DateTime<Utc>>) = sqlx::query_as(
                         r"
                         INSERT INTO messages (channel_id, user_id, content)
                         VALUES ($1, $2, $3)
-                        RETURNING id, created_at
+                        RETURNING id, created_at, expires_at
                         ",
                     )
                     .bind(channel_id)
@@ -671,11 +832,15 @@ pub async fn create(
                         thread_last_reply_at: None,
                         edited_at: None,
                         created_at: msg.1,
+                        expires_at: msg.2,
                         mention_type: None,
                         reactions: None,
                         thread_info: None,
                         pinned: false,
                         message_type: "user".to_string(),
+                        components: None,
+                        poll: None,
+                        forwarded_from: None,
                     };
 
                     let message_json = serde_json::to_value(&response).unwrap_or_default();
@@ -701,11 +866,13 @@ pub async fn create(
                     bot_user_id: Option<Uuid>,
                     application_id: Uuid,
                     options: Option<serde_json::Value>,
+                    default_member_permissions: Option<i64>,
                     guild_scoped: bool,
                 }
                 let commands: Vec<SlashCommandRow> = sqlx::query_as(
                     r"
-                    SELECT ba.bot_user_id, sc.application_id, sc.options, (sc.guild_id IS NOT NULL) AS guild_scoped
+                    SELECT ba.bot_user_id, sc.application_id, sc.options, sc.default_member_permissions,
+                           (sc.guild_id IS NOT NULL) AS guild_scoped
                     FROM slash_commands sc
                     JOIN bot_applications ba ON ba.id = sc.application_id
                     JOIN guild_bot_installations gbi ON gbi.application_id = sc.application_id
@@ -754,23 +921,38 @@ pub async fn create(
                         )));
                     }
 
-                    if let Some(bot_user_id) = command.bot_user_id {
-                        let mut option_map = serde_json::Map::new();
-                        let args: Vec<String> = parts.map(str::to_string).collect();
-
-                        if let Some(options) = command.options.as_ref().and_then(|v| v.as_array()) {
-                            for (idx, option_def) in options.iter().enumerate() {
-                                if let Some(name) = option_def.get("name").and_then(|n| n.as_str())
-                                {
-                                    if let Some(arg) = args.get(idx) {
-                                        option_map.insert(
-                                            name.to_string(),
-                                            serde_json::Value::String(arg.clone()),
-                                        );
-                                    }
-                                }
-                            }
+                    crate::permissions::require_command_permission(
+                        &state.db,
+                        guild_id,
+                        auth_user.id,
+                        channel_id,
+                        command.application_id,
+                        &command_name,
+                        command
+                            .default_member_permissions
+                            .map(crate::permissions::GuildPermissions::from_db),
+                    )
+                    .await
+                    .map_err(|e| match e {
+                        crate::permissions::PermissionError::DatabaseError(msg) => {
+                            tracing::error!("Failed to check command permissions: {}", msg);
+                            MessageError::Database(sqlx::Error::Protocol(msg))
                         }
+                        _ => MessageError::CommandForbidden(format!(
+                            "You cannot use '/{command_name}' here"
+                        )),
+                    })?;
+
+                    if let Some(bot_user_id) = command.bot_user_id {
+                        let registered: Vec<crate::api::commands::CommandOption> = command
+                            .options
+                            .clone()
+                            .and_then(|v| serde_json::from_value(v).ok())
+                            .unwrap_or_default();
+                        let args: Vec<&str> = parts.collect();
+                        let invocation =
+                            crate::api::commands::resolve_invocation(&registered, &args)
+                                .map_err(MessageError::Validation)?;
 
                         let interaction_id = Uuid::new_v4();
                         let event = crate::ws::bot_gateway::BotServerEvent::CommandInvoked {
@@ -779,7 +961,9 @@ pub async fn create(
                             guild_id: Some(guild_id),
                             channel_id,
                             user_id: auth_user.id,
-                            options: serde_json::Value::Object(option_map),
+                            subcommand_group: invocation.subcommand_group.clone(),
+                            subcommand: invocation.subcommand.clone(),
+                            options: serde_json::Value::Object(invocation.options),
                         };
 
                         let payload = serde_json::to_string(&event).map_err(|e| {
@@ -859,6 +1043,8 @@ pub async fn create(
                             let wh_payload = serde_json::json!({
                                 "interaction_id": interaction_id,
                                 "command_name": command_name,
+                                "subcommand_group": invocation.subcommand_group,
+                                "subcommand": invocation.subcommand,
                                 "guild_id": guild_id,
                                 "channel_id": channel_id,
                                 "user_id": auth_user.id,
@@ -923,11 +1109,15 @@ pub async fn create(
                             thread_last_reply_at: None,
                             edited_at: None,
                             created_at: Utc::now(),
+                            expires_at: None,
                             mention_type: None,
                             reactions: None,
                             thread_info: None,
                             pinned: false,
                             message_type: "user".to_string(),
+                            components: None,
+                            poll: None,
+                            forwarded_from: None,
                         };
 
                         return Ok((StatusCode::ACCEPTED, Json(accepted)));
@@ -974,8 +1164,12 @@ pub async fn create(
         }
     }
 
+    let cooldown = slowmode::cooldown(&state, &channel, body.parent_id, auth_user.id, &ctx)
+        .await
+        .map_err(|retry_after| MessageError::SlowMode { retry_after })?;
+
     // Create message (either regular or thread reply)
-    let message = if let Some(parent_id) = body.parent_id {
+    let created = if let Some(parent_id) = body.parent_id {
         db::create_thread_reply(
             &state.db,
             db::CreateThreadReplyParams {
@@ -988,7 +1182,7 @@ pub async fn create(
                 reply_to: body.reply_to,
             },
         )
-        .await?
+        .await
     } else {
         db::create_message(
             &state.db,
@@ -999,8 +1193,37 @@ pub async fn create(
             body.nonce.as_deref(),
             body.reply_to,
         )
-        .await?
+        .await
     };
+    let mut message = cooldown.settle(&state, created).await?;
+
+    let poll = match &body.poll {
+        Some(poll) => Some(polls::create_poll(&state.db, message.id, poll).await?),
+        None => None,
+    };
+
+    // Stickers render from the stored component, not an attachment
+    if let Some(sticker) = sticker {
+        let rows = [ActionRow {
+            components: vec![components::Component::Sticker(sticker)],
+        }];
+        message.components = components::to_stored(Some(&rows));
+        sqlx::query("UPDATE messages SET components = $1 WHERE id = $2")
+            .bind(&message.components)
+            .bind(message.id)
+            .execute(&state.db)
+            .await?;
+    }
+
+    // Index mentions for the inbox and unread mention counts, then notify
+    // recipients (and their offline devices) whose settings allow it
+    let recipients = mentions::record_mentions(&state.db, &message, channel.guild_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(message_id = %message.id, error = %e, "Failed to record message mentions");
+            Vec::new()
+        });
+    mentions::spawn_message_notifications(&state, &message, channel.guild_id, recipients);
 
     // Get author profile for response
     let author = db::find_user_by_id(&state.db, auth_user.id)
@@ -1034,11 +1257,15 @@ pub async fn create(
         thread_last_reply_at: message.thread_last_reply_at,
         edited_at: message.edited_at,
         created_at: message.created_at,
+        expires_at: message.expires_at,
         mention_type,
         reactions: None,
         thread_info: None,
         pinned: false,
+        components: components::from_stored(message.components.as_ref()),
         message_type: message.message_type,
+        poll,
+        forwarded_from: None,
     };
 
     // Broadcast via Redis pub-sub
@@ -1082,32 +1309,18 @@ pub async fn create(
     // Dispatch to bot ecosystem (non-blocking, fire-and-forget)
     if let Some(guild_id) = channel.guild_id {
         if !body.encrypted {
-            let db = state.db.clone();
-            let redis = state.redis.clone();
-            let msg_id = message.id;
-            let ch_id = channel_id;
-            let uid = auth_user.id;
-            let content = body.content.clone();
-            tokio::spawn(async move {
-                crate::ws::bot_events::publish_message_created(
-                    &db, &redis, guild_id, ch_id, msg_id, uid, &content,
-                )
-                .await;
-                crate::webhooks::dispatch::dispatch_guild_event(
-                    &db,
-                    &redis,
-                    guild_id,
-                    crate::webhooks::events::BotEventType::MessageCreated,
-                    serde_json::json!({
-                        "guild_id": guild_id,
-                        "channel_id": ch_id,
-                        "message_id": msg_id,
-                        "user_id": uid,
-                        "content": content,
-                    }),
-                )
-                .await;
-            });
+            crate::ws::bot_events::spawn_guild_event(
+                &state.db,
+                &state.redis,
+                guild_id,
+                BotServerEvent::MessageCreated {
+                    message_id: message.id,
+                    channel_id,
+                    guild_id: Some(guild_id),
+                    user_id: auth_user.id,
+                    content: body.content.clone(),
+                },
+            );
         }
     }
 
@@ -1152,71 +1365,165 @@ pub async fn update(
     .await
     .map_err(|_| MessageError::Forbidden)?;
 
+    let response = apply_message_edit(&state, auth_user.id, &existing_message, &body).await?;
+
+    Ok(Json(response))
+}
+
+/// Delete a message (soft delete).
+/// DELETE /api/messages/:id
+#[utoipa::path(
+    delete,
+    path = "/api/messages/{id}",
+    tag = "messages",
+    params(("id" = Uuid, Path, description = "Message ID")),
+    responses(
+        (status = 204, description = "Message deleted"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id, message_id = %id))]
+pub async fn delete(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, MessageError> {
+    // Get message to find channel_id before deleting
+    let message = db::find_message_by_id(&state.db, id)
+        .await?
+        .ok_or(MessageError::NotFound)?;
+
+    // Check if user has VIEW_CHANNEL permission
+    crate::permissions::require_channel_access(&state.db, auth_user.id, message.channel_id)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+
+    // Check ownership (deleted/anonymized messages cannot be modified)
+    if message.user_id != Some(auth_user.id) {
+        return Err(MessageError::Forbidden);
+    }
+
+    let channel_id = message.channel_id;
+    let parent_id = message.parent_id;
+
+    // Delete message
+    let deleted = db::delete_message(&state.db, id, auth_user.id).await?;
+
+    if deleted {
+        broadcast_message_removed(&state, channel_id, id, parent_id).await;
+
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(MessageError::NotFound)
+    }
+}
+
+// ============================================================================
+// Shared Helpers
+// ============================================================================
+
+/// Apply an edit to a message on behalf of its author.
+///
+/// Runs the channel's encryption check and the guild content filter, persists
+/// the new content (only the author's own message is updated), and broadcasts
+/// `MessageEdit`. An edit keeps the message's encryption: plaintext stays
+/// plaintext and encrypted messages need a new nonce. Callers are responsible
+/// for the channel access check.
+pub async fn apply_message_edit(
+    state: &AppState,
+    author_id: Uuid,
+    existing_message: &db::Message,
+    edit: &UpdateMessageRequest,
+) -> Result<MessageResponse, MessageError> {
+    let id = existing_message.id;
+    let content = edit.content.as_str();
+
+    let channel = db::find_channel_by_id(&state.db, existing_message.channel_id)
+        .await?
+        .ok_or(MessageError::ChannelNotFound)?;
+    // Plaintext sent before the channel switched to encryption can't be edited
+    super::encryption::check_message(&channel, existing_message.encrypted, edit.encryption_epoch)?;
+    if existing_message.encrypted && edit.nonce.is_none() {
+        return Err(MessageError::Validation(
+            "Encrypted messages require a nonce".to_string(),
+        ));
+    }
+    if !existing_message.encrypted && edit.nonce.is_some() {
+        return Err(MessageError::Validation(
+            "nonce is only accepted for encrypted messages".to_string(),
+        ));
+    }
+
     // Content filtering on edited content: skip encrypted messages and DMs
-    if !existing_message.encrypted {
-        let channel = db::find_channel_by_id(&state.db, existing_message.channel_id)
-            .await?
-            .ok_or(MessageError::ChannelNotFound)?;
-        if let Some(guild_id) = channel.guild_id {
-            if let Ok(engine) = state.filter_cache.get_or_build(&state.db, guild_id).await {
-                let result = engine.check(&body.content);
-                if result.blocked {
-                    for m in &result.matches {
-                        filter_queries::log_moderation_action(
-                            &state.db,
-                            &filter_queries::LogActionParams {
-                                guild_id,
-                                user_id: auth_user.id,
-                                channel_id: existing_message.channel_id,
-                                action: m.action,
-                                category: Some(m.category),
-                                matched_pattern: &m.matched_pattern,
-                                original_content: &body.content,
-                                custom_pattern_id: m.custom_pattern_id,
-                            },
-                        )
-                        .await
-                        .ok();
-                    }
-                    return Err(MessageError::ContentFiltered);
-                }
-                // For "log" and "warn" actions, still log but allow the edit
-                for m in result
-                    .matches
-                    .iter()
-                    .filter(|m| m.action == FilterAction::Log || m.action == FilterAction::Warn)
-                {
+    let guild_id = if existing_message.encrypted {
+        None
+    } else {
+        channel.guild_id
+    };
+    if let Some(guild_id) = guild_id {
+        if let Ok(engine) = state.filter_cache.get_or_build(&state.db, guild_id).await {
+            let result = engine.check(content);
+            if result.blocked {
+                for m in &result.matches {
                     filter_queries::log_moderation_action(
                         &state.db,
                         &filter_queries::LogActionParams {
                             guild_id,
-                            user_id: auth_user.id,
+                            user_id: author_id,
                             channel_id: existing_message.channel_id,
                             action: m.action,
                             category: Some(m.category),
                             matched_pattern: &m.matched_pattern,
-                            original_content: &body.content,
+                            original_content: content,
                             custom_pattern_id: m.custom_pattern_id,
                         },
                     )
                     .await
                     .ok();
                 }
+                return Err(MessageError::ContentFiltered);
+            }
+            // For "log" and "warn" actions, still log but allow the edit
+            for m in result
+                .matches
+                .iter()
+                .filter(|m| m.action == FilterAction::Log || m.action == FilterAction::Warn)
+            {
+                filter_queries::log_moderation_action(
+                    &state.db,
+                    &filter_queries::LogActionParams {
+                        guild_id,
+                        user_id: author_id,
+                        channel_id: existing_message.channel_id,
+                        action: m.action,
+                        category: Some(m.category),
+                        matched_pattern: &m.matched_pattern,
+                        original_content: content,
+                        custom_pattern_id: m.custom_pattern_id,
+                    },
+                )
+                .await
+                .ok();
             }
         }
     }
 
     // Update message (only owner can edit)
-    let message = db::update_message(&state.db, id, auth_user.id, &body.content)
+    let message = db::update_message(&state.db, id, author_id, content, edit.nonce.as_deref())
         .await?
         .ok_or(MessageError::NotFound)?;
 
+    // Re-index mentions so the inbox reflects the edited content
+    if let Err(e) = mentions::record_mentions(&state.db, &message, guild_id).await {
+        warn!(message_id = %message.id, error = %e, "Failed to record message mentions");
+    }
+
     // Get author profile for response
-    let author = db::find_user_by_id(&state.db, auth_user.id)
+    let author = db::find_user_by_id(&state.db, author_id)
         .await?
         .map(AuthorProfile::from)
         .unwrap_or_else(|| AuthorProfile {
-            id: auth_user.id,
+            id: author_id,
             username: "unknown".to_string(),
             display_name: "Unknown User".to_string(),
             avatar_url: None,
@@ -1243,6 +1550,7 @@ pub async fn update(
         thread_last_reply_at: message.thread_last_reply_at,
         edited_at: message.edited_at,
         created_at: message.created_at,
+        expires_at: message.expires_at,
         mention_type: None, // Edits don't trigger new notifications
         reactions: None,
         thread_info: None,
@@ -1255,8 +1563,27 @@ pub async fn update(
         .await
         .unwrap_or(false),
         message_type: message.message_type.clone(),
+        components: components::from_stored(message.components.as_ref()),
+        poll: polls::load_poll(&state.db, author_id, message.id).await?,
+        forwarded_from: forwarding::load_forward(&state.db, author_id, message.id).await?,
     };
 
+    // Dispatch to bot ecosystem (non-blocking, fire-and-forget)
+    if let Some(guild_id) = guild_id {
+        crate::ws::bot_events::spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            BotServerEvent::MessageUpdated {
+                message_id: message.id,
+                channel_id: message.channel_id,
+                guild_id: Some(guild_id),
+                user_id: author_id,
+                content: message.content.clone(),
+            },
+        );
+    }
+
     // Broadcast edit via Redis pub-sub
     if let Err(e) = broadcast_to_channel(
         &state.redis,
@@ -1269,6 +1596,7 @@ pub async fn update(
                 .edited_at
                 .map(|t| t.to_rfc3339())
                 .unwrap_or_default(),
+            components: message.components,
         },
     )
     .await
@@ -1276,123 +1604,103 @@ pub async fn update(
         warn!(channel_id = %message.channel_id, message_id = %message.id, error = %e, "Failed to broadcast message edit event");
     }
 
-    Ok(Json(response))
+    Ok(response)
 }
 
-/// Delete a message (soft delete).
-/// DELETE /api/messages/:id
-#[utoipa::path(
-    delete,
-    path = "/api/messages/{id}",
-    tag = "messages",
-    params(("id" = Uuid, Path, description = "Message ID")),
-    responses(
-        (status = 204, description = "Message deleted"),
-    ),
-    security(("bearer_auth" = [])),
-)]
-#[tracing::instrument(skip(state), fields(user_id = %auth_user.id, message_id = %id))]
-pub async fn delete(
-    State(state): State<AppState>,
-    auth_user: AuthUser,
-    Path(id): Path<Uuid>,
-) -> Result<StatusCode, MessageError> {
-    // Get message to find channel_id before deleting
-    let message = db::find_message_by_id(&state.db, id)
-        .await?
-        .ok_or(MessageError::NotFound)?;
-
-    // Check if user has VIEW_CHANNEL permission
-    crate::permissions::require_channel_access(&state.db, auth_user.id, message.channel_id)
-        .await
-        .map_err(|_| MessageError::Forbidden)?;
-
-    // Check ownership (deleted/anonymized messages cannot be modified)
-    if message.user_id != Some(auth_user.id) {
-        return Err(MessageError::Forbidden);
+/// Clean up and broadcast after a message has been deleted.
+///
+/// Removes any channel pin, then emits `ThreadReplyDelete` (updating the parent
+/// thread counters) for thread replies or `MessageDelete` for regular messages.
+/// Bots in the guild receive `MessageDeleted` either way.
+pub async fn broadcast_message_removed(
+    state: &AppState,
+    channel_id: Uuid,
+    id: Uuid,
+    parent_id: Option<Uuid>,
+) {
+    if let Ok(Some(guild_id)) =
+        sqlx::query_scalar::<_, Option<Uuid>>("SELECT guild_id FROM channels WHERE id = $1")
+            .bind(channel_id)
+            .fetch_one(&state.db)
+            .await
+    {
+        crate::ws::bot_events::spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            BotServerEvent::MessageDeleted {
+                message_id: id,
+                channel_id,
+                guild_id: Some(guild_id),
+            },
+        );
     }
 
-    let channel_id = message.channel_id;
-    let parent_id = message.parent_id;
-
-    // Delete message
-    let deleted = db::delete_message(&state.db, id, auth_user.id).await?;
-
-    if deleted {
-        // Clean up channel pin if message was pinned
-        let pin_deleted =
-            sqlx::query("DELETE FROM channel_pins WHERE channel_id = $1 AND message_id = $2")
-                .bind(channel_id)
-                .bind(id)
-                .execute(&state.db)
-                .await;
-
-        if let Ok(result) = pin_deleted {
-            if result.rows_affected() > 0 {
-                if let Err(e) = broadcast_to_channel(
-                    &state.redis,
-                    channel_id,
-                    &ServerEvent::ChannelPinRemoved {
-                        channel_id,
-                        message_id: id,
-                    },
-                )
-                .await
-                {
-                    warn!(channel_id = %channel_id, message_id = %id, error = %e, "Failed to broadcast channel_pin_removed on delete");
-                }
-            }
-        }
-
-        if let Some(parent_id) = parent_id {
-            // Thread reply deleted: decrement parent counters and broadcast ThreadReplyDelete
-            if let Err(e) = db::decrement_thread_counters(&state.db, parent_id).await {
-                warn!(parent_id = %parent_id, error = %e, "Failed to decrement thread counters");
-            }
-
-            let thread_info = build_thread_info(&state.db, parent_id).await;
-            let thread_info_json = serde_json::to_value(&thread_info).unwrap_or_default();
+    // Clean up channel pin if message was pinned
+    let pin_deleted =
+        sqlx::query("DELETE FROM channel_pins WHERE channel_id = $1 AND message_id = $2")
+            .bind(channel_id)
+            .bind(id)
+            .execute(&state.db)
+            .await;
 
+    if let Ok(result) = pin_deleted {
+        if result.rows_affected() > 0 {
             if let Err(e) = broadcast_to_channel(
                 &state.redis,
                 channel_id,
-                &ServerEvent::ThreadReplyDelete {
-                    channel_id,
-                    parent_id,
-                    message_id: id,
-                    thread_info: thread_info_json,
-                },
-            )
-            .await
-            {
-                warn!(channel_id = %channel_id, message_id = %id, error = %e, "Failed to broadcast thread reply delete event");
-            }
-        } else {
-            // Regular message deleted: broadcast MessageDelete
-            if let Err(e) = broadcast_to_channel(
-                &state.redis,
-                channel_id,
-                &ServerEvent::MessageDelete {
+                &ServerEvent::ChannelPinRemoved {
                     channel_id,
                     message_id: id,
                 },
             )
             .await
             {
-                warn!(channel_id = %channel_id, message_id = %id, error = %e, "Failed to broadcast message delete event");
+                warn!(channel_id = %channel_id, message_id = %id, error = %e, "Failed to broadcast channel_pin_removed on delete");
             }
         }
+    }
 
-        Ok(StatusCode::NO_CONTENT)
+    if let Some(parent_id) = parent_id {
+        // Thread reply deleted: decrement parent counters and broadcast ThreadReplyDelete
+        if let Err(e) = db::decrement_thread_counters(&state.db, parent_id).await {
+            warn!(parent_id = %parent_id, error = %e, "Failed to decrement thread counters");
+        }
+
+        let thread_info = build_thread_info(&state.db, parent_id).await;
+        let thread_info_json = serde_json::to_value(&thread_info).unwrap_or_default();
+
+        if let Err(e) = broadcast_to_channel(
+            &state.redis,
+            channel_id,
+            &ServerEvent::ThreadReplyDelete {
+                channel_id,
+                parent_id,
+                message_id: id,
+                thread_info: thread_info_json,
+            },
+        )
+        .await
+        {
+            warn!(channel_id = %channel_id, message_id = %id, error = %e, "Failed to broadcast thread reply delete event");
+        }
     } else {
-        Err(MessageError::NotFound)
+        // Regular message deleted: broadcast MessageDelete
+        if let Err(e) = broadcast_to_channel(
+            &state.redis,
+            channel_id,
+            &ServerEvent::MessageDelete {
+                channel_id,
+                message_id: id,
+            },
+        )
+        .await
+        {
+            warn!(channel_id = %channel_id, message_id = %id, error = %e, "Failed to broadcast message delete event");
+        }
     }
 }
 
-// ============================================================================
-// Shared Helpers
-// ============================================================================
-
 /// Bulk-fetch users, attachments, and reactions for a set of messages, then
 /// map them into `MessageResponse` objects. Used by both `list` and
 /// `list_thread_replies` to avoid duplicating the N+1 avoidance logic.
@@ -1466,6 +1774,13 @@ pub async fn build_message_responses(
     .into_iter()
     .collect();
 
+    // Bulk fetch polls with the viewer's votes
+    let mut polls_map = polls::load_polls(pool, requesting_user_id, &message_ids).await?;
+
+    // Bulk fetch forward snapshots, linking only sources the viewer can see
+    let mut forwards_map =
+        forwarding::load_forwards(pool, requesting_user_id, &message_ids).await?;
+
     // Batch-fetch thread info for parent messages with replies
     let parent_ids_with_threads: Vec<Uuid> = messages
         .iter()
@@ -1520,11 +1835,15 @@ pub async fn build_message_responses(
                 thread_last_reply_at: msg.thread_last_reply_at,
                 edited_at: msg.edited_at,
                 created_at: msg.created_at,
+                expires_at: msg.expires_at,
                 mention_type,
                 reactions,
                 thread_info,
                 pinned: pinned_ids.contains(&msg.id),
                 message_type: msg.message_type.clone(),
+                components: components::from_stored(msg.components.as_ref()),
+                poll: polls_map.remove(&msg.id),
+                forwarded_from: forwards_map.remove(&msg.id),
             }
         })
         .collect();
@@ -1668,7 +1987,11 @@ async fn build_batch_thread_infos(
 
         let participant_avatars: Vec<Option<String>> = participant_ids
             .iter()
-            .map(|uid| user_map.get(uid).and_then(|u| crate::api::files::maybe_file_url(u.avatar_url.clone())))
+            .map(|uid| {
+                user_map
+                    .get(uid)
+                    .and_then(|u| crate::api::files::maybe_file_url(u.avatar_url.clone()))
+            })
             .collect();
 
         // Determine unread status
@@ -2,14 +2,22 @@
 //!
 //! Handles channels, messages, and file uploads.
 
+pub(crate) mod bulk_delete;
 pub(crate) mod channels;
+pub(crate) mod components;
+pub mod disappearing;
 pub mod dm;
 pub mod dm_search;
+pub(crate) mod encryption;
+pub(crate) mod forwarding;
 pub(crate) mod media_processing;
+pub(crate) mod mentions;
 pub(crate) mod messages;
 pub mod overrides;
+pub mod polls;
 pub mod s3;
 pub(crate) mod screenshare;
+pub(crate) mod slowmode;
 pub(crate) mod uploads;
 
 use axum::routing::{delete, get, patch, post, put};
@@ -34,6 +42,16 @@ pub fn channels_router() -> Router<AppState> {
             "/{id}/overrides/{role_id}",
             put(overrides::set_override).delete(overrides::delete_override),
         )
+        // End-to-end encryption
+        .route(
+            "/{id}/encryption",
+            get(encryption::get_encryption).put(encryption::enable_encryption),
+        )
+        // Disappearing messages
+        .route(
+            "/{id}/disappearing-messages",
+            put(disappearing::set_disappearing_messages),
+        )
         // Read state
         .route("/{id}/read", post(channels::mark_as_read))
         // Screen Share
@@ -54,8 +72,14 @@ pub fn messages_router() -> Router<AppState> {
             post(uploads::upload_message_with_file),
         )
         .route("/{id}", patch(messages::update).delete(messages::delete))
+        .route("/{id}/forward", post(forwarding::forward))
+        .route("/{id}/poll/voters", get(polls::list_voters))
         .route("/{parent_id}/thread", get(messages::list_thread_replies))
         .route("/{parent_id}/thread/read", post(messages::mark_thread_read))
+        .route(
+            "/{parent_id}/thread/slowmode",
+            put(slowmode::set_thread_slowmode),
+        )
         .route("/upload", post(uploads::upload_file))
         .route("/attachments/{id}", get(uploads::get_attachment))
         .route("/attachments/{id}/url", get(uploads::get_signed_url))
@@ -0,0 +1,846 @@
+//! Message Polls
+//!
+//! Polls that users and bots attach to messages. Every voter has a single
+//! ballot per poll (one or more options, depending on the poll), tallies are
+//! pushed to the channel as `PollUpdated` events, and results are frozen when
+//! the poll expires.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use fred::prelude::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgExecutor, PgPool};
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::messages::MessageError;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db;
+use crate::ws::{broadcast_to_channel, ServerEvent};
+
+/// Minimum number of options in a poll.
+pub const MIN_POLL_OPTIONS: usize = 2;
+/// Maximum number of options in a poll.
+pub const MAX_POLL_OPTIONS: usize = 10;
+/// Maximum length of a poll question.
+pub const MAX_QUESTION_LENGTH: usize = 300;
+/// Maximum length of a poll option.
+pub const MAX_OPTION_LENGTH: usize = 100;
+/// Shortest allowed poll duration (seconds).
+pub const MIN_POLL_DURATION_SECS: u32 = 60;
+/// Longest allowed poll duration (seconds).
+pub const MAX_POLL_DURATION_SECS: u32 = 30 * 24 * 60 * 60;
+
+/// How often expired polls are closed and their results frozen.
+const EXPIRY_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A poll to attach to a new message.
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct CreatePollRequest {
+    /// The question being asked.
+    pub question: String,
+    /// Answer options (2-10).
+    pub options: Vec<String>,
+    /// Whether voters may pick more than one option.
+    #[serde(default)]
+    pub allow_multiple: bool,
+    /// Hide who voted for what; only tallies are visible.
+    #[serde(default)]
+    pub anonymous: bool,
+    /// Seconds until the poll closes. Omit for a poll that never expires.
+    pub duration_secs: Option<u32>,
+}
+
+/// A poll option with its current tally.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PollOptionResponse {
+    pub text: String,
+    pub votes: i64,
+}
+
+/// A poll as shown on its message.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PollResponse {
+    pub question: String,
+    /// Options in display order; votes reference them by index.
+    pub options: Vec<PollOptionResponse>,
+    pub allow_multiple: bool,
+    pub anonymous: bool,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether voting has ended. Tallies of closed polls no longer change.
+    pub closed: bool,
+    /// Number of users who voted.
+    pub total_voters: i64,
+    /// Option indexes the requesting user voted for.
+    pub my_votes: Vec<u8>,
+}
+
+/// Cast or change the caller's vote.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PollVoteRequest {
+    /// Indexes of the chosen options (exactly one unless the poll allows multiple).
+    pub options: Vec<u8>,
+}
+
+/// Voters of one option of a non-anonymous poll.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PollOptionVoters {
+    pub option: u8,
+    pub user_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, FromRow)]
+struct PollRow {
+    message_id: Uuid,
+    question: String,
+    options: Vec<String>,
+    allow_multiple: bool,
+    anonymous: bool,
+    expires_at: Option<DateTime<Utc>>,
+    closed_at: Option<DateTime<Utc>>,
+    final_counts: Option<Vec<i64>>,
+    final_voters: Option<i64>,
+}
+
+impl PollRow {
+    fn is_closed(&self, now: DateTime<Utc>) -> bool {
+        self.closed_at.is_some() || self.expires_at.is_some_and(|at| at <= now)
+    }
+
+    fn into_response(
+        self,
+        counts: &HashMap<i16, i64>,
+        voters: i64,
+        my_votes: Vec<u8>,
+    ) -> PollResponse {
+        let closed = self.is_closed(Utc::now());
+        let frozen = self.final_counts.unwrap_or_default();
+        let options = self
+            .options
+            .into_iter()
+            .enumerate()
+            .map(|(idx, text)| {
+                let votes = if self.final_voters.is_some() {
+                    frozen.get(idx).copied().unwrap_or(0)
+                } else {
+                    i16::try_from(idx)
+                        .ok()
+                        .and_then(|idx| counts.get(&idx).copied())
+                        .unwrap_or(0)
+                };
+                PollOptionResponse { text, votes }
+            })
+            .collect();
+
+        PollResponse {
+            question: self.question,
+            options,
+            allow_multiple: self.allow_multiple,
+            anonymous: self.anonymous,
+            expires_at: self.expires_at,
+            closed,
+            total_voters: self.final_voters.unwrap_or(voters),
+            my_votes,
+        }
+    }
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+fn validate_text(field: &str, value: &str, max: usize) -> Result<(), String> {
+    if value.trim().is_empty() || value.chars().count() > max {
+        return Err(format!("Poll {field} must be 1-{max} characters"));
+    }
+    Ok(())
+}
+
+/// Validate a poll before it is attached to a message.
+pub fn validate_poll(poll: &CreatePollRequest) -> Result<(), String> {
+    validate_text("question", &poll.question, MAX_QUESTION_LENGTH)?;
+
+    if poll.options.len() < MIN_POLL_OPTIONS || poll.options.len() > MAX_POLL_OPTIONS {
+        return Err(format!(
+            "Polls must have {MIN_POLL_OPTIONS}-{MAX_POLL_OPTIONS} options"
+        ));
+    }
+    for (idx, option) in poll.options.iter().enumerate() {
+        validate_text("option", option, MAX_OPTION_LENGTH)?;
+        if poll.options[..idx]
+            .iter()
+            .any(|other| other.trim().eq_ignore_ascii_case(option.trim()))
+        {
+            return Err(format!("Duplicate poll option '{}'", option.trim()));
+        }
+    }
+
+    if let Some(duration) = poll.duration_secs {
+        if !(MIN_POLL_DURATION_SECS..=MAX_POLL_DURATION_SECS).contains(&duration) {
+            return Err(format!(
+                "Poll duration must be between {MIN_POLL_DURATION_SECS} and {MAX_POLL_DURATION_SECS} seconds"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check a ballot against the poll and normalize it (sorted, as stored).
+fn validate_ballot(poll: &PollRow, options: &[u8]) -> Result<Vec<i16>, String> {
+    if poll.is_closed(Utc::now()) {
+        return Err("This poll has closed".to_string());
+    }
+    if options.is_empty() {
+        return Err("Choose at least one option".to_string());
+    }
+    if options.len() > 1 && !poll.allow_multiple {
+        return Err("This poll allows only one choice".to_string());
+    }
+
+    let mut ballot: Vec<i16> = Vec::with_capacity(options.len());
+    for &option in options {
+        if usize::from(option) >= poll.options.len() {
+            return Err(format!("Unknown poll option {option}"));
+        }
+        if ballot.contains(&i16::from(option)) {
+            return Err(format!("Duplicate poll option {option}"));
+        }
+        ballot.push(i16::from(option));
+    }
+    ballot.sort_unstable();
+    Ok(ballot)
+}
+
+/// Message content plus poll text, for the content filter.
+pub fn filterable_text<'a>(content: &'a str, poll: Option<&CreatePollRequest>) -> Cow<'a, str> {
+    match poll {
+        Some(poll) => Cow::Owned(format!(
+            "{content}\n{}\n{}",
+            poll.question,
+            poll.options.join("\n")
+        )),
+        None => Cow::Borrowed(content),
+    }
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Attach a validated poll to a freshly created message.
+pub async fn create_poll(
+    executor: impl PgExecutor<'_>,
+    message_id: Uuid,
+    poll: &CreatePollRequest,
+) -> sqlx::Result<PollResponse> {
+    let options: Vec<String> = poll.options.iter().map(|o| o.trim().to_string()).collect();
+    let row = sqlx::query_as::<_, PollRow>(
+        r"
+        INSERT INTO message_polls (message_id, question, options, allow_multiple, anonymous, expires_at)
+        VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(secs => $6))
+        RETURNING message_id, question, options, allow_multiple, anonymous, expires_at,
+                  closed_at, final_counts, final_voters
+        ",
+    )
+    .bind(message_id)
+    .bind(poll.question.trim())
+    .bind(&options)
+    .bind(poll.allow_multiple)
+    .bind(poll.anonymous)
+    .bind(poll.duration_secs.map(f64::from))
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row.into_response(&HashMap::new(), 0, Vec::new()))
+}
+
+async fn find_poll(pool: &PgPool, message_id: Uuid) -> sqlx::Result<Option<PollRow>> {
+    sqlx::query_as::<_, PollRow>(
+        r"
+        SELECT message_id, question, options, allow_multiple, anonymous, expires_at,
+               closed_at, final_counts, final_voters
+        FROM message_polls
+        WHERE message_id = $1
+        ",
+    )
+    .bind(message_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Load the polls attached to `message_ids`, with tallies and the viewer's votes.
+pub async fn load_polls(
+    pool: &PgPool,
+    viewer_id: Uuid,
+    message_ids: &[Uuid],
+) -> sqlx::Result<HashMap<Uuid, PollResponse>> {
+    let rows = sqlx::query_as::<_, PollRow>(
+        r"
+        SELECT message_id, question, options, allow_multiple, anonymous, expires_at,
+               closed_at, final_counts, final_voters
+        FROM message_polls
+        WHERE message_id = ANY($1)
+        ",
+    )
+    .bind(message_ids)
+    .fetch_all(pool)
+    .await?;
+    if rows.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    // Frozen polls carry their own tallies
+    let live_ids: Vec<Uuid> = rows
+        .iter()
+        .filter(|row| row.final_voters.is_none())
+        .map(|row| row.message_id)
+        .collect();
+    let poll_ids: Vec<Uuid> = rows.iter().map(|row| row.message_id).collect();
+
+    let option_counts: Vec<(Uuid, i16, i64)> = sqlx::query_as(
+        r"
+        SELECT v.message_id, o.option, COUNT(*)
+        FROM message_poll_votes v
+        CROSS JOIN LATERAL unnest(v.options) AS o(option)
+        WHERE v.message_id = ANY($1)
+        GROUP BY v.message_id, o.option
+        ",
+    )
+    .bind(&live_ids)
+    .fetch_all(pool)
+    .await?;
+    let mut counts: HashMap<Uuid, HashMap<i16, i64>> = HashMap::new();
+    for (message_id, option, votes) in option_counts {
+        counts.entry(message_id).or_default().insert(option, votes);
+    }
+
+    let voters: HashMap<Uuid, i64> = sqlx::query_as::<_, (Uuid, i64)>(
+        r"
+        SELECT message_id, COUNT(*)
+        FROM message_poll_votes
+        WHERE message_id = ANY($1)
+        GROUP BY message_id
+        ",
+    )
+    .bind(&live_ids)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut my_votes: HashMap<Uuid, Vec<i16>> = sqlx::query_as::<_, (Uuid, Vec<i16>)>(
+        "SELECT message_id, options FROM message_poll_votes WHERE user_id = $1 AND message_id = ANY($2)",
+    )
+    .bind(viewer_id)
+    .bind(&poll_ids)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let empty = HashMap::new();
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let message_id = row.message_id;
+            let mine = my_votes
+                .remove(&message_id)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|option| u8::try_from(option).ok())
+                .collect();
+            let response = row.into_response(
+                counts.get(&message_id).unwrap_or(&empty),
+                voters.get(&message_id).copied().unwrap_or(0),
+                mine,
+            );
+            (message_id, response)
+        })
+        .collect())
+}
+
+/// Load a single message's poll for `viewer_id`.
+pub async fn load_poll(
+    pool: &PgPool,
+    viewer_id: Uuid,
+    message_id: Uuid,
+) -> sqlx::Result<Option<PollResponse>> {
+    Ok(load_polls(pool, viewer_id, &[message_id])
+        .await?
+        .remove(&message_id))
+}
+
+/// Replace the user's ballot, or remove it when `options` is `None`.
+///
+/// Returns `false` when the poll has closed in the meantime. The poll row is
+/// share-locked so the expiry sweep can't freeze tallies mid-vote.
+async fn set_ballot(
+    pool: &PgPool,
+    message_id: Uuid,
+    user_id: Uuid,
+    options: Option<&[i16]>,
+) -> sqlx::Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let open: Option<bool> = sqlx::query_scalar(
+        r"
+        SELECT closed_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())
+        FROM message_polls
+        WHERE message_id = $1
+        FOR SHARE
+        ",
+    )
+    .bind(message_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if open != Some(true) {
+        return Ok(false);
+    }
+
+    if let Some(options) = options {
+        sqlx::query(
+            r"
+            INSERT INTO message_poll_votes (message_id, user_id, options)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (message_id, user_id)
+            DO UPDATE SET options = EXCLUDED.options, voted_at = NOW()
+            ",
+        )
+        .bind(message_id)
+        .bind(user_id)
+        .bind(options)
+        .execute(&mut *tx)
+        .await?;
+    } else {
+        sqlx::query("DELETE FROM message_poll_votes WHERE message_id = $1 AND user_id = $2")
+            .bind(message_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Close every expired poll, freezing its tallies.
+///
+/// Returns `(channel_id, message_id)` of each poll that was closed.
+pub async fn close_expired_polls(pool: &PgPool) -> sqlx::Result<Vec<(Uuid, Uuid)>> {
+    let mut tx = pool.begin().await?;
+
+    // Lock first (waiting for in-flight votes), then count in a fresh statement
+    // so those votes are included in the frozen result
+    let expired: Vec<Uuid> = sqlx::query_scalar(
+        r"
+        SELECT message_id
+        FROM message_polls
+        WHERE closed_at IS NULL AND expires_at <= NOW()
+        FOR UPDATE
+        ",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    if expired.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let closed = sqlx::query_as(
+        r"
+        UPDATE message_polls p
+        SET closed_at = NOW(),
+            final_counts = ARRAY(
+                SELECT COUNT(v.user_id)
+                FROM generate_series(0, cardinality(p.options) - 1) AS i
+                LEFT JOIN message_poll_votes v
+                    ON v.message_id = p.message_id AND i::smallint = ANY(v.options)
+                GROUP BY i
+                ORDER BY i
+            ),
+            final_voters = (
+                SELECT COUNT(*) FROM message_poll_votes v WHERE v.message_id = p.message_id
+            )
+        FROM messages m
+        WHERE m.id = p.message_id AND p.message_id = ANY($1)
+        RETURNING m.channel_id, p.message_id
+        ",
+    )
+    .bind(&expired)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(closed)
+}
+
+/// Broadcast a poll's current tallies to its channel.
+pub async fn broadcast_tallies(
+    redis: &Client,
+    channel_id: Uuid,
+    message_id: Uuid,
+    poll: &PollResponse,
+) {
+    let event = ServerEvent::PollUpdated {
+        channel_id,
+        message_id,
+        votes: poll.options.iter().map(|o| o.votes).collect(),
+        total_voters: poll.total_voters,
+        closed: poll.closed,
+    };
+    if let Err(e) = broadcast_to_channel(redis, channel_id, &event).await {
+        warn!(channel_id = %channel_id, message_id = %message_id, error = %e, "Failed to broadcast poll update");
+    }
+}
+
+/// Spawn a background task that closes expired polls every 30 seconds and
+/// broadcasts their final results.
+pub fn spawn_poll_expiry_sweep(db: PgPool, redis: Client) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let closed = match close_expired_polls(&db).await {
+                Ok(closed) => closed,
+                Err(e) => {
+                    warn!(error = %e, "Poll expiry sweep: close failed");
+                    continue;
+                }
+            };
+
+            for (channel_id, message_id) in closed {
+                match load_poll(&db, Uuid::nil(), message_id).await {
+                    Ok(Some(poll)) => {
+                        broadcast_tallies(&redis, channel_id, message_id, &poll).await;
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        warn!(message_id = %message_id, error = %e, "Poll expiry sweep: load failed");
+                    }
+                }
+            }
+        }
+    })
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Load a message's poll after checking the caller can see the channel.
+async fn poll_for_member(
+    state: &AppState,
+    user_id: Uuid,
+    message_id: Uuid,
+) -> Result<(db::Message, PollRow), MessageError> {
+    let message = db::find_message_by_id(&state.db, message_id)
+        .await?
+        .ok_or(MessageError::NotFound)?;
+
+    crate::permissions::require_channel_access(&state.db, user_id, message.channel_id)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+
+    let poll = find_poll(&state.db, message_id)
+        .await?
+        .ok_or_else(|| MessageError::Validation("Message has no poll".to_string()))?;
+    Ok((message, poll))
+}
+
+/// Store the ballot, then broadcast and return the updated poll.
+async fn apply_ballot(
+    state: &AppState,
+    user_id: Uuid,
+    message: &db::Message,
+    ballot: Option<&[i16]>,
+) -> Result<Json<PollResponse>, MessageError> {
+    if !set_ballot(&state.db, message.id, user_id, ballot).await? {
+        return Err(MessageError::Validation("This poll has closed".to_string()));
+    }
+
+    let poll = load_poll(&state.db, user_id, message.id)
+        .await?
+        .ok_or(MessageError::NotFound)?;
+    broadcast_tallies(&state.redis, message.channel_id, message.id, &poll).await;
+    Ok(Json(poll))
+}
+
+/// Vote on a poll, replacing any earlier vote by the caller.
+///
+/// PUT /api/messages/{id}/poll/votes
+#[utoipa::path(
+    put,
+    path = "/api/messages/{id}/poll/votes",
+    tag = "messages",
+    params(("id" = Uuid, Path, description = "Message ID")),
+    request_body = PollVoteRequest,
+    responses(
+        (status = 200, body = PollResponse),
+        (status = 400, description = "Invalid choice, no poll on the message, or poll closed"),
+        (status = 403, description = "No access to channel"),
+        (status = 404, description = "Message not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, body), fields(user_id = %auth_user.id, message_id = %id))]
+pub async fn vote(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+    Json(body): Json<PollVoteRequest>,
+) -> Result<Json<PollResponse>, MessageError> {
+    let (message, poll) = poll_for_member(&state, auth_user.id, id).await?;
+    let ballot = validate_ballot(&poll, &body.options).map_err(MessageError::Validation)?;
+    apply_ballot(&state, auth_user.id, &message, Some(&ballot)).await
+}
+
+/// Withdraw the caller's vote.
+///
+/// DELETE /api/messages/{id}/poll/votes
+#[utoipa::path(
+    delete,
+    path = "/api/messages/{id}/poll/votes",
+    tag = "messages",
+    params(("id" = Uuid, Path, description = "Message ID")),
+    responses(
+        (status = 200, body = PollResponse),
+        (status = 400, description = "No poll on the message, or poll closed"),
+        (status = 403, description = "No access to channel"),
+        (status = 404, description = "Message not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id, message_id = %id))]
+pub async fn retract_vote(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<PollResponse>, MessageError> {
+    let (message, _) = poll_for_member(&state, auth_user.id, id).await?;
+    apply_ballot(&state, auth_user.id, &message, None).await
+}
+
+/// List who voted for each option of a non-anonymous poll.
+///
+/// GET /api/messages/{id}/poll/voters
+#[utoipa::path(
+    get,
+    path = "/api/messages/{id}/poll/voters",
+    tag = "messages",
+    params(("id" = Uuid, Path, description = "Message ID")),
+    responses(
+        (status = 200, body = Vec<PollOptionVoters>),
+        (status = 400, description = "No poll on the message"),
+        (status = 403, description = "No access to channel, or the poll is anonymous"),
+        (status = 404, description = "Message not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id, message_id = %id))]
+pub async fn list_voters(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<PollOptionVoters>>, MessageError> {
+    let (_, poll) = poll_for_member(&state, auth_user.id, id).await?;
+    if poll.anonymous {
+        return Err(MessageError::Forbidden);
+    }
+
+    let ballots: Vec<(Uuid, Vec<i16>)> = sqlx::query_as(
+        "SELECT user_id, options FROM message_poll_votes WHERE message_id = $1 ORDER BY voted_at",
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut voters: Vec<PollOptionVoters> = (0..poll.options.len())
+        .filter_map(|idx| u8::try_from(idx).ok())
+        .map(|option| PollOptionVoters {
+            option,
+            user_ids: Vec::new(),
+        })
+        .collect();
+    for (user_id, options) in ballots {
+        for option in options {
+            if let Some(entry) = usize::try_from(option).ok().and_then(|o| voters.get_mut(o)) {
+                entry.user_ids.push(user_id);
+            }
+        }
+    }
+    Ok(Json(voters))
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn poll(options: &[&str]) -> CreatePollRequest {
+        CreatePollRequest {
+            question: "Which map?".to_string(),
+            options: options.iter().map(ToString::to_string).collect(),
+            allow_multiple: false,
+            anonymous: false,
+            duration_secs: None,
+        }
+    }
+
+    fn row(allow_multiple: bool, expires_at: Option<DateTime<Utc>>) -> PollRow {
+        PollRow {
+            message_id: Uuid::new_v4(),
+            question: "Which map?".to_string(),
+            options: vec![
+                "Dust".to_string(),
+                "Nuke".to_string(),
+                "Inferno".to_string(),
+            ],
+            allow_multiple,
+            anonymous: false,
+            expires_at,
+            closed_at: None,
+            final_counts: None,
+            final_voters: None,
+        }
+    }
+
+    #[test]
+    fn test_poll_limits() {
+        assert!(validate_poll(&poll(&["Dust", "Nuke"])).is_ok());
+        assert!(validate_poll(&poll(&["Dust"])).is_err());
+
+        let too_many: Vec<String> = (0..=MAX_POLL_OPTIONS).map(|i| format!("map {i}")).collect();
+        let too_many: Vec<&str> = too_many.iter().map(String::as_str).collect();
+        assert!(validate_poll(&poll(&too_many)).is_err());
+
+        assert!(validate_poll(&poll(&["Dust", " dust "])).is_err());
+        assert!(validate_poll(&poll(&["Dust", "  "])).is_err());
+        assert!(validate_poll(&poll(&["Dust", &"x".repeat(MAX_OPTION_LENGTH + 1)])).is_err());
+
+        let mut question = poll(&["Dust", "Nuke"]);
+        question.question = "x".repeat(MAX_QUESTION_LENGTH + 1);
+        assert!(validate_poll(&question).is_err());
+
+        let mut short = poll(&["Dust", "Nuke"]);
+        short.duration_secs = Some(MIN_POLL_DURATION_SECS - 1);
+        assert!(validate_poll(&short).is_err());
+        short.duration_secs = Some(MAX_POLL_DURATION_SECS);
+        assert!(validate_poll(&short).is_ok());
+    }
+
+    #[test]
+    fn test_ballot_rules() {
+        let single = row(false, None);
+        assert_eq!(validate_ballot(&single, &[1]), Ok(vec![1]));
+        assert!(validate_ballot(&single, &[0, 1]).is_err());
+        assert!(validate_ballot(&single, &[]).is_err());
+        assert!(validate_ballot(&single, &[3]).is_err());
+
+        let multi = row(true, None);
+        assert_eq!(validate_ballot(&multi, &[2, 0]), Ok(vec![0, 2]));
+        assert!(validate_ballot(&multi, &[1, 1]).is_err());
+
+        let expired = row(false, Some(Utc::now() - chrono::Duration::seconds(1)));
+        assert_eq!(
+            validate_ballot(&expired, &[0]),
+            Err("This poll has closed".to_string())
+        );
+    }
+
+    #[sqlx::test]
+    async fn tallies_enforce_one_ballot_and_freeze_at_expiry(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create bob");
+        let channel_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO channels (id, name, channel_type, guild_id, position) VALUES ($1, 'alice, bob', 'dm', NULL, 0)",
+        )
+        .bind(channel_id)
+        .execute(&pool)
+        .await
+        .expect("create channel");
+        let message = db::create_message(&pool, channel_id, alice.id, "", false, None, None)
+            .await
+            .expect("create message");
+
+        let mut request = poll(&["Dust", "Nuke", "Inferno"]);
+        request.allow_multiple = true;
+        request.duration_secs = Some(MIN_POLL_DURATION_SECS);
+        let created = create_poll(&pool, message.id, &request)
+            .await
+            .expect("create poll");
+        assert!(!created.closed);
+        assert_eq!(created.total_voters, 0);
+
+        // Voting again replaces the earlier ballot
+        assert!(set_ballot(&pool, message.id, alice.id, Some(&[0]))
+            .await
+            .expect("vote"));
+        assert!(set_ballot(&pool, message.id, alice.id, Some(&[1, 2]))
+            .await
+            .expect("revote"));
+        assert!(set_ballot(&pool, message.id, bob.id, Some(&[1]))
+            .await
+            .expect("vote"));
+
+        let live = load_poll(&pool, alice.id, message.id)
+            .await
+            .expect("load")
+            .expect("poll");
+        let votes: Vec<i64> = live.options.iter().map(|o| o.votes).collect();
+        assert_eq!(votes, vec![0, 2, 1]);
+        assert_eq!(live.total_voters, 2);
+        assert_eq!(live.my_votes, vec![1, 2]);
+
+        // Expire the poll and let the sweep freeze it
+        sqlx::query("UPDATE message_polls SET expires_at = NOW() - INTERVAL '1 second' WHERE message_id = $1")
+            .bind(message.id)
+            .execute(&pool)
+            .await
+            .expect("expire poll");
+        assert!(!set_ballot(&pool, message.id, bob.id, Some(&[0]))
+            .await
+            .expect("late vote"));
+        assert_eq!(
+            close_expired_polls(&pool).await.expect("close"),
+            vec![(channel_id, message.id)]
+        );
+        assert!(close_expired_polls(&pool)
+            .await
+            .expect("close again")
+            .is_empty());
+
+        // Removing a voter afterwards doesn't change the published result
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(bob.id)
+            .execute(&pool)
+            .await
+            .expect("delete bob");
+        let frozen = load_poll(&pool, alice.id, message.id)
+            .await
+            .expect("load")
+            .expect("poll");
+        assert!(frozen.closed);
+        let votes: Vec<i64> = frozen.options.iter().map(|o| o.votes).collect();
+        assert_eq!(votes, vec![0, 2, 1]);
+        assert_eq!(frozen.total_voters, 2);
+    }
+}
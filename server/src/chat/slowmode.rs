@@ -0,0 +1,305 @@
+//! Slow Mode
+//!
+//! Per-channel (and optionally per-thread) cooldown between a member's
+//! messages. The interval lives on the channel row, with a `thread_slowmode`
+//! override for busy threads; cooldowns themselves are Redis keys that expire
+//! when the member may post again. Members with `MANAGE_MESSAGES` are exempt.
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use fred::interfaces::KeysInterface;
+use serde::Deserialize;
+use sqlx::PgPool;
+use tracing::warn;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::messages::MessageError;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::db;
+use crate::permissions::{GuildPermissions, MemberPermissionContext};
+
+/// Longest slow mode interval (6 hours), matching the database constraint.
+pub const MAX_SLOWMODE_SECONDS: i32 = 6 * 60 * 60;
+
+/// Set or clear a thread's slow mode override.
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ThreadSlowmodeRequest {
+    /// Interval in seconds; `null` falls back to the channel's setting.
+    #[validate(range(
+        min = 0,
+        max = MAX_SLOWMODE_SECONDS,
+        message = "Slow mode must be between 0 and 21600 seconds"
+    ))]
+    pub slowmode_seconds: Option<i32>,
+}
+
+/// The thread's own interval, if it overrides the channel's.
+pub async fn thread_override(pool: &PgPool, parent_id: Uuid) -> sqlx::Result<Option<i32>> {
+    sqlx::query_scalar("SELECT slowmode_seconds FROM thread_slowmode WHERE parent_id = $1")
+        .bind(parent_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Resolve the cooldown scope and interval for a new message.
+///
+/// Thread replies use the thread's override when it has one, with a cooldown
+/// of their own; otherwise they share the channel's.
+async fn effective_slowmode(
+    pool: &PgPool,
+    channel: &db::Channel,
+    parent_id: Option<Uuid>,
+) -> sqlx::Result<(String, i32)> {
+    if let Some(parent_id) = parent_id {
+        if let Some(seconds) = thread_override(pool, parent_id).await? {
+            return Ok((format!("thread:{parent_id}"), seconds));
+        }
+    }
+    Ok((format!("channel:{}", channel.id), channel.slowmode_seconds))
+}
+
+/// A cooldown started for a message that is about to be sent.
+///
+/// Keeping it lets the cooldown run; [`Cooldown::settle`] cancels it when the
+/// send fails so the member isn't throttled for a message that never posted.
+#[must_use]
+pub struct Cooldown {
+    key: Option<String>,
+}
+
+impl Cooldown {
+    /// Pass `result` through, cancelling the cooldown if it is an error.
+    pub async fn settle<T, E>(self, state: &AppState, result: Result<T, E>) -> Result<T, E> {
+        if let Some(key) = self.key.filter(|_| result.is_err()) {
+            let cleared: Result<i64, fred::error::Error> = state.redis.del(&key).await;
+            if let Err(e) = cleared {
+                warn!(error = %e, "Failed to cancel slow mode cooldown");
+            }
+        }
+        result
+    }
+}
+
+/// Start the member's cooldown, or return the seconds left on a running one.
+///
+/// DMs and members with `MANAGE_MESSAGES` are never throttled. Database or
+/// Redis failures are logged and let the message through rather than
+/// blocking the channel.
+pub async fn cooldown(
+    state: &AppState,
+    channel: &db::Channel,
+    parent_id: Option<Uuid>,
+    user_id: Uuid,
+    ctx: &MemberPermissionContext,
+) -> Result<Cooldown, u64> {
+    let unthrottled = Cooldown { key: None };
+    if channel.guild_id.is_none() || ctx.has_permission(GuildPermissions::MANAGE_MESSAGES) {
+        return Ok(unthrottled);
+    }
+
+    let (scope, seconds) = match effective_slowmode(&state.db, channel, parent_id).await {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            warn!(channel_id = %channel.id, error = %e, "Failed to resolve slow mode");
+            return Ok(unthrottled);
+        }
+    };
+    if seconds <= 0 {
+        return Ok(unthrottled);
+    }
+
+    let key = format!("slowmode:{scope}:{user_id}");
+    let started: Result<bool, fred::error::Error> = state
+        .redis
+        .set(
+            &key,
+            1,
+            Some(fred::types::Expiration::EX(i64::from(seconds))),
+            Some(fred::types::SetOptions::NX),
+            false,
+        )
+        .await;
+    match started {
+        Ok(true) => Ok(Cooldown { key: Some(key) }),
+        Ok(false) => {
+            let ttl: i64 = state
+                .redis
+                .ttl(&key)
+                .await
+                .unwrap_or_else(|_| i64::from(seconds));
+            // The key can expire between SET and TTL; round up to one second
+            Err(u64::try_from(ttl).unwrap_or(0).max(1))
+        }
+        Err(e) => {
+            warn!(channel_id = %channel.id, error = %e, "Slow mode check failed, allowing message");
+            Ok(unthrottled)
+        }
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Override slow mode for one thread.
+///
+/// `PUT /api/messages/{parent_id}/thread/slowmode`
+#[utoipa::path(
+    put,
+    path = "/api/messages/{parent_id}/thread/slowmode",
+    tag = "messages",
+    params(("parent_id" = Uuid, Path, description = "Thread parent message ID")),
+    request_body = ThreadSlowmodeRequest,
+    responses(
+        (status = 204, description = "Thread slow mode updated"),
+        (status = 400, description = "Invalid interval, not a thread parent or not a guild channel"),
+        (status = 403, description = "Missing MANAGE_MESSAGES"),
+        (status = 404, description = "Message not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, body), fields(user_id = %auth_user.id, message_id = %parent_id))]
+pub async fn set_thread_slowmode(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(parent_id): Path<Uuid>,
+    Json(body): Json<ThreadSlowmodeRequest>,
+) -> Result<StatusCode, MessageError> {
+    body.validate()
+        .map_err(|e| MessageError::Validation(e.to_string()))?;
+
+    let parent = db::find_message_by_id(&state.db, parent_id)
+        .await?
+        .filter(|m| m.deleted_at.is_none())
+        .ok_or(MessageError::NotFound)?;
+    if parent.parent_id.is_some() {
+        return Err(MessageError::Validation(
+            "Slow mode can only be set on a thread's parent message".to_string(),
+        ));
+    }
+
+    let channel = db::find_channel_by_id(&state.db, parent.channel_id)
+        .await?
+        .ok_or(MessageError::ChannelNotFound)?;
+    if channel.guild_id.is_none() {
+        return Err(MessageError::Validation(
+            "Slow mode is not available in DMs".to_string(),
+        ));
+    }
+
+    let ctx = crate::permissions::require_channel_access(&state.db, auth_user.id, channel.id)
+        .await
+        .map_err(|_| MessageError::Forbidden)?;
+    if !ctx.has_permission(GuildPermissions::MANAGE_MESSAGES) {
+        return Err(MessageError::Forbidden);
+    }
+
+    if let Some(seconds) = body.slowmode_seconds {
+        sqlx::query(
+            r"
+            INSERT INTO thread_slowmode (parent_id, slowmode_seconds)
+            VALUES ($1, $2)
+            ON CONFLICT (parent_id)
+            DO UPDATE SET slowmode_seconds = EXCLUDED.slowmode_seconds, updated_at = NOW()
+            ",
+        )
+        .bind(parent_id)
+        .bind(seconds)
+        .execute(&state.db)
+        .await?;
+    } else {
+        sqlx::query("DELETE FROM thread_slowmode WHERE parent_id = $1")
+            .bind(parent_id)
+            .execute(&state.db)
+            .await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_slowmode_request_is_bounded() {
+        let request = |seconds| ThreadSlowmodeRequest {
+            slowmode_seconds: Some(seconds),
+        };
+        assert!(request(0).validate().is_ok());
+        assert!(request(MAX_SLOWMODE_SECONDS).validate().is_ok());
+        assert!(request(-1).validate().is_err());
+        assert!(request(MAX_SLOWMODE_SECONDS + 1).validate().is_err());
+        assert!(ThreadSlowmodeRequest {
+            slowmode_seconds: None
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[sqlx::test]
+    async fn thread_overrides_replace_the_channel_interval(pool: PgPool) {
+        let user = db::create_user(&pool, "slow", "Slow", None, "hash")
+            .await
+            .expect("create user");
+        let channel = db::create_channel(
+            &pool,
+            db::CreateChannelParams {
+                name: "general",
+                channel_type: &db::ChannelType::Text,
+                category_id: None,
+                guild_id: None,
+                topic: None,
+                icon_url: None,
+                user_limit: None,
+            },
+        )
+        .await
+        .expect("create channel");
+        sqlx::query("UPDATE channels SET slowmode_seconds = 30 WHERE id = $1")
+            .bind(channel.id)
+            .execute(&pool)
+            .await
+            .expect("set slowmode");
+        let channel = db::find_channel_by_id(&pool, channel.id)
+            .await
+            .expect("load channel")
+            .expect("channel exists");
+        let parent = db::create_message(&pool, channel.id, user.id, "topic", false, None, None)
+            .await
+            .expect("create parent");
+
+        let channel_scope = format!("channel:{}", channel.id);
+        assert_eq!(
+            effective_slowmode(&pool, &channel, None).await.unwrap(),
+            (channel_scope.clone(), 30)
+        );
+        // Without an override, replies share the channel's cooldown
+        assert_eq!(
+            effective_slowmode(&pool, &channel, Some(parent.id))
+                .await
+                .unwrap(),
+            (channel_scope, 30)
+        );
+
+        sqlx::query("INSERT INTO thread_slowmode (parent_id, slowmode_seconds) VALUES ($1, 0)")
+            .bind(parent.id)
+            .execute(&pool)
+            .await
+            .expect("override");
+        assert_eq!(
+            effective_slowmode(&pool, &channel, Some(parent.id))
+                .await
+                .unwrap(),
+            (format!("thread:{}", parent.id), 0)
+        );
+    }
+}
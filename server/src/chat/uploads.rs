@@ -10,7 +10,9 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use uuid::Uuid;
 
-use super::messages::{detect_mention_type, AttachmentInfo, AuthorProfile, MessageResponse};
+use super::messages::{
+    detect_mention_type, AttachmentInfo, AuthorProfile, MessageError, MessageResponse,
+};
 use super::s3::S3Client;
 use crate::api::AppState;
 use crate::auth::jwt::validate_access_token;
@@ -74,6 +76,24 @@ pub enum UploadError {
     /// Validation error.
     #[error("Validation error: {0}")]
     Validation(String),
+
+    /// Channel slow mode is still cooling down.
+    #[error("Slow mode is on. Wait {retry_after} seconds.")]
+    SlowMode {
+        /// Seconds until the member may post again.
+        retry_after: u64,
+    },
+
+    /// Plaintext upload to an end-to-end encrypted channel.
+    #[error("This channel is end-to-end encrypted")]
+    EncryptionRequired,
+
+    /// Encrypted upload sent with an outdated membership epoch.
+    #[error("Channel membership changed. Rotate the session and retry.")]
+    StaleEncryptionEpoch {
+        /// The channel's current epoch.
+        epoch: i64,
+    },
 }
 
 impl IntoResponse for UploadError {
@@ -118,14 +138,36 @@ impl IntoResponse for UploadError {
                 "VALIDATION_ERROR",
                 self.to_string(),
             ),
+            Self::SlowMode { .. } => (StatusCode::TOO_MANY_REQUESTS, "SLOW_MODE", self.to_string()),
+            Self::EncryptionRequired => (
+                StatusCode::BAD_REQUEST,
+                "ENCRYPTION_REQUIRED",
+                self.to_string(),
+            ),
+            Self::StaleEncryptionEpoch { .. } => (
+                StatusCode::CONFLICT,
+                "ENCRYPTION_EPOCH_STALE",
+                self.to_string(),
+            ),
         };
 
-        let body = Json(serde_json::json!({
+        let mut body = serde_json::json!({
             "error": code,
             "message": message,
-        }));
+        });
+        if let Self::SlowMode { retry_after } = self {
+            body["retry_after"] = retry_after.into();
+            let mut response = (status, Json(body)).into_response();
+            response
+                .headers_mut()
+                .insert(axum::http::header::RETRY_AFTER, retry_after.into());
+            return response;
+        }
+        if let Self::StaleEncryptionEpoch { epoch } = self {
+            body["epoch"] = epoch.into();
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
 
@@ -201,6 +243,13 @@ const DEFAULT_ALLOWED_TYPES: &[&str] = &[
     "video/webm",
 ];
 
+/// MIME type stored for client-encrypted uploads. The real type is only known
+/// to those who can read the encrypted message.
+const ENCRYPTED_MIME_TYPE: &str = "application/octet-stream";
+
+/// Maximum size of a client-encrypted thumbnail.
+const MAX_ENCRYPTED_THUMBNAIL_SIZE: usize = 512 * 1024;
+
 /// Validate file content against its claimed MIME type using magic byte detection.
 ///
 /// Returns the verified MIME type (detected from content, or the claimed type for
@@ -268,6 +317,10 @@ fn validate_file_content(data: &[u8], claimed_mime: &str) -> Result<String, Uplo
 /// Expects multipart form with:
 /// - `file`: The file data
 /// - `message_id`: UUID of the message to attach to
+/// - `thumbnail`: Optional client-encrypted thumbnail (encrypted messages only)
+///
+/// Files attached to encrypted messages are stored as opaque ciphertext:
+/// no MIME sniffing and no server-side thumbnails.
 #[utoipa::path(
     post,
     path = "/api/messages/upload",
@@ -291,6 +344,7 @@ pub async fn upload_file(
     let mut filename: Option<String> = None;
     let mut content_type: Option<String> = None;
     let mut message_id: Option<Uuid> = None;
+    let mut thumbnail: Option<Vec<u8>> = None;
 
     // Parse multipart form
     while let Ok(Some(field)) = multipart.next_field().await {
@@ -315,6 +369,18 @@ pub async fn upload_file(
 
                 file_data = Some(data.to_vec());
             }
+            "thumbnail" => {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| UploadError::Validation(e.to_string()))?;
+                if data.len() > MAX_ENCRYPTED_THUMBNAIL_SIZE {
+                    return Err(UploadError::TooLarge {
+                        max_size: MAX_ENCRYPTED_THUMBNAIL_SIZE,
+                    });
+                }
+                thumbnail = Some(data.to_vec());
+            }
             "message_id" => {
                 let text = field
                     .text()
@@ -344,30 +410,6 @@ pub async fn upload_file(
         return Err(UploadError::InvalidFilename);
     }
 
-    // Determine content type
-    let content_type = content_type
-        .or_else(|| {
-            mime_guess::from_path(&filename)
-                .first()
-                .map(|m| m.to_string())
-        })
-        .unwrap_or_else(|| "application/octet-stream".to_string());
-
-    // Validate MIME type
-    let allowed_types: Vec<&str> = state.config.allowed_mime_types.as_ref().map_or_else(
-        || DEFAULT_ALLOWED_TYPES.to_vec(),
-        |v| v.iter().map(std::string::String::as_str).collect(),
-    );
-
-    if !allowed_types.contains(&content_type.as_str()) {
-        return Err(UploadError::InvalidMimeType {
-            mime_type: content_type,
-        });
-    }
-
-    // Validate actual file content matches claimed MIME type (magic byte check)
-    let content_type = validate_file_content(&file_data, &content_type)?;
-
     // Verify message exists and user has access
     let message = db::find_message_by_id(&state.db, message_id)
         .await?
@@ -378,20 +420,40 @@ pub async fn upload_file(
         return Err(UploadError::Forbidden);
     }
 
+    // Files attached to encrypted messages are ciphertext the server can't
+    // inspect, but only where the channel actually takes ciphertext
+    let channel = db::find_channel_by_id(&state.db, message.channel_id)
+        .await?
+        .ok_or(UploadError::MessageNotFound)?;
+    let encrypted = message.encrypted && super::encryption::accepts_ciphertext(&channel);
+    if !encrypted && thumbnail.is_some() {
+        return Err(UploadError::Validation(
+            "thumbnail is only accepted for encrypted uploads".to_string(),
+        ));
+    }
+    let content_type = if encrypted {
+        ENCRYPTED_MIME_TYPE.to_string()
+    } else {
+        verified_content_type(&state, &filename, content_type, &file_data)?
+    };
+
     // Generate S3 key
     let file_id = Uuid::now_v7();
-    let extension = std::path::Path::new(&safe_filename)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("bin");
     let s3_key = format!(
         "attachments/{}/{}/{}.{}",
-        message.channel_id, message_id, file_id, extension
+        message.channel_id,
+        message_id,
+        file_id,
+        s3_extension(&safe_filename, encrypted)
     );
 
     // Process image before S3 upload (clones data internally for spawn_blocking)
     let file_size = file_data.len() as i64;
-    let media = process_and_upload_variants(s3, &file_data, &content_type, &s3_key).await;
+    let media = if encrypted {
+        upload_encrypted_thumbnail(s3, thumbnail, &s3_key).await?
+    } else {
+        process_and_upload_variants(s3, &file_data, &content_type, &s3_key).await
+    };
 
     // Upload original to S3
     if let Err(e) = s3.upload(&s3_key, file_data, &content_type).await {
@@ -423,6 +485,7 @@ pub async fn upload_file(
         media.thumb_key.as_deref(),
         media.medium_key.as_deref(),
         media.processing_status,
+        encrypted,
     )
     .await
     .map_err(|e| {
@@ -472,6 +535,14 @@ pub async fn upload_file(
 /// Expects multipart form with:
 /// - `file`: The file data (required)
 /// - `content`: Optional message text content
+/// - `encrypted`: `true` if `file` and `content` are client-encrypted
+/// - `nonce`: Nonce of the encrypted content (required when encrypted)
+/// - `encryption_epoch`: Current epoch of an encrypted channel
+/// - `thumbnail`: Optional client-encrypted thumbnail (encrypted uploads only)
+///
+/// Encrypted uploads are stored as opaque ciphertext: no MIME sniffing,
+/// content filtering or server-side thumbnails. The file key, dimensions and
+/// blurhash travel inside the encrypted message body.
 #[utoipa::path(
     post,
     path = "/api/messages/channel/{channel_id}/upload",
@@ -488,10 +559,12 @@ pub async fn upload_message_with_file(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(channel_id): Path<Uuid>,
-    mut multipart: Multipart,
+    multipart: Multipart,
 ) -> Result<(StatusCode, Json<MessageResponse>), UploadError> {
     // Check S3 is configured
-    let s3 = state.s3.as_ref().ok_or(UploadError::NotConfigured)?;
+    if state.s3.is_none() {
+        return Err(UploadError::NotConfigured);
+    }
 
     // Check channel exists
     let channel = db::find_channel_by_id(&state.db, channel_id)
@@ -510,10 +583,35 @@ pub async fn upload_message_with_file(
         return Err(UploadError::Forbidden);
     }
 
+    let cooldown = super::slowmode::cooldown(&state, &channel, None, auth_user.id, &ctx)
+        .await
+        .map_err(|retry_after| UploadError::SlowMode { retry_after })?;
+
+    let created = create_message_with_file(&state, auth_user.id, &channel, multipart).await;
+    cooldown.settle(&state, created).await
+}
+
+/// Parse a multipart upload and post it as a new message with one attachment.
+///
+/// Expects the same form fields as `upload_message_with_file`.
+/// Callers are responsible for checking that `author_id` may post in `channel`.
+pub async fn create_message_with_file(
+    state: &AppState,
+    author_id: Uuid,
+    channel: &db::Channel,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<MessageResponse>), UploadError> {
+    let s3 = state.s3.as_ref().ok_or(UploadError::NotConfigured)?;
+    let channel_id = channel.id;
+
     let mut file_data: Option<Vec<u8>> = None;
     let mut filename: Option<String> = None;
     let mut content_type: Option<String> = None;
     let mut content: String = String::new();
+    let mut thumbnail: Option<Vec<u8>> = None;
+    let mut encrypted = false;
+    let mut nonce: Option<String> = None;
+    let mut encryption_epoch: Option<i64> = None;
 
     // Parse multipart form
     while let Ok(Some(field)) = multipart.next_field().await {
@@ -544,6 +642,44 @@ pub async fn upload_message_with_file(
                     .await
                     .map_err(|e| UploadError::Validation(e.to_string()))?;
             }
+            "thumbnail" => {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| UploadError::Validation(e.to_string()))?;
+                if data.len() > MAX_ENCRYPTED_THUMBNAIL_SIZE {
+                    return Err(UploadError::TooLarge {
+                        max_size: MAX_ENCRYPTED_THUMBNAIL_SIZE,
+                    });
+                }
+                thumbnail = Some(data.to_vec());
+            }
+            "encrypted" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| UploadError::Validation(e.to_string()))?;
+                encrypted = text
+                    .parse()
+                    .map_err(|_| UploadError::Validation("Invalid encrypted flag".to_string()))?;
+            }
+            "nonce" => {
+                nonce = Some(
+                    field
+                        .text()
+                        .await
+                        .map_err(|e| UploadError::Validation(e.to_string()))?,
+                );
+            }
+            "encryption_epoch" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| UploadError::Validation(e.to_string()))?;
+                encryption_epoch = Some(text.parse().map_err(|_| {
+                    UploadError::Validation("Invalid encryption_epoch".to_string())
+                })?);
+            }
             _ => {
                 // Ignore unknown fields
             }
@@ -560,37 +696,42 @@ pub async fn upload_message_with_file(
         return Err(UploadError::InvalidFilename);
     }
 
-    // Determine content type
-    let file_content_type = content_type
-        .or_else(|| {
-            mime_guess::from_path(&filename)
-                .first()
-                .map(|m| m.to_string())
-        })
-        .unwrap_or_else(|| "application/octet-stream".to_string());
-
-    // Validate MIME type
-    let allowed_types: Vec<&str> = state.config.allowed_mime_types.as_ref().map_or_else(
-        || DEFAULT_ALLOWED_TYPES.to_vec(),
-        |v| v.iter().map(std::string::String::as_str).collect(),
-    );
-
-    if !allowed_types.contains(&file_content_type.as_str()) {
-        return Err(UploadError::InvalidMimeType {
-            mime_type: file_content_type,
-        });
+    // The server can't encrypt on the sender's behalf
+    super::encryption::check_message(channel, encrypted, encryption_epoch).map_err(
+        |e| match e {
+            MessageError::StaleEncryptionEpoch { epoch } => {
+                UploadError::StaleEncryptionEpoch { epoch }
+            }
+            _ => UploadError::EncryptionRequired,
+        },
+    )?;
+    if encrypted && nonce.is_none() {
+        return Err(UploadError::Validation(
+            "Encrypted messages require a nonce".to_string(),
+        ));
+    }
+    // Only encrypted channels and DMs take files the server can't inspect
+    let opaque = encrypted && super::encryption::accepts_ciphertext(channel);
+    if !opaque && thumbnail.is_some() {
+        return Err(UploadError::Validation(
+            "thumbnail is only accepted for encrypted uploads".to_string(),
+        ));
     }
 
-    // Validate actual file content matches claimed MIME type (magic byte check)
-    let file_content_type = validate_file_content(&file_data, &file_content_type)?;
+    let file_content_type = if opaque {
+        ENCRYPTED_MIME_TYPE.to_string()
+    } else {
+        verified_content_type(state, &filename, content_type, &file_data)?
+    };
 
     // Validate message content length if provided
     if !content.is_empty() {
         super::messages::validate_message_content(&content)
             .map_err(|e| UploadError::Validation(e.to_string()))?;
     }
-    // Content filtering on message text (if non-empty, guild channels only)
-    if !content.is_empty() {
+    // Content filtering on message text (if non-empty, guild channels only;
+    // encrypted content can't be inspected)
+    if !content.is_empty() && !opaque {
         if let Some(guild_id) = channel.guild_id {
             if let Ok(engine) = state.filter_cache.get_or_build(&state.db, guild_id).await {
                 let result = engine.check(&content);
@@ -600,7 +741,7 @@ pub async fn upload_message_with_file(
                             &state.db,
                             &crate::moderation::filter_queries::LogActionParams {
                                 guild_id,
-                                user_id: auth_user.id,
+                                user_id: author_id,
                                 channel_id,
                                 action: m.action,
                                 category: Some(m.category),
@@ -625,7 +766,7 @@ pub async fn upload_message_with_file(
                         &state.db,
                         &crate::moderation::filter_queries::LogActionParams {
                             guild_id,
-                            user_id: auth_user.id,
+                            user_id: author_id,
                             channel_id,
                             action: m.action,
                             category: Some(m.category),
@@ -649,28 +790,40 @@ pub async fn upload_message_with_file(
     let message = db::create_message(
         &state.db,
         channel_id,
-        auth_user.id,
+        author_id,
         &content,
-        false, // encrypted
-        None,  // nonce
-        None,  // reply_to
+        encrypted,
+        nonce.as_deref(),
+        None, // reply_to
     )
     .await?;
 
+    // Index mentions for the inbox and unread mention counts, then notify
+    let recipients = super::mentions::record_mentions(&state.db, &message, channel.guild_id)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!(message_id = %message.id, error = %e, "Failed to record message mentions");
+            Vec::new()
+        });
+    super::mentions::spawn_message_notifications(state, &message, channel.guild_id, recipients);
+
     // Generate S3 key using actual message ID
     let file_id = Uuid::now_v7();
-    let extension = std::path::Path::new(&safe_filename)
-        .extension()
-        .and_then(|e| e.to_str())
-        .unwrap_or("bin");
     let s3_key = format!(
         "attachments/{}/{}/{}.{}",
-        channel_id, message.id, file_id, extension
+        channel_id,
+        message.id,
+        file_id,
+        s3_extension(&safe_filename, opaque)
     );
 
     // Process image before S3 upload (clones data internally for spawn_blocking)
     let file_size = file_data.len() as i64;
-    let media = process_and_upload_variants(s3, &file_data, &file_content_type, &s3_key).await;
+    let media = if opaque {
+        upload_encrypted_thumbnail(s3, thumbnail, &s3_key).await?
+    } else {
+        process_and_upload_variants(s3, &file_data, &file_content_type, &s3_key).await
+    };
 
     // Upload original to S3 - if this fails, message is already created (acceptable trade-off)
     if let Err(e) = s3.upload(&s3_key, file_data, &file_content_type).await {
@@ -707,6 +860,7 @@ pub async fn upload_message_with_file(
         media.thumb_key.as_deref(),
         media.medium_key.as_deref(),
         media.processing_status,
+        opaque,
     )
     .await
     .map_err(|e| {
@@ -728,11 +882,11 @@ pub async fn upload_message_with_file(
     })?;
 
     // Get author profile for response
-    let author = db::find_user_by_id(&state.db, auth_user.id)
+    let author = db::find_user_by_id(&state.db, author_id)
         .await?
         .map(AuthorProfile::from)
         .unwrap_or_else(|| AuthorProfile {
-            id: auth_user.id,
+            id: author_id,
             username: "unknown".to_string(),
             display_name: "Unknown User".to_string(),
             avatar_url: None,
@@ -755,10 +909,14 @@ pub async fn upload_message_with_file(
         thread_info: None,
         edited_at: message.edited_at,
         created_at: message.created_at,
+        expires_at: message.expires_at,
         mention_type,
         reactions: None,
         pinned: false,
         message_type: message.message_type,
+        components: None,
+        poll: None,
+        forwarded_from: None,
     };
 
     // Broadcast new message via Redis pub-sub
@@ -945,6 +1103,12 @@ pub async fn download(
         }
         None => (attachment.s3_key.clone(), attachment.mime_type.clone()),
     };
+    // Encrypted variants are opaque too; never let a browser render them
+    let content_type = if attachment.encrypted {
+        ENCRYPTED_MIME_TYPE.to_string()
+    } else {
+        content_type
+    };
 
     // Fetch from S3
     let stream = s3
@@ -1078,6 +1242,83 @@ pub async fn get_signed_url(
 // Helpers
 // ============================================================================
 
+/// Resolve the MIME type of a plaintext upload and check it against the
+/// allowed types and the file's magic bytes.
+fn verified_content_type(
+    state: &AppState,
+    filename: &str,
+    claimed: Option<String>,
+    file_data: &[u8],
+) -> Result<String, UploadError> {
+    // Determine content type
+    let content_type = claimed
+        .or_else(|| {
+            mime_guess::from_path(filename)
+                .first()
+                .map(|m| m.to_string())
+        })
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    // Validate MIME type
+    let allowed_types: Vec<&str> = state.config.allowed_mime_types.as_ref().map_or_else(
+        || DEFAULT_ALLOWED_TYPES.to_vec(),
+        |v| v.iter().map(std::string::String::as_str).collect(),
+    );
+
+    if !allowed_types.contains(&content_type.as_str()) {
+        return Err(UploadError::InvalidMimeType {
+            mime_type: content_type,
+        });
+    }
+
+    // Validate actual file content matches claimed MIME type (magic byte check)
+    validate_file_content(file_data, &content_type)
+}
+
+/// Extension of the S3 object key. Encrypted uploads don't reveal their type.
+fn s3_extension(filename: &str, encrypted: bool) -> &str {
+    if encrypted {
+        return "bin";
+    }
+    std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("bin")
+}
+
+/// Upload a client-encrypted thumbnail next to its encrypted original.
+///
+/// Encrypted files are never processed; their dimensions and blurhash are
+/// part of the encrypted message body.
+async fn upload_encrypted_thumbnail(
+    s3: &S3Client,
+    thumbnail: Option<Vec<u8>>,
+    base_s3_key: &str,
+) -> Result<MediaProcessingOutput, UploadError> {
+    let thumb_key = match thumbnail {
+        Some(data) => {
+            let base_key = base_s3_key
+                .rsplit_once('.')
+                .map_or(base_s3_key, |(base, _)| base);
+            let key = format!("{base_key}_thumb.bin");
+            s3.upload(&key, data, ENCRYPTED_MIME_TYPE)
+                .await
+                .map_err(|e| UploadError::Storage(e.to_string()))?;
+            Some(key)
+        }
+        None => None,
+    };
+
+    Ok(MediaProcessingOutput {
+        width: None,
+        height: None,
+        blurhash: None,
+        thumb_key,
+        medium_key: None,
+        processing_status: "skipped",
+    })
+}
+
 /// Output of image processing + variant S3 upload pipeline.
 struct MediaProcessingOutput {
     width: Option<i32>,
@@ -120,6 +120,12 @@ pub struct Config {
     /// Must be ≤ `max_upload_size` to avoid middleware rejection.
     pub max_emoji_size: usize,
 
+    /// Maximum sticker size in bytes (guild stickers, default: 512KB)
+    ///
+    /// Validated by upload handlers before processing.
+    /// Must be ≤ `max_upload_size` to avoid middleware rejection.
+    pub max_sticker_size: usize,
+
     /// WebRTC STUN server
     pub stun_server: String,
 
@@ -185,6 +191,24 @@ pub struct Config {
     /// SMTP TLS mode: "starttls" (default), "tls", or "none"
     pub smtp_tls: String,
 
+    /// Public base URL of this server (e.g. `https://chat.example.com`), used
+    /// for links in emails. Email digests are disabled without it. Override via
+    /// `PUBLIC_URL`.
+    pub public_url: Option<String>,
+
+    /// VAPID private key for Web Push: base64url-encoded raw 32-byte P-256 scalar
+    /// (optional, enables Web Push delivery). Override via `VAPID_PRIVATE_KEY`.
+    pub vapid_private_key: Option<String>,
+
+    /// VAPID contact (`mailto:` or `https:` URL) sent to push services.
+    /// Override via `VAPID_SUBJECT` (default: `mailto:admin@localhost`).
+    pub vapid_subject: String,
+
+    /// Allow push endpoints on private or loopback addresses over plain HTTP.
+    /// Only for local testing against an HTTP sink. Override via
+    /// `PUSH_ALLOW_PRIVATE_ENDPOINTS` (default: `false`).
+    pub push_allow_private_endpoints: bool,
+
     /// Whether to enable API documentation (Swagger UI) at /api/docs
     ///
     /// Defaults to `true` in debug builds, `false` in release builds.
@@ -214,6 +238,9 @@ pub struct Config {
     /// Maximum number of custom emojis per guild (default: 50)
     pub max_emojis_per_guild: i64,
 
+    /// Maximum number of stickers per guild (default: 30)
+    pub max_stickers_per_guild: i64,
+
     /// Maximum number of bot installations per guild (default: 10)
     pub max_bots_per_guild: i64,
 
@@ -308,6 +335,10 @@ impl Config {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(256 * 1024), // 256KB
+            max_sticker_size: env::var("MAX_STICKER_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(512 * 1024), // 512KB
             stun_server: env::var("STUN_SERVER")
                 .unwrap_or_else(|_| "stun:stun.l.google.com:19302".into()),
             turn_server: env::var("TURN_SERVER").ok().filter(|s| !s.is_empty()),
@@ -356,6 +387,14 @@ impl Config {
             smtp_password: env::var("SMTP_PASSWORD").ok(),
             smtp_from: env::var("SMTP_FROM").ok(),
             smtp_tls: env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".into()),
+            public_url: env::var("PUBLIC_URL").ok(),
+            vapid_private_key: env::var("VAPID_PRIVATE_KEY").ok(),
+            vapid_subject: env::var("VAPID_SUBJECT")
+                .unwrap_or_else(|_| "mailto:admin@localhost".into()),
+            push_allow_private_endpoints: env::var("PUSH_ALLOW_PRIVATE_ENDPOINTS")
+                .ok()
+                .map(|v| v.to_lowercase() == "true" || v == "1")
+                .unwrap_or(false),
             enable_api_docs: env::var("ENABLE_API_DOCS")
                 .ok()
                 .map(|v| v.to_lowercase() == "true" || v == "1")
@@ -389,6 +428,11 @@ impl Config {
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(50)
                 .max(1),
+            max_stickers_per_guild: env::var("MAX_STICKERS_PER_GUILD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30)
+                .max(1),
             max_bots_per_guild: env::var("MAX_BOTS_PER_GUILD")
                 .ok()
                 .and_then(|v| v.parse().ok())
@@ -494,6 +538,7 @@ impl Config {
             max_upload_size: 50 * 1024 * 1024,
             max_avatar_size: 5 * 1024 * 1024,
             max_emoji_size: 256 * 1024,
+            max_sticker_size: 512 * 1024,
             oidc_issuer_url: None,
             oidc_client_id: None,
             oidc_client_secret: None,
@@ -515,6 +560,10 @@ impl Config {
             smtp_password: None,
             smtp_from: None,
             smtp_tls: "starttls".into(),
+            public_url: None,
+            vapid_private_key: None,
+            vapid_subject: "mailto:admin@localhost".into(),
+            push_allow_private_endpoints: false,
             enable_api_docs: true,
             enable_guild_discovery: true,
             max_guilds_per_user: 100,
@@ -522,6 +571,7 @@ impl Config {
             max_channels_per_guild: 200,
             max_roles_per_guild: 50,
             max_emojis_per_guild: 50,
+            max_stickers_per_guild: 30,
             max_bots_per_guild: 10,
             max_webhooks_per_app: 5,
             max_workspaces_per_user: 20,
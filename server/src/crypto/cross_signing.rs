@@ -0,0 +1,593 @@
+//! Cross-Signing Keys and Signatures
+//!
+//! Stores each user's published cross-signing keys and the signatures made
+//! with them: self-signing signatures over the user's own devices, and
+//! user-signing signatures over other users' master keys. Every signature is
+//! checked against the stored keys with [`vc_crypto::cross_signing`] before
+//! it is accepted, so clients can trust what the key endpoints return once
+//! they have verified the master key itself.
+
+use axum::extract::State;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use vc_crypto::CrossSigningPublicKeys;
+
+use crate::api::AppState;
+use crate::auth::{verify_mfa_code, verify_password, AuthError, AuthUser};
+use crate::db;
+
+/// Maximum number of signatures uploaded in one request.
+pub const MAX_SIGNATURES_PER_UPLOAD: usize = 100;
+
+// ============================================================================
+// Request/Response Types
+// ============================================================================
+
+/// A user's published cross-signing keys.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct CrossSigningKeys {
+    /// Master key (base64 Ed25519).
+    pub master_key: String,
+    /// Self-signing key (base64 Ed25519), signs the user's devices.
+    pub self_signing_key: String,
+    /// User-signing key (base64 Ed25519), signs other users' master keys.
+    pub user_signing_key: String,
+    /// Master key signature over the self-signing key.
+    pub self_signing_signature: String,
+    /// Master key signature over the user-signing key.
+    pub user_signing_signature: String,
+}
+
+impl From<CrossSigningKeys> for CrossSigningPublicKeys {
+    fn from(keys: CrossSigningKeys) -> Self {
+        Self {
+            master_key: keys.master_key,
+            self_signing_key: keys.self_signing_key,
+            user_signing_key: keys.user_signing_key,
+            self_signing_signature: keys.self_signing_signature,
+            user_signing_signature: keys.user_signing_signature,
+        }
+    }
+}
+
+/// Request to publish cross-signing keys.
+///
+/// Replacing published keys requires re-authentication: the current password
+/// for local accounts, and a TOTP or backup code when MFA is enabled.
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct UploadCrossSigningKeysRequest {
+    /// Keys to publish.
+    #[serde(flatten)]
+    pub keys: CrossSigningKeys,
+    /// Current password, to replace published keys.
+    pub password: Option<String>,
+    /// TOTP or backup code, to replace published keys.
+    pub mfa_code: Option<String>,
+}
+
+impl std::fmt::Debug for UploadCrossSigningKeysRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UploadCrossSigningKeysRequest")
+            .field("keys", &self.keys)
+            .field("password", &self.password.as_ref().map(|_| "[REDACTED]"))
+            .field("mfa_code", &self.mfa_code.as_ref().map(|_| "[REDACTED]"))
+            .finish()
+    }
+}
+
+/// Signature over one of the caller's own devices.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct DeviceSignatureUpload {
+    /// Device being signed.
+    pub device_id: Uuid,
+    /// Self-signing key signature over the device's identity keys.
+    pub signature: String,
+}
+
+/// Signature over another user's master key.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct UserSignatureUpload {
+    /// User being signed.
+    pub user_id: Uuid,
+    /// User-signing key signature over the user's current master key.
+    pub signature: String,
+}
+
+/// Request to upload cross-signing signatures.
+#[derive(Debug, Default, Deserialize, utoipa::ToSchema)]
+pub struct UploadSignaturesRequest {
+    /// Signatures over the caller's own devices.
+    #[serde(default)]
+    pub devices: Vec<DeviceSignatureUpload>,
+    /// Signatures over other users' master keys.
+    #[serde(default)]
+    pub users: Vec<UserSignatureUpload>,
+}
+
+/// Response after uploading signatures.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct UploadSignaturesResponse {
+    /// Number of device signatures stored.
+    pub devices_signed: usize,
+    /// Number of user signatures stored.
+    pub users_signed: usize,
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Fetch a user's cross-signing keys, if published.
+pub async fn get_keys(pool: &PgPool, user_id: Uuid) -> sqlx::Result<Option<CrossSigningKeys>> {
+    sqlx::query_as(
+        r"
+        SELECT master_key, self_signing_key, user_signing_key,
+               self_signing_signature, user_signing_signature
+        FROM cross_signing_keys
+        WHERE user_id = $1
+        ",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Fetch `signer_id`'s signature over `target_id`'s current master key.
+///
+/// Signatures over a master key that has since been replaced are ignored.
+pub async fn get_user_signature(
+    pool: &PgPool,
+    signer_id: Uuid,
+    target_id: Uuid,
+) -> sqlx::Result<Option<String>> {
+    sqlx::query_scalar(
+        r"
+        SELECT s.signature
+        FROM user_signatures s
+        JOIN cross_signing_keys k ON k.user_id = s.target_id AND k.master_key = s.master_key
+        WHERE s.signer_id = $1 AND s.target_id = $2
+        ",
+    )
+    .bind(signer_id)
+    .bind(target_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Re-authenticate the caller before their published keys are replaced.
+///
+/// Local accounts must confirm their password and accounts with MFA enabled a
+/// TOTP or backup code. Accounts with neither have nothing to confirm beyond
+/// the access token, so they must enable MFA first.
+async fn verify_reauthentication(
+    state: &AppState,
+    user_id: Uuid,
+    req: &UploadCrossSigningKeysRequest,
+) -> Result<(), AuthError> {
+    let user = db::find_user_by_id(&state.db, user_id)
+        .await
+        .map_err(AuthError::Database)?
+        .ok_or(AuthError::UserNotFound)?;
+
+    match user.password_hash.as_deref() {
+        Some(password_hash) => {
+            let password = req
+                .password
+                .as_deref()
+                .ok_or(AuthError::InvalidCredentials)?;
+            let password = password.to_owned();
+            let password_hash = password_hash.to_owned();
+            let valid =
+                tokio::task::spawn_blocking(move || verify_password(&password, &password_hash))
+                    .await
+                    .map_err(|e| AuthError::Internal(format!("Password verification failed: {e}")))?
+                    .map_err(|_| AuthError::PasswordHash)?;
+            if !valid {
+                return Err(AuthError::InvalidCredentials);
+            }
+        }
+        None if user.mfa_secret.is_none() => return Err(AuthError::MfaRequired),
+        None => {}
+    }
+
+    if user.mfa_secret.is_some() {
+        let mfa_code = req.mfa_code.as_deref().ok_or(AuthError::MfaRequired)?;
+        verify_mfa_code(state, &user, mfa_code).await?;
+    }
+    Ok(())
+}
+
+fn validate_signatures(req: &UploadSignaturesRequest) -> Result<(), AuthError> {
+    if req.devices.is_empty() && req.users.is_empty() {
+        return Err(AuthError::Validation("No signatures provided".to_string()));
+    }
+    if req.devices.len() + req.users.len() > MAX_SIGNATURES_PER_UPLOAD {
+        return Err(AuthError::Validation(format!(
+            "Cannot upload more than {MAX_SIGNATURES_PER_UPLOAD} signatures at once"
+        )));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Publish the caller's cross-signing keys.
+///
+/// The sub-keys must be signed by the master key. The first upload needs no
+/// further proof, but replacing any of the keys requires re-authentication
+/// and invalidates everything signed with the old identity: device signatures
+/// are cleared and signatures made by or for the old keys are removed.
+///
+/// PUT /api/keys/cross-signing
+#[utoipa::path(
+    put,
+    path = "/api/keys/cross-signing",
+    tag = "crypto",
+    request_body = UploadCrossSigningKeysRequest,
+    responses(
+        (status = 200, description = "Cross-signing keys published", body = CrossSigningKeys),
+        (status = 401, description = "Invalid password or MFA code"),
+        (status = 403, description = "MFA code required"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, req), fields(user_id = %auth_user.id))]
+pub async fn upload_cross_signing_keys(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<UploadCrossSigningKeysRequest>,
+) -> Result<Json<CrossSigningKeys>, AuthError> {
+    let user_id = auth_user.id;
+    CrossSigningPublicKeys::from(req.keys.clone())
+        .verify(&user_id.to_string())
+        .map_err(|e| AuthError::Validation(format!("Invalid cross-signing keys: {e}")))?;
+
+    // Re-authenticate before taking the row lock; Argon2 is too slow to hold it
+    let keys = req.keys.clone();
+    let replaces = |previous: &CrossSigningKeys| {
+        previous.master_key != keys.master_key
+            || previous.self_signing_key != keys.self_signing_key
+            || previous.user_signing_key != keys.user_signing_key
+    };
+    let reauthenticated = match get_keys(&state.db, user_id)
+        .await
+        .map_err(AuthError::Database)?
+    {
+        Some(previous) if replaces(&previous) => {
+            verify_reauthentication(&state, user_id, &req).await?;
+            true
+        }
+        _ => false,
+    };
+
+    let mut tx = state.db.begin().await.map_err(AuthError::Database)?;
+
+    let previous: Option<CrossSigningKeys> = sqlx::query_as(
+        r"
+        SELECT master_key, self_signing_key, user_signing_key,
+               self_signing_signature, user_signing_signature
+        FROM cross_signing_keys
+        WHERE user_id = $1
+        FOR UPDATE
+        ",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(AuthError::Database)?;
+
+    let reset = previous.as_ref().is_some_and(replaces);
+    if reset && !reauthenticated {
+        // Another upload published keys since the check above
+        return Err(AuthError::Validation(
+            "Cross-signing keys changed during upload, try again".to_string(),
+        ));
+    }
+
+    sqlx::query(
+        r"
+        INSERT INTO cross_signing_keys (
+            user_id, master_key, self_signing_key, user_signing_key,
+            self_signing_signature, user_signing_signature
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (user_id) DO UPDATE SET
+            master_key = EXCLUDED.master_key,
+            self_signing_key = EXCLUDED.self_signing_key,
+            user_signing_key = EXCLUDED.user_signing_key,
+            self_signing_signature = EXCLUDED.self_signing_signature,
+            user_signing_signature = EXCLUDED.user_signing_signature,
+            updated_at = NOW()
+        ",
+    )
+    .bind(user_id)
+    .bind(&keys.master_key)
+    .bind(&keys.self_signing_key)
+    .bind(&keys.user_signing_key)
+    .bind(&keys.self_signing_signature)
+    .bind(&keys.user_signing_signature)
+    .execute(&mut *tx)
+    .await
+    .map_err(AuthError::Database)?;
+
+    // Signatures made with replaced keys, or over a replaced master key, no longer verify
+    if reset {
+        sqlx::query(
+            r"
+            UPDATE user_devices
+            SET cross_signing_signature = NULL, is_verified = FALSE
+            WHERE user_id = $1
+            ",
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AuthError::Database)?;
+
+        sqlx::query("DELETE FROM user_signatures WHERE signer_id = $1 OR target_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(AuthError::Database)?;
+    }
+
+    tx.commit().await.map_err(AuthError::Database)?;
+
+    tracing::info!(user_id = %user_id, reset = reset, "Cross-signing keys published");
+
+    Ok(Json(keys))
+}
+
+/// Upload cross-signing signatures.
+///
+/// Device signatures must be made with the caller's self-signing key over one
+/// of their own devices; user signatures with the caller's user-signing key
+/// over the target's current master key. The request is rejected as a whole
+/// if any signature fails to verify.
+///
+/// POST /api/keys/signatures
+#[utoipa::path(
+    post,
+    path = "/api/keys/signatures",
+    tag = "crypto",
+    request_body = UploadSignaturesRequest,
+    responses(
+        (status = 200, description = "Signatures stored", body = UploadSignaturesResponse),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, req), fields(user_id = %auth_user.id))]
+pub async fn upload_signatures(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<UploadSignaturesRequest>,
+) -> Result<Json<UploadSignaturesResponse>, AuthError> {
+    let user_id = auth_user.id;
+    validate_signatures(&req)?;
+
+    let own_keys: CrossSigningPublicKeys = get_keys(&state.db, user_id)
+        .await
+        .map_err(AuthError::Database)?
+        .ok_or_else(|| AuthError::Validation("Publish cross-signing keys first".to_string()))?
+        .into();
+    let signer = user_id.to_string();
+
+    // Verify everything before storing anything
+    let mut devices = Vec::with_capacity(req.devices.len());
+    for upload in &req.devices {
+        let (ed25519, curve25519): (String, String) = sqlx::query_as(
+            r"
+            SELECT identity_key_ed25519, identity_key_curve25519
+            FROM user_devices
+            WHERE id = $1 AND user_id = $2
+            ",
+        )
+        .bind(upload.device_id)
+        .bind(user_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(AuthError::Database)?
+        .ok_or_else(|| AuthError::NotFound("Device not found".to_string()))?;
+
+        own_keys
+            .verify_device(
+                &signer,
+                &upload.device_id.to_string(),
+                &ed25519,
+                &curve25519,
+                &upload.signature,
+            )
+            .map_err(|_| {
+                AuthError::Validation(format!("Invalid signature for device {}", upload.device_id))
+            })?;
+        devices.push(upload);
+    }
+
+    let mut users = Vec::with_capacity(req.users.len());
+    for upload in &req.users {
+        if upload.user_id == user_id {
+            return Err(AuthError::Validation(
+                "Sign your own devices, not your own master key".to_string(),
+            ));
+        }
+        let target = get_keys(&state.db, upload.user_id)
+            .await
+            .map_err(AuthError::Database)?
+            .ok_or_else(|| AuthError::NotFound("User has no cross-signing keys".to_string()))?;
+
+        own_keys
+            .verify_user(
+                &upload.user_id.to_string(),
+                &target.master_key,
+                &upload.signature,
+            )
+            .map_err(|_| {
+                AuthError::Validation(format!("Invalid signature for user {}", upload.user_id))
+            })?;
+        users.push((upload, target.master_key));
+    }
+
+    let mut tx = state.db.begin().await.map_err(AuthError::Database)?;
+    for upload in &devices {
+        sqlx::query(
+            r"
+            UPDATE user_devices
+            SET cross_signing_signature = $1, is_verified = TRUE
+            WHERE id = $2 AND user_id = $3
+            ",
+        )
+        .bind(&upload.signature)
+        .bind(upload.device_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(AuthError::Database)?;
+    }
+    for (upload, master_key) in &users {
+        sqlx::query(
+            r"
+            INSERT INTO user_signatures (signer_id, target_id, master_key, signature)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (signer_id, target_id) DO UPDATE SET
+                master_key = EXCLUDED.master_key,
+                signature = EXCLUDED.signature,
+                created_at = NOW()
+            ",
+        )
+        .bind(user_id)
+        .bind(upload.user_id)
+        .bind(master_key)
+        .bind(&upload.signature)
+        .execute(&mut *tx)
+        .await
+        .map_err(AuthError::Database)?;
+    }
+    tx.commit().await.map_err(AuthError::Database)?;
+
+    tracing::info!(
+        user_id = %user_id,
+        devices_signed = devices.len(),
+        users_signed = users.len(),
+        "Cross-signing signatures uploaded"
+    );
+
+    Ok(Json(UploadSignaturesResponse {
+        devices_signed: devices.len(),
+        users_signed: users.len(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use vc_crypto::CrossSigningIdentity;
+
+    use super::*;
+    use crate::db;
+
+    fn keys_for(identity: &CrossSigningIdentity) -> CrossSigningKeys {
+        let keys = identity.public_keys();
+        CrossSigningKeys {
+            master_key: keys.master_key,
+            self_signing_key: keys.self_signing_key,
+            user_signing_key: keys.user_signing_key,
+            self_signing_signature: keys.self_signing_signature,
+            user_signing_signature: keys.user_signing_signature,
+        }
+    }
+
+    async fn store_keys(pool: &PgPool, user_id: Uuid, keys: &CrossSigningKeys) {
+        sqlx::query(
+            r"
+            INSERT INTO cross_signing_keys (
+                user_id, master_key, self_signing_key, user_signing_key,
+                self_signing_signature, user_signing_signature
+            )
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (user_id) DO UPDATE SET master_key = EXCLUDED.master_key
+            ",
+        )
+        .bind(user_id)
+        .bind(&keys.master_key)
+        .bind(&keys.self_signing_key)
+        .bind(&keys.user_signing_key)
+        .bind(&keys.self_signing_signature)
+        .bind(&keys.user_signing_signature)
+        .execute(pool)
+        .await
+        .expect("store keys");
+    }
+
+    #[test]
+    fn test_signature_request_limits() {
+        assert!(validate_signatures(&UploadSignaturesRequest::default()).is_err());
+
+        let users = |count: usize| UploadSignaturesRequest {
+            devices: Vec::new(),
+            users: (0..count)
+                .map(|_| UserSignatureUpload {
+                    user_id: Uuid::now_v7(),
+                    signature: "sig".to_string(),
+                })
+                .collect(),
+        };
+        assert!(validate_signatures(&users(1)).is_ok());
+        assert!(validate_signatures(&users(MAX_SIGNATURES_PER_UPLOAD + 1)).is_err());
+    }
+
+    #[test]
+    fn test_published_keys_are_bound_to_owner() {
+        let user_id = Uuid::now_v7();
+        let identity = CrossSigningIdentity::new(&user_id.to_string());
+        let keys = CrossSigningPublicKeys::from(keys_for(&identity));
+
+        assert!(keys.verify(&user_id.to_string()).is_ok());
+        assert!(keys.verify(&Uuid::now_v7().to_string()).is_err());
+    }
+
+    #[sqlx::test]
+    async fn user_signature_follows_current_master_key(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create bob");
+        let bob_keys = keys_for(&CrossSigningIdentity::new(&bob.id.to_string()));
+        store_keys(&pool, bob.id, &bob_keys).await;
+
+        sqlx::query(
+            r"
+            INSERT INTO user_signatures (signer_id, target_id, master_key, signature)
+            VALUES ($1, $2, $3, 'sig')
+            ",
+        )
+        .bind(alice.id)
+        .bind(bob.id)
+        .bind(&bob_keys.master_key)
+        .execute(&pool)
+        .await
+        .expect("store signature");
+
+        assert_eq!(
+            get_user_signature(&pool, alice.id, bob.id).await.unwrap(),
+            Some("sig".to_string())
+        );
+        // Signatures are only visible to the signer
+        assert_eq!(
+            get_user_signature(&pool, bob.id, alice.id).await.unwrap(),
+            None
+        );
+
+        // A new master key orphans the old signature
+        let new_keys = keys_for(&CrossSigningIdentity::new(&bob.id.to_string()));
+        store_keys(&pool, bob.id, &new_keys).await;
+        assert_eq!(
+            get_user_signature(&pool, alice.id, bob.id).await.unwrap(),
+            None
+        );
+    }
+}
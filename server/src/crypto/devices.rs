@@ -0,0 +1,398 @@
+//! E2EE Device Management
+//!
+//! Rename and delete a user's registered E2EE devices. Each device is linked
+//! to the auth session that last uploaded its keys, so deleting a lost device
+//! also logs that session out. Removals are announced over the WebSocket as
+//! [`ServerEvent::DeviceRemoved`] so the user's other devices and their
+//! contacts stop encrypting to it and rotate their Megolm sessions.
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use serde::Deserialize;
+use sqlx::{FromRow, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use super::handlers::DeviceKeys;
+use crate::api::AppState;
+use crate::auth::handlers::extract_current_token_hash;
+use crate::auth::{AuthError, AuthUser};
+use crate::ws::{broadcast_to_contacts, broadcast_to_user, ServerEvent};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Maximum length of a device name.
+pub const MAX_DEVICE_NAME_LEN: usize = 128;
+
+// ============================================================================
+// Request Types
+// ============================================================================
+
+/// Request to rename a device.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct RenameDeviceRequest {
+    /// New device name. An empty or missing name clears it.
+    pub device_name: Option<String>,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+/// Trim a device name, mapping blank names to `None`.
+fn normalize_device_name(name: Option<&str>) -> Result<Option<String>, AuthError> {
+    let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) else {
+        return Ok(None);
+    };
+    if name.chars().count() > MAX_DEVICE_NAME_LEN {
+        return Err(AuthError::Validation(format!(
+            "Device name must be {MAX_DEVICE_NAME_LEN} characters or less"
+        )));
+    }
+    Ok(Some(name.to_string()))
+}
+
+/// Resolve the auth session the request was made from, if any.
+///
+/// Requests authenticated without a session-backed token (or with an expired
+/// one) resolve to `None`; the device is then simply not linked.
+pub async fn current_session_id(
+    pool: &PgPool,
+    user_id: Uuid,
+    headers: &HeaderMap,
+    jar: &CookieJar,
+) -> sqlx::Result<Option<Uuid>> {
+    let Some(token_hash) = extract_current_token_hash(headers, jar) else {
+        return Ok(None);
+    };
+    sqlx::query_scalar(
+        "SELECT id FROM sessions WHERE token_hash = $1 AND user_id = $2 AND expires_at > NOW()",
+    )
+    .bind(&token_hash)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Move device links from a rotated session to its replacement.
+pub async fn move_session_devices(
+    executor: impl PgExecutor<'_>,
+    old_session_id: Uuid,
+    new_session_id: Uuid,
+) -> sqlx::Result<()> {
+    sqlx::query("UPDATE user_devices SET session_id = $2 WHERE session_id = $1")
+        .bind(old_session_id)
+        .bind(new_session_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// A device removed by [`remove_device`].
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+struct RemovedDevice {
+    /// Curve25519 identity key, so peers can drop their Olm sessions with it.
+    identity_key_curve25519: String,
+    /// The session that was logged out with the device, if it had one.
+    session_id: Option<Uuid>,
+}
+
+/// Delete a device, its prekeys and its linked session.
+///
+/// Pending to-device messages and push registrations go with the device and
+/// session through their foreign keys. The device linked to
+/// `current_session_id` is refused, as deleting it would log the caller out.
+async fn remove_device(
+    pool: &PgPool,
+    user_id: Uuid,
+    device_id: Uuid,
+    current_session_id: Option<Uuid>,
+) -> Result<RemovedDevice, AuthError> {
+    let mut tx = pool.begin().await?;
+
+    let device: RemovedDevice = sqlx::query_as(
+        "
+        SELECT identity_key_curve25519, session_id FROM user_devices
+        WHERE id = $1 AND user_id = $2
+        FOR UPDATE
+        ",
+    )
+    .bind(device_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AuthError::NotFound("Device not found".to_string()))?;
+    if device.session_id.is_some() && device.session_id == current_session_id {
+        return Err(AuthError::Forbidden);
+    }
+
+    sqlx::query("DELETE FROM prekeys WHERE device_id = $1")
+        .bind(device_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM user_devices WHERE id = $1")
+        .bind(device_id)
+        .execute(&mut *tx)
+        .await?;
+    if let Some(session_id) = device.session_id {
+        sqlx::query("DELETE FROM sessions WHERE id = $1 AND user_id = $2")
+            .bind(session_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+    Ok(device)
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Rename one of the current user's devices.
+///
+/// PATCH /api/keys/devices/:id
+#[utoipa::path(
+    patch,
+    path = "/api/keys/devices/{device_id}",
+    tag = "crypto",
+    params(("device_id" = Uuid, Path, description = "Device ID")),
+    request_body = RenameDeviceRequest,
+    responses(
+        (status = 200, description = "Device renamed", body = DeviceKeys),
+        (status = 404, description = "Device not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, req), fields(user_id = %auth_user.id, device_id = %device_id))]
+pub async fn rename_device(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(device_id): Path<Uuid>,
+    Json(req): Json<RenameDeviceRequest>,
+) -> Result<Json<DeviceKeys>, AuthError> {
+    let device_name = normalize_device_name(req.device_name.as_deref())?;
+
+    let device: DeviceKeys = sqlx::query_as(
+        "
+        UPDATE user_devices SET device_name = $3
+        WHERE id = $1 AND user_id = $2
+        RETURNING
+            id as device_id,
+            device_name,
+            identity_key_ed25519,
+            identity_key_curve25519,
+            cross_signing_signature
+        ",
+    )
+    .bind(device_id)
+    .bind(auth_user.id)
+    .bind(&device_name)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| AuthError::NotFound("Device not found".to_string()))?;
+
+    Ok(Json(device))
+}
+
+/// Delete one of the current user's devices.
+///
+/// Wipes the device's prekeys, logs out the session linked to it, and tells
+/// the user's other devices and contacts to stop encrypting to it. The device
+/// used for the current session cannot be deleted this way (use logout
+/// instead).
+///
+/// DELETE /api/keys/devices/:id
+#[utoipa::path(
+    delete,
+    path = "/api/keys/devices/{device_id}",
+    tag = "crypto",
+    params(("device_id" = Uuid, Path, description = "Device ID")),
+    responses(
+        (status = 204, description = "Device deleted"),
+        (status = 403, description = "Device belongs to the current session"),
+        (status = 404, description = "Device not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, headers, jar), fields(user_id = %auth_user.id, device_id = %device_id))]
+pub async fn delete_device(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(device_id): Path<Uuid>,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> Result<StatusCode, AuthError> {
+    let current_session = current_session_id(&state.db, auth_user.id, &headers, &jar).await?;
+    let removed = remove_device(&state.db, auth_user.id, device_id, current_session).await?;
+
+    tracing::info!(
+        user_id = %auth_user.id,
+        device_id = %device_id,
+        session_id = ?removed.session_id,
+        "Device deleted"
+    );
+
+    // The rows are gone either way; clients also drop unknown devices the
+    // next time they fetch keys
+    let event = ServerEvent::DeviceRemoved {
+        user_id: auth_user.id,
+        device_id,
+        identity_key_curve25519: removed.identity_key_curve25519,
+    };
+    if let Err(e) = broadcast_to_user(&state.redis, auth_user.id, &event).await {
+        tracing::warn!(error = %e, "Failed to notify own devices of device removal");
+    }
+    if let Err(e) = broadcast_to_contacts(&state.db, &state.redis, auth_user.id, &event).await {
+        tracing::warn!(error = %e, "Failed to notify contacts of device removal");
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, fixtures};
+
+    async fn create_session(pool: &PgPool, user_id: Uuid, token_hash: &str) -> Uuid {
+        sqlx::query_scalar(
+            r"
+            INSERT INTO sessions (user_id, token_hash, expires_at)
+            VALUES ($1, $2, NOW() + INTERVAL '1 day')
+            RETURNING id
+            ",
+        )
+        .bind(user_id)
+        .bind(token_hash)
+        .fetch_one(pool)
+        .await
+        .expect("create session")
+    }
+
+    async fn linked_device(pool: &PgPool, user_id: Uuid, session_id: Uuid) -> Uuid {
+        let device_id = fixtures::create_device(pool, user_id).await;
+        sqlx::query("UPDATE user_devices SET session_id = $1 WHERE id = $2")
+            .bind(session_id)
+            .bind(device_id)
+            .execute(pool)
+            .await
+            .expect("link session");
+        device_id
+    }
+
+    #[test]
+    fn test_device_name_normalization() {
+        assert_eq!(normalize_device_name(None).unwrap(), None);
+        assert_eq!(normalize_device_name(Some("   ")).unwrap(), None);
+        assert_eq!(
+            normalize_device_name(Some("  Laptop ")).unwrap().as_deref(),
+            Some("Laptop")
+        );
+        assert!(normalize_device_name(Some(&"a".repeat(MAX_DEVICE_NAME_LEN))).is_ok());
+        assert!(normalize_device_name(Some(&"a".repeat(MAX_DEVICE_NAME_LEN + 1))).is_err());
+    }
+
+    #[sqlx::test]
+    async fn remove_device_wipes_prekeys_and_session(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let session_id = create_session(&pool, user.id, "hash-a").await;
+        let device_id = linked_device(&pool, user.id, session_id).await;
+        sqlx::query("INSERT INTO prekeys (device_id, key_id, public_key) VALUES ($1, 'k1', 'pk')")
+            .bind(device_id)
+            .execute(&pool)
+            .await
+            .expect("create prekey");
+
+        let removed = remove_device(&pool, user.id, device_id, None)
+            .await
+            .expect("remove device");
+        assert_eq!(removed.session_id, Some(session_id));
+
+        let prekeys: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM prekeys WHERE device_id = $1")
+            .bind(device_id)
+            .fetch_one(&pool)
+            .await
+            .expect("count prekeys");
+        assert_eq!(prekeys, 0);
+        let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_one(&pool)
+            .await
+            .expect("count sessions");
+        assert_eq!(sessions, 0);
+
+        // Already gone
+        let result = remove_device(&pool, user.id, device_id, None).await;
+        assert!(matches!(result, Err(AuthError::NotFound(_))));
+    }
+
+    #[sqlx::test]
+    async fn remove_device_rejects_other_users(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create bob");
+        let device_id = fixtures::create_device(&pool, alice.id).await;
+
+        let result = remove_device(&pool, bob.id, device_id, None).await;
+        assert!(matches!(result, Err(AuthError::NotFound(_))));
+
+        let exists: bool =
+            sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM user_devices WHERE id = $1)")
+                .bind(device_id)
+                .fetch_one(&pool)
+                .await
+                .expect("device exists");
+        assert!(exists);
+    }
+
+    #[sqlx::test]
+    async fn remove_device_refuses_current_session(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let session_id = create_session(&pool, user.id, "hash-a").await;
+        let device_id = linked_device(&pool, user.id, session_id).await;
+
+        let result = remove_device(&pool, user.id, device_id, Some(session_id)).await;
+        assert!(matches!(result, Err(AuthError::Forbidden)));
+
+        // Devices without a linked session are never the current one
+        let unlinked = fixtures::create_device(&pool, user.id).await;
+        let removed = remove_device(&pool, user.id, unlinked, Some(session_id))
+            .await
+            .expect("remove unlinked device");
+        assert_eq!(removed.session_id, None);
+    }
+
+    #[sqlx::test]
+    async fn rotated_session_keeps_device_link(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let old_session = create_session(&pool, user.id, "hash-old").await;
+        let new_session = create_session(&pool, user.id, "hash-new").await;
+        let device_id = linked_device(&pool, user.id, old_session).await;
+
+        move_session_devices(&pool, old_session, new_session)
+            .await
+            .expect("move devices");
+
+        let linked: Option<Uuid> =
+            sqlx::query_scalar("SELECT session_id FROM user_devices WHERE id = $1")
+                .bind(device_id)
+                .fetch_one(&pool)
+                .await
+                .expect("linked session");
+        assert_eq!(linked, Some(new_session));
+    }
+}
@@ -3,16 +3,21 @@
 //! Handlers for uploading identity keys, prekeys, retrieving user keys, and key backups.
 
 use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use axum::http::{HeaderMap, StatusCode};
 use axum::Json;
+use axum_extra::extract::CookieJar;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::cross_signing::{self, CrossSigningKeys};
+use super::devices::{self, MAX_DEVICE_NAME_LEN};
+use super::prekeys::{self, FallbackKey, LOW_PREKEY_THRESHOLD};
 use crate::api::AppState;
 use crate::auth::{AuthError, AuthUser};
+use crate::ws::broadcast_to_user;
 
 // ============================================================================
 // Request/Response Types
@@ -29,6 +34,9 @@ pub struct UploadKeysRequest {
     pub identity_key_curve25519: String,
     /// One-time prekeys to upload.
     pub one_time_prekeys: Vec<PrekeyUpload>,
+    /// Signed fallback key, replacing the device's current one.
+    #[serde(default)]
+    pub fallback_key: Option<FallbackKey>,
 }
 
 /// A single prekey to upload.
@@ -56,6 +64,11 @@ pub struct UploadKeysResponse {
 pub struct UserKeysResponse {
     /// List of devices with their public keys.
     pub devices: Vec<DeviceKeys>,
+    /// The user's cross-signing keys, if published.
+    pub cross_signing: Option<CrossSigningKeys>,
+    /// The caller's user-signing signature over this user's master key, if
+    /// the caller has verified them.
+    pub user_signature: Option<String>,
 }
 
 /// Request to claim a prekey from a specific device.
@@ -76,6 +89,9 @@ pub struct ClaimPrekeyResponse {
     pub identity_key_curve25519: String,
     /// The claimed one-time prekey (if available).
     pub one_time_prekey: Option<ClaimedPrekey>,
+    /// The device's signed fallback key, returned only when no one-time
+    /// prekey was left. Verify the signature before using it.
+    pub fallback_key: Option<FallbackKey>,
 }
 
 /// A claimed one-time prekey.
@@ -98,6 +114,8 @@ pub struct DeviceKeys {
     pub identity_key_ed25519: String,
     /// Curve25519 key exchange key (base64-encoded).
     pub identity_key_curve25519: String,
+    /// Self-signing key signature over this device, if cross-signed.
+    pub cross_signing_signature: Option<String>,
 }
 
 // ============================================================================
@@ -120,8 +138,9 @@ const MAX_DEVICES_PER_USER: i64 = 10;
 /// Upload identity keys and prekeys for a device.
 ///
 /// Creates a new device if the identity key is new, or updates the existing
-/// device's `last_seen_at` timestamp. Prekeys are uploaded with `ON CONFLICT
-/// DO NOTHING` to avoid duplicate key errors.
+/// device's `last_seen_at` timestamp. The device is linked to the uploading
+/// auth session so deleting it later logs that session out. Prekeys are
+/// uploaded with `ON CONFLICT DO NOTHING` to avoid duplicate key errors.
 ///
 /// POST /api/keys/upload
 #[utoipa::path(
@@ -134,20 +153,22 @@ const MAX_DEVICES_PER_USER: i64 = 10;
     ),
     security(("bearer_auth" = [])),
 )]
-#[tracing::instrument(skip(state, req), fields(user_id = %auth_user.id))]
+#[tracing::instrument(skip(state, headers, jar, req), fields(user_id = %auth_user.id))]
 pub async fn upload_keys(
     State(state): State<AppState>,
     auth_user: AuthUser,
+    headers: HeaderMap,
+    jar: CookieJar,
     Json(req): Json<UploadKeysRequest>,
 ) -> Result<Json<UploadKeysResponse>, AuthError> {
     let user_id = auth_user.id;
 
     // Validate input lengths to prevent abuse
     if let Some(ref name) = req.device_name {
-        if name.len() > 128 {
-            return Err(AuthError::Validation(
-                "Device name must be 128 characters or less".to_string(),
-            ));
+        if name.len() > MAX_DEVICE_NAME_LEN {
+            return Err(AuthError::Validation(format!(
+                "Device name must be {MAX_DEVICE_NAME_LEN} characters or less"
+            )));
         }
     }
     // Validate identity keys are valid base64-encoded curve points
@@ -160,6 +181,13 @@ pub async fn upload_keys(
             "Cannot upload more than {MAX_PREKEYS_PER_UPLOAD} prekeys at once"
         )));
     }
+    if let Some(ref fallback_key) = req.fallback_key {
+        prekeys::validate_fallback_key(
+            fallback_key,
+            &req.identity_key_ed25519,
+            &req.identity_key_curve25519,
+        )?;
+    }
 
     // Check device count limit before inserting a new device.
     // We only enforce this for genuinely new devices (not updates to existing ones).
@@ -187,13 +215,20 @@ pub async fn upload_keys(
         }
     }
 
+    let session_id = devices::current_session_id(&state.db, user_id, &headers, &jar)
+        .await
+        .map_err(AuthError::Database)?;
+
     // Insert or update device
     let device_id: Uuid = sqlx::query_scalar(
         "
-        INSERT INTO user_devices (user_id, device_name, identity_key_ed25519, identity_key_curve25519)
-        VALUES ($1, $2, $3, $4)
+        INSERT INTO user_devices (user_id, device_name, identity_key_ed25519, identity_key_curve25519, session_id)
+        VALUES ($1, $2, $3, $4, $5)
         ON CONFLICT (user_id, identity_key_curve25519)
-        DO UPDATE SET last_seen_at = NOW(), device_name = COALESCE(EXCLUDED.device_name, user_devices.device_name)
+        DO UPDATE SET
+            last_seen_at = NOW(),
+            device_name = COALESCE(EXCLUDED.device_name, user_devices.device_name),
+            session_id = COALESCE(EXCLUDED.session_id, user_devices.session_id)
         RETURNING id
         ",
     )
@@ -201,6 +236,7 @@ pub async fn upload_keys(
     .bind(&req.device_name)
     .bind(&req.identity_key_ed25519)
     .bind(&req.identity_key_curve25519)
+    .bind(session_id)
     .fetch_one(&state.db)
     .await
     .map_err(AuthError::Database)?;
@@ -236,11 +272,18 @@ pub async fn upload_keys(
         }
     }
 
+    if let Some(ref fallback_key) = req.fallback_key {
+        prekeys::store_fallback_key(&state.db, device_id, fallback_key)
+            .await
+            .map_err(AuthError::Database)?;
+    }
+
     tracing::info!(
         user_id = %user_id,
         device_id = %device_id,
         prekeys_uploaded = prekeys_uploaded,
         prekeys_skipped = prekeys_skipped,
+        fallback_key = req.fallback_key.is_some(),
         "Keys uploaded"
     );
 
@@ -253,8 +296,10 @@ pub async fn upload_keys(
 
 /// Get a user's device keys for encryption.
 ///
-/// Returns all devices and their public identity keys for a given user.
-/// This is used when establishing encrypted sessions with another user.
+/// Returns all devices and their public identity keys for a given user,
+/// along with the user's cross-signing keys and, if the caller has verified
+/// the user, the caller's signature over their master key. This is used when
+/// establishing encrypted sessions with another user.
 ///
 /// GET /api/users/:id/keys
 #[utoipa::path(
@@ -267,22 +312,20 @@ pub async fn upload_keys(
     ),
     security(("bearer_auth" = [])),
 )]
-#[tracing::instrument(skip(state, auth_user), fields(target_user_id = %user_id))]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id, target_user_id = %user_id))]
 pub async fn get_user_keys(
     State(state): State<AppState>,
     auth_user: AuthUser,
     Path(user_id): Path<Uuid>,
 ) -> Result<Json<UserKeysResponse>, AuthError> {
-    // auth_user is required for authentication but not used in this handler
-    let _ = auth_user;
-
     let devices: Vec<DeviceKeys> = sqlx::query_as(
         "
         SELECT
             id as device_id,
             device_name,
             identity_key_ed25519,
-            identity_key_curve25519
+            identity_key_curve25519,
+            cross_signing_signature
         FROM user_devices
         WHERE user_id = $1
         ORDER BY last_seen_at DESC
@@ -293,7 +336,18 @@ pub async fn get_user_keys(
     .await
     .map_err(AuthError::Database)?;
 
-    Ok(Json(UserKeysResponse { devices }))
+    let cross_signing = cross_signing::get_keys(&state.db, user_id)
+        .await
+        .map_err(AuthError::Database)?;
+    let user_signature = cross_signing::get_user_signature(&state.db, auth_user.id, user_id)
+        .await
+        .map_err(AuthError::Database)?;
+
+    Ok(Json(UserKeysResponse {
+        devices,
+        cross_signing,
+        user_signature,
+    }))
 }
 
 /// Get the current user's own device keys.
@@ -321,7 +375,8 @@ pub async fn get_own_devices(
             id as device_id,
             device_name,
             identity_key_ed25519,
-            identity_key_curve25519
+            identity_key_curve25519,
+            cross_signing_signature
         FROM user_devices
         WHERE user_id = $1
         ORDER BY last_seen_at DESC
@@ -332,7 +387,15 @@ pub async fn get_own_devices(
     .await
     .map_err(AuthError::Database)?;
 
-    Ok(Json(UserKeysResponse { devices }))
+    let cross_signing = cross_signing::get_keys(&state.db, auth_user.id)
+        .await
+        .map_err(AuthError::Database)?;
+
+    Ok(Json(UserKeysResponse {
+        devices,
+        cross_signing,
+        user_signature: None,
+    }))
 }
 
 /// Claim a prekey for a specific device (atomic).
@@ -399,21 +462,68 @@ pub async fn claim_prekey(
     .await
     .map_err(AuthError::Database)?;
 
+    // Once the pool is empty, hand out the reusable fallback key instead
+    let fallback = if prekey.is_none() {
+        prekeys::claim_fallback_key(&state.db, req.device_id)
+            .await
+            .map_err(AuthError::Database)?
+    } else {
+        None
+    };
+
     tracing::info!(
         claimer_id = %claimer_id,
         device_id = %req.device_id,
         prekey_claimed = prekey.is_some(),
+        fallback_key_claimed = fallback.is_some(),
         "Prekey claim attempt"
     );
 
+    // Ask the device to replenish when the pool drops below the threshold,
+    // or when its fallback key is first handed out
+    let notify = match &fallback {
+        Some(fallback) => fallback.first_use,
+        None => prekey.is_some(),
+    };
+    if notify {
+        notify_low_prekeys(&state, target_user_id, req.device_id, fallback.is_some()).await;
+    }
+
     Ok(Json(ClaimPrekeyResponse {
         device_id: req.device_id,
         identity_key_ed25519: device.identity_key_ed25519,
         identity_key_curve25519: device.identity_key_curve25519,
         one_time_prekey: prekey,
+        fallback_key: fallback.map(|f| f.key),
     }))
 }
 
+/// Tell a device's owner that it should upload keys.
+///
+/// After a one-time prekey claim this only fires as the pool crosses
+/// [`LOW_PREKEY_THRESHOLD`]; the `Ready` payload covers devices that missed it.
+async fn notify_low_prekeys(
+    state: &AppState,
+    owner_id: Uuid,
+    device_id: Uuid,
+    fallback_claimed: bool,
+) {
+    let status = match prekeys::device_prekey_status(&state.db, device_id).await {
+        Ok(Some(status)) => status,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::warn!(device_id = %device_id, error = %e, "Failed to check prekey supply");
+            return;
+        }
+    };
+    if !fallback_claimed && status.one_time_prekeys != LOW_PREKEY_THRESHOLD - 1 {
+        return;
+    }
+    if let Err(e) = broadcast_to_user(&state.redis, owner_id, &status.into()).await {
+        tracing::warn!(device_id = %device_id, error = %e, "Failed to send low prekey notice");
+    }
+}
+
 /// Internal struct for fetching device identity keys.
 #[derive(Debug, FromRow)]
 struct DeviceIdentityKeys {
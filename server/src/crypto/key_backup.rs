@@ -0,0 +1,801 @@
+//! Megolm Key Backup
+//!
+//! Versioned, per-session backup of inbound Megolm sessions. A backup version
+//! carries the client's `auth_data` for deriving the backup key from the
+//! recovery key; each session is then uploaded as its own encrypted entry, so
+//! clients only send what changed. The server never sees the backup key and
+//! only compares the unencrypted session metadata when deciding which copy of
+//! a session to keep.
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgExecutor, PgPool};
+use uuid::Uuid;
+
+use crate::api::AppState;
+use crate::auth::{AuthError, AuthUser};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Maximum length of a backup algorithm identifier.
+const MAX_ALGORITHM_LEN: usize = 64;
+
+/// Maximum serialized size of a version's `auth_data`.
+const MAX_AUTH_DATA_LEN: usize = 4_096;
+
+/// Maximum length of a room or session ID.
+const MAX_ID_LEN: usize = 255;
+
+/// Maximum length of an entry's nonce (base64).
+const MAX_NONCE_LEN: usize = 64;
+
+/// Maximum length of an entry's ciphertext (base64).
+pub const MAX_SESSION_CIPHERTEXT_LEN: usize = 16_384;
+
+/// Maximum number of sessions stored per backup version.
+pub const MAX_BACKUP_SESSIONS: i64 = 100_000;
+
+/// Default number of sessions per page when listing a backup.
+const DEFAULT_SESSIONS_PAGE_SIZE: i64 = 500;
+
+/// Maximum number of sessions per page (about 16 MiB of ciphertext).
+const MAX_SESSIONS_PAGE_SIZE: i64 = 1_000;
+
+// ============================================================================
+// Request/Response Types
+// ============================================================================
+
+/// Request to start a new backup version, replacing the current one.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CreateBackupVersionRequest {
+    /// Backup algorithm, e.g. `kaiku.megolm_backup.v1`.
+    pub algorithm: String,
+    /// Public parameters for deriving the backup key (opaque to the server).
+    #[schema(value_type = Object)]
+    pub auth_data: serde_json::Value,
+}
+
+/// Response after creating a backup version.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CreateBackupVersionResponse {
+    /// The new version.
+    pub version: i64,
+}
+
+/// The current backup version.
+#[derive(Debug, Serialize, FromRow, utoipa::ToSchema)]
+pub struct BackupVersionInfo {
+    /// Version number.
+    pub version: i64,
+    /// Backup algorithm.
+    pub algorithm: String,
+    /// Public parameters for deriving the backup key.
+    #[schema(value_type = Object)]
+    pub auth_data: serde_json::Value,
+    /// Number of sessions in the backup.
+    pub count: i64,
+    /// Changes whenever a session is added or replaced.
+    pub etag: String,
+}
+
+/// One encrypted Megolm session in the backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct KeyBackupSession {
+    /// First message index the session can decrypt.
+    pub first_message_index: i64,
+    /// How many times the session was forwarded before reaching this user.
+    #[serde(default)]
+    pub forwarded_count: i32,
+    /// Whether the session came from a verified device.
+    #[serde(default)]
+    pub is_verified: bool,
+    /// AES-GCM nonce (base64).
+    pub nonce: String,
+    /// Encrypted exported session (base64).
+    pub ciphertext: String,
+}
+
+/// A backed-up session together with its room and session ID.
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct KeyBackupSessionEntry {
+    /// Room (channel) the session belongs to.
+    pub room_id: String,
+    /// Megolm session ID.
+    pub session_id: String,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub session: KeyBackupSession,
+}
+
+/// Query parameters for listing the sessions of a backup version.
+///
+/// Sessions are ordered by room and session ID; pass the last entry of a page
+/// as `after_room_id` and `after_session_id` to get the next one.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct ListBackupSessionsQuery {
+    /// Room ID of the last session on the previous page.
+    pub after_room_id: Option<String>,
+    /// Session ID of the last session on the previous page.
+    pub after_session_id: Option<String>,
+    #[serde(default = "default_sessions_page_size")]
+    pub limit: i64,
+}
+
+const fn default_sessions_page_size() -> i64 {
+    DEFAULT_SESSIONS_PAGE_SIZE
+}
+
+/// A page of sessions of a backup version.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BackupSessionsResponse {
+    /// Etag of the backup when the page was read.
+    pub etag: String,
+    /// The backed-up sessions.
+    pub sessions: Vec<KeyBackupSessionEntry>,
+    /// Whether more sessions follow the last one on this page.
+    pub has_more: bool,
+}
+
+/// Backup state after storing a session.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct BackupUpdateResponse {
+    /// Number of sessions in the backup.
+    pub count: i64,
+    /// Changes whenever a session is added or replaced.
+    pub etag: String,
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+fn validate_version_request(req: &CreateBackupVersionRequest) -> Result<(), AuthError> {
+    if req.algorithm.is_empty() || req.algorithm.len() > MAX_ALGORITHM_LEN {
+        return Err(AuthError::Validation(format!(
+            "Algorithm must be 1-{MAX_ALGORITHM_LEN} characters"
+        )));
+    }
+    if !req.auth_data.is_object() || req.auth_data.to_string().len() > MAX_AUTH_DATA_LEN {
+        return Err(AuthError::Validation(format!(
+            "Auth data must be a JSON object of at most {MAX_AUTH_DATA_LEN} bytes"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_session(
+    room_id: &str,
+    session_id: &str,
+    session: &KeyBackupSession,
+) -> Result<(), AuthError> {
+    if room_id.is_empty()
+        || room_id.len() > MAX_ID_LEN
+        || session_id.is_empty()
+        || session_id.len() > MAX_ID_LEN
+    {
+        return Err(AuthError::Validation(format!(
+            "Room and session IDs must be 1-{MAX_ID_LEN} characters"
+        )));
+    }
+    if session.first_message_index < 0 || session.forwarded_count < 0 {
+        return Err(AuthError::Validation(
+            "Message index and forward count must not be negative".to_string(),
+        ));
+    }
+    if session.nonce.is_empty() || session.nonce.len() > MAX_NONCE_LEN {
+        return Err(AuthError::Validation(format!(
+            "Nonce must be 1-{MAX_NONCE_LEN} characters"
+        )));
+    }
+    if session.ciphertext.is_empty() || session.ciphertext.len() > MAX_SESSION_CIPHERTEXT_LEN {
+        return Err(AuthError::Validation(format!(
+            "Ciphertext must be between 1 and {MAX_SESSION_CIPHERTEXT_LEN} bytes"
+        )));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Replace the user's backup version with a new, empty one.
+///
+/// Sessions of the previous version are deleted with it.
+pub async fn create_version(
+    pool: &PgPool,
+    user_id: Uuid,
+    algorithm: &str,
+    auth_data: &serde_json::Value,
+) -> sqlx::Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM megolm_backup_versions WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    let version = sqlx::query_scalar(
+        r"
+        INSERT INTO megolm_backup_versions (user_id, algorithm, auth_data)
+        VALUES ($1, $2, $3)
+        RETURNING version
+        ",
+    )
+    .bind(user_id)
+    .bind(algorithm)
+    .bind(auth_data)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(version)
+}
+
+/// The user's current backup version, with its session count.
+pub async fn current_version(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> sqlx::Result<Option<BackupVersionInfo>> {
+    sqlx::query_as(
+        r"
+        SELECT v.version, v.algorithm, v.auth_data, v.etag::TEXT AS etag,
+               (
+                   SELECT COUNT(*) FROM megolm_backup_sessions s
+                   WHERE s.user_id = v.user_id AND s.version = v.version
+               ) AS count
+        FROM megolm_backup_versions v
+        WHERE v.user_id = $1
+        ",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Store a session unless the backup already holds a better copy of it.
+///
+/// A copy from a verified device wins, then the one reaching further back
+/// (lower first message index), then the one forwarded fewer times. Returns
+/// `None` if `version` is not the user's current version.
+pub async fn store_session(
+    pool: &PgPool,
+    user_id: Uuid,
+    version: i64,
+    room_id: &str,
+    session_id: &str,
+    session: &KeyBackupSession,
+) -> Result<Option<BackupUpdateResponse>, AuthError> {
+    let mut tx = pool.begin().await?;
+
+    // Lock the version so concurrent uploads serialize on the etag
+    let etag: Option<i64> = sqlx::query_scalar(
+        "SELECT etag FROM megolm_backup_versions WHERE user_id = $1 AND version = $2 FOR UPDATE",
+    )
+    .bind(user_id)
+    .bind(version)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(mut etag) = etag else {
+        return Ok(None);
+    };
+
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM megolm_backup_sessions WHERE user_id = $1 AND version = $2",
+    )
+    .bind(user_id)
+    .bind(version)
+    .fetch_one(&mut *tx)
+    .await?;
+    let exists = get_session(&mut *tx, user_id, version, room_id, session_id)
+        .await?
+        .is_some();
+    if !exists && count >= MAX_BACKUP_SESSIONS {
+        return Err(AuthError::Validation(format!(
+            "Backup cannot hold more than {MAX_BACKUP_SESSIONS} sessions"
+        )));
+    }
+
+    let changed = sqlx::query(
+        r"
+        INSERT INTO megolm_backup_sessions
+            (user_id, version, room_id, session_id, first_message_index,
+             forwarded_count, is_verified, nonce, ciphertext)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        ON CONFLICT (user_id, version, room_id, session_id) DO UPDATE SET
+            first_message_index = EXCLUDED.first_message_index,
+            forwarded_count = EXCLUDED.forwarded_count,
+            is_verified = EXCLUDED.is_verified,
+            nonce = EXCLUDED.nonce,
+            ciphertext = EXCLUDED.ciphertext,
+            updated_at = NOW()
+        WHERE (EXCLUDED.is_verified AND NOT megolm_backup_sessions.is_verified)
+           OR (
+               EXCLUDED.is_verified = megolm_backup_sessions.is_verified
+               AND (EXCLUDED.first_message_index, EXCLUDED.forwarded_count)
+                   < (megolm_backup_sessions.first_message_index,
+                      megolm_backup_sessions.forwarded_count)
+           )
+        ",
+    )
+    .bind(user_id)
+    .bind(version)
+    .bind(room_id)
+    .bind(session_id)
+    .bind(session.first_message_index)
+    .bind(session.forwarded_count)
+    .bind(session.is_verified)
+    .bind(&session.nonce)
+    .bind(&session.ciphertext)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected()
+        > 0;
+
+    if changed {
+        etag += 1;
+        sqlx::query(
+            "UPDATE megolm_backup_versions SET etag = $3 WHERE user_id = $1 AND version = $2",
+        )
+        .bind(user_id)
+        .bind(version)
+        .bind(etag)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(Some(BackupUpdateResponse {
+        count: count + i64::from(!exists),
+        etag: etag.to_string(),
+    }))
+}
+
+/// One backed-up session.
+pub async fn get_session(
+    executor: impl PgExecutor<'_>,
+    user_id: Uuid,
+    version: i64,
+    room_id: &str,
+    session_id: &str,
+) -> sqlx::Result<Option<KeyBackupSession>> {
+    sqlx::query_as(
+        r"
+        SELECT first_message_index, forwarded_count, is_verified, nonce, ciphertext
+        FROM megolm_backup_sessions
+        WHERE user_id = $1 AND version = $2 AND room_id = $3 AND session_id = $4
+        ",
+    )
+    .bind(user_id)
+    .bind(version)
+    .bind(room_id)
+    .bind(session_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Up to `limit` sessions of a backup version, ordered by room and session
+/// ID and starting after the `(room_id, session_id)` cursor.
+pub async fn list_sessions(
+    pool: &PgPool,
+    user_id: Uuid,
+    version: i64,
+    after: Option<(&str, &str)>,
+    limit: i64,
+) -> sqlx::Result<Vec<KeyBackupSessionEntry>> {
+    let (after_room_id, after_session_id) = after.unzip();
+    sqlx::query_as(
+        r"
+        SELECT room_id, session_id, first_message_index, forwarded_count,
+               is_verified, nonce, ciphertext
+        FROM megolm_backup_sessions
+        WHERE user_id = $1 AND version = $2
+          AND ($3::text IS NULL OR (room_id, session_id) > ($3, $4))
+        ORDER BY room_id, session_id
+        LIMIT $5
+        ",
+    )
+    .bind(user_id)
+    .bind(version)
+    .bind(after_room_id)
+    .bind(after_session_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+async fn version_etag(pool: &PgPool, user_id: Uuid, version: i64) -> Result<i64, AuthError> {
+    sqlx::query_scalar(
+        "SELECT etag FROM megolm_backup_versions WHERE user_id = $1 AND version = $2",
+    )
+    .bind(user_id)
+    .bind(version)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AuthError::NotFound("Backup version not found".into()))
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Start a new backup version.
+///
+/// Replaces the current version, deleting its sessions; clients create a new
+/// version when the recovery key changes and then re-upload their sessions.
+///
+/// POST /api/keys/backup/version
+#[utoipa::path(
+    post,
+    path = "/api/keys/backup/version",
+    tag = "crypto",
+    request_body = CreateBackupVersionRequest,
+    responses(
+        (status = 201, description = "Backup version created", body = CreateBackupVersionResponse),
+        (status = 400, description = "Invalid algorithm or auth data"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, req), fields(user_id = %auth_user.id))]
+pub async fn create_backup_version(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<CreateBackupVersionRequest>,
+) -> Result<(StatusCode, Json<CreateBackupVersionResponse>), AuthError> {
+    validate_version_request(&req)?;
+
+    let version = create_version(&state.db, auth_user.id, &req.algorithm, &req.auth_data).await?;
+
+    tracing::info!(version, algorithm = %req.algorithm, "Key backup version created");
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateBackupVersionResponse { version }),
+    ))
+}
+
+/// Get the current backup version.
+///
+/// GET /api/keys/backup/version
+#[utoipa::path(
+    get,
+    path = "/api/keys/backup/version",
+    tag = "crypto",
+    responses(
+        (status = 200, description = "Current backup version", body = BackupVersionInfo),
+        (status = 404, description = "No backup version"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id))]
+pub async fn get_backup_version(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<BackupVersionInfo>, AuthError> {
+    current_version(&state.db, auth_user.id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AuthError::NotFound("No backup version".into()))
+}
+
+/// Delete a backup version and all of its sessions.
+///
+/// DELETE /api/keys/backup/:version
+#[utoipa::path(
+    delete,
+    path = "/api/keys/backup/{version}",
+    tag = "crypto",
+    params(("version" = i64, Path, description = "Backup version")),
+    responses(
+        (status = 204, description = "Backup version deleted"),
+        (status = 404, description = "Backup version not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id))]
+pub async fn delete_backup_version(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(version): Path<i64>,
+) -> Result<StatusCode, AuthError> {
+    let result =
+        sqlx::query("DELETE FROM megolm_backup_versions WHERE user_id = $1 AND version = $2")
+            .bind(auth_user.id)
+            .bind(version)
+            .execute(&state.db)
+            .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AuthError::NotFound("Backup version not found".into()));
+    }
+
+    tracing::info!(version, "Key backup version deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Back up one Megolm session.
+///
+/// Keeps the stored copy if it is at least as good as the uploaded one (see
+/// [`store_session`]); the etag only changes when the entry does.
+///
+/// `PUT /api/keys/backup/:version/rooms/:room_id/sessions/:session_id`
+#[utoipa::path(
+    put,
+    path = "/api/keys/backup/{version}/rooms/{room_id}/sessions/{session_id}",
+    tag = "crypto",
+    params(
+        ("version" = i64, Path, description = "Backup version"),
+        ("room_id" = String, Path, description = "Room the session belongs to"),
+        ("session_id" = String, Path, description = "Megolm session ID"),
+    ),
+    request_body = KeyBackupSession,
+    responses(
+        (status = 200, description = "Session stored", body = BackupUpdateResponse),
+        (status = 400, description = "Invalid entry or backup full"),
+        (status = 404, description = "Backup version not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, session), fields(user_id = %auth_user.id))]
+pub async fn put_backup_session(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((version, room_id, session_id)): Path<(i64, String, String)>,
+    Json(session): Json<KeyBackupSession>,
+) -> Result<Json<BackupUpdateResponse>, AuthError> {
+    validate_session(&room_id, &session_id, &session)?;
+
+    store_session(
+        &state.db,
+        auth_user.id,
+        version,
+        &room_id,
+        &session_id,
+        &session,
+    )
+    .await?
+    .map(Json)
+    .ok_or_else(|| AuthError::NotFound("Backup version not found".into()))
+}
+
+/// Get one backed-up Megolm session.
+///
+/// `GET /api/keys/backup/:version/rooms/:room_id/sessions/:session_id`
+#[utoipa::path(
+    get,
+    path = "/api/keys/backup/{version}/rooms/{room_id}/sessions/{session_id}",
+    tag = "crypto",
+    params(
+        ("version" = i64, Path, description = "Backup version"),
+        ("room_id" = String, Path, description = "Room the session belongs to"),
+        ("session_id" = String, Path, description = "Megolm session ID"),
+    ),
+    responses(
+        (status = 200, description = "Backed-up session", body = KeyBackupSession),
+        (status = 404, description = "Session not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id))]
+pub async fn get_backup_session(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((version, room_id, session_id)): Path<(i64, String, String)>,
+) -> Result<Json<KeyBackupSession>, AuthError> {
+    get_session(&state.db, auth_user.id, version, &room_id, &session_id)
+        .await?
+        .map(Json)
+        .ok_or_else(|| AuthError::NotFound("Session not found".into()))
+}
+
+/// Page through the sessions of a backup version, for restoring on a new
+/// device.
+///
+/// GET /api/keys/backup/:version/sessions
+#[utoipa::path(
+    get,
+    path = "/api/keys/backup/{version}/sessions",
+    tag = "crypto",
+    params(("version" = i64, Path, description = "Backup version"), ListBackupSessionsQuery),
+    responses(
+        (status = 200, description = "Backed-up sessions", body = BackupSessionsResponse),
+        (status = 404, description = "Backup version not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id))]
+pub async fn get_backup_sessions(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(version): Path<i64>,
+    Query(query): Query<ListBackupSessionsQuery>,
+) -> Result<Json<BackupSessionsResponse>, AuthError> {
+    let after = match (&query.after_room_id, &query.after_session_id) {
+        (Some(room_id), Some(session_id)) => Some((room_id.as_str(), session_id.as_str())),
+        (None, None) => None,
+        _ => {
+            return Err(AuthError::Validation(
+                "after_room_id and after_session_id must be given together".to_string(),
+            ))
+        }
+    };
+    let limit = query.limit.clamp(1, MAX_SESSIONS_PAGE_SIZE);
+
+    let etag = version_etag(&state.db, auth_user.id, version).await?;
+    let mut sessions = list_sessions(&state.db, auth_user.id, version, after, limit + 1).await?;
+    let has_more = sessions.len() as i64 > limit;
+    if has_more {
+        sessions.pop();
+    }
+
+    Ok(Json(BackupSessionsResponse {
+        etag: etag.to_string(),
+        sessions,
+        has_more,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn session(first_message_index: i64, is_verified: bool, ciphertext: &str) -> KeyBackupSession {
+        KeyBackupSession {
+            first_message_index,
+            forwarded_count: 0,
+            is_verified,
+            nonce: "nonce".to_string(),
+            ciphertext: ciphertext.to_string(),
+        }
+    }
+
+    #[sqlx::test]
+    async fn better_copies_replace_backed_up_sessions(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let auth_data = serde_json::json!({ "salt": "s", "key_check": "k" });
+        let version = create_version(&pool, user.id, "test", &auth_data)
+            .await
+            .expect("create version");
+
+        let stored = store_session(
+            &pool,
+            user.id,
+            version,
+            "room",
+            "s1",
+            &session(5, false, "a"),
+        )
+        .await
+        .expect("store")
+        .expect("version");
+        assert_eq!(stored.count, 1);
+        assert_eq!(stored.etag, "1");
+
+        // A copy reaching less far back is ignored, a verified one wins
+        let ignored = store_session(
+            &pool,
+            user.id,
+            version,
+            "room",
+            "s1",
+            &session(9, false, "b"),
+        )
+        .await
+        .expect("store")
+        .expect("version");
+        assert_eq!(ignored.etag, "1");
+        let replaced = store_session(
+            &pool,
+            user.id,
+            version,
+            "room",
+            "s1",
+            &session(9, true, "c"),
+        )
+        .await
+        .expect("store")
+        .expect("version");
+        assert_eq!(replaced.count, 1);
+        assert_eq!(replaced.etag, "2");
+        assert_eq!(
+            get_session(&pool, user.id, version, "room", "s1")
+                .await
+                .expect("get")
+                .expect("session")
+                .ciphertext,
+            "c"
+        );
+
+        // Unknown versions are rejected
+        assert!(store_session(
+            &pool,
+            user.id,
+            version + 1,
+            "room",
+            "s2",
+            &session(0, false, "d")
+        )
+        .await
+        .expect("store")
+        .is_none());
+    }
+
+    #[sqlx::test]
+    async fn new_version_drops_previous_sessions(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let auth_data = serde_json::json!({});
+        let first = create_version(&pool, user.id, "test", &auth_data)
+            .await
+            .expect("create version");
+        store_session(&pool, user.id, first, "room", "s1", &session(0, false, "a"))
+            .await
+            .expect("store");
+
+        let second = create_version(&pool, user.id, "test", &auth_data)
+            .await
+            .expect("create version");
+        assert!(second > first);
+
+        let info = current_version(&pool, user.id)
+            .await
+            .expect("current")
+            .expect("version");
+        assert_eq!(info.version, second);
+        assert_eq!(info.count, 0);
+        assert_eq!(info.etag, "0");
+        assert!(list_sessions(&pool, user.id, first, None, 10)
+            .await
+            .expect("list")
+            .is_empty());
+    }
+
+    #[sqlx::test]
+    async fn sessions_are_listed_in_pages(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let version = create_version(&pool, user.id, "test", &serde_json::json!({}))
+            .await
+            .expect("create version");
+        for (room_id, session_id) in [("b", "s1"), ("a", "s2"), ("a", "s1")] {
+            store_session(
+                &pool,
+                user.id,
+                version,
+                room_id,
+                session_id,
+                &session(0, false, "x"),
+            )
+            .await
+            .expect("store");
+        }
+
+        let ids = |page: Vec<KeyBackupSessionEntry>| {
+            page.into_iter()
+                .map(|e| (e.room_id, e.session_id))
+                .collect::<Vec<_>>()
+        };
+        let first = list_sessions(&pool, user.id, version, None, 2)
+            .await
+            .expect("list");
+        assert_eq!(
+            ids(first),
+            vec![
+                ("a".to_string(), "s1".to_string()),
+                ("a".to_string(), "s2".to_string())
+            ]
+        );
+        let rest = list_sessions(&pool, user.id, version, Some(("a", "s2")), 2)
+            .await
+            .expect("list");
+        assert_eq!(ids(rest), vec![("b".to_string(), "s1".to_string())]);
+    }
+}
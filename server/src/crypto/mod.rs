@@ -1,11 +1,16 @@
 //! E2EE Key Management
 //!
-//! Handles device identity keys, one-time prekeys, and key backups
+//! Handles device identity keys, one-time and fallback prekeys, cross-signing, and key backups
 //! for end-to-end encrypted messaging using the Olm/Megolm protocol.
 
+pub mod cross_signing;
+pub mod devices;
 pub mod handlers;
+pub mod key_backup;
+pub mod prekeys;
+pub mod to_device;
 
-use axum::routing::{get, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::Router;
 
 use crate::api::AppState;
@@ -17,7 +22,20 @@ use crate::api::AppState;
 /// - GET /backup - Download encrypted key backup
 /// - POST /backup - Upload encrypted key backup
 /// - GET /backup/status - Check backup existence and metadata
+/// - GET /backup/version - Get the current Megolm key backup version
+/// - POST /backup/version - Start a new Megolm key backup version
+/// - DELETE /backup/:version - Delete a Megolm key backup version
+/// - GET /backup/:version/sessions - Get all backed-up Megolm sessions
+/// - GET `/backup/:version/rooms/:room_id/sessions/:session_id` - Get one backed-up session
+/// - PUT `/backup/:version/rooms/:room_id/sessions/:session_id` - Back up one session
 /// - GET /devices - Get current user's devices
+/// - PATCH /devices/:id - Rename a device
+/// - DELETE /devices/:id - Delete a device and log out its session
+/// - PUT /cross-signing - Publish cross-signing keys
+/// - POST /signatures - Upload device and user cross-signing signatures
+/// - PUT /send-to-device - Send encrypted payloads to specific devices
+/// - GET /to-device/:device_id - Fetch messages waiting for a device
+/// - POST /to-device/:device_id/ack - Acknowledge delivered messages
 pub fn router() -> Router<AppState> {
     Router::new()
         .route("/upload", post(handlers::upload_keys))
@@ -26,7 +44,35 @@ pub fn router() -> Router<AppState> {
             get(handlers::get_backup).post(handlers::upload_backup),
         )
         .route("/backup/status", get(handlers::get_backup_status))
+        .route(
+            "/backup/version",
+            get(key_backup::get_backup_version).post(key_backup::create_backup_version),
+        )
+        .route(
+            "/backup/{version}",
+            delete(key_backup::delete_backup_version),
+        )
+        .route(
+            "/backup/{version}/sessions",
+            get(key_backup::get_backup_sessions),
+        )
+        .route(
+            "/backup/{version}/rooms/{room_id}/sessions/{session_id}",
+            get(key_backup::get_backup_session).put(key_backup::put_backup_session),
+        )
         .route("/devices", get(handlers::get_own_devices))
+        .route(
+            "/devices/{device_id}",
+            patch(devices::rename_device).delete(devices::delete_device),
+        )
+        .route(
+            "/cross-signing",
+            put(cross_signing::upload_cross_signing_keys),
+        )
+        .route("/signatures", post(cross_signing::upload_signatures))
+        .route("/send-to-device", put(to_device::send_to_device))
+        .route("/to-device/{device_id}", get(to_device::get_pending))
+        .route("/to-device/{device_id}/ack", post(to_device::ack))
 }
 
 /// Create user keys router for fetching other users' keys.
@@ -0,0 +1,354 @@
+//! Prekey Supply
+//!
+//! Signed fallback keys and low-prekey signals. Each device publishes one
+//! fallback key next to its one-time prekeys; `claim_prekey` hands it out once
+//! the pool is empty, so Olm sessions can still be established with offline
+//! devices. Devices learn that they should replenish prekeys or replace a used
+//! fallback key from the `Ready` payload and [`ServerEvent::PrekeysLow`].
+
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgExecutor, PgPool};
+use uuid::Uuid;
+use vc_crypto::olm::SignedFallbackKey;
+
+use crate::auth::AuthError;
+use crate::ws::ServerEvent;
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// Devices with fewer unclaimed one-time prekeys than this are asked to
+/// upload more.
+pub const LOW_PREKEY_THRESHOLD: i64 = 10;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// A fallback key signed by its device's Ed25519 identity key.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct FallbackKey {
+    /// Key ID (base64).
+    pub key_id: String,
+    /// Curve25519 public key (base64).
+    pub public_key: String,
+    /// Signature over the key by the device (base64).
+    pub signature: String,
+}
+
+impl From<&FallbackKey> for SignedFallbackKey {
+    fn from(key: &FallbackKey) -> Self {
+        Self {
+            key_id: key.key_id.clone(),
+            public_key: key.public_key.clone(),
+            signature: key.signature.clone(),
+        }
+    }
+}
+
+/// Prekey supply of one device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct DevicePrekeyStatus {
+    /// Device ID.
+    pub device_id: Uuid,
+    /// Curve25519 identity key (base64), so clients can recognise themselves.
+    pub identity_key_curve25519: String,
+    /// Unclaimed one-time prekeys left on the server.
+    pub one_time_prekeys: i64,
+    /// The device has no fallback key, or it has been handed out.
+    pub needs_fallback_key: bool,
+}
+
+impl From<DevicePrekeyStatus> for ServerEvent {
+    fn from(status: DevicePrekeyStatus) -> Self {
+        Self::PrekeysLow {
+            device_id: status.device_id,
+            identity_key_curve25519: status.identity_key_curve25519,
+            one_time_prekeys: status.one_time_prekeys,
+            needs_fallback_key: status.needs_fallback_key,
+        }
+    }
+}
+
+/// Fallback key handed out by [`claim_fallback_key`].
+#[derive(Debug, FromRow)]
+pub struct ClaimedFallbackKey {
+    #[sqlx(flatten)]
+    pub key: FallbackKey,
+    /// This claim was the first use of the key.
+    pub first_use: bool,
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Check a fallback key's length and signature against its device's keys.
+pub fn validate_fallback_key(
+    key: &FallbackKey,
+    identity_key_ed25519: &str,
+    identity_key_curve25519: &str,
+) -> Result<(), AuthError> {
+    if key.key_id.is_empty() || key.key_id.len() > 64 {
+        return Err(AuthError::Validation(
+            "Fallback key ID must be 1-64 characters".to_string(),
+        ));
+    }
+    SignedFallbackKey::from(key)
+        .verify(identity_key_ed25519, identity_key_curve25519)
+        .map_err(|_| AuthError::Validation("Invalid fallback key signature".to_string()))
+}
+
+/// Store a device's fallback key, replacing the previous one.
+///
+/// Re-uploading the current key keeps its used state.
+pub async fn store_fallback_key(
+    executor: impl PgExecutor<'_>,
+    device_id: Uuid,
+    key: &FallbackKey,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "
+        INSERT INTO fallback_keys (device_id, key_id, public_key, signature)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (device_id) DO UPDATE SET
+            key_id = EXCLUDED.key_id,
+            public_key = EXCLUDED.public_key,
+            signature = EXCLUDED.signature,
+            used_at = CASE
+                WHEN fallback_keys.public_key = EXCLUDED.public_key THEN fallback_keys.used_at
+            END,
+            created_at = CASE
+                WHEN fallback_keys.public_key = EXCLUDED.public_key THEN fallback_keys.created_at
+                ELSE NOW()
+            END
+        ",
+    )
+    .bind(device_id)
+    .bind(&key.key_id)
+    .bind(&key.public_key)
+    .bind(&key.signature)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Hand out a device's fallback key, marking it used.
+pub async fn claim_fallback_key(
+    pool: &PgPool,
+    device_id: Uuid,
+) -> sqlx::Result<Option<ClaimedFallbackKey>> {
+    sqlx::query_as(
+        "
+        WITH previous AS (
+            SELECT used_at FROM fallback_keys WHERE device_id = $1 FOR UPDATE
+        )
+        UPDATE fallback_keys f
+        SET used_at = COALESCE(f.used_at, NOW())
+        FROM previous
+        WHERE f.device_id = $1
+        RETURNING f.key_id, f.public_key, f.signature, previous.used_at IS NULL AS first_use
+        ",
+    )
+    .bind(device_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Prekey supply of a single device.
+pub async fn device_prekey_status(
+    pool: &PgPool,
+    device_id: Uuid,
+) -> sqlx::Result<Option<DevicePrekeyStatus>> {
+    sqlx::query_as(
+        "
+        SELECT
+            d.id AS device_id,
+            d.identity_key_curve25519,
+            (
+                SELECT COUNT(*) FROM prekeys p
+                WHERE p.device_id = d.id AND p.claimed_at IS NULL
+            ) AS one_time_prekeys,
+            (f.device_id IS NULL OR f.used_at IS NOT NULL) AS needs_fallback_key
+        FROM user_devices d
+        LEFT JOIN fallback_keys f ON f.device_id = d.id
+        WHERE d.id = $1
+        ",
+    )
+    .bind(device_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// A user's devices that should upload new keys.
+pub async fn low_prekey_devices(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> sqlx::Result<Vec<DevicePrekeyStatus>> {
+    sqlx::query_as(
+        "
+        SELECT * FROM (
+            SELECT
+                d.id AS device_id,
+                d.identity_key_curve25519,
+                (
+                    SELECT COUNT(*) FROM prekeys p
+                    WHERE p.device_id = d.id AND p.claimed_at IS NULL
+                ) AS one_time_prekeys,
+                (f.device_id IS NULL OR f.used_at IS NOT NULL) AS needs_fallback_key
+            FROM user_devices d
+            LEFT JOIN fallback_keys f ON f.device_id = d.id
+            WHERE d.user_id = $1
+        ) s
+        WHERE s.one_time_prekeys < $2 OR s.needs_fallback_key
+        ORDER BY s.device_id
+        ",
+    )
+    .bind(user_id)
+    .bind(LOW_PREKEY_THRESHOLD)
+    .fetch_all(pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use vc_crypto::olm::OlmAccount;
+
+    use super::*;
+    use crate::db::{self, fixtures};
+
+    async fn register_account(pool: &PgPool, user_id: Uuid, account: &OlmAccount) -> Uuid {
+        let identity = account.identity_keys();
+        fixtures::create_device_with_keys(pool, user_id, &identity.ed25519, &identity.curve25519)
+            .await
+    }
+
+    fn fallback_key(account: &mut OlmAccount) -> FallbackKey {
+        account.generate_fallback_key();
+        let key = account.fallback_key().expect("fallback key");
+        account.mark_keys_as_published();
+        FallbackKey {
+            key_id: key.key_id,
+            public_key: key.public_key,
+            signature: key.signature,
+        }
+    }
+
+    #[test]
+    fn test_fallback_key_validation() {
+        let mut account = OlmAccount::new();
+        let identity = account.identity_keys();
+        let key = fallback_key(&mut account);
+        assert!(validate_fallback_key(&key, &identity.ed25519, &identity.curve25519).is_ok());
+
+        let other = OlmAccount::new().identity_keys();
+        assert!(validate_fallback_key(&key, &other.ed25519, &other.curve25519).is_err());
+
+        let mut long_id = key;
+        long_id.key_id = "a".repeat(65);
+        assert!(validate_fallback_key(&long_id, &identity.ed25519, &identity.curve25519).is_err());
+    }
+
+    #[sqlx::test]
+    async fn fallback_key_is_reused_until_replaced(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let mut account = OlmAccount::new();
+        let device_id = register_account(&pool, user.id, &account).await;
+
+        assert!(claim_fallback_key(&pool, device_id)
+            .await
+            .expect("claim")
+            .is_none());
+        let status = device_prekey_status(&pool, device_id)
+            .await
+            .expect("status")
+            .expect("device");
+        assert_eq!(status.one_time_prekeys, 0);
+        assert!(status.needs_fallback_key);
+
+        let key = fallback_key(&mut account);
+        store_fallback_key(&pool, device_id, &key)
+            .await
+            .expect("store");
+        assert!(
+            !device_prekey_status(&pool, device_id)
+                .await
+                .expect("status")
+                .expect("device")
+                .needs_fallback_key
+        );
+
+        let first = claim_fallback_key(&pool, device_id)
+            .await
+            .expect("claim")
+            .expect("fallback key");
+        assert!(first.first_use);
+        assert_eq!(first.key.public_key, key.public_key);
+        let second = claim_fallback_key(&pool, device_id)
+            .await
+            .expect("claim")
+            .expect("fallback key");
+        assert!(!second.first_use);
+
+        // Re-uploading the same key keeps it marked used; a new key clears it
+        store_fallback_key(&pool, device_id, &key)
+            .await
+            .expect("store");
+        assert!(
+            device_prekey_status(&pool, device_id)
+                .await
+                .expect("status")
+                .expect("device")
+                .needs_fallback_key
+        );
+        store_fallback_key(&pool, device_id, &fallback_key(&mut account))
+            .await
+            .expect("store");
+        assert!(
+            !device_prekey_status(&pool, device_id)
+                .await
+                .expect("status")
+                .expect("device")
+                .needs_fallback_key
+        );
+    }
+
+    #[sqlx::test]
+    async fn low_prekey_devices_lists_only_devices_needing_keys(pool: PgPool) {
+        let user = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create user");
+        let mut stocked = OlmAccount::new();
+        let stocked_id = register_account(&pool, user.id, &stocked).await;
+        let drained = OlmAccount::new();
+        let drained_id = register_account(&pool, user.id, &drained).await;
+
+        store_fallback_key(&pool, stocked_id, &fallback_key(&mut stocked))
+            .await
+            .expect("store");
+        for i in 0..LOW_PREKEY_THRESHOLD {
+            sqlx::query(
+                "INSERT INTO prekeys (device_id, key_id, public_key) VALUES ($1, $2, 'pk')",
+            )
+            .bind(stocked_id)
+            .bind(format!("k{i}"))
+            .execute(&pool)
+            .await
+            .expect("insert prekey");
+        }
+
+        let low = low_prekey_devices(&pool, user.id).await.expect("low");
+        assert_eq!(
+            low,
+            vec![DevicePrekeyStatus {
+                device_id: drained_id,
+                identity_key_curve25519: drained.identity_keys().curve25519,
+                one_time_prekeys: 0,
+                needs_fallback_key: true,
+            }]
+        );
+    }
+}
@@ -0,0 +1,770 @@
+//! To-Device Messaging
+//!
+//! Server-side inbox for Olm-encrypted payloads addressed to a single device,
+//! the transport for Megolm room keys and verification messages. Messages are
+//! queued per recipient device, pushed over the WebSocket as
+//! [`ServerEvent::ToDeviceMessage`], and kept until the device acknowledges
+//! them or they expire, so offline devices catch up on reconnect.
+//!
+//! Users can only message the devices of users they share a guild or DM with,
+//! unless either has blocked the other, and each sender gets its own quota in
+//! a device's inbox.
+
+use std::collections::HashMap;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+use vc_crypto::room_key::ROOM_KEY_REQUEST_EVENT_TYPE;
+
+use crate::api::AppState;
+use crate::auth::{AuthError, AuthUser};
+use crate::ws::{broadcast_to_user, ServerEvent};
+
+// ============================================================================
+// Constants
+// ============================================================================
+
+/// How long an unacknowledged message waits for its device.
+pub const TO_DEVICE_TTL_DAYS: i32 = 7;
+
+/// Maximum ciphertext length per message (64 KiB of base64).
+pub const MAX_TO_DEVICE_CIPHERTEXT_LEN: usize = 65_536;
+
+/// Maximum number of recipient devices in one send request.
+pub const MAX_TO_DEVICE_RECIPIENTS: usize = 100;
+
+/// Maximum number of pending messages one user may have queued for a single
+/// device. Further messages from that user are skipped until the device
+/// drains its inbox; other senders are unaffected.
+pub const MAX_PENDING_PER_SENDER: i64 = 500;
+
+/// Maximum number of unacknowledged room key requests per sending device,
+/// counted across all recipients.
+///
+/// The server can't see inside the ciphertext and only knows a request by
+/// its client-chosen `event_type`, so this throttles honest clients that
+/// retry too eagerly. A device that labels its requests as something else
+/// is still held to [`MAX_PENDING_PER_SENDER`] per recipient device.
+pub const MAX_PENDING_KEY_REQUESTS_PER_SENDER: i64 = 250;
+
+/// Maximum number of messages returned per fetch, or acknowledged per call.
+pub const MAX_TO_DEVICE_BATCH: usize = 100;
+
+/// Maximum length of an event type (e.g. `m.room_key`).
+const MAX_EVENT_TYPE_LEN: usize = 64;
+
+// ============================================================================
+// Request/Response Types
+// ============================================================================
+
+/// Request to send encrypted payloads to specific devices.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct SendToDeviceRequest {
+    /// The sending device (must belong to the caller).
+    pub sender_device_id: Uuid,
+    /// Payload type, e.g. `m.room_key` or `m.key.verification.start`.
+    pub event_type: String,
+    /// Ciphertexts keyed by recipient user ID, then recipient device ID.
+    pub messages: HashMap<Uuid, HashMap<Uuid, String>>,
+}
+
+/// Response after queueing to-device messages.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SendToDeviceResponse {
+    /// Number of messages queued for delivery.
+    pub queued: usize,
+    /// Number of messages skipped (unknown device, recipient out of reach, or
+    /// full inbox).
+    pub skipped: usize,
+}
+
+/// Request to acknowledge delivered messages.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct AckToDeviceRequest {
+    /// Messages the device has processed.
+    pub message_ids: Vec<Uuid>,
+}
+
+/// A queued message addressed to one device.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct ToDeviceMessage {
+    /// Message ID, used for acknowledgement.
+    pub id: Uuid,
+    /// User who sent the message.
+    pub sender_user_id: Uuid,
+    /// Device that sent the message.
+    pub sender_device_id: Uuid,
+    /// Device the message is addressed to.
+    pub recipient_device_id: Uuid,
+    /// Payload type.
+    pub event_type: String,
+    /// Olm-encrypted payload (opaque to the server).
+    pub ciphertext: String,
+    /// When the message was queued.
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<ToDeviceMessage> for ServerEvent {
+    fn from(message: ToDeviceMessage) -> Self {
+        Self::ToDeviceMessage {
+            id: message.id,
+            sender_user_id: message.sender_user_id,
+            sender_device_id: message.sender_device_id,
+            recipient_device_id: message.recipient_device_id,
+            event_type: message.event_type,
+            ciphertext: message.ciphertext,
+            created_at: message.created_at,
+        }
+    }
+}
+
+/// Sender of a to-device message.
+#[derive(Debug, Clone, Copy)]
+pub struct ToDeviceSender {
+    pub user_id: Uuid,
+    pub device_id: Uuid,
+}
+
+// ============================================================================
+// Validation
+// ============================================================================
+
+/// Event types are short dotted identifiers such as `m.room_key`.
+fn validate_event_type(event_type: &str) -> Result<(), AuthError> {
+    if event_type.is_empty()
+        || event_type.len() > MAX_EVENT_TYPE_LEN
+        || !event_type
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        return Err(AuthError::Validation(format!(
+            "Event type must be 1-{MAX_EVENT_TYPE_LEN} characters of letters, digits, '.', '_' or '-'"
+        )));
+    }
+    Ok(())
+}
+
+fn validate_request(req: &SendToDeviceRequest) -> Result<(), AuthError> {
+    validate_event_type(&req.event_type)?;
+
+    let recipients: usize = req.messages.values().map(HashMap::len).sum();
+    if recipients == 0 {
+        return Err(AuthError::Validation(
+            "At least one recipient device is required".to_string(),
+        ));
+    }
+    if recipients > MAX_TO_DEVICE_RECIPIENTS {
+        return Err(AuthError::Validation(format!(
+            "Cannot send to more than {MAX_TO_DEVICE_RECIPIENTS} devices at once"
+        )));
+    }
+    if req
+        .messages
+        .values()
+        .flat_map(HashMap::values)
+        .any(|c| c.is_empty() || c.len() > MAX_TO_DEVICE_CIPHERTEXT_LEN)
+    {
+        return Err(AuthError::Validation(format!(
+            "Ciphertext must be between 1 and {MAX_TO_DEVICE_CIPHERTEXT_LEN} bytes"
+        )));
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Whether `sender_id` may message the devices of `recipient_id`: their own,
+/// or those of a user they share a guild or DM with and neither has blocked.
+pub async fn can_reach(pool: &PgPool, sender_id: Uuid, recipient_id: Uuid) -> sqlx::Result<bool> {
+    if sender_id == recipient_id {
+        return Ok(true);
+    }
+    sqlx::query_scalar(
+        r"
+        SELECT NOT EXISTS(
+                   SELECT 1 FROM friendships
+                   WHERE status = 'blocked'
+                     AND ((requester_id = $1 AND addressee_id = $2)
+                       OR (requester_id = $2 AND addressee_id = $1))
+               )
+           AND (
+               EXISTS(
+                   SELECT 1 FROM guild_members a
+                   INNER JOIN guild_members b ON b.guild_id = a.guild_id
+                   WHERE a.user_id = $1 AND b.user_id = $2
+               )
+               OR EXISTS(
+                   SELECT 1 FROM dm_participants a
+                   INNER JOIN dm_participants b ON b.channel_id = a.channel_id
+                   WHERE a.user_id = $1 AND b.user_id = $2
+               )
+           )
+        ",
+    )
+    .bind(sender_id)
+    .bind(recipient_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Queue a message for one device.
+///
+/// Returns `None` if the device does not belong to `recipient_user_id`, the
+/// sender already has [`MAX_PENDING_PER_SENDER`] unexpired messages waiting
+/// for it, or the message is a room key request and the sending device
+/// already has [`MAX_PENDING_KEY_REQUESTS_PER_SENDER`] of them waiting.
+pub async fn enqueue(
+    pool: &PgPool,
+    sender: ToDeviceSender,
+    recipient_user_id: Uuid,
+    recipient_device_id: Uuid,
+    event_type: &str,
+    ciphertext: &str,
+) -> sqlx::Result<Option<ToDeviceMessage>> {
+    let mut tx = pool.begin().await?;
+
+    // Lock both devices so concurrent sends can't all pass the counts below
+    sqlx::query("SELECT id FROM user_devices WHERE id = ANY($1) ORDER BY id FOR NO KEY UPDATE")
+        .bind([sender.device_id, recipient_device_id].as_slice())
+        .execute(&mut *tx)
+        .await?;
+
+    let message = sqlx::query_as(
+        r"
+        INSERT INTO to_device_messages
+            (id, recipient_user_id, recipient_device_id, sender_user_id, sender_device_id,
+             event_type, ciphertext, expires_at)
+        SELECT $1, d.user_id, d.id, $4, $5, $6, $7, NOW() + make_interval(days => $8)
+        FROM user_devices d
+        WHERE d.id = $3 AND d.user_id = $2
+          AND (
+              SELECT COUNT(*) FROM to_device_messages
+              WHERE recipient_device_id = d.id AND sender_user_id = $4 AND expires_at > NOW()
+          ) < $9
+          AND (
+              $6 <> $10 OR (
+                  SELECT COUNT(*) FROM to_device_messages
+                  WHERE sender_device_id = $5 AND event_type = $10 AND expires_at > NOW()
+              ) < $11
+          )
+        RETURNING id, sender_user_id, sender_device_id, recipient_device_id,
+                  event_type, ciphertext, created_at
+        ",
+    )
+    .bind(Uuid::now_v7())
+    .bind(recipient_user_id)
+    .bind(recipient_device_id)
+    .bind(sender.user_id)
+    .bind(sender.device_id)
+    .bind(event_type)
+    .bind(ciphertext)
+    .bind(TO_DEVICE_TTL_DAYS)
+    .bind(MAX_PENDING_PER_SENDER)
+    .bind(ROOM_KEY_REQUEST_EVENT_TYPE)
+    .bind(MAX_PENDING_KEY_REQUESTS_PER_SENDER)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(message)
+}
+
+/// Unexpired messages waiting for one of the user's devices, oldest first.
+pub async fn pending(
+    pool: &PgPool,
+    user_id: Uuid,
+    device_id: Uuid,
+) -> sqlx::Result<Vec<ToDeviceMessage>> {
+    sqlx::query_as(
+        r"
+        SELECT id, sender_user_id, sender_device_id, recipient_device_id,
+               event_type, ciphertext, created_at
+        FROM to_device_messages
+        WHERE recipient_user_id = $1 AND recipient_device_id = $2 AND expires_at > NOW()
+        ORDER BY created_at, id
+        LIMIT $3
+        ",
+    )
+    .bind(user_id)
+    .bind(device_id)
+    .bind(MAX_TO_DEVICE_BATCH as i64)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete acknowledged messages from one of the user's device inboxes.
+///
+/// IDs that don't belong to the device are ignored. Returns the number of
+/// messages removed.
+pub async fn acknowledge(
+    pool: &PgPool,
+    user_id: Uuid,
+    device_id: Uuid,
+    message_ids: &[Uuid],
+) -> sqlx::Result<u64> {
+    let result = sqlx::query(
+        r"
+        DELETE FROM to_device_messages
+        WHERE recipient_user_id = $1 AND recipient_device_id = $2 AND id = ANY($3)
+        ",
+    )
+    .bind(user_id)
+    .bind(device_id)
+    .bind(message_ids)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Clean up expired to-device messages (for background job).
+pub async fn cleanup_expired(pool: &PgPool) -> sqlx::Result<u64> {
+    let result = sqlx::query("DELETE FROM to_device_messages WHERE expires_at < NOW()")
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+async fn require_own_device(
+    pool: &PgPool,
+    user_id: Uuid,
+    device_id: Uuid,
+) -> Result<(), AuthError> {
+    let exists: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM user_devices WHERE id = $1 AND user_id = $2)",
+    )
+    .bind(device_id)
+    .bind(user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(AuthError::Database)?;
+
+    if exists {
+        Ok(())
+    } else {
+        Err(AuthError::NotFound("Device not found".into()))
+    }
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// Send encrypted payloads to specific devices.
+///
+/// Each ciphertext is queued for its device and pushed to the recipient's
+/// connected sessions. Devices that don't belong to the given user, devices of
+/// users the caller shares no guild or DM with (or who blocked the caller),
+/// and devices holding the caller's full quota are skipped rather than
+/// failing the whole request.
+///
+/// PUT /api/keys/send-to-device
+#[utoipa::path(
+    put,
+    path = "/api/keys/send-to-device",
+    tag = "crypto",
+    request_body = SendToDeviceRequest,
+    responses(
+        (status = 200, description = "Messages queued", body = SendToDeviceResponse),
+        (status = 400, description = "Invalid event type, ciphertext or recipient count"),
+        (status = 404, description = "Sender device not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, req), fields(user_id = %auth_user.id))]
+pub async fn send_to_device(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(req): Json<SendToDeviceRequest>,
+) -> Result<Json<SendToDeviceResponse>, AuthError> {
+    validate_request(&req)?;
+    require_own_device(&state.db, auth_user.id, req.sender_device_id).await?;
+
+    let sender = ToDeviceSender {
+        user_id: auth_user.id,
+        device_id: req.sender_device_id,
+    };
+    let mut queued = 0;
+    let mut skipped = 0;
+    for (recipient_user_id, devices) in &req.messages {
+        if !can_reach(&state.db, auth_user.id, *recipient_user_id)
+            .await
+            .map_err(AuthError::Database)?
+        {
+            skipped += devices.len();
+            continue;
+        }
+        for (recipient_device_id, ciphertext) in devices {
+            let Some(message) = enqueue(
+                &state.db,
+                sender,
+                *recipient_user_id,
+                *recipient_device_id,
+                &req.event_type,
+                ciphertext,
+            )
+            .await
+            .map_err(AuthError::Database)?
+            else {
+                skipped += 1;
+                continue;
+            };
+            queued += 1;
+
+            // The row is the source of truth; a missed push is picked up on reconnect
+            if let Err(e) =
+                broadcast_to_user(&state.redis, *recipient_user_id, &message.into()).await
+            {
+                tracing::warn!(
+                    recipient_user_id = %recipient_user_id,
+                    error = %e,
+                    "Failed to push to-device message"
+                );
+            }
+        }
+    }
+
+    tracing::info!(
+        sender_device_id = %req.sender_device_id,
+        event_type = %req.event_type,
+        queued,
+        skipped,
+        "To-device messages queued"
+    );
+
+    Ok(Json(SendToDeviceResponse { queued, skipped }))
+}
+
+/// Fetch messages waiting for one of the caller's devices.
+///
+/// Returns up to [`MAX_TO_DEVICE_BATCH`] messages, oldest first. Clients
+/// call this after connecting and keep fetching until it comes back empty,
+/// acknowledging each batch.
+///
+/// GET /api/keys/to-device/:device_id
+#[utoipa::path(
+    get,
+    path = "/api/keys/to-device/{device_id}",
+    tag = "crypto",
+    params(("device_id" = Uuid, Path, description = "Recipient device ID")),
+    responses(
+        (status = 200, description = "Pending messages", body = Vec<ToDeviceMessage>),
+        (status = 404, description = "Device not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state), fields(user_id = %auth_user.id))]
+pub async fn get_pending(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(device_id): Path<Uuid>,
+) -> Result<Json<Vec<ToDeviceMessage>>, AuthError> {
+    require_own_device(&state.db, auth_user.id, device_id).await?;
+
+    let messages = pending(&state.db, auth_user.id, device_id)
+        .await
+        .map_err(AuthError::Database)?;
+
+    Ok(Json(messages))
+}
+
+/// Acknowledge processed messages, removing them from the device's inbox.
+///
+/// Equivalent to the `to_device_ack` WebSocket event.
+///
+/// POST /api/keys/to-device/:device_id/ack
+#[utoipa::path(
+    post,
+    path = "/api/keys/to-device/{device_id}/ack",
+    tag = "crypto",
+    params(("device_id" = Uuid, Path, description = "Recipient device ID")),
+    request_body = AckToDeviceRequest,
+    responses(
+        (status = 204, description = "Messages acknowledged"),
+        (status = 400, description = "Too many message IDs"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, req), fields(user_id = %auth_user.id))]
+pub async fn ack(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(device_id): Path<Uuid>,
+    Json(req): Json<AckToDeviceRequest>,
+) -> Result<StatusCode, AuthError> {
+    if req.message_ids.len() > MAX_TO_DEVICE_BATCH {
+        return Err(AuthError::Validation(format!(
+            "Cannot acknowledge more than {MAX_TO_DEVICE_BATCH} messages at once"
+        )));
+    }
+
+    acknowledge(&state.db, auth_user.id, device_id, &req.message_ids)
+        .await
+        .map_err(AuthError::Database)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, fixtures};
+
+    fn request(messages: HashMap<Uuid, HashMap<Uuid, String>>) -> SendToDeviceRequest {
+        SendToDeviceRequest {
+            sender_device_id: Uuid::now_v7(),
+            event_type: "m.room_key".to_string(),
+            messages,
+        }
+    }
+
+    #[test]
+    fn test_event_type_validation() {
+        assert!(validate_event_type("m.room_key").is_ok());
+        assert!(validate_event_type("m.key.verification.start").is_ok());
+        assert!(validate_event_type("").is_err());
+        assert!(validate_event_type("m room key").is_err());
+        assert!(validate_event_type(&"a".repeat(MAX_EVENT_TYPE_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn test_request_limits() {
+        assert!(validate_request(&request(HashMap::new())).is_err());
+
+        let one = |ciphertext: String| {
+            HashMap::from([(
+                Uuid::now_v7(),
+                HashMap::from([(Uuid::now_v7(), ciphertext)]),
+            )])
+        };
+        assert!(validate_request(&request(one("AwogX".to_string()))).is_ok());
+        assert!(validate_request(&request(one(String::new()))).is_err());
+        assert!(
+            validate_request(&request(one("a".repeat(MAX_TO_DEVICE_CIPHERTEXT_LEN + 1)))).is_err()
+        );
+
+        let devices = (0..=MAX_TO_DEVICE_RECIPIENTS)
+            .map(|_| (Uuid::now_v7(), "AwogX".to_string()))
+            .collect();
+        assert!(validate_request(&request(HashMap::from([(Uuid::now_v7(), devices)]))).is_err());
+    }
+
+    #[sqlx::test]
+    async fn messages_queue_until_acknowledged(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create bob");
+        let sender = ToDeviceSender {
+            user_id: alice.id,
+            device_id: fixtures::create_device(&pool, alice.id).await,
+        };
+        let bob_laptop = fixtures::create_device(&pool, bob.id).await;
+        let bob_phone = fixtures::create_device(&pool, bob.id).await;
+
+        let queued = enqueue(&pool, sender, bob.id, bob_laptop, "m.room_key", "cipher")
+            .await
+            .unwrap()
+            .expect("queued");
+        assert_eq!(queued.recipient_device_id, bob_laptop);
+        assert_eq!(queued.sender_device_id, sender.device_id);
+
+        // A device must belong to the addressed user
+        assert!(
+            enqueue(&pool, sender, alice.id, bob_phone, "m.room_key", "cipher")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Inboxes are per device
+        assert_eq!(pending(&pool, bob.id, bob_laptop).await.unwrap().len(), 1);
+        assert!(pending(&pool, bob.id, bob_phone).await.unwrap().is_empty());
+        assert!(pending(&pool, alice.id, bob_laptop)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Only the recipient can acknowledge
+        assert_eq!(
+            acknowledge(&pool, alice.id, bob_laptop, &[queued.id])
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            acknowledge(&pool, bob.id, bob_laptop, &[queued.id])
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(pending(&pool, bob.id, bob_laptop).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn room_key_requests_are_capped_per_sender(pool: PgPool) {
+        let user = db::create_user(&pool, "requester", "Requester", None, "hash")
+            .await
+            .expect("create user");
+        let sender = ToDeviceSender {
+            user_id: user.id,
+            device_id: fixtures::create_device(&pool, user.id).await,
+        };
+        let recipient = fixtures::create_device(&pool, user.id).await;
+
+        sqlx::query(
+            r"
+            INSERT INTO to_device_messages
+                (id, recipient_user_id, recipient_device_id, sender_user_id, sender_device_id,
+                 event_type, ciphertext, expires_at)
+            SELECT gen_random_uuid(), $1, $2, $1, $3, $4, 'cipher', NOW() + INTERVAL '1 day'
+            FROM generate_series(1, $5)
+            ",
+        )
+        .bind(user.id)
+        .bind(recipient)
+        .bind(sender.device_id)
+        .bind(ROOM_KEY_REQUEST_EVENT_TYPE)
+        .bind(MAX_PENDING_KEY_REQUESTS_PER_SENDER)
+        .execute(&pool)
+        .await
+        .expect("fill requests");
+
+        assert!(enqueue(
+            &pool,
+            sender,
+            user.id,
+            recipient,
+            ROOM_KEY_REQUEST_EVENT_TYPE,
+            "cipher"
+        )
+        .await
+        .unwrap()
+        .is_none());
+
+        // Other payloads from the same device are unaffected
+        assert!(
+            enqueue(&pool, sender, user.id, recipient, "m.room_key", "cipher")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[sqlx::test]
+    async fn inbox_quota_is_per_sender(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create bob");
+        let flooder = ToDeviceSender {
+            user_id: alice.id,
+            device_id: fixtures::create_device(&pool, alice.id).await,
+        };
+        let bob_phone = fixtures::create_device(&pool, bob.id).await;
+        let bob_laptop = ToDeviceSender {
+            user_id: bob.id,
+            device_id: fixtures::create_device(&pool, bob.id).await,
+        };
+
+        sqlx::query(
+            r"
+            INSERT INTO to_device_messages
+                (id, recipient_user_id, recipient_device_id, sender_user_id, sender_device_id,
+                 event_type, ciphertext, expires_at)
+            SELECT gen_random_uuid(), $1, $2, $3, $4, 'm.dummy', 'cipher', NOW() + INTERVAL '1 day'
+            FROM generate_series(1, $5)
+            ",
+        )
+        .bind(bob.id)
+        .bind(bob_phone)
+        .bind(alice.id)
+        .bind(flooder.device_id)
+        .bind(MAX_PENDING_PER_SENDER)
+        .execute(&pool)
+        .await
+        .expect("fill inbox");
+
+        assert!(
+            enqueue(&pool, flooder, bob.id, bob_phone, "m.room_key", "cipher")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Other senders still get their room keys through
+        assert!(
+            enqueue(&pool, bob_laptop, bob.id, bob_phone, "m.room_key", "cipher")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[sqlx::test]
+    async fn only_related_unblocked_users_are_reachable(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", None, "hash")
+            .await
+            .expect("create bob");
+
+        assert!(can_reach(&pool, alice.id, alice.id).await.unwrap());
+        assert!(!can_reach(&pool, alice.id, bob.id).await.unwrap());
+
+        fixtures::create_dm(&pool, "dm", &[alice.id, bob.id]).await;
+        assert!(can_reach(&pool, alice.id, bob.id).await.unwrap());
+        assert!(can_reach(&pool, bob.id, alice.id).await.unwrap());
+
+        // A block in either direction cuts both ways
+        sqlx::query(
+            "INSERT INTO friendships (requester_id, addressee_id, status) VALUES ($1, $2, 'blocked')",
+        )
+        .bind(bob.id)
+        .bind(alice.id)
+        .execute(&pool)
+        .await
+        .expect("block");
+        assert!(!can_reach(&pool, alice.id, bob.id).await.unwrap());
+        assert!(!can_reach(&pool, bob.id, alice.id).await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn expired_messages_are_hidden_and_cleaned_up(pool: PgPool) {
+        let user = db::create_user(&pool, "solo", "Solo", None, "hash")
+            .await
+            .expect("create user");
+        let sender = ToDeviceSender {
+            user_id: user.id,
+            device_id: fixtures::create_device(&pool, user.id).await,
+        };
+        let recipient = fixtures::create_device(&pool, user.id).await;
+
+        enqueue(&pool, sender, user.id, recipient, "m.room_key", "cipher")
+            .await
+            .unwrap()
+            .expect("queued");
+        sqlx::query("UPDATE to_device_messages SET expires_at = NOW() - INTERVAL '1 minute'")
+            .execute(&pool)
+            .await
+            .expect("expire");
+
+        assert!(pending(&pool, user.id, recipient).await.unwrap().is_empty());
+        assert_eq!(cleanup_expired(&pool).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,130 @@
+//! Shared Database Test Fixtures
+//!
+//! Rows that many modules' tests need but that have no production
+//! constructor of their own: guilds with members, DMs and E2EE devices.
+
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{create_channel, find_channel_by_id, Channel, ChannelType, CreateChannelParams};
+use crate::permissions::GuildPermissions;
+
+/// Create a guild owned by `owner`, with `members` joined alongside.
+///
+/// The `@everyone` role can view channels.
+pub async fn create_guild(pool: &PgPool, name: &str, owner: Uuid, members: &[Uuid]) -> Uuid {
+    let guild_id = Uuid::new_v4();
+    sqlx::query("INSERT INTO guilds (id, name, owner_id) VALUES ($1, $2, $3)")
+        .bind(guild_id)
+        .bind(name)
+        .bind(owner)
+        .execute(pool)
+        .await
+        .expect("create guild");
+    sqlx::query(
+        "INSERT INTO guild_roles (guild_id, name, permissions, position, is_default) VALUES ($1, '@everyone', $2, 0, true)",
+    )
+    .bind(guild_id)
+    .bind(GuildPermissions::VIEW_CHANNEL.to_db())
+    .execute(pool)
+    .await
+    .expect("create everyone role");
+    for user_id in std::iter::once(&owner).chain(members) {
+        sqlx::query("INSERT INTO guild_members (guild_id, user_id) VALUES ($1, $2)")
+            .bind(guild_id)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .expect("join guild");
+    }
+    guild_id
+}
+
+/// Create a text channel in `guild_id`.
+pub async fn create_text_channel(pool: &PgPool, guild_id: Uuid, name: &str) -> Uuid {
+    create_channel(
+        pool,
+        CreateChannelParams {
+            name,
+            channel_type: &ChannelType::Text,
+            category_id: None,
+            guild_id: Some(guild_id),
+            topic: None,
+            icon_url: None,
+            user_limit: None,
+        },
+    )
+    .await
+    .expect("create channel")
+    .id
+}
+
+/// Create a guild as in [`create_guild`] with a single `#general` channel.
+///
+/// Returns the guild and channel IDs.
+pub async fn guild_with_channel(
+    pool: &PgPool,
+    name: &str,
+    owner: Uuid,
+    members: &[Uuid],
+) -> (Uuid, Uuid) {
+    let guild_id = create_guild(pool, name, owner, members).await;
+    let channel_id = create_text_channel(pool, guild_id, "general").await;
+    (guild_id, channel_id)
+}
+
+/// Create a DM channel between `users`.
+pub async fn create_dm(pool: &PgPool, name: &str, users: &[Uuid]) -> Uuid {
+    let channel_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO channels (name, channel_type) VALUES ($1, 'dm') RETURNING id",
+    )
+    .bind(name)
+    .fetch_one(pool)
+    .await
+    .expect("create dm");
+    for user_id in users {
+        sqlx::query("INSERT INTO dm_participants (channel_id, user_id) VALUES ($1, $2)")
+            .bind(channel_id)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .expect("join dm");
+    }
+    channel_id
+}
+
+/// Fetch a channel that must exist.
+pub async fn channel(pool: &PgPool, channel_id: Uuid) -> Channel {
+    find_channel_by_id(pool, channel_id)
+        .await
+        .expect("find channel")
+        .expect("channel exists")
+}
+
+/// Register an E2EE device with placeholder identity keys.
+pub async fn create_device(pool: &PgPool, user_id: Uuid) -> Uuid {
+    let curve_key = Uuid::now_v7().simple().to_string();
+    create_device_with_keys(pool, user_id, "ed25519", &curve_key).await
+}
+
+/// Register an E2EE device with the given identity keys.
+pub async fn create_device_with_keys(
+    pool: &PgPool,
+    user_id: Uuid,
+    identity_key_ed25519: &str,
+    identity_key_curve25519: &str,
+) -> Uuid {
+    sqlx::query_scalar(
+        r"
+        INSERT INTO user_devices (user_id, identity_key_ed25519, identity_key_curve25519)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        ",
+    )
+    .bind(user_id)
+    .bind(identity_key_ed25519)
+    .bind(identity_key_curve25519)
+    .fetch_one(pool)
+    .await
+    .expect("create device")
+}
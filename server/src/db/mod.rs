@@ -26,11 +26,15 @@
 //!   - Called from: `server/src/pages/handlers.rs`
 //! - 63 = `bot_install` (per-guild bot installation limit)
 //!   - Called from: `server/src/guild/handlers.rs`
+//! - 65 = `sticker_create` (per-guild sticker creation limit, COUNT + INSERT only)
+//!   - Called from: `server/src/guild/stickers.rs`
 
 mod models;
 mod queries;
 pub mod user_features;
 
+#[cfg(test)]
+pub mod fixtures;
 #[cfg(test)]
 mod tests;
 
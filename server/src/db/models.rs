@@ -95,6 +95,19 @@ pub struct Channel {
     /// Maximum concurrent screen shares (voice channels only).
     #[serde(default = "default_max_screen_shares")]
     pub max_screen_shares: i32,
+    /// Seconds members must wait between messages (0 = off).
+    #[serde(default)]
+    pub slowmode_seconds: i32,
+    /// Messages must be end-to-end encrypted (can't be turned off).
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Bumped when membership may have changed; senders rotate Megolm
+    /// sessions created in an older epoch.
+    #[serde(default)]
+    pub encryption_epoch: i64,
+    /// Lifetime of new messages in seconds (`None` = messages don't disappear).
+    #[serde(default)]
+    pub message_ttl_seconds: Option<i32>,
     /// When the channel was created.
     pub created_at: DateTime<Utc>,
     /// When the channel was last updated.
@@ -145,8 +158,14 @@ pub struct Message {
     /// Message type: "user" for regular, "system" for system events.
     #[serde(default = "default_message_type")]
     pub message_type: String,
+    /// Interactive components (action rows) attached by a bot.
+    #[serde(default)]
+    pub components: Option<serde_json::Value>,
     /// When the message was created.
     pub created_at: DateTime<Utc>,
+    /// When the message is hard-deleted (disappearing messages).
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 fn default_message_type() -> String {
@@ -213,6 +232,8 @@ pub struct FileAttachment {
     pub medium_s3_key: Option<String>,
     /// Processing status: pending, processed, failed, skipped.
     pub processing_status: String,
+    /// Client-encrypted ciphertext; the key lives in the encrypted message.
+    pub encrypted: bool,
 }
 
 /// Session model for refresh token tracking.
@@ -347,6 +368,9 @@ pub struct ChannelUnread {
     pub channel_name: String,
     /// Number of unread messages.
     pub unread_count: i64,
+    /// Number of unread messages that mention the user.
+    #[serde(default)]
+    pub mention_count: i64,
 }
 
 /// Guild unread summary for the unread aggregator.
@@ -435,6 +459,8 @@ pub struct SlashCommand {
     /// Command options/parameters as JSON.
     #[schema(value_type = Option<Object>)]
     pub options: Option<serde_json::Value>,
+    /// Guild permission bits a member needs to use the command (None = everyone).
+    pub default_member_permissions: Option<i64>,
     /// When the command was created.
     pub created_at: DateTime<Utc>,
     /// When the command was last updated.
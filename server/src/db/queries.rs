@@ -7,7 +7,7 @@
 use std::collections::HashMap;
 
 use chrono::{DateTime, Utc};
-use sqlx::{PgPool, QueryBuilder, Row};
+use sqlx::{PgExecutor, PgPool, QueryBuilder, Row};
 use tracing::error;
 use uuid::Uuid;
 
@@ -519,7 +519,7 @@ pub async fn delete_mfa_backup_codes(pool: &PgPool, user_id: Uuid) -> sqlx::Resu
 pub async fn find_channel_by_id(pool: &PgPool, id: Uuid) -> sqlx::Result<Option<Channel>> {
     sqlx::query_as::<_, Channel>(
         r"
-        SELECT id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, created_at, updated_at
+        SELECT id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, slowmode_seconds, encrypted, encryption_epoch, message_ttl_seconds, created_at, updated_at
         FROM channels
         WHERE id = $1
         ",
@@ -560,7 +560,7 @@ pub async fn create_channel(
         r"
         INSERT INTO channels (name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position)
         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
-        RETURNING id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, created_at, updated_at
+        RETURNING id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, slowmode_seconds, encrypted, encryption_epoch, message_ttl_seconds, created_at, updated_at
         ",
     )
     .bind(params.name)
@@ -576,6 +576,7 @@ pub async fn create_channel(
 }
 
 /// Update a channel.
+#[allow(clippy::too_many_arguments)]
 pub async fn update_channel(
     pool: &PgPool,
     id: Uuid,
@@ -584,6 +585,7 @@ pub async fn update_channel(
     icon_url: Option<&str>,
     user_limit: Option<i32>,
     position: Option<i32>,
+    slowmode_seconds: Option<i32>,
 ) -> sqlx::Result<Option<Channel>> {
     sqlx::query_as::<_, Channel>(
         r"
@@ -593,9 +595,10 @@ pub async fn update_channel(
             icon_url = COALESCE($4, icon_url),
             user_limit = COALESCE($5, user_limit),
             position = COALESCE($6, position),
+            slowmode_seconds = COALESCE($7, slowmode_seconds),
             updated_at = NOW()
         WHERE id = $1
-        RETURNING id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, created_at, updated_at
+        RETURNING id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, slowmode_seconds, encrypted, encryption_epoch, message_ttl_seconds, created_at, updated_at
         ",
     )
     .bind(id)
@@ -604,6 +607,7 @@ pub async fn update_channel(
     .bind(icon_url)
     .bind(user_limit)
     .bind(position)
+    .bind(slowmode_seconds)
     .fetch_optional(pool)
     .await
 }
@@ -794,6 +798,7 @@ pub async fn list_messages(
             WHERE m.channel_id = $1
               AND m.deleted_at IS NULL
               AND m.parent_id IS NULL
+              AND (m.expires_at IS NULL OR m.expires_at > NOW())
               AND (m.created_at, m.id) < (
                 SELECT created_at, id FROM messages WHERE id = $2
               )
@@ -813,6 +818,7 @@ pub async fn list_messages(
             WHERE channel_id = $1
               AND deleted_at IS NULL
               AND parent_id IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
             ORDER BY created_at DESC, id DESC
             LIMIT $2
             ",
@@ -826,11 +832,18 @@ pub async fn list_messages(
 
 /// Find message by ID.
 pub async fn find_message_by_id(pool: &PgPool, id: Uuid) -> sqlx::Result<Option<Message>> {
-    sqlx::query_as::<_, Message>("SELECT * FROM messages WHERE id = $1 AND deleted_at IS NULL")
-        .bind(id)
-        .fetch_optional(pool)
-        .await
-        .map_err(db_error!("find_message_by_id", message_id = %id))
+    sqlx::query_as::<_, Message>(
+        r"
+        SELECT * FROM messages
+        WHERE id = $1
+          AND deleted_at IS NULL
+          AND (expires_at IS NULL OR expires_at > NOW())
+        ",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error!("find_message_by_id", message_id = %id))
 }
 
 /// Create a new message.
@@ -860,17 +873,65 @@ pub async fn create_message(
     .await
 }
 
-/// Update a message (edit).
+/// Create a bot message with optional interactive components.
+pub async fn create_bot_message(
+    executor: impl PgExecutor<'_>,
+    channel_id: Uuid,
+    bot_user_id: Uuid,
+    content: &str,
+    components: Option<&serde_json::Value>,
+) -> sqlx::Result<Message> {
+    sqlx::query_as::<_, Message>(
+        r"
+        INSERT INTO messages (channel_id, user_id, content, components)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        ",
+    )
+    .bind(channel_id)
+    .bind(bot_user_id)
+    .bind(content)
+    .bind(components)
+    .fetch_one(executor)
+    .await
+}
+
+/// Replace the content and components of a bot message.
+pub async fn update_message_components(
+    pool: &PgPool,
+    id: Uuid,
+    user_id: Uuid,
+    content: &str,
+    components: Option<&serde_json::Value>,
+) -> sqlx::Result<Option<Message>> {
+    sqlx::query_as::<_, Message>(
+        r"
+        UPDATE messages
+        SET content = $3, components = $4, edited_at = NOW()
+        WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
+        RETURNING *
+        ",
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(content)
+    .bind(components)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Update a message (edit). Encrypted messages are re-encrypted with a new `nonce`.
 pub async fn update_message(
     pool: &PgPool,
     id: Uuid,
     user_id: Uuid,
     content: &str,
+    nonce: Option<&str>,
 ) -> sqlx::Result<Option<Message>> {
     sqlx::query_as::<_, Message>(
         r"
         UPDATE messages
-        SET content = $3, edited_at = NOW()
+        SET content = $3, nonce = $4, edited_at = NOW()
         WHERE id = $1 AND user_id = $2 AND deleted_at IS NULL
         RETURNING *
         ",
@@ -878,6 +939,7 @@ pub async fn update_message(
     .bind(id)
     .bind(user_id)
     .bind(content)
+    .bind(nonce)
     .fetch_optional(pool)
     .await
 }
@@ -949,6 +1011,7 @@ pub async fn list_thread_replies(
             SELECT m.* FROM messages m
             WHERE m.parent_id = $1
               AND m.deleted_at IS NULL
+              AND (m.expires_at IS NULL OR m.expires_at > NOW())
               AND (m.created_at, m.id) > (
                 SELECT created_at, id FROM messages WHERE id = $2
               )
@@ -967,6 +1030,7 @@ pub async fn list_thread_replies(
             SELECT * FROM messages
             WHERE parent_id = $1
               AND deleted_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
             ORDER BY created_at ASC, id ASC
             LIMIT $2
             ",
@@ -1227,6 +1291,7 @@ pub async fn search_messages_in_channels(
         WHERE m.channel_id = ANY($1)
           AND m.deleted_at IS NULL
           AND m.encrypted = false
+          AND (m.expires_at IS NULL OR m.expires_at > NOW())
           AND m.content_search @@ websearch_to_tsquery('english', $2)
         ORDER BY m.created_at DESC
         LIMIT $3 OFFSET $4
@@ -1260,6 +1325,7 @@ pub async fn count_search_messages_in_channels(
         WHERE m.channel_id = ANY($1)
           AND m.deleted_at IS NULL
           AND m.encrypted = false
+          AND (m.expires_at IS NULL OR m.expires_at > NOW())
           AND m.content_search @@ websearch_to_tsquery('english', $2)
         ",
     )
@@ -1353,6 +1419,7 @@ pub async fn search_messages_filtered(
     builder.push_bind(channel_ids);
     builder.push(
         ") AND m.deleted_at IS NULL AND m.encrypted = false \
+         AND (m.expires_at IS NULL OR m.expires_at > NOW()) \
          AND m.content_search @@ websearch_to_tsquery('english', ",
     );
     builder.push_bind(query);
@@ -1412,6 +1479,7 @@ pub async fn count_search_messages_filtered(
     builder.push_bind(channel_ids);
     builder.push(
         ") AND m.deleted_at IS NULL AND m.encrypted = false \
+         AND (m.expires_at IS NULL OR m.expires_at > NOW()) \
          AND m.content_search @@ websearch_to_tsquery('english', ",
     );
     builder.push_bind(query);
@@ -1453,13 +1521,14 @@ pub async fn create_file_attachment(
     thumbnail_s3_key: Option<&str>,
     medium_s3_key: Option<&str>,
     processing_status: &str,
+    encrypted: bool,
 ) -> sqlx::Result<FileAttachment> {
     sqlx::query_as::<_, FileAttachment>(
         r"
         INSERT INTO file_attachments (message_id, filename, mime_type, size_bytes, s3_key,
                                       width, height, blurhash, thumbnail_s3_key, medium_s3_key,
-                                      processing_status)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                                      processing_status, encrypted)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
         RETURNING *
         ",
     )
@@ -1474,6 +1543,7 @@ pub async fn create_file_attachment(
     .bind(thumbnail_s3_key)
     .bind(medium_s3_key)
     .bind(processing_status)
+    .bind(encrypted)
     .fetch_one(pool)
     .await
 }
@@ -1605,7 +1675,7 @@ pub async fn is_guild_member(pool: &PgPool, guild_id: Uuid, user_id: Uuid) -> sq
 pub async fn get_guild_channels(pool: &PgPool, guild_id: Uuid) -> sqlx::Result<Vec<Channel>> {
     sqlx::query_as::<_, Channel>(
         r"
-        SELECT id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, created_at, updated_at
+        SELECT id, name, channel_type, category_id, guild_id, topic, icon_url, user_limit, position, max_screen_shares, slowmode_seconds, encrypted, encryption_epoch, message_ttl_seconds, created_at, updated_at
         FROM channels
         WHERE guild_id = $1
         ORDER BY position ASC
@@ -1733,6 +1803,11 @@ pub async fn count_users(pool: &PgPool) -> sqlx::Result<i64> {
 ///
 /// Unread count is calculated by comparing message `created_at` with the user's
 /// `last_read_at` from `channel_read_state`.
+///
+/// Notification settings are applied the same way as for push delivery:
+/// muted channels and channels set to `nothing` are left out, channels set to
+/// `mentions` only count messages that mention the user, and replies in muted
+/// threads are ignored. Mention counts come from the `message_mentions` index.
 #[tracing::instrument(skip(pool))]
 pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<UnreadAggregate> {
     // Get guild channel unreads
@@ -1743,10 +1818,36 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
             g.name as guild_name,
             c.id as channel_id,
             c.name as channel_name,
-            COUNT(m.id)::bigint as unread_count
+            ns.level::text as level,
+            COUNT(m.id)::bigint as unread_count,
+            COUNT(mm.message_id) FILTER (
+                WHERE mm.kind = 'user'
+                  OR (mm.kind = 'role' AND NOT COALESCE(ns.suppress_roles, FALSE))
+                  OR (mm.kind IN ('everyone', 'here') AND NOT COALESCE(ns.suppress_everyone, FALSE))
+            )::bigint as mention_count
         FROM guild_members gm
         INNER JOIN guilds g ON g.id = gm.guild_id
         INNER JOIN channels c ON c.guild_id = g.id
+        LEFT JOIN channel_categories cat ON cat.id = c.category_id
+        LEFT JOIN LATERAL (
+            SELECT
+                bool_or(s.muted AND (s.muted_until IS NULL OR s.muted_until > NOW())) as muted,
+                (array_agg(s.level ORDER BY CASE
+                    WHEN s.scope = 'channel' THEN 0
+                    WHEN s.scope_id = c.category_id THEN 1
+                    WHEN s.scope = 'category' THEN 2
+                    ELSE 3
+                END) FILTER (WHERE s.level IS NOT NULL))[1] as level,
+                bool_or(s.scope = 'guild' AND s.suppress_everyone) as suppress_everyone,
+                bool_or(s.scope = 'guild' AND s.suppress_roles) as suppress_roles
+            FROM notification_settings s
+            WHERE s.user_id = $1
+              AND (
+                  (s.scope = 'channel' AND s.scope_id = c.id)
+                  OR (s.scope = 'category' AND s.scope_id IN (c.category_id, cat.parent_id))
+                  OR (s.scope = 'guild' AND s.scope_id = g.id)
+              )
+        ) ns ON TRUE
         LEFT JOIN channel_read_state crs ON crs.channel_id = c.id AND crs.user_id = $1
         LEFT JOIN messages m ON m.channel_id = c.id
             AND m.deleted_at IS NULL
@@ -1754,8 +1855,21 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
                 crs.last_read_at IS NULL
                 OR m.created_at > crs.last_read_at
             )
+            AND NOT EXISTS (
+                SELECT 1 FROM notification_settings ts
+                WHERE ts.user_id = $1
+                  AND ts.scope = 'thread'
+                  AND ts.scope_id = m.parent_id
+                  AND (
+                      ts.level = 'nothing'
+                      OR (ts.muted AND (ts.muted_until IS NULL OR ts.muted_until > NOW()))
+                  )
+            )
+        LEFT JOIN message_mentions mm ON mm.message_id = m.id AND mm.user_id = $1
         WHERE gm.user_id = $1
-        GROUP BY g.id, g.name, c.id, c.name
+          AND NOT COALESCE(ns.muted, FALSE)
+          AND ns.level IS DISTINCT FROM 'nothing'
+        GROUP BY g.id, g.name, c.id, c.name, ns.level
         HAVING COUNT(m.id) > 0
         ORDER BY g.name, c.position
         ",
@@ -1765,7 +1879,7 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
     .await
     .map_err(db_error!("get_unread_aggregate:guilds", user_id = %user_id))?;
 
-    // Get DM unreads
+    // Get DM unreads (every DM message is addressed to the user)
     let dm_rows = sqlx::query(
         r"
         SELECT
@@ -1774,6 +1888,9 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
             COUNT(m.id)::bigint as unread_count
         FROM dm_participants dp
         INNER JOIN channels c ON c.id = dp.channel_id
+        LEFT JOIN notification_settings s ON s.user_id = $1
+            AND s.scope = 'channel'
+            AND s.scope_id = c.id
         LEFT JOIN channel_read_state crs ON crs.channel_id = c.id AND crs.user_id = $1
         LEFT JOIN messages m ON m.channel_id = c.id
             AND m.deleted_at IS NULL
@@ -1783,6 +1900,11 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
                 OR m.created_at > crs.last_read_at
             )
         WHERE dp.user_id = $1 AND c.channel_type = 'dm'
+          AND s.level IS DISTINCT FROM 'nothing'
+          AND NOT COALESCE(
+              s.muted AND (s.muted_until IS NULL OR s.muted_until > NOW()),
+              FALSE
+          )
         GROUP BY c.id, c.name
         HAVING COUNT(m.id) > 0
         ORDER BY c.name
@@ -1802,7 +1924,16 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
         let guild_name: String = row.get("guild_name");
         let channel_id: Uuid = row.get("channel_id");
         let channel_name: String = row.get("channel_name");
-        let unread_count: i64 = row.get("unread_count");
+        let level: Option<String> = row.get("level");
+        let mention_count: i64 = row.get("mention_count");
+        let unread_count: i64 = if level.as_deref() == Some("mentions") {
+            mention_count
+        } else {
+            row.get("unread_count")
+        };
+        if unread_count == 0 {
+            continue;
+        }
 
         let guild_summary = guilds_map
             .entry(guild_id)
@@ -1817,6 +1948,7 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
             channel_id,
             channel_name,
             unread_count,
+            mention_count,
         });
         guild_summary.total_unread += unread_count;
     }
@@ -1831,6 +1963,7 @@ pub async fn get_unread_aggregate(pool: &PgPool, user_id: Uuid) -> sqlx::Result<
             channel_id: row.get("channel_id"),
             channel_name: row.get("channel_name"),
             unread_count: row.get("unread_count"),
+            mention_count: row.get("unread_count"),
         })
         .collect();
 
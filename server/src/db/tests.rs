@@ -414,6 +414,7 @@ mod postgres_tests {
             None,
             None,
             None, // position
+            None, // slowmode_seconds
         )
         .await
         .expect("Failed to update channel")
@@ -608,7 +609,7 @@ mod postgres_tests {
         assert_eq!(found.id, message.id);
 
         // Update message
-        let updated = update_message(&pool, message.id, user.id, "Updated message")
+        let updated = update_message(&pool, message.id, user.id, "Updated message", None)
             .await
             .expect("Failed to update message")
             .expect("Message not found");
@@ -827,6 +828,7 @@ mod postgres_tests {
             None,
             None,
             "skipped",
+            false,
         )
         .await
         .expect("Failed to create attachment");
@@ -904,6 +906,7 @@ mod postgres_tests {
             None,
             None,
             "skipped",
+            false,
         )
         .await
         .expect("Failed to create attachment 1");
@@ -920,6 +923,7 @@ mod postgres_tests {
             None,
             None,
             "skipped",
+            false,
         )
         .await
         .expect("Failed to create attachment 2");
@@ -1033,6 +1037,118 @@ mod postgres_tests {
         assert!(result.dms.is_empty());
     }
 
+    #[sqlx::test]
+    async fn test_get_unread_aggregate_respects_notification_settings(pool: PgPool) {
+        let owner = create_user(&pool, "quietowner", "Quiet Owner", None, "hash")
+            .await
+            .expect("create owner");
+        let sender = create_user(&pool, "noisy", "Noisy", None, "hash")
+            .await
+            .expect("create sender");
+
+        let guild_id = uuid::Uuid::new_v4();
+        sqlx::query("INSERT INTO guilds (id, name, owner_id) VALUES ($1, $2, $3)")
+            .bind(guild_id)
+            .bind("Quiet Guild")
+            .bind(owner.id)
+            .execute(&pool)
+            .await
+            .expect("create guild");
+        sqlx::query("INSERT INTO guild_members (guild_id, user_id) VALUES ($1, $2)")
+            .bind(guild_id)
+            .bind(owner.id)
+            .execute(&pool)
+            .await
+            .expect("join guild");
+
+        let mut channel_ids = Vec::new();
+        for name in ["muted", "mentions"] {
+            let channel = create_channel(
+                &pool,
+                CreateChannelParams {
+                    name,
+                    channel_type: &ChannelType::Text,
+                    category_id: None,
+                    guild_id: Some(guild_id),
+                    topic: None,
+                    icon_url: None,
+                    user_limit: None,
+                },
+            )
+            .await
+            .expect("create channel");
+            for (content, kind) in [
+                ("hello", None),
+                ("hey @quietowner", Some("user")),
+                ("@everyone standup", Some("everyone")),
+            ] {
+                let message =
+                    create_message(&pool, channel.id, sender.id, content, false, None, None)
+                        .await
+                        .expect("create message");
+                if let Some(kind) = kind {
+                    sqlx::query(
+                        "INSERT INTO message_mentions (message_id, user_id, channel_id, guild_id, kind, created_at)
+                         VALUES ($1, $2, $3, $4, $5::message_mention_kind, $6)",
+                    )
+                    .bind(message.id)
+                    .bind(owner.id)
+                    .bind(channel.id)
+                    .bind(guild_id)
+                    .bind(kind)
+                    .bind(message.created_at)
+                    .execute(&pool)
+                    .await
+                    .expect("record mention");
+                }
+            }
+            channel_ids.push(channel.id);
+        }
+
+        sqlx::query(
+            "INSERT INTO notification_settings (user_id, scope, scope_id, muted) VALUES ($1, 'channel', $2, TRUE)",
+        )
+        .bind(owner.id)
+        .bind(channel_ids[0])
+        .execute(&pool)
+        .await
+        .expect("mute channel");
+        sqlx::query(
+            "INSERT INTO notification_settings (user_id, scope, scope_id, level) VALUES ($1, 'channel', $2, 'mentions')",
+        )
+        .bind(owner.id)
+        .bind(channel_ids[1])
+        .execute(&pool)
+        .await
+        .expect("set mentions level");
+
+        let result = get_unread_aggregate(&pool, owner.id)
+            .await
+            .expect("get_unread_aggregate");
+        assert_eq!(result.guilds.len(), 1);
+        assert_eq!(result.guilds[0].channels.len(), 1);
+        assert_eq!(result.guilds[0].channels[0].channel_id, channel_ids[1]);
+        assert_eq!(result.guilds[0].channels[0].unread_count, 2);
+        assert_eq!(result.guilds[0].channels[0].mention_count, 2);
+        assert_eq!(result.total, 2);
+
+        // Suppressing @everyone at guild scope drops it from the mention count
+        sqlx::query(
+            "INSERT INTO notification_settings (user_id, scope, scope_id, suppress_everyone) VALUES ($1, 'guild', $2, TRUE)",
+        )
+        .bind(owner.id)
+        .bind(guild_id)
+        .execute(&pool)
+        .await
+        .expect("suppress everyone");
+
+        let result = get_unread_aggregate(&pool, owner.id)
+            .await
+            .expect("get_unread_aggregate");
+        assert_eq!(result.guilds[0].channels[0].unread_count, 1);
+        assert_eq!(result.total, 1);
+    }
+
     #[sqlx::test]
     async fn test_get_unread_aggregate_no_unreads_after_read(pool: PgPool) {
         // Create owner and guild
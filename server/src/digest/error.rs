@@ -0,0 +1,56 @@
+//! Email Digest Error Types
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DigestError {
+    #[error("Email digests are not configured on this server")]
+    NotConfigured,
+
+    #[error("An email address is required for digests")]
+    EmailRequired,
+
+    #[error("Invalid unsubscribe token")]
+    InvalidToken,
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for DigestError {
+    fn into_response(self) -> axum::response::Response {
+        let (status, code, message) = match &self {
+            Self::NotConfigured => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "DIGEST_NOT_CONFIGURED",
+                "Email digests are not configured on this server".to_string(),
+            ),
+            Self::EmailRequired => (
+                StatusCode::BAD_REQUEST,
+                "EMAIL_REQUIRED",
+                "Add an email address to your account to receive digests".to_string(),
+            ),
+            Self::InvalidToken => (
+                StatusCode::BAD_REQUEST,
+                "INVALID_TOKEN",
+                "Invalid unsubscribe link".to_string(),
+            ),
+            Self::Database(err) => {
+                tracing::error!(%err, "Email digest endpoint database error");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "Database error".to_string(),
+                )
+            }
+        };
+
+        (
+            status,
+            Json(serde_json::json!({ "error": code, "message": message })),
+        )
+            .into_response()
+    }
+}
@@ -0,0 +1,156 @@
+//! Email Digest HTTP Handlers
+
+use axum::extract::{Query, State};
+use axum::response::Html;
+use axum::Json;
+
+use super::error::DigestError;
+use super::queries;
+use super::types::{
+    DigestFrequency, EmailDigestSettings, UnsubscribeQuery, UpdateEmailDigestRequest,
+};
+use super::unsubscribe;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+
+/// Whether this server can send digests.
+fn digests_available(state: &AppState) -> bool {
+    state.email.is_some() && state.config.public_url.is_some()
+}
+
+/// Key that signs this server's unsubscribe tokens.
+fn unsubscribe_key(state: &AppState) -> String {
+    unsubscribe::derive_key(&state.config.jwt_private_key)
+}
+
+/// Get the user's email digest settings.
+///
+/// GET /api/me/email-digest
+#[utoipa::path(
+    get,
+    path = "/api/me/email-digest",
+    tag = "email-digest",
+    responses(
+        (status = 200, body = EmailDigestSettings),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn get_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<EmailDigestSettings>, DigestError> {
+    let (frequency, last_sent_at) = queries::get_settings(&state.db, auth_user.id)
+        .await?
+        .unwrap_or_default();
+
+    Ok(Json(EmailDigestSettings {
+        frequency,
+        last_sent_at,
+        available: digests_available(&state),
+    }))
+}
+
+/// Set how often the user receives email digests.
+///
+/// PUT /api/me/email-digest
+#[utoipa::path(
+    put,
+    path = "/api/me/email-digest",
+    tag = "email-digest",
+    request_body = UpdateEmailDigestRequest,
+    responses(
+        (status = 200, body = EmailDigestSettings),
+        (status = 400, description = "The account has no email address"),
+        (status = 503, description = "Email digests are not configured"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn update_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Json(request): Json<UpdateEmailDigestRequest>,
+) -> Result<Json<EmailDigestSettings>, DigestError> {
+    if request.frequency != DigestFrequency::Off {
+        if !digests_available(&state) {
+            return Err(DigestError::NotConfigured);
+        }
+        if auth_user.email.is_none() {
+            return Err(DigestError::EmailRequired);
+        }
+    }
+
+    let last_sent_at = queries::set_frequency(&state.db, auth_user.id, request.frequency).await?;
+
+    Ok(Json(EmailDigestSettings {
+        frequency: request.frequency,
+        last_sent_at,
+        available: digests_available(&state),
+    }))
+}
+
+/// Confirmation page for the unsubscribe link in digest emails.
+///
+/// Doesn't change anything itself, so link scanners can't unsubscribe users.
+///
+/// GET /api/email/unsubscribe
+#[utoipa::path(
+    get,
+    path = "/api/email/unsubscribe",
+    tag = "email-digest",
+    params(UnsubscribeQuery),
+    responses(
+        (status = 200, description = "Confirmation page", content_type = "text/html"),
+        (status = 400, description = "Invalid token"),
+    ),
+)]
+#[tracing::instrument(skip(state, query))]
+pub async fn unsubscribe_page(
+    State(state): State<AppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<Html<String>, DigestError> {
+    let user_id = unsubscribe::verify(&unsubscribe_key(&state), &query.token)
+        .ok_or(DigestError::InvalidToken)?;
+
+    Ok(Html(format!(
+        "<!DOCTYPE html>\n<html><body style=\"font-family: sans-serif;\">\n\
+         <p>Stop receiving email digests?</p>\n\
+         <form method=\"post\" action=\"?token={}\"><button type=\"submit\">Unsubscribe</button></form>\n\
+         </body></html>\n",
+        unsubscribe::sign(&unsubscribe_key(&state), user_id)
+    )))
+}
+
+/// Turn off email digests for the user the token was issued to.
+///
+/// Used by the confirmation page and by mail clients for one-click
+/// unsubscribe (RFC 8058).
+///
+/// POST /api/email/unsubscribe
+#[utoipa::path(
+    post,
+    path = "/api/email/unsubscribe",
+    tag = "email-digest",
+    params(UnsubscribeQuery),
+    responses(
+        (status = 200, description = "Unsubscribed", content_type = "text/html"),
+        (status = 400, description = "Invalid token"),
+    ),
+)]
+#[tracing::instrument(skip(state, query))]
+pub async fn confirm_unsubscribe(
+    State(state): State<AppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> Result<Html<&'static str>, DigestError> {
+    let user_id = unsubscribe::verify(&unsubscribe_key(&state), &query.token)
+        .ok_or(DigestError::InvalidToken)?;
+    queries::unsubscribe(&state.db, user_id).await?;
+
+    tracing::info!(%user_id, "Unsubscribed from email digests");
+    Ok(Html(
+        "<!DOCTYPE html>\n<html><body style=\"font-family: sans-serif;\">\n\
+         <p>You won't receive email digests anymore. You can turn them back on in your notification settings.</p>\n\
+         </body></html>\n",
+    ))
+}
@@ -0,0 +1,39 @@
+//! Email Digests
+//!
+//! Opt-in emails that batch unread mentions and DM notices for users who have
+//! been away. A background worker sends one digest per user per daily or
+//! weekly window, built on the unread aggregate so notification settings
+//! apply. Encrypted messages only ever contribute metadata.
+
+pub mod error;
+pub mod handlers;
+pub mod queries;
+pub mod render;
+pub mod types;
+pub mod unsubscribe;
+pub mod worker;
+
+use axum::routing::get;
+use axum::Router;
+
+use crate::api::AppState;
+
+/// Create digest settings routes.
+///
+/// Mounted at `/api/me/email-digest` in the main router.
+pub fn router() -> Router<AppState> {
+    Router::new().route(
+        "/",
+        get(handlers::get_settings).put(handlers::update_settings),
+    )
+}
+
+/// Create public unsubscribe routes (token-authenticated).
+///
+/// Mounted at `/api/email` in the main router.
+pub fn public_router() -> Router<AppState> {
+    Router::new().route(
+        "/unsubscribe",
+        get(handlers::unsubscribe_page).post(handlers::confirm_unsubscribe),
+    )
+}
@@ -0,0 +1,251 @@
+//! Email Digest Database Queries
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use super::types::{Digest, DigestDm, DigestFrequency, DigestMention, DueDigest};
+use crate::db;
+
+/// Most mentions listed in one digest; the rest are only counted.
+const MAX_LISTED_MENTIONS: i64 = 10;
+
+/// Get a user's digest frequency and last send time.
+pub async fn get_settings(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> sqlx::Result<Option<(DigestFrequency, Option<DateTime<Utc>>)>> {
+    sqlx::query_as("SELECT frequency, last_sent_at FROM email_digest_settings WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Set a user's digest frequency.
+///
+/// Turning digests on starts a fresh window, so the first digest only covers
+/// activity from now on.
+pub async fn set_frequency(
+    pool: &PgPool,
+    user_id: Uuid,
+    frequency: DigestFrequency,
+) -> sqlx::Result<Option<DateTime<Utc>>> {
+    sqlx::query_scalar(
+        r"
+        INSERT INTO email_digest_settings (user_id, frequency)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE SET
+            frequency = EXCLUDED.frequency,
+            window_start = CASE
+                WHEN email_digest_settings.frequency = 'off' THEN NOW()
+                ELSE email_digest_settings.window_start
+            END,
+            updated_at = NOW()
+        RETURNING last_sent_at
+        ",
+    )
+    .bind(user_id)
+    .bind(frequency)
+    .fetch_one(pool)
+    .await
+}
+
+/// Users whose digest window has elapsed at `now`.
+///
+/// Users without an email address, bots and accounts pending deletion are
+/// skipped.
+pub async fn due_digests(
+    pool: &PgPool,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> sqlx::Result<Vec<DueDigest>> {
+    sqlx::query_as(
+        r"
+        SELECT s.user_id, u.display_name, u.email, s.frequency, s.window_start
+        FROM email_digest_settings s
+        INNER JOIN users u ON u.id = s.user_id
+        WHERE (
+                (s.frequency = 'daily' AND s.window_start <= $1 - INTERVAL '1 day')
+                OR (s.frequency = 'weekly' AND s.window_start <= $1 - INTERVAL '7 days')
+            )
+          AND u.email IS NOT NULL
+          AND NOT u.is_bot
+          AND u.deletion_requested_at IS NULL
+        ORDER BY s.window_start
+        LIMIT $2
+        ",
+    )
+    .bind(now)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Close a user's digest window at `window_end`.
+pub async fn finish_window(
+    pool: &PgPool,
+    user_id: Uuid,
+    window_end: DateTime<Utc>,
+    sent: bool,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        r"
+        UPDATE email_digest_settings
+        SET window_start = $2,
+            last_sent_at = CASE WHEN $3 THEN $2 ELSE last_sent_at END
+        WHERE user_id = $1
+        ",
+    )
+    .bind(user_id)
+    .bind(window_end)
+    .bind(sent)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Turn digests off for a user (unsubscribe link).
+pub async fn unsubscribe(pool: &PgPool, user_id: Uuid) -> sqlx::Result<()> {
+    sqlx::query(
+        "UPDATE email_digest_settings SET frequency = 'off', updated_at = NOW() WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Collect what a user missed since `since`.
+///
+/// Starts from the unread aggregate, so muted channels, `nothing` levels and
+/// read messages are already left out, then narrows it to mentions and DM
+/// messages that arrived during the window. Encrypted messages contribute
+/// metadata only; their content is never selected.
+pub async fn collect_digest(
+    pool: &PgPool,
+    user_id: Uuid,
+    since: DateTime<Utc>,
+) -> sqlx::Result<Digest> {
+    let aggregate = db::get_unread_aggregate(pool, user_id).await?;
+    let mention_channels: Vec<Uuid> = aggregate
+        .guilds
+        .iter()
+        .flat_map(|guild| &guild.channels)
+        .filter(|channel| channel.mention_count > 0)
+        .map(|channel| channel.channel_id)
+        .collect();
+
+    let mut digest = Digest::default();
+
+    if !mention_channels.is_empty() {
+        let rows = sqlx::query(
+            r"
+            SELECT
+                m.id AS message_id,
+                c.id AS channel_id,
+                c.name AS channel_name,
+                g.name AS guild_name,
+                u.display_name AS author_name,
+                CASE WHEN m.encrypted THEN NULL ELSE m.content END AS content,
+                m.created_at,
+                COUNT(*) OVER ()::bigint AS total
+            FROM message_mentions mm
+            INNER JOIN messages m ON m.id = mm.message_id
+                AND (m.expires_at IS NULL OR m.expires_at > NOW())
+            INNER JOIN channels c ON c.id = mm.channel_id
+            LEFT JOIN guilds g ON g.id = mm.guild_id
+            LEFT JOIN users u ON u.id = m.user_id
+            LEFT JOIN channel_read_state crs ON crs.channel_id = c.id AND crs.user_id = $1
+            WHERE mm.user_id = $1
+              AND mm.channel_id = ANY($2)
+              AND mm.created_at >= $3
+              AND m.deleted_at IS NULL
+              AND (crs.last_read_at IS NULL OR m.created_at > crs.last_read_at)
+              AND NOT EXISTS (
+                  SELECT 1 FROM notification_settings s
+                  WHERE s.user_id = $1
+                    AND (
+                        (s.scope = 'guild' AND s.scope_id = mm.guild_id AND (
+                            (mm.kind = 'role' AND s.suppress_roles)
+                            OR (mm.kind IN ('everyone', 'here') AND s.suppress_everyone)
+                        ))
+                        OR (s.scope = 'thread' AND s.scope_id = m.parent_id AND (
+                            s.level = 'nothing'
+                            OR (s.muted AND (s.muted_until IS NULL OR s.muted_until > NOW()))
+                        ))
+                    )
+              )
+            ORDER BY mm.created_at DESC
+            LIMIT $4
+            ",
+        )
+        .bind(user_id)
+        .bind(&mention_channels)
+        .bind(since)
+        .bind(MAX_LISTED_MENTIONS)
+        .fetch_all(pool)
+        .await?;
+
+        digest.mention_total = rows.first().map_or(0, |row| row.get("total"));
+        digest.mentions = rows
+            .iter()
+            .map(|row| DigestMention {
+                message_id: row.get("message_id"),
+                channel_id: row.get("channel_id"),
+                channel_name: row.get("channel_name"),
+                guild_name: row.get("guild_name"),
+                author_name: row.get("author_name"),
+                content: row.get("content"),
+                created_at: row.get("created_at"),
+            })
+            .collect();
+    }
+
+    if !aggregate.dms.is_empty() {
+        let dm_ids: Vec<Uuid> = aggregate.dms.iter().map(|dm| dm.channel_id).collect();
+        let rows = sqlx::query(
+            r"
+            SELECT DISTINCT ON (m.channel_id)
+                m.channel_id,
+                u.display_name AS author_name,
+                CASE WHEN m.encrypted THEN NULL ELSE m.content END AS content,
+                COUNT(*) OVER (PARTITION BY m.channel_id)::bigint AS new_count
+            FROM messages m
+            LEFT JOIN users u ON u.id = m.user_id
+            LEFT JOIN channel_read_state crs ON crs.channel_id = m.channel_id AND crs.user_id = $1
+            WHERE m.channel_id = ANY($2)
+              AND m.created_at >= $3
+              AND m.user_id <> $1
+              AND m.deleted_at IS NULL
+              AND (m.expires_at IS NULL OR m.expires_at > NOW())
+              AND (crs.last_read_at IS NULL OR m.created_at > crs.last_read_at)
+            ORDER BY m.channel_id, m.created_at DESC
+            ",
+        )
+        .bind(user_id)
+        .bind(&dm_ids)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+
+        // Keep the aggregate's ordering
+        digest.dms = aggregate
+            .dms
+            .iter()
+            .filter_map(|dm| {
+                let row = rows
+                    .iter()
+                    .find(|row| row.get::<Uuid, _>("channel_id") == dm.channel_id)?;
+                Some(DigestDm {
+                    channel_id: dm.channel_id,
+                    channel_name: dm.channel_name.clone(),
+                    new_count: row.get("new_count"),
+                    latest_author: row.get("author_name"),
+                    content: row.get("content"),
+                })
+            })
+            .collect();
+    }
+
+    Ok(digest)
+}
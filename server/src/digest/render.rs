@@ -0,0 +1,243 @@
+//! Digest Email Templates
+//!
+//! Renders a [`Digest`] as a plain-text and an HTML body. All user-provided
+//! text is escaped in the HTML version.
+
+use std::fmt::Write;
+
+use super::types::{Digest, DigestFrequency};
+
+/// Longest message excerpt shown in a digest (characters).
+const MAX_EXCERPT_CHARS: usize = 140;
+
+/// A rendered digest email.
+#[derive(Debug, Clone)]
+pub struct RenderedDigest {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+fn plural(count: i64, singular: &str, plural: &str) -> String {
+    if count == 1 {
+        format!("1 {singular}")
+    } else {
+        format!("{count} {plural}")
+    }
+}
+
+/// Single-line excerpt of a message, truncated to `MAX_EXCERPT_CHARS`.
+fn excerpt(content: &str) -> String {
+    let flat = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut excerpt: String = flat.chars().take(MAX_EXCERPT_CHARS).collect();
+    if flat.chars().nth(MAX_EXCERPT_CHARS).is_some() {
+        excerpt.push('…');
+    }
+    excerpt
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Render the digest for `display_name`.
+#[must_use]
+pub fn render(
+    display_name: &str,
+    frequency: DigestFrequency,
+    digest: &Digest,
+    app_url: &str,
+    unsubscribe_url: &str,
+) -> RenderedDigest {
+    let dm_messages: i64 = digest.dms.iter().map(|dm| dm.new_count).sum();
+    let subject = match (digest.mention_total, dm_messages) {
+        (0, dms) => format!(
+            "You have {} waiting",
+            plural(dms, "direct message", "direct messages")
+        ),
+        (mentions, 0) => format!("You were mentioned {}", plural(mentions, "time", "times")),
+        (mentions, dms) => format!(
+            "You have {} and {} waiting",
+            plural(mentions, "mention", "mentions"),
+            plural(dms, "direct message", "direct messages")
+        ),
+    };
+    let period = match frequency {
+        DigestFrequency::Weekly => "weekly",
+        _ => "daily",
+    };
+
+    let mut text = format!("Hi {display_name},\n\nHere's what you missed.\n");
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html><body style=\"font-family: sans-serif; color: #1f2328;\">\n\
+         <p>Hi {},</p>\n<p>Here's what you missed.</p>\n",
+        escape_html(display_name)
+    );
+
+    if digest.mention_total > 0 {
+        let _ = write!(text, "\nMentions ({})\n", digest.mention_total);
+        let _ = writeln!(html, "<h3>Mentions ({})</h3>\n<ul>", digest.mention_total);
+        for mention in &digest.mentions {
+            let author = mention.author_name.as_deref().unwrap_or("Someone");
+            let place = mention.guild_name.as_ref().map_or_else(
+                || format!("#{}", mention.channel_name),
+                |guild| format!("#{} ({guild})", mention.channel_name),
+            );
+            let body = mention
+                .content
+                .as_deref()
+                .map_or_else(|| "[encrypted message]".to_string(), excerpt);
+            let _ = writeln!(text, "- {author} in {place}: {body}");
+            let _ = writeln!(
+                html,
+                "<li><strong>{}</strong> in {}: {}</li>",
+                escape_html(author),
+                escape_html(&place),
+                escape_html(&body)
+            );
+        }
+        let unlisted = digest.mention_total - digest.mentions.len() as i64;
+        if unlisted > 0 {
+            let _ = writeln!(text, "  …and {unlisted} more");
+            let _ = writeln!(html, "<li>…and {unlisted} more</li>");
+        }
+        html.push_str("</ul>\n");
+    }
+
+    if !digest.dms.is_empty() {
+        text.push_str("\nDirect messages\n");
+        html.push_str("<h3>Direct messages</h3>\n<ul>\n");
+        for dm in &digest.dms {
+            let count = plural(dm.new_count, "new message", "new messages");
+            let latest = match (&dm.latest_author, &dm.content) {
+                (Some(author), Some(content)) => format!(" — {author}: {}", excerpt(content)),
+                (Some(author), None) => format!(" — latest from {author} (encrypted)"),
+                (None, _) => String::new(),
+            };
+            let _ = writeln!(text, "- {}: {count}{latest}", dm.channel_name);
+            let _ = writeln!(
+                html,
+                "<li><strong>{}</strong>: {count}{}</li>",
+                escape_html(&dm.channel_name),
+                escape_html(&latest)
+            );
+        }
+        html.push_str("</ul>\n");
+    }
+
+    let _ = write!(
+        text,
+        "\nCatch up at {app_url}\n\n\
+         You're receiving this because you turned on {period} email digests.\n\
+         Unsubscribe: {unsubscribe_url}\n"
+    );
+    let _ = write!(
+        html,
+        "<p><a href=\"{}\">Catch up now</a></p>\n\
+         <p style=\"font-size: 12px; color: #656d76;\">You're receiving this because you turned on \
+         {period} email digests. <a href=\"{}\">Unsubscribe</a></p>\n</body></html>\n",
+        escape_html(app_url),
+        escape_html(unsubscribe_url)
+    );
+
+    RenderedDigest {
+        subject,
+        text,
+        html,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::digest::types::{DigestDm, DigestMention};
+
+    fn digest() -> Digest {
+        Digest {
+            mentions: vec![DigestMention {
+                message_id: Uuid::new_v4(),
+                channel_id: Uuid::new_v4(),
+                channel_name: "general".to_string(),
+                guild_name: Some("Rust <Fans>".to_string()),
+                author_name: Some("Alice".to_string()),
+                content: Some(format!("hey @bob\n{}", "x".repeat(200))),
+                created_at: Utc::now(),
+            }],
+            mention_total: 3,
+            dms: vec![DigestDm {
+                channel_id: Uuid::new_v4(),
+                channel_name: "alice, bob".to_string(),
+                new_count: 2,
+                latest_author: Some("Alice".to_string()),
+                content: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn renders_text_and_escaped_html() {
+        let rendered = render(
+            "Bob",
+            DigestFrequency::Daily,
+            &digest(),
+            "https://chat.example.com",
+            "https://chat.example.com/api/email/unsubscribe?token=t&x=1",
+        );
+        assert_eq!(
+            rendered.subject,
+            "You have 3 mentions and 2 direct messages waiting"
+        );
+
+        assert!(rendered
+            .text
+            .contains("- Alice in #general (Rust <Fans>): hey @bob xxx"));
+        assert!(rendered.text.contains("…and 2 more"));
+        assert!(rendered
+            .text
+            .contains("- alice, bob: 2 new messages — latest from Alice (encrypted)"));
+        assert!(rendered.text.contains("turned on daily email digests"));
+
+        assert!(rendered.html.contains("Rust &lt;Fans&gt;"));
+        assert!(!rendered.html.contains("<Fans>"));
+        assert!(rendered.html.contains("token=t&amp;x=1"));
+    }
+
+    #[test]
+    fn excerpts_are_flattened_and_truncated() {
+        let long = excerpt(&format!("a\n\n{}", "b".repeat(MAX_EXCERPT_CHARS)));
+        assert!(!long.contains('\n'));
+        assert_eq!(long.chars().count(), MAX_EXCERPT_CHARS + 1);
+        assert!(long.ends_with('…'));
+        assert_eq!(excerpt("short"), "short");
+    }
+
+    #[test]
+    fn subjects_cover_mentions_or_dms_alone() {
+        let mut only_dms = digest();
+        only_dms.mention_total = 0;
+        only_dms.mentions.clear();
+        only_dms.dms[0].new_count = 1;
+        let rendered = render("Bob", DigestFrequency::Weekly, &only_dms, "u", "u");
+        assert_eq!(rendered.subject, "You have 1 direct message waiting");
+        assert!(rendered.text.contains("weekly"));
+
+        let mut only_mentions = digest();
+        only_mentions.dms.clear();
+        let rendered = render("Bob", DigestFrequency::Daily, &only_mentions, "u", "u");
+        assert_eq!(rendered.subject, "You were mentioned 3 times");
+    }
+}
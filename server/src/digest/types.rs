@@ -0,0 +1,115 @@
+//! Email Digest Request/Response Types
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// How often a user receives digests.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema,
+)]
+#[sqlx(type_name = "digest_frequency", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    /// No digests (the default).
+    #[default]
+    Off,
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    /// Length of one digest window, `None` when digests are off.
+    #[must_use]
+    pub const fn interval(self) -> Option<Duration> {
+        match self {
+            Self::Off => None,
+            Self::Daily => Some(Duration::days(1)),
+            Self::Weekly => Some(Duration::weeks(1)),
+        }
+    }
+}
+
+// ============================================================================
+// Request/Response Types
+// ============================================================================
+
+/// A user's digest settings.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct EmailDigestSettings {
+    pub frequency: DigestFrequency,
+    /// When the last digest email was sent.
+    pub last_sent_at: Option<DateTime<Utc>>,
+    /// Whether this server can send digests (SMTP and `PUBLIC_URL` configured).
+    pub available: bool,
+}
+
+/// Request to change the digest frequency.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct UpdateEmailDigestRequest {
+    pub frequency: DigestFrequency,
+}
+
+/// Query string of the unsubscribe link.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct UnsubscribeQuery {
+    /// Signed unsubscribe token from the digest email.
+    pub token: String,
+}
+
+// ============================================================================
+// Digest Contents
+// ============================================================================
+
+/// A user whose digest window has elapsed.
+#[derive(Debug, Clone, FromRow)]
+pub struct DueDigest {
+    pub user_id: Uuid,
+    pub display_name: String,
+    pub email: String,
+    pub frequency: DigestFrequency,
+    pub window_start: DateTime<Utc>,
+}
+
+/// An unread mention in a digest.
+#[derive(Debug, Clone)]
+pub struct DigestMention {
+    pub message_id: Uuid,
+    pub channel_id: Uuid,
+    pub channel_name: String,
+    pub guild_name: Option<String>,
+    pub author_name: Option<String>,
+    /// Message text, `None` for encrypted messages.
+    pub content: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A DM conversation with unread messages in a digest.
+#[derive(Debug, Clone)]
+pub struct DigestDm {
+    pub channel_id: Uuid,
+    pub channel_name: String,
+    /// New messages since the previous digest.
+    pub new_count: i64,
+    pub latest_author: Option<String>,
+    /// Latest message text, `None` for encrypted DMs.
+    pub content: Option<String>,
+}
+
+/// Everything a user missed during one digest window.
+#[derive(Debug, Clone, Default)]
+pub struct Digest {
+    /// Most recent unread mentions (capped).
+    pub mentions: Vec<DigestMention>,
+    /// Total unread mentions in the window, including those not listed.
+    pub mention_total: i64,
+    pub dms: Vec<DigestDm>,
+}
+
+impl Digest {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.mention_total == 0 && self.dms.is_empty()
+    }
+}
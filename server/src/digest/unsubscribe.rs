@@ -0,0 +1,70 @@
+//! One-Click Unsubscribe Tokens
+//!
+//! Stateless `{user_id}.{hmac}` tokens embedded in digest links and the
+//! `List-Unsubscribe` header (RFC 8058), so unsubscribing needs no login.
+
+use hkdf::Hkdf;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::webhooks::signing::{sign_payload, verify_signature};
+
+fn message(user_id: Uuid) -> String {
+    format!("email-digest-unsubscribe:{user_id}")
+}
+
+/// Derive the token signing key from the server's JWT signing key.
+///
+/// Tokens sit in plain email links, so they are signed with a purpose-bound
+/// HKDF subkey rather than the JWT key itself.
+#[must_use]
+pub fn derive_key(jwt_key: &str) -> String {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(None, jwt_key.as_bytes())
+        .expand(b"digest-unsubscribe", &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    hex::encode(key)
+}
+
+/// Create the unsubscribe token for `user_id`.
+#[must_use]
+pub fn sign(key: &str, user_id: Uuid) -> String {
+    format!(
+        "{user_id}.{}",
+        sign_payload(key, message(user_id).as_bytes())
+    )
+}
+
+/// Verify a token and return the user it unsubscribes.
+#[must_use]
+pub fn verify(key: &str, token: &str) -> Option<Uuid> {
+    let (user_id, signature) = token.split_once('.')?;
+    let user_id = Uuid::parse_str(user_id).ok()?;
+    verify_signature(key, message(user_id).as_bytes(), signature).then_some(user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens_roundtrip_and_reject_tampering() {
+        let user_id = Uuid::new_v4();
+        let token = sign("secret", user_id);
+        assert_eq!(verify("secret", &token), Some(user_id));
+        assert_eq!(verify("other-secret", &token), None);
+
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!("{}.{signature}", Uuid::new_v4());
+        assert_eq!(verify("secret", &forged), None);
+        assert_eq!(verify("secret", "garbage"), None);
+    }
+
+    #[test]
+    fn derived_key_differs_from_jwt_key() {
+        let key = derive_key("jwt-key");
+        assert_ne!(key, "jwt-key");
+        assert_eq!(key, derive_key("jwt-key"));
+        assert_ne!(key, derive_key("other-jwt-key"));
+    }
+}
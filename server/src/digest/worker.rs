@@ -0,0 +1,265 @@
+//! Email Digest Worker
+//!
+//! Periodically picks up users whose digest window has elapsed, collects the
+//! mentions and DMs they missed and mails them. Users with a live connection
+//! are skipped and their window advances anyway: they are around to see it.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use fred::prelude::Client;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::queries;
+use super::render::render;
+use super::types::DueDigest;
+use super::unsubscribe;
+use crate::config::Config;
+use crate::email::EmailService;
+use crate::push::activity::has_live_connection;
+
+/// How often the worker looks for due digests.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of users processed per poll.
+const BATCH_SIZE: i64 = 500;
+
+/// Everything needed to send digest emails.
+#[derive(Clone)]
+pub struct DigestMailer {
+    pub email: EmailService,
+    /// Public base URL, without a trailing slash.
+    pub public_url: String,
+    /// Key used to sign unsubscribe tokens.
+    pub unsubscribe_key: String,
+}
+
+impl DigestMailer {
+    /// Build a mailer, or `None` when `PUBLIC_URL` is not configured.
+    #[must_use]
+    pub fn new(email: EmailService, config: &Config) -> Option<Self> {
+        let public_url = config.public_url.as_deref()?.trim_end_matches('/');
+        Some(Self {
+            email,
+            public_url: public_url.to_string(),
+            unsubscribe_key: unsubscribe::derive_key(&config.jwt_private_key),
+        })
+    }
+
+    /// One-click unsubscribe URL for `user_id`.
+    #[must_use]
+    pub fn unsubscribe_url(&self, user_id: Uuid) -> String {
+        format!(
+            "{}/api/email/unsubscribe?token={}",
+            self.public_url,
+            unsubscribe::sign(&self.unsubscribe_key, user_id)
+        )
+    }
+}
+
+/// Run the digest worker forever.
+pub async fn spawn_digest_worker(db: PgPool, redis: Client, mailer: DigestMailer) {
+    tracing::info!("Email digest worker started");
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        match process_due_digests(&db, &redis, &mailer, Utc::now()).await {
+            Ok(sent) if sent > 0 => tracing::info!(sent, "Sent email digests"),
+            Err(e) => tracing::error!(error = %e, "Failed to process email digests"),
+            _ => {}
+        }
+    }
+}
+
+/// Process every digest due at `now`. Returns how many emails were sent.
+pub async fn process_due_digests(
+    db: &PgPool,
+    redis: &Client,
+    mailer: &DigestMailer,
+    now: DateTime<Utc>,
+) -> sqlx::Result<usize> {
+    let due = queries::due_digests(db, now, BATCH_SIZE).await?;
+    let mut sent = 0;
+    for user in due {
+        let live = has_live_connection(redis, user.user_id)
+            .await
+            .unwrap_or_else(|e| {
+                tracing::warn!(user_id = %user.user_id, error = %e, "Failed to check live connections");
+                false
+            });
+        let result = if live {
+            queries::finish_window(db, user.user_id, now, false)
+                .await
+                .map(|()| false)
+                .map_err(anyhow::Error::from)
+        } else {
+            deliver(db, mailer, &user, now).await
+        };
+        match result {
+            Ok(true) => sent += 1,
+            Ok(false) => {}
+            // The window stays open, so the next poll retries
+            Err(e) => {
+                tracing::warn!(user_id = %user.user_id, error = %e, "Failed to send email digest");
+            }
+        }
+    }
+    Ok(sent)
+}
+
+/// Collect, render and send one user's digest, then close the window.
+///
+/// Returns whether an email was sent; empty digests are skipped.
+async fn deliver(
+    db: &PgPool,
+    mailer: &DigestMailer,
+    user: &DueDigest,
+    now: DateTime<Utc>,
+) -> anyhow::Result<bool> {
+    let digest = queries::collect_digest(db, user.user_id, user.window_start).await?;
+    if digest.is_empty() {
+        queries::finish_window(db, user.user_id, now, false).await?;
+        return Ok(false);
+    }
+
+    let unsubscribe_url = mailer.unsubscribe_url(user.user_id);
+    let rendered = render(
+        &user.display_name,
+        user.frequency,
+        &digest,
+        &mailer.public_url,
+        &unsubscribe_url,
+    );
+    mailer
+        .email
+        .send_digest(
+            &user.email,
+            &rendered.subject,
+            rendered.text,
+            rendered.html,
+            &unsubscribe_url,
+        )
+        .await?;
+
+    queries::finish_window(db, user.user_id, now, true).await?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::mentions::record_mentions;
+    use crate::db::{self, fixtures};
+    use crate::digest::types::DigestFrequency;
+    use crate::email::smtp_sink;
+
+    #[sqlx::test]
+    async fn delivers_missed_mentions_and_dms_to_local_sink(pool: PgPool) {
+        let alice = db::create_user(&pool, "alice", "Alice", None, "hash")
+            .await
+            .expect("create alice");
+        let bob = db::create_user(&pool, "bob", "Bob", Some("bob@example.com"), "hash")
+            .await
+            .expect("create bob");
+        let (guild_id, channel_id) =
+            fixtures::guild_with_channel(&pool, "Digest Guild", alice.id, &[bob.id]).await;
+        let dm_id = fixtures::create_dm(&pool, "alice, bob", &[alice.id, bob.id]).await;
+
+        queries::set_frequency(&pool, bob.id, DigestFrequency::Daily)
+            .await
+            .expect("enable digest");
+        sqlx::query(
+            "UPDATE email_digest_settings SET window_start = NOW() - INTERVAL '2 days' WHERE user_id = $1",
+        )
+        .bind(bob.id)
+        .execute(&pool)
+        .await
+        .expect("backdate window");
+
+        let mention = db::create_message(
+            &pool,
+            channel_id,
+            alice.id,
+            "@bob can you review?",
+            false,
+            None,
+            None,
+        )
+        .await
+        .expect("create mention");
+        record_mentions(&pool, &mention, Some(guild_id))
+            .await
+            .expect("record mentions");
+        db::create_message(
+            &pool,
+            dm_id,
+            alice.id,
+            "SECRET-CIPHERTEXT",
+            true,
+            Some("bm9uY2U="),
+            None,
+        )
+        .await
+        .expect("create encrypted dm");
+
+        let now = Utc::now();
+        let due = queries::due_digests(&pool, now, 10)
+            .await
+            .expect("due digests");
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].user_id, bob.id);
+
+        let (port, mut received) = smtp_sink::spawn().await;
+        let mut config = Config::default_for_test();
+        config.smtp_host = Some("127.0.0.1".into());
+        config.smtp_port = port;
+        config.smtp_username = Some("digest".into());
+        config.smtp_password = Some("digest".into());
+        config.smtp_from = Some("noreply@example.com".into());
+        config.smtp_tls = "none".into();
+        config.public_url = Some("https://chat.example.com/".into());
+        let mailer = DigestMailer::new(EmailService::new(&config).unwrap(), &config)
+            .expect("public url configured");
+
+        assert!(deliver(&pool, &mailer, &due[0], now)
+            .await
+            .expect("deliver"));
+        let raw = received.recv().await.unwrap();
+        assert!(raw.contains("To: bob@example.com"));
+        assert!(raw
+            .contains("List-Unsubscribe: <https://chat.example.com/api/email/unsubscribe?token="));
+        let body = smtp_sink::decode_quoted_printable(&raw);
+        assert!(body.contains("Alice in #general (Digest Guild): @bob can you review?"));
+        assert!(body.contains("alice, bob: 1 new message — latest from Alice (encrypted)"));
+        assert!(!raw.contains("SECRET-CIPHERTEXT"));
+        assert!(!body.contains("SECRET-CIPHERTEXT"));
+
+        // The window is closed, so nothing is due until tomorrow
+        assert!(queries::due_digests(&pool, now, 10)
+            .await
+            .expect("due digests")
+            .is_empty());
+        let (_, last_sent_at) = queries::get_settings(&pool, bob.id)
+            .await
+            .expect("settings")
+            .expect("settings row");
+        assert!(last_sent_at.is_some());
+
+        // Reading the conversation empties the next digest
+        sqlx::query(
+            "INSERT INTO channel_read_state (user_id, channel_id, last_read_at, last_read_message_id) VALUES ($1, $2, NOW(), $3)",
+        )
+        .bind(bob.id)
+        .bind(channel_id)
+        .bind(mention.id)
+        .execute(&pool)
+        .await
+        .expect("mark read");
+        let digest = queries::collect_digest(&pool, bob.id, now - chrono::Duration::days(3))
+            .await
+            .expect("collect");
+        assert_eq!(digest.mention_total, 0);
+        assert_eq!(digest.dms.len(), 1);
+    }
+}
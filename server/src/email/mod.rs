@@ -1,14 +1,19 @@
 //! Email Service
 //!
-//! SMTP-based email delivery for transactional emails (password resets, etc.).
+//! SMTP-based email delivery for transactional emails (password resets, etc.)
+//! and opt-in notification digests.
 
 use anyhow::{Context, Result};
-use lettre::message::Mailbox;
+use lettre::message::header::{HeaderName, HeaderValue};
+use lettre::message::{Mailbox, MultiPart};
 use lettre::transport::smtp::authentication::Credentials;
 use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
 
 use crate::config::Config;
 
+#[cfg(test)]
+pub mod smtp_sink;
+
 /// Email service for sending transactional emails via SMTP.
 #[derive(Clone)]
 pub struct EmailService {
@@ -144,6 +149,45 @@ impl EmailService {
 
         Ok(())
     }
+
+    /// Send a notification digest with plain-text and HTML bodies.
+    ///
+    /// Adds `List-Unsubscribe` and `List-Unsubscribe-Post` headers so mail
+    /// clients can offer one-click unsubscribe (RFC 8058).
+    pub async fn send_digest(
+        &self,
+        to_email: &str,
+        subject: &str,
+        text: String,
+        html: String,
+        unsubscribe_url: &str,
+    ) -> Result<()> {
+        let to_mailbox: Mailbox = to_email
+            .parse()
+            .context("Invalid recipient email address")?;
+
+        let email = Message::builder()
+            .from(self.from_address.clone())
+            .to(to_mailbox)
+            .subject(subject)
+            .raw_header(HeaderValue::new(
+                HeaderName::new_from_ascii_str("List-Unsubscribe"),
+                format!("<{unsubscribe_url}>"),
+            ))
+            .raw_header(HeaderValue::new(
+                HeaderName::new_from_ascii_str("List-Unsubscribe-Post"),
+                "List-Unsubscribe=One-Click".to_string(),
+            ))
+            .multipart(MultiPart::alternative_plain_html(text, html))
+            .context("Failed to build email message")?;
+
+        self.mailer
+            .send(email)
+            .await
+            .context("Failed to send digest email")?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -224,6 +268,36 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_send_digest_to_local_sink() {
+        let (port, mut received) = smtp_sink::spawn().await;
+        let mut config = smtp_test_config();
+        config.smtp_host = Some("127.0.0.1".into());
+        config.smtp_port = port;
+        let service = EmailService::new(&config).unwrap();
+
+        service
+            .send_digest(
+                "bob@example.com",
+                "You were mentioned 2 times",
+                "plain body".into(),
+                "<p>html body</p>".into(),
+                "https://chat.example.com/api/email/unsubscribe?token=abc",
+            )
+            .await
+            .expect("digest should be accepted by the sink");
+
+        let raw = received.recv().await.unwrap();
+        assert!(raw.contains("To: bob@example.com"));
+        assert!(raw.contains(
+            "List-Unsubscribe: <https://chat.example.com/api/email/unsubscribe?token=abc>"
+        ));
+        assert!(raw.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click"));
+        assert!(raw.contains("multipart/alternative"));
+        assert!(raw.contains("plain body"));
+        assert!(raw.contains("<p>html body</p>"));
+    }
+
     #[test]
     fn test_new_invalid_from_address() {
         let mut config = smtp_test_config();
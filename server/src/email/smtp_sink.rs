@@ -0,0 +1,83 @@
+//! Local SMTP Sink (tests only)
+//!
+//! Speaks just enough SMTP for lettre to deliver to it and hands every
+//! received message (raw DATA, dot-unstuffed) to the test.
+
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+
+/// Start a sink on a random loopback port. Returns the port and a receiver of
+/// raw messages.
+pub async fn spawn() -> (u16, mpsc::UnboundedReceiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (tx, rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read).lines();
+                write.write_all(b"220 sink ESMTP\r\n").await.ok()?;
+                while let Some(line) = lines.next_line().await.ok()? {
+                    let command = line.to_ascii_uppercase();
+                    if command.starts_with("EHLO") {
+                        write
+                            .write_all(b"250-sink\r\n250 AUTH PLAIN LOGIN\r\n")
+                            .await
+                            .ok()?;
+                    } else if command.starts_with("AUTH") {
+                        write.write_all(b"235 2.7.0 Accepted\r\n").await.ok()?;
+                    } else if command == "DATA" {
+                        write.write_all(b"354 Go ahead\r\n").await.ok()?;
+                        let mut data = String::new();
+                        while let Some(line) = lines.next_line().await.ok()? {
+                            if line == "." {
+                                break;
+                            }
+                            data.push_str(line.strip_prefix('.').unwrap_or(&line));
+                            data.push('\n');
+                        }
+                        tx.send(data).ok()?;
+                        write.write_all(b"250 2.0.0 Queued\r\n").await.ok()?;
+                    } else if command == "QUIT" {
+                        write.write_all(b"221 Bye\r\n").await.ok()?;
+                        return Some(());
+                    } else {
+                        write.write_all(b"250 OK\r\n").await.ok()?;
+                    }
+                }
+                Some(())
+            });
+        }
+    });
+
+    (port, rx)
+}
+
+/// Decode quoted-printable text (lettre's encoding for non-ASCII bodies).
+pub fn decode_quoted_printable(raw: &str) -> String {
+    let joined = raw.replace("=\n", "");
+    let bytes = joined.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|pair| std::str::from_utf8(pair).ok())
+            .and_then(|pair| u8::from_str_radix(pair, 16).ok());
+        match (bytes[i], hex) {
+            (b'=', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
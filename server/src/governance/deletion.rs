@@ -30,11 +30,17 @@ async fn collect_user_s3_keys(pool: &PgPool, user_id: Uuid) -> anyhow::Result<Us
             .flatten()
             .and_then(|url: String| url.find("avatars/").map(|pos| url[pos..].to_string()));
 
-    // File attachments on the user's messages
+    // File attachments on the user's messages, except objects that forwards
+    // by other users still reference
     let attachment_keys: Vec<String> = sqlx::query_scalar(
-        "SELECT fa.s3_key FROM file_attachments fa
+        "SELECT DISTINCT fa.s3_key FROM file_attachments fa
          JOIN messages m ON m.id = fa.message_id
-         WHERE m.user_id = $1",
+         WHERE m.user_id = $1
+           AND NOT EXISTS (
+               SELECT 1 FROM file_attachments other
+               JOIN messages om ON om.id = other.message_id
+               WHERE other.s3_key = fa.s3_key AND om.user_id IS DISTINCT FROM $1
+           )",
     )
     .bind(user_id)
     .fetch_all(pool)
@@ -20,6 +20,10 @@ use crate::email::EmailService;
 const EXPORT_CAP_MESSAGES: i64 = 500_000;
 /// Maximum number of reactions included in a data export.
 const EXPORT_CAP_REACTIONS: i64 = 500_000;
+/// Maximum number of polls included in a data export.
+const EXPORT_CAP_POLLS: i64 = 100_000;
+/// Maximum number of poll votes included in a data export.
+const EXPORT_CAP_POLL_VOTES: i64 = 500_000;
 /// Maximum number of attachment metadata rows included in a data export.
 const EXPORT_CAP_ATTACHMENTS: i64 = 100_000;
 /// Maximum number of audit log entries included in a data export.
@@ -107,6 +111,30 @@ struct ExportReaction {
     created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Exported poll created by the user, with its current or final tallies.
+#[derive(Serialize, sqlx::FromRow)]
+struct ExportPoll {
+    message_id: Uuid,
+    channel_id: Uuid,
+    question: String,
+    options: Vec<String>,
+    votes: Vec<i64>,
+    allow_multiple: bool,
+    anonymous: bool,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    closed_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Exported poll vote cast by the user.
+#[derive(Serialize, sqlx::FromRow)]
+struct ExportPollVote {
+    message_id: Uuid,
+    question: String,
+    choices: Vec<String>,
+    voted_at: chrono::DateTime<chrono::Utc>,
+}
+
 /// Exported file attachment metadata (no S3 keys).
 #[derive(Serialize, sqlx::FromRow)]
 struct ExportAttachment {
@@ -305,13 +333,14 @@ async fn build_export_archive(
     zip.start_file("profile.json", options)?;
     serde_json::to_writer_pretty(&mut zip, &profile)?;
 
-    // 2. Messages (non-deleted, includes encrypted) — capped
+    // 2. Messages (non-deleted, includes encrypted) — capped. Disappearing
+    // messages are left out: the archive would outlive them.
     let mut truncated_sections: Vec<&'static str> = Vec::new();
 
     let messages: Vec<ExportMessage> = sqlx::query_as(
         "SELECT id, channel_id, content, encrypted, created_at, edited_at
          FROM messages
-         WHERE user_id = $1 AND deleted_at IS NULL
+         WHERE user_id = $1 AND deleted_at IS NULL AND expires_at IS NULL
          ORDER BY created_at ASC
          LIMIT $2",
     )
@@ -459,7 +488,96 @@ async fn build_export_archive(
     serde_json::to_writer_pretty(&mut zip, &reactions)?;
     drop(reactions);
 
-    // 9. Attachments (metadata only, no S3 keys) — capped
+    // 9. Polls the user created — capped
+    let polls: Vec<ExportPoll> = sqlx::query_as(
+        "SELECT
+            p.message_id,
+            m.channel_id,
+            p.question,
+            p.options,
+            COALESCE(p.final_counts, ARRAY(
+                SELECT COUNT(v.user_id)
+                FROM generate_series(0, cardinality(p.options) - 1) AS i
+                LEFT JOIN message_poll_votes v
+                    ON v.message_id = p.message_id AND i::smallint = ANY(v.options)
+                GROUP BY i
+                ORDER BY i
+            )) as votes,
+            p.allow_multiple,
+            p.anonymous,
+            p.expires_at,
+            p.closed_at,
+            p.created_at
+         FROM message_polls p
+         JOIN messages m ON m.id = p.message_id
+         WHERE m.user_id = $1
+         ORDER BY p.created_at ASC
+         LIMIT $2",
+    )
+    .bind(user_id)
+    .bind(EXPORT_CAP_POLLS)
+    .fetch_all(pool)
+    .await?;
+
+    if polls.len() as i64 >= EXPORT_CAP_POLLS {
+        truncated_sections.push("polls");
+        tracing::warn!(
+            section = "polls",
+            rows = polls.len(),
+            user_id = %user_id,
+            "Export section truncated at cap"
+        );
+    } else {
+        tracing::info!(
+            section = "polls",
+            rows = polls.len(),
+            user_id = %user_id,
+            "Export section collected"
+        );
+    }
+    zip.start_file("polls.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, &polls)?;
+    drop(polls);
+
+    // 10. Poll votes — capped
+    let poll_votes: Vec<ExportPollVote> = sqlx::query_as(
+        "SELECT
+            v.message_id,
+            p.question,
+            ARRAY(SELECT p.options[o + 1] FROM unnest(v.options) AS o ORDER BY o) as choices,
+            v.voted_at
+         FROM message_poll_votes v
+         JOIN message_polls p ON p.message_id = v.message_id
+         WHERE v.user_id = $1
+         ORDER BY v.voted_at ASC
+         LIMIT $2",
+    )
+    .bind(user_id)
+    .bind(EXPORT_CAP_POLL_VOTES)
+    .fetch_all(pool)
+    .await?;
+
+    if poll_votes.len() as i64 >= EXPORT_CAP_POLL_VOTES {
+        truncated_sections.push("poll_votes");
+        tracing::warn!(
+            section = "poll_votes",
+            rows = poll_votes.len(),
+            user_id = %user_id,
+            "Export section truncated at cap"
+        );
+    } else {
+        tracing::info!(
+            section = "poll_votes",
+            rows = poll_votes.len(),
+            user_id = %user_id,
+            "Export section collected"
+        );
+    }
+    zip.start_file("poll_votes.json", options)?;
+    serde_json::to_writer_pretty(&mut zip, &poll_votes)?;
+    drop(poll_votes);
+
+    // 11. Attachments (metadata only, no S3 keys) — capped
     let attachments: Vec<ExportAttachment> = sqlx::query_as(
         "SELECT fa.id, fa.filename, fa.mime_type, fa.size_bytes, fa.created_at
          FROM file_attachments fa
@@ -493,7 +611,7 @@ async fn build_export_archive(
     serde_json::to_writer_pretty(&mut zip, &attachments)?;
     drop(attachments);
 
-    // 10. Sessions (bounded by session expiry cleanup — no token_hash)
+    // 12. Sessions (bounded by session expiry cleanup — no token_hash)
     let sessions: Vec<ExportSession> = sqlx::query_as(
         "SELECT id, host(ip_address) as ip_address, user_agent, created_at, expires_at
          FROM sessions
@@ -507,7 +625,7 @@ async fn build_export_archive(
     zip.start_file("sessions.json", options)?;
     serde_json::to_writer_pretty(&mut zip, &sessions)?;
 
-    // 11. Devices (bounded by max_devices_per_user config — no raw key material)
+    // 13. Devices (bounded by max_devices_per_user config — no raw key material)
     let devices: Vec<ExportDevice> = sqlx::query_as(
         "SELECT id, device_name, created_at, last_seen_at, is_verified
          FROM user_devices
@@ -521,7 +639,7 @@ async fn build_export_archive(
     zip.start_file("devices.json", options)?;
     serde_json::to_writer_pretty(&mut zip, &devices)?;
 
-    // 12. Key backup metadata (bounded by max_devices_per_user — no encrypted data)
+    // 14. Key backup metadata (bounded by max_devices_per_user — no encrypted data)
     let key_backups: Vec<ExportKeyBackup> = sqlx::query_as(
         "SELECT version, created_at
          FROM key_backups
@@ -535,7 +653,7 @@ async fn build_export_archive(
     zip.start_file("key_backups.json", options)?;
     serde_json::to_writer_pretty(&mut zip, &key_backups)?;
 
-    // 13. Audit log — capped
+    // 15. Audit log — capped
     let audit_log: Vec<ExportAuditLogEntry> = sqlx::query_as(
         "SELECT action, target_type, target_id, details,
                 host(ip_address) as ip_address, created_at
@@ -571,7 +689,7 @@ async fn build_export_archive(
 
     // Manifest
     let manifest = ExportManifest {
-        version: "1.1",
+        version: "1.2",
         exported_at: Utc::now().to_rfc3339(),
         user_id: user_id.to_string(),
         sections: vec![
@@ -583,6 +701,8 @@ async fn build_export_archive(
             "direct_messages",
             "blocked_users",
             "reactions",
+            "polls",
+            "poll_votes",
             "attachments",
             "sessions",
             "devices",
@@ -0,0 +1,187 @@
+//! Slash Command Permission Handlers
+//!
+//! Lets guild admins disable installed bots' commands or restrict them to
+//! specific roles and channels. Enforcement happens in
+//! [`crate::permissions::can_use_command`].
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use uuid::Uuid;
+
+use super::handlers::GuildError;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::permissions::{
+    require_guild_permission, CommandPermissionOverride, GuildPermissions, PermissionError,
+    SetCommandPermissionsRequest,
+};
+
+/// Maximum number of roles or channels in a single command override.
+const MAX_OVERRIDE_TARGETS: usize = 100;
+
+async fn require_manage_guild(
+    state: &AppState,
+    guild_id: Uuid,
+    user_id: Uuid,
+) -> Result<(), GuildError> {
+    require_guild_permission(&state.db, guild_id, user_id, GuildPermissions::MANAGE_GUILD)
+        .await
+        .map_err(|e| match e {
+            PermissionError::NotGuildMember => GuildError::Forbidden,
+            other => GuildError::Permission(other),
+        })?;
+    Ok(())
+}
+
+/// List command permission overrides for a guild.
+///
+/// `GET /api/guilds/:guild_id/commands/permissions`
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{id}/commands/permissions",
+    tag = "guilds",
+    params(("id" = Uuid, Path, description = "Guild ID")),
+    responses((status = 200, body = Vec<CommandPermissionOverride>)),
+    security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state))]
+pub async fn list_command_permissions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<Uuid>,
+) -> Result<Json<Vec<CommandPermissionOverride>>, GuildError> {
+    require_manage_guild(&state, guild_id, auth.id).await?;
+
+    let overrides =
+        crate::permissions::get_command_permission_overrides(&state.db, guild_id).await?;
+    Ok(Json(overrides))
+}
+
+/// Set the permission override for a bot command in a guild.
+///
+/// `PUT /api/guilds/:guild_id/commands/:application_id/:name/permissions`
+#[utoipa::path(
+    put,
+    path = "/api/guilds/{id}/commands/{application_id}/{name}/permissions",
+    tag = "guilds",
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("application_id" = Uuid, Path, description = "Application ID"),
+        ("name" = String, Path, description = "Command name"),
+    ),
+    request_body = SetCommandPermissionsRequest,
+    responses((status = 200, body = CommandPermissionOverride)),
+    security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, body))]
+pub async fn set_command_permissions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, application_id, name)): Path<(Uuid, Uuid, String)>,
+    Json(mut body): Json<SetCommandPermissionsRequest>,
+) -> Result<Json<CommandPermissionOverride>, GuildError> {
+    require_manage_guild(&state, guild_id, auth.id).await?;
+
+    body.role_ids.sort_unstable();
+    body.role_ids.dedup();
+    body.channel_ids.sort_unstable();
+    body.channel_ids.dedup();
+    if body.role_ids.len() > MAX_OVERRIDE_TARGETS || body.channel_ids.len() > MAX_OVERRIDE_TARGETS {
+        return Err(GuildError::Validation(format!(
+            "At most {MAX_OVERRIDE_TARGETS} roles and {MAX_OVERRIDE_TARGETS} channels are allowed"
+        )));
+    }
+
+    // The command must be available in this guild
+    let command_exists: bool = sqlx::query_scalar(
+        r"SELECT EXISTS(
+               SELECT 1 FROM slash_commands sc
+               INNER JOIN guild_bot_installations gbi ON gbi.application_id = sc.application_id
+               WHERE gbi.guild_id = $1 AND sc.application_id = $2 AND sc.name = $3
+                 AND (sc.guild_id = $1 OR sc.guild_id IS NULL)
+           )",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .bind(&name)
+    .fetch_one(&state.db)
+    .await?;
+    if !command_exists {
+        return Err(GuildError::Validation(
+            "Command is not available in this guild".to_string(),
+        ));
+    }
+
+    let valid_roles: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM guild_roles WHERE guild_id = $1 AND id = ANY($2)")
+            .bind(guild_id)
+            .bind(&body.role_ids)
+            .fetch_one(&state.db)
+            .await?;
+    if usize::try_from(valid_roles).unwrap_or(0) != body.role_ids.len() {
+        return Err(GuildError::Validation(
+            "All roles must belong to this guild".to_string(),
+        ));
+    }
+
+    let valid_channels: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM channels WHERE guild_id = $1 AND id = ANY($2)")
+            .bind(guild_id)
+            .bind(&body.channel_ids)
+            .fetch_one(&state.db)
+            .await?;
+    if usize::try_from(valid_channels).unwrap_or(0) != body.channel_ids.len() {
+        return Err(GuildError::Validation(
+            "All channels must belong to this guild".to_string(),
+        ));
+    }
+
+    let saved = crate::permissions::set_command_permission_override(
+        &state.db,
+        guild_id,
+        application_id,
+        &name,
+        body.enabled,
+        &body.role_ids,
+        &body.channel_ids,
+        auth.id,
+    )
+    .await?;
+
+    Ok(Json(saved))
+}
+
+/// Remove the permission override for a bot command, restoring its defaults.
+///
+/// `DELETE /api/guilds/:guild_id/commands/:application_id/:name/permissions`
+#[utoipa::path(
+    delete,
+    path = "/api/guilds/{id}/commands/{application_id}/{name}/permissions",
+    tag = "guilds",
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("application_id" = Uuid, Path, description = "Application ID"),
+        ("name" = String, Path, description = "Command name"),
+    ),
+    responses((status = 204, description = "Override removed (or none existed)")),
+    security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state))]
+pub async fn delete_command_permissions(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, application_id, name)): Path<(Uuid, Uuid, String)>,
+) -> Result<StatusCode, GuildError> {
+    require_manage_guild(&state, guild_id, auth.id).await?;
+
+    crate::permissions::delete_command_permission_override(
+        &state.db,
+        guild_id,
+        application_id,
+        &name,
+    )
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
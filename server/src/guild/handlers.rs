@@ -1,20 +1,21 @@
 //! Guild Management Handlers
 
-use axum::extract::{Multipart, Path, State};
+use axum::extract::{Multipart, Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use sqlx::QueryBuilder;
 use uuid::Uuid;
 use validator::Validate;
-use chrono::Utc;
 
 use crate::util::format_file_size;
 
 use super::limits;
 use super::types::{
-    CreateGuildRequest, Guild, GuildCommandInfo, GuildMember, GuildSettings, GuildWithMemberCount,
+    CommandAutocompleteRequest, CommandAutocompleteResponse, CreateGuildRequest, Guild,
+    GuildCommandInfo, GuildMember, GuildSettings, GuildWithMemberCount, ListGuildCommandsQuery,
     UpdateGuildRequest, UpdateGuildSettingsRequest,
 };
 use crate::api::AppState;
@@ -22,6 +23,7 @@ use crate::auth::AuthUser;
 use crate::db::{self, ChannelType};
 use crate::discovery::types::TAG_REGEX;
 use crate::permissions::{require_guild_permission, GuildPermissions, PermissionError};
+use crate::webhooks::events::GatewayIntent;
 use crate::ws::{broadcast_to_user, ServerEvent};
 
 // ============================================================================
@@ -45,6 +47,14 @@ pub struct InstalledBot {
     pub bot_user_id: Uuid,
     pub name: String,
     pub description: Option<String>,
+    /// Privileged gateway intents declared by the bot.
+    pub requested_intents: Vec<String>,
+    /// Privileged gateway intents approved for this guild.
+    pub approved_intents: Vec<String>,
+    /// REST API scopes granted to the bot's application.
+    pub requested_scopes: Vec<String>,
+    /// REST API scopes approved for this guild.
+    pub approved_scopes: Vec<String>,
     pub installed_by: Uuid,
     pub installed_at: chrono::DateTime<chrono::Utc>,
 }
@@ -53,6 +63,20 @@ pub struct InstalledBot {
 // Request Types
 // ============================================================================
 
+/// Privileged gateway intents to approve for an installed bot.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct ApproveBotIntentsRequest {
+    /// Intent names (`message_content`, `presence`); replaces the current approvals.
+    pub intents: Vec<String>,
+}
+
+/// REST API scopes to approve for an installed bot.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct ApproveBotScopesRequest {
+    /// Scope names (e.g. `roles.write`); replaces the current approvals.
+    pub scopes: Vec<String>,
+}
+
 /// Position specification for a channel in reorder request.
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct ChannelPosition {
@@ -901,7 +925,9 @@ pub async fn add_bot_to_guild(
     }
 
     sqlx::query(
-        "INSERT INTO guild_bot_installations (guild_id, application_id, installed_by) VALUES ($1, $2, $3) ON CONFLICT (guild_id, application_id) DO NOTHING",
+        r"INSERT INTO guild_bot_installations (guild_id, application_id, installed_by, approved_scopes)
+           SELECT $1, id, $3, scopes FROM bot_applications WHERE id = $2
+           ON CONFLICT (guild_id, application_id) DO NOTHING",
     )
     .bind(guild_id)
     .bind(application_id)
@@ -943,6 +969,12 @@ pub async fn list_guild_bots(
             ba.bot_user_id,
             ba.name,
             ba.description,
+            ARRAY(
+                SELECT i FROM unnest(ba.gateway_intents) AS i WHERE i = ANY($2)
+            ) AS requested_intents,
+            gbi.approved_intents,
+            ba.scopes AS requested_scopes,
+            gbi.approved_scopes,
             gbi.installed_by,
             gbi.installed_at
            FROM guild_bot_installations gbi
@@ -951,12 +983,202 @@ pub async fn list_guild_bots(
            ORDER BY gbi.installed_at",
     )
     .bind(guild_id)
+    .bind(GatewayIntent::PRIVILEGED)
     .fetch_all(&state.db)
     .await?;
 
     Ok(Json(bots))
 }
 
+/// Approve privileged gateway intents for a bot installed in a guild.
+///
+/// Only intents the bot has declared can be approved. Approving an empty list
+/// revokes all privileged intents.
+///
+/// `PUT /api/guilds/:guild_id/bots/:bot_id/intents`
+#[utoipa::path(
+    put,
+    path = "/api/guilds/{id}/bots/{bot_id}/intents",
+    tag = "guilds",
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("bot_id" = Uuid, Path, description = "Bot user ID")
+    ),
+    request_body = ApproveBotIntentsRequest,
+    responses((status = 200, body = InstalledBot)),
+    security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, body))]
+pub async fn approve_bot_intents(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, bot_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<ApproveBotIntentsRequest>,
+) -> Result<Json<InstalledBot>, GuildError> {
+    let _ctx =
+        require_guild_permission(&state.db, guild_id, auth.id, GuildPermissions::MANAGE_GUILD)
+            .await
+            .map_err(|e| match e {
+                PermissionError::NotGuildMember => GuildError::Forbidden,
+                other => GuildError::Permission(other),
+            })?;
+
+    let app: Option<(Uuid, Vec<String>)> = sqlx::query_as(
+        r"SELECT ba.id, ba.gateway_intents
+           FROM bot_applications ba
+           INNER JOIN guild_bot_installations gbi ON gbi.application_id = ba.id
+           WHERE ba.bot_user_id = $1 AND gbi.guild_id = $2",
+    )
+    .bind(bot_id)
+    .bind(guild_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let (application_id, declared) = app.ok_or(GuildError::NotFound)?;
+
+    let mut intents: Vec<String> = Vec::with_capacity(body.intents.len());
+    for intent in body.intents {
+        if !GatewayIntent::PRIVILEGED.contains(&intent.as_str()) {
+            return Err(GuildError::Validation(format!(
+                "'{intent}' is not a privileged intent. Privileged intents: {}",
+                GatewayIntent::PRIVILEGED.join(", ")
+            )));
+        }
+        if !declared.contains(&intent) {
+            return Err(GuildError::Validation(format!(
+                "Bot has not requested the '{intent}' intent"
+            )));
+        }
+        if !intents.contains(&intent) {
+            intents.push(intent);
+        }
+    }
+
+    let bot = sqlx::query_as::<_, InstalledBot>(
+        r"WITH updated AS (
+            UPDATE guild_bot_installations
+            SET approved_intents = $3
+            WHERE guild_id = $1 AND application_id = $2
+            RETURNING application_id, approved_intents, approved_scopes, installed_by, installed_at
+          )
+          SELECT
+            u.application_id,
+            ba.bot_user_id,
+            ba.name,
+            ba.description,
+            ARRAY(
+                SELECT i FROM unnest(ba.gateway_intents) AS i WHERE i = ANY($4)
+            ) AS requested_intents,
+            u.approved_intents,
+            ba.scopes AS requested_scopes,
+            u.approved_scopes,
+            u.installed_by,
+            u.installed_at
+          FROM updated u
+          INNER JOIN bot_applications ba ON u.application_id = ba.id",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .bind(&intents)
+    .bind(GatewayIntent::PRIVILEGED)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(GuildError::NotFound)?;
+
+    Ok(Json(bot))
+}
+
+/// Approve REST API scopes for a bot installed in a guild.
+///
+/// Installing a bot approves the scopes it has at that time; scopes its
+/// application gains later only apply here once approved. Only scopes granted
+/// to the application can be approved.
+///
+/// `PUT /api/guilds/:guild_id/bots/:bot_id/scopes`
+#[utoipa::path(
+    put,
+    path = "/api/guilds/{id}/bots/{bot_id}/scopes",
+    tag = "guilds",
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("bot_id" = Uuid, Path, description = "Bot user ID")
+    ),
+    request_body = ApproveBotScopesRequest,
+    responses((status = 200, body = InstalledBot)),
+    security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, body))]
+pub async fn approve_bot_scopes(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path((guild_id, bot_id)): Path<(Uuid, Uuid)>,
+    Json(body): Json<ApproveBotScopesRequest>,
+) -> Result<Json<InstalledBot>, GuildError> {
+    let _ctx =
+        require_guild_permission(&state.db, guild_id, auth.id, GuildPermissions::MANAGE_GUILD)
+            .await
+            .map_err(|e| match e {
+                PermissionError::NotGuildMember => GuildError::Forbidden,
+                other => GuildError::Permission(other),
+            })?;
+
+    let app: Option<(Uuid, Vec<String>)> = sqlx::query_as(
+        r"SELECT ba.id, ba.scopes
+           FROM bot_applications ba
+           INNER JOIN guild_bot_installations gbi ON gbi.application_id = ba.id
+           WHERE ba.bot_user_id = $1 AND gbi.guild_id = $2",
+    )
+    .bind(bot_id)
+    .bind(guild_id)
+    .fetch_optional(&state.db)
+    .await?;
+    let (application_id, granted) = app.ok_or(GuildError::NotFound)?;
+
+    let mut scopes: Vec<String> = Vec::with_capacity(body.scopes.len());
+    for scope in body.scopes {
+        if !granted.contains(&scope) {
+            return Err(GuildError::Validation(format!(
+                "Bot has not requested the '{scope}' scope"
+            )));
+        }
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    let bot = sqlx::query_as::<_, InstalledBot>(
+        r"WITH updated AS (
+            UPDATE guild_bot_installations
+            SET approved_scopes = $3
+            WHERE guild_id = $1 AND application_id = $2
+            RETURNING application_id, approved_intents, approved_scopes, installed_by, installed_at
+          )
+          SELECT
+            u.application_id,
+            ba.bot_user_id,
+            ba.name,
+            ba.description,
+            ARRAY(
+                SELECT i FROM unnest(ba.gateway_intents) AS i WHERE i = ANY($4)
+            ) AS requested_intents,
+            u.approved_intents,
+            ba.scopes AS requested_scopes,
+            u.approved_scopes,
+            u.installed_by,
+            u.installed_at
+          FROM updated u
+          INNER JOIN bot_applications ba ON u.application_id = ba.id",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .bind(&scopes)
+    .bind(GatewayIntent::PRIVILEGED)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(GuildError::NotFound)?;
+
+    Ok(Json(bot))
+}
+
 /// Remove a bot from a guild.
 ///
 /// `DELETE /api/guilds/:guild_id/bots/:bot_id`
@@ -1010,6 +1232,13 @@ pub async fn remove_bot_from_guild(
         return Err(GuildError::NotFound);
     }
 
+    // Roles assigned to the bot only apply while it is installed
+    sqlx::query("DELETE FROM guild_member_roles WHERE guild_id = $1 AND user_id = $2")
+        .bind(guild_id)
+        .bind(bot_id)
+        .execute(&state.db)
+        .await?;
+
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -1017,13 +1246,19 @@ pub async fn remove_bot_from_guild(
 ///
 /// Returns both guild-scoped and global commands from all installed bots.
 /// Guild-scoped commands take precedence over global commands with the same name.
+/// Commands the member cannot use (disabled, restricted to other roles, or
+/// missing default member permissions) are omitted. With `channel_id`, channel
+/// permissions and channel restrictions are applied as well.
 ///
 /// `GET /api/guilds/:guild_id/commands`
 #[utoipa::path(
     get,
     path = "/api/guilds/{id}/commands",
     tag = "guilds",
-    params(("id" = Uuid, Path, description = "Guild ID")),
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("channel_id" = Option<Uuid>, Query, description = "Only list commands usable in this channel"),
+    ),
     responses((status = 200, body = Vec<GuildCommandInfo>)),
     security(("bearer_auth" = []))
 )]
@@ -1032,16 +1267,37 @@ pub async fn list_guild_commands(
     State(state): State<AppState>,
     auth: AuthUser,
     Path(guild_id): Path<Uuid>,
+    Query(query): Query<ListGuildCommandsQuery>,
 ) -> Result<Json<Vec<GuildCommandInfo>>, GuildError> {
-    // Verify membership
-    let is_member = db::is_guild_member(&state.db, guild_id, auth.id).await?;
-    if !is_member {
-        return Err(GuildError::Forbidden);
+    #[derive(sqlx::FromRow)]
+    struct CommandRow {
+        name: String,
+        description: String,
+        bot_name: String,
+        application_id: Uuid,
+        options: Option<serde_json::Value>,
+        default_member_permissions: Option<i64>,
     }
 
+    // Verify membership (and channel access when listing for a channel)
+    let ctx = if let Some(channel_id) = query.channel_id {
+        let channel = db::find_channel_by_id(&state.db, channel_id)
+            .await?
+            .filter(|c| c.guild_id == Some(guild_id))
+            .ok_or_else(|| GuildError::Validation("Channel not found in this guild".to_string()))?;
+        crate::permissions::require_channel_access(&state.db, auth.id, channel.id)
+            .await
+            .map_err(|_| GuildError::Forbidden)?
+    } else {
+        crate::permissions::get_member_permission_context(&state.db, guild_id, auth.id)
+            .await?
+            .ok_or(GuildError::Forbidden)?
+    };
+
     // Return all commands from installed bots (no DISTINCT ON).
-    let rows: Vec<(String, String, String, Uuid)> = sqlx::query_as(
-        r"SELECT sc.name, sc.description, ba.name as bot_name, ba.id as application_id
+    let rows: Vec<CommandRow> = sqlx::query_as(
+        r"SELECT sc.name, sc.description, ba.name as bot_name, ba.id as application_id,
+                  sc.options, sc.default_member_permissions
            FROM slash_commands sc
            INNER JOIN bot_applications ba ON sc.application_id = ba.id
            INNER JOIN guild_bot_installations gbi ON ba.id = gbi.application_id
@@ -1052,23 +1308,46 @@ pub async fn list_guild_commands(
     .fetch_all(&state.db)
     .await?;
 
+    let overrides =
+        crate::permissions::get_command_permission_overrides(&state.db, guild_id).await?;
+    let rows: Vec<CommandRow> = rows
+        .into_iter()
+        .filter(|row| {
+            let guild_override = overrides.iter().find(|o| {
+                (o.application_id, o.command_name.as_str())
+                    == (row.application_id, row.name.as_str())
+            });
+            ctx.can_use_command(
+                query.channel_id,
+                row.default_member_permissions
+                    .map(GuildPermissions::from_db),
+                guild_override,
+            )
+            .is_ok()
+        })
+        .collect();
+
     // Compute ambiguity: count how many distinct apps provide each command name.
     let mut name_counts: std::collections::HashMap<String, usize> =
         std::collections::HashMap::new();
-    for (name, _, _, _) in &rows {
-        *name_counts.entry(name.clone()).or_insert(0) += 1;
+    for row in &rows {
+        *name_counts.entry(row.name.clone()).or_insert(0) += 1;
     }
 
     let result: Vec<GuildCommandInfo> = rows
         .into_iter()
-        .map(|(name, description, bot_name, application_id)| {
-            let is_ambiguous = name_counts.get(&name).copied().unwrap_or(0) > 1;
+        .map(|row| {
+            let is_ambiguous = name_counts.get(&row.name).copied().unwrap_or(0) > 1;
             GuildCommandInfo {
-                name,
-                description,
-                bot_name,
-                application_id,
+                name: row.name,
+                description: row.description,
+                bot_name: row.bot_name,
+                application_id: row.application_id,
                 is_ambiguous,
+                options: row
+                    .options
+                    .and_then(|v| serde_json::from_value(v).ok())
+                    .unwrap_or_default(),
             }
         })
         .collect();
@@ -1076,6 +1355,163 @@ pub async fn list_guild_commands(
     Ok(Json(result))
 }
 
+/// How long an autocomplete request waits for the bot to answer.
+const AUTOCOMPLETE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(2500);
+
+/// Lifetime of autocomplete interaction keys in Redis.
+const AUTOCOMPLETE_TTL_SECS: i64 = 10;
+
+/// Request autocomplete suggestions for a command option.
+///
+/// Forwards the partial input to the bot over its gateway and waits briefly for
+/// an `autocomplete_response`. Returns an empty list when the bot is offline or
+/// too slow, so clients can keep typing without an error.
+///
+/// `POST /api/guilds/:guild_id/commands/autocomplete`
+#[utoipa::path(
+    post,
+    path = "/api/guilds/{id}/commands/autocomplete",
+    tag = "guilds",
+    params(("id" = Uuid, Path, description = "Guild ID")),
+    request_body = CommandAutocompleteRequest,
+    responses((status = 200, body = CommandAutocompleteResponse)),
+    security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, body))]
+pub async fn command_autocomplete(
+    State(state): State<AppState>,
+    auth: AuthUser,
+    Path(guild_id): Path<Uuid>,
+    Json(body): Json<CommandAutocompleteRequest>,
+) -> Result<Json<CommandAutocompleteResponse>, GuildError> {
+    use fred::interfaces::{KeysInterface, PubsubInterface};
+
+    use crate::api::commands::{select_subcommand, validate_choice, CommandOption, MAX_CHOICES};
+
+    let channel = db::find_channel_by_id(&state.db, body.channel_id)
+        .await?
+        .filter(|c| c.guild_id == Some(guild_id))
+        .ok_or_else(|| GuildError::Validation("Channel not found in this guild".to_string()))?;
+
+    let command: Option<(Uuid, Option<serde_json::Value>, Option<i64>)> = sqlx::query_as(
+        r"SELECT ba.bot_user_id, sc.options, sc.default_member_permissions
+           FROM slash_commands sc
+           INNER JOIN bot_applications ba ON sc.application_id = ba.id
+           INNER JOIN guild_bot_installations gbi ON ba.id = gbi.application_id
+           WHERE gbi.guild_id = $1 AND sc.application_id = $2 AND sc.name = $3
+             AND (sc.guild_id = $1 OR sc.guild_id IS NULL)
+             AND ba.bot_user_id IS NOT NULL
+           ORDER BY (sc.guild_id IS NOT NULL) DESC
+           LIMIT 1",
+    )
+    .bind(guild_id)
+    .bind(body.application_id)
+    .bind(&body.command_name)
+    .fetch_optional(&state.db)
+    .await?;
+    let (bot_user_id, options, default_member_permissions) =
+        command.ok_or_else(|| GuildError::Validation("Unknown command".to_string()))?;
+
+    crate::permissions::require_command_permission(
+        &state.db,
+        guild_id,
+        auth.id,
+        channel.id,
+        body.application_id,
+        &body.command_name,
+        default_member_permissions.map(GuildPermissions::from_db),
+    )
+    .await
+    .map_err(|e| match e {
+        PermissionError::DatabaseError(msg) => GuildError::Internal(msg),
+        other => GuildError::Permission(other),
+    })?;
+
+    let options: Vec<CommandOption> = options
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    let focused = select_subcommand(
+        &options,
+        body.subcommand_group.as_deref(),
+        body.subcommand.as_deref(),
+    )
+    .ok_or_else(|| GuildError::Validation("Unknown subcommand".to_string()))?
+    .iter()
+    .find(|o| o.name == body.focused && o.autocomplete)
+    .ok_or_else(|| GuildError::Validation("Option does not support autocomplete".to_string()))?;
+    let focused_type = focused.option_type;
+
+    let interaction_id = Uuid::new_v4();
+    let event = crate::ws::bot_gateway::BotServerEvent::Autocomplete {
+        interaction_id,
+        command_name: body.command_name,
+        subcommand_group: body.subcommand_group,
+        subcommand: body.subcommand,
+        guild_id,
+        channel_id: channel.id,
+        user_id: auth.id,
+        focused: body.focused,
+        value: body.value,
+        options: serde_json::Value::Object(body.options),
+    };
+    let payload = serde_json::to_string(&event)
+        .map_err(|e| GuildError::Internal(format!("Failed to serialize autocomplete: {e}")))?;
+
+    let routing_unavailable = |e: fred::error::Error| {
+        tracing::warn!(error = %e, "Failed to route autocomplete interaction");
+        GuildError::Internal("Bot interaction routing unavailable".to_string())
+    };
+    let expiration = || Some(fred::types::Expiration::EX(AUTOCOMPLETE_TTL_SECS));
+    state
+        .redis
+        .set::<(), _, _>(
+            format!("interaction:{interaction_id}:owner"),
+            bot_user_id.to_string(),
+            expiration(),
+            None,
+            false,
+        )
+        .await
+        .map_err(routing_unavailable)?;
+    state
+        .redis
+        .set::<(), _, _>(
+            format!("interaction:{interaction_id}:context"),
+            serde_json::json!({
+                "kind": "autocomplete",
+                "user_id": auth.id,
+                "channel_id": channel.id,
+                "guild_id": guild_id,
+            })
+            .to_string(),
+            expiration(),
+            None,
+            false,
+        )
+        .await
+        .map_err(routing_unavailable)?;
+    let pending = crate::ws::interactions::register(&state.redis, interaction_id).await;
+    state
+        .redis
+        .publish::<(), _, _>(format!("bot:{bot_user_id}"), payload)
+        .await
+        .map_err(routing_unavailable)?;
+
+    let response = pending.wait(&state.redis, AUTOCOMPLETE_TIMEOUT).await;
+
+    // Drop suggestions whose value does not fit the option type
+    let choices = response
+        .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+        .and_then(|v| serde_json::from_value::<Vec<_>>(v["choices"].clone()).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| validate_choice(c, focused_type).is_ok())
+        .take(MAX_CHOICES)
+        .collect();
+
+    Ok(Json(CommandAutocompleteResponse { choices }))
+}
+
 /// Mark all text channels in a guild as read.
 /// POST /api/guilds/{id}/read-all
 #[utoipa::path(
@@ -1369,6 +1805,7 @@ pub struct GuildUsageStats {
     pub channels: UsageStat,
     pub roles: UsageStat,
     pub emojis: UsageStat,
+    pub stickers: UsageStat,
     pub bots: UsageStat,
     pub pages: UsageStat,
 }
@@ -1403,11 +1840,12 @@ pub async fn get_guild_usage(
         .ok_or(GuildError::NotFound)?;
 
     // Run count queries in parallel
-    let (members, channels, roles, emojis, bots, pages, page_limit) = tokio::join!(
+    let (members, channels, roles, emojis, stickers, bots, pages, page_limit) = tokio::join!(
         limits::get_member_count(&state.db, guild_id),
         limits::count_guild_channels(&state.db, guild_id),
         limits::count_guild_roles(&state.db, guild_id),
         limits::count_guild_emojis(&state.db, guild_id),
+        limits::count_guild_stickers(&state.db, guild_id),
         limits::count_guild_bots(&state.db, guild_id),
         crate::pages::count_pages(&state.db, Some(guild_id)),
         crate::pages::get_effective_page_limit(
@@ -1436,6 +1874,10 @@ pub async fn get_guild_usage(
             current: emojis?,
             limit: state.config.max_emojis_per_guild,
         },
+        stickers: UsageStat {
+            current: stickers?,
+            limit: state.config.max_stickers_per_guild,
+        },
         bots: UsageStat {
             current: bots?,
             limit: state.config.max_bots_per_guild,
@@ -1470,17 +1912,13 @@ pub async fn upload_guild_banner(
     mut multipart: Multipart,
 ) -> Result<Json<Guild>, GuildError> {
     // Check permission
-    let _ctx = require_guild_permission(
-        &state.db,
-        guild_id,
-        auth.id,
-        GuildPermissions::MANAGE_GUILD,
-    )
-    .await
-    .map_err(|e| match e {
-        PermissionError::NotGuildMember => GuildError::Forbidden,
-        other => GuildError::Permission(other),
-    })?;
+    let _ctx =
+        require_guild_permission(&state.db, guild_id, auth.id, GuildPermissions::MANAGE_GUILD)
+            .await
+            .map_err(|e| match e {
+                PermissionError::NotGuildMember => GuildError::Forbidden,
+                other => GuildError::Permission(other),
+            })?;
 
     // Check if S3 is configured
     let s3 = state
@@ -1512,7 +1950,9 @@ pub async fn upload_guild_banner(
         }
     }
 
-    let data = file_data.ok_or(GuildError::Validation("No banner file provided".to_string()))?;
+    let data = file_data.ok_or(GuildError::Validation(
+        "No banner file provided".to_string(),
+    ))?;
 
     // Validate file size (using 5MB limit for banners)
     let max_size = 5 * 1024 * 1024;
@@ -50,6 +50,16 @@ pub async fn count_guild_emojis(pool: &PgPool, guild_id: Uuid) -> Result<i64, sq
     Ok(count)
 }
 
+/// Count stickers in a guild.
+pub async fn count_guild_stickers(pool: &PgPool, guild_id: Uuid) -> Result<i64, sqlx::Error> {
+    let (count,): (i64,) =
+        sqlx::query_as("SELECT COUNT(*) FROM guild_stickers WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_one(pool)
+            .await?;
+    Ok(count)
+}
+
 /// Count bot installations in a guild.
 pub async fn count_guild_bots(pool: &PgPool, guild_id: Uuid) -> Result<i64, sqlx::Error> {
     let (count,): (i64,) =
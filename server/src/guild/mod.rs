@@ -3,15 +3,17 @@
 //! Handles guild creation, membership, invites, roles, categories, search, and management.
 
 pub mod categories;
+pub mod command_permissions;
 pub mod emojis;
 pub mod handlers;
 pub mod invites;
 pub mod limits;
 pub mod roles;
 pub mod search;
+pub mod stickers;
 pub mod types;
 
-use axum::routing::{delete, get, patch, post};
+use axum::routing::{delete, get, patch, post, put};
 use axum::Router;
 
 use crate::api::AppState;
@@ -36,11 +38,28 @@ pub fn router() -> Router<AppState> {
             "/{id}/bots/{bot_id}",
             delete(handlers::remove_bot_from_guild),
         )
+        .route(
+            "/{id}/bots/{bot_id}/intents",
+            put(handlers::approve_bot_intents),
+        )
+        .route(
+            "/{id}/bots/{bot_id}/scopes",
+            put(handlers::approve_bot_scopes),
+        )
         .route("/{id}/usage", get(handlers::get_guild_usage))
         .route("/{id}/channels", get(handlers::list_channels))
         .route("/{id}/channels/reorder", post(handlers::reorder_channels))
         .route("/{id}/read-all", post(handlers::mark_all_channels_read))
         .route("/{id}/commands", get(handlers::list_guild_commands))
+        .route(
+            "/{id}/commands/permissions",
+            get(command_permissions::list_command_permissions),
+        )
+        .route(
+            "/{id}/commands/{application_id}/{name}/permissions",
+            put(command_permissions::set_command_permissions)
+                .delete(command_permissions::delete_command_permissions),
+        )
         // Guild settings
         .route(
             "/{id}/settings",
@@ -51,10 +70,7 @@ pub fn router() -> Router<AppState> {
             post(handlers::dismiss_discovery_prompt),
         )
         // Banner upload
-        .route(
-            "/{id}/banner",
-            post(handlers::upload_guild_banner),
-        )
+        .route("/{id}/banner", post(handlers::upload_guild_banner))
         // Role routes
         .route(
             "/{id}/roles",
@@ -96,6 +112,8 @@ pub fn router() -> Router<AppState> {
         )
         // Emoji routes
         .nest("/{id}/emojis", emojis::router())
+        // Sticker routes
+        .nest("/{id}/stickers", stickers::router())
 }
 
 /// Create the invite join router (separate for public access pattern)
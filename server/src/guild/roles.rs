@@ -14,6 +14,7 @@ use crate::auth::AuthUser;
 use crate::permissions::{
     can_manage_role, require_guild_permission, GuildPermissions, PermissionError,
 };
+use crate::ws::bot_gateway::BotServerEvent;
 
 // ============================================================================
 // Error Type
@@ -142,11 +143,12 @@ pub async fn list_roles(
             i64,
             i32,
             bool,
+            bool,
             chrono::DateTime<chrono::Utc>,
         ),
     >(
         r"
-        SELECT id, guild_id, name, color, permissions, position, is_default, created_at
+        SELECT id, guild_id, name, color, permissions, position, is_default, mentionable, created_at
         FROM guild_roles
         WHERE guild_id = $1
         ORDER BY position ASC
@@ -159,7 +161,17 @@ pub async fn list_roles(
     let response: Vec<RoleResponse> = roles
         .into_iter()
         .map(
-            |(id, guild_id, name, color, permissions, position, is_default, created_at)| {
+            |(
+                id,
+                guild_id,
+                name,
+                color,
+                permissions,
+                position,
+                is_default,
+                mentionable,
+                created_at,
+            )| {
                 RoleResponse {
                     id,
                     guild_id,
@@ -168,6 +180,7 @@ pub async fn list_roles(
                     permissions: permissions as u64,
                     position,
                     is_default,
+                    mentionable,
                     created_at,
                 }
             },
@@ -264,13 +277,14 @@ pub async fn create_role(
             i64,
             i32,
             bool,
+            bool,
             chrono::DateTime<chrono::Utc>,
         ),
     >(
         r"
-        INSERT INTO guild_roles (id, guild_id, name, color, permissions, position)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, guild_id, name, color, permissions, position, is_default, created_at
+        INSERT INTO guild_roles (id, guild_id, name, color, permissions, position, mentionable)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING id, guild_id, name, color, permissions, position, is_default, mentionable, created_at
         ",
     )
     .bind(role_id)
@@ -279,11 +293,25 @@ pub async fn create_role(
     .bind(&body.color)
     .bind(new_perms.bits() as i64)
     .bind(position)
+    .bind(body.mentionable.unwrap_or(false))
     .fetch_one(&mut *tx)
     .await?;
 
     tx.commit().await?;
 
+    crate::ws::bot_events::spawn_guild_event(
+        &state.db,
+        &state.redis,
+        guild_id,
+        BotServerEvent::RoleCreated {
+            guild_id,
+            role_id: role.0,
+            name: role.2.clone(),
+            permissions: role.4 as u64,
+            position: role.5,
+        },
+    );
+
     Ok(Json(RoleResponse {
         id: role.0,
         guild_id: role.1,
@@ -292,7 +320,8 @@ pub async fn create_role(
         permissions: role.4 as u64,
         position: role.5,
         is_default: role.6,
-        created_at: role.7,
+        mentionable: role.7,
+        created_at: role.8,
     }))
 }
 
@@ -348,6 +377,13 @@ pub async fn update_role(
             ));
         }
 
+        // @everyone is pinged via `@everyone`, not as a role
+        if body.mentionable == Some(true) {
+            return Err(RoleError::Validation(
+                "Cannot make @everyone role mentionable".to_string(),
+            ));
+        }
+
         // Cannot give @everyone dangerous permissions
         if let Some(perms) = new_perms {
             if !perms.validate_for_everyone() {
@@ -396,6 +432,7 @@ pub async fn update_role(
             i64,
             i32,
             bool,
+            bool,
             chrono::DateTime<chrono::Utc>,
         ),
     >(
@@ -404,9 +441,10 @@ pub async fn update_role(
             name = COALESCE($3, name),
             color = COALESCE($4, color),
             permissions = COALESCE($5, permissions),
-            position = COALESCE($6, position)
+            position = COALESCE($6, position),
+            mentionable = COALESCE($7, mentionable)
         WHERE id = $1 AND guild_id = $2
-        RETURNING id, guild_id, name, color, permissions, position, is_default, created_at
+        RETURNING id, guild_id, name, color, permissions, position, is_default, mentionable, created_at
         ",
     )
     .bind(role_id)
@@ -415,9 +453,23 @@ pub async fn update_role(
     .bind(&body.color)
     .bind(body.permissions.map(|p| p as i64))
     .bind(body.position)
+    .bind(body.mentionable)
     .fetch_one(&state.db)
     .await?;
 
+    crate::ws::bot_events::spawn_guild_event(
+        &state.db,
+        &state.redis,
+        guild_id,
+        BotServerEvent::RoleUpdated {
+            guild_id,
+            role_id: role.0,
+            name: role.2.clone(),
+            permissions: role.4 as u64,
+            position: role.5,
+        },
+    );
+
     Ok(Json(RoleResponse {
         id: role.0,
         guild_id: role.1,
@@ -426,7 +478,8 @@ pub async fn update_role(
         permissions: role.4 as u64,
         position: role.5,
         is_default: role.6,
-        created_at: role.7,
+        mentionable: role.7,
+        created_at: role.8,
     }))
 }
 
@@ -488,6 +541,13 @@ pub async fn delete_role(
         .execute(&state.db)
         .await?;
 
+    crate::ws::bot_events::spawn_guild_event(
+        &state.db,
+        &state.redis,
+        guild_id,
+        BotServerEvent::RoleDeleted { guild_id, role_id },
+    );
+
     Ok(Json(
         serde_json::json!({"deleted": true, "role_id": role_id}),
     ))
@@ -547,13 +607,20 @@ pub async fn assign_role(
     };
     can_manage_role(ctx.computed_permissions, actor_position, role.0, None)?;
 
-    // Check target is a member
-    let is_member: Option<(i32,)> =
-        sqlx::query_as("SELECT 1 FROM guild_members WHERE guild_id = $1 AND user_id = $2")
-            .bind(guild_id)
-            .bind(user_id)
-            .fetch_optional(&state.db)
-            .await?;
+    // Check target is a member or a bot installed in the guild
+    let is_member: Option<(i32,)> = sqlx::query_as(
+        r"
+        SELECT 1 FROM guild_members WHERE guild_id = $1 AND user_id = $2
+        UNION ALL
+        SELECT 1 FROM guild_bot_installations gbi
+        INNER JOIN bot_applications ba ON ba.id = gbi.application_id
+        WHERE gbi.guild_id = $1 AND ba.bot_user_id = $2
+        ",
+    )
+    .bind(guild_id)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?;
 
     if is_member.is_none() {
         return Err(RoleError::Validation(
@@ -576,6 +643,8 @@ pub async fn assign_role(
     .execute(&state.db)
     .await?;
 
+    broadcast_member_roles(&state, guild_id, user_id).await;
+
     Ok(Json(
         serde_json::json!({"assigned": true, "user_id": user_id, "role_id": role_id}),
     ))
@@ -641,7 +710,38 @@ pub async fn remove_role(
         return Err(RoleError::NotFound);
     }
 
+    broadcast_member_roles(&state, guild_id, user_id).await;
+
     Ok(Json(
         serde_json::json!({"removed": true, "user_id": user_id, "role_id": role_id}),
     ))
 }
+
+/// Broadcast a member's current role IDs to the guild after a role change.
+pub async fn broadcast_member_roles(state: &AppState, guild_id: Uuid, user_id: Uuid) {
+    let role_ids: Vec<Uuid> = match sqlx::query_scalar(
+        "SELECT role_id FROM guild_member_roles WHERE guild_id = $1 AND user_id = $2",
+    )
+    .bind(guild_id)
+    .bind(user_id)
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(role_ids) => role_ids,
+        Err(e) => {
+            tracing::warn!(%guild_id, %user_id, error = %e, "Failed to load member roles");
+            return;
+        }
+    };
+
+    if let Err(e) = crate::ws::broadcast_member_patch(
+        &state.redis,
+        guild_id,
+        user_id,
+        serde_json::json!({ "role_ids": role_ids }),
+    )
+    .await
+    {
+        tracing::warn!(%guild_id, %user_id, error = %e, "Failed to broadcast member roles");
+    }
+}
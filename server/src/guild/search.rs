@@ -120,6 +120,9 @@ pub struct SearchResponse {
     pub total: i64,
     pub limit: i64,
     pub offset: i64,
+    /// Searched channels that are end-to-end encrypted. Their encrypted
+    /// messages can't be searched by the server.
+    pub encrypted_channel_ids: Vec<Uuid>,
 }
 
 // ============================================================================
@@ -230,9 +233,16 @@ pub async fn search_messages(
             total: 0,
             limit: query.limit,
             offset: query.offset,
+            encrypted_channel_ids: vec![],
         }));
     }
 
+    let encrypted_channel_ids: Vec<Uuid> = guild_channels
+        .iter()
+        .filter(|c| c.encrypted && accessible_channel_ids.contains(&c.id))
+        .map(|c| c.id)
+        .collect();
+
     // Build search filters
     let filters = db::SearchFilters {
         date_from: query.date_from,
@@ -336,5 +346,6 @@ pub async fn search_messages(
         total,
         limit,
         offset,
+        encrypted_channel_ids,
     }))
 }
@@ -0,0 +1,777 @@
+//! Guild Stickers API
+//!
+//! Handlers for managing guild stickers: larger PNG/APNG/WebP/GIF images with
+//! a name, description and tags. Members send them as a message component
+//! (see `chat::components::Sticker`), so sending never uploads an attachment.
+//! Stickers can be used in any channel by members of the owning guild.
+
+use axum::extract::{Multipart, Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use fred::interfaces::PubsubInterface;
+use serde_json::json;
+use sqlx::PgPool;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::files::file_url;
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::chat::components;
+use crate::chat::media_processing;
+use crate::db;
+use crate::guild::types::{CreateStickerRequest, GuildSticker, UpdateStickerRequest};
+use crate::ws::ServerEvent;
+
+/// Largest sticker width or height in pixels.
+pub const MAX_STICKER_DIMENSION: u32 = 1024;
+
+/// Longest side of the static preview.
+const PREVIEW_MAX_DIM: u32 = 160;
+
+// ============================================================================
+// Error Types
+// ============================================================================
+
+#[derive(Debug, thiserror::Error)]
+pub enum StickerError {
+    #[error("Guild not found")]
+    GuildNotFound,
+    #[error("Sticker not found")]
+    StickerNotFound,
+    #[error("Insufficient permissions")]
+    Forbidden,
+    #[error("File too large (maximum {max_size} bytes)")]
+    FileTooLarge { max_size: usize },
+    #[error("No file provided")]
+    NoFile,
+    #[error("Storage error: {0}")]
+    Storage(String),
+    #[error("Validation error: {0}")]
+    Validation(String),
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for StickerError {
+    fn into_response(self) -> axum::response::Response {
+        if let Self::FileTooLarge { max_size } = self {
+            let message = format!(
+                "File too large (max {} for stickers)",
+                crate::util::format_file_size(max_size)
+            );
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(json!({
+                    "error": "FILE_TOO_LARGE",
+                    "message": message,
+                    "max_size_bytes": max_size
+                })),
+            )
+                .into_response();
+        }
+
+        let (status, code, message) = match &self {
+            Self::GuildNotFound => (StatusCode::NOT_FOUND, "GUILD_NOT_FOUND", "Guild not found"),
+            Self::StickerNotFound => (
+                StatusCode::NOT_FOUND,
+                "STICKER_NOT_FOUND",
+                "Sticker not found",
+            ),
+            Self::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "FORBIDDEN",
+                "Insufficient permissions",
+            ),
+            Self::NoFile => (StatusCode::BAD_REQUEST, "NO_FILE", "No file provided"),
+            Self::Storage(msg) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "STORAGE_ERROR",
+                msg.as_str(),
+            ),
+            Self::Validation(msg) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", msg.as_str()),
+            Self::LimitExceeded(msg) => (StatusCode::FORBIDDEN, "LIMIT_EXCEEDED", msg.as_str()),
+            Self::Database(err) => {
+                tracing::error!("Database error: {}", err);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "Database error",
+                )
+            }
+            Self::FileTooLarge { .. } => unreachable!("Handled above"),
+        };
+        (status, Json(json!({ "error": code, "message": message }))).into_response()
+    }
+}
+
+// ============================================================================
+// Storage Types
+// ============================================================================
+
+#[derive(Debug, sqlx::FromRow)]
+struct StickerRow {
+    id: Uuid,
+    guild_id: Uuid,
+    name: String,
+    description: Option<String>,
+    tags: Vec<String>,
+    format: String,
+    animated: bool,
+    s3_key: String,
+    preview_key: String,
+    width: i32,
+    height: i32,
+    blurhash: Option<String>,
+    uploaded_by: Option<Uuid>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<StickerRow> for GuildSticker {
+    fn from(row: StickerRow) -> Self {
+        Self {
+            id: row.id,
+            guild_id: row.guild_id,
+            name: row.name,
+            description: row.description,
+            tags: row.tags,
+            format: row.format,
+            animated: row.animated,
+            url: file_url(&row.s3_key),
+            preview_url: file_url(&row.preview_key),
+            width: row.width,
+            height: row.height,
+            blurhash: row.blurhash,
+            uploaded_by: row.uploaded_by,
+            created_at: row.created_at,
+        }
+    }
+}
+
+// ============================================================================
+// Internal Helpers
+// ============================================================================
+
+async fn list_guild_stickers(pool: &PgPool, guild_id: Uuid) -> sqlx::Result<Vec<GuildSticker>> {
+    let rows = sqlx::query_as::<_, StickerRow>(
+        "SELECT * FROM guild_stickers WHERE guild_id = $1 ORDER BY created_at DESC",
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(GuildSticker::from).collect())
+}
+
+async fn find_sticker(
+    pool: &PgPool,
+    guild_id: Uuid,
+    sticker_id: Uuid,
+) -> Result<StickerRow, StickerError> {
+    sqlx::query_as::<_, StickerRow>("SELECT * FROM guild_stickers WHERE id = $1 AND guild_id = $2")
+        .bind(sticker_id)
+        .bind(guild_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(StickerError::StickerNotFound)
+}
+
+/// Uploader or `MANAGE_GUILD` may edit and delete a sticker.
+async fn require_sticker_manager(
+    pool: &PgPool,
+    sticker: &StickerRow,
+    user_id: Uuid,
+) -> Result<(), StickerError> {
+    if sticker.uploaded_by == Some(user_id) {
+        return Ok(());
+    }
+    crate::permissions::require_guild_permission(
+        pool,
+        sticker.guild_id,
+        user_id,
+        crate::permissions::GuildPermissions::MANAGE_GUILD,
+    )
+    .await
+    .map(|_| ())
+    .map_err(|_| StickerError::Forbidden)
+}
+
+/// Broadcast the guild's full sticker list.
+async fn broadcast_stickers(state: &AppState, guild_id: Uuid) -> Result<(), StickerError> {
+    let event = ServerEvent::GuildStickersUpdated {
+        guild_id,
+        stickers: list_guild_stickers(&state.db, guild_id).await?,
+    };
+
+    let channel = crate::ws::channels::guild_events(guild_id);
+    match serde_json::to_string(&event) {
+        Ok(payload) => {
+            if let Err(e) = state.redis.publish::<(), _, _>(channel, payload).await {
+                tracing::error!(
+                    error = %e,
+                    guild_id = %guild_id,
+                    event = "GuildStickersUpdated",
+                    "Failed to broadcast sticker update via Redis - other clients will not receive real-time update"
+                );
+            }
+        }
+        Err(e) => {
+            tracing::error!(
+                error = %e,
+                guild_id = %guild_id,
+                "Failed to serialize GuildStickersUpdated event - broadcast skipped"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Split the multipart `tags` field (comma separated) into trimmed tags.
+fn parse_tags(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Build the message component for `sticker_id`, if `user_id` may use it.
+///
+/// Stickers are usable wherever the sender can post, as long as they are a
+/// member of the guild that owns the sticker. Returns `None` otherwise.
+pub async fn message_component(
+    pool: &PgPool,
+    user_id: Uuid,
+    sticker_id: Uuid,
+) -> sqlx::Result<Option<components::Sticker>> {
+    let row = sqlx::query_as::<_, StickerRow>(
+        r"
+        SELECT s.* FROM guild_stickers s
+        JOIN guild_members gm ON gm.guild_id = s.guild_id AND gm.user_id = $2
+        WHERE s.id = $1
+        ",
+    )
+    .bind(sticker_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| components::Sticker {
+        sticker_id: row.id,
+        guild_id: row.guild_id,
+        url: file_url(&row.s3_key),
+        preview_url: file_url(&row.preview_key),
+        name: row.name,
+        format: row.format,
+        width: row.width,
+        height: row.height,
+    }))
+}
+
+// ============================================================================
+// Router
+// ============================================================================
+
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(list_stickers).post(create_sticker))
+        .route(
+            "/{sticker_id}",
+            get(get_sticker)
+                .patch(update_sticker)
+                .delete(delete_sticker),
+        )
+}
+
+// ============================================================================
+// Handlers
+// ============================================================================
+
+/// List guild stickers.
+///
+/// `GET /api/guilds/{id}/stickers`
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{id}/stickers",
+    tag = "stickers",
+    params(("id" = Uuid, Path, description = "Guild ID")),
+    responses((status = 200, body = Vec<GuildSticker>)),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_stickers(
+    State(state): State<AppState>,
+    Path(guild_id): Path<Uuid>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<GuildSticker>>, StickerError> {
+    if !db::is_guild_member(&state.db, guild_id, auth_user.id).await? {
+        return Err(StickerError::GuildNotFound);
+    }
+
+    Ok(Json(list_guild_stickers(&state.db, guild_id).await?))
+}
+
+/// List stickers from every guild the user belongs to.
+///
+/// `GET /api/me/stickers`
+#[utoipa::path(
+    get,
+    path = "/api/me/stickers",
+    tag = "stickers",
+    responses((status = 200, body = Vec<GuildSticker>)),
+    security(("bearer_auth" = []))
+)]
+pub async fn list_available_stickers(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<GuildSticker>>, StickerError> {
+    let rows = sqlx::query_as::<_, StickerRow>(
+        r"
+        SELECT s.* FROM guild_stickers s
+        JOIN guild_members gm ON gm.guild_id = s.guild_id
+        WHERE gm.user_id = $1
+        ORDER BY s.guild_id, s.created_at DESC
+        ",
+    )
+    .bind(auth_user.id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(rows.into_iter().map(GuildSticker::from).collect()))
+}
+
+/// Get a specific sticker.
+///
+/// `GET /api/guilds/{id}/stickers/{sticker_id}`
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{id}/stickers/{sticker_id}",
+    tag = "stickers",
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("sticker_id" = Uuid, Path, description = "Sticker ID")
+    ),
+    responses((status = 200, body = GuildSticker)),
+    security(("bearer_auth" = []))
+)]
+pub async fn get_sticker(
+    State(state): State<AppState>,
+    Path((guild_id, sticker_id)): Path<(Uuid, Uuid)>,
+    auth_user: AuthUser,
+) -> Result<Json<GuildSticker>, StickerError> {
+    if !db::is_guild_member(&state.db, guild_id, auth_user.id).await? {
+        return Err(StickerError::GuildNotFound);
+    }
+
+    Ok(Json(
+        find_sticker(&state.db, guild_id, sticker_id).await?.into(),
+    ))
+}
+
+/// Create a sticker.
+///
+/// `POST /api/guilds/{id}/stickers`
+/// Expects multipart form with `name`, `file`, and optional `description`
+/// and comma-separated `tags`.
+#[utoipa::path(
+    post,
+    path = "/api/guilds/{id}/stickers",
+    tag = "stickers",
+    params(("id" = Uuid, Path, description = "Guild ID")),
+    request_body(content = Vec<u8>, content_type = "multipart/form-data"),
+    responses((status = 200, body = GuildSticker)),
+    security(("bearer_auth" = []))
+)]
+#[tracing::instrument(skip(state, auth_user, multipart))]
+pub async fn create_sticker(
+    State(state): State<AppState>,
+    Path(guild_id): Path<Uuid>,
+    auth_user: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<GuildSticker>, StickerError> {
+    if !db::is_guild_member(&state.db, guild_id, auth_user.id).await? {
+        return Err(StickerError::GuildNotFound);
+    }
+
+    let s3 = state
+        .s3
+        .as_ref()
+        .ok_or(StickerError::Storage("S3 not configured".into()))?;
+
+    let mut name: Option<String> = None;
+    let mut description: Option<String> = None;
+    let mut tags = Vec::new();
+    let mut file_data: Option<Vec<u8>> = None;
+
+    while let Ok(Some(field)) = multipart.next_field().await {
+        let field_name = field.name().unwrap_or_default().to_string();
+        match field_name.as_str() {
+            "name" | "description" | "tags" => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| StickerError::Validation(e.to_string()))?;
+                match field_name.as_str() {
+                    "name" => name = Some(text),
+                    "description" => description = Some(text).filter(|d| !d.trim().is_empty()),
+                    _ => tags = parse_tags(&text),
+                }
+            }
+            "file" => {
+                let data = field
+                    .bytes()
+                    .await
+                    .map_err(|e| StickerError::Validation(e.to_string()))?;
+                if data.len() > state.config.max_sticker_size {
+                    return Err(StickerError::FileTooLarge {
+                        max_size: state.config.max_sticker_size,
+                    });
+                }
+                file_data = Some(data.to_vec());
+            }
+            _ => {}
+        }
+    }
+
+    let file_data = file_data.ok_or(StickerError::NoFile)?;
+    let req = CreateStickerRequest {
+        name: name.ok_or(StickerError::Validation("Name required".into()))?,
+        description,
+        tags,
+    };
+    req.validate()
+        .map_err(|e| StickerError::Validation(e.to_string()))?;
+
+    // Validate actual file content using magic bytes (don't trust client-provided MIME type)
+    let format = image::guess_format(&file_data)
+        .map_err(|_| StickerError::Validation("Unable to detect image format".to_string()))?;
+    let (content_type, extension) = match format {
+        image::ImageFormat::Png => ("image/png", "png"),
+        image::ImageFormat::WebP => ("image/webp", "webp"),
+        image::ImageFormat::Gif => ("image/gif", "gif"),
+        _ => {
+            return Err(StickerError::Validation(
+                "Unsupported image format. Only PNG, APNG, WebP, and GIF are allowed.".to_string(),
+            ))
+        }
+    };
+    let animated = media_processing::is_animated(&file_data, content_type);
+    let format_name = if animated && extension == "png" {
+        "apng"
+    } else {
+        extension
+    };
+
+    // Dimensions, blurhash and the static preview come from the shared
+    // attachment pipeline (CPU-bound)
+    let data = file_data.clone();
+    let (meta, preview) = tokio::task::spawn_blocking(move || {
+        let meta = media_processing::process_image(&data, content_type)?;
+        let preview = media_processing::generate_preview(&data, content_type, PREVIEW_MAX_DIM)?;
+        Ok::<_, media_processing::ProcessingError>((meta, preview))
+    })
+    .await
+    .map_err(|e| StickerError::Storage(e.to_string()))?
+    .map_err(|e| StickerError::Validation(format!("Unable to process image: {e}")))?;
+
+    if meta.width > MAX_STICKER_DIMENSION || meta.height > MAX_STICKER_DIMENSION {
+        return Err(StickerError::Validation(format!(
+            "Stickers can be at most {MAX_STICKER_DIMENSION}x{MAX_STICKER_DIMENSION} pixels"
+        )));
+    }
+
+    let sticker_id = Uuid::now_v7();
+    let s3_key = format!("stickers/{guild_id}/{sticker_id}.{extension}");
+    let preview_key = format!("stickers/{guild_id}/{sticker_id}_preview.webp");
+
+    // Phase 1 — Reserve DB slot under advisory lock (short-lived).
+    // Advisory lock seed 65 = sticker_create (see db/mod.rs registry).
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtextextended($1::text, 65))")
+        .bind(guild_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let sticker_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM guild_stickers WHERE guild_id = $1")
+            .bind(guild_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+    if sticker_count >= state.config.max_stickers_per_guild {
+        return Err(StickerError::LimitExceeded(format!(
+            "Maximum number of stickers per guild reached ({})",
+            state.config.max_stickers_per_guild
+        )));
+    }
+
+    let name_taken: bool = sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM guild_stickers WHERE guild_id = $1 AND name = $2)",
+    )
+    .bind(guild_id)
+    .bind(&req.name)
+    .fetch_one(&mut *tx)
+    .await?;
+    if name_taken {
+        return Err(StickerError::Validation(
+            "A sticker with this name already exists".to_string(),
+        ));
+    }
+
+    let sticker = sqlx::query_as::<_, StickerRow>(
+        r"
+        INSERT INTO guild_stickers
+            (id, guild_id, name, description, tags, format, animated, s3_key, preview_key,
+             width, height, blurhash, uploaded_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+        RETURNING *
+        ",
+    )
+    .bind(sticker_id)
+    .bind(guild_id)
+    .bind(&req.name)
+    .bind(req.description.as_deref())
+    .bind(&req.tags)
+    .bind(format_name)
+    .bind(animated)
+    .bind(&s3_key)
+    .bind(&preview_key)
+    .bind(i32::try_from(meta.width).unwrap_or(i32::MAX))
+    .bind(i32::try_from(meta.height).unwrap_or(i32::MAX))
+    .bind(&meta.blurhash)
+    .bind(auth_user.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    // Phase 2 — Upload to S3 outside the advisory lock
+    let uploaded = match s3.upload(&s3_key, file_data, content_type).await {
+        Ok(()) => {
+            s3.upload(&preview_key, preview.data, &preview.content_type)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(upload_err) = uploaded {
+        tracing::warn!(
+            sticker_id = %sticker_id,
+            guild_id = %guild_id,
+            error = %upload_err,
+            "S3 upload failed after DB insert, compensating by deleting sticker row"
+        );
+
+        if let Err(delete_err) = sqlx::query("DELETE FROM guild_stickers WHERE id = $1")
+            .bind(sticker_id)
+            .execute(&state.db)
+            .await
+        {
+            tracing::error!(
+                sticker_id = %sticker_id,
+                guild_id = %guild_id,
+                error = %delete_err,
+                "Failed to compensate: sticker DB row orphaned without S3 object"
+            );
+        }
+        // Either upload may have landed before the other failed
+        for key in [&s3_key, &preview_key] {
+            s3.delete(key).await.ok();
+        }
+
+        return Err(StickerError::Storage(upload_err.to_string()));
+    }
+
+    broadcast_stickers(&state, guild_id).await?;
+
+    Ok(Json(sticker.into()))
+}
+
+/// Update a sticker's name, description or tags.
+///
+/// `PATCH /api/guilds/{id}/stickers/{sticker_id}`
+#[utoipa::path(
+    patch,
+    path = "/api/guilds/{id}/stickers/{sticker_id}",
+    tag = "stickers",
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("sticker_id" = Uuid, Path, description = "Sticker ID")
+    ),
+    request_body = UpdateStickerRequest,
+    responses((status = 200, body = GuildSticker)),
+    security(("bearer_auth" = []))
+)]
+pub async fn update_sticker(
+    State(state): State<AppState>,
+    Path((guild_id, sticker_id)): Path<(Uuid, Uuid)>,
+    auth_user: AuthUser,
+    Json(req): Json<UpdateStickerRequest>,
+) -> Result<Json<GuildSticker>, StickerError> {
+    req.validate()
+        .map_err(|e| StickerError::Validation(e.to_string()))?;
+
+    let sticker = find_sticker(&state.db, guild_id, sticker_id).await?;
+    require_sticker_manager(&state.db, &sticker, auth_user.id).await?;
+
+    let tags = req
+        .tags
+        .map(|tags| parse_tags(&tags.join(",")))
+        .unwrap_or(sticker.tags);
+    let description = match req.description {
+        Some(d) if d.trim().is_empty() => None,
+        Some(d) => Some(d),
+        None => sticker.description,
+    };
+
+    let updated = sqlx::query_as::<_, StickerRow>(
+        r"
+        UPDATE guild_stickers
+        SET name = $1, description = $2, tags = $3
+        WHERE id = $4 AND guild_id = $5
+        RETURNING *
+        ",
+    )
+    .bind(req.name.as_deref().unwrap_or(&sticker.name))
+    .bind(description.as_deref())
+    .bind(&tags)
+    .bind(sticker_id)
+    .bind(guild_id)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(db_err) if db_err.is_unique_violation() => {
+            StickerError::Validation("A sticker with this name already exists".to_string())
+        }
+        _ => StickerError::Database(e),
+    })?;
+
+    broadcast_stickers(&state, guild_id).await?;
+
+    Ok(Json(updated.into()))
+}
+
+/// Delete a sticker.
+///
+/// Messages that already carry the sticker keep their component, but its
+/// image is gone once the stored objects are removed.
+///
+/// `DELETE /api/guilds/{id}/stickers/{sticker_id}`
+#[utoipa::path(
+    delete,
+    path = "/api/guilds/{id}/stickers/{sticker_id}",
+    tag = "stickers",
+    params(
+        ("id" = Uuid, Path, description = "Guild ID"),
+        ("sticker_id" = Uuid, Path, description = "Sticker ID")
+    ),
+    responses((status = 204, description = "Sticker deleted")),
+    security(("bearer_auth" = []))
+)]
+pub async fn delete_sticker(
+    State(state): State<AppState>,
+    Path((guild_id, sticker_id)): Path<(Uuid, Uuid)>,
+    auth_user: AuthUser,
+) -> Result<StatusCode, StickerError> {
+    let sticker = find_sticker(&state.db, guild_id, sticker_id).await?;
+    require_sticker_manager(&state.db, &sticker, auth_user.id).await?;
+
+    sqlx::query("DELETE FROM guild_stickers WHERE id = $1")
+        .bind(sticker_id)
+        .execute(&state.db)
+        .await?;
+
+    // Delete from S3 (best effort)
+    if let Some(s3) = &state.s3 {
+        for key in [&sticker.s3_key, &sticker.preview_key] {
+            if let Err(e) = s3.delete(key).await {
+                tracing::warn!(
+                    sticker_id = %sticker_id,
+                    guild_id = %guild_id,
+                    s3_key = %key,
+                    error = %e,
+                    "Failed to delete sticker file from S3"
+                );
+            }
+        }
+    }
+
+    broadcast_stickers(&state, guild_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::fixtures;
+    use crate::guild::types::MAX_STICKER_TAGS;
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags(" Happy, wave ,,HELLO "),
+            vec!["happy", "wave", "hello"]
+        );
+        assert!(parse_tags("").is_empty());
+    }
+
+    #[test]
+    fn test_create_request_validation() {
+        let request = |name: &str, tags: Vec<String>| CreateStickerRequest {
+            name: name.to_string(),
+            description: None,
+            tags,
+        };
+        assert!(request("wave", vec!["hi".into()]).validate().is_ok());
+        assert!(request("w", vec![]).validate().is_err());
+        assert!(request("wave", vec!["x".repeat(21)]).validate().is_err());
+        assert!(request("wave", vec!["t".into(); MAX_STICKER_TAGS + 1])
+            .validate()
+            .is_err());
+    }
+
+    #[sqlx::test]
+    async fn stickers_are_usable_only_by_members_of_their_guild(pool: PgPool) {
+        let owner = db::create_user(&pool, "sticker_owner", "Owner", None, "hash")
+            .await
+            .expect("create owner");
+        let outsider = db::create_user(&pool, "sticker_outsider", "Outsider", None, "hash")
+            .await
+            .expect("create outsider");
+        let guild_id = fixtures::create_guild(&pool, "Stickers", owner.id, &[]).await;
+        let sticker_id = Uuid::now_v7();
+        sqlx::query(
+            r"
+            INSERT INTO guild_stickers
+                (id, guild_id, name, format, s3_key, preview_key, width, height, uploaded_by)
+            VALUES ($1, $2, 'wave', 'apng', 'stickers/w.png', 'stickers/w_preview.webp', 320, 320, $3)
+            ",
+        )
+        .bind(sticker_id)
+        .bind(guild_id)
+        .bind(owner.id)
+        .execute(&pool)
+        .await
+        .expect("create sticker");
+
+        let component = message_component(&pool, owner.id, sticker_id)
+            .await
+            .unwrap()
+            .expect("members can use the sticker");
+        assert_eq!(component.guild_id, guild_id);
+        assert_eq!(component.format, "apng");
+        assert_eq!(component.url, "/api/files/stickers/w.png");
+
+        assert!(message_component(&pool, outsider.id, sticker_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
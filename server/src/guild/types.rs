@@ -125,6 +125,8 @@ pub struct CreateRoleRequest {
     pub name: String,
     pub color: Option<String>,
     pub permissions: Option<u64>,
+    /// Whether members without `MENTION_EVERYONE` may mention this role.
+    pub mentionable: Option<bool>,
 }
 
 /// Request to update a guild role.
@@ -134,6 +136,7 @@ pub struct UpdateRoleRequest {
     pub color: Option<String>,
     pub permissions: Option<u64>,
     pub position: Option<i32>,
+    pub mentionable: Option<bool>,
 }
 
 /// Guild role response.
@@ -146,6 +149,8 @@ pub struct RoleResponse {
     pub permissions: u64,
     pub position: i32,
     pub is_default: bool,
+    /// Whether members without `MENTION_EVERYONE` may mention this role.
+    pub mentionable: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
@@ -176,6 +181,67 @@ pub struct UpdateEmojiRequest {
     pub name: String,
 }
 
+// ============================================================================
+// Sticker Types
+// ============================================================================
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct GuildSticker {
+    pub id: Uuid,
+    pub guild_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    /// `png`, `apng`, `webp` or `gif`.
+    pub format: String,
+    pub animated: bool,
+    pub url: String,
+    /// Static downscaled first frame, for pickers and reduced motion.
+    pub preview_url: String,
+    pub width: i32,
+    pub height: i32,
+    pub blurhash: Option<String>,
+    pub uploaded_by: Option<Uuid>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, utoipa::ToSchema)]
+pub struct CreateStickerRequest {
+    #[validate(length(min = 2, max = 30, message = "Name must be 2-30 characters"))]
+    pub name: String,
+    #[validate(length(max = 100, message = "Description must be at most 100 characters"))]
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_sticker_tags"))]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Validate, utoipa::ToSchema)]
+pub struct UpdateStickerRequest {
+    #[validate(length(min = 2, max = 30, message = "Name must be 2-30 characters"))]
+    pub name: Option<String>,
+    /// Empty string clears the description.
+    #[validate(length(max = 100, message = "Description must be at most 100 characters"))]
+    pub description: Option<String>,
+    #[validate(custom(function = "validate_sticker_tags"))]
+    pub tags: Option<Vec<String>>,
+}
+
+/// Maximum tags per sticker.
+pub const MAX_STICKER_TAGS: usize = 10;
+
+/// Up to 10 non-empty tags of at most 20 characters.
+fn validate_sticker_tags(tags: &[String]) -> Result<(), validator::ValidationError> {
+    if tags.len() > MAX_STICKER_TAGS
+        || tags
+            .iter()
+            .any(|t| t.trim().is_empty() || t.chars().count() > 20)
+    {
+        return Err(validator::ValidationError::new("sticker_tags")
+            .with_message("Stickers can have up to 10 tags of 1-20 characters".into()));
+    }
+    Ok(())
+}
+
 // ============================================================================
 // Guild Settings Types
 // ============================================================================
@@ -212,4 +278,44 @@ pub struct GuildCommandInfo {
     pub bot_name: String,
     pub application_id: Uuid,
     pub is_ambiguous: bool,
+    /// Option schema, so clients can render choices and request autocomplete.
+    pub options: Vec<crate::api::commands::CommandOption>,
+}
+
+/// Query parameters for listing guild commands.
+#[derive(Debug, Deserialize)]
+pub struct ListGuildCommandsQuery {
+    /// Only list commands the member can use in this channel.
+    pub channel_id: Option<Uuid>,
+}
+
+/// Request for autocomplete suggestions while typing a command option.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct CommandAutocompleteRequest {
+    /// Application providing the command (see `GuildCommandInfo`).
+    pub application_id: Uuid,
+    /// Channel the command is being typed in.
+    pub channel_id: Uuid,
+    /// Command name.
+    pub command_name: String,
+    /// Selected subcommand group, if the command has groups.
+    pub subcommand_group: Option<String>,
+    /// Selected subcommand, if the command has subcommands.
+    pub subcommand: Option<String>,
+    /// Name of the option being typed (must have autocomplete enabled).
+    pub focused: String,
+    /// Partial input for the focused option.
+    #[serde(default)]
+    pub value: String,
+    /// Values already entered for other options.
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Autocomplete suggestions returned by the bot.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct CommandAutocompleteResponse {
+    /// Suggestions, empty when the bot did not answer in time.
+    pub choices: Vec<crate::api::commands::CommandOptionChoice>,
 }
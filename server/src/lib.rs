@@ -6,21 +6,25 @@
 pub mod admin;
 pub mod api;
 pub mod auth;
+pub mod bot_api;
 pub mod chat;
 pub mod config;
 pub mod connectivity;
 pub mod crypto;
 pub mod db;
+pub mod digest;
 pub mod discovery;
 pub mod email;
 pub mod governance;
 pub mod guild;
 pub mod moderation;
+pub mod notifications;
 pub mod observability;
 pub mod openapi;
 pub mod pages;
 pub mod permissions;
 pub mod presence;
+pub mod push;
 pub mod ratelimit;
 pub mod social;
 pub mod util;
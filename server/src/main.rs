@@ -153,7 +153,7 @@ async fn main() -> Result<()> {
         }
     });
 
-    // Start background cleanup task for database (sessions, prekeys, device transfers, governance)
+    // Start background cleanup task for database (sessions, prekeys, to-device messages, device transfers, governance)
     let db_pool_clone = db_pool.clone();
     let s3_clone = s3.clone();
     let db_cleanup_handle = tokio::spawn(async move {
@@ -183,6 +183,17 @@ async fn main() -> Result<()> {
                 _ => {}
             }
 
+            // Cleanup expired to-device messages
+            match vc_server::crypto::to_device::cleanup_expired(&db_pool_clone).await {
+                Ok(count) if count > 0 => {
+                    tracing::debug!(count, "Cleaned up expired to-device messages");
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to cleanup expired to-device messages");
+                }
+                _ => {}
+            }
+
             // Cleanup expired device transfers
             match db::cleanup_expired_device_transfers(&db_pool_clone).await {
                 Ok(count) if count > 0 => {
@@ -262,6 +273,17 @@ async fn main() -> Result<()> {
     let custom_status_sweep_handle =
         vc_server::ws::spawn_custom_status_sweep(db_pool.clone(), redis.clone());
 
+    // Start poll expiry sweep (every 30 seconds)
+    let poll_expiry_sweep_handle =
+        vc_server::chat::polls::spawn_poll_expiry_sweep(db_pool.clone(), redis.clone());
+
+    // Start disappearing message sweep (every 30 seconds)
+    let message_expiry_sweep_handle = vc_server::chat::disappearing::spawn_expiry_sweep(
+        db_pool.clone(),
+        redis.clone(),
+        s3.clone(),
+    );
+
     info!("Voice SFU server initialized");
 
     // Initialize email service (optional - password reset will be disabled if not configured)
@@ -349,6 +371,44 @@ async fn main() -> Result<()> {
     ));
     info!("Webhook delivery worker started");
 
+    // Spawn push delivery worker
+    let push_config = vc_server::push::delivery::PushDeliveryConfig {
+        vapid: config.vapid_private_key.as_deref().and_then(|key| {
+            vc_server::push::web_push::VapidKeys::from_base64url(key)
+                .map_err(
+                    |e| tracing::warn!(error = %e, "Invalid VAPID_PRIVATE_KEY. Web Push disabled."),
+                )
+                .ok()
+                .map(std::sync::Arc::new)
+        }),
+        vapid_subject: config.vapid_subject.clone(),
+        allow_private_endpoints: config.push_allow_private_endpoints,
+    };
+    let push_worker_handle = tokio::spawn(vc_server::push::delivery::spawn_delivery_worker(
+        db_pool.clone(),
+        redis.clone(),
+        reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .expect("Failed to build push HTTP client"),
+        push_config,
+    ));
+
+    // Spawn email digest worker (requires SMTP and PUBLIC_URL)
+    let digest_mailer = email_service
+        .clone()
+        .and_then(|email| vc_server::digest::worker::DigestMailer::new(email, &config));
+    if digest_mailer.is_none() {
+        info!("SMTP or PUBLIC_URL not configured. Email digests disabled.");
+    }
+    let digest_worker_handle = digest_mailer.map(|mailer| {
+        tokio::spawn(vc_server::digest::worker::spawn_digest_worker(
+            db_pool.clone(),
+            redis.clone(),
+            mailer,
+        ))
+    });
+
     // Build application state
     let state = api::AppState::new(api::AppStateConfig {
         db: db_pool.clone(),
@@ -398,17 +458,29 @@ async fn main() -> Result<()> {
     voice_cleanup_handle.abort();
     db_cleanup_handle.abort();
     webhook_worker_handle.abort();
+    push_worker_handle.abort();
+    if let Some(handle) = &digest_worker_handle {
+        handle.abort();
+    }
     rtp_flush_handle.abort();
     retention_handle.abort();
     voice_health_handle.abort();
     custom_status_sweep_handle.abort();
+    poll_expiry_sweep_handle.abort();
+    message_expiry_sweep_handle.abort();
     let _ = voice_cleanup_handle.await;
     let _ = db_cleanup_handle.await;
     let _ = webhook_worker_handle.await;
+    let _ = push_worker_handle.await;
+    if let Some(handle) = digest_worker_handle {
+        let _ = handle.await;
+    }
     let _ = rtp_flush_handle.await;
     let _ = retention_handle.await;
     let _ = voice_health_handle.await;
     let _ = custom_status_sweep_handle.await;
+    let _ = poll_expiry_sweep_handle.await;
+    let _ = message_expiry_sweep_handle.await;
     info!("Background cleanup tasks stopped");
 
     // 2. Flush and shut down OTel providers. Dropping these closes the channel senders
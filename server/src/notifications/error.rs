@@ -0,0 +1,52 @@
+//! Notification Settings Error Types
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+
+#[derive(Debug, thiserror::Error)]
+pub enum NotificationSettingsError {
+    #[error("Notification setting not found")]
+    NotFound,
+
+    #[error("Scope not found or no access")]
+    ScopeNotFound,
+
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for NotificationSettingsError {
+    fn into_response(self) -> axum::response::Response {
+        let (status, code, message) = match &self {
+            Self::NotFound => (
+                StatusCode::NOT_FOUND,
+                "SETTING_NOT_FOUND",
+                "Notification setting not found".to_string(),
+            ),
+            Self::ScopeNotFound => (
+                StatusCode::NOT_FOUND,
+                "SCOPE_NOT_FOUND",
+                "Guild, category, channel or thread not found or you don't have access".to_string(),
+            ),
+            Self::Validation(msg) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", msg.clone()),
+            Self::Database(err) => {
+                tracing::error!(%err, "Notification settings endpoint database error");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "Database error".to_string(),
+                )
+            }
+        };
+
+        (
+            status,
+            Json(serde_json::json!({ "error": code, "message": message })),
+        )
+            .into_response()
+    }
+}
@@ -0,0 +1,344 @@
+//! Notification Settings HTTP Handlers
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::error::NotificationSettingsError;
+use super::resolver::effective_settings;
+use super::types::{
+    EffectiveNotificationSettings, NotificationScope, NotificationSetting,
+    UpdateNotificationSettingRequest,
+};
+use crate::api::AppState;
+use crate::auth::AuthUser;
+use crate::ws::{broadcast_to_user, ServerEvent};
+
+/// Verify the user can see the guild, category, channel or thread a setting targets.
+async fn require_scope_access(
+    state: &AppState,
+    user_id: Uuid,
+    scope: NotificationScope,
+    scope_id: Uuid,
+) -> Result<(), NotificationSettingsError> {
+    let channel_id = match scope {
+        NotificationScope::Guild | NotificationScope::Category => {
+            let guild_id: Option<Uuid> = if scope == NotificationScope::Guild {
+                Some(scope_id)
+            } else {
+                sqlx::query_scalar("SELECT guild_id FROM channel_categories WHERE id = $1")
+                    .bind(scope_id)
+                    .fetch_optional(&state.db)
+                    .await?
+            };
+            let Some(guild_id) = guild_id else {
+                return Err(NotificationSettingsError::ScopeNotFound);
+            };
+            let is_member =
+                sqlx::query("SELECT 1 FROM guild_members WHERE guild_id = $1 AND user_id = $2")
+                    .bind(guild_id)
+                    .bind(user_id)
+                    .fetch_optional(&state.db)
+                    .await?
+                    .is_some();
+            return if is_member {
+                Ok(())
+            } else {
+                Err(NotificationSettingsError::ScopeNotFound)
+            };
+        }
+        NotificationScope::Channel => scope_id,
+        // Threads are identified by their root message
+        NotificationScope::Thread => sqlx::query_scalar(
+            "SELECT channel_id FROM messages WHERE id = $1 AND parent_id IS NULL AND deleted_at IS NULL",
+        )
+        .bind(scope_id)
+        .fetch_optional(&state.db)
+        .await?
+        .ok_or(NotificationSettingsError::ScopeNotFound)?,
+    };
+
+    crate::permissions::require_channel_access(&state.db, user_id, channel_id)
+        .await
+        .map_err(|_| NotificationSettingsError::ScopeNotFound)?;
+    Ok(())
+}
+
+fn validate_request(
+    scope: NotificationScope,
+    request: &UpdateNotificationSettingRequest,
+) -> Result<(), NotificationSettingsError> {
+    if let Some(until) = request.muted_until {
+        if !request.muted {
+            return Err(NotificationSettingsError::Validation(
+                "muted_until requires muted".to_string(),
+            ));
+        }
+        if until <= Utc::now() {
+            return Err(NotificationSettingsError::Validation(
+                "muted_until must be in the future".to_string(),
+            ));
+        }
+    }
+    if scope != NotificationScope::Guild && (request.suppress_everyone || request.suppress_roles) {
+        return Err(NotificationSettingsError::Validation(
+            "Mention suppression can only be set at guild scope".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+async fn broadcast_setting(
+    state: &AppState,
+    user_id: Uuid,
+    scope: NotificationScope,
+    scope_id: Uuid,
+    setting: Option<NotificationSetting>,
+) {
+    if let Err(e) = broadcast_to_user(
+        &state.redis,
+        user_id,
+        &ServerEvent::NotificationSettingsUpdated {
+            scope,
+            scope_id,
+            setting,
+        },
+    )
+    .await
+    {
+        tracing::warn!(
+            "Failed to broadcast NotificationSettingsUpdated event: {}",
+            e
+        );
+    }
+}
+
+/// List all of the user's notification settings.
+///
+/// GET /api/me/notification-settings
+#[utoipa::path(
+    get,
+    path = "/api/me/notification-settings",
+    tag = "notifications",
+    responses(
+        (status = 200, body = Vec<NotificationSetting>),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn list_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<NotificationSetting>>, NotificationSettingsError> {
+    let settings = sqlx::query_as::<_, NotificationSetting>(
+        r"
+        SELECT scope, scope_id, level, muted, muted_until,
+               suppress_everyone, suppress_roles, updated_at
+        FROM notification_settings
+        WHERE user_id = $1
+        ORDER BY scope, scope_id
+        ",
+    )
+    .bind(auth_user.id)
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(Json(settings))
+}
+
+/// Get the user's notification setting for one scope.
+///
+/// GET /api/me/notification-settings/{scope}/{scope_id}
+#[utoipa::path(
+    get,
+    path = "/api/me/notification-settings/{scope}/{scope_id}",
+    tag = "notifications",
+    params(
+        ("scope" = NotificationScope, Path, description = "guild, category, channel or thread"),
+        ("scope_id" = Uuid, Path, description = "Guild, category, channel or thread root message ID"),
+    ),
+    responses(
+        (status = 200, body = NotificationSetting),
+        (status = 404, description = "No setting stored for this scope"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn get_setting(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((scope, scope_id)): Path<(NotificationScope, Uuid)>,
+) -> Result<Json<NotificationSetting>, NotificationSettingsError> {
+    let setting = sqlx::query_as::<_, NotificationSetting>(
+        r"
+        SELECT scope, scope_id, level, muted, muted_until,
+               suppress_everyone, suppress_roles, updated_at
+        FROM notification_settings
+        WHERE user_id = $1 AND scope = $2 AND scope_id = $3
+        ",
+    )
+    .bind(auth_user.id)
+    .bind(scope)
+    .bind(scope_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(NotificationSettingsError::NotFound)?;
+
+    Ok(Json(setting))
+}
+
+/// Create or replace the user's notification setting for one scope.
+///
+/// PUT /api/me/notification-settings/{scope}/{scope_id}
+#[utoipa::path(
+    put,
+    path = "/api/me/notification-settings/{scope}/{scope_id}",
+    tag = "notifications",
+    params(
+        ("scope" = NotificationScope, Path, description = "guild, category, channel or thread"),
+        ("scope_id" = Uuid, Path, description = "Guild, category, channel or thread root message ID"),
+    ),
+    request_body = UpdateNotificationSettingRequest,
+    responses(
+        (status = 200, body = NotificationSetting),
+        (status = 400, description = "Invalid setting"),
+        (status = 404, description = "Scope not found or no access"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn update_setting(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((scope, scope_id)): Path<(NotificationScope, Uuid)>,
+    Json(request): Json<UpdateNotificationSettingRequest>,
+) -> Result<Json<NotificationSetting>, NotificationSettingsError> {
+    validate_request(scope, &request)?;
+    require_scope_access(&state, auth_user.id, scope, scope_id).await?;
+
+    let setting = sqlx::query_as::<_, NotificationSetting>(
+        r"
+        INSERT INTO notification_settings
+            (user_id, scope, scope_id, level, muted, muted_until, suppress_everyone, suppress_roles)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (user_id, scope, scope_id) DO UPDATE SET
+            level = EXCLUDED.level,
+            muted = EXCLUDED.muted,
+            muted_until = EXCLUDED.muted_until,
+            suppress_everyone = EXCLUDED.suppress_everyone,
+            suppress_roles = EXCLUDED.suppress_roles,
+            updated_at = NOW()
+        RETURNING scope, scope_id, level, muted, muted_until,
+                  suppress_everyone, suppress_roles, updated_at
+        ",
+    )
+    .bind(auth_user.id)
+    .bind(scope)
+    .bind(scope_id)
+    .bind(request.level)
+    .bind(request.muted)
+    .bind(request.muted_until)
+    .bind(request.suppress_everyone)
+    .bind(request.suppress_roles)
+    .fetch_one(&state.db)
+    .await?;
+
+    broadcast_setting(&state, auth_user.id, scope, scope_id, Some(setting.clone())).await;
+
+    Ok(Json(setting))
+}
+
+/// Reset the user's notification setting for one scope so it inherits again.
+///
+/// DELETE /api/me/notification-settings/{scope}/{scope_id}
+#[utoipa::path(
+    delete,
+    path = "/api/me/notification-settings/{scope}/{scope_id}",
+    tag = "notifications",
+    params(
+        ("scope" = NotificationScope, Path, description = "guild, category, channel or thread"),
+        ("scope_id" = Uuid, Path, description = "Guild, category, channel or thread root message ID"),
+    ),
+    responses(
+        (status = 204, description = "Setting removed"),
+        (status = 404, description = "No setting stored for this scope"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn delete_setting(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path((scope, scope_id)): Path<(NotificationScope, Uuid)>,
+) -> Result<StatusCode, NotificationSettingsError> {
+    let result = sqlx::query(
+        "DELETE FROM notification_settings WHERE user_id = $1 AND scope = $2 AND scope_id = $3",
+    )
+    .bind(auth_user.id)
+    .bind(scope)
+    .bind(scope_id)
+    .execute(&state.db)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(NotificationSettingsError::NotFound);
+    }
+
+    broadcast_setting(&state, auth_user.id, scope, scope_id, None).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Query parameters for the effective settings endpoint.
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct EffectiveSettingsQuery {
+    /// Resolve for a thread (root message ID) in the channel.
+    pub thread_id: Option<Uuid>,
+}
+
+/// Get the resolved notification settings for a channel or a thread in it.
+///
+/// GET /api/me/notification-settings/channels/{channel_id}/effective
+#[utoipa::path(
+    get,
+    path = "/api/me/notification-settings/channels/{channel_id}/effective",
+    tag = "notifications",
+    params(
+        ("channel_id" = Uuid, Path, description = "Channel ID"),
+        EffectiveSettingsQuery,
+    ),
+    responses(
+        (status = 200, body = EffectiveNotificationSettings),
+        (status = 404, description = "Channel not found, no access, or thread not in the channel"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn get_effective_settings(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(channel_id): Path<Uuid>,
+    Query(query): Query<EffectiveSettingsQuery>,
+) -> Result<Json<EffectiveNotificationSettings>, NotificationSettingsError> {
+    require_scope_access(&state, auth_user.id, NotificationScope::Channel, channel_id).await?;
+    if let Some(thread_id) = query.thread_id {
+        let in_channel = sqlx::query(
+            "SELECT 1 FROM messages WHERE id = $1 AND channel_id = $2 AND parent_id IS NULL AND deleted_at IS NULL",
+        )
+        .bind(thread_id)
+        .bind(channel_id)
+        .fetch_optional(&state.db)
+        .await?
+        .is_some();
+        if !in_channel {
+            return Err(NotificationSettingsError::ScopeNotFound);
+        }
+    }
+
+    let effective =
+        effective_settings(&state.db, auth_user.id, channel_id, query.thread_id).await?;
+    Ok(Json(effective))
+}
@@ -0,0 +1,36 @@
+//! Notification Settings
+//!
+//! Per-user notification levels and mutes at guild, category, channel and
+//! thread scope. Settings are resolved server-side so unread badges and
+//! push/email delivery agree with what every device shows.
+
+pub mod error;
+pub mod handlers;
+pub mod resolver;
+pub mod types;
+
+use axum::routing::get;
+use axum::Router;
+
+pub use resolver::{effective_settings, should_notify, MentionKind};
+pub use types::{EffectiveNotificationSettings, NotificationLevel, NotificationScope};
+
+use crate::api::AppState;
+
+/// Create notification settings routes.
+///
+/// Mounted at `/api/me/notification-settings` in the main router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/", get(handlers::list_settings))
+        .route(
+            "/channels/{channel_id}/effective",
+            get(handlers::get_effective_settings),
+        )
+        .route(
+            "/{scope}/{scope_id}",
+            get(handlers::get_setting)
+                .put(handlers::update_setting)
+                .delete(handlers::delete_setting),
+        )
+}
@@ -0,0 +1,298 @@
+//! Notification Settings Resolution
+//!
+//! Walks the scope chain thread → channel → category → parent category →
+//! guild. The level comes from the most specific scope that sets one, a mute
+//! at any scope silences everything below it, and mention suppression is read
+//! from the guild scope.
+
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::types::{
+    EffectiveNotificationSettings, NotificationLevel, NotificationScope, NotificationSetting,
+};
+
+/// How a message mentions the recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MentionKind {
+    /// `@username` of the recipient.
+    Direct,
+    /// `@everyone` or `@here`.
+    Everyone,
+    /// A role the recipient holds.
+    Role,
+}
+
+impl MentionKind {
+    /// Detect how `content` mentions the recipient with `username` holding
+    /// `role_ids`, preferring a direct mention over a role over `@everyone`.
+    #[must_use]
+    pub fn detect(content: &str, username: &str, role_ids: &[Uuid]) -> Option<Self> {
+        let direct = content.match_indices('@').any(|(i, _)| {
+            let rest = &content[i + 1..];
+            rest.get(..username.len())
+                .is_some_and(|name| name.eq_ignore_ascii_case(username))
+                && !rest[username.len()..]
+                    .starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if direct {
+            Some(Self::Direct)
+        } else if crate::chat::mentions::parse_mentions(content)
+            .role_ids
+            .iter()
+            .any(|id| role_ids.contains(id))
+        {
+            Some(Self::Role)
+        } else if content.contains("@everyone") || content.contains("@here") {
+            Some(Self::Everyone)
+        } else {
+            None
+        }
+    }
+}
+
+/// Resolve stored settings ordered from the most specific scope to the guild.
+#[must_use]
+pub fn resolve(chain: &[NotificationSetting], now: DateTime<Utc>) -> EffectiveNotificationSettings {
+    let level = chain.iter().find_map(|s| s.level).unwrap_or_default();
+
+    let mutes: Vec<_> = chain.iter().filter(|s| s.is_muted_at(now)).collect();
+    let muted_until = if mutes.iter().any(|s| s.muted_until.is_none()) {
+        None
+    } else {
+        mutes.iter().filter_map(|s| s.muted_until).max()
+    };
+
+    let guild = chain.iter().find(|s| s.scope == NotificationScope::Guild);
+
+    EffectiveNotificationSettings {
+        level,
+        muted: !mutes.is_empty(),
+        muted_until,
+        suppress_everyone: guild.is_some_and(|s| s.suppress_everyone),
+        suppress_roles: guild.is_some_and(|s| s.suppress_roles),
+    }
+}
+
+impl EffectiveNotificationSettings {
+    /// Whether a message with the given mention should notify the user.
+    #[must_use]
+    pub const fn should_notify(&self, mention: Option<MentionKind>) -> bool {
+        if self.muted {
+            return false;
+        }
+        match (self.level, mention) {
+            (NotificationLevel::Nothing, _) => false,
+            (_, Some(MentionKind::Direct)) => true,
+            (_, Some(MentionKind::Everyone)) if !self.suppress_everyone => true,
+            (_, Some(MentionKind::Role)) if !self.suppress_roles => true,
+            (NotificationLevel::All, _) => true,
+            (NotificationLevel::Mentions, _) => false,
+        }
+    }
+}
+
+/// Scopes enclosing a channel (and optionally a thread in it), most specific first.
+async fn scope_chain(
+    pool: &PgPool,
+    channel_id: Uuid,
+    thread_id: Option<Uuid>,
+) -> sqlx::Result<Vec<(NotificationScope, Uuid)>> {
+    let row: Option<(Option<Uuid>, Option<Uuid>, Option<Uuid>)> = sqlx::query_as(
+        r"
+        SELECT c.guild_id, c.category_id, cc.parent_id
+        FROM channels c
+        LEFT JOIN channel_categories cc ON cc.id = c.category_id
+        WHERE c.id = $1
+        ",
+    )
+    .bind(channel_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let mut chain = Vec::with_capacity(5);
+    if let Some(thread_id) = thread_id {
+        chain.push((NotificationScope::Thread, thread_id));
+    }
+    chain.push((NotificationScope::Channel, channel_id));
+    if let Some((guild_id, category_id, parent_id)) = row {
+        chain.extend(category_id.map(|id| (NotificationScope::Category, id)));
+        chain.extend(parent_id.map(|id| (NotificationScope::Category, id)));
+        chain.extend(guild_id.map(|id| (NotificationScope::Guild, id)));
+    }
+    Ok(chain)
+}
+
+/// Effective notification settings of a user for a channel or a thread in it.
+pub async fn effective_settings(
+    pool: &PgPool,
+    user_id: Uuid,
+    channel_id: Uuid,
+    thread_id: Option<Uuid>,
+) -> sqlx::Result<EffectiveNotificationSettings> {
+    let chain = scope_chain(pool, channel_id, thread_id).await?;
+    let ids: Vec<Uuid> = chain.iter().map(|&(_, id)| id).collect();
+
+    let rows: Vec<NotificationSetting> = sqlx::query_as(
+        r"
+        SELECT ns.scope, ns.scope_id, ns.level, ns.muted, ns.muted_until,
+               ns.suppress_everyone, ns.suppress_roles, ns.updated_at
+        FROM notification_settings ns
+        WHERE ns.user_id = $1 AND ns.scope_id = ANY($2)
+        ",
+    )
+    .bind(user_id)
+    .bind(&ids)
+    .fetch_all(pool)
+    .await?;
+
+    let ordered: Vec<_> = chain
+        .iter()
+        .filter_map(|&(scope, id)| {
+            rows.iter()
+                .find(|r| r.scope == scope && r.scope_id == id)
+                .cloned()
+        })
+        .collect();
+
+    Ok(resolve(&ordered, Utc::now()))
+}
+
+/// Whether a message in a channel (or thread) should notify a user.
+///
+/// Push and email delivery go through this so they respect the same settings
+/// as unread badges.
+pub async fn should_notify(
+    pool: &PgPool,
+    user_id: Uuid,
+    channel_id: Uuid,
+    thread_id: Option<Uuid>,
+    mention: Option<MentionKind>,
+) -> sqlx::Result<bool> {
+    Ok(effective_settings(pool, user_id, channel_id, thread_id)
+        .await?
+        .should_notify(mention))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn setting(scope: NotificationScope, level: Option<NotificationLevel>) -> NotificationSetting {
+        NotificationSetting {
+            scope,
+            scope_id: Uuid::new_v4(),
+            level,
+            muted: false,
+            muted_until: None,
+            suppress_everyone: false,
+            suppress_roles: false,
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn defaults_to_all_messages() {
+        let effective = resolve(&[], Utc::now());
+        assert_eq!(effective, EffectiveNotificationSettings::default());
+        assert!(effective.should_notify(None));
+    }
+
+    #[test]
+    fn most_specific_level_wins() {
+        let chain = [
+            setting(NotificationScope::Thread, None),
+            setting(NotificationScope::Channel, Some(NotificationLevel::All)),
+            setting(NotificationScope::Guild, Some(NotificationLevel::Nothing)),
+        ];
+        assert_eq!(resolve(&chain, Utc::now()).level, NotificationLevel::All);
+
+        let chain = [
+            setting(NotificationScope::Channel, None),
+            setting(
+                NotificationScope::Category,
+                Some(NotificationLevel::Mentions),
+            ),
+            setting(NotificationScope::Guild, Some(NotificationLevel::All)),
+        ];
+        assert_eq!(
+            resolve(&chain, Utc::now()).level,
+            NotificationLevel::Mentions
+        );
+    }
+
+    #[test]
+    fn mute_anywhere_in_chain_silences() {
+        let now = Utc::now();
+        let mut guild = setting(NotificationScope::Guild, None);
+        guild.muted = true;
+        guild.muted_until = Some(now + Duration::hours(1));
+        let chain = [
+            setting(NotificationScope::Channel, Some(NotificationLevel::All)),
+            guild,
+        ];
+
+        let effective = resolve(&chain, now);
+        assert!(effective.muted);
+        assert_eq!(effective.muted_until, Some(now + Duration::hours(1)));
+        assert!(!effective.should_notify(Some(MentionKind::Direct)));
+
+        // Expired mutes no longer apply
+        let later = resolve(&chain, now + Duration::hours(2));
+        assert!(!later.muted);
+        assert!(later.should_notify(None));
+    }
+
+    #[test]
+    fn mentions_level_and_suppression() {
+        let mut guild = setting(NotificationScope::Guild, Some(NotificationLevel::Mentions));
+        guild.suppress_everyone = true;
+        let effective = resolve(&[guild], Utc::now());
+
+        assert!(!effective.should_notify(None));
+        assert!(effective.should_notify(Some(MentionKind::Direct)));
+        assert!(effective.should_notify(Some(MentionKind::Role)));
+        assert!(!effective.should_notify(Some(MentionKind::Everyone)));
+    }
+
+    #[test]
+    fn detects_mentions() {
+        assert_eq!(
+            MentionKind::detect("hey @Alice!", "alice", &[]),
+            Some(MentionKind::Direct)
+        );
+        assert_eq!(MentionKind::detect("hey @alice_2", "alice", &[]), None);
+        assert_eq!(
+            MentionKind::detect("@here @alice", "alice", &[]),
+            Some(MentionKind::Direct)
+        );
+        assert_eq!(
+            MentionKind::detect("@everyone look", "alice", &[]),
+            Some(MentionKind::Everyone)
+        );
+        assert_eq!(MentionKind::detect("alice@example.com", "bob", &[]), None);
+    }
+
+    #[test]
+    fn detects_role_mentions_of_held_roles() {
+        let held = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let content = format!("@everyone <@&{held}> standup");
+
+        assert_eq!(
+            MentionKind::detect(&content, "alice", &[other, held]),
+            Some(MentionKind::Role)
+        );
+        assert_eq!(
+            MentionKind::detect(&content, "alice", &[other]),
+            Some(MentionKind::Everyone)
+        );
+        assert_eq!(
+            MentionKind::detect(&format!("<@&{held}> @alice"), "alice", &[held]),
+            Some(MentionKind::Direct)
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! Notification Settings Request/Response Types
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// Scope a notification setting applies to, from broadest to narrowest.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema,
+)]
+#[sqlx(type_name = "notification_scope", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationScope {
+    Guild,
+    Category,
+    Channel,
+    Thread,
+}
+
+/// Which messages produce notifications and unread badges.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema,
+)]
+#[sqlx(type_name = "notification_level", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationLevel {
+    /// Every message.
+    #[default]
+    All,
+    /// Only messages that mention the user.
+    Mentions,
+    /// Nothing at all.
+    Nothing,
+}
+
+// ============================================================================
+// Database Row Types
+// ============================================================================
+
+/// A stored notification setting for one scope.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, utoipa::ToSchema)]
+pub struct NotificationSetting {
+    pub scope: NotificationScope,
+    pub scope_id: Uuid,
+    /// `null` inherits the level from the enclosing scope.
+    pub level: Option<NotificationLevel>,
+    pub muted: bool,
+    /// When the mute expires. `null` with `muted` mutes indefinitely.
+    pub muted_until: Option<DateTime<Utc>>,
+    /// Ignore `@everyone` and `@here` (guild scope only).
+    pub suppress_everyone: bool,
+    /// Ignore role mentions (guild scope only).
+    pub suppress_roles: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NotificationSetting {
+    /// Whether this setting mutes its scope at `now`.
+    #[must_use]
+    pub fn is_muted_at(&self, now: DateTime<Utc>) -> bool {
+        self.muted && self.muted_until.is_none_or(|until| until > now)
+    }
+}
+
+// ============================================================================
+// API Request/Response Types
+// ============================================================================
+
+/// Request body for creating or replacing a notification setting.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct UpdateNotificationSettingRequest {
+    /// `null` or omitted inherits the level from the enclosing scope.
+    #[serde(default)]
+    pub level: Option<NotificationLevel>,
+    #[serde(default)]
+    pub muted: bool,
+    /// Requires `muted`; must be in the future.
+    #[serde(default)]
+    pub muted_until: Option<DateTime<Utc>>,
+    /// Guild scope only.
+    #[serde(default)]
+    pub suppress_everyone: bool,
+    /// Guild scope only.
+    #[serde(default)]
+    pub suppress_roles: bool,
+}
+
+/// Notification settings after resolving the scope chain for a channel or thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct EffectiveNotificationSettings {
+    pub level: NotificationLevel,
+    /// Whether any enclosing scope is currently muted.
+    pub muted: bool,
+    /// Latest expiry among the active mutes, `null` if muted indefinitely or not muted.
+    pub muted_until: Option<DateTime<Utc>>,
+    pub suppress_everyone: bool,
+    pub suppress_roles: bool,
+}
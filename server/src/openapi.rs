@@ -7,7 +7,7 @@
     reason = "triggered by utoipa OpenApi derive macro"
 )]
 
-use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
 use utoipa::{Modify, OpenApi};
 
 /// `OpenAPI` documentation for the `Kaiku` API.
@@ -30,6 +30,7 @@ use utoipa::{Modify, OpenApi};
         (name = "invites", description = "Guild invite management"),
         (name = "categories", description = "Channel category management"),
         (name = "emojis", description = "Custom emoji management"),
+        (name = "stickers", description = "Guild sticker management"),
         (name = "search", description = "Search endpoints"),
         (name = "admin", description = "System administration"),
         (name = "moderation", description = "Content moderation and reports"),
@@ -44,6 +45,9 @@ use utoipa::{Modify, OpenApi};
         (name = "reactions", description = "Message reactions"),
         (name = "unread", description = "Unread message tracking"),
         (name = "preferences", description = "User preferences"),
+        (name = "notifications", description = "Notification levels and mutes"),
+        (name = "push", description = "Push notification device registrations"),
+        (name = "email-digest", description = "Email digests of missed mentions and DMs"),
         (name = "pages", description = "Platform and guild pages"),
         (name = "connectivity", description = "Connection and session info"),
         (name = "discovery", description = "Public guild discovery and browsing"),
@@ -97,6 +101,13 @@ use utoipa::{Modify, OpenApi};
         crate::chat::messages::delete,
         crate::chat::messages::list_thread_replies,
         crate::chat::messages::mark_thread_read,
+        crate::chat::components::interact,
+        crate::chat::polls::vote,
+        crate::chat::polls::retract_vote,
+        crate::chat::polls::list_voters,
+        crate::chat::forwarding::forward,
+        crate::chat::bulk_delete::bulk_delete,
+        crate::chat::slowmode::set_thread_slowmode,
         // Uploads
         crate::chat::uploads::upload_message_with_file,
         crate::chat::uploads::upload_file,
@@ -119,6 +130,9 @@ use utoipa::{Modify, OpenApi};
         crate::chat::overrides::list_overrides,
         crate::chat::overrides::set_override,
         crate::chat::overrides::delete_override,
+        crate::chat::encryption::get_encryption,
+        crate::chat::encryption::enable_encryption,
+        crate::chat::disappearing::set_disappearing_messages,
         // Guilds
         crate::guild::handlers::list_guilds,
         crate::guild::handlers::create_guild,
@@ -134,7 +148,13 @@ use utoipa::{Modify, OpenApi};
         crate::guild::handlers::list_guild_bots,
         crate::guild::handlers::add_bot_to_guild,
         crate::guild::handlers::remove_bot_from_guild,
+        crate::guild::handlers::approve_bot_intents,
+        crate::guild::handlers::approve_bot_scopes,
         crate::guild::handlers::list_guild_commands,
+        crate::guild::handlers::command_autocomplete,
+        crate::guild::command_permissions::list_command_permissions,
+        crate::guild::command_permissions::set_command_permissions,
+        crate::guild::command_permissions::delete_command_permissions,
         crate::guild::handlers::get_guild_settings,
         crate::guild::handlers::update_guild_settings,
         crate::guild::handlers::get_guild_usage,
@@ -162,6 +182,12 @@ use utoipa::{Modify, OpenApi};
         crate::guild::emojis::create_emoji,
         crate::guild::emojis::update_emoji,
         crate::guild::emojis::delete_emoji,
+        crate::guild::stickers::list_stickers,
+        crate::guild::stickers::list_available_stickers,
+        crate::guild::stickers::get_sticker,
+        crate::guild::stickers::create_sticker,
+        crate::guild::stickers::update_sticker,
+        crate::guild::stickers::delete_sticker,
         // Guild Search
         crate::guild::search::search_messages,
         // Discovery
@@ -243,9 +269,22 @@ use utoipa::{Modify, OpenApi};
         crate::crypto::handlers::get_backup,
         crate::crypto::handlers::upload_backup,
         crate::crypto::handlers::get_backup_status,
+        crate::crypto::key_backup::create_backup_version,
+        crate::crypto::key_backup::get_backup_version,
+        crate::crypto::key_backup::delete_backup_version,
+        crate::crypto::key_backup::put_backup_session,
+        crate::crypto::key_backup::get_backup_session,
+        crate::crypto::key_backup::get_backup_sessions,
         crate::crypto::handlers::get_own_devices,
+        crate::crypto::devices::rename_device,
+        crate::crypto::devices::delete_device,
         crate::crypto::handlers::get_user_keys,
         crate::crypto::handlers::claim_prekey,
+        crate::crypto::cross_signing::upload_cross_signing_keys,
+        crate::crypto::cross_signing::upload_signatures,
+        crate::crypto::to_device::send_to_device,
+        crate::crypto::to_device::get_pending,
+        crate::crypto::to_device::ack,
         // Bots
         crate::api::bots::list_applications,
         crate::api::bots::create_application,
@@ -254,6 +293,16 @@ use utoipa::{Modify, OpenApi};
         crate::api::bots::create_bot,
         crate::api::bots::reset_bot_token,
         crate::api::bots::update_gateway_intents,
+        crate::api::bots::update_bot_scopes,
+        // Bot REST API
+        crate::bot_api::handlers::list_messages,
+        crate::bot_api::handlers::edit_message,
+        crate::bot_api::handlers::delete_message,
+        crate::bot_api::handlers::add_reaction,
+        crate::bot_api::handlers::list_members,
+        crate::bot_api::handlers::assign_role,
+        crate::bot_api::handlers::remove_role,
+        crate::bot_api::handlers::upload_attachment,
         // Commands
         crate::api::commands::list_commands,
         crate::api::commands::register_commands,
@@ -283,6 +332,23 @@ use utoipa::{Modify, OpenApi};
         crate::api::favorites::reorder_guilds,
         crate::api::favorites::add_favorite,
         crate::api::favorites::remove_favorite,
+        // Notifications
+        crate::notifications::handlers::list_settings,
+        crate::notifications::handlers::get_setting,
+        crate::notifications::handlers::update_setting,
+        crate::notifications::handlers::delete_setting,
+        crate::notifications::handlers::get_effective_settings,
+        // Push
+        crate::push::handlers::get_vapid_key,
+        crate::push::handlers::list_registrations,
+        crate::push::handlers::register,
+        crate::push::handlers::unregister,
+        crate::push::handlers::delete_registration,
+        // Email digests
+        crate::digest::handlers::get_settings,
+        crate::digest::handlers::update_settings,
+        crate::digest::handlers::unsubscribe_page,
+        crate::digest::handlers::confirm_unsubscribe,
         // Workspaces
         crate::workspaces::handlers::create_workspace,
         crate::workspaces::handlers::list_workspaces,
@@ -295,6 +361,7 @@ use utoipa::{Modify, OpenApi};
         crate::workspaces::handlers::reorder_workspaces,
         // Unread
         crate::api::unread::get_unread_aggregate,
+        crate::chat::mentions::list_mentions,
         crate::api::unread::mark_all_read,
         // Preferences
         crate::api::preferences::get_preferences,
@@ -387,6 +454,28 @@ use utoipa::{Modify, OpenApi};
         crate::chat::messages::ListThreadRepliesQuery,
         crate::chat::messages::UpdateMessageRequest,
         crate::chat::messages::CursorPaginatedResponse<crate::chat::messages::MessageResponse>,
+        crate::chat::mentions::MessageMentionKind,
+        crate::chat::mentions::MentionResponse,
+        crate::chat::messages::CursorPaginatedResponse<crate::chat::mentions::MentionResponse>,
+        crate::chat::components::ActionRow,
+        crate::chat::components::Component,
+        crate::chat::components::Sticker,
+        crate::chat::components::Button,
+        crate::chat::components::ButtonStyle,
+        crate::chat::components::SelectMenu,
+        crate::chat::components::SelectOption,
+        crate::chat::components::ComponentInteractionRequest,
+        crate::chat::components::ComponentInteractionResponse,
+        crate::chat::polls::CreatePollRequest,
+        crate::chat::polls::PollResponse,
+        crate::chat::polls::PollOptionResponse,
+        crate::chat::polls::PollVoteRequest,
+        crate::chat::polls::PollOptionVoters,
+        crate::chat::forwarding::ForwardMessageRequest,
+        crate::chat::forwarding::ForwardedFrom,
+        crate::chat::bulk_delete::BulkDeleteRequest,
+        crate::chat::bulk_delete::BulkDeleteResponse,
+        crate::chat::slowmode::ThreadSlowmodeRequest,
         // Chat - DM
         crate::chat::dm::CreateDMRequest,
         crate::chat::dm::DMResponse,
@@ -405,6 +494,9 @@ use utoipa::{Modify, OpenApi};
         // Chat - Overrides
         crate::chat::overrides::OverrideResponse,
         crate::chat::overrides::SetOverrideRequest,
+        crate::chat::encryption::ChannelEncryptionResponse,
+        crate::chat::disappearing::DisappearingMessagesRequest,
+        crate::chat::disappearing::DisappearingMessagesResponse,
         // Guild
         crate::guild::types::Guild,
         crate::guild::types::GuildWithMemberCount,
@@ -421,13 +513,22 @@ use utoipa::{Modify, OpenApi};
         crate::guild::types::GuildEmoji,
         crate::guild::types::CreateEmojiRequest,
         crate::guild::types::UpdateEmojiRequest,
+        crate::guild::types::GuildSticker,
+        crate::guild::types::CreateStickerRequest,
+        crate::guild::types::UpdateStickerRequest,
         crate::guild::types::GuildSettings,
         crate::guild::types::UpdateGuildSettingsRequest,
         crate::guild::types::GuildCommandInfo,
+        crate::guild::types::CommandAutocompleteRequest,
+        crate::guild::types::CommandAutocompleteResponse,
+        crate::permissions::CommandPermissionOverride,
+        crate::permissions::SetCommandPermissionsRequest,
         crate::guild::handlers::UsageStat,
         crate::guild::handlers::GuildUsageStats,
         crate::guild::handlers::ChannelWithUnread,
         crate::guild::handlers::InstalledBot,
+        crate::guild::handlers::ApproveBotIntentsRequest,
+        crate::guild::handlers::ApproveBotScopesRequest,
         crate::guild::handlers::ChannelPosition,
         crate::guild::handlers::ReorderChannelsRequest,
         // Guild - Categories
@@ -513,6 +614,26 @@ use utoipa::{Modify, OpenApi};
         crate::api::bots::CreateApplicationRequest,
         crate::api::bots::ApplicationResponse,
         crate::api::bots::BotTokenResponse,
+        crate::api::bots::UpdateScopesRequest,
+        // Bot REST API
+        crate::bot_api::types::BotGuildMember,
+        crate::bot_api::types::BotReactionRequest,
+        // Notifications
+        crate::notifications::types::NotificationScope,
+        crate::notifications::types::NotificationLevel,
+        crate::notifications::types::NotificationSetting,
+        crate::notifications::types::UpdateNotificationSettingRequest,
+        crate::notifications::types::EffectiveNotificationSettings,
+        // Push
+        crate::push::types::PushProvider,
+        crate::push::types::PushRegistration,
+        crate::push::types::PushSubscriptionKeys,
+        crate::push::types::RegisterPushRequest,
+        crate::push::types::VapidKeyResponse,
+        // Email digests
+        crate::digest::types::DigestFrequency,
+        crate::digest::types::EmailDigestSettings,
+        crate::digest::types::UpdateEmailDigestRequest,
         // Workspaces
         crate::workspaces::types::WorkspaceResponse,
         crate::workspaces::types::WorkspaceListItem,
@@ -548,5 +669,12 @@ impl Modify for SecurityAddon {
                     .build(),
             ),
         );
+        components.add_security_scheme(
+            "bot_token",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::with_description(
+                "Authorization",
+                "Bot token in the form `Bot <token>`",
+            ))),
+        );
     }
 }
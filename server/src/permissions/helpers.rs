@@ -6,8 +6,8 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use super::guild::GuildPermissions;
-use super::models::GuildRole;
-use super::resolver::{compute_guild_permissions, PermissionError};
+use super::models::{CommandPermissionOverride, GuildRole};
+use super::resolver::{can_use_command, compute_guild_permissions, PermissionError};
 
 /// Pre-computed permission context for a guild member.
 ///
@@ -57,6 +57,36 @@ impl MemberPermissionContext {
             Err(PermissionError::MissingPermission(permission))
         }
     }
+
+    /// IDs of all roles the member holds, including @everyone.
+    #[must_use]
+    pub fn role_ids(&self) -> Vec<Uuid> {
+        self.member_roles
+            .iter()
+            .map(|r| r.id)
+            .chain(self.everyone_role_id)
+            .collect()
+    }
+
+    /// Check whether the member may use a bot's slash command.
+    ///
+    /// See [`can_use_command`] for the resolution order. `computed_permissions`
+    /// should already include channel overrides when `channel_id` is given.
+    pub fn can_use_command(
+        &self,
+        channel_id: Option<Uuid>,
+        default_member_permissions: Option<GuildPermissions>,
+        guild_override: Option<&CommandPermissionOverride>,
+    ) -> Result<(), PermissionError> {
+        can_use_command(
+            self.is_owner,
+            self.computed_permissions,
+            &self.role_ids(),
+            channel_id,
+            default_member_permissions,
+            guild_override,
+        )
+    }
 }
 
 /// Load permission context for a guild member.
@@ -102,7 +132,19 @@ pub async fn get_member_permission_context(
         return Ok(None);
     };
 
-    let is_owner = guild_info.guild_owner_id == user_id;
+    load_permission_context(pool, guild_id, user_id, guild_info.guild_owner_id)
+        .await
+        .map(Some)
+}
+
+/// Load @everyone and the user's assigned roles and compute guild permissions.
+async fn load_permission_context(
+    pool: &PgPool,
+    guild_id: Uuid,
+    user_id: Uuid,
+    guild_owner_id: Uuid,
+) -> sqlx::Result<MemberPermissionContext> {
+    let is_owner = guild_owner_id == user_id;
 
     // Get @everyone role permissions
     let everyone_role: Option<GuildRole> = sqlx::query_as(
@@ -140,21 +182,21 @@ pub async fn get_member_permission_context(
     // Compute permissions
     let computed_permissions = compute_guild_permissions(
         user_id,
-        guild_info.guild_owner_id,
+        guild_owner_id,
         everyone_permissions,
         &member_roles,
         None, // No channel overrides for guild-level context
     );
 
-    Ok(Some(MemberPermissionContext {
-        guild_owner_id: guild_info.guild_owner_id,
+    Ok(MemberPermissionContext {
+        guild_owner_id,
         everyone_permissions,
         everyone_role_id,
         member_roles,
         computed_permissions,
         highest_role_position,
         is_owner,
-    }))
+    })
 }
 
 /// Load permission context and require a specific permission.
@@ -287,6 +329,219 @@ pub async fn require_channel_access(
     })
 }
 
+/// Check whether a member may use a bot's slash command in a channel.
+///
+/// Combines channel access (`VIEW_CHANNEL` with overrides), the guild's
+/// command permission override and the command's default member permissions.
+///
+/// # Errors
+///
+/// Returns:
+/// - Any error from [`require_channel_access`]
+/// - `PermissionError::Forbidden` if the command is disabled, not allowed in the
+///   channel, or restricted to roles the member lacks
+/// - `PermissionError::MissingPermission` if the member lacks the default member permissions
+#[tracing::instrument(skip(pool))]
+pub async fn require_command_permission(
+    pool: &PgPool,
+    guild_id: Uuid,
+    user_id: Uuid,
+    channel_id: Uuid,
+    application_id: Uuid,
+    command_name: &str,
+    default_member_permissions: Option<GuildPermissions>,
+) -> Result<(), PermissionError> {
+    let ctx = require_channel_access(pool, user_id, channel_id).await?;
+
+    let guild_override = super::queries::get_command_permission_override(
+        pool,
+        guild_id,
+        application_id,
+        command_name,
+    )
+    .await
+    .map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
+
+    ctx.can_use_command(
+        Some(channel_id),
+        default_member_permissions,
+        guild_override.as_ref(),
+    )
+}
+
+/// Find the guild's @everyone role and its permissions, plus the permissions
+/// it has in `channel_id` once the @everyone channel override is applied.
+async fn everyone_channel_permissions(
+    pool: &PgPool,
+    guild_id: Uuid,
+    channel_id: Uuid,
+) -> sqlx::Result<(Option<Uuid>, GuildPermissions, GuildPermissions)> {
+    let everyone_role: Option<GuildRole> = sqlx::query_as(
+        r"
+        SELECT id, guild_id, name, color, permissions, position, is_default, created_at, updated_at
+        FROM guild_roles
+        WHERE guild_id = $1 AND is_default = true
+        ",
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+
+    let everyone_role_id = everyone_role.as_ref().map(|r| r.id);
+    let everyone_permissions = everyone_role.map(|r| r.permissions).unwrap_or_default();
+
+    let mut perms = everyone_permissions;
+    if let Some(everyone_role_id) = everyone_role_id {
+        let overrides = crate::db::get_channel_overrides(pool, channel_id).await?;
+        if let Some(override_entry) = overrides.iter().find(|ovr| ovr.role_id == everyone_role_id) {
+            perms |= override_entry.allow_permissions;
+            perms &= !override_entry.deny_permissions;
+        }
+    }
+
+    Ok((everyone_role_id, everyone_permissions, perms))
+}
+
+/// Permissions installed bots have in a guild channel.
+///
+/// Bots are not guild members, so every bot gets the @everyone role's
+/// permissions with the @everyone channel override applied.
+pub async fn bot_channel_permissions(
+    pool: &PgPool,
+    guild_id: Uuid,
+    channel_id: Uuid,
+) -> sqlx::Result<GuildPermissions> {
+    let (_, _, perms) = everyone_channel_permissions(pool, guild_id, channel_id).await?;
+    Ok(perms)
+}
+
+/// Check whether a bot application can view a specific channel.
+///
+/// Bots are not guild members, so their permissions come from the guild's
+/// @everyone role plus any @everyone channel override, and only apply in
+/// guilds where the application is installed.
+///
+/// **Resolution order:**
+/// 1. DM channels → bot must be a participant
+/// 2. Guild channels → application installed and `VIEW_CHANNEL` granted to @everyone
+///
+/// # Errors
+///
+/// Returns:
+/// - `PermissionError::NotFound` if channel doesn't exist
+/// - `PermissionError::InvalidChannel` if guild channel missing `guild_id`
+/// - `PermissionError::Forbidden` if bot is not a DM participant
+/// - `PermissionError::NotGuildMember` if the application is not installed in the guild
+/// - `PermissionError::MissingPermission` if @everyone lacks `VIEW_CHANNEL`
+/// - `PermissionError::DatabaseError` on database errors
+#[tracing::instrument(skip(pool))]
+pub async fn require_bot_channel_access(
+    pool: &PgPool,
+    application_id: Uuid,
+    bot_user_id: Uuid,
+    channel_id: Uuid,
+) -> Result<MemberPermissionContext, PermissionError> {
+    let channel = crate::db::get_channel_by_id(pool, channel_id)
+        .await
+        .map_err(|e| PermissionError::DatabaseError(e.to_string()))?
+        .ok_or(PermissionError::NotFound)?;
+
+    if channel.channel_type == crate::db::ChannelType::Dm {
+        let is_participant = crate::db::is_dm_participant(pool, channel_id, bot_user_id)
+            .await
+            .map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
+
+        if !is_participant {
+            return Err(PermissionError::Forbidden);
+        }
+
+        return Ok(MemberPermissionContext {
+            guild_owner_id: Uuid::nil(),
+            everyone_permissions: GuildPermissions::empty(),
+            everyone_role_id: None,
+            member_roles: vec![],
+            computed_permissions: GuildPermissions::all(),
+            highest_role_position: None,
+            is_owner: false,
+        });
+    }
+
+    let guild_id = channel.guild_id.ok_or(PermissionError::InvalidChannel)?;
+
+    let guild_info: Option<GuildInfo> = sqlx::query_as(
+        r"
+        SELECT g.owner_id as guild_owner_id
+        FROM guilds g
+        INNER JOIN guild_bot_installations gbi ON gbi.guild_id = g.id
+        WHERE g.id = $1 AND gbi.application_id = $2
+        ",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
+
+    let guild_info = guild_info.ok_or(PermissionError::NotGuildMember)?;
+
+    let (everyone_role_id, everyone_permissions, perms) =
+        everyone_channel_permissions(pool, guild_id, channel_id)
+            .await
+            .map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
+
+    if !perms.has(GuildPermissions::VIEW_CHANNEL) {
+        return Err(PermissionError::MissingPermission(
+            GuildPermissions::VIEW_CHANNEL,
+        ));
+    }
+
+    Ok(MemberPermissionContext {
+        guild_owner_id: guild_info.guild_owner_id,
+        everyone_permissions,
+        everyone_role_id,
+        member_roles: vec![],
+        computed_permissions: perms,
+        highest_role_position: None,
+        is_owner: false,
+    })
+}
+
+/// Load the guild-level permission context for a bot installed in a guild.
+///
+/// Bots are not guild members: their guild permissions are @everyone plus any
+/// roles a guild admin assigned to the bot user, and those roles set their rank.
+///
+/// # Errors
+/// - `PermissionError::NotGuildMember` if the application is not installed in the guild
+/// - `PermissionError::DatabaseError` on database errors
+#[tracing::instrument(skip(pool))]
+pub async fn bot_guild_permission_context(
+    pool: &PgPool,
+    guild_id: Uuid,
+    application_id: Uuid,
+    bot_user_id: Uuid,
+) -> Result<MemberPermissionContext, PermissionError> {
+    let guild_info: Option<GuildInfo> = sqlx::query_as(
+        r"
+        SELECT g.owner_id as guild_owner_id
+        FROM guilds g
+        INNER JOIN guild_bot_installations gbi ON gbi.guild_id = g.id
+        WHERE g.id = $1 AND gbi.application_id = $2
+        ",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
+
+    let guild_info = guild_info.ok_or(PermissionError::NotGuildMember)?;
+
+    load_permission_context(pool, guild_id, bot_user_id, guild_info.guild_owner_id)
+        .await
+        .map_err(|e| PermissionError::DatabaseError(e.to_string()))
+}
+
 /// Filter a list of guild channel IDs down to those the user can view.
 ///
 /// Fetches membership + roles once, batch-fetches all channel overrides in a single query,
@@ -362,6 +617,160 @@ pub async fn filter_accessible_channels(
     Ok(accessible)
 }
 
+/// Filter any mix of guild and DM channel IDs down to those the user can view.
+///
+/// DM channels require participation; guild channels are grouped by guild and
+/// checked with [`filter_accessible_channels`]. Channels in guilds the user
+/// isn't a member of, and unknown channels, are dropped rather than erroring.
+#[tracing::instrument(skip(pool, channel_ids))]
+pub async fn filter_viewable_channels(
+    pool: &PgPool,
+    user_id: Uuid,
+    channel_ids: &[Uuid],
+) -> Result<std::collections::HashSet<Uuid>, PermissionError> {
+    let mut viewable = std::collections::HashSet::new();
+    if channel_ids.is_empty() {
+        return Ok(viewable);
+    }
+
+    let channels: Vec<(Uuid, Option<Uuid>, bool)> = sqlx::query_as(
+        r"
+        SELECT c.id, c.guild_id,
+               EXISTS(SELECT 1 FROM dm_participants dp
+                      WHERE dp.channel_id = c.id AND dp.user_id = $2)
+        FROM channels c
+        WHERE c.id = ANY($1)
+        ",
+    )
+    .bind(channel_ids)
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| PermissionError::DatabaseError(e.to_string()))?;
+
+    let mut by_guild: std::collections::HashMap<Uuid, Vec<Uuid>> = std::collections::HashMap::new();
+    for (channel_id, guild_id, is_participant) in channels {
+        match guild_id {
+            Some(guild_id) => by_guild.entry(guild_id).or_default().push(channel_id),
+            None if is_participant => {
+                viewable.insert(channel_id);
+            }
+            None => {}
+        }
+    }
+
+    for (guild_id, ids) in by_guild {
+        match filter_accessible_channels(pool, guild_id, user_id, &ids).await {
+            Ok(accessible) => viewable.extend(accessible),
+            Err(PermissionError::NotGuildMember) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(viewable)
+}
+
+/// Filter a list of users down to the guild members who can view a channel.
+///
+/// Loads the guild owner, `@everyone` role, channel overrides and the roles of
+/// all candidates up front, then computes `VIEW_CHANNEL` in-memory per user.
+/// Non-members are dropped.
+///
+/// **Result: 4 queries regardless of user count.**
+#[tracing::instrument(skip(pool, user_ids))]
+pub async fn filter_channel_viewers(
+    pool: &PgPool,
+    guild_id: Uuid,
+    channel_id: Uuid,
+    user_ids: &[Uuid],
+) -> sqlx::Result<Vec<Uuid>> {
+    if user_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let members: Vec<(Uuid, Uuid)> = sqlx::query_as(
+        r"
+        SELECT gm.user_id, g.owner_id
+        FROM guild_members gm
+        INNER JOIN guilds g ON g.id = gm.guild_id
+        WHERE gm.guild_id = $1 AND gm.user_id = ANY($2)
+        ",
+    )
+    .bind(guild_id)
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await?;
+    if members.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let everyone_role: Option<GuildRole> = sqlx::query_as(
+        r"
+        SELECT id, guild_id, name, color, permissions, position, is_default, created_at, updated_at
+        FROM guild_roles
+        WHERE guild_id = $1 AND is_default = true
+        ",
+    )
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await?;
+    let everyone_role_id = everyone_role.as_ref().map(|r| r.id);
+    let everyone_permissions = everyone_role.map(|r| r.permissions).unwrap_or_default();
+
+    let member_roles: Vec<MemberRoleRow> = sqlx::query_as(
+        r"
+        SELECT gmr.user_id, r.id, r.guild_id, r.name, r.color, r.permissions, r.position,
+               r.is_default, r.created_at, r.updated_at
+        FROM guild_roles r
+        INNER JOIN guild_member_roles gmr ON gmr.role_id = r.id
+        WHERE gmr.guild_id = $1 AND gmr.user_id = ANY($2)
+        ",
+    )
+    .bind(guild_id)
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await?;
+    let mut roles_by_user: std::collections::HashMap<Uuid, Vec<GuildRole>> =
+        std::collections::HashMap::new();
+    for row in member_roles {
+        roles_by_user.entry(row.user_id).or_default().push(row.role);
+    }
+
+    let overrides = crate::db::get_channel_overrides(pool, channel_id).await?;
+    let everyone_override =
+        everyone_role_id.and_then(|id| overrides.iter().find(|ovr| ovr.role_id == id));
+
+    Ok(members
+        .into_iter()
+        .filter(|&(user_id, owner_id)| {
+            let roles = roles_by_user.get(&user_id).map_or(&[][..], Vec::as_slice);
+            let mut perms = compute_guild_permissions(
+                user_id,
+                owner_id,
+                everyone_permissions,
+                roles,
+                Some(&overrides),
+            );
+            if user_id != owner_id {
+                if let Some(override_entry) = everyone_override {
+                    perms |= override_entry.allow_permissions;
+                    perms &= !override_entry.deny_permissions;
+                }
+            }
+            perms.has(GuildPermissions::VIEW_CHANNEL)
+        })
+        .map(|(user_id, _)| user_id)
+        .collect())
+}
+
+/// Internal struct for the batched member role query.
+#[derive(Debug, sqlx::FromRow)]
+struct MemberRoleRow {
+    user_id: Uuid,
+    #[sqlx(flatten)]
+    role: GuildRole,
+}
+
 /// Internal struct for guild membership query.
 #[derive(Debug, sqlx::FromRow)]
 struct GuildInfo {
@@ -13,12 +13,15 @@ pub mod system;
 
 pub use guild::GuildPermissions;
 pub use helpers::{
-    filter_accessible_channels, get_member_permission_context, require_channel_access,
+    bot_channel_permissions, bot_guild_permission_context, filter_accessible_channels,
+    filter_channel_viewers, filter_viewable_channels, get_member_permission_context,
+    require_bot_channel_access, require_channel_access, require_command_permission,
     require_guild_permission, MemberPermissionContext,
 };
 pub use models::*;
 pub use queries::*;
 pub use resolver::{
-    can_manage_role, can_moderate_member, compute_guild_permissions, PermissionError,
+    can_manage_role, can_moderate_member, can_use_command, compute_guild_permissions,
+    PermissionError,
 };
 pub use system::SystemPermission;
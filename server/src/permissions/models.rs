@@ -64,6 +64,22 @@ pub struct ChannelOverride {
     pub deny_permissions: GuildPermissions,
 }
 
+/// Per-guild restrictions on a bot's slash command.
+#[derive(Debug, Clone, FromRow, Serialize, utoipa::ToSchema)]
+pub struct CommandPermissionOverride {
+    pub guild_id: Uuid,
+    pub application_id: Uuid,
+    pub command_name: String,
+    /// Disabled commands cannot be used by anyone.
+    pub enabled: bool,
+    /// Roles allowed to use the command (empty = default member permissions apply).
+    pub role_ids: Vec<Uuid>,
+    /// Channels the command can be used in (empty = all channels).
+    pub channel_ids: Vec<Uuid>,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// System audit log entry.
 #[derive(Debug, Clone, FromRow, Serialize)]
 pub struct AuditLogEntry {
@@ -168,6 +184,20 @@ pub struct SetChannelOverrideRequest {
     pub deny: Option<u64>,
 }
 
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct SetCommandPermissionsRequest {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub role_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub channel_ids: Vec<Uuid>,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ElevateSessionRequest {
     pub mfa_code: String,
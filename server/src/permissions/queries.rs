@@ -5,15 +5,19 @@
 //! - Elevated sessions (sudo-style)
 //! - Guild roles and member assignments
 //! - Channel permission overrides
+//! - Slash command permission overrides
 //! - Audit logging
 
 use chrono::{Duration, Utc};
 use serde_json::Value as JsonValue;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 use uuid::Uuid;
 
 use super::guild::GuildPermissions;
-use super::models::{AuditLogEntry, ChannelOverride, ElevatedSession, GuildRole, SystemAdmin};
+use super::models::{
+    AuditLogEntry, ChannelOverride, CommandPermissionOverride, ElevatedSession, GuildRole,
+    SystemAdmin,
+};
 
 // ============================================================================
 // System Admin Queries
@@ -537,6 +541,140 @@ pub async fn delete_channel_override(
     Ok(result.rows_affected() > 0)
 }
 
+// ============================================================================
+// Command Permission Queries
+// ============================================================================
+
+/// Get all slash command permission overrides for a guild.
+pub async fn get_command_permission_overrides(
+    pool: &PgPool,
+    guild_id: Uuid,
+) -> sqlx::Result<Vec<CommandPermissionOverride>> {
+    sqlx::query_as::<_, CommandPermissionOverride>(
+        r"
+        SELECT guild_id, application_id, command_name, enabled, role_ids, channel_ids,
+               updated_by, updated_at
+        FROM guild_command_permissions
+        WHERE guild_id = $1
+        ORDER BY command_name, application_id
+        ",
+    )
+    .bind(guild_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Get the permission override for a single slash command in a guild.
+pub async fn get_command_permission_override(
+    pool: &PgPool,
+    guild_id: Uuid,
+    application_id: Uuid,
+    command_name: &str,
+) -> sqlx::Result<Option<CommandPermissionOverride>> {
+    sqlx::query_as::<_, CommandPermissionOverride>(
+        r"
+        SELECT guild_id, application_id, command_name, enabled, role_ids, channel_ids,
+               updated_by, updated_at
+        FROM guild_command_permissions
+        WHERE guild_id = $1 AND application_id = $2 AND command_name = $3
+        ",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .bind(command_name)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Set or update the permission override for a slash command in a guild.
+#[allow(clippy::too_many_arguments)]
+pub async fn set_command_permission_override(
+    pool: &PgPool,
+    guild_id: Uuid,
+    application_id: Uuid,
+    command_name: &str,
+    enabled: bool,
+    role_ids: &[Uuid],
+    channel_ids: &[Uuid],
+    updated_by: Uuid,
+) -> sqlx::Result<CommandPermissionOverride> {
+    sqlx::query_as::<_, CommandPermissionOverride>(
+        r"
+        INSERT INTO guild_command_permissions
+            (guild_id, application_id, command_name, enabled, role_ids, channel_ids, updated_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (guild_id, application_id, command_name) DO UPDATE
+        SET enabled = EXCLUDED.enabled,
+            role_ids = EXCLUDED.role_ids,
+            channel_ids = EXCLUDED.channel_ids,
+            updated_by = EXCLUDED.updated_by,
+            updated_at = NOW()
+        RETURNING guild_id, application_id, command_name, enabled, role_ids, channel_ids,
+                  updated_by, updated_at
+        ",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .bind(command_name)
+    .bind(enabled)
+    .bind(role_ids)
+    .bind(channel_ids)
+    .bind(updated_by)
+    .fetch_one(pool)
+    .await
+}
+
+/// Delete the permission override for a slash command, restoring its defaults.
+///
+/// Returns `true` if an override was deleted, `false` if not found.
+pub async fn delete_command_permission_override(
+    pool: &PgPool,
+    guild_id: Uuid,
+    application_id: Uuid,
+    command_name: &str,
+) -> sqlx::Result<bool> {
+    let result = sqlx::query(
+        r"
+        DELETE FROM guild_command_permissions
+        WHERE guild_id = $1 AND application_id = $2 AND command_name = $3
+        ",
+    )
+    .bind(guild_id)
+    .bind(application_id)
+    .bind(command_name)
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete an application's overrides for commands it no longer registers.
+///
+/// Overrides are keyed by name so they survive re-registration, and go once
+/// no global or guild-scoped command of that name is left for the guild.
+pub async fn prune_command_permission_overrides(
+    executor: impl PgExecutor<'_>,
+    application_id: Uuid,
+) -> sqlx::Result<u64> {
+    let result = sqlx::query(
+        r"
+        DELETE FROM guild_command_permissions p
+        WHERE p.application_id = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM slash_commands c
+              WHERE c.application_id = p.application_id
+                AND c.name = p.command_name
+                AND (c.guild_id IS NULL OR c.guild_id = p.guild_id)
+          )
+        ",
+    )
+    .bind(application_id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
 // ============================================================================
 // Audit Log Queries
 // ============================================================================
@@ -5,7 +5,7 @@
 use uuid::Uuid;
 
 use super::guild::GuildPermissions;
-use super::models::{ChannelOverride, GuildRole};
+use super::models::{ChannelOverride, CommandPermissionOverride, GuildRole};
 
 /// Compute guild permissions for a user.
 ///
@@ -120,6 +120,57 @@ pub const fn can_moderate_member(
     Ok(())
 }
 
+/// Check whether a member may use a bot's slash command.
+///
+/// Resolution order:
+/// 1. A command disabled for the guild cannot be used by anyone
+/// 2. A channel allowlist limits where the command can be used (skipped without
+///    channel context, e.g. when listing commands)
+/// 3. Guild owner can use any enabled command
+/// 4. A role allowlist replaces the bot's default member permissions
+/// 5. Otherwise the member needs all of the command's default member permissions
+///
+/// `member_role_ids` should include the @everyone role so admins can allow it explicitly.
+pub fn can_use_command(
+    is_owner: bool,
+    member_permissions: GuildPermissions,
+    member_role_ids: &[Uuid],
+    channel_id: Option<Uuid>,
+    default_member_permissions: Option<GuildPermissions>,
+    guild_override: Option<&CommandPermissionOverride>,
+) -> Result<(), PermissionError> {
+    if let Some(ovr) = guild_override {
+        if !ovr.enabled {
+            return Err(PermissionError::Forbidden);
+        }
+        if let Some(channel_id) = channel_id {
+            if !ovr.channel_ids.is_empty() && !ovr.channel_ids.contains(&channel_id) {
+                return Err(PermissionError::Forbidden);
+            }
+        }
+    }
+
+    if is_owner {
+        return Ok(());
+    }
+
+    if let Some(ovr) = guild_override.filter(|o| !o.role_ids.is_empty()) {
+        if member_role_ids.iter().any(|id| ovr.role_ids.contains(id)) {
+            return Ok(());
+        }
+        return Err(PermissionError::Forbidden);
+    }
+
+    if let Some(required) = default_member_permissions {
+        let missing = required & !member_permissions;
+        if !missing.is_empty() {
+            return Err(PermissionError::MissingPermission(missing));
+        }
+    }
+
+    Ok(())
+}
+
 /// Permission check errors.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PermissionError {
@@ -581,4 +632,96 @@ mod tests {
         assert!(!perms_a.has(GuildPermissions::VIEW_CHANNEL));
         assert!(!perms_b.has(GuildPermissions::VIEW_CHANNEL));
     }
+
+    fn command_override(
+        enabled: bool,
+        role_ids: Vec<Uuid>,
+        channel_ids: Vec<Uuid>,
+    ) -> CommandPermissionOverride {
+        CommandPermissionOverride {
+            guild_id: Uuid::new_v4(),
+            application_id: Uuid::new_v4(),
+            command_name: "ban".to_string(),
+            enabled,
+            role_ids,
+            channel_ids,
+            updated_by: None,
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_command_default_member_permissions() {
+        let member = GuildPermissions::SEND_MESSAGES;
+        let required = Some(GuildPermissions::BAN_MEMBERS);
+
+        assert!(can_use_command(false, member, &[], None, None, None).is_ok());
+        assert_eq!(
+            can_use_command(false, member, &[], None, required, None),
+            Err(PermissionError::MissingPermission(
+                GuildPermissions::BAN_MEMBERS
+            ))
+        );
+        assert!(can_use_command(
+            false,
+            member | GuildPermissions::BAN_MEMBERS,
+            &[],
+            None,
+            required,
+            None
+        )
+        .is_ok());
+        // Owner bypasses default member permissions
+        assert!(can_use_command(true, member, &[], None, required, None).is_ok());
+    }
+
+    #[test]
+    fn test_command_role_override_replaces_defaults() {
+        let role_id = Uuid::new_v4();
+        let ovr = command_override(true, vec![role_id], vec![]);
+        let required = Some(GuildPermissions::BAN_MEMBERS);
+
+        // Listed role grants access without the default permissions
+        assert!(can_use_command(
+            false,
+            GuildPermissions::empty(),
+            &[role_id],
+            None,
+            required,
+            Some(&ovr)
+        )
+        .is_ok());
+        // Unlisted members are denied even with the default permissions
+        assert_eq!(
+            can_use_command(
+                false,
+                GuildPermissions::all(),
+                &[Uuid::new_v4()],
+                None,
+                required,
+                Some(&ovr)
+            ),
+            Err(PermissionError::Forbidden)
+        );
+    }
+
+    #[test]
+    fn test_command_channel_and_disabled_overrides() {
+        let channel_id = Uuid::new_v4();
+        let ovr = command_override(true, vec![], vec![channel_id]);
+        let perms = GuildPermissions::all();
+
+        assert!(can_use_command(false, perms, &[], Some(channel_id), None, Some(&ovr)).is_ok());
+        assert!(can_use_command(false, perms, &[], None, None, Some(&ovr)).is_ok());
+        assert_eq!(
+            can_use_command(true, perms, &[], Some(Uuid::new_v4()), None, Some(&ovr)),
+            Err(PermissionError::Forbidden)
+        );
+
+        let disabled = command_override(false, vec![], vec![]);
+        assert_eq!(
+            can_use_command(true, perms, &[], None, None, Some(&disabled)),
+            Err(PermissionError::Forbidden)
+        );
+    }
 }
@@ -0,0 +1,89 @@
+//! Live Session Tracking
+//!
+//! Records which users have an open WebSocket so pushes can be skipped while
+//! the user is reading along on another device. Each connection keeps a
+//! heartbeat in a per-user sorted set (score = last heartbeat, Unix seconds);
+//! entries left behind by a crashed node age out after `STALE_AFTER_SECS`.
+
+use std::time::Duration;
+
+use fred::interfaces::{KeysInterface, SortedSetsInterface};
+use fred::prelude::*;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// How often a connection refreshes its heartbeat.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Heartbeats older than this no longer count as a live connection.
+const STALE_AFTER_SECS: i64 = 90;
+
+fn key(user_id: Uuid) -> String {
+    format!("push:active:{user_id}")
+}
+
+/// Record a heartbeat for one WebSocket connection.
+pub async fn touch_connection(
+    redis: &Client,
+    user_id: Uuid,
+    connection_id: Uuid,
+) -> Result<(), Error> {
+    let key = key(user_id);
+    let now = chrono::Utc::now().timestamp() as f64;
+    redis
+        .zadd::<(), _, _>(
+            &key,
+            None,
+            None,
+            false,
+            false,
+            (now, connection_id.to_string()),
+        )
+        .await?;
+    redis.expire::<(), _>(&key, STALE_AFTER_SECS, None).await?;
+    Ok(())
+}
+
+/// Forget a WebSocket connection when it closes.
+pub async fn remove_connection(
+    redis: &Client,
+    user_id: Uuid,
+    connection_id: Uuid,
+) -> Result<(), Error> {
+    redis
+        .zrem::<(), _, _>(key(user_id), connection_id.to_string())
+        .await
+}
+
+/// Whether the user has any live WebSocket connection.
+pub async fn has_live_connection(redis: &Client, user_id: Uuid) -> Result<bool, Error> {
+    let cutoff = chrono::Utc::now().timestamp() - STALE_AFTER_SECS;
+    let live: i64 = redis
+        .zcount(key(user_id), cutoff as f64, f64::INFINITY)
+        .await?;
+    Ok(live > 0)
+}
+
+/// Whether the user is actively connected on some device.
+///
+/// A user counts as active while they have a live WebSocket and their status
+/// is `online`; idle (`away`) clients still get pushes on their other devices.
+/// Lookup failures count as inactive so notifications aren't lost.
+pub async fn is_user_active(db: &PgPool, redis: &Client, user_id: Uuid) -> bool {
+    match has_live_connection(redis, user_id).await {
+        Ok(true) => {}
+        Ok(false) => return false,
+        Err(e) => {
+            tracing::warn!(%user_id, error = %e, "Failed to check live connections");
+            return false;
+        }
+    }
+
+    sqlx::query_scalar::<_, bool>("SELECT status = 'online' FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+}
@@ -0,0 +1,574 @@
+//! Push Delivery Worker
+//!
+//! Background worker that sends queued pushes to Web Push services and
+//! `UnifiedPush` distributors, with exponential backoff retries.
+//!
+//! Architecture (same as webhook delivery):
+//! - New pushes go into `DELIVERY_QUEUE_KEY` (list, BRPOP).
+//! - Failed pushes are scheduled into `RETRY_ZSET_KEY` (sorted set, score = Unix timestamp).
+//! - The worker loop polls both: immediate queue and due retries.
+//!
+//! Endpoints and keys are looked up at send time, so pushes for registrations
+//! removed in the meantime are dropped. Endpoints the push service reports as
+//! gone (404/410) are deleted.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use fred::interfaces::{ListInterface, LuaInterface, SortedSetsInterface};
+use fred::prelude::*;
+use reqwest::StatusCode;
+use sqlx::PgPool;
+use tracing::{debug, error, info, warn};
+
+use super::queries;
+use super::types::{PushDeliveryItem, PushProvider};
+use super::web_push::{self, VapidKeys};
+use crate::webhooks::ssrf;
+
+/// Redis key for the immediate push delivery queue.
+const DELIVERY_QUEUE_KEY: &str = "push:delivery:queue";
+
+/// Redis key for the delayed retry sorted set (score = Unix timestamp when due).
+const RETRY_ZSET_KEY: &str = "push:delivery:retry";
+
+/// Maximum retry attempts before giving up. Pushes are only useful while
+/// fresh, so this is shorter than the webhook schedule.
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Retry delays in seconds (exponential backoff).
+const RETRY_DELAYS_SECS: [u64; 4] = [5, 30, 120, 600];
+
+const _: () = assert!(MAX_ATTEMPTS as usize <= RETRY_DELAYS_SECS.len());
+
+/// How long push services should hold an undelivered push (seconds).
+const PUSH_TTL_SECS: u32 = 4 * 60 * 60;
+
+/// Lua script that atomically removes and returns due items from the retry sorted set.
+const PROMOTE_RETRIES_LUA: &str = r"
+local items = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', ARGV[1], 'LIMIT', 0, 50)
+if #items > 0 then
+    redis.call('ZREM', KEYS[1], unpack(items))
+end
+return items
+";
+
+/// Settings the worker needs to reach push services.
+#[derive(Debug, Clone, Default)]
+pub struct PushDeliveryConfig {
+    /// VAPID key pair. Web Push registrations can't be delivered without it.
+    pub vapid: Option<Arc<VapidKeys>>,
+    /// VAPID `sub` claim (`mailto:` or `https:` contact).
+    pub vapid_subject: String,
+    /// Skip SSRF checks so local HTTP sinks can receive pushes (testing only).
+    pub allow_private_endpoints: bool,
+}
+
+/// Enqueue a push for immediate delivery.
+pub async fn enqueue(redis: &Client, item: &PushDeliveryItem) -> Result<(), Error> {
+    let payload = serde_json::to_string(item)
+        .map_err(|e| Error::new(ErrorKind::Parse, format!("JSON serialize error: {e}")))?;
+
+    redis.lpush::<(), _, _>(DELIVERY_QUEUE_KEY, payload).await?;
+    Ok(())
+}
+
+/// Schedule a push for retry at a future timestamp.
+async fn schedule_retry(
+    redis: &Client,
+    item: &PushDeliveryItem,
+    deliver_at: f64,
+) -> Result<(), Error> {
+    let payload = serde_json::to_string(item)
+        .map_err(|e| Error::new(ErrorKind::Parse, format!("JSON serialize error: {e}")))?;
+
+    redis
+        .zadd::<(), _, _>(
+            RETRY_ZSET_KEY,
+            None,
+            None,
+            false,
+            false,
+            (deliver_at, payload),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Move due retries from the sorted set into the immediate queue (atomic via Lua).
+async fn promote_due_retries(redis: &Client) {
+    let now = chrono::Utc::now().timestamp() as f64;
+
+    let items: Vec<String> = match redis
+        .eval(
+            PROMOTE_RETRIES_LUA,
+            vec![RETRY_ZSET_KEY],
+            vec![now.to_string()],
+        )
+        .await
+    {
+        Ok(items) => items,
+        Err(e) => {
+            error!("Failed to promote due push retries (Lua): {}", e);
+            return;
+        }
+    };
+
+    for payload in &items {
+        if let Err(e) = redis
+            .lpush::<(), _, _>(DELIVERY_QUEUE_KEY, payload.as_str())
+            .await
+        {
+            error!("Failed to re-enqueue promoted push retry: {}", e);
+        }
+    }
+}
+
+/// Spawn the background push delivery worker.
+pub async fn spawn_delivery_worker(
+    db: PgPool,
+    redis: Client,
+    http_client: reqwest::Client,
+    config: PushDeliveryConfig,
+) {
+    info!(
+        web_push = config.vapid.is_some(),
+        "Push delivery worker started"
+    );
+
+    let config = Arc::new(config);
+    let mut consecutive_errors: u32 = 0;
+
+    loop {
+        promote_due_retries(&redis).await;
+
+        let result: Result<Option<(String, String)>, _> =
+            redis.brpop(DELIVERY_QUEUE_KEY, 2.0).await;
+
+        let payload_str = match result {
+            Ok(Some((_key, value))) => {
+                consecutive_errors = 0;
+                value
+            }
+            Ok(None) => {
+                consecutive_errors = 0;
+                continue;
+            }
+            Err(ref e) if matches!(e.kind(), fred::error::ErrorKind::Timeout) => {
+                consecutive_errors = 0;
+                continue;
+            }
+            Err(e) => {
+                consecutive_errors += 1;
+                let backoff_secs = 1u64 << consecutive_errors.min(6);
+                error!(
+                    consecutive_errors,
+                    backoff_secs, "Failed to BRPOP from push delivery queue: {}", e
+                );
+                tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                continue;
+            }
+        };
+
+        let item: PushDeliveryItem = match serde_json::from_str(&payload_str) {
+            Ok(item) => item,
+            Err(e) => {
+                let truncated: String = payload_str.chars().take(500).collect();
+                error!(
+                    error = %e,
+                    payload_preview = %truncated,
+                    "Failed to deserialize push delivery item"
+                );
+                continue;
+            }
+        };
+
+        let db = db.clone();
+        let redis = redis.clone();
+        let client = http_client.clone();
+        let config = config.clone();
+
+        tokio::spawn(async move {
+            let registration_id = item.registration_id;
+            let handle = tokio::spawn(async move {
+                process_delivery(&db, &redis, &client, &config, item).await;
+            });
+            if let Err(e) = handle.await {
+                error!(%registration_id, "Push delivery task panicked: {}", e);
+            }
+        });
+    }
+}
+
+/// Outcome of a single send attempt.
+#[derive(Debug, PartialEq, Eq)]
+enum SendOutcome {
+    Delivered,
+    /// The push service no longer knows the endpoint.
+    Gone,
+    /// Worth retrying (network error, 429, 5xx).
+    Retry(String),
+    /// Permanent failure; retrying won't help.
+    Failed(String),
+}
+
+/// Classify a push service response status.
+fn classify_status(status: StatusCode) -> SendOutcome {
+    if status.is_success() {
+        SendOutcome::Delivered
+    } else if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+        SendOutcome::Gone
+    } else if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        SendOutcome::Retry(format!("HTTP {}", status.as_u16()))
+    } else {
+        SendOutcome::Failed(format!("HTTP {}", status.as_u16()))
+    }
+}
+
+/// Process a single push delivery.
+async fn process_delivery(
+    db: &PgPool,
+    redis: &Client,
+    client: &reqwest::Client,
+    config: &PushDeliveryConfig,
+    item: PushDeliveryItem,
+) {
+    let target = match queries::get_target(db, item.registration_id).await {
+        Ok(Some(target)) => target,
+        Ok(None) => {
+            debug!(registration_id = %item.registration_id, "Push registration removed before delivery");
+            return;
+        }
+        Err(e) => {
+            error!(registration_id = %item.registration_id, error = %e, "Failed to look up push registration");
+            handle_retry(redis, item, &format!("DB error: {e}")).await;
+            return;
+        }
+    };
+
+    let outcome = send(client, config, &target, &item).await;
+    match outcome {
+        SendOutcome::Delivered => {
+            if let Err(e) = queries::mark_delivered(db, item.registration_id).await {
+                warn!(registration_id = %item.registration_id, error = %e, "Failed to record push delivery");
+            }
+        }
+        SendOutcome::Gone => {
+            info!(registration_id = %item.registration_id, "Push endpoint gone, removing registration");
+            if let Err(e) = queries::delete_expired_registration(db, item.registration_id).await {
+                error!(registration_id = %item.registration_id, error = %e, "Failed to remove expired push registration");
+            }
+        }
+        SendOutcome::Retry(reason) => {
+            warn!(
+                registration_id = %item.registration_id,
+                attempt = item.attempt,
+                reason = %reason,
+                "Push delivery failed, will retry"
+            );
+            handle_retry(redis, item, &reason).await;
+        }
+        SendOutcome::Failed(reason) => {
+            warn!(
+                registration_id = %item.registration_id,
+                reason = %reason,
+                "Push delivery rejected"
+            );
+        }
+    }
+}
+
+/// Encrypt, sign and POST one push.
+async fn send(
+    client: &reqwest::Client,
+    config: &PushDeliveryConfig,
+    target: &super::types::PushTarget,
+    item: &PushDeliveryItem,
+) -> SendOutcome {
+    let json = match serde_json::to_vec(&item.payload) {
+        Ok(json) => json,
+        Err(e) => return SendOutcome::Failed(format!("Failed to serialize payload: {e}")),
+    };
+
+    // SSRF protection: pin the resolved address unless local sinks are allowed
+    let pinned;
+    let client = if config.allow_private_endpoints {
+        client
+    } else {
+        let verified = match ssrf::verify_resolved_ip(&target.endpoint).await {
+            Ok(v) => v,
+            Err(e) => return SendOutcome::Failed(format!("SSRF blocked: {e}")),
+        };
+        pinned = match reqwest::Client::builder()
+            .resolve(&verified.host, verified.addr)
+            .timeout(Duration::from_secs(10))
+            .build()
+        {
+            Ok(c) => c,
+            Err(e) => return SendOutcome::Retry(format!("Client build error: {e}")),
+        };
+        &pinned
+    };
+
+    let mut request = client
+        .post(&target.endpoint)
+        .header("TTL", PUSH_TTL_SECS.to_string())
+        .header("Urgency", "high");
+
+    request = match (target.p256dh.as_deref(), target.auth_secret.as_deref()) {
+        (Some(p256dh), Some(auth)) => match web_push::encrypt(&json, p256dh, auth) {
+            Ok(body) => request
+                .header("Content-Type", "application/octet-stream")
+                .header("Content-Encoding", "aes128gcm")
+                .body(body),
+            Err(e) => return SendOutcome::Failed(format!("Encryption failed: {e}")),
+        },
+        // UnifiedPush distributors without Web Push support get plain JSON
+        _ => request
+            .header("Content-Type", "application/json")
+            .body(json),
+    };
+
+    match (&config.vapid, target.provider) {
+        (Some(vapid), _) => {
+            let now = chrono::Utc::now().timestamp();
+            match vapid.authorization(&target.endpoint, &config.vapid_subject, now) {
+                Ok(header) => request = request.header("Authorization", header),
+                Err(e) => return SendOutcome::Failed(format!("VAPID signing failed: {e}")),
+            }
+        }
+        (None, PushProvider::WebPush) => {
+            return SendOutcome::Failed("VAPID keys not configured".to_string());
+        }
+        (None, PushProvider::UnifiedPush) => {}
+    }
+
+    match request.send().await {
+        Ok(resp) => classify_status(resp.status()),
+        Err(e) => SendOutcome::Retry(e.to_string()),
+    }
+}
+
+/// Schedule a retry, or give up after `MAX_ATTEMPTS`.
+async fn handle_retry(redis: &Client, mut item: PushDeliveryItem, error: &str) {
+    if item.attempt >= MAX_ATTEMPTS {
+        warn!(
+            registration_id = %item.registration_id,
+            message_id = %item.payload.message_id,
+            error,
+            "Push delivery exhausted all retries, dropping"
+        );
+        return;
+    }
+
+    let delay_secs = RETRY_DELAYS_SECS
+        .get(item.attempt as usize)
+        .copied()
+        .unwrap_or(600);
+    item.attempt += 1;
+    let deliver_at = chrono::Utc::now().timestamp() as f64 + delay_secs as f64;
+
+    if let Err(e) = schedule_retry(redis, &item, deliver_at).await {
+        error!(
+            registration_id = %item.registration_id,
+            attempt = item.attempt,
+            "Failed to schedule push retry, dropping: {}", e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use std::sync::Mutex;
+
+    use aes_gcm::aead::{Aead, KeyInit, OsRng};
+    use aes_gcm::{Aes128Gcm, Nonce};
+    use axum::body::Bytes;
+    use axum::extract::State;
+    use axum::http::HeaderMap;
+    use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    use base64::Engine;
+    use hkdf::Hkdf;
+    use p256::elliptic_curve::sec1::ToEncodedPoint;
+    use p256::{PublicKey, SecretKey};
+    use sha2::Sha256;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::push::types::{PushPayload, PushTarget};
+
+    #[derive(Clone, Default)]
+    struct Sink {
+        received: Arc<Mutex<Vec<(HeaderMap, Bytes)>>>,
+        status: Arc<AtomicU16>,
+    }
+
+    async fn record(
+        State(sink): State<Sink>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> axum::http::StatusCode {
+        sink.received.lock().unwrap().push((headers, body));
+        axum::http::StatusCode::from_u16(sink.status.load(Ordering::SeqCst)).unwrap()
+    }
+
+    /// Start a local HTTP sink that records pushes and answers with `sink.status`.
+    async fn spawn_sink() -> (Sink, String) {
+        let sink = Sink::default();
+        sink.status.store(201, Ordering::SeqCst);
+        let app = axum::Router::new()
+            .route("/push/{id}", axum::routing::post(record))
+            .with_state(sink.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (sink, format!("http://{addr}/push/device"))
+    }
+
+    fn item() -> PushDeliveryItem {
+        PushDeliveryItem {
+            registration_id: Uuid::new_v4(),
+            payload: PushPayload {
+                kind: "message".to_string(),
+                message_id: Uuid::new_v4(),
+                channel_id: Uuid::new_v4(),
+                guild_id: None,
+                encrypted: false,
+                title: Some("Alice".to_string()),
+                body: Some("hello".to_string()),
+            },
+            attempt: 0,
+            queued_at: chrono::Utc::now(),
+        }
+    }
+
+    /// Decrypt an `aes128gcm` body as the receiving browser would.
+    fn decrypt(body: &[u8], ua_secret: &SecretKey, auth: &[u8]) -> Vec<u8> {
+        let (salt, rest) = body.split_at(16);
+        assert_eq!(rest[4], 65);
+        let (as_public, ciphertext) = rest[5..].split_at(65);
+        let as_key = PublicKey::from_sec1_bytes(as_public).unwrap();
+        let shared = p256::ecdh::diffie_hellman(ua_secret.to_nonzero_scalar(), as_key.as_affine());
+
+        let mut key_info = b"WebPush: info\0".to_vec();
+        key_info.extend_from_slice(ua_secret.public_key().to_encoded_point(false).as_bytes());
+        key_info.extend_from_slice(as_public);
+        let mut ikm = [0u8; 32];
+        Hkdf::<Sha256>::new(Some(auth), shared.raw_secret_bytes())
+            .expand(&key_info, &mut ikm)
+            .unwrap();
+        let content = Hkdf::<Sha256>::new(Some(salt), &ikm);
+        let (mut cek, mut nonce) = ([0u8; 16], [0u8; 12]);
+        content
+            .expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+            .unwrap();
+        content
+            .expand(b"Content-Encoding: nonce\0", &mut nonce)
+            .unwrap();
+
+        let mut plaintext = Aes128Gcm::new(&cek.into())
+            .decrypt(Nonce::from_slice(&nonce), ciphertext)
+            .unwrap();
+        assert_eq!(plaintext.pop(), Some(0x02));
+        plaintext
+    }
+
+    #[tokio::test]
+    async fn delivers_encrypted_web_push_to_local_sink() {
+        let (sink, endpoint) = spawn_sink().await;
+        let vapid_secret = SecretKey::random(&mut OsRng);
+        let config = PushDeliveryConfig {
+            vapid: Some(Arc::new(
+                VapidKeys::from_base64url(&URL_SAFE_NO_PAD.encode(vapid_secret.to_bytes()))
+                    .unwrap(),
+            )),
+            vapid_subject: "mailto:ops@example.com".to_string(),
+            allow_private_endpoints: true,
+        };
+        let ua_secret = SecretKey::random(&mut OsRng);
+        let auth = [7u8; 16];
+        let target = PushTarget {
+            provider: PushProvider::WebPush,
+            endpoint,
+            p256dh: Some(
+                URL_SAFE_NO_PAD.encode(ua_secret.public_key().to_encoded_point(false).as_bytes()),
+            ),
+            auth_secret: Some(URL_SAFE_NO_PAD.encode(auth)),
+        };
+        let item = item();
+
+        let outcome = send(&reqwest::Client::new(), &config, &target, &item).await;
+        assert_eq!(outcome, SendOutcome::Delivered);
+
+        let (headers, body) = sink.received.lock().unwrap().pop().unwrap();
+        assert_eq!(headers["content-encoding"], "aes128gcm");
+        assert_eq!(headers["ttl"], PUSH_TTL_SECS.to_string().as_str());
+        assert!(headers["authorization"]
+            .to_str()
+            .unwrap()
+            .starts_with("vapid t="));
+        let payload: PushPayload =
+            serde_json::from_slice(&decrypt(&body, &ua_secret, &auth)).unwrap();
+        assert_eq!(payload, item.payload);
+    }
+
+    #[tokio::test]
+    async fn delivers_plain_unified_push_and_reports_gone_endpoints() {
+        let (sink, endpoint) = spawn_sink().await;
+        let config = PushDeliveryConfig {
+            allow_private_endpoints: true,
+            ..PushDeliveryConfig::default()
+        };
+        let target = PushTarget {
+            provider: PushProvider::UnifiedPush,
+            endpoint,
+            p256dh: None,
+            auth_secret: None,
+        };
+        let item = item();
+        let client = reqwest::Client::new();
+
+        assert_eq!(
+            send(&client, &config, &target, &item).await,
+            SendOutcome::Delivered
+        );
+        let (headers, body) = sink.received.lock().unwrap().pop().unwrap();
+        assert_eq!(headers["content-type"], "application/json");
+        assert!(headers.get("authorization").is_none());
+        let payload: PushPayload = serde_json::from_slice(&body).unwrap();
+        assert_eq!(payload, item.payload);
+
+        sink.status.store(410, Ordering::SeqCst);
+        assert_eq!(
+            send(&client, &config, &target, &item).await,
+            SendOutcome::Gone
+        );
+
+        // Without the testing override, loopback endpoints are refused
+        let strict = PushDeliveryConfig::default();
+        assert!(matches!(
+            send(&client, &strict, &target, &item).await,
+            SendOutcome::Failed(reason) if reason.starts_with("SSRF blocked")
+        ));
+    }
+
+    #[test]
+    fn classifies_push_service_responses() {
+        assert_eq!(classify_status(StatusCode::CREATED), SendOutcome::Delivered);
+        assert_eq!(classify_status(StatusCode::GONE), SendOutcome::Gone);
+        assert_eq!(classify_status(StatusCode::NOT_FOUND), SendOutcome::Gone);
+        assert!(matches!(
+            classify_status(StatusCode::TOO_MANY_REQUESTS),
+            SendOutcome::Retry(_)
+        ));
+        assert!(matches!(
+            classify_status(StatusCode::BAD_GATEWAY),
+            SendOutcome::Retry(_)
+        ));
+        assert!(matches!(
+            classify_status(StatusCode::PAYLOAD_TOO_LARGE),
+            SendOutcome::Failed(_)
+        ));
+    }
+}
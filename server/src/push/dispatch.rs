@@ -0,0 +1,211 @@
+//! Push Dispatch
+//!
+//! Decides who gets a push for a new message and queues one delivery per
+//! registered device.
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use super::activity::is_user_active;
+use super::delivery::enqueue;
+use super::queries;
+use super::types::{PushDeliveryItem, PushPayload};
+use crate::api::AppState;
+use crate::db;
+use crate::notifications::should_notify;
+
+/// Longest message excerpt included in a push body (characters).
+const MAX_BODY_CHARS: usize = 200;
+
+/// Build the payload for a message.
+///
+/// Encrypted messages get identifiers only: no author, channel name or text.
+#[must_use]
+pub fn build_payload(
+    message: &db::Message,
+    guild_id: Option<Uuid>,
+    author_name: &str,
+    channel_name: Option<&str>,
+) -> PushPayload {
+    let (title, body) = if message.encrypted {
+        (None, None)
+    } else {
+        let title = match (guild_id, channel_name) {
+            (Some(_), Some(channel)) => format!("{author_name} in #{channel}"),
+            _ => author_name.to_string(),
+        };
+        let mut body: String = message.content.chars().take(MAX_BODY_CHARS).collect();
+        if message.content.chars().nth(MAX_BODY_CHARS).is_some() {
+            body.push('…');
+        }
+        (Some(title), Some(body))
+    };
+
+    PushPayload {
+        kind: "message".to_string(),
+        message_id: message.id,
+        channel_id: message.channel_id,
+        guild_id,
+        encrypted: message.encrypted,
+        title,
+        body,
+    }
+}
+
+/// Queue pushes for a new message.
+///
+/// Guild messages push to `notified`, the mentioned users whose notification
+/// settings already allowed it. DMs additionally push to every other
+/// participant whose settings allow it. Users with a live, active session are
+/// skipped; they see the message over the WebSocket.
+pub async fn push_message(
+    state: &AppState,
+    message: &db::Message,
+    guild_id: Option<Uuid>,
+    notified: &[Uuid],
+) {
+    let Some(author_id) = message.user_id else {
+        return;
+    };
+
+    let mut candidates = notified.to_vec();
+    if guild_id.is_none() {
+        let participants: Vec<Uuid> = match sqlx::query_scalar(
+            "SELECT user_id FROM dm_participants WHERE channel_id = $1 AND user_id <> $2",
+        )
+        .bind(message.channel_id)
+        .bind(author_id)
+        .fetch_all(&state.db)
+        .await
+        {
+            Ok(ids) => ids,
+            Err(e) => {
+                tracing::warn!(message_id = %message.id, error = %e, "Failed to load DM participants for push");
+                Vec::new()
+            }
+        };
+        for user_id in participants {
+            if candidates.contains(&user_id) {
+                continue;
+            }
+            match should_notify(
+                &state.db,
+                user_id,
+                message.channel_id,
+                message.parent_id,
+                None,
+            )
+            .await
+            {
+                Ok(true) => candidates.push(user_id),
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!(%user_id, error = %e, "Failed to resolve notification settings");
+                }
+            }
+        }
+    }
+
+    let mut recipients = Vec::with_capacity(candidates.len());
+    for user_id in candidates {
+        if !is_user_active(&state.db, &state.redis, user_id).await {
+            recipients.push(user_id);
+        }
+    }
+    if recipients.is_empty() {
+        return;
+    }
+
+    let registrations = match queries::registration_ids_for_users(&state.db, &recipients).await {
+        Ok(ids) if !ids.is_empty() => ids,
+        Ok(_) => return,
+        Err(e) => {
+            tracing::warn!(message_id = %message.id, error = %e, "Failed to load push registrations");
+            return;
+        }
+    };
+
+    let names: Option<(String, Option<String>)> = sqlx::query_as(
+        r"
+        SELECT u.display_name, c.name
+        FROM users u, channels c
+        WHERE u.id = $1 AND c.id = $2
+        ",
+    )
+    .bind(author_id)
+    .bind(message.channel_id)
+    .fetch_optional(&state.db)
+    .await
+    .unwrap_or_default();
+    let (author_name, channel_name) = names.unwrap_or_else(|| ("Someone".to_string(), None));
+
+    let payload = build_payload(message, guild_id, &author_name, channel_name.as_deref());
+    let queued_at = Utc::now();
+    for registration_id in registrations {
+        let item = PushDeliveryItem {
+            registration_id,
+            payload: payload.clone(),
+            attempt: 0,
+            queued_at,
+        };
+        if let Err(e) = enqueue(&state.redis, &item).await {
+            tracing::warn!(%registration_id, error = %e, "Failed to enqueue push delivery");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(content: &str, encrypted: bool) -> db::Message {
+        db::Message {
+            id: Uuid::new_v4(),
+            channel_id: Uuid::new_v4(),
+            user_id: Some(Uuid::new_v4()),
+            content: content.to_string(),
+            encrypted,
+            nonce: None,
+            reply_to: None,
+            parent_id: None,
+            thread_reply_count: 0,
+            thread_last_reply_at: None,
+            edited_at: None,
+            deleted_at: None,
+            created_at: Utc::now(),
+            message_type: "user".to_string(),
+            components: None,
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn encrypted_payloads_carry_no_content() {
+        let msg = message("ciphertext", true);
+        let payload = build_payload(&msg, None, "Alice", None);
+        assert!(payload.encrypted);
+        assert_eq!(payload.title, None);
+        assert_eq!(payload.body, None);
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["message_id"], msg.id.to_string());
+        assert!(json.get("title").is_none());
+        assert!(json.get("body").is_none());
+        assert!(!json.to_string().contains("Alice"));
+    }
+
+    #[test]
+    fn plaintext_payloads_are_titled_and_truncated() {
+        let guild_id = Some(Uuid::new_v4());
+        let long = "x".repeat(MAX_BODY_CHARS + 10);
+        let payload = build_payload(&message(&long, false), guild_id, "Alice", Some("general"));
+        assert_eq!(payload.title.as_deref(), Some("Alice in #general"));
+        let body = payload.body.unwrap();
+        assert_eq!(body.chars().count(), MAX_BODY_CHARS + 1);
+        assert!(body.ends_with('…'));
+
+        let payload = build_payload(&message("hi", false), None, "Alice", Some("dm"));
+        assert_eq!(payload.title.as_deref(), Some("Alice"));
+        assert_eq!(payload.body.as_deref(), Some("hi"));
+    }
+}
@@ -0,0 +1,61 @@
+//! Push Notification Error Types
+
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::Json;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PushError {
+    #[error("Push registration not found")]
+    NotFound,
+
+    #[error("Web Push is not configured on this server")]
+    NotConfigured,
+
+    #[error("Current session could not be identified")]
+    SessionRequired,
+
+    #[error("Validation error: {0}")]
+    Validation(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl IntoResponse for PushError {
+    fn into_response(self) -> axum::response::Response {
+        let (status, code, message) = match &self {
+            Self::NotFound => (
+                StatusCode::NOT_FOUND,
+                "REGISTRATION_NOT_FOUND",
+                "Push registration not found".to_string(),
+            ),
+            Self::NotConfigured => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "PUSH_NOT_CONFIGURED",
+                "Web Push is not configured on this server".to_string(),
+            ),
+            Self::SessionRequired => (
+                StatusCode::BAD_REQUEST,
+                "SESSION_REQUIRED",
+                "Push registration requires the session's refresh token (cookie or X-Refresh-Token header)"
+                    .to_string(),
+            ),
+            Self::Validation(msg) => (StatusCode::BAD_REQUEST, "VALIDATION_ERROR", msg.clone()),
+            Self::Database(err) => {
+                tracing::error!(%err, "Push endpoint database error");
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    "Database error".to_string(),
+                )
+            }
+        };
+
+        (
+            status,
+            Json(serde_json::json!({ "error": code, "message": message })),
+        )
+            .into_response()
+    }
+}
@@ -0,0 +1,247 @@
+//! Push Registration HTTP Handlers
+
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::Json;
+use axum_extra::extract::CookieJar;
+use uuid::Uuid;
+
+use super::error::PushError;
+use super::queries;
+use super::types::{PushProvider, PushRegistration, RegisterPushRequest, VapidKeyResponse};
+use super::web_push::{validate_subscription_keys, VapidKeys};
+use crate::api::AppState;
+use crate::auth::handlers::extract_current_token_hash;
+use crate::auth::AuthUser;
+use crate::config::Config;
+use crate::webhooks::ssrf;
+
+/// Maximum length of a device label.
+const MAX_DEVICE_NAME_LEN: usize = 128;
+
+/// Resolve the auth session the request was made from.
+async fn current_session_id(
+    state: &AppState,
+    user_id: Uuid,
+    headers: &HeaderMap,
+    jar: &CookieJar,
+) -> Result<Uuid, PushError> {
+    let token_hash = extract_current_token_hash(headers, jar).ok_or(PushError::SessionRequired)?;
+    sqlx::query_scalar(
+        "SELECT id FROM sessions WHERE token_hash = $1 AND user_id = $2 AND expires_at > NOW()",
+    )
+    .bind(&token_hash)
+    .bind(user_id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or(PushError::SessionRequired)
+}
+
+/// Validate a push endpoint URL (includes SSRF protection).
+fn validate_endpoint(config: &Config, endpoint: &str) -> Result<(), PushError> {
+    if endpoint.len() > 2048 {
+        return Err(PushError::Validation(
+            "Endpoint must be at most 2048 characters".to_string(),
+        ));
+    }
+    let parsed = reqwest::Url::parse(endpoint)
+        .map_err(|_| PushError::Validation("Invalid endpoint URL".to_string()))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| PushError::Validation("Endpoint must contain a host".to_string()))?;
+
+    // Local sinks are only reachable when explicitly allowed for testing
+    if config.push_allow_private_endpoints {
+        return if matches!(parsed.scheme(), "http" | "https") {
+            Ok(())
+        } else {
+            Err(PushError::Validation(
+                "Endpoint must use http or https".to_string(),
+            ))
+        };
+    }
+    if parsed.scheme() != "https" {
+        return Err(PushError::Validation("Endpoint must use https".to_string()));
+    }
+    if ssrf::is_blocked_host(host) {
+        return Err(PushError::Validation(
+            "Endpoint must not point to a private or reserved address".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Get the server's VAPID public key for `PushManager.subscribe()`.
+///
+/// GET /api/me/push/vapid-key
+#[utoipa::path(
+    get,
+    path = "/api/me/push/vapid-key",
+    tag = "push",
+    responses(
+        (status = 200, body = VapidKeyResponse),
+        (status = 503, description = "Web Push is not configured"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn get_vapid_key(
+    State(state): State<AppState>,
+    _: AuthUser,
+) -> Result<Json<VapidKeyResponse>, PushError> {
+    let keys = state
+        .config
+        .vapid_private_key
+        .as_deref()
+        .and_then(|key| VapidKeys::from_base64url(key).ok())
+        .ok_or(PushError::NotConfigured)?;
+
+    Ok(Json(VapidKeyResponse {
+        public_key: keys.public_key().to_string(),
+    }))
+}
+
+/// List the user's push registrations across all sessions.
+///
+/// GET /api/me/push/registrations
+#[utoipa::path(
+    get,
+    path = "/api/me/push/registrations",
+    tag = "push",
+    responses(
+        (status = 200, body = Vec<PushRegistration>),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn list_registrations(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+) -> Result<Json<Vec<PushRegistration>>, PushError> {
+    let registrations = queries::list_registrations(&state.db, auth_user.id).await?;
+    Ok(Json(registrations))
+}
+
+/// Register (or replace) the push endpoint of the current session's device.
+///
+/// PUT /api/me/push/registration
+#[utoipa::path(
+    put,
+    path = "/api/me/push/registration",
+    tag = "push",
+    request_body = RegisterPushRequest,
+    responses(
+        (status = 200, body = PushRegistration),
+        (status = 400, description = "Invalid registration or session not identifiable"),
+        (status = 503, description = "Web Push is not configured"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, headers, jar, request))]
+pub async fn register(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+    jar: CookieJar,
+    Json(request): Json<RegisterPushRequest>,
+) -> Result<Json<PushRegistration>, PushError> {
+    validate_endpoint(&state.config, &request.endpoint)?;
+    if request.provider == PushProvider::WebPush {
+        if state.config.vapid_private_key.is_none() {
+            return Err(PushError::NotConfigured);
+        }
+        if request.keys.is_none() {
+            return Err(PushError::Validation(
+                "Web Push registrations require subscription keys".to_string(),
+            ));
+        }
+    }
+    if let Some(keys) = &request.keys {
+        validate_subscription_keys(&keys.p256dh, &keys.auth)
+            .map_err(|e| PushError::Validation(e.to_string()))?;
+    }
+    let device_name = request
+        .device_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty());
+    if device_name.is_some_and(|name| name.chars().count() > MAX_DEVICE_NAME_LEN) {
+        return Err(PushError::Validation(format!(
+            "Device name must be at most {MAX_DEVICE_NAME_LEN} characters"
+        )));
+    }
+
+    let session_id = current_session_id(&state, auth_user.id, &headers, &jar).await?;
+    let registration = queries::upsert_registration(
+        &state.db,
+        auth_user.id,
+        session_id,
+        request.provider,
+        &request.endpoint,
+        request.keys.as_ref().map(|k| k.p256dh.as_str()),
+        request.keys.as_ref().map(|k| k.auth.as_str()),
+        device_name,
+    )
+    .await?;
+
+    tracing::info!(
+        user_id = %auth_user.id,
+        registration_id = %registration.id,
+        provider = ?registration.provider,
+        "Push registration saved"
+    );
+    Ok(Json(registration))
+}
+
+/// Remove the current session's push registration.
+///
+/// DELETE /api/me/push/registration
+#[utoipa::path(
+    delete,
+    path = "/api/me/push/registration",
+    tag = "push",
+    responses(
+        (status = 204, description = "Registration removed"),
+        (status = 404, description = "This session has no registration"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state, headers, jar))]
+pub async fn unregister(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    headers: HeaderMap,
+    jar: CookieJar,
+) -> Result<StatusCode, PushError> {
+    let session_id = current_session_id(&state, auth_user.id, &headers, &jar).await?;
+    if !queries::delete_session_registration(&state.db, session_id).await? {
+        return Err(PushError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Remove one of the user's push registrations, e.g. a lost device.
+///
+/// DELETE `/api/me/push/registrations/{registration_id}`
+#[utoipa::path(
+    delete,
+    path = "/api/me/push/registrations/{registration_id}",
+    tag = "push",
+    params(("registration_id" = Uuid, Path, description = "Registration ID")),
+    responses(
+        (status = 204, description = "Registration removed"),
+        (status = 404, description = "Registration not found"),
+    ),
+    security(("bearer_auth" = [])),
+)]
+#[tracing::instrument(skip(state))]
+pub async fn delete_registration(
+    State(state): State<AppState>,
+    auth_user: AuthUser,
+    Path(registration_id): Path<Uuid>,
+) -> Result<StatusCode, PushError> {
+    if !queries::delete_registration(&state.db, auth_user.id, registration_id).await? {
+        return Err(PushError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
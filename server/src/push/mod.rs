@@ -0,0 +1,37 @@
+//! Push Notifications
+//!
+//! Delivers DM and mention notifications to devices without a live
+//! WebSocket, through Web Push (VAPID, RFC 8291 encryption) or `UnifiedPush`.
+//! Each auth session can register one device endpoint; a background worker
+//! sends queued pushes with retries, modeled on webhook delivery.
+
+pub mod activity;
+pub mod delivery;
+pub mod dispatch;
+pub mod error;
+pub mod handlers;
+pub mod queries;
+pub mod types;
+pub mod web_push;
+
+use axum::routing::{delete, get, put};
+use axum::Router;
+
+use crate::api::AppState;
+
+/// Create push registration routes.
+///
+/// Mounted at `/api/me/push` in the main router.
+pub fn router() -> Router<AppState> {
+    Router::new()
+        .route("/vapid-key", get(handlers::get_vapid_key))
+        .route("/registrations", get(handlers::list_registrations))
+        .route(
+            "/registrations/{registration_id}",
+            delete(handlers::delete_registration),
+        )
+        .route(
+            "/registration",
+            put(handlers::register).delete(handlers::unregister),
+        )
+}
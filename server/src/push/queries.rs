@@ -0,0 +1,150 @@
+//! Push Registration Database Queries
+//!
+//! Uses runtime queries (`sqlx::query` / `sqlx::query_as`) to avoid
+//! requiring a live database at compile time.
+
+use sqlx::{PgExecutor, PgPool};
+use uuid::Uuid;
+
+use super::types::{PushProvider, PushRegistration, PushTarget};
+
+/// Create or replace the registration for a session.
+#[allow(clippy::too_many_arguments)]
+pub async fn upsert_registration(
+    pool: &PgPool,
+    user_id: Uuid,
+    session_id: Uuid,
+    provider: PushProvider,
+    endpoint: &str,
+    p256dh: Option<&str>,
+    auth_secret: Option<&str>,
+    device_name: Option<&str>,
+) -> sqlx::Result<PushRegistration> {
+    sqlx::query_as::<_, PushRegistration>(
+        r"
+        INSERT INTO push_registrations
+            (user_id, session_id, provider, endpoint, p256dh, auth_secret, device_name)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (session_id) DO UPDATE SET
+            provider = EXCLUDED.provider,
+            endpoint = EXCLUDED.endpoint,
+            p256dh = EXCLUDED.p256dh,
+            auth_secret = EXCLUDED.auth_secret,
+            device_name = EXCLUDED.device_name,
+            updated_at = NOW()
+        RETURNING id, session_id, provider, endpoint, device_name,
+                  created_at, updated_at, last_delivered_at
+        ",
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .bind(provider)
+    .bind(endpoint)
+    .bind(p256dh)
+    .bind(auth_secret)
+    .bind(device_name)
+    .fetch_one(pool)
+    .await
+}
+
+/// List a user's registrations, newest first.
+pub async fn list_registrations(
+    pool: &PgPool,
+    user_id: Uuid,
+) -> sqlx::Result<Vec<PushRegistration>> {
+    sqlx::query_as::<_, PushRegistration>(
+        r"
+        SELECT id, session_id, provider, endpoint, device_name,
+               created_at, updated_at, last_delivered_at
+        FROM push_registrations
+        WHERE user_id = $1
+        ORDER BY created_at DESC
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete one of a user's registrations. Returns whether a row was removed.
+pub async fn delete_registration(
+    pool: &PgPool,
+    user_id: Uuid,
+    registration_id: Uuid,
+) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM push_registrations WHERE id = $1 AND user_id = $2")
+        .bind(registration_id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete the registration of a session. Returns whether a row was removed.
+pub async fn delete_session_registration(pool: &PgPool, session_id: Uuid) -> sqlx::Result<bool> {
+    let result = sqlx::query("DELETE FROM push_registrations WHERE session_id = $1")
+        .bind(session_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Move a registration to the session that replaced it on token refresh.
+pub async fn move_session_registration(
+    executor: impl PgExecutor<'_>,
+    old_session_id: Uuid,
+    new_session_id: Uuid,
+) -> sqlx::Result<()> {
+    sqlx::query("UPDATE push_registrations SET session_id = $2 WHERE session_id = $1")
+        .bind(old_session_id)
+        .bind(new_session_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Registration IDs for a set of users whose sessions are still valid.
+pub async fn registration_ids_for_users(
+    pool: &PgPool,
+    user_ids: &[Uuid],
+) -> sqlx::Result<Vec<Uuid>> {
+    sqlx::query_scalar(
+        r"
+        SELECT pr.id
+        FROM push_registrations pr
+        INNER JOIN sessions s ON s.id = pr.session_id
+        WHERE pr.user_id = ANY($1) AND s.expires_at > NOW()
+        ",
+    )
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await
+}
+
+/// Look up where to deliver a queued push.
+pub async fn get_target(pool: &PgPool, registration_id: Uuid) -> sqlx::Result<Option<PushTarget>> {
+    sqlx::query_as::<_, PushTarget>(
+        "SELECT provider, endpoint, p256dh, auth_secret FROM push_registrations WHERE id = $1",
+    )
+    .bind(registration_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Record a successful delivery.
+pub async fn mark_delivered(pool: &PgPool, registration_id: Uuid) -> sqlx::Result<()> {
+    sqlx::query("UPDATE push_registrations SET last_delivered_at = NOW() WHERE id = $1")
+        .bind(registration_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Drop a registration the push service reported as gone.
+pub async fn delete_expired_registration(pool: &PgPool, registration_id: Uuid) -> sqlx::Result<()> {
+    sqlx::query("DELETE FROM push_registrations WHERE id = $1")
+        .bind(registration_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Push Notification Types
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// How a device receives pushes.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type, utoipa::ToSchema,
+)]
+#[sqlx(type_name = "push_provider", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PushProvider {
+    /// Browser push service, authenticated with VAPID (RFC 8292).
+    WebPush,
+    /// `UnifiedPush` distributor endpoint (Android).
+    UnifiedPush,
+}
+
+// ============================================================================
+// Database Row Types
+// ============================================================================
+
+/// A device's push registration, tied to one auth session.
+#[derive(Debug, Clone, Serialize, FromRow, utoipa::ToSchema)]
+pub struct PushRegistration {
+    pub id: Uuid,
+    pub session_id: Uuid,
+    pub provider: PushProvider,
+    pub endpoint: String,
+    pub device_name: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_delivered_at: Option<DateTime<Utc>>,
+}
+
+/// Delivery target looked up by the worker at send time.
+#[derive(Debug, Clone, FromRow)]
+pub struct PushTarget {
+    pub provider: PushProvider,
+    pub endpoint: String,
+    pub p256dh: Option<String>,
+    pub auth_secret: Option<String>,
+}
+
+// ============================================================================
+// API Request/Response Types
+// ============================================================================
+
+/// RFC 8291 subscription keys, as returned by `PushSubscription.toJSON()`.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct PushSubscriptionKeys {
+    /// Base64url-encoded P-256 public key of the device.
+    pub p256dh: String,
+    /// Base64url-encoded 16-byte authentication secret.
+    pub auth: String,
+}
+
+/// Request body for registering the current session's device.
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct RegisterPushRequest {
+    pub provider: PushProvider,
+    /// HTTPS endpoint issued by the push service or distributor.
+    pub endpoint: String,
+    /// Required for `web_push`; optional for `unified_push`.
+    #[serde(default)]
+    pub keys: Option<PushSubscriptionKeys>,
+    /// Optional label shown in the device list.
+    #[serde(default)]
+    pub device_name: Option<String>,
+}
+
+/// Public VAPID key browsers pass as `applicationServerKey`.
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct VapidKeyResponse {
+    /// Base64url-encoded uncompressed P-256 public key.
+    pub public_key: String,
+}
+
+// ============================================================================
+// Delivery Types
+// ============================================================================
+
+/// Notification payload delivered to devices.
+///
+/// Payloads for encrypted messages carry identifiers only, so neither the
+/// push service nor the device's notification shade sees message content.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PushPayload {
+    /// Always `"message"` for now.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message_id: Uuid,
+    pub channel_id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub guild_id: Option<Uuid>,
+    /// Whether the message is end-to-end encrypted. Encrypted payloads omit
+    /// `title` and `body`.
+    pub encrypted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+/// Queued push delivery for one registration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushDeliveryItem {
+    pub registration_id: Uuid,
+    pub payload: PushPayload,
+    /// 0-based attempt counter.
+    pub attempt: u32,
+    pub queued_at: DateTime<Utc>,
+}
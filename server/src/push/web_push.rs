@@ -0,0 +1,283 @@
+//! Web Push Message Encryption and VAPID
+//!
+//! Implements the two pieces of Web Push the server needs:
+//! - `aes128gcm` payload encryption (RFC 8291 on top of RFC 8188), so push
+//!   services only ever relay ciphertext.
+//! - VAPID application server authentication (RFC 8292), an ES256 JWT bound
+//!   to the push service origin.
+
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes128Gcm, Nonce};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use hkdf::Hkdf;
+use p256::ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use sha2::Sha256;
+
+/// Record size advertised in the `aes128gcm` header. Push payloads are far
+/// smaller, so everything fits in a single record.
+const RECORD_SIZE: u32 = 4096;
+
+/// Largest plaintext that fits in one record after the delimiter and tag.
+pub const MAX_PLAINTEXT_LEN: usize = 3993;
+
+/// How long a VAPID token stays valid (RFC 8292 caps this at 24 hours).
+const VAPID_TOKEN_TTL_SECS: i64 = 12 * 60 * 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebPushError {
+    #[error("Invalid key: {0}")]
+    InvalidKey(&'static str),
+
+    #[error("Payload too large")]
+    PayloadTooLarge,
+
+    #[error("Encryption failed")]
+    Encryption,
+
+    #[error("Invalid endpoint URL")]
+    InvalidEndpoint,
+}
+
+/// Decode a base64url string, tolerating padding.
+fn decode_b64(value: &str) -> Option<Vec<u8>> {
+    URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')).ok()
+}
+
+/// Validate subscription keys as sent by a browser or distributor.
+pub fn validate_subscription_keys(p256dh: &str, auth: &str) -> Result<(), WebPushError> {
+    let public = decode_b64(p256dh).ok_or(WebPushError::InvalidKey("p256dh"))?;
+    PublicKey::from_sec1_bytes(&public).map_err(|_| WebPushError::InvalidKey("p256dh"))?;
+    match decode_b64(auth) {
+        Some(secret) if secret.len() == 16 => Ok(()),
+        _ => Err(WebPushError::InvalidKey("auth")),
+    }
+}
+
+/// Encrypt `payload` for a subscription with `aes128gcm` content coding.
+pub fn encrypt(payload: &[u8], p256dh: &str, auth: &str) -> Result<Vec<u8>, WebPushError> {
+    let ua_public = decode_b64(p256dh).ok_or(WebPushError::InvalidKey("p256dh"))?;
+    let auth_secret = decode_b64(auth).ok_or(WebPushError::InvalidKey("auth"))?;
+    let as_secret = SecretKey::random(&mut OsRng);
+    let mut salt = [0u8; 16];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut salt);
+    encrypt_with(payload, &ua_public, &auth_secret, &as_secret, salt)
+}
+
+/// Encrypt with an explicit sender key and salt (deterministic for tests).
+fn encrypt_with(
+    payload: &[u8],
+    ua_public: &[u8],
+    auth_secret: &[u8],
+    as_secret: &SecretKey,
+    salt: [u8; 16],
+) -> Result<Vec<u8>, WebPushError> {
+    if payload.len() > MAX_PLAINTEXT_LEN {
+        return Err(WebPushError::PayloadTooLarge);
+    }
+    if auth_secret.len() != 16 {
+        return Err(WebPushError::InvalidKey("auth"));
+    }
+    let ua_key =
+        PublicKey::from_sec1_bytes(ua_public).map_err(|_| WebPushError::InvalidKey("p256dh"))?;
+    let as_public = as_secret.public_key().to_encoded_point(false);
+    let ua_public = ua_key.to_encoded_point(false);
+
+    let shared = p256::ecdh::diffie_hellman(as_secret.to_nonzero_scalar(), ua_key.as_affine());
+
+    // IKM = HKDF(auth_secret, ecdh_secret, "WebPush: info" || 0x00 || ua_public || as_public)
+    let mut key_info = Vec::with_capacity(14 + 65 + 65);
+    key_info.extend_from_slice(b"WebPush: info\0");
+    key_info.extend_from_slice(ua_public.as_bytes());
+    key_info.extend_from_slice(as_public.as_bytes());
+    let mut ikm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(auth_secret), shared.raw_secret_bytes())
+        .expand(&key_info, &mut ikm)
+        .map_err(|_| WebPushError::Encryption)?;
+
+    let content = Hkdf::<Sha256>::new(Some(&salt), &ikm);
+    let mut cek = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    content
+        .expand(b"Content-Encoding: aes128gcm\0", &mut cek)
+        .map_err(|_| WebPushError::Encryption)?;
+    content
+        .expand(b"Content-Encoding: nonce\0", &mut nonce)
+        .map_err(|_| WebPushError::Encryption)?;
+
+    // Single (last) record: plaintext followed by the 0x02 delimiter
+    let mut record = Vec::with_capacity(payload.len() + 1);
+    record.extend_from_slice(payload);
+    record.push(0x02);
+    let ciphertext = Aes128Gcm::new(&cek.into())
+        .encrypt(Nonce::from_slice(&nonce), record.as_slice())
+        .map_err(|_| WebPushError::Encryption)?;
+
+    // Header: salt || rs || idlen || keyid (the sender's public key)
+    let key_id = as_public.as_bytes();
+    let mut body = Vec::with_capacity(16 + 4 + 1 + key_id.len() + ciphertext.len());
+    body.extend_from_slice(&salt);
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(key_id.len() as u8);
+    body.extend_from_slice(key_id);
+    body.extend_from_slice(&ciphertext);
+    Ok(body)
+}
+
+/// The server's VAPID key pair.
+pub struct VapidKeys {
+    signing_key: SigningKey,
+    public_key: String,
+}
+
+impl VapidKeys {
+    /// Load from a base64url-encoded raw 32-byte P-256 private key.
+    pub fn from_base64url(private_key: &str) -> Result<Self, WebPushError> {
+        let bytes = decode_b64(private_key).ok_or(WebPushError::InvalidKey("VAPID"))?;
+        let secret =
+            SecretKey::from_slice(&bytes).map_err(|_| WebPushError::InvalidKey("VAPID"))?;
+        let public_key =
+            URL_SAFE_NO_PAD.encode(secret.public_key().to_encoded_point(false).as_bytes());
+        Ok(Self {
+            signing_key: SigningKey::from(secret),
+            public_key,
+        })
+    }
+
+    /// Base64url-encoded uncompressed public key (the `applicationServerKey`).
+    #[must_use]
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    /// Build the `Authorization` header value for a push to `endpoint`.
+    pub fn authorization(
+        &self,
+        endpoint: &str,
+        subject: &str,
+        now: i64,
+    ) -> Result<String, WebPushError> {
+        let url = reqwest::Url::parse(endpoint).map_err(|_| WebPushError::InvalidEndpoint)?;
+        let audience = url.origin().ascii_serialization();
+        if audience == "null" {
+            return Err(WebPushError::InvalidEndpoint);
+        }
+
+        let header = URL_SAFE_NO_PAD.encode(br#"{"typ":"JWT","alg":"ES256"}"#);
+        let claims = serde_json::json!({
+            "aud": audience,
+            "exp": now + VAPID_TOKEN_TTL_SECS,
+            "sub": subject,
+        });
+        let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+        let signing_input = format!("{header}.{claims}");
+        let signature: Signature = self.signing_key.sign(signing_input.as_bytes());
+        let token = format!(
+            "{signing_input}.{}",
+            URL_SAFE_NO_PAD.encode(signature.to_bytes())
+        );
+
+        Ok(format!("vapid t={token}, k={}", self.public_key))
+    }
+}
+
+impl std::fmt::Debug for VapidKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VapidKeys")
+            .field("public_key", &self.public_key)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p256::ecdsa::signature::Verifier;
+    use p256::ecdsa::VerifyingKey;
+
+    use super::*;
+
+    fn b64(value: &str) -> Vec<u8> {
+        decode_b64(value).expect("valid base64url")
+    }
+
+    /// Example from RFC 8291, Appendix A.
+    #[test]
+    fn encrypts_rfc8291_example() {
+        let plaintext = b"When I grow up, I want to be a watermelon";
+        let as_secret =
+            SecretKey::from_slice(&b64("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw")).unwrap();
+        let ua_secret =
+            SecretKey::from_slice(&b64("q1dXpw3UpT5VOmu_cf_v6ih07Aems3njxI-JWgLcM94")).unwrap();
+        let ua_public = ua_secret.public_key().to_encoded_point(false);
+        let salt: [u8; 16] = b64("DGv6ra1nlYgDCS1FRnbzlw").try_into().unwrap();
+
+        let body = encrypt_with(
+            plaintext,
+            ua_public.as_bytes(),
+            &b64("BTBZMqHH6r4Tts7J_aSIgg"),
+            &as_secret,
+            salt,
+        )
+        .unwrap();
+
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(body),
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_subscription_keys() {
+        let ua_public = URL_SAFE_NO_PAD.encode(
+            SecretKey::random(&mut OsRng)
+                .public_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+        assert!(validate_subscription_keys(&ua_public, "BTBZMqHH6r4Tts7J_aSIgg").is_ok());
+        assert!(validate_subscription_keys("not-a-key", "BTBZMqHH6r4Tts7J_aSIgg").is_err());
+        assert!(validate_subscription_keys(&ua_public, "c2hvcnQ").is_err());
+        assert!(matches!(
+            encrypt(
+                &[0; MAX_PLAINTEXT_LEN + 1],
+                &ua_public,
+                "BTBZMqHH6r4Tts7J_aSIgg"
+            ),
+            Err(WebPushError::PayloadTooLarge)
+        ));
+    }
+
+    #[test]
+    fn vapid_token_is_signed_for_push_service_origin() {
+        let secret = SecretKey::random(&mut OsRng);
+        let keys = VapidKeys::from_base64url(&URL_SAFE_NO_PAD.encode(secret.to_bytes())).unwrap();
+        let header = keys
+            .authorization(
+                "https://push.example.net:8443/wpush/v2/abc",
+                "mailto:ops@example.com",
+                1_700_000_000,
+            )
+            .unwrap();
+
+        let (token, key) = header
+            .strip_prefix("vapid t=")
+            .and_then(|rest| rest.split_once(", k="))
+            .unwrap();
+        assert_eq!(key, keys.public_key());
+
+        let (signing_input, signature) = token.rsplit_once('.').unwrap();
+        let claims: serde_json::Value =
+            serde_json::from_slice(&b64(signing_input.split_once('.').unwrap().1)).unwrap();
+        assert_eq!(claims["aud"], "https://push.example.net:8443");
+        assert_eq!(claims["sub"], "mailto:ops@example.com");
+        assert_eq!(claims["exp"], 1_700_000_000 + VAPID_TOKEN_TTL_SECS);
+
+        let signature = Signature::from_slice(&b64(signature)).unwrap();
+        VerifyingKey::from(secret.public_key())
+            .verify(signing_input.as_bytes(), &signature)
+            .expect("valid ES256 signature");
+    }
+}
@@ -46,6 +46,28 @@ pub struct RateLimits {
     pub search: LimitConfig,
     /// Data governance operations (export, deletion)
     pub data_governance: LimitConfig,
+    /// Poll votes and vote retractions
+    pub poll_vote: LimitConfig,
+    /// Bulk message deletes
+    pub bulk_delete: LimitConfig,
+    /// Message component interactions
+    pub interaction: LimitConfig,
+    /// Slash command autocomplete requests
+    pub autocomplete: LimitConfig,
+    /// Bot REST API: token checks per client IP
+    pub bot_auth: LimitConfig,
+    /// Bot REST API: channel message history reads
+    pub bot_message_read: LimitConfig,
+    /// Bot REST API: editing and deleting own messages
+    pub bot_message_write: LimitConfig,
+    /// Bot REST API: adding reactions
+    pub bot_reaction: LimitConfig,
+    /// Bot REST API: guild member listing
+    pub bot_member_read: LimitConfig,
+    /// Bot REST API: assigning and removing member roles
+    pub bot_role_manage: LimitConfig,
+    /// Bot REST API: attachment uploads
+    pub bot_upload: LimitConfig,
     /// Failed authentication tracking
     pub failed_auth: FailedAuthConfig,
     /// Failed auth as `LimitConfig` (for consistency in `get_limit_config`)
@@ -144,6 +166,50 @@ impl Default for RateLimits {
                 requests: 2,
                 window_secs: 60,
             },
+            poll_vote: LimitConfig {
+                requests: 20,
+                window_secs: 60,
+            },
+            bulk_delete: LimitConfig {
+                requests: 5,
+                window_secs: 60,
+            },
+            interaction: LimitConfig {
+                requests: 30,
+                window_secs: 60,
+            },
+            autocomplete: LimitConfig {
+                requests: 60,
+                window_secs: 60,
+            },
+            bot_auth: LimitConfig {
+                requests: 600,
+                window_secs: 60,
+            },
+            bot_message_read: LimitConfig {
+                requests: 120,
+                window_secs: 60,
+            },
+            bot_message_write: LimitConfig {
+                requests: 30,
+                window_secs: 60,
+            },
+            bot_reaction: LimitConfig {
+                requests: 60,
+                window_secs: 60,
+            },
+            bot_member_read: LimitConfig {
+                requests: 30,
+                window_secs: 60,
+            },
+            bot_role_manage: LimitConfig {
+                requests: 20,
+                window_secs: 60,
+            },
+            bot_upload: LimitConfig {
+                requests: 10,
+                window_secs: 60,
+            },
             failed_auth_as_limit: LimitConfig {
                 requests: failed_auth.max_failures,
                 window_secs: failed_auth.window_secs,
@@ -172,6 +238,17 @@ impl RateLimitConfig {
     /// - `RATE_LIMIT_WS_CONNECT`: WebSocket connect limit as "`requests,window_secs`"
     /// - `RATE_LIMIT_WS_MESSAGE`: WebSocket message limit as "`requests,window_secs`"
     /// - `RATE_LIMIT_SEARCH`: Search limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_POLL_VOTE`: Poll votes and vote retractions limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BULK_DELETE`: Bulk message deletes limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_INTERACTION`: Message component interactions limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_AUTOCOMPLETE`: Slash command autocomplete requests limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BOT_AUTH`: Bot token checks per IP as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BOT_MESSAGE_READ`: Bot message history read limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BOT_MESSAGE_WRITE`: Bot message edit/delete limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BOT_REACTION`: Bot reaction limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BOT_MEMBER_READ`: Bot member listing limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BOT_ROLE_MANAGE`: Bot role management limit as "`requests,window_secs`"
+    /// - `RATE_LIMIT_BOT_UPLOAD`: Bot upload limit as "`requests,window_secs`"
     /// - `RATE_LIMIT_FAILED_AUTH`: Failed auth as "`max_failures,block_duration_secs,window_secs`"
     pub fn from_env() -> Self {
         let mut config = Self::default();
@@ -248,6 +325,61 @@ impl RateLimitConfig {
                 config.limits.search = limit;
             }
         }
+        if let Ok(val) = std::env::var("RATE_LIMIT_POLL_VOTE") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.poll_vote = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BULK_DELETE") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bulk_delete = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_INTERACTION") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.interaction = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_AUTOCOMPLETE") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.autocomplete = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BOT_AUTH") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bot_auth = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BOT_MESSAGE_READ") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bot_message_read = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BOT_MESSAGE_WRITE") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bot_message_write = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BOT_REACTION") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bot_reaction = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BOT_MEMBER_READ") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bot_member_read = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BOT_ROLE_MANAGE") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bot_role_manage = limit;
+            }
+        }
+        if let Ok(val) = std::env::var("RATE_LIMIT_BOT_UPLOAD") {
+            if let Some(limit) = parse_limit_config(&val) {
+                config.limits.bot_upload = limit;
+            }
+        }
         if let Ok(val) = std::env::var("RATE_LIMIT_FAILED_AUTH") {
             if let Some(limit) = parse_failed_auth_config(&val) {
                 config.limits.failed_auth = limit;
@@ -408,6 +408,17 @@ impl RateLimiter {
             RateLimitCategory::VoiceJoin => &self.config.limits.voice_join,
             RateLimitCategory::Search => &self.config.limits.search,
             RateLimitCategory::DataGovernance => &self.config.limits.data_governance,
+            RateLimitCategory::PollVote => &self.config.limits.poll_vote,
+            RateLimitCategory::BulkDelete => &self.config.limits.bulk_delete,
+            RateLimitCategory::Interaction => &self.config.limits.interaction,
+            RateLimitCategory::Autocomplete => &self.config.limits.autocomplete,
+            RateLimitCategory::BotAuth => &self.config.limits.bot_auth,
+            RateLimitCategory::BotMessageRead => &self.config.limits.bot_message_read,
+            RateLimitCategory::BotMessageWrite => &self.config.limits.bot_message_write,
+            RateLimitCategory::BotReaction => &self.config.limits.bot_reaction,
+            RateLimitCategory::BotMemberRead => &self.config.limits.bot_member_read,
+            RateLimitCategory::BotRoleManage => &self.config.limits.bot_role_manage,
+            RateLimitCategory::BotUpload => &self.config.limits.bot_upload,
             RateLimitCategory::FailedAuth => {
                 // FailedAuth uses max_failures as requests and window_secs from failed_auth config.
                 // Note: This category should not be used with check() - use record_failed_auth()
@@ -31,6 +31,28 @@ pub enum RateLimitCategory {
     Search,
     /// Data governance operations (export, deletion)
     DataGovernance,
+    /// Poll votes and vote retractions
+    PollVote,
+    /// Bulk message deletes
+    BulkDelete,
+    /// Message component interactions
+    Interaction,
+    /// Slash command autocomplete requests
+    Autocomplete,
+    /// Bot REST API: token checks per client IP
+    BotAuth,
+    /// Bot REST API: channel message history reads
+    BotMessageRead,
+    /// Bot REST API: editing and deleting own messages
+    BotMessageWrite,
+    /// Bot REST API: adding reactions
+    BotReaction,
+    /// Bot REST API: guild member listing
+    BotMemberRead,
+    /// Bot REST API: assigning and removing member roles
+    BotRoleManage,
+    /// Bot REST API: attachment uploads
+    BotUpload,
 }
 
 impl RateLimitCategory {
@@ -50,6 +72,17 @@ impl RateLimitCategory {
             Self::VoiceJoin => "voice_join",
             Self::Search => "search",
             Self::DataGovernance => "data_governance",
+            Self::PollVote => "poll_vote",
+            Self::BulkDelete => "bulk_delete",
+            Self::Interaction => "interaction",
+            Self::Autocomplete => "autocomplete",
+            Self::BotAuth => "bot_auth",
+            Self::BotMessageRead => "bot_message_read",
+            Self::BotMessageWrite => "bot_message_write",
+            Self::BotReaction => "bot_reaction",
+            Self::BotMemberRead => "bot_member_read",
+            Self::BotRoleManage => "bot_role_manage",
+            Self::BotUpload => "bot_upload",
         }
     }
 
@@ -68,6 +101,17 @@ impl RateLimitCategory {
             Self::VoiceJoin,
             Self::Search,
             Self::DataGovernance,
+            Self::PollVote,
+            Self::BulkDelete,
+            Self::Interaction,
+            Self::Autocomplete,
+            Self::BotAuth,
+            Self::BotMessageRead,
+            Self::BotMessageWrite,
+            Self::BotReaction,
+            Self::BotMemberRead,
+            Self::BotRoleManage,
+            Self::BotUpload,
         ]
     }
 }
@@ -503,6 +503,35 @@ impl SfuServer {
         rooms.get(&channel_id).cloned()
     }
 
+    /// Voice channels where the user's peer signals over `signal_tx`.
+    pub async fn channels_signaling_to(
+        &self,
+        user_id: Uuid,
+        signal_tx: &mpsc::Sender<ServerEvent>,
+    ) -> Vec<Uuid> {
+        let rooms: Vec<Arc<Room>> = self.rooms.read().await.values().cloned().collect();
+        let mut channel_ids = Vec::new();
+        for room in rooms {
+            if let Some(peer) = room.get_peer(user_id).await {
+                if peer.signal_tx.same_channel(signal_tx) {
+                    channel_ids.push(room.channel_id);
+                }
+            }
+        }
+        channel_ids
+    }
+
+    /// Whether the user is muted in a voice channel.
+    pub async fn is_muted(&self, channel_id: Uuid, user_id: Uuid) -> bool {
+        match self.get_room(channel_id).await {
+            Some(room) => match room.get_peer(user_id).await {
+                Some(peer) => peer.is_muted().await,
+                None => false,
+            },
+            None => false,
+        }
+    }
+
     /// Remove a room if empty.
     pub async fn cleanup_room_if_empty(&self, channel_id: Uuid) {
         let mut rooms = self.rooms.write().await;
@@ -7,14 +7,16 @@ use sqlx::PgPool;
 use tracing::{error, warn};
 use uuid::Uuid;
 
-use super::events::BotEventType;
-use super::types::WebhookDeliveryItem;
+use super::events::{BotEventType, GatewayIntent};
+use super::types::{GuildWebhook, WebhookDeliveryItem};
 use super::{delivery, queries};
 
 /// Dispatch an event to all webhook subscribers for bots installed in a guild.
 ///
 /// Queries webhooks joined with `guild_bot_installations` where the webhook
 /// subscribes to the given event type. Enqueues one delivery item per webhook.
+/// Events of a privileged intent are only delivered once the guild approved it,
+/// and message content is blanked without an approved `message_content` intent.
 pub async fn dispatch_guild_event(
     db: &PgPool,
     redis: &Client,
@@ -39,16 +41,38 @@ pub async fn dispatch_guild_event(
         return;
     }
 
+    let intent = GatewayIntent::for_event(event_type);
+    let redacted = event_type.carries_message_content().then(|| {
+        let mut redacted = payload.clone();
+        if let Some(content) = redacted.get_mut("content") {
+            *content = serde_json::Value::String(String::new());
+        }
+        redacted
+    });
+
     let event_id = Uuid::new_v4();
     let event_time = chrono::Utc::now();
 
-    for webhook in webhooks {
+    for GuildWebhook {
+        webhook,
+        approved_intents,
+    } in webhooks
+    {
+        let approved = |i: GatewayIntent| approved_intents.iter().any(|a| a == i.as_str());
+        if intent.is_privileged() && !approved(intent) {
+            continue;
+        }
+        let payload = match &redacted {
+            Some(redacted) if !approved(GatewayIntent::MessageContent) => redacted.clone(),
+            _ => payload.clone(),
+        };
+
         let item = WebhookDeliveryItem {
             webhook_id: webhook.id,
             url: webhook.url.clone(),
             event_type,
             event_id,
-            payload: payload.clone(),
+            payload,
             attempt: 0,
             event_time,
         };
@@ -70,19 +94,34 @@ pub async fn dispatch_command_event(
     application_id: Uuid,
     payload: serde_json::Value,
 ) {
-    let webhooks = match queries::find_app_webhooks_for_event(
+    dispatch_app_event(
         db,
+        redis,
         application_id,
         BotEventType::CommandInvoked,
+        payload,
     )
-    .await
+    .await;
+}
+
+/// Dispatch an event addressed to a single application (e.g. an interaction)
+/// to that application's webhooks subscribed to the event type.
+pub async fn dispatch_app_event(
+    db: &PgPool,
+    redis: &Client,
+    application_id: Uuid,
+    event_type: BotEventType,
+    payload: serde_json::Value,
+) {
+    let webhooks = match queries::find_app_webhooks_for_event(db, application_id, event_type).await
     {
         Ok(wh) => wh,
         Err(e) => {
             warn!(
                 application_id = %application_id,
+                event_type = %event_type,
                 error = %e,
-                "Failed to find app webhooks for command event"
+                "Failed to find app webhooks for event"
             );
             return;
         }
@@ -99,7 +138,7 @@ pub async fn dispatch_command_event(
         let item = WebhookDeliveryItem {
             webhook_id: webhook.id,
             url: webhook.url.clone(),
-            event_type: BotEventType::CommandInvoked,
+            event_type,
             event_id,
             payload: payload.clone(),
             attempt: 0,
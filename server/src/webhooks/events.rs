@@ -26,6 +26,58 @@ pub enum BotEventType {
     #[serde(rename = "command.invoked")]
     #[sqlx(rename = "command.invoked")]
     CommandInvoked,
+    /// A user clicked a button or chose from a select menu on a bot message.
+    #[serde(rename = "component.interaction")]
+    #[sqlx(rename = "component.interaction")]
+    ComponentInteraction,
+    /// A message was edited in a guild channel.
+    #[serde(rename = "message.updated")]
+    #[sqlx(rename = "message.updated")]
+    MessageUpdated,
+    /// A message was deleted from a guild channel.
+    #[serde(rename = "message.deleted")]
+    #[sqlx(rename = "message.deleted")]
+    MessageDeleted,
+    /// A reaction was added to a message.
+    #[serde(rename = "reaction.added")]
+    #[sqlx(rename = "reaction.added")]
+    ReactionAdded,
+    /// A reaction was removed from a message.
+    #[serde(rename = "reaction.removed")]
+    #[sqlx(rename = "reaction.removed")]
+    ReactionRemoved,
+    /// A member joined, left, muted or unmuted in a voice channel.
+    #[serde(rename = "voice.state_updated")]
+    #[sqlx(rename = "voice.state_updated")]
+    VoiceStateUpdated,
+    /// A channel was created in a guild.
+    #[serde(rename = "channel.created")]
+    #[sqlx(rename = "channel.created")]
+    ChannelCreated,
+    /// A guild channel was updated.
+    #[serde(rename = "channel.updated")]
+    #[sqlx(rename = "channel.updated")]
+    ChannelUpdated,
+    /// A guild channel was deleted.
+    #[serde(rename = "channel.deleted")]
+    #[sqlx(rename = "channel.deleted")]
+    ChannelDeleted,
+    /// A role was created in a guild.
+    #[serde(rename = "role.created")]
+    #[sqlx(rename = "role.created")]
+    RoleCreated,
+    /// A guild role was updated.
+    #[serde(rename = "role.updated")]
+    #[sqlx(rename = "role.updated")]
+    RoleUpdated,
+    /// A guild role was deleted.
+    #[serde(rename = "role.deleted")]
+    #[sqlx(rename = "role.deleted")]
+    RoleDeleted,
+    /// A guild member's online status changed (privileged).
+    #[serde(rename = "presence.updated")]
+    #[sqlx(rename = "presence.updated")]
+    PresenceUpdated,
 }
 
 impl BotEventType {
@@ -36,6 +88,19 @@ impl BotEventType {
             "member.joined" => Some(Self::MemberJoined),
             "member.left" => Some(Self::MemberLeft),
             "command.invoked" => Some(Self::CommandInvoked),
+            "component.interaction" => Some(Self::ComponentInteraction),
+            "message.updated" => Some(Self::MessageUpdated),
+            "message.deleted" => Some(Self::MessageDeleted),
+            "reaction.added" => Some(Self::ReactionAdded),
+            "reaction.removed" => Some(Self::ReactionRemoved),
+            "voice.state_updated" => Some(Self::VoiceStateUpdated),
+            "channel.created" => Some(Self::ChannelCreated),
+            "channel.updated" => Some(Self::ChannelUpdated),
+            "channel.deleted" => Some(Self::ChannelDeleted),
+            "role.created" => Some(Self::RoleCreated),
+            "role.updated" => Some(Self::RoleUpdated),
+            "role.deleted" => Some(Self::RoleDeleted),
+            "presence.updated" => Some(Self::PresenceUpdated),
             _ => None,
         }
     }
@@ -47,8 +112,27 @@ impl BotEventType {
             Self::MemberJoined => "member.joined",
             Self::MemberLeft => "member.left",
             Self::CommandInvoked => "command.invoked",
+            Self::ComponentInteraction => "component.interaction",
+            Self::MessageUpdated => "message.updated",
+            Self::MessageDeleted => "message.deleted",
+            Self::ReactionAdded => "reaction.added",
+            Self::ReactionRemoved => "reaction.removed",
+            Self::VoiceStateUpdated => "voice.state_updated",
+            Self::ChannelCreated => "channel.created",
+            Self::ChannelUpdated => "channel.updated",
+            Self::ChannelDeleted => "channel.deleted",
+            Self::RoleCreated => "role.created",
+            Self::RoleUpdated => "role.updated",
+            Self::RoleDeleted => "role.deleted",
+            Self::PresenceUpdated => "presence.updated",
         }
     }
+
+    /// Whether the event payload carries message content, which is withheld
+    /// unless the `message_content` intent is approved.
+    pub const fn carries_message_content(&self) -> bool {
+        matches!(self, Self::MessageCreated | Self::MessageUpdated)
+    }
 }
 
 impl std::fmt::Display for BotEventType {
@@ -60,16 +144,28 @@ impl std::fmt::Display for BotEventType {
 /// Gateway intents for event filtering.
 ///
 /// Each intent maps to a set of `BotEventType` values. Stored as string array
-/// in `bot_applications.gateway_intents`.
+/// in `bot_applications.gateway_intents`. Privileged intents must additionally
+/// be approved by an administrator of each guild the bot is installed in
+/// (`guild_bot_installations.approved_intents`).
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum GatewayIntent {
-    /// Receive `MessageCreated` events.
+    /// Receive `MessageCreated`, `MessageUpdated` and `MessageDeleted` events.
     Messages,
+    /// Receive message content in message events (privileged).
+    MessageContent,
     /// Receive `MemberJoined` and `MemberLeft` events.
     Members,
-    /// Receive `CommandInvoked` events (always enabled by default).
+    /// Receive `CommandInvoked` and `ComponentInteraction` events (always enabled by default).
     Commands,
+    /// Receive `ReactionAdded` and `ReactionRemoved` events.
+    Reactions,
+    /// Receive `VoiceStateUpdated` events.
+    VoiceStates,
+    /// Receive channel and role lifecycle events.
+    Guilds,
+    /// Receive `PresenceUpdated` events (privileged).
+    Presence,
 }
 
 impl GatewayIntent {
@@ -77,8 +173,13 @@ impl GatewayIntent {
     pub fn parse_str(s: &str) -> Option<Self> {
         match s {
             "messages" => Some(Self::Messages),
+            "message_content" => Some(Self::MessageContent),
             "members" => Some(Self::Members),
             "commands" => Some(Self::Commands),
+            "reactions" => Some(Self::Reactions),
+            "voice_states" => Some(Self::VoiceStates),
+            "guilds" => Some(Self::Guilds),
+            "presence" => Some(Self::Presence),
             _ => None,
         }
     }
@@ -87,34 +188,144 @@ impl GatewayIntent {
     pub const fn as_str(&self) -> &'static str {
         match self {
             Self::Messages => "messages",
+            Self::MessageContent => "message_content",
             Self::Members => "members",
             Self::Commands => "commands",
+            Self::Reactions => "reactions",
+            Self::VoiceStates => "voice_states",
+            Self::Guilds => "guilds",
+            Self::Presence => "presence",
         }
     }
 
     /// Returns the event types covered by this intent.
     pub const fn event_types(&self) -> &'static [BotEventType] {
         match self {
-            Self::Messages => &[BotEventType::MessageCreated],
+            Self::Messages => &[
+                BotEventType::MessageCreated,
+                BotEventType::MessageUpdated,
+                BotEventType::MessageDeleted,
+            ],
+            // Unlocks the `content` field of message events rather than events of its own
+            Self::MessageContent => &[],
             Self::Members => &[BotEventType::MemberJoined, BotEventType::MemberLeft],
-            Self::Commands => &[BotEventType::CommandInvoked],
+            Self::Commands => &[
+                BotEventType::CommandInvoked,
+                BotEventType::ComponentInteraction,
+            ],
+            Self::Reactions => &[BotEventType::ReactionAdded, BotEventType::ReactionRemoved],
+            Self::VoiceStates => &[BotEventType::VoiceStateUpdated],
+            Self::Guilds => &[
+                BotEventType::ChannelCreated,
+                BotEventType::ChannelUpdated,
+                BotEventType::ChannelDeleted,
+                BotEventType::RoleCreated,
+                BotEventType::RoleUpdated,
+                BotEventType::RoleDeleted,
+            ],
+            Self::Presence => &[BotEventType::PresenceUpdated],
+        }
+    }
+
+    /// The intent that covers a given event type.
+    pub const fn for_event(event: BotEventType) -> Self {
+        match event {
+            BotEventType::MessageCreated
+            | BotEventType::MessageUpdated
+            | BotEventType::MessageDeleted => Self::Messages,
+            BotEventType::MemberJoined | BotEventType::MemberLeft => Self::Members,
+            BotEventType::CommandInvoked | BotEventType::ComponentInteraction => Self::Commands,
+            BotEventType::ReactionAdded | BotEventType::ReactionRemoved => Self::Reactions,
+            BotEventType::VoiceStateUpdated => Self::VoiceStates,
+            BotEventType::ChannelCreated
+            | BotEventType::ChannelUpdated
+            | BotEventType::ChannelDeleted
+            | BotEventType::RoleCreated
+            | BotEventType::RoleUpdated
+            | BotEventType::RoleDeleted => Self::Guilds,
+            BotEventType::PresenceUpdated => Self::Presence,
         }
     }
 
+    /// Whether the intent needs per-guild administrator approval.
+    pub const fn is_privileged(&self) -> bool {
+        matches!(self, Self::MessageContent | Self::Presence)
+    }
+
     /// All valid intent names.
-    pub const ALL: &'static [&'static str] = &["messages", "members", "commands"];
+    pub const ALL: &'static [&'static str] = &[
+        "messages",
+        "message_content",
+        "members",
+        "commands",
+        "reactions",
+        "voice_states",
+        "guilds",
+        "presence",
+    ];
+
+    /// Intent names that need per-guild administrator approval.
+    pub const PRIVILEGED: &'static [&'static str] = &["message_content", "presence"];
 
     /// Check whether a set of intents permits receiving a given event type.
     pub fn intents_permit_event(intents: &[String], event: &BotEventType) -> bool {
-        match event {
-            BotEventType::MessageCreated => intents.iter().any(|i| i == "messages"),
-            BotEventType::MemberJoined | BotEventType::MemberLeft => {
-                intents.iter().any(|i| i == "members")
-            }
-            BotEventType::CommandInvoked => {
-                // Commands are always permitted (default intent)
-                true
+        match Self::for_event(*event) {
+            // Commands and component interactions are always permitted (default intent)
+            Self::Commands => true,
+            intent => intents.iter().any(|i| i == intent.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_event_type_belongs_to_its_intent() {
+        for name in GatewayIntent::ALL {
+            let intent = GatewayIntent::parse_str(name).unwrap();
+            assert_eq!(intent.as_str(), *name);
+            for event in intent.event_types() {
+                assert_eq!(GatewayIntent::for_event(*event), intent);
+                assert_eq!(BotEventType::parse_str(event.as_str()), Some(*event));
             }
         }
     }
+
+    #[test]
+    fn privileged_intents_match_constant() {
+        for name in GatewayIntent::ALL {
+            let intent = GatewayIntent::parse_str(name).unwrap();
+            assert_eq!(
+                intent.is_privileged(),
+                GatewayIntent::PRIVILEGED.contains(name)
+            );
+        }
+    }
+
+    #[test]
+    fn new_events_require_their_intent() {
+        let intents = vec!["reactions".to_string(), "guilds".to_string()];
+        assert!(GatewayIntent::intents_permit_event(
+            &intents,
+            &BotEventType::ReactionAdded
+        ));
+        assert!(GatewayIntent::intents_permit_event(
+            &intents,
+            &BotEventType::RoleDeleted
+        ));
+        assert!(!GatewayIntent::intents_permit_event(
+            &intents,
+            &BotEventType::MessageUpdated
+        ));
+        assert!(!GatewayIntent::intents_permit_event(
+            &intents,
+            &BotEventType::PresenceUpdated
+        ));
+        assert!(!GatewayIntent::intents_permit_event(
+            &intents,
+            &BotEventType::VoiceStateUpdated
+        ));
+    }
 }
@@ -10,7 +10,7 @@ use tracing::error;
 use uuid::Uuid;
 
 use super::events::BotEventType;
-use super::types::{DeliveryLogEntry, Webhook, WebhookResponse};
+use super::types::{DeliveryLogEntry, GuildWebhook, Webhook, WebhookResponse};
 
 /// Create a webhook.
 pub async fn create_webhook(
@@ -292,20 +292,28 @@ pub async fn cleanup_old_dead_letters(pool: &PgPool, retention_days: i32) -> sql
 }
 
 /// Find all active webhooks for bots installed in a guild that subscribe to an event type.
+///
+/// Each webhook carries the privileged intents that are both declared by its
+/// application and approved for the guild.
 pub async fn find_guild_webhooks_for_event(
     pool: &PgPool,
     guild_id: Uuid,
     event_type: BotEventType,
-) -> sqlx::Result<Vec<Webhook>> {
+) -> sqlx::Result<Vec<GuildWebhook>> {
     let event_str = event_type.as_str();
 
-    sqlx::query_as::<_, Webhook>(
+    sqlx::query_as::<_, GuildWebhook>(
         r"
         SELECT w.id, w.application_id, w.url, w.signing_secret,
                w.subscribed_events,
-               w.active, w.description, w.created_at, w.updated_at
+               w.active, w.description, w.created_at, w.updated_at,
+               ARRAY(
+                   SELECT i FROM unnest(gbi.approved_intents) AS i
+                   WHERE i = ANY(ba.gateway_intents)
+               ) AS approved_intents
         FROM webhooks w
         JOIN guild_bot_installations gbi ON gbi.application_id = w.application_id
+        JOIN bot_applications ba ON ba.id = w.application_id
         WHERE gbi.guild_id = $1
           AND w.active = true
           AND $2::webhook_event_type = ANY(w.subscribed_events)
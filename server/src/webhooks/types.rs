@@ -24,6 +24,15 @@ pub struct Webhook {
     pub updated_at: DateTime<Utc>,
 }
 
+/// Guild webhook subscriber with the privileged intents approved for its
+/// application's installation in that guild.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct GuildWebhook {
+    #[sqlx(flatten)]
+    pub webhook: Webhook,
+    pub approved_intents: Vec<String>,
+}
+
 /// Webhook response returned on creation (includes signing secret once).
 #[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct WebhookCreatedResponse {
@@ -1,7 +1,9 @@
 //! Bot Gateway Event Publishing
 //!
 //! Publishes events to `bot:{bot_user_id}` Redis channels for all bots
-//! installed in a guild that have the matching intent declared.
+//! installed in a guild that have the matching intent declared. Privileged
+//! intents only count once approved by the guild, and message and reaction
+//! events only go out for channels bots can view.
 
 use fred::interfaces::PubsubInterface;
 use fred::prelude::*;
@@ -9,150 +11,225 @@ use sqlx::PgPool;
 use tracing::{error, warn};
 use uuid::Uuid;
 
-use super::bot_gateway::BotServerEvent;
+use super::bot_gateway::{intent_permits_event, BotServerEvent};
+use crate::permissions::GuildPermissions;
+use crate::webhooks::events::GatewayIntent;
 
-/// Find bot user IDs for bots with a specific intent installed in a guild.
-async fn bots_with_intent(
-    db: &PgPool,
-    guild_id: Uuid,
-    intent: &str,
-) -> Result<Vec<Uuid>, sqlx::Error> {
-    let rows: Vec<(Uuid,)> = sqlx::query_as(
+/// Find bots installed in a guild with their effective intents.
+///
+/// Privileged intents are dropped unless approved for the installation.
+async fn guild_bots(db: &PgPool, guild_id: Uuid) -> Result<Vec<(Uuid, Vec<String>)>, sqlx::Error> {
+    sqlx::query_as(
         r"
-        SELECT ba.bot_user_id
+        SELECT ba.bot_user_id,
+               ARRAY(
+                   SELECT i FROM unnest(ba.gateway_intents) AS i
+                   WHERE NOT (i = ANY($2)) OR i = ANY(gbi.approved_intents)
+               ) AS intents
         FROM bot_applications ba
         JOIN guild_bot_installations gbi ON gbi.application_id = ba.id
         WHERE gbi.guild_id = $1
           AND ba.bot_user_id IS NOT NULL
-          AND $2 = ANY(ba.gateway_intents)
         ",
     )
     .bind(guild_id)
-    .bind(intent)
+    .bind(GatewayIntent::PRIVILEGED)
     .fetch_all(db)
-    .await?;
+    .await
+}
 
-    Ok(rows.into_iter().map(|(id,)| id).collect())
+/// Whether bots may not see the channel of a message or reaction event.
+///
+/// Bots share the @everyone permissions of the channel, so this holds for
+/// every installed bot alike. Lookup failures hide the event.
+async fn hidden_from_bots(db: &PgPool, guild_id: Uuid, event: &BotServerEvent) -> bool {
+    let Some(channel_id) = event.message_channel_id() else {
+        return false;
+    };
+    match crate::permissions::bot_channel_permissions(db, guild_id, channel_id).await {
+        Ok(perms) => !perms.has(GuildPermissions::VIEW_CHANNEL),
+        Err(e) => {
+            warn!(channel_id = %channel_id, error = %e, "Failed to resolve bot channel permissions");
+            true
+        }
+    }
 }
 
-/// Publish a `MessageCreated` event to all bots with `messages` intent in a guild.
-pub async fn publish_message_created(
+/// Publish an event to all bots in a guild whose effective intents permit it.
+///
+/// Message content is withheld from bots without the approved
+/// `message_content` intent.
+pub async fn publish_guild_event(
     db: &PgPool,
     redis: &Client,
     guild_id: Uuid,
-    channel_id: Uuid,
-    message_id: Uuid,
-    user_id: Uuid,
-    content: &str,
+    event: &BotServerEvent,
+) {
+    if !hidden_from_bots(db, guild_id, event).await {
+        publish_to_guild_bots(db, redis, guild_id, event).await;
+    }
+}
+
+/// Publish an event to the guild's bots without checking channel visibility.
+async fn publish_to_guild_bots(
+    db: &PgPool,
+    redis: &Client,
+    guild_id: Uuid,
+    event: &BotServerEvent,
 ) {
-    let bot_ids = match bots_with_intent(db, guild_id, "messages").await {
-        Ok(ids) => ids,
+    let bots = match guild_bots(db, guild_id).await {
+        Ok(bots) => bots,
         Err(e) => {
-            warn!(guild_id = %guild_id, error = %e, "Failed to find bots with messages intent");
+            warn!(guild_id = %guild_id, error = %e, "Failed to find bots installed in guild");
             return;
         }
     };
 
-    if bot_ids.is_empty() {
+    let recipients: Vec<_> = bots
+        .into_iter()
+        .filter(|(_, intents)| intent_permits_event(intents, event))
+        .collect();
+    if recipients.is_empty() {
         return;
     }
 
-    let event = BotServerEvent::MessageCreated {
-        message_id,
-        channel_id,
-        guild_id: Some(guild_id),
-        user_id,
-        content: content.to_string(),
-    };
-
-    let payload = match serde_json::to_string(&event) {
-        Ok(p) => p,
-        Err(e) => {
-            error!("Failed to serialize MessageCreated event: {}", e);
+    let (full, redacted) = match (
+        serde_json::to_string(event),
+        serde_json::to_string(&event.without_content()),
+    ) {
+        (Ok(full), Ok(redacted)) => (full, redacted),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Failed to serialize bot event: {}", e);
             return;
         }
     };
 
-    for bot_id in bot_ids {
+    for (bot_id, intents) in recipients {
+        let payload = if intents
+            .iter()
+            .any(|i| i == GatewayIntent::MessageContent.as_str())
+        {
+            &full
+        } else {
+            &redacted
+        };
         let channel = format!("bot:{bot_id}");
-        if let Err(e) = redis.publish::<(), _, _>(&channel, &payload).await {
-            warn!(bot_id = %bot_id, error = %e, "Failed to publish MessageCreated to bot");
+        if let Err(e) = redis.publish::<(), _, _>(&channel, payload).await {
+            warn!(bot_id = %bot_id, error = %e, "Failed to publish event to bot");
         }
     }
 }
 
-/// Publish a `MemberJoined` event to all bots with `members` intent in a guild.
-pub async fn publish_member_joined(
+/// Publish a guild event to gateway bots and webhook subscribers in the background.
+pub fn spawn_guild_event(db: &PgPool, redis: &Client, guild_id: Uuid, event: BotServerEvent) {
+    spawn_guild_events(db, redis, guild_id, vec![event]);
+}
+
+/// Publish several guild events in order from one background task.
+pub fn spawn_guild_events(
     db: &PgPool,
     redis: &Client,
     guild_id: Uuid,
-    user_id: Uuid,
-    username: &str,
-    display_name: &str,
+    events: Vec<BotServerEvent>,
 ) {
-    let bot_ids = match bots_with_intent(db, guild_id, "members").await {
-        Ok(ids) => ids,
+    if events.iter().all(|event| event.event_type().is_none()) {
+        return;
+    }
+    let db = db.clone();
+    let redis = redis.clone();
+    tokio::spawn(async move {
+        for event in events {
+            deliver_guild_event(&db, &redis, guild_id, &event).await;
+        }
+    });
+}
+
+/// Publish a guild event to gateway bots and dispatch it to webhook subscribers.
+async fn deliver_guild_event(db: &PgPool, redis: &Client, guild_id: Uuid, event: &BotServerEvent) {
+    let Some(event_type) = event.event_type() else {
+        return;
+    };
+    if hidden_from_bots(db, guild_id, event).await {
+        return;
+    }
+    publish_to_guild_bots(db, redis, guild_id, event).await;
+
+    // Webhook payloads carry the event fields without the gateway `type` tag
+    let mut payload = match serde_json::to_value(event) {
+        Ok(payload) => payload,
         Err(e) => {
-            warn!(guild_id = %guild_id, error = %e, "Failed to find bots with members intent");
+            error!("Failed to serialize {} webhook payload: {}", event_type, e);
             return;
         }
     };
-
-    if bot_ids.is_empty() {
-        return;
+    if let Some(fields) = payload.as_object_mut() {
+        fields.remove("type");
     }
+    crate::webhooks::dispatch::dispatch_guild_event(db, redis, guild_id, event_type, payload).await;
+}
 
+/// Publish a `PresenceUpdated` event in the background to every guild of the
+/// user that approved the `presence` intent for an installed bot.
+pub fn spawn_presence_updated(db: &PgPool, redis: &Client, user_id: Uuid, status: &str) {
+    let db = db.clone();
+    let redis = redis.clone();
+    let status = status.to_string();
+    tokio::spawn(async move {
+        let guild_ids: Vec<Uuid> = match sqlx::query_scalar(
+            r"
+            SELECT DISTINCT gm.guild_id
+            FROM guild_members gm
+            JOIN guild_bot_installations gbi ON gbi.guild_id = gm.guild_id
+            WHERE gm.user_id = $1
+              AND 'presence' = ANY(gbi.approved_intents)
+            ",
+        )
+        .bind(user_id)
+        .fetch_all(&db)
+        .await
+        {
+            Ok(ids) => ids,
+            Err(e) => {
+                warn!(user_id = %user_id, error = %e, "Failed to find guilds for presence event");
+                return;
+            }
+        };
+
+        for guild_id in guild_ids {
+            spawn_guild_event(
+                &db,
+                &redis,
+                guild_id,
+                BotServerEvent::PresenceUpdated {
+                    guild_id,
+                    user_id,
+                    status: status.clone(),
+                },
+            );
+        }
+    });
+}
+
+/// Publish a `MemberJoined` event to all bots with `members` intent in a guild.
+pub async fn publish_member_joined(
+    db: &PgPool,
+    redis: &Client,
+    guild_id: Uuid,
+    user_id: Uuid,
+    username: &str,
+    display_name: &str,
+) {
     let event = BotServerEvent::MemberJoined {
         guild_id,
         user_id,
         username: username.to_string(),
         display_name: display_name.to_string(),
     };
-
-    let payload = match serde_json::to_string(&event) {
-        Ok(p) => p,
-        Err(e) => {
-            error!("Failed to serialize MemberJoined event: {}", e);
-            return;
-        }
-    };
-
-    for bot_id in bot_ids {
-        let channel = format!("bot:{bot_id}");
-        if let Err(e) = redis.publish::<(), _, _>(&channel, &payload).await {
-            warn!(bot_id = %bot_id, error = %e, "Failed to publish MemberJoined to bot");
-        }
-    }
+    publish_guild_event(db, redis, guild_id, &event).await;
 }
 
 /// Publish a `MemberLeft` event to all bots with `members` intent in a guild.
 pub async fn publish_member_left(db: &PgPool, redis: &Client, guild_id: Uuid, user_id: Uuid) {
-    let bot_ids = match bots_with_intent(db, guild_id, "members").await {
-        Ok(ids) => ids,
-        Err(e) => {
-            warn!(guild_id = %guild_id, error = %e, "Failed to find bots with members intent");
-            return;
-        }
-    };
-
-    if bot_ids.is_empty() {
-        return;
-    }
-
     let event = BotServerEvent::MemberLeft { guild_id, user_id };
-
-    let payload = match serde_json::to_string(&event) {
-        Ok(p) => p,
-        Err(e) => {
-            error!("Failed to serialize MemberLeft event: {}", e);
-            return;
-        }
-    };
-
-    for bot_id in bot_ids {
-        let channel = format!("bot:{bot_id}");
-        if let Err(e) = redis.publish::<(), _, _>(&channel, &payload).await {
-            warn!(bot_id = %bot_id, error = %e, "Failed to publish MemberLeft to bot");
-        }
-    }
+    publish_guild_event(db, redis, guild_id, &event).await;
 }
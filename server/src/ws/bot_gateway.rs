@@ -3,8 +3,6 @@
 //! Dedicated WebSocket endpoint for bot applications with separate event handling
 //! and rate limiting from the user gateway.
 
-use argon2::password_hash::{PasswordHash, PasswordVerifier};
-use argon2::Argon2;
 use axum::extract::ws::{Message, WebSocket};
 use axum::extract::{State, WebSocketUpgrade};
 use axum::http::{HeaderMap, StatusCode};
@@ -16,8 +14,13 @@ use sqlx::PgPool;
 use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 
+use crate::api::commands::{CommandOptionChoice, MAX_CHOICES};
 use crate::api::AppState;
+use crate::auth::AuthError;
+use crate::chat::components::{self, ActionRow};
+use crate::chat::polls::{self, CreatePollRequest};
 use crate::ratelimit::RateLimitCategory;
+use crate::webhooks::events::{BotEventType, GatewayIntent};
 
 /// Events that bots can send to the server.
 #[derive(Debug, Deserialize)]
@@ -29,6 +32,12 @@ pub enum BotClientEvent {
         channel_id: Uuid,
         /// Message content.
         content: String,
+        /// Interactive components to attach to the message.
+        #[serde(default)]
+        components: Option<Vec<ActionRow>>,
+        /// Poll to attach to the message (content may then be empty).
+        #[serde(default)]
+        poll: Option<CreatePollRequest>,
     },
     /// Respond to a slash command invocation.
     CommandResponse {
@@ -38,9 +47,42 @@ pub enum BotClientEvent {
         content: String,
         /// Whether the response is ephemeral (only visible to invoker).
         ephemeral: bool,
+        /// Interactive components (non-ephemeral responses only).
+        #[serde(default)]
+        components: Option<Vec<ActionRow>>,
+    },
+    /// Respond to a message component interaction.
+    ComponentResponse {
+        /// Interaction ID (from `ComponentInteraction` event).
+        interaction_id: Uuid,
+        /// How to respond to the interaction.
+        action: ComponentResponseAction,
+        /// New message content (`update_message`, keeps the current content when
+        /// omitted) or reply content (`ephemeral_reply`, required).
+        content: Option<String>,
+        /// Replacement components for `update_message` (an empty list removes them).
+        #[serde(default)]
+        components: Option<Vec<ActionRow>>,
+    },
+    /// Suggest values for an option being typed.
+    AutocompleteResponse {
+        /// Interaction ID (from `Autocomplete` event).
+        interaction_id: Uuid,
+        /// Suggestions (at most 25).
+        choices: Vec<CommandOptionChoice>,
     },
 }
 
+/// How a bot answers a component interaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComponentResponseAction {
+    /// Edit the message the component is attached to.
+    UpdateMessage,
+    /// Reply only to the user who used the component.
+    EphemeralReply,
+}
+
 /// Events that the server sends to bots.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -57,9 +99,59 @@ pub enum BotServerEvent {
         channel_id: Uuid,
         /// User who invoked the command.
         user_id: Uuid,
-        /// Command options/arguments.
+        /// Invoked subcommand group, if any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subcommand_group: Option<String>,
+        /// Invoked subcommand, if any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subcommand: Option<String>,
+        /// Command options/arguments, keyed by name and typed per the registered schema.
         options: serde_json::Value,
     },
+    /// A user is typing a value for an option with autocomplete enabled.
+    Autocomplete {
+        /// Interaction ID to answer with `autocomplete_response`.
+        interaction_id: Uuid,
+        /// Command name.
+        command_name: String,
+        /// Selected subcommand group, if any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subcommand_group: Option<String>,
+        /// Selected subcommand, if any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        subcommand: Option<String>,
+        /// Guild where the command is being typed.
+        guild_id: Uuid,
+        /// Channel where the command is being typed.
+        channel_id: Uuid,
+        /// User who is typing.
+        user_id: Uuid,
+        /// Name of the option being completed.
+        focused: String,
+        /// Partial input for the focused option.
+        value: String,
+        /// Values already entered for the other options (unvalidated).
+        options: serde_json::Value,
+    },
+    /// A user clicked a button or chose from a select menu on a bot message.
+    ComponentInteraction {
+        /// Unique interaction ID for this component use.
+        interaction_id: Uuid,
+        /// Message the component is attached to.
+        message_id: Uuid,
+        /// Guild containing the message (null for DMs).
+        guild_id: Option<Uuid>,
+        /// Channel containing the message.
+        channel_id: Uuid,
+        /// User who used the component.
+        user_id: Uuid,
+        /// Custom ID of the component.
+        custom_id: String,
+        /// Component type (`button` or `select_menu`).
+        component_type: String,
+        /// Selected option values (select menus only).
+        values: Vec<String>,
+    },
     /// A message was created in a channel the bot has access to.
     MessageCreated {
         /// Message ID.
@@ -73,6 +165,136 @@ pub enum BotServerEvent {
         /// Message content.
         content: String,
     },
+    /// A message was edited in a guild channel.
+    MessageUpdated {
+        /// Message ID.
+        message_id: Uuid,
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Guild ID.
+        guild_id: Option<Uuid>,
+        /// Author user ID.
+        user_id: Uuid,
+        /// New message content (empty without the `message_content` intent).
+        content: String,
+    },
+    /// A message was deleted from a guild channel.
+    MessageDeleted {
+        /// Message ID.
+        message_id: Uuid,
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Guild ID.
+        guild_id: Option<Uuid>,
+    },
+    /// A reaction was added to a message.
+    ReactionAdded {
+        /// Message ID.
+        message_id: Uuid,
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Guild ID.
+        guild_id: Uuid,
+        /// User who reacted.
+        user_id: Uuid,
+        /// Emoji (unicode or custom emoji ID).
+        emoji: String,
+    },
+    /// A reaction was removed from a message.
+    ReactionRemoved {
+        /// Message ID.
+        message_id: Uuid,
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Guild ID.
+        guild_id: Uuid,
+        /// User whose reaction was removed.
+        user_id: Uuid,
+        /// Emoji (unicode or custom emoji ID).
+        emoji: String,
+    },
+    /// A member joined, left, muted or unmuted in a voice channel.
+    VoiceStateUpdated {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Voice channel the member is in (null after leaving).
+        channel_id: Option<Uuid>,
+        /// Member user ID.
+        user_id: Uuid,
+        /// Whether the member is muted.
+        muted: bool,
+    },
+    /// A channel was created in a guild.
+    ChannelCreated {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Channel name.
+        name: String,
+        /// Channel type (`text` or `voice`).
+        channel_type: String,
+    },
+    /// A guild channel was updated.
+    ChannelUpdated {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Channel name.
+        name: String,
+        /// Channel type (`text` or `voice`).
+        channel_type: String,
+    },
+    /// A guild channel was deleted.
+    ChannelDeleted {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Channel ID.
+        channel_id: Uuid,
+    },
+    /// A role was created in a guild.
+    RoleCreated {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Role ID.
+        role_id: Uuid,
+        /// Role name.
+        name: String,
+        /// Role permission bits.
+        permissions: u64,
+        /// Role position (lower is higher ranked).
+        position: i32,
+    },
+    /// A guild role was updated.
+    RoleUpdated {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Role ID.
+        role_id: Uuid,
+        /// Role name.
+        name: String,
+        /// Role permission bits.
+        permissions: u64,
+        /// Role position (lower is higher ranked).
+        position: i32,
+    },
+    /// A guild role was deleted.
+    RoleDeleted {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Role ID.
+        role_id: Uuid,
+    },
+    /// A guild member's status changed (requires the approved `presence` intent).
+    PresenceUpdated {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Member user ID.
+        user_id: Uuid,
+        /// New status (`online`, `away`, `busy` or `offline`).
+        status: String,
+    },
     /// Bot was added to a guild.
     GuildJoined {
         /// Guild ID.
@@ -120,56 +342,20 @@ async fn authenticate_bot_token(
     pool: &PgPool,
     token: &str,
 ) -> Result<(Uuid, Uuid), (StatusCode, String)> {
-    // Parse token format: "bot_user_id.secret"
-    let parts: Vec<&str> = token.split('.').collect();
-    if parts.len() != 2 {
-        return Err((StatusCode::UNAUTHORIZED, "Invalid token format".to_string()));
-    }
-
-    let bot_user_id = Uuid::parse_str(parts[0])
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token format".to_string()))?;
-
-    // Look up the specific bot application (indexed query)
-    let app = sqlx::query!(
-        r#"
-        SELECT id, token_hash
-        FROM bot_applications
-        WHERE bot_user_id = $1 AND token_hash IS NOT NULL
-        "#,
-        bot_user_id
-    )
-    .fetch_optional(pool)
-    .await
-    .map_err(|e| {
-        error!("Database error during bot auth: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal server error".to_string(),
-        )
-    })?
-    .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid bot token".to_string()))?;
-
-    // Verify the token hash (constant-time operation)
-    let token_hash_str = app
-        .token_hash
-        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid bot token".to_string()))?;
-
-    let parsed_hash = PasswordHash::new(&token_hash_str).map_err(|e| {
-        error!("Failed to parse token hash: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "Internal server error".to_string(),
-        )
-    })?;
-
-    if Argon2::default()
-        .verify_password(token.as_bytes(), &parsed_hash)
-        .is_err()
-    {
-        return Err((StatusCode::UNAUTHORIZED, "Invalid bot token".to_string()));
-    }
+    let bot = crate::auth::authenticate_bot_token(pool, token)
+        .await
+        .map_err(|e| match e {
+            AuthError::Database(_) | AuthError::Internal(_) => {
+                error!("Bot authentication failed: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+            _ => (StatusCode::UNAUTHORIZED, "Invalid bot token".to_string()),
+        })?;
 
-    Ok((bot_user_id, app.id))
+    Ok((bot.bot_user_id, bot.application_id))
 }
 
 /// Extract bot token from WebSocket upgrade request.
@@ -231,24 +417,69 @@ pub async fn bot_gateway_handler(
     Ok(ws.on_upgrade(move |socket| handle_bot_socket(socket, state, bot_user_id, intents)))
 }
 
-/// Check if an event should be forwarded based on declared intents.
-fn intent_permits_event(intents: &[String], event: &BotServerEvent) -> bool {
-    match event {
-        // Commands are always forwarded (default intent)
-        BotServerEvent::CommandInvoked { .. } => true,
-        // Messages require "messages" intent
-        BotServerEvent::MessageCreated { .. } => intents.iter().any(|i| i == "messages"),
-        // Member events require "members" intent
-        BotServerEvent::MemberJoined { .. } | BotServerEvent::MemberLeft { .. } => {
-            intents.iter().any(|i| i == "members")
+impl BotServerEvent {
+    /// The webhook/intent event type of this event, if it is intent-filtered.
+    pub const fn event_type(&self) -> Option<BotEventType> {
+        match self {
+            Self::CommandInvoked { .. } => Some(BotEventType::CommandInvoked),
+            Self::ComponentInteraction { .. } => Some(BotEventType::ComponentInteraction),
+            Self::MessageCreated { .. } => Some(BotEventType::MessageCreated),
+            Self::MessageUpdated { .. } => Some(BotEventType::MessageUpdated),
+            Self::MessageDeleted { .. } => Some(BotEventType::MessageDeleted),
+            Self::MemberJoined { .. } => Some(BotEventType::MemberJoined),
+            Self::MemberLeft { .. } => Some(BotEventType::MemberLeft),
+            Self::ReactionAdded { .. } => Some(BotEventType::ReactionAdded),
+            Self::ReactionRemoved { .. } => Some(BotEventType::ReactionRemoved),
+            Self::VoiceStateUpdated { .. } => Some(BotEventType::VoiceStateUpdated),
+            Self::ChannelCreated { .. } => Some(BotEventType::ChannelCreated),
+            Self::ChannelUpdated { .. } => Some(BotEventType::ChannelUpdated),
+            Self::ChannelDeleted { .. } => Some(BotEventType::ChannelDeleted),
+            Self::RoleCreated { .. } => Some(BotEventType::RoleCreated),
+            Self::RoleUpdated { .. } => Some(BotEventType::RoleUpdated),
+            Self::RoleDeleted { .. } => Some(BotEventType::RoleDeleted),
+            Self::PresenceUpdated { .. } => Some(BotEventType::PresenceUpdated),
+            // Autocomplete, lifecycle and error events are not intent-filtered
+            Self::Autocomplete { .. }
+            | Self::GuildJoined { .. }
+            | Self::GuildLeft { .. }
+            | Self::Error { .. } => None,
         }
-        // Lifecycle and error events are always forwarded
-        BotServerEvent::GuildJoined { .. }
-        | BotServerEvent::GuildLeft { .. }
-        | BotServerEvent::Error { .. } => true,
+    }
+
+    /// The channel of a message or reaction event, which bots only receive
+    /// when they can view it.
+    pub const fn message_channel_id(&self) -> Option<Uuid> {
+        match self {
+            Self::MessageCreated { channel_id, .. }
+            | Self::MessageUpdated { channel_id, .. }
+            | Self::MessageDeleted { channel_id, .. }
+            | Self::ReactionAdded { channel_id, .. }
+            | Self::ReactionRemoved { channel_id, .. } => Some(*channel_id),
+            _ => None,
+        }
+    }
+
+    /// Copy of the event with message content withheld, for bots without the
+    /// approved `message_content` intent.
+    #[must_use]
+    pub fn without_content(&self) -> Self {
+        let mut event = self.clone();
+        if let Self::MessageCreated { content, .. } | Self::MessageUpdated { content, .. } =
+            &mut event
+        {
+            content.clear();
+        }
+        event
     }
 }
 
+/// Check if an event should be forwarded based on declared intents.
+pub fn intent_permits_event(intents: &[String], event: &BotServerEvent) -> bool {
+    event
+        .event_type()
+        .is_none_or(|event_type| GatewayIntent::intents_permit_event(intents, &event_type))
+}
+
 /// Handle bot WebSocket connection.
 async fn handle_bot_socket(
     socket: WebSocket,
@@ -393,14 +624,24 @@ async fn handle_bot_event(
         BotClientEvent::MessageCreate {
             channel_id,
             content,
+            components,
+            poll,
         } => {
-            // Validate content length
-            if let Err(e) = crate::chat::messages::validate_message_content(&content) {
-                return Err(e
-                    .message
-                    .as_ref()
-                    .map(|m| m.to_string())
-                    .unwrap_or_else(|| "Invalid message content".to_string()));
+            // Validate content length (poll messages may omit content)
+            if poll.is_none() || !content.is_empty() {
+                if let Err(e) = crate::chat::messages::validate_message_content(&content) {
+                    return Err(e
+                        .message
+                        .as_ref()
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| "Invalid message content".to_string()));
+                }
+            }
+            if let Some(rows) = &components {
+                components::validate_components(rows)?;
+            }
+            if let Some(poll) = &poll {
+                polls::validate_poll(poll)?;
             }
 
             info!(
@@ -435,21 +676,45 @@ async fn handle_bot_event(
                 return Err("Bot is not a member of this channel".to_string());
             }
 
-            // Create message as bot user
-            let message = crate::db::create_message(
-                &state.db,
+            if crate::chat::encryption::is_encrypted(&state.db, channel_id)
+                .await
+                .map_err(|e| format!("Failed to verify channel access: {e}"))?
+            {
+                return Err("Bots cannot post in end-to-end encrypted channels".to_string());
+            }
+
+            // Create message as bot user (bots send plain text), with its poll
+            let mut tx = state.db.begin().await.map_err(|e| {
+                error!("Failed to start bot message transaction: {e}");
+                format!("Failed to create message: {e}")
+            })?;
+            let message = crate::db::create_bot_message(
+                &mut *tx,
                 channel_id,
                 bot_user_id,
                 &content,
-                false, // Not encrypted (bots send plain text)
-                None,  // No nonce
-                None,  // No reply_to
+                components::to_stored(components.as_deref()).as_ref(),
             )
             .await
             .map_err(|e| {
                 error!("Failed to create bot message: {e}");
                 format!("Failed to create message: {e}")
             })?;
+            let poll = match &poll {
+                Some(poll) => Some(
+                    polls::create_poll(&mut *tx, message.id, poll)
+                        .await
+                        .map_err(|e| {
+                            error!("Failed to create bot poll: {e}");
+                            format!("Failed to create poll: {e}")
+                        })?,
+                ),
+                None => None,
+            };
+            tx.commit().await.map_err(|e| {
+                error!("Failed to commit bot message: {e}");
+                format!("Failed to create message: {e}")
+            })?;
 
             // Broadcast message to channel subscribers
             crate::ws::broadcast_to_channel(
@@ -467,6 +732,8 @@ async fn handle_bot_event(
                         "encrypted": message.encrypted,
                         "nonce": message.nonce,
                         "reply_to": message.reply_to,
+                        "components": message.components,
+                        "poll": poll,
                         "created_at": message.created_at.to_rfc3339(),
                     }),
                 },
@@ -483,6 +750,7 @@ async fn handle_bot_event(
             interaction_id,
             content,
             ephemeral,
+            components,
         } => {
             // Validate content length
             if let Err(e) = crate::chat::messages::validate_message_content(&content) {
@@ -492,6 +760,12 @@ async fn handle_bot_event(
                     .map(|m| m.to_string())
                     .unwrap_or_else(|| "Invalid response content".to_string()));
             }
+            if let Some(rows) = &components {
+                if ephemeral {
+                    return Err("Components are not supported on ephemeral responses".to_string());
+                }
+                components::validate_components(rows)?;
+            }
 
             info!(
                 interaction_id = %interaction_id,
@@ -499,90 +773,25 @@ async fn handle_bot_event(
                 "Bot responding to command"
             );
 
-            let owner_key = format!("interaction:{interaction_id}:owner");
-            let expected_owner = state
-                .redis
-                .get::<Option<String>, _>(&owner_key)
-                .await
-                .map_err(|e| {
-                    error!(
-                        interaction_id = %interaction_id,
-                        error = %e,
-                        "Failed to fetch interaction owner"
-                    );
-                    "Failed to validate interaction ownership".to_string()
-                })?
-                .ok_or_else(|| "Interaction not found or expired".to_string())?;
-
-            let bot_user_id_str = bot_user_id.to_string();
-            if expected_owner != bot_user_id_str {
-                warn!(
-                    interaction_id = %interaction_id,
-                    bot_user_id = %bot_user_id,
-                    expected_owner = %expected_owner,
-                    "Bot attempted to respond to interaction it does not own"
-                );
-                return Err("Interaction does not belong to this bot".to_string());
-            }
-
             // Store command response in Redis with expiry (5 minutes)
             // The command invoker's WebSocket client will poll/listen for this
-            let response_key = format!("interaction:{interaction_id}:response");
             let response_data = serde_json::json!({
                 "content": content,
                 "ephemeral": ephemeral,
                 "bot_user_id": bot_user_id,
             });
-
-            let was_set: bool = state
-                .redis
-                .set(
-                    &response_key,
-                    response_data.to_string(),
-                    Some(fred::types::Expiration::EX(300)),
-                    Some(fred::types::SetOptions::NX),
-                    false,
-                )
-                .await
-                .map_err(|e| {
-                    error!("Failed to store command response: {e}");
-                    format!("Failed to store response: {e}")
-                })?;
-
-            if !was_set {
-                return Err("Response already provided for this interaction".to_string());
-            }
-
-            // Publish event to notify waiting clients
-            state
-                .redis
-                .publish::<(), _, _>(
-                    format!("interaction:{interaction_id}"),
-                    response_data.to_string(),
-                )
-                .await
-                .map_err(|e| {
-                    error!("Failed to publish command response: {e}");
-                    format!("Failed to publish response: {e}")
-                })?;
-
-            // Deliver response to the invoking user/channel
-            let context_key = format!("interaction:{interaction_id}:context");
-            let context_raw: Option<String> = state.redis.get(&context_key).await.map_err(|e| {
-                error!("Failed to fetch interaction context: {e}");
-                format!("Failed to fetch interaction context: {e}")
-            })?;
-
-            let Some(context_raw) = context_raw else {
-                warn!(interaction_id = %interaction_id, "Interaction context not found, skipping delivery");
+            let Some(context) = claim_interaction(
+                state,
+                interaction_id,
+                bot_user_id,
+                "command",
+                &response_data,
+            )
+            .await?
+            else {
                 return Ok(());
             };
 
-            let context: serde_json::Value = serde_json::from_str(&context_raw).map_err(|e| {
-                error!("Failed to parse interaction context: {e}");
-                format!("Failed to parse interaction context: {e}")
-            })?;
-
             let user_id = context["user_id"]
                 .as_str()
                 .and_then(|s| Uuid::parse_str(s).ok())
@@ -624,15 +833,20 @@ async fn handle_bot_event(
                     format!("Failed to deliver response: {e}")
                 })?;
             } else {
+                if crate::chat::encryption::is_encrypted(&state.db, channel_id)
+                    .await
+                    .map_err(|e| format!("Failed to verify channel access: {e}"))?
+                {
+                    return Err("Bots cannot post in end-to-end encrypted channels".to_string());
+                }
+
                 // Non-ephemeral: insert a real message and broadcast to channel
-                let message = crate::db::create_message(
+                let message = crate::db::create_bot_message(
                     &state.db,
                     channel_id,
                     bot_user_id,
                     &content,
-                    false,
-                    None,
-                    None,
+                    components::to_stored(components.as_deref()).as_ref(),
                 )
                 .await
                 .map_err(|e| {
@@ -671,6 +885,7 @@ async fn handle_bot_event(
                             "encrypted": message.encrypted,
                             "nonce": message.nonce,
                             "reply_to": message.reply_to,
+                            "components": message.components,
                             "created_at": message.created_at.to_rfc3339(),
                         }),
                     },
@@ -682,7 +897,268 @@ async fn handle_bot_event(
                 })?;
             }
 
+            Ok(())
+        }
+        BotClientEvent::ComponentResponse {
+            interaction_id,
+            action,
+            content,
+            components,
+        } => {
+            if let Some(content) = &content {
+                if let Err(e) = crate::chat::messages::validate_message_content(content) {
+                    return Err(e
+                        .message
+                        .as_ref()
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| "Invalid response content".to_string()));
+                }
+            }
+            if let Some(rows) = &components {
+                if action == ComponentResponseAction::EphemeralReply {
+                    return Err("Components are not supported on ephemeral responses".to_string());
+                }
+                components::validate_components(rows)?;
+            }
+            if action == ComponentResponseAction::EphemeralReply && content.is_none() {
+                return Err("Ephemeral replies require content".to_string());
+            }
+
+            info!(
+                interaction_id = %interaction_id,
+                action = ?action,
+                "Bot responding to component interaction"
+            );
+
+            let response_data = serde_json::json!({
+                "action": action,
+                "content": content,
+                "bot_user_id": bot_user_id,
+            });
+            let Some(context) = claim_interaction(
+                state,
+                interaction_id,
+                bot_user_id,
+                "component",
+                &response_data,
+            )
+            .await?
+            else {
+                return Ok(());
+            };
+
+            let user_id = context["user_id"]
+                .as_str()
+                .and_then(|s| Uuid::parse_str(s).ok())
+                .ok_or("Invalid user_id in interaction context")?;
+            let channel_id = context["channel_id"]
+                .as_str()
+                .and_then(|s| Uuid::parse_str(s).ok())
+                .ok_or("Invalid channel_id in interaction context")?;
+            let message_id = context["message_id"]
+                .as_str()
+                .and_then(|s| Uuid::parse_str(s).ok())
+                .ok_or("Invalid message_id in interaction context")?;
+
+            match action {
+                ComponentResponseAction::UpdateMessage => {
+                    let existing = crate::db::find_message_by_id(&state.db, message_id)
+                        .await
+                        .map_err(|e| format!("Failed to look up message: {e}"))?
+                        .filter(|m| m.user_id == Some(bot_user_id))
+                        .ok_or("Interaction message no longer exists")?;
+
+                    let new_components = match &components {
+                        Some(rows) => components::to_stored(Some(rows.as_slice())),
+                        None => existing.components,
+                    };
+                    let message = crate::db::update_message_components(
+                        &state.db,
+                        message_id,
+                        bot_user_id,
+                        content.as_deref().unwrap_or(&existing.content),
+                        new_components.as_ref(),
+                    )
+                    .await
+                    .map_err(|e| {
+                        error!("Failed to update component message: {e}");
+                        format!("Failed to update message: {e}")
+                    })?
+                    .ok_or("Interaction message no longer exists")?;
+
+                    crate::ws::broadcast_to_channel(
+                        &state.redis,
+                        channel_id,
+                        &crate::ws::ServerEvent::MessageEdit {
+                            channel_id,
+                            message_id,
+                            content: message.content,
+                            edited_at: message
+                                .edited_at
+                                .map(|t| t.to_rfc3339())
+                                .unwrap_or_default(),
+                            components: message.components,
+                        },
+                    )
+                    .await
+                    .map_err(|e| {
+                        warn!("Failed to broadcast component message update: {e}");
+                        format!("Failed to broadcast: {e}")
+                    })?;
+                }
+                ComponentResponseAction::EphemeralReply => {
+                    let bot_name = crate::db::find_user_by_id(&state.db, bot_user_id)
+                        .await
+                        .map_err(|e| format!("Failed to look up bot user: {e}"))?
+                        .map_or_else(|| "Bot".to_string(), |u| u.display_name);
+
+                    crate::ws::broadcast_to_user(
+                        &state.redis,
+                        user_id,
+                        &crate::ws::ServerEvent::ComponentResponse {
+                            interaction_id,
+                            message_id,
+                            channel_id,
+                            bot_name,
+                            content: content.unwrap_or_default(),
+                        },
+                    )
+                    .await
+                    .map_err(|e| {
+                        warn!("Failed to deliver ephemeral component response: {e}");
+                        format!("Failed to deliver response: {e}")
+                    })?;
+                }
+            }
+
+            Ok(())
+        }
+        BotClientEvent::AutocompleteResponse {
+            interaction_id,
+            choices,
+        } => {
+            if choices.len() > MAX_CHOICES {
+                return Err(format!("At most {MAX_CHOICES} suggestions are allowed"));
+            }
+            if choices
+                .iter()
+                .any(|c| c.name.is_empty() || c.name.chars().count() > 100)
+            {
+                return Err("Choice names must be 1-100 characters".to_string());
+            }
+
+            // The waiting autocomplete request reads the stored response
+            claim_interaction(
+                state,
+                interaction_id,
+                bot_user_id,
+                "autocomplete",
+                &serde_json::json!({ "choices": choices }),
+            )
+            .await?;
+
             Ok(())
         }
     }
 }
+
+/// Claim a pending interaction on behalf of the bot that owns it.
+///
+/// Verifies ownership, stores the response (first response wins) and notifies
+/// waiting clients. Returns the interaction context stored by the invoker, or
+/// `None` when it has already expired. `kind` (`command`, `component` or
+/// `autocomplete`) prevents answering one interaction type with another's response
+/// event.
+async fn claim_interaction(
+    state: &AppState,
+    interaction_id: Uuid,
+    bot_user_id: Uuid,
+    kind: &str,
+    response_data: &serde_json::Value,
+) -> Result<Option<serde_json::Value>, String> {
+    let owner_key = format!("interaction:{interaction_id}:owner");
+    let expected_owner = state
+        .redis
+        .get::<Option<String>, _>(&owner_key)
+        .await
+        .map_err(|e| {
+            error!(
+                interaction_id = %interaction_id,
+                error = %e,
+                "Failed to fetch interaction owner"
+            );
+            "Failed to validate interaction ownership".to_string()
+        })?
+        .ok_or_else(|| "Interaction not found or expired".to_string())?;
+
+    let bot_user_id_str = bot_user_id.to_string();
+    if expected_owner != bot_user_id_str {
+        warn!(
+            interaction_id = %interaction_id,
+            bot_user_id = %bot_user_id,
+            expected_owner = %expected_owner,
+            "Bot attempted to respond to interaction it does not own"
+        );
+        return Err("Interaction does not belong to this bot".to_string());
+    }
+
+    let context_key = format!("interaction:{interaction_id}:context");
+    let context_raw: Option<String> = state.redis.get(&context_key).await.map_err(|e| {
+        error!("Failed to fetch interaction context: {e}");
+        format!("Failed to fetch interaction context: {e}")
+    })?;
+
+    let context = context_raw
+        .map(|raw| serde_json::from_str::<serde_json::Value>(&raw))
+        .transpose()
+        .map_err(|e| {
+            error!("Failed to parse interaction context: {e}");
+            format!("Failed to parse interaction context: {e}")
+        })?;
+
+    // Slash command contexts predate the `kind` field
+    if let Some(context) = &context {
+        if context["kind"].as_str().unwrap_or("command") != kind {
+            return Err(format!("Interaction is not a {kind} interaction"));
+        }
+    }
+
+    let response_key = format!("interaction:{interaction_id}:response");
+    let was_set: bool = state
+        .redis
+        .set(
+            &response_key,
+            response_data.to_string(),
+            Some(fred::types::Expiration::EX(300)),
+            Some(fred::types::SetOptions::NX),
+            false,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to store interaction response: {e}");
+            format!("Failed to store response: {e}")
+        })?;
+
+    if !was_set {
+        return Err("Response already provided for this interaction".to_string());
+    }
+
+    // Publish event to notify waiting clients
+    state
+        .redis
+        .publish::<(), _, _>(
+            format!("interaction:{interaction_id}"),
+            response_data.to_string(),
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to publish interaction response: {e}");
+            format!("Failed to publish response: {e}")
+        })?;
+
+    if context.is_none() {
+        warn!(interaction_id = %interaction_id, "Interaction context not found, skipping delivery");
+    }
+
+    Ok(context)
+}
@@ -0,0 +1,109 @@
+//! Interaction Response Waiters
+//!
+//! Bots answer interactions through the gateway, which stores the response
+//! and publishes it on `interaction:{id}`. One pattern subscription per
+//! process hands those responses to the requests waiting for them, so
+//! invokers wait on a channel with a timeout instead of polling Redis.
+
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use fred::prelude::*;
+use tokio::sync::{broadcast, oneshot, Mutex};
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// Requests waiting for a bot response, by interaction ID.
+static WAITERS: LazyLock<DashMap<Uuid, oneshot::Sender<String>>> = LazyLock::new(DashMap::new);
+
+/// Subscriber client receiving `interaction:*` responses for this process.
+static LISTENER: LazyLock<Mutex<Option<Client>>> = LazyLock::new(|| Mutex::new(None));
+
+/// A registered wait for the bot's response to one interaction.
+///
+/// Register before the interaction is relayed to the bot so an early response
+/// can't be missed. Dropping it stops waiting.
+pub struct PendingResponse {
+    interaction_id: Uuid,
+    rx: oneshot::Receiver<String>,
+}
+
+impl PendingResponse {
+    /// Wait up to `timeout` for the response data.
+    ///
+    /// Falls back to the stored response in case the subscription missed it.
+    pub async fn wait(mut self, redis: &Client, timeout: Duration) -> Option<String> {
+        if let Ok(Ok(response)) = tokio::time::timeout(timeout, &mut self.rx).await {
+            return Some(response);
+        }
+        let response_key = format!("interaction:{}:response", self.interaction_id);
+        redis
+            .get::<Option<String>, _>(&response_key)
+            .await
+            .unwrap_or_else(|e| {
+                warn!(interaction_id = %self.interaction_id, error = %e, "Failed to fetch interaction response");
+                None
+            })
+    }
+}
+
+impl Drop for PendingResponse {
+    fn drop(&mut self) {
+        WAITERS.remove(&self.interaction_id);
+    }
+}
+
+/// Start waiting for the response to `interaction_id`.
+pub async fn register(redis: &Client, interaction_id: Uuid) -> PendingResponse {
+    if let Err(e) = ensure_listener(redis).await {
+        warn!(error = %e, "Interaction response listener unavailable");
+    }
+    let (tx, rx) = oneshot::channel();
+    WAITERS.insert(interaction_id, tx);
+    PendingResponse { interaction_id, rx }
+}
+
+/// Subscribe to interaction responses unless a live subscription exists.
+async fn ensure_listener(redis: &Client) -> Result<(), fred::error::Error> {
+    let mut listener = LISTENER.lock().await;
+    if listener.as_ref().is_some_and(ClientLike::is_connected) {
+        return Ok(());
+    }
+
+    let subscriber = redis.clone_new();
+    let _connect_handle = subscriber.connect();
+    subscriber.wait_for_connect().await?;
+    let mut pubsub_stream = subscriber.message_rx();
+    subscriber.psubscribe("interaction:*").await?;
+
+    tokio::spawn(async move {
+        loop {
+            let message = match pubsub_stream.recv().await {
+                Ok(message) => message,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "Interaction response listener lagged");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Some(interaction_id) = message
+                .channel
+                .strip_prefix("interaction:")
+                .and_then(|id| Uuid::parse_str(id).ok())
+            else {
+                continue;
+            };
+            let Some(response) = message.value.as_string() else {
+                continue;
+            };
+            if let Some((_, tx)) = WAITERS.remove(&interaction_id) {
+                let _ = tx.send(response);
+            }
+        }
+        error!("Interaction response listener stopped");
+    });
+
+    *listener = Some(subscriber);
+    Ok(())
+}
@@ -20,6 +20,7 @@
 
 pub mod bot_events;
 pub mod bot_gateway;
+pub mod interactions;
 
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -241,6 +242,14 @@ pub enum ClientEvent {
         custom_status: Option<crate::presence::CustomStatus>,
     },
 
+    /// Acknowledge to-device messages, removing them from the device's inbox.
+    ToDeviceAck {
+        /// The receiving device.
+        device_id: Uuid,
+        /// Messages the device has processed.
+        message_ids: Vec<Uuid>,
+    },
+
     /// Subscribe to admin events (requires elevated admin).
     AdminSubscribe,
     /// Unsubscribe from admin events.
@@ -271,6 +280,7 @@ impl ClientEvent {
             Self::SetActivity { .. } => "set_activity",
             Self::SetStatus { .. } => "set_status",
             Self::SetCustomStatus { .. } => "set_custom_status",
+            Self::ToDeviceAck { .. } => "to_device_ack",
             Self::AdminSubscribe => "admin_subscribe",
             Self::AdminUnsubscribe => "admin_unsubscribe",
         }
@@ -306,6 +316,9 @@ pub enum ServerEvent {
     Ready {
         /// Authenticated user ID.
         user_id: Uuid,
+        /// The user's E2EE devices that are running low on one-time prekeys
+        /// or need a new fallback key.
+        low_prekey_devices: Vec<crate::crypto::prekeys::DevicePrekeyStatus>,
     },
     /// Pong response
     Pong,
@@ -336,6 +349,9 @@ pub enum ServerEvent {
         content: String,
         /// Edit timestamp (RFC3339).
         edited_at: String,
+        /// Updated interactive components (omitted when the message has none).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        components: Option<serde_json::Value>,
     },
     /// Message deleted
     MessageDelete {
@@ -344,6 +360,13 @@ pub enum ServerEvent {
         /// Deleted message ID.
         message_id: Uuid,
     },
+    /// Many messages deleted at once by a moderator
+    MessageBulkDelete {
+        /// Channel containing the messages.
+        channel_id: Uuid,
+        /// Deleted message IDs (top-level messages and thread replies).
+        message_ids: Vec<Uuid>,
+    },
     /// Reaction added to a message
     ReactionAdd {
         /// Channel containing the message.
@@ -366,6 +389,19 @@ pub enum ServerEvent {
         /// Emoji that was removed.
         emoji: String,
     },
+    /// Poll tallies changed (a vote was cast or withdrawn, or the poll closed)
+    PollUpdated {
+        /// Channel containing the message.
+        channel_id: Uuid,
+        /// Message the poll is attached to.
+        message_id: Uuid,
+        /// Votes per option, in option order.
+        votes: Vec<i64>,
+        /// Number of users who voted.
+        total_voters: i64,
+        /// Whether voting has ended; tallies of closed polls are final.
+        closed: bool,
+    },
     /// Message pinned in channel
     ChannelPinAdded {
         /// Channel containing the pinned message.
@@ -391,6 +427,13 @@ pub enum ServerEvent {
         /// Updated emojis list.
         emojis: Vec<crate::guild::types::GuildEmoji>,
     },
+    /// Guild stickers updated
+    GuildStickersUpdated {
+        /// Guild ID.
+        guild_id: Uuid,
+        /// Updated stickers list.
+        stickers: Vec<crate::guild::types::GuildSticker>,
+    },
     /// User typing
     TypingStart {
         /// Channel user is typing in.
@@ -686,6 +729,67 @@ pub enum ServerEvent {
         /// When the preferences were updated.
         updated_at: DateTime<Utc>,
     },
+    /// Notification settings for a scope changed on another device.
+    NotificationSettingsUpdated {
+        /// Scope the setting applies to.
+        scope: crate::notifications::NotificationScope,
+        /// Guild, category, channel or thread ID.
+        scope_id: Uuid,
+        /// The new setting, or `None` if it was reset to inherit.
+        setting: Option<crate::notifications::types::NotificationSetting>,
+    },
+    /// The user was mentioned in a new message and their notification
+    /// settings allow a notification for it.
+    MentionCreated {
+        message_id: Uuid,
+        channel_id: Uuid,
+        guild_id: Option<Uuid>,
+        /// Thread root message, if the mention is a thread reply.
+        parent_id: Option<Uuid>,
+        kind: crate::chat::mentions::MessageMentionKind,
+    },
+    /// Encrypted payload queued for one of the user's devices. Sessions on
+    /// other devices ignore it; the recipient acknowledges it with
+    /// `to_device_ack` once processed.
+    ToDeviceMessage {
+        /// Message ID, used for acknowledgement.
+        id: Uuid,
+        /// User who sent the message.
+        sender_user_id: Uuid,
+        /// Device that sent the message.
+        sender_device_id: Uuid,
+        /// Device the message is addressed to.
+        recipient_device_id: Uuid,
+        /// Payload type, e.g. `m.room_key`.
+        event_type: String,
+        /// Olm-encrypted payload.
+        ciphertext: String,
+        /// When the message was queued.
+        created_at: DateTime<Utc>,
+    },
+    /// One of the user's devices is running low on one-time prekeys, or its
+    /// fallback key was handed out. The device should upload new keys.
+    PrekeysLow {
+        /// Device that should upload keys.
+        device_id: Uuid,
+        /// The device's Curve25519 identity key (base64).
+        identity_key_curve25519: String,
+        /// Unclaimed one-time prekeys left on the server.
+        one_time_prekeys: i64,
+        /// The device has no unused fallback key.
+        needs_fallback_key: bool,
+    },
+    /// One of a user's E2EE devices was deleted. Sent to the owner's other
+    /// sessions and to their contacts, who should stop encrypting to the
+    /// device and rotate any Megolm sessions it received.
+    DeviceRemoved {
+        /// Owner of the removed device.
+        user_id: Uuid,
+        /// The removed device.
+        device_id: Uuid,
+        /// The removed device's Curve25519 identity key.
+        identity_key_curve25519: String,
+    },
 
     // Friend events
     /// Friend request received (sent to the addressee).
@@ -811,6 +915,26 @@ pub enum ServerEvent {
         /// User who changed the name.
         updated_by: Uuid,
     },
+    /// A channel was switched to end-to-end encryption. Members should only
+    /// send Megolm-encrypted messages to it from now on.
+    ChannelEncryptionEnabled {
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Current membership epoch.
+        epoch: i64,
+        /// User who enabled encryption.
+        enabled_by: Uuid,
+    },
+    /// A channel's disappearing message timer changed. Applies to messages
+    /// sent from now on.
+    DisappearingMessagesUpdated {
+        /// Channel ID.
+        channel_id: Uuid,
+        /// Lifetime of new messages in seconds (`None` = off).
+        message_ttl_seconds: Option<i32>,
+        /// User who changed the timer.
+        updated_by: Uuid,
+    },
 
     // Admin events (broadcast to admin subscribers)
     /// User was banned
@@ -908,6 +1032,30 @@ pub enum ServerEvent {
         /// Channel where command was invoked.
         channel_id: Uuid,
     },
+
+    // Message component interaction events
+    /// Ephemeral bot reply to a component interaction (only sent to the clicking user).
+    ComponentResponse {
+        /// Interaction ID.
+        interaction_id: Uuid,
+        /// Message whose component was used.
+        message_id: Uuid,
+        /// Channel containing the message.
+        channel_id: Uuid,
+        /// Bot display name.
+        bot_name: String,
+        /// Reply content from the bot.
+        content: String,
+    },
+    /// Bot did not respond to a component interaction in time.
+    ComponentInteractionTimeout {
+        /// Interaction ID.
+        interaction_id: Uuid,
+        /// Message whose component was used.
+        message_id: Uuid,
+        /// Channel containing the message.
+        channel_id: Uuid,
+    },
 }
 
 /// Redis pub/sub channels.
@@ -989,6 +1137,55 @@ pub async fn broadcast_to_user(
     Ok(())
 }
 
+/// Broadcast an event to a user's contacts (friends, DM peers and guild members).
+///
+/// Published on the user's presence channel for friends, on each of the
+/// user's guild channels, and to each participant of the user's DMs and
+/// group DMs.
+#[tracing::instrument(skip(db, redis, event), fields(user_id = %user_id))]
+pub async fn broadcast_to_contacts(
+    db: &sqlx::PgPool,
+    redis: &Client,
+    user_id: Uuid,
+    event: &ServerEvent,
+) -> Result<(), Error> {
+    let payload = serde_json::to_string(event)
+        .map_err(|e| Error::new(ErrorKind::Parse, format!("JSON error: {e}")))?;
+    let db_error = |e: sqlx::Error| Error::new(ErrorKind::Unknown, format!("Database error: {e}"));
+
+    let guild_ids = db::get_user_guild_ids(db, user_id)
+        .await
+        .map_err(db_error)?;
+    let dm_peer_ids: Vec<Uuid> = sqlx::query_scalar(
+        r"
+        SELECT DISTINCT peer.user_id
+        FROM dm_participants own
+        INNER JOIN dm_participants peer ON peer.channel_id = own.channel_id
+        WHERE own.user_id = $1 AND peer.user_id <> $1
+        ",
+    )
+    .bind(user_id)
+    .fetch_all(db)
+    .await
+    .map_err(db_error)?;
+
+    redis
+        .publish::<(), _, _>(channels::user_presence(user_id), payload.as_str())
+        .await?;
+    for guild_id in guild_ids {
+        redis
+            .publish::<(), _, _>(channels::guild_events(guild_id), payload.as_str())
+            .await?;
+    }
+    for peer_id in dm_peer_ids {
+        redis
+            .publish::<(), _, _>(channels::user_events(peer_id), payload.as_str())
+            .await?;
+    }
+
+    Ok(())
+}
+
 /// Broadcast a presence update to all users who should see it.
 async fn broadcast_presence_update(state: &AppState, user_id: Uuid, event: &ServerEvent) {
     let json = match serde_json::to_string(event) {
@@ -1187,8 +1384,19 @@ async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid) {
     info!("WebSocket connected: user={}", user_id);
     crate::observability::metrics::record_ws_connect();
 
-    // Send ready event
-    let _ = tx.send(ServerEvent::Ready { user_id }).await;
+    // Send ready event, flagging devices that should replenish their prekeys
+    let low_prekey_devices = crate::crypto::prekeys::low_prekey_devices(&state.db, user_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to check prekey supply for user {}: {}", user_id, e);
+            Vec::new()
+        });
+    let _ = tx
+        .send(ServerEvent::Ready {
+            user_id,
+            low_prekey_devices,
+        })
+        .await;
 
     // Fetch user's friends for presence subscriptions
     let friend_ids = match get_user_friends(&state.db, user_id).await {
@@ -1346,6 +1554,22 @@ async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid) {
         }
     });
 
+    // Heartbeat so pushes are skipped while this connection is live
+    let connection_id = Uuid::new_v4();
+    let heartbeat_redis = state.redis.clone();
+    let heartbeat_handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(crate::push::activity::HEARTBEAT_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) =
+                crate::push::activity::touch_connection(&heartbeat_redis, user_id, connection_id)
+                    .await
+            {
+                warn!("Failed to record connection heartbeat: {}", e);
+            }
+        }
+    });
+
     // Per-connection mutable state for rate limiting and deduplication
     let mut msg_state = ClientMessageState::default();
 
@@ -1389,9 +1613,33 @@ async fn handle_socket(socket: WebSocket, state: AppState, user_id: Uuid) {
         }
     }
 
+    // Voice peers signaling over this connection can't be renegotiated anymore
+    for channel_id in state.sfu.channels_signaling_to(user_id, &tx).await {
+        let muted = state.sfu.is_muted(channel_id, user_id).await;
+        match crate::voice::ws_handler::handle_voice_event(
+            &state.sfu,
+            &state.db,
+            user_id,
+            ClientEvent::VoiceLeave { channel_id },
+            &tx,
+            state.screen_share_limiter.as_ref(),
+        )
+        .await
+        {
+            Ok(()) => publish_voice_state(&state, user_id, channel_id, None, muted).await,
+            Err(e) => warn!("Failed to leave voice channel on disconnect: {}", e),
+        }
+    }
+
     // Cleanup
     pubsub_handle.abort();
     sender_handle.abort();
+    heartbeat_handle.abort();
+    if let Err(e) =
+        crate::push::activity::remove_connection(&state.redis, user_id, connection_id).await
+    {
+        warn!("Failed to clear connection heartbeat: {}", e);
+    }
 
     // Update user presence to offline
     if let Err(e) = update_presence(&state, user_id, "offline").await {
@@ -1527,6 +1775,25 @@ pub async fn handle_client_message(
         | ClientEvent::VoiceWebcamStart { .. }
         | ClientEvent::VoiceWebcamStop { .. }
         | ClientEvent::VoiceSetLayerPreference { .. } => {
+            // (voice channel, channel after the change, muted) for bot voice state events
+            let voice_state = match &event {
+                ClientEvent::VoiceJoin { channel_id } => {
+                    Some((*channel_id, Some(*channel_id), false))
+                }
+                ClientEvent::VoiceLeave { channel_id } => Some((
+                    *channel_id,
+                    None,
+                    state.sfu.is_muted(*channel_id, user_id).await,
+                )),
+                ClientEvent::VoiceMute { channel_id } => {
+                    Some((*channel_id, Some(*channel_id), true))
+                }
+                ClientEvent::VoiceUnmute { channel_id } => {
+                    Some((*channel_id, Some(*channel_id), false))
+                }
+                _ => None,
+            };
+
             if let Err(e) = crate::voice::ws_handler::handle_voice_event(
                 &state.sfu,
                 &state.db,
@@ -1543,6 +1810,8 @@ pub async fn handle_client_message(
                     message: e.to_string(),
                 })
                 .await?;
+            } else if let Some((voice_channel_id, channel_id, muted)) = voice_state {
+                publish_voice_state(state, user_id, voice_channel_id, channel_id, muted).await;
             }
         }
 
@@ -1688,6 +1957,24 @@ pub async fn handle_client_message(
             debug!("Admin {} subscribed to admin events", user_id);
         }
 
+        ClientEvent::ToDeviceAck {
+            device_id,
+            message_ids,
+        } => {
+            use crate::crypto::to_device::{acknowledge, MAX_TO_DEVICE_BATCH};
+
+            if message_ids.len() > MAX_TO_DEVICE_BATCH {
+                return Err(format!(
+                    "Cannot acknowledge more than {MAX_TO_DEVICE_BATCH} messages at once"
+                )
+                .into());
+            }
+            let removed = acknowledge(&state.db, user_id, device_id, &message_ids).await?;
+            debug!(
+                "User {} acknowledged {} to-device messages for device {}",
+                user_id, removed, device_id
+            );
+        }
         ClientEvent::AdminUnsubscribe => {
             *admin_subscribed.write().await = false;
             debug!("Admin {} unsubscribed from admin events", user_id);
@@ -1708,6 +1995,35 @@ struct HandlePubsubParams {
     guild_ids: Vec<Uuid>,
 }
 
+/// Publish a `VoiceStateUpdated` bot event for a member of a guild voice channel.
+///
+/// `channel_id` is the channel after the change, `None` once the member left.
+async fn publish_voice_state(
+    state: &AppState,
+    user_id: Uuid,
+    voice_channel_id: Uuid,
+    channel_id: Option<Uuid>,
+    muted: bool,
+) {
+    if let Ok(Some(db::Channel {
+        guild_id: Some(guild_id),
+        ..
+    })) = db::find_channel_by_id(&state.db, voice_channel_id).await
+    {
+        bot_events::spawn_guild_event(
+            &state.db,
+            &state.redis,
+            guild_id,
+            bot_gateway::BotServerEvent::VoiceStateUpdated {
+                guild_id,
+                channel_id,
+                user_id,
+                muted,
+            },
+        );
+    }
+}
+
 /// Handle Redis pub/sub messages.
 async fn handle_pubsub(redis: Client, params: HandlePubsubParams) {
     // Create a subscriber client
@@ -1974,6 +2290,8 @@ async fn update_presence(state: &AppState, user_id: Uuid, status: &str) -> Resul
         .execute(&state.db)
         .await?;
 
+    bot_events::spawn_presence_updated(&state.db, &state.redis, user_id, status);
+
     Ok(())
 }
 
@@ -0,0 +1,415 @@
+//! Bot REST API Integration Tests
+
+use axum::body::Body;
+use axum::http::{Method, StatusCode};
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::{
+    body_to_json, create_bot_application, create_channel, create_guild_with_default_role,
+    create_test_user, delete_guild, generate_access_token, insert_message, send_json, BotFixture,
+    TestApp,
+};
+
+/// @everyone permissions of the bot fixture guild.
+const EVERYONE: GuildPermissions = GuildPermissions::VIEW_CHANNEL
+    .union(GuildPermissions::SEND_MESSAGES)
+    .union(GuildPermissions::ADD_REACTIONS);
+
+fn bot_request(method: Method, uri: &str, token: &str) -> axum::http::request::Builder {
+    TestApp::request(method, uri).header("Authorization", format!("Bot {token}"))
+}
+
+#[tokio::test]
+async fn bot_api_requires_bot_authorization() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let jwt = generate_access_token(&app.config, user_id);
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(user_id);
+
+    let channel_id = Uuid::now_v7();
+
+    let req = TestApp::request(
+        Method::GET,
+        &format!("/api/bot/channels/{channel_id}/messages"),
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // A user JWT is not accepted on bot routes
+    let req = TestApp::request(
+        Method::GET,
+        &format!("/api/bot/channels/{channel_id}/messages"),
+    )
+    .header("Authorization", format!("Bearer {jwt}"))
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Malformed bot token
+    let req = bot_request(
+        Method::GET,
+        &format!("/api/bot/channels/{channel_id}/messages"),
+        "not-a-token",
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn bot_api_rejects_missing_scope() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &["members.read"]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+
+    let req = bot_request(
+        Method::GET,
+        &format!("/api/bot/channels/{}/messages", fx.channel_id),
+        &fx.bot_token,
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let json = body_to_json(resp).await;
+    assert_eq!(json["error"], "MISSING_SCOPE");
+}
+
+#[tokio::test]
+async fn bot_api_lists_channel_history() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &["messages.read"]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+
+    insert_message(&app.pool, fx.channel_id, fx.owner_id, "first").await;
+    insert_message(&app.pool, fx.channel_id, fx.owner_id, "second").await;
+
+    let req = bot_request(
+        Method::GET,
+        &format!("/api/bot/channels/{}/messages?limit=1", fx.channel_id),
+        &fx.bot_token,
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let json = body_to_json(resp).await;
+    assert_eq!(json["items"].as_array().unwrap().len(), 1);
+    assert_eq!(json["items"][0]["content"], "second");
+    assert_eq!(json["has_more"], true);
+}
+
+#[tokio::test]
+async fn bot_api_denies_channel_in_uninstalled_guild() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &["messages.read"]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+
+    let other_guild =
+        create_guild_with_default_role(&app.pool, fx.owner_id, GuildPermissions::VIEW_CHANNEL)
+            .await;
+    guard.add(move |pool| async move { delete_guild(&pool, other_guild).await });
+    let other_channel = create_channel(&app.pool, other_guild, "private").await;
+
+    let req = bot_request(
+        Method::GET,
+        &format!("/api/bot/channels/{other_channel}/messages"),
+        &fx.bot_token,
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn bot_api_edits_only_own_messages() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &["messages.write"]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+
+    let own = insert_message(&app.pool, fx.channel_id, fx.bot_user_id, "bot says hi").await;
+    let other = insert_message(&app.pool, fx.channel_id, fx.owner_id, "owner says hi").await;
+
+    let body = serde_json::json!({ "content": "bot edited" });
+    let req = bot_request(
+        Method::PATCH,
+        &format!("/api/bot/messages/{own}"),
+        &fx.bot_token,
+    )
+    .header("Content-Type", "application/json")
+    .body(Body::from(body.to_string()))
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["content"], "bot edited");
+
+    let req = bot_request(
+        Method::PATCH,
+        &format!("/api/bot/messages/{other}"),
+        &fx.bot_token,
+    )
+    .header("Content-Type", "application/json")
+    .body(Body::from(body.to_string()))
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let req = bot_request(
+        Method::DELETE,
+        &format!("/api/bot/messages/{own}"),
+        &fx.bot_token,
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn bot_api_validates_reaction_emoji() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &["reactions.write"]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+
+    let message_id = insert_message(&app.pool, fx.channel_id, fx.owner_id, "react here").await;
+    let uri = format!(
+        "/api/bot/channels/{}/messages/{message_id}/reactions",
+        fx.channel_id
+    );
+
+    for (emoji, status) in [
+        ("ok", StatusCode::BAD_REQUEST),
+        (":unknown:", StatusCode::BAD_REQUEST),
+        ("👍", StatusCode::CREATED),
+    ] {
+        let req = bot_request(Method::PUT, &uri, &fx.bot_token)
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::json!({ "emoji": emoji }).to_string(),
+            ))
+            .unwrap();
+        let resp = app.oneshot(req).await;
+        assert_eq!(resp.status(), status, "emoji {emoji}");
+    }
+}
+
+/// Create a role in the fixture guild and return its ID.
+async fn create_role(app: &TestApp, fx: &BotFixture, permissions: i64, position: i32) -> Uuid {
+    let role_id = Uuid::now_v7();
+    sqlx::query(
+        "INSERT INTO guild_roles (id, guild_id, name, permissions, position, is_default) VALUES ($1, $2, 'Role', $3, $4, false)",
+    )
+    .bind(role_id)
+    .bind(fx.guild_id)
+    .bind(permissions)
+    .bind(position)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    role_id
+}
+
+#[tokio::test]
+async fn bot_api_role_management_follows_bot_hierarchy() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &["roles.write", "members.read"]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+
+    let member_id = fx.add_member(&app, &mut guard).await;
+
+    let bot_role = create_role(&app, &fx, GuildPermissions::MANAGE_ROLES.to_db(), 5).await;
+    let helper_role = create_role(&app, &fx, 0, 10).await;
+    let senior_role = create_role(&app, &fx, 0, 2).await;
+
+    let uri = format!(
+        "/api/bot/guilds/{}/members/{member_id}/roles/{helper_role}",
+        fx.guild_id
+    );
+
+    // Without roles of its own the bot cannot manage roles, even though the owner installed it
+    let req = bot_request(Method::PUT, &uri, &fx.bot_token)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Guild admins can give the installed bot a role
+    let path = format!(
+        "/api/guilds/{}/members/{}/roles/{bot_role}",
+        fx.guild_id, fx.bot_user_id
+    );
+    let resp = send_json(&app, fx.owner_id, Method::POST, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let req = bot_request(Method::PUT, &uri, &fx.bot_token)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let req = bot_request(
+        Method::GET,
+        &format!("/api/bot/guilds/{}/members", fx.guild_id),
+        &fx.bot_token,
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    let member = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|m| m["user_id"] == member_id.to_string())
+        .expect("member should be listed");
+    assert_eq!(member["role_ids"][0], helper_role.to_string());
+
+    // Roles ranked above the bot's own are off limits
+    let req = bot_request(
+        Method::PUT,
+        &format!(
+            "/api/bot/guilds/{}/members/{member_id}/roles/{senior_role}",
+            fx.guild_id
+        ),
+        &fx.bot_token,
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // So are members who outrank the bot
+    sqlx::query("INSERT INTO guild_member_roles (guild_id, user_id, role_id) VALUES ($1, $2, $3)")
+        .bind(fx.guild_id)
+        .bind(member_id)
+        .bind(senior_role)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let req = bot_request(Method::DELETE, &uri, &fx.bot_token)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn bot_api_scopes_added_after_install_need_guild_approval() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &["members.read"]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+
+    // The application owner grants roles.write after the bot was installed
+    let resp = send_json(
+        &app,
+        fx.owner_id,
+        Method::PUT,
+        &format!("/api/applications/{}/scopes", fx.app_id),
+        Some(serde_json::json!({ "scopes": ["members.read", "roles.write"] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let role_id = create_role(&app, &fx, 0, 10).await;
+    let uri = format!(
+        "/api/bot/guilds/{}/members/{}/roles/{role_id}",
+        fx.guild_id, fx.owner_id
+    );
+    let req = bot_request(Method::PUT, &uri, &fx.bot_token)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["error"], "SCOPE_NOT_APPROVED");
+
+    // Only scopes granted to the application can be approved
+    let path = format!("/api/guilds/{}/bots/{}/scopes", fx.guild_id, fx.bot_user_id);
+    let resp = send_json(
+        &app,
+        fx.owner_id,
+        Method::PUT,
+        &path,
+        Some(serde_json::json!({ "scopes": ["attachments.write"] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json(
+        &app,
+        fx.owner_id,
+        Method::PUT,
+        &path,
+        Some(serde_json::json!({ "scopes": ["members.read", "roles.write"] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    assert_eq!(
+        json["approved_scopes"],
+        serde_json::json!(["members.read", "roles.write"])
+    );
+
+    // Approved, the request now reaches the hierarchy check: the owner outranks the bot
+    let req = bot_request(Method::PUT, &uri, &fx.bot_token)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let json = body_to_json(resp).await;
+    assert_ne!(json["error"], "SCOPE_NOT_APPROVED");
+}
+
+#[tokio::test]
+async fn update_scopes_validates_names() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let (app_id, _, _) = create_bot_application(&app.pool, user_id).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(user_id);
+
+    let path = format!("/api/applications/{app_id}/scopes");
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::PUT,
+        &path,
+        Some(serde_json::json!({ "scopes": ["messages.read", "admin"] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::PUT,
+        &path,
+        Some(serde_json::json!({ "scopes": ["roles.write", "messages.read"] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let json = body_to_json(resp).await;
+    assert_eq!(
+        json["scopes"],
+        serde_json::json!(["messages.read", "roles.write"])
+    );
+}
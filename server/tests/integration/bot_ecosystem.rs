@@ -427,6 +427,88 @@ async fn test_register_command_invalid_name() {
     delete_user(&app.pool, user_id).await;
 }
 
+/// Test that option schemas are validated on registration.
+#[tokio::test]
+async fn test_register_command_invalid_options() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let token = generate_access_token(&app.config, user_id);
+
+    // Create application
+    let create_req = TestApp::request(Method::POST, "/api/applications")
+        .header("Authorization", format!("Bearer {token}"))
+        .header("Content-Type", "application/json")
+        .body(Body::from(
+            serde_json::to_string(&json!({
+                "name": "Test Bot"
+            }))
+            .unwrap(),
+        ))
+        .unwrap();
+
+    let create_resp = app.oneshot(create_req).await;
+    let body = create_resp.into_body().collect().await.unwrap().to_bytes();
+    let app_data: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let app_id = app_data["id"].as_str().unwrap();
+
+    let register = |options: serde_json::Value| {
+        TestApp::request(Method::PUT, &format!("/api/applications/{app_id}/commands"))
+            .header("Authorization", format!("Bearer {token}"))
+            .header("Content-Type", "application/json")
+            .body(Body::from(
+                serde_json::to_string(&json!({
+                    "commands": [
+                        {
+                            "name": "config",
+                            "description": "Configure the bot",
+                            "options": options
+                        }
+                    ]
+                }))
+                .unwrap(),
+            ))
+            .unwrap()
+    };
+
+    // Choices on a boolean option are rejected
+    let resp = app
+        .oneshot(register(json!([{
+            "name": "enabled",
+            "description": "Enable",
+            "type": "boolean",
+            "choices": [{ "name": "Yes", "value": "yes" }]
+        }])))
+        .await;
+    assert_eq!(resp.status(), 400);
+
+    // Nested subcommands with choices and constraints are accepted
+    let resp = app
+        .oneshot(register(json!([{
+            "name": "volume",
+            "description": "Volume settings",
+            "type": "subcommand_group",
+            "options": [{
+                "name": "set",
+                "description": "Set the volume",
+                "type": "subcommand",
+                "options": [
+                    { "name": "level", "description": "Level", "type": "integer", "required": true, "min_value": 0, "max_value": 100 },
+                    { "name": "preset", "description": "Preset", "type": "string", "choices": [{ "name": "Loud", "value": "loud" }] },
+                    { "name": "note", "description": "Note", "type": "string", "autocomplete": true }
+                ]
+            }]
+        }])))
+        .await;
+    assert_eq!(resp.status(), 200);
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    let commands: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+    let set = &commands[0]["options"][0]["options"][0];
+    assert_eq!(set["type"], "subcommand");
+    assert_eq!(set["options"][2]["autocomplete"], true);
+
+    delete_user(&app.pool, user_id).await;
+}
+
 /// Test listing slash commands.
 #[tokio::test]
 async fn test_list_slash_commands() {
@@ -2,6 +2,7 @@
 
 use axum::body::Body;
 use axum::http::{Method, StatusCode};
+use uuid::Uuid;
 
 use super::helpers::*;
 
@@ -280,3 +281,141 @@ async fn no_intents_still_permits_commands() {
         &BotEventType::CommandInvoked
     ));
 }
+
+// ============================================================================
+// Privileged Intent Approval Tests
+// ============================================================================
+
+/// Set an application's declared intents directly.
+async fn declare_intents(app: &TestApp, app_id: Uuid, intents: &[&str]) {
+    sqlx::query("UPDATE bot_applications SET gateway_intents = $1 WHERE id = $2")
+        .bind(intents)
+        .bind(app_id)
+        .execute(&app.pool)
+        .await
+        .expect("Failed to set gateway intents");
+}
+
+async fn approve_intents(
+    app: &TestApp,
+    user_id: Uuid,
+    guild_id: Uuid,
+    bot_user_id: Uuid,
+    intents: serde_json::Value,
+) -> axum::http::Response<Body> {
+    let token = generate_access_token(&app.config, user_id);
+    let req = TestApp::request(
+        Method::PUT,
+        &format!("/api/guilds/{guild_id}/bots/{bot_user_id}/intents"),
+    )
+    .header("Authorization", format!("Bearer {token}"))
+    .header("Content-Type", "application/json")
+    .body(Body::from(
+        serde_json::json!({ "intents": intents }).to_string(),
+    ))
+    .unwrap();
+    app.oneshot(req).await
+}
+
+#[tokio::test]
+async fn new_intents_accepted() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let (app_id, _, _) = create_bot_application(&app.pool, user_id).await;
+    let token = generate_access_token(&app.config, user_id);
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(user_id);
+
+    let body = serde_json::json!({
+        "intents": ["reactions", "voice_states", "guilds", "presence", "message_content"],
+    });
+    let req = TestApp::request(Method::PUT, &format!("/api/applications/{app_id}/intents"))
+        .header("Authorization", format!("Bearer {token}"))
+        .header("Content-Type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["gateway_intents"].as_array().unwrap().len(), 5);
+}
+
+#[tokio::test]
+async fn guild_admin_approves_privileged_intents() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let guild_id = create_guild(&app.pool, owner_id).await;
+    let (app_id, bot_user_id, _) = create_bot_application(&app.pool, owner_id).await;
+    install_bot_in_guild(&app.pool, guild_id, app_id, owner_id).await;
+    declare_intents(&app, app_id, &["messages", "presence"]).await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move {
+        delete_guild(&pool, guild_id).await;
+        delete_bot_application(&pool, app_id).await;
+    });
+    guard.delete_user(bot_user_id);
+    guard.delete_user(owner_id);
+
+    let resp = approve_intents(
+        &app,
+        owner_id,
+        guild_id,
+        bot_user_id,
+        serde_json::json!(["presence"]),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["approved_intents"], serde_json::json!(["presence"]));
+    assert_eq!(json["requested_intents"], serde_json::json!(["presence"]));
+
+    // Only privileged intents the bot requested can be approved
+    for intents in [
+        serde_json::json!(["message_content"]),
+        serde_json::json!(["messages"]),
+    ] {
+        let resp = approve_intents(&app, owner_id, guild_id, bot_user_id, intents).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let approved: Vec<String> = sqlx::query_scalar(
+        "SELECT approved_intents FROM guild_bot_installations WHERE guild_id = $1 AND application_id = $2",
+    )
+    .bind(guild_id)
+    .bind(app_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(approved, vec!["presence".to_string()]);
+}
+
+#[tokio::test]
+async fn non_admin_cannot_approve_intents() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (member_id, _) = create_test_user(&app.pool).await;
+    let guild_id = create_guild(&app.pool, owner_id).await;
+    add_guild_member(&app.pool, guild_id, member_id).await;
+    let (app_id, bot_user_id, _) = create_bot_application(&app.pool, owner_id).await;
+    install_bot_in_guild(&app.pool, guild_id, app_id, owner_id).await;
+    declare_intents(&app, app_id, &["presence"]).await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move {
+        delete_guild(&pool, guild_id).await;
+        delete_bot_application(&pool, app_id).await;
+    });
+    guard.delete_user(bot_user_id);
+    guard.delete_user(member_id);
+    guard.delete_user(owner_id);
+
+    let resp = approve_intents(
+        &app,
+        member_id,
+        guild_id,
+        bot_user_id,
+        serde_json::json!(["presence"]),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,93 @@
+//! Bulk Message Delete Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+#[tokio::test]
+async fn moderators_purge_a_raiders_messages() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (member_id, _) = create_test_user(&app.pool).await;
+    let (raider_id, _) = create_test_user(&app.pool).await;
+    let guild_id = create_guild_with_default_role(
+        &app.pool,
+        owner_id,
+        GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES,
+    )
+    .await;
+    add_guild_member(&app.pool, guild_id, member_id).await;
+    add_guild_member(&app.pool, guild_id, raider_id).await;
+    let channel_id = create_channel(&app.pool, guild_id, "general").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(owner_id);
+    guard.delete_user(member_id);
+    guard.delete_user(raider_id);
+
+    let kept = insert_message(&app.pool, channel_id, member_id, "hello").await;
+    for i in 0..5 {
+        insert_message(&app.pool, channel_id, raider_id, &format!("spam {i}")).await;
+    }
+    let path = format!("/api/messages/channel/{channel_id}/bulk-delete");
+
+    // Members without MANAGE_MESSAGES are refused
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::POST,
+        &path,
+        Some(json!({ "author_id": raider_id })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Unfiltered purges are rejected
+    let resp = send_json(&app, owner_id, Method::POST, &path, Some(json!({}))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Dry run previews without deleting
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::POST,
+        &path,
+        Some(json!({ "author_id": raider_id, "dry_run": true })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let preview = body_to_json(resp).await;
+    assert_eq!(preview["dry_run"], true);
+    assert_eq!(preview["deleted"], 5);
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::POST,
+        &path,
+        Some(json!({ "author_id": raider_id })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let result = body_to_json(resp).await;
+    assert_eq!(result["deleted"], 5);
+    assert_eq!(result["message_ids"], preview["message_ids"]);
+
+    let path = format!("/api/messages/channel/{channel_id}");
+    let list = body_to_json(send_json(&app, owner_id, Method::GET, &path, None).await).await;
+    let items = list["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], kept.to_string());
+
+    // The purge is in the audit log
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM system_audit_log WHERE action = 'guild.messages.bulk_deleted' AND target_id = $1",
+    )
+    .bind(guild_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(audited, 1);
+}
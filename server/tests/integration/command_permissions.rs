@@ -0,0 +1,231 @@
+//! Slash Command Permission Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::{body_to_json, create_channel, send_json, BotFixture, TestApp};
+
+/// @everyone permissions of the bot fixture guild.
+const EVERYONE: GuildPermissions =
+    GuildPermissions::VIEW_CHANNEL.union(GuildPermissions::SEND_MESSAGES);
+
+/// Register `/ban` (requires `BAN_MEMBERS`) and `/roll` for the fixture bot.
+async fn register_commands(app: &TestApp, fx: &BotFixture) {
+    for (name, perms) in [
+        ("ban", Some(GuildPermissions::BAN_MEMBERS.to_db())),
+        ("roll", None),
+    ] {
+        sqlx::query(
+            "INSERT INTO slash_commands (application_id, name, description, default_member_permissions) VALUES ($1, $2, 'Test command', $3)",
+        )
+        .bind(fx.app_id)
+        .bind(name)
+        .bind(perms)
+        .execute(&app.pool)
+        .await
+        .expect("Failed to insert slash command");
+    }
+}
+
+async fn visible_commands(app: &TestApp, user_id: Uuid, uri: &str) -> Vec<String> {
+    let resp = send_json(app, user_id, Method::GET, uri, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    json.as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn set_permissions(
+    app: &TestApp,
+    user_id: Uuid,
+    fx: &BotFixture,
+    name: &str,
+    body: serde_json::Value,
+) -> StatusCode {
+    let path = format!(
+        "/api/guilds/{}/commands/{}/{name}/permissions",
+        fx.guild_id, fx.app_id
+    );
+    send_json(app, user_id, Method::PUT, &path, Some(body))
+        .await
+        .status()
+}
+
+#[tokio::test]
+async fn default_member_permissions_filter_listing() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    let member_id = fx.add_member(&app, &mut guard).await;
+    register_commands(&app, &fx).await;
+
+    let uri = format!("/api/guilds/{}/commands", fx.guild_id);
+    assert_eq!(
+        visible_commands(&app, fx.owner_id, &uri).await,
+        ["ban", "roll"]
+    );
+    assert_eq!(visible_commands(&app, member_id, &uri).await, ["roll"]);
+}
+
+#[tokio::test]
+async fn overrides_restrict_commands() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    let member_id = fx.add_member(&app, &mut guard).await;
+    register_commands(&app, &fx).await;
+    let other_channel = create_channel(&app.pool, fx.guild_id, "elsewhere").await;
+
+    // Members cannot manage command permissions
+    let status = set_permissions(&app, member_id, &fx, "roll", json!({ "enabled": false })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Restrict /roll to one channel
+    let status = set_permissions(
+        &app,
+        fx.owner_id,
+        &fx,
+        "roll",
+        json!({ "channel_ids": [fx.channel_id] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let in_channel = format!(
+        "/api/guilds/{}/commands?channel_id={}",
+        fx.guild_id, fx.channel_id
+    );
+    let elsewhere = format!(
+        "/api/guilds/{}/commands?channel_id={other_channel}",
+        fx.guild_id
+    );
+    assert_eq!(
+        visible_commands(&app, member_id, &in_channel).await,
+        ["roll"]
+    );
+    assert!(visible_commands(&app, member_id, &elsewhere)
+        .await
+        .is_empty());
+
+    // Disable /roll entirely
+    let status = set_permissions(&app, fx.owner_id, &fx, "roll", json!({ "enabled": false })).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/guilds/{}/commands", fx.guild_id);
+    assert!(visible_commands(&app, member_id, &uri).await.is_empty());
+
+    // Invoking a disabled command is rejected before reaching the bot
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::POST,
+        &format!("/api/messages/channel/{}", fx.channel_id),
+        Some(json!({ "content": "/roll" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["error"], "COMMAND_FORBIDDEN");
+}
+
+#[tokio::test]
+async fn role_override_replaces_default_permissions() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    let member_id = fx.add_member(&app, &mut guard).await;
+    register_commands(&app, &fx).await;
+
+    let role_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO guild_roles (guild_id, name, permissions, position) VALUES ($1, 'Judges', 0, 5) RETURNING id",
+    )
+    .bind(fx.guild_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO guild_member_roles (guild_id, user_id, role_id) VALUES ($1, $2, $3)")
+        .bind(fx.guild_id)
+        .bind(member_id)
+        .bind(role_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let status = set_permissions(
+        &app,
+        fx.owner_id,
+        &fx,
+        "ban",
+        json!({ "role_ids": [role_id] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let uri = format!("/api/guilds/{}/commands", fx.guild_id);
+    assert_eq!(
+        visible_commands(&app, member_id, &uri).await,
+        ["ban", "roll"]
+    );
+
+    // Roles from other guilds are rejected
+    let status = set_permissions(
+        &app,
+        fx.owner_id,
+        &fx,
+        "ban",
+        json!({ "role_ids": [Uuid::new_v4()] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Resetting restores the default member permissions
+    let path = format!(
+        "/api/guilds/{}/commands/{}/ban/permissions",
+        fx.guild_id, fx.app_id
+    );
+    let resp = send_json(&app, fx.owner_id, Method::DELETE, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(visible_commands(&app, member_id, &uri).await, ["roll"]);
+}
+
+#[tokio::test]
+async fn deleting_a_command_drops_its_overrides() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    register_commands(&app, &fx).await;
+
+    for name in ["ban", "roll"] {
+        let status =
+            set_permissions(&app, fx.owner_id, &fx, name, json!({ "enabled": false })).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let roll_id: Uuid = sqlx::query_scalar(
+        "SELECT id FROM slash_commands WHERE application_id = $1 AND name = 'roll'",
+    )
+    .bind(fx.app_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    let path = format!("/api/applications/{}/commands/{roll_id}", fx.app_id);
+    let resp = send_json(&app, fx.owner_id, Method::DELETE, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let remaining: Vec<String> = sqlx::query_scalar(
+        "SELECT command_name FROM guild_command_permissions WHERE application_id = $1",
+    )
+    .bind(fx.app_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(remaining, ["ban"]);
+}
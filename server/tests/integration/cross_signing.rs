@@ -0,0 +1,174 @@
+//! Cross-Signing Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use vc_crypto::CrossSigningIdentity;
+
+use super::helpers::*;
+
+async fn insert_device(pool: &sqlx::PgPool, user_id: Uuid) -> (Uuid, String) {
+    let curve_key = Uuid::now_v7().simple().to_string();
+    let device_id = sqlx::query_scalar(
+        r"
+        INSERT INTO user_devices (user_id, identity_key_ed25519, identity_key_curve25519)
+        VALUES ($1, 'ed25519', $2)
+        RETURNING id
+        ",
+    )
+    .bind(user_id)
+    .bind(&curve_key)
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    (device_id, curve_key)
+}
+
+async fn publish(
+    app: &TestApp,
+    user_id: Uuid,
+    identity: &CrossSigningIdentity,
+    password: Option<&str>,
+) -> StatusCode {
+    let mut body = serde_json::to_value(identity.public_keys()).unwrap();
+    body["password"] = json!(password);
+    send_json(
+        app,
+        user_id,
+        Method::PUT,
+        "/api/keys/cross-signing",
+        Some(body),
+    )
+    .await
+    .status()
+}
+
+#[tokio::test]
+async fn cross_signed_devices_and_users() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    // Keys must be signed for their owner
+    let alice_identity = CrossSigningIdentity::new(&alice.to_string());
+    assert_eq!(
+        publish(&app, bob, &alice_identity, None).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        publish(&app, alice, &alice_identity, None).await,
+        StatusCode::OK
+    );
+
+    // Sign one of Alice's devices
+    let (device_id, curve_key) = insert_device(&app.pool, alice).await;
+    let bad = alice_identity.sign_device(&device_id.to_string(), "ed25519", "other-curve");
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        "/api/keys/signatures",
+        Some(json!({ "devices": [{ "device_id": device_id, "signature": bad }] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let signature = alice_identity.sign_device(&device_id.to_string(), "ed25519", &curve_key);
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        "/api/keys/signatures",
+        Some(json!({ "devices": [{ "device_id": device_id, "signature": signature }] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["devices_signed"], 1);
+
+    // Bob sees the signed device and Alice's cross-signing keys
+    let resp = send_json(
+        &app,
+        bob,
+        Method::GET,
+        &format!("/api/users/{alice}/keys"),
+        None,
+    )
+    .await;
+    let keys = body_to_json(resp).await;
+    assert_eq!(
+        keys["cross_signing"]["master_key"],
+        alice_identity.master_key()
+    );
+    assert_eq!(keys["devices"][0]["cross_signing_signature"], signature);
+    assert_eq!(keys["user_signature"], Value::Null);
+
+    // Alice verifies Bob
+    let bob_identity = CrossSigningIdentity::new(&bob.to_string());
+    assert_eq!(
+        publish(&app, bob, &bob_identity, None).await,
+        StatusCode::OK
+    );
+    let user_signature = alice_identity.sign_user(&bob.to_string(), &bob_identity.master_key());
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        "/api/keys/signatures",
+        Some(json!({ "users": [{ "user_id": bob, "signature": user_signature }] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let bob_keys = format!("/api/users/{bob}/keys");
+    let resp = send_json(&app, alice, Method::GET, &bob_keys, None).await;
+    assert_eq!(body_to_json(resp).await["user_signature"], user_signature);
+    // Only the signer sees their signature
+    let resp = send_json(&app, bob, Method::GET, &bob_keys, None).await;
+    assert_eq!(body_to_json(resp).await["user_signature"], Value::Null);
+
+    // Replacing published keys requires Alice's password
+    let password_hash = vc_server::auth::hash_password("correct horse").unwrap();
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(&password_hash)
+        .bind(alice)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let new_identity = CrossSigningIdentity::new(&alice.to_string());
+    assert_eq!(
+        publish(&app, alice, &new_identity, None).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        publish(&app, alice, &new_identity, Some("wrong horse")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    // Re-publishing the current keys replaces nothing
+    assert_eq!(
+        publish(&app, alice, &alice_identity, None).await,
+        StatusCode::OK
+    );
+    let resp = send_json(&app, alice, Method::GET, "/api/keys/devices", None).await;
+    assert_eq!(
+        body_to_json(resp).await["devices"][0]["cross_signing_signature"],
+        signature
+    );
+
+    // Resetting Alice's identity drops her device signatures
+    assert_eq!(
+        publish(&app, alice, &new_identity, Some("correct horse")).await,
+        StatusCode::OK
+    );
+    let resp = send_json(&app, alice, Method::GET, "/api/keys/devices", None).await;
+    let own = body_to_json(resp).await;
+    assert_eq!(
+        own["cross_signing"]["master_key"],
+        new_identity.master_key()
+    );
+    assert_eq!(own["devices"][0]["cross_signing_signature"], Value::Null);
+    let resp = send_json(&app, alice, Method::GET, &bob_keys, None).await;
+    assert_eq!(body_to_json(resp).await["user_signature"], Value::Null);
+}
@@ -0,0 +1,138 @@
+//! E2EE Device Management Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_crypto::olm::OlmAccount;
+
+use super::helpers::*;
+
+/// Insert a session for `user_id` and return its raw refresh token.
+async fn create_session(app: &TestApp, user_id: Uuid) -> String {
+    let token = format!("device-test-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO sessions (user_id, token_hash, expires_at) VALUES ($1, $2, NOW() + INTERVAL '1 day')",
+    )
+    .bind(user_id)
+    .bind(vc_server::auth::hash_token(&token))
+    .execute(&app.pool)
+    .await
+    .expect("Session insert should succeed");
+    token
+}
+
+/// Upload a fresh device with one prekey from the given session.
+async fn upload_device(app: &TestApp, user_id: Uuid, refresh_token: &str, name: &str) -> Uuid {
+    let account = OlmAccount::new();
+    let keys = account.identity_keys();
+    let resp = send_json_in_session(
+        app,
+        user_id,
+        refresh_token,
+        Method::POST,
+        "/api/keys/upload",
+        Some(json!({
+            "device_name": name,
+            "identity_key_ed25519": keys.ed25519,
+            "identity_key_curve25519": keys.curve25519,
+            "one_time_prekeys": [{ "key_id": "k1", "public_key": keys.curve25519 }],
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    body["device_id"].as_str().unwrap().parse().unwrap()
+}
+
+#[tokio::test]
+async fn rename_and_delete_devices() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    let desktop_session = create_session(&app, alice).await;
+    let laptop_session = create_session(&app, alice).await;
+    let desktop = upload_device(&app, alice, &desktop_session, "Desktop").await;
+    let laptop = upload_device(&app, alice, &laptop_session, "Laptop").await;
+
+    // Rename trims and validates
+    let path = format!("/api/keys/devices/{laptop}");
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PATCH,
+        &path,
+        Some(json!({ "device_name": "  Old laptop " })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    assert_eq!(body["device_name"], "Old laptop");
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PATCH,
+        &path,
+        Some(json!({ "device_name": "x".repeat(129) })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Other users cannot touch the device
+    let resp = send_json(
+        &app,
+        bob,
+        Method::PATCH,
+        &path,
+        Some(json!({ "device_name": "Mine now" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = send_json(&app, bob, Method::DELETE, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // The device behind the current session cannot be deleted
+    let resp = send_json_in_session(
+        &app,
+        alice,
+        &desktop_session,
+        Method::DELETE,
+        &format!("/api/keys/devices/{desktop}"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Deleting the laptop wipes its prekeys and logs its session out
+    let resp =
+        send_json_in_session(&app, alice, &desktop_session, Method::DELETE, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let prekeys: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM prekeys WHERE device_id = $1")
+        .bind(laptop)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(prekeys, 0);
+    let laptop_logged_in: bool =
+        sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM sessions WHERE token_hash = $1)")
+            .bind(vc_server::auth::hash_token(&laptop_session))
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert!(!laptop_logged_in);
+
+    let resp = send_json(&app, alice, Method::GET, "/api/keys/devices", None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    let devices = body["devices"].as_array().unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0]["device_id"], desktop.to_string());
+
+    let resp = send_json(&app, alice, Method::DELETE, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
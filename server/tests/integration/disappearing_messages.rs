@@ -0,0 +1,156 @@
+//! Disappearing Messages Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+async fn system_messages(app: &TestApp, channel_id: Uuid) -> Vec<String> {
+    sqlx::query_scalar(
+        "SELECT content FROM messages WHERE channel_id = $1 AND message_type = 'system' ORDER BY created_at",
+    )
+    .bind(channel_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn dm_participants_set_the_timer() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let (carol, _) = create_test_user(&app.pool).await;
+    let dm = create_dm_channel(&app.pool, alice, bob).await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_dm_channel(&pool, dm).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+    guard.delete_user(carol);
+
+    let path = format!("/api/channels/{dm}/disappearing-messages");
+    let hour = json!({ "message_ttl_seconds": 3600 });
+    let resp = send_json(&app, carol, Method::PUT, &path, Some(hour.clone())).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // Outside 5 minutes to 4 weeks
+    for seconds in [60, 2_419_201] {
+        let body = json!({ "message_ttl_seconds": seconds });
+        let resp = send_json(&app, bob, Method::PUT, &path, Some(body)).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let resp = send_json(&app, bob, Method::PUT, &path, Some(hour.clone())).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    assert_eq!(body["channel_id"], dm.to_string());
+    assert_eq!(body["message_ttl_seconds"], 3600);
+
+    // Setting the same timer again doesn't announce it twice
+    let resp = send_json(&app, alice, Method::PUT, &path, Some(hour)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        system_messages(&app, dm).await,
+        vec!["set disappearing messages to 1 hour.".to_string()]
+    );
+
+    // New messages get an expiry; expired ones are hidden before the sweep
+    let message = insert_message(&app.pool, dm, alice, "short-lived").await;
+    let expires_in: f64 = sqlx::query_scalar(
+        "SELECT EXTRACT(EPOCH FROM expires_at - created_at)::float8 FROM messages WHERE id = $1",
+    )
+    .bind(message)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!((expires_in - 3600.0).abs() < 1.0);
+
+    sqlx::query("UPDATE messages SET expires_at = NOW() - INTERVAL '1 second' WHERE id = $1")
+        .bind(message)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let resp = send_json(
+        &app,
+        bob,
+        Method::GET,
+        &format!("/api/messages/channel/{dm}"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    assert!(body["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|m| m["id"] != message.to_string()));
+    let resp = send_json(
+        &app,
+        bob,
+        Method::GET,
+        &format!("/api/messages/{message}/thread"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let off = json!({ "message_ttl_seconds": null });
+    let resp = send_json(&app, alice, Method::PUT, &path, Some(off)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["message_ttl_seconds"], Value::Null);
+    assert_eq!(
+        system_messages(&app, dm).await.last().map(String::as_str),
+        Some("turned off disappearing messages.")
+    );
+}
+
+#[tokio::test]
+async fn guild_channels_require_manage_channels() {
+    let app = TestApp::new().await;
+    let (owner, _) = create_test_user(&app.pool).await;
+    let (member, _) = create_test_user(&app.pool).await;
+    let guild = create_guild_with_default_role(
+        &app.pool,
+        owner,
+        GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES,
+    )
+    .await;
+    add_guild_member(&app.pool, guild, member).await;
+    let channel = create_channel(&app.pool, guild, "ephemeral").await;
+    let voice = create_voice_channel(&app.pool, guild, "lounge").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild).await });
+    guard.delete_user(owner);
+    guard.delete_user(member);
+
+    let day = json!({ "message_ttl_seconds": 86_400 });
+    let path = format!("/api/channels/{channel}/disappearing-messages");
+    let resp = send_json(&app, member, Method::PUT, &path, Some(day.clone())).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(system_messages(&app, channel).await.is_empty());
+
+    let resp = send_json(&app, owner, Method::PUT, &path, Some(day.clone())).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(
+        system_messages(&app, channel).await,
+        vec!["set disappearing messages to 1 day.".to_string()]
+    );
+
+    let resp = send_json(
+        &app,
+        member,
+        Method::GET,
+        &format!("/api/channels/{channel}"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["message_ttl_seconds"], 86_400);
+
+    let path = format!("/api/channels/{voice}/disappearing-messages");
+    let resp = send_json(&app, owner, Method::PUT, &path, Some(day)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,104 @@
+//! Email Digest Integration Tests
+
+use axum::body::Body;
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_server::digest::unsubscribe;
+
+use super::helpers::*;
+
+#[tokio::test]
+async fn digests_require_smtp_to_opt_in() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(user_id);
+
+    let resp = send_json(&app, user_id, Method::GET, "/api/me/email-digest", None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["frequency"], "off");
+    assert_eq!(json["available"], false);
+
+    // The test server has no SMTP, so opting in is refused
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::PUT,
+        "/api/me/email-digest",
+        Some(json!({ "frequency": "daily" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body_to_json(resp).await["error"], "DIGEST_NOT_CONFIGURED");
+
+    // Opting out always works
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::PUT,
+        "/api/me/email-digest",
+        Some(json!({ "frequency": "off" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["frequency"], "off");
+}
+
+#[tokio::test]
+async fn unsubscribe_link_turns_digests_off() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(user_id);
+    sqlx::query("INSERT INTO email_digest_settings (user_id, frequency) VALUES ($1, 'weekly')")
+        .bind(user_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let token = unsubscribe::sign(
+        &unsubscribe::derive_key(&app.config.jwt_private_key),
+        user_id,
+    );
+    let uri = format!("/api/email/unsubscribe?token={token}");
+
+    // Opening the link only shows a confirmation page
+    let resp = app
+        .oneshot(
+            TestApp::request(Method::GET, &uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = send_json(&app, user_id, Method::GET, "/api/me/email-digest", None).await;
+    assert_eq!(body_to_json(resp).await["frequency"], "weekly");
+
+    // One-click POST, as sent by mail clients (RFC 8058)
+    let resp = app
+        .oneshot(
+            TestApp::request(Method::POST, &uri)
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .body(Body::from("List-Unsubscribe=One-Click"))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = send_json(&app, user_id, Method::GET, "/api/me/email-digest", None).await;
+    assert_eq!(body_to_json(resp).await["frequency"], "off");
+
+    // Tokens for another user or with a bad signature are rejected
+    let forged = format!("{}.{}", Uuid::new_v4(), token.split_once('.').unwrap().1);
+    for bad in [forged.as_str(), "not-a-token"] {
+        let resp = app
+            .oneshot(
+                TestApp::request(Method::POST, &format!("/api/email/unsubscribe?token={bad}"))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,221 @@
+//! End-to-End Encrypted Channel Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+fn encrypted_message(epoch: Option<i64>) -> Value {
+    json!({
+        "content": "{\"megolm_ciphertext\":\"AwgA\"}",
+        "encrypted": true,
+        "nonce": "bm9uY2U",
+        "encryption_epoch": epoch,
+    })
+}
+
+#[tokio::test]
+async fn encrypted_dm_requires_current_epoch() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let (carol, _) = create_test_user(&app.pool).await;
+    let dm = create_dm_channel(&app.pool, alice, bob).await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_dm_channel(&pool, dm).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+    guard.delete_user(carol);
+
+    let path = format!("/api/channels/{dm}/encryption");
+    let resp = send_json(&app, carol, Method::PUT, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = send_json(&app, alice, Method::PUT, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    assert_eq!(body["encrypted"], true);
+    assert_eq!(body["epoch"], 0);
+    assert_eq!(body["members"].as_array().unwrap().len(), 2);
+
+    let messages = format!("/api/messages/channel/{dm}");
+    let resp = send_json(
+        &app,
+        bob,
+        Method::POST,
+        &messages,
+        Some(json!({ "content": "hello" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_to_json(resp).await["error"], "ENCRYPTION_REQUIRED");
+
+    let resp = send_json(
+        &app,
+        bob,
+        Method::POST,
+        &messages,
+        Some(encrypted_message(Some(0))),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // Adding a participant moves the epoch on
+    sqlx::query("INSERT INTO dm_participants (channel_id, user_id) VALUES ($1, $2)")
+        .bind(dm)
+        .bind(carol)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let resp = send_json(
+        &app,
+        bob,
+        Method::POST,
+        &messages,
+        Some(encrypted_message(Some(0))),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let body = body_to_json(resp).await;
+    assert_eq!(body["error"], "ENCRYPTION_EPOCH_STALE");
+    assert_eq!(body["epoch"], 1);
+
+    let resp = send_json(&app, carol, Method::GET, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    assert_eq!(body["epoch"], 1);
+    assert_eq!(body["members"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn only_private_guild_channels_can_be_encrypted() {
+    let app = TestApp::new().await;
+    let (owner, _) = create_test_user(&app.pool).await;
+    let guild = create_guild_with_default_role(
+        &app.pool,
+        owner,
+        GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES,
+    )
+    .await;
+    let channel = create_channel(&app.pool, guild, "secret").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild).await });
+    guard.delete_user(owner);
+
+    let path = format!("/api/channels/{channel}/encryption");
+    let resp = send_json(&app, owner, Method::PUT, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    sqlx::query(
+        r"
+        INSERT INTO channel_overrides (id, channel_id, role_id, allow_permissions, deny_permissions)
+        SELECT $1, $2, id, 0, $3 FROM guild_roles WHERE guild_id = $4 AND is_default
+        ",
+    )
+    .bind(Uuid::now_v7())
+    .bind(channel)
+    .bind(GuildPermissions::VIEW_CHANNEL.to_db())
+    .bind(guild)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let resp = send_json(&app, owner, Method::PUT, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    assert_eq!(body["encrypted"], true);
+    assert_eq!(body["members"], json!([owner]));
+
+    // Enabling again is a no-op
+    let resp = send_json(&app, owner, Method::PUT, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["epoch"], 0);
+}
+
+#[tokio::test]
+async fn edits_follow_channel_encryption() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let dm = create_dm_channel(&app.pool, alice, bob).await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_dm_channel(&pool, dm).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    let plaintext = insert_message(&app.pool, dm, alice, "before the switch").await;
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PUT,
+        &format!("/api/channels/{dm}/encryption"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Plaintext from before the switch can't be edited into new plaintext
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PATCH,
+        &format!("/api/messages/{plaintext}"),
+        Some(json!({ "content": "still readable" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(body_to_json(resp).await["error"], "ENCRYPTION_REQUIRED");
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        &format!("/api/messages/channel/{dm}"),
+        Some(encrypted_message(Some(0))),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let message = body_to_json(resp).await["id"].as_str().unwrap().to_string();
+    let path = format!("/api/messages/{message}");
+    let content = "{\"megolm_ciphertext\":\"AwgB\"}";
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PATCH,
+        &path,
+        Some(json!({ "content": content, "encryption_epoch": 0 })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PATCH,
+        &path,
+        Some(json!({ "content": content, "nonce": "bm9uY2Uy" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    assert_eq!(body_to_json(resp).await["error"], "ENCRYPTION_EPOCH_STALE");
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PATCH,
+        &path,
+        Some(json!({ "content": content, "nonce": "bm9uY2Uy", "encryption_epoch": 0 })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let nonce: Option<String> = sqlx::query_scalar("SELECT nonce FROM messages WHERE id = $1")
+        .bind(Uuid::parse_str(&message).unwrap())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(nonce.as_deref(), Some("bm9uY2Uy"));
+}
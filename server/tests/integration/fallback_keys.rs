@@ -0,0 +1,131 @@
+//! Fallback Key Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+use vc_crypto::olm::OlmAccount;
+
+use super::helpers::*;
+
+async fn upload(app: &TestApp, user_id: Uuid, account: &OlmAccount, fallback_key: Value) -> Value {
+    let identity = account.identity_keys();
+    let prekeys: Vec<Value> = account
+        .one_time_keys()
+        .into_iter()
+        .map(|(key_id, public_key)| {
+            json!({ "key_id": key_id.to_base64(), "public_key": public_key })
+        })
+        .collect();
+    let resp = send_json(
+        app,
+        user_id,
+        Method::POST,
+        "/api/keys/upload",
+        Some(json!({
+            "identity_key_ed25519": identity.ed25519,
+            "identity_key_curve25519": identity.curve25519,
+            "one_time_prekeys": prekeys,
+            "fallback_key": fallback_key,
+        })),
+    )
+    .await;
+    let status = resp.status();
+    let body = body_to_json(resp).await;
+    assert_eq!(status, StatusCode::OK, "{body}");
+    body
+}
+
+async fn claim(app: &TestApp, claimer: Uuid, owner: Uuid, device_id: &str) -> Value {
+    let resp = send_json(
+        app,
+        claimer,
+        Method::POST,
+        &format!("/api/users/{owner}/keys/claim"),
+        Some(json!({ "device_id": device_id })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    body_to_json(resp).await
+}
+
+#[tokio::test]
+async fn fallback_key_is_claimed_once_prekeys_run_out() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    let mut account = OlmAccount::new();
+    account.generate_one_time_keys(1);
+    account.generate_fallback_key();
+    let fallback = account.fallback_key().unwrap();
+
+    // Signatures from another device are rejected
+    let mut other = OlmAccount::new();
+    other.generate_fallback_key();
+    let mut forged = fallback.clone();
+    forged.signature = other.fallback_key().unwrap().signature;
+    let identity = account.identity_keys();
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        "/api/keys/upload",
+        Some(json!({
+            "identity_key_ed25519": identity.ed25519,
+            "identity_key_curve25519": identity.curve25519,
+            "one_time_prekeys": [],
+            "fallback_key": forged,
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let body = upload(
+        &app,
+        alice,
+        &account,
+        serde_json::to_value(&fallback).unwrap(),
+    )
+    .await;
+    let device_id = body["device_id"].as_str().unwrap().to_string();
+    assert_eq!(body["prekeys_uploaded"], 1);
+
+    // The one-time prekey goes first, without the fallback key
+    let first = claim(&app, bob, alice, &device_id).await;
+    assert!(first["one_time_prekey"].is_object());
+    assert!(first["fallback_key"].is_null());
+
+    // Then the fallback key, as often as needed
+    for _ in 0..2 {
+        let next = claim(&app, bob, alice, &device_id).await;
+        assert!(next["one_time_prekey"].is_null());
+        assert_eq!(next["fallback_key"]["public_key"], fallback.public_key);
+        assert_eq!(next["fallback_key"]["signature"], fallback.signature);
+    }
+
+    let used: bool = sqlx::query_scalar(
+        "SELECT used_at IS NOT NULL FROM fallback_keys WHERE device_id = $1::uuid",
+    )
+    .bind(&device_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(used);
+
+    // Rotating the key clears the used flag
+    account.mark_keys_as_published();
+    account.generate_fallback_key();
+    let rotated = account.fallback_key().unwrap();
+    upload(
+        &app,
+        alice,
+        &account,
+        serde_json::to_value(&rotated).unwrap(),
+    )
+    .await;
+    let next = claim(&app, bob, alice, &device_id).await;
+    assert_eq!(next["fallback_key"]["public_key"], rotated.public_key);
+}
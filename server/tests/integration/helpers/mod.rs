@@ -534,6 +534,52 @@ pub async fn create_test_report(pool: &PgPool, reporter_id: Uuid, target_id: Uui
     row.0
 }
 
+/// Build a JSON request authenticated as `user_id`.
+fn json_request(
+    app: &TestApp,
+    user_id: Uuid,
+    method: Method,
+    path: &str,
+) -> http::request::Builder {
+    let token = generate_access_token(&app.config, user_id);
+    TestApp::request(method, path)
+        .header("Authorization", format!("Bearer {token}"))
+        .header("Content-Type", "application/json")
+}
+
+/// Send a request as `user_id`, with `body` serialized as JSON (empty if `None`).
+pub async fn send_json(
+    app: &TestApp,
+    user_id: Uuid,
+    method: Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Response<Body> {
+    let req = json_request(app, user_id, method, path)
+        .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+        .unwrap();
+    app.oneshot(req).await
+}
+
+/// Like [`send_json`], but from the session `refresh_token` belongs to.
+///
+/// Session-scoped endpoints identify the caller's session through the
+/// `X-Refresh-Token` header, as Tauri clients send it.
+pub async fn send_json_in_session(
+    app: &TestApp,
+    user_id: Uuid,
+    refresh_token: &str,
+    method: Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Response<Body> {
+    let req = json_request(app, user_id, method, path)
+        .header("X-Refresh-Token", refresh_token)
+        .body(body.map_or_else(Body::empty, |b| Body::from(b.to_string())))
+        .unwrap();
+    app.oneshot(req).await
+}
+
 /// Collect a response body and parse it as JSON.
 pub async fn body_to_json(response: Response<Body>) -> serde_json::Value {
     let bytes = response
@@ -841,10 +887,10 @@ pub async fn create_test_webhook(pool: &PgPool, app_id: Uuid, url: &str, events:
     webhook_id
 }
 
-/// Install a bot application in a guild.
+/// Install a bot application in a guild, approving the scopes it has been granted.
 pub async fn install_bot_in_guild(pool: &PgPool, guild_id: Uuid, app_id: Uuid, user_id: Uuid) {
     sqlx::query(
-        "INSERT INTO guild_bot_installations (guild_id, application_id, installed_by) VALUES ($1, $2, $3)",
+        "INSERT INTO guild_bot_installations (guild_id, application_id, installed_by, approved_scopes) SELECT $1, id, $3, scopes FROM bot_applications WHERE id = $2",
     )
     .bind(guild_id)
     .bind(app_id)
@@ -869,3 +915,72 @@ pub async fn delete_bot_application(pool: &PgPool, app_id: Uuid) {
         .await
         .ok();
 }
+
+/// Enable the bot REST API for an application created by [`create_bot_application`].
+///
+/// Stores an Argon2 hash of `token` (the REST API verifies tokens the same way
+/// as the bot gateway) and grants the given scopes.
+pub async fn grant_bot_scopes(pool: &PgPool, app_id: Uuid, token: &str, scopes: &[&str]) {
+    let token_hash = vc_server::auth::hash_password(token).expect("Failed to hash bot token");
+    let scopes: Vec<String> = scopes.iter().map(|s| (*s).to_string()).collect();
+
+    sqlx::query("UPDATE bot_applications SET token_hash = $1, scopes = $2 WHERE id = $3")
+        .bind(&token_hash)
+        .bind(&scopes)
+        .bind(app_id)
+        .execute(pool)
+        .await
+        .expect("Failed to grant bot scopes");
+}
+
+/// Guild with one text channel and an installed bot, owned by a fresh user.
+pub struct BotFixture {
+    pub owner_id: Uuid,
+    pub guild_id: Uuid,
+    pub channel_id: Uuid,
+    pub app_id: Uuid,
+    pub bot_user_id: Uuid,
+    /// Bot token accepted by the bot gateway and REST API.
+    pub bot_token: String,
+}
+
+impl BotFixture {
+    /// Create the guild with `everyone` as @everyone permissions and install a
+    /// bot granted (and approved for) `scopes`.
+    pub async fn new(app: &TestApp, everyone: GuildPermissions, scopes: &[&str]) -> Self {
+        let (owner_id, _) = create_test_user(&app.pool).await;
+        let guild_id = create_guild_with_default_role(&app.pool, owner_id, everyone).await;
+        let channel_id = create_channel(&app.pool, guild_id, "bots").await;
+        let (app_id, bot_user_id, bot_token) = create_bot_application(&app.pool, owner_id).await;
+        grant_bot_scopes(&app.pool, app_id, &bot_token, scopes).await;
+        install_bot_in_guild(&app.pool, guild_id, app_id, owner_id).await;
+
+        Self {
+            owner_id,
+            guild_id,
+            channel_id,
+            app_id,
+            bot_user_id,
+            bot_token,
+        }
+    }
+
+    /// Register cleanup of the guild, bot application and their users.
+    pub fn register_cleanup(&self, guard: &mut CleanupGuard) {
+        let (guild_id, app_id) = (self.guild_id, self.app_id);
+        guard.add(move |pool| async move {
+            delete_guild(&pool, guild_id).await;
+            delete_bot_application(&pool, app_id).await;
+        });
+        guard.delete_user(self.bot_user_id);
+        guard.delete_user(self.owner_id);
+    }
+
+    /// Add a new user to the guild, deleted with `guard`.
+    pub async fn add_member(&self, app: &TestApp, guard: &mut CleanupGuard) -> Uuid {
+        let (user_id, _) = create_test_user(&app.pool).await;
+        guard.delete_user(user_id);
+        add_guild_member(&app.pool, self.guild_id, user_id).await;
+        user_id
+    }
+}
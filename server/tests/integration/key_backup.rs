@@ -0,0 +1,105 @@
+//! Megolm Key Backup Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use super::helpers::*;
+
+fn entry(first_message_index: i64, ciphertext: &str) -> Value {
+    json!({
+        "first_message_index": first_message_index,
+        "nonce": "bm9uY2U",
+        "ciphertext": ciphertext,
+    })
+}
+
+#[tokio::test]
+async fn sessions_are_backed_up_per_version() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    let resp = send_json(&app, alice, Method::GET, "/api/keys/backup/version", None).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        "/api/keys/backup/version",
+        Some(json!({
+            "algorithm": "kaiku.megolm_backup.v1",
+            "auth_data": { "salt": "c2FsdA", "key_check": "a.b" },
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let version = body_to_json(resp).await["version"].as_i64().unwrap();
+
+    let path = format!("/api/keys/backup/{version}/rooms/room-1/sessions/session-1");
+    let resp = send_json(&app, alice, Method::PUT, &path, Some(entry(3, "first"))).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_to_json(resp).await;
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["etag"], "1");
+
+    // A copy that reaches less far back doesn't replace the stored one
+    let resp = send_json(&app, alice, Method::PUT, &path, Some(entry(7, "worse"))).await;
+    assert_eq!(body_to_json(resp).await["etag"], "1");
+
+    let resp = send_json(&app, alice, Method::GET, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["ciphertext"], "first");
+
+    // Other users can't see the backup
+    let resp = send_json(&app, bob, Method::GET, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = send_json(&app, bob, Method::PUT, &path, Some(entry(0, "bob"))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = send_json(&app, alice, Method::GET, "/api/keys/backup/version", None).await;
+    let info = body_to_json(resp).await;
+    assert_eq!(info["version"], version);
+    assert_eq!(info["count"], 1);
+    assert_eq!(info["auth_data"]["salt"], "c2FsdA");
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::GET,
+        &format!("/api/keys/backup/{version}/sessions"),
+        None,
+    )
+    .await;
+    let body = body_to_json(resp).await;
+    assert_eq!(body["sessions"][0]["room_id"], "room-1");
+    assert_eq!(body["sessions"][0]["session_id"], "session-1");
+    assert_eq!(body["has_more"], false);
+
+    // Rotating the version leaves the old one behind
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        "/api/keys/backup/version",
+        Some(json!({ "algorithm": "kaiku.megolm_backup.v1", "auth_data": {} })),
+    )
+    .await;
+    let rotated = body_to_json(resp).await["version"].as_i64().unwrap();
+    assert!(rotated > version);
+    let resp = send_json(&app, alice, Method::PUT, &path, Some(entry(0, "stale"))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::DELETE,
+        &format!("/api/keys/backup/{rotated}"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+}
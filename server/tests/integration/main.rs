@@ -4,27 +4,43 @@ mod admin_elevation;
 mod admin_reports;
 mod auth;
 mod blocking;
+mod bot_api;
 mod bot_ecosystem;
 mod bot_intents;
+mod bulk_delete;
 mod channel_permissions;
 mod channel_pins;
 mod channels_http;
+mod command_permissions;
 mod connectivity_http;
+mod cross_signing;
 mod custom_status;
+mod device_management;
+mod disappearing_messages;
 mod dm_http;
 mod e2ee_keys;
 mod e2ee_settings;
+mod email_digest;
+mod encrypted_channels;
+mod fallback_keys;
 mod favorites;
 mod filters_http;
 mod global_search_http;
 mod governance;
 mod guild_invite;
 mod guild_limits;
+mod key_backup;
 mod media_processing;
 mod mention_permission;
+mod mentions_inbox;
+mod message_components;
+mod message_forwarding;
 mod messages_http;
+mod notification_settings;
 mod oidc;
 mod pages;
+mod polls;
+mod push_registrations;
 mod ratelimit;
 mod ratelimit_http;
 mod reports;
@@ -36,7 +52,10 @@ mod setup;
 mod setup_concurrent_http;
 mod setup_http;
 mod setup_integration;
+mod slowmode;
+mod stickers;
 mod threads;
+mod to_device;
 mod upload_limits;
 mod uploads_http;
 mod voice_sfu;
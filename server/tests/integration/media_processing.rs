@@ -55,6 +55,40 @@ fn build_upload_multipart(
     (boundary.to_string(), body)
 }
 
+/// Build a multipart body for an encrypted upload of opaque ciphertext.
+///
+/// The file bytes would fail the magic byte check for the claimed type.
+fn build_encrypted_multipart(with_thumbnail: bool) -> (String, Vec<u8>) {
+    let boundary = "----TestBoundary12345";
+    let mut body = Vec::new();
+    for (name, value) in [
+        ("content", "{\"megolm_ciphertext\":\"AwgA\"}"),
+        ("encrypted", "true"),
+        ("nonce", "bm9uY2U"),
+        ("encryption_epoch", "0"),
+    ] {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+    let thumbnail = with_thumbnail.then_some(("thumbnail", &[0xa5_u8; 16][..]));
+    for (name, data) in std::iter::once(("file", &[0x5a_u8; 64][..])).chain(thumbnail) {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"; filename=\"encrypted.bin\"\r\nContent-Type: image/png\r\n\r\n"
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    (boundary.to_string(), body)
+}
+
 // ============================================================================
 // Auth & Error Path Tests (no S3 required)
 // ============================================================================
@@ -423,3 +457,114 @@ async fn test_invalid_variant_returns_validation_error() {
         error
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_encrypted_upload_is_stored_opaque() {
+    if !super::helpers::rustfs_available().await {
+        return;
+    }
+    let (app, _bucket) = super::helpers::fresh_test_app_with_s3().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let token = generate_access_token(&app.config, alice);
+    let dm = super::helpers::create_dm_channel(&app.pool, alice, bob).await;
+    sqlx::query("UPDATE channels SET encrypted = TRUE WHERE id = $1")
+        .bind(dm)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { super::helpers::delete_dm_channel(&pool, dm).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    // Plaintext uploads are refused in encrypted channels
+    let png_data = create_test_png(500, 400);
+    let (boundary, body) = build_upload_multipart("photo.png", "image/png", &png_data, "");
+    let req = TestApp::request(Method::POST, &format!("/api/messages/channel/{dm}/upload"))
+        .header("Authorization", format!("Bearer {token}"))
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), 400);
+
+    let (boundary, body) = build_encrypted_multipart(true);
+
+    let req = TestApp::request(Method::POST, &format!("/api/messages/channel/{dm}/upload"))
+        .header("Authorization", format!("Bearer {token}"))
+        .header(
+            "Content-Type",
+            format!("multipart/form-data; boundary={boundary}"),
+        )
+        .body(Body::from(body))
+        .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), 201, "Encrypted upload should return 201");
+
+    let body_bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = serde_json::from_slice(&body_bytes).unwrap();
+    assert_eq!(json["encrypted"], true);
+    let attachment = &json["attachments"][0];
+    assert_eq!(attachment["encrypted"], true);
+    assert_eq!(attachment["mime_type"], "application/octet-stream");
+    assert!(attachment["blurhash"].is_null());
+    assert!(attachment["width"].is_null());
+    assert!(attachment["thumbnail_url"].is_string());
+
+    let attachment_id = attachment["id"].as_str().unwrap();
+    let req = TestApp::request(
+        Method::GET,
+        &format!(
+            "/api/messages/attachments/{attachment_id}/download?variant=thumbnail&token={token}"
+        ),
+    )
+    .body(Body::empty())
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(
+        resp.headers().get("content-type").unwrap(),
+        "application/octet-stream"
+    );
+    let thumb_bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(thumb_bytes.as_ref(), &[0xa5_u8; 16]);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_encrypted_flag_is_ignored_in_plaintext_channels() {
+    if !super::helpers::rustfs_available().await {
+        return;
+    }
+    let (app, _bucket) = super::helpers::fresh_test_app_with_s3().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let token = generate_access_token(&app.config, user_id);
+
+    let perms = GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES;
+    let guild_id = super::helpers::create_guild_with_default_role(&app.pool, user_id, perms).await;
+    let channel_id = super::helpers::create_channel(&app.pool, guild_id, "plaintext").await;
+
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { super::helpers::delete_guild(&pool, guild_id).await });
+    guard.delete_user(user_id);
+
+    // Claiming encryption doesn't skip the type and magic byte checks
+    let (boundary, body) = build_encrypted_multipart(false);
+    let req = TestApp::request(
+        Method::POST,
+        &format!("/api/messages/channel/{channel_id}/upload"),
+    )
+    .header("Authorization", format!("Bearer {token}"))
+    .header(
+        "Content-Type",
+        format!("multipart/form-data; boundary={boundary}"),
+    )
+    .body(Body::from(body))
+    .unwrap();
+    let resp = app.oneshot(req).await;
+    assert_eq!(resp.status(), 400);
+}
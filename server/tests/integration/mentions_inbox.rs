@@ -0,0 +1,184 @@
+//! Recent Mentions Inbox Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+async fn post_message_status(
+    app: &TestApp,
+    user_id: Uuid,
+    channel_id: Uuid,
+    content: &str,
+) -> StatusCode {
+    send_json(
+        app,
+        user_id,
+        Method::POST,
+        &format!("/api/messages/channel/{channel_id}"),
+        Some(json!({ "content": content })),
+    )
+    .await
+    .status()
+}
+
+async fn post_message(app: &TestApp, user_id: Uuid, channel_id: Uuid, content: &str) {
+    assert_eq!(
+        post_message_status(app, user_id, channel_id, content).await,
+        StatusCode::CREATED
+    );
+}
+
+async fn list_mentions(app: &TestApp, user_id: Uuid, query: &str) -> serde_json::Value {
+    let path = format!("/api/me/mentions{query}");
+    let resp = send_json(app, user_id, Method::GET, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    body_to_json(resp).await
+}
+
+#[tokio::test]
+async fn mentions_inbox_lists_and_filters_mentions() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (member_id, member_name) = create_test_user(&app.pool).await;
+    let perms = GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES;
+    let guild_a = create_guild_with_default_role(&app.pool, owner_id, perms).await;
+    let guild_b = create_guild_with_default_role(&app.pool, owner_id, perms).await;
+    add_guild_member(&app.pool, guild_a, member_id).await;
+    add_guild_member(&app.pool, guild_b, member_id).await;
+    let channel_a = create_channel(&app.pool, guild_a, "alpha").await;
+    let channel_b = create_channel(&app.pool, guild_b, "beta").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move {
+        delete_guild(&pool, guild_a).await;
+        delete_guild(&pool, guild_b).await;
+    });
+    guard.delete_user(owner_id);
+    guard.delete_user(member_id);
+
+    post_message(&app, owner_id, channel_a, &format!("hi @{member_name}")).await;
+    post_message(&app, owner_id, channel_a, "no mention").await;
+    post_message(&app, owner_id, channel_b, &format!("@{member_name} ping")).await;
+
+    let json = list_mentions(&app, member_id, "").await;
+    let items = json["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["channel_id"], channel_b.to_string());
+    assert_eq!(items[0]["kind"], "user");
+    assert_eq!(items[0]["author"]["id"], owner_id.to_string());
+
+    let json = list_mentions(&app, member_id, &format!("?guild_id={guild_a}")).await;
+    let items = json["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["content"], format!("hi @{member_name}"));
+
+    // Cursor pagination
+    let first = list_mentions(&app, member_id, "?limit=1").await;
+    assert_eq!(first["has_more"], true);
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let second = list_mentions(&app, member_id, &format!("?limit=1&before={cursor}")).await;
+    assert_eq!(second["has_more"], false);
+    assert_eq!(second["items"][0]["channel_id"], channel_a.to_string());
+
+    // The author is never in their own inbox
+    let json = list_mentions(&app, owner_id, "").await;
+    assert!(json["items"].as_array().unwrap().is_empty());
+
+    // Losing VIEW_CHANNEL hides earlier mentions in that channel
+    sqlx::query("UPDATE guild_roles SET permissions = 0 WHERE guild_id = $1 AND is_default")
+        .bind(guild_a)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let json = list_mentions(&app, member_id, "").await;
+    let items = json["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["channel_id"], channel_b.to_string());
+
+    // Newer hidden mentions don't leave a page short
+    post_message(&app, owner_id, channel_a, &format!("@{member_name} again")).await;
+    let json = list_mentions(&app, member_id, "?limit=1").await;
+    assert_eq!(json["items"][0]["channel_id"], channel_b.to_string());
+    assert_eq!(json["has_more"], false);
+}
+
+#[tokio::test]
+async fn role_mentions_require_mentionable_role() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (author_id, _) = create_test_user(&app.pool).await;
+    let (holder_id, _) = create_test_user(&app.pool).await;
+    let perms = GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES;
+    let guild_id = create_guild_with_default_role(&app.pool, owner_id, perms).await;
+    add_guild_member(&app.pool, guild_id, author_id).await;
+    add_guild_member(&app.pool, guild_id, holder_id).await;
+    let channel_id = create_channel(&app.pool, guild_id, "support").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(owner_id);
+    guard.delete_user(author_id);
+    guard.delete_user(holder_id);
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::POST,
+        &format!("/api/guilds/{guild_id}/roles"),
+        Some(json!({ "name": "helpers" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let role = body_to_json(resp).await;
+    assert_eq!(role["mentionable"], false);
+    let role_id = role["id"].as_str().unwrap().to_string();
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::POST,
+        &format!("/api/guilds/{guild_id}/members/{holder_id}/roles/{role_id}"),
+        Some(json!({})),
+    )
+    .await;
+    assert!(resp.status().is_success());
+
+    let mention = format!("<@&{role_id}> can someone help?");
+    assert_eq!(
+        post_message_status(&app, author_id, channel_id, &mention).await,
+        StatusCode::BAD_REQUEST
+    );
+    assert_eq!(
+        post_message_status(
+            &app,
+            author_id,
+            channel_id,
+            &format!("<@&{}>", Uuid::new_v4())
+        )
+        .await,
+        StatusCode::BAD_REQUEST
+    );
+
+    // Members with MENTION_EVERYONE bypass the flag
+    post_message(&app, owner_id, channel_id, &mention).await;
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::PATCH,
+        &format!("/api/guilds/{guild_id}/roles/{role_id}"),
+        Some(json!({ "mentionable": true })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["mentionable"], true);
+
+    post_message(&app, author_id, channel_id, &mention).await;
+
+    let json = list_mentions(&app, holder_id, "").await;
+    let items = json["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert!(items.iter().all(|item| item["kind"] == "role"));
+    assert_eq!(items[0]["author"]["id"], author_id.to_string());
+}
@@ -0,0 +1,152 @@
+//! Message Component Interaction Integration Tests
+
+use axum::body::Body;
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::{body_to_json, create_test_user, send_json, BotFixture, TestApp};
+
+/// @everyone permissions of the bot fixture guild.
+const EVERYONE: GuildPermissions =
+    GuildPermissions::VIEW_CHANNEL.union(GuildPermissions::SEND_MESSAGES);
+
+/// Post a bot message with two buttons and a select menu in the fixture channel.
+async fn post_component_message(app: &TestApp, fx: &BotFixture) -> Uuid {
+    let components = json!([
+        {
+            "components": [
+                { "type": "button", "style": "primary", "label": "Approve", "custom_id": "approve" },
+                { "type": "button", "style": "danger", "label": "Locked", "custom_id": "locked", "disabled": true }
+            ]
+        },
+        {
+            "components": [{
+                "type": "select_menu",
+                "custom_id": "color",
+                "options": [
+                    { "label": "Red", "value": "red" },
+                    { "label": "Blue", "value": "blue" }
+                ],
+                "min_values": 1,
+                "max_values": 1
+            }]
+        }
+    ]);
+    sqlx::query_scalar(
+        "INSERT INTO messages (channel_id, user_id, content, components) VALUES ($1, $2, $3, $4) RETURNING id",
+    )
+    .bind(fx.channel_id)
+    .bind(fx.bot_user_id)
+    .bind("Pick one")
+    .bind(&components)
+    .fetch_one(&app.pool)
+    .await
+    .expect("Failed to insert component message")
+}
+
+async fn interact(
+    app: &TestApp,
+    user_id: Uuid,
+    message_id: Uuid,
+    body: serde_json::Value,
+) -> axum::http::Response<Body> {
+    send_json(
+        app,
+        user_id,
+        Method::POST,
+        &format!("/api/messages/{message_id}/interactions"),
+        Some(body),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn message_response_includes_components() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    let message_id = post_component_message(&app, &fx).await;
+
+    let path = format!("/api/messages/channel/{}", fx.channel_id);
+    let resp = send_json(&app, fx.owner_id, Method::GET, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let json = body_to_json(resp).await;
+    assert_eq!(json["items"][0]["id"], message_id.to_string());
+    let components = &json["items"][0]["components"];
+    assert_eq!(components.as_array().map(Vec::len), Some(2));
+    assert_eq!(components[0]["components"][0]["custom_id"], "approve");
+    assert_eq!(components[1]["components"][0]["type"], "select_menu");
+}
+
+#[tokio::test]
+async fn component_interaction_is_accepted() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    let message_id = post_component_message(&app, &fx).await;
+
+    let resp = interact(
+        &app,
+        fx.owner_id,
+        message_id,
+        json!({ "custom_id": "approve" }),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+    let json = body_to_json(resp).await;
+    assert!(json["interaction_id"].as_str().is_some());
+
+    let resp = interact(
+        &app,
+        fx.owner_id,
+        message_id,
+        json!({ "custom_id": "color", "values": ["blue"] }),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::ACCEPTED);
+}
+
+#[tokio::test]
+async fn component_interaction_validates_component() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    let message_id = post_component_message(&app, &fx).await;
+
+    for body in [
+        json!({ "custom_id": "missing" }),
+        json!({ "custom_id": "locked" }),
+        json!({ "custom_id": "approve", "values": ["red"] }),
+        json!({ "custom_id": "color", "values": ["green"] }),
+        json!({ "custom_id": "color", "values": ["red", "blue"] }),
+    ] {
+        let resp = interact(&app, fx.owner_id, message_id, body.clone()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "body: {body}");
+    }
+}
+
+#[tokio::test]
+async fn component_interaction_requires_channel_access() {
+    let app = TestApp::new().await;
+    let fx = BotFixture::new(&app, EVERYONE, &[]).await;
+    let (outsider_id, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    fx.register_cleanup(&mut guard);
+    let message_id = post_component_message(&app, &fx).await;
+    guard.delete_user(outsider_id);
+
+    let resp = interact(
+        &app,
+        outsider_id,
+        message_id,
+        json!({ "custom_id": "approve" }),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
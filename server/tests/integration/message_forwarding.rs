@@ -0,0 +1,155 @@
+//! Message Forwarding Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+#[tokio::test]
+async fn forwards_link_back_only_for_viewers_of_the_source() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let perms = GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES;
+    // Alice's private guild, and a shared one Bob is also in
+    let private_guild = create_guild_with_default_role(&app.pool, alice, perms).await;
+    let shared_guild = create_guild_with_default_role(&app.pool, alice, perms).await;
+    add_guild_member(&app.pool, shared_guild, bob).await;
+    let source_channel = create_channel(&app.pool, private_guild, "notes").await;
+    let target_channel = create_channel(&app.pool, shared_guild, "general").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, private_guild).await });
+    guard.add(move |pool| async move { delete_guild(&pool, shared_guild).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    let source_id = insert_message(&app.pool, source_channel, alice, "Release is friday").await;
+    insert_attachment(&app.pool, source_id).await;
+    let forward_path = format!("/api/messages/{source_id}/forward");
+
+    // Bob can't see the source, so can't forward it either
+    let resp = send_json(
+        &app,
+        bob,
+        Method::POST,
+        &forward_path,
+        Some(json!({ "channel_id": target_channel })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        &forward_path,
+        Some(json!({ "channel_id": target_channel, "content": "FYI" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let forwarded = body_to_json(resp).await;
+    assert_eq!(forwarded["channel_id"], target_channel.to_string());
+    assert_eq!(forwarded["content"], "FYI");
+    assert_eq!(forwarded["attachments"].as_array().unwrap().len(), 1);
+    assert_eq!(forwarded["forwarded_from"]["content"], "Release is friday");
+    assert_eq!(
+        forwarded["forwarded_from"]["message_id"],
+        source_id.to_string()
+    );
+
+    // Bob sees the snapshot and attachment but no link to the private channel
+    let history = format!("/api/messages/channel/{target_channel}");
+    let resp = send_json(&app, bob, Method::GET, &history, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let list = body_to_json(resp).await;
+    let seen = &list["items"][0];
+    assert_eq!(seen["forwarded_from"]["content"], "Release is friday");
+    assert_eq!(seen["forwarded_from"]["author_id"], alice.to_string());
+    assert_eq!(
+        seen["forwarded_from"]["message_id"],
+        serde_json::Value::Null
+    );
+    assert_eq!(
+        seen["forwarded_from"]["channel_id"],
+        serde_json::Value::Null
+    );
+    assert_eq!(seen["attachments"].as_array().unwrap().len(), 1);
+
+    // Alice still gets the link
+    let resp = send_json(&app, alice, Method::GET, &history, None).await;
+    let list = body_to_json(resp).await;
+    assert_eq!(
+        list["items"][0]["forwarded_from"]["channel_id"],
+        source_channel.to_string()
+    );
+}
+
+#[tokio::test]
+async fn encrypted_messages_require_client_reencryption() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let (carol, _) = create_test_user(&app.pool).await;
+    let source_dm = create_dm_channel(&app.pool, alice, bob).await;
+    let target_dm = create_dm_channel(&app.pool, alice, carol).await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_dm_channel(&pool, source_dm).await });
+    guard.add(move |pool| async move { delete_dm_channel(&pool, target_dm).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+    guard.delete_user(carol);
+
+    let source_id = insert_encrypted_message(&app.pool, source_dm, bob, "Y2lwaGVydGV4dA==").await;
+    let forward_path = format!("/api/messages/{source_id}/forward");
+
+    // A plaintext forward would leak the ciphertext into a new conversation
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        &forward_path,
+        Some(json!({ "channel_id": target_dm })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        &forward_path,
+        Some(json!({
+            "channel_id": target_dm,
+            "content": "cmUtZW5jcnlwdGVk",
+            "encrypted": true,
+            "nonce": "bm9uY2U="
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let forwarded = body_to_json(resp).await;
+    assert_eq!(forwarded["encrypted"], true);
+    assert_eq!(forwarded["content"], "cmUtZW5jcnlwdGVk");
+    assert_eq!(
+        forwarded["forwarded_from"]["content"],
+        serde_json::Value::Null
+    );
+    assert_eq!(forwarded["forwarded_from"]["author_id"], bob.to_string());
+
+    // Carol isn't in the source DM, so can't forward from it
+    let resp = send_json(
+        &app,
+        carol,
+        Method::POST,
+        &forward_path,
+        Some(json!({
+            "channel_id": target_dm,
+            "content": "eA==",
+            "encrypted": true,
+            "nonce": "bm9uY2U="
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,184 @@
+//! Notification Settings Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+#[tokio::test]
+async fn settings_roundtrip_and_resolve() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let guild_id =
+        create_guild_with_default_role(&app.pool, user_id, GuildPermissions::VIEW_CHANNEL).await;
+    let channel_id = create_channel(&app.pool, guild_id, "noisy").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(user_id);
+
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::PUT,
+        &format!("/api/me/notification-settings/guild/{guild_id}"),
+        Some(json!({ "level": "mentions", "suppress_everyone": true })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["level"], "mentions");
+    assert_eq!(json["suppress_everyone"], true);
+
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::PUT,
+        &format!("/api/me/notification-settings/channel/{channel_id}"),
+        Some(json!({ "muted": true })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::GET,
+        &format!("/api/me/notification-settings/channels/{channel_id}/effective"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let json = body_to_json(resp).await;
+    assert_eq!(json["level"], "mentions");
+    assert_eq!(json["muted"], true);
+    assert_eq!(json["suppress_everyone"], true);
+
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::GET,
+        "/api/me/notification-settings",
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await.as_array().map(Vec::len), Some(2));
+
+    // Resetting the channel lets it inherit again
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::DELETE,
+        &format!("/api/me/notification-settings/channel/{channel_id}"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::GET,
+        &format!("/api/me/notification-settings/channels/{channel_id}/effective"),
+        None,
+    )
+    .await;
+    assert_eq!(body_to_json(resp).await["muted"], false);
+}
+
+#[tokio::test]
+async fn settings_are_validated() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let guild_id =
+        create_guild_with_default_role(&app.pool, user_id, GuildPermissions::VIEW_CHANNEL).await;
+    let channel_id = create_channel(&app.pool, guild_id, "general").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(user_id);
+
+    for body in [
+        json!({ "muted_until": "2999-01-01T00:00:00Z" }),
+        json!({ "muted": true, "muted_until": "2000-01-01T00:00:00Z" }),
+        json!({ "suppress_everyone": true }),
+        json!({ "level": "sometimes" }),
+    ] {
+        let resp = send_json(
+            &app,
+            user_id,
+            Method::PUT,
+            &format!("/api/me/notification-settings/channel/{channel_id}"),
+            Some(body.clone()),
+        )
+        .await;
+        assert!(resp.status().is_client_error(), "body: {body}");
+    }
+}
+
+#[tokio::test]
+async fn settings_require_scope_access() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (outsider_id, _) = create_test_user(&app.pool).await;
+    let guild_id =
+        create_guild_with_default_role(&app.pool, owner_id, GuildPermissions::VIEW_CHANNEL).await;
+    let channel_id = create_channel(&app.pool, guild_id, "private").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(owner_id);
+    guard.delete_user(outsider_id);
+
+    for path in [
+        format!("/api/me/notification-settings/guild/{guild_id}"),
+        format!("/api/me/notification-settings/channel/{channel_id}"),
+        format!("/api/me/notification-settings/thread/{}", Uuid::new_v4()),
+    ] {
+        let resp = send_json(
+            &app,
+            outsider_id,
+            Method::PUT,
+            &path,
+            Some(json!({ "level": "nothing" })),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND, "path: {path}");
+    }
+}
+
+#[tokio::test]
+async fn effective_settings_reject_threads_from_other_channels() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let guild_id =
+        create_guild_with_default_role(&app.pool, user_id, GuildPermissions::VIEW_CHANNEL).await;
+    let channel_id = create_channel(&app.pool, guild_id, "general").await;
+    let other_channel = create_channel(&app.pool, guild_id, "elsewhere").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(user_id);
+
+    let root =
+        vc_server::db::create_message(&app.pool, other_channel, user_id, "root", false, None, None)
+            .await
+            .expect("create message");
+
+    for (channel, status) in [
+        (channel_id, StatusCode::NOT_FOUND),
+        (other_channel, StatusCode::OK),
+    ] {
+        let resp = send_json(
+            &app,
+            user_id,
+            Method::GET,
+            &format!(
+                "/api/me/notification-settings/channels/{channel}/effective?thread_id={}",
+                root.id
+            ),
+            None,
+        )
+        .await;
+        assert_eq!(resp.status(), status);
+    }
+}
@@ -0,0 +1,223 @@
+//! Message Poll Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+fn votes(poll: &serde_json::Value) -> Vec<i64> {
+    poll["options"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|o| o["votes"].as_i64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn poll_messages_collect_one_ballot_per_user() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (member_id, _) = create_test_user(&app.pool).await;
+    let guild_id = create_guild_with_default_role(
+        &app.pool,
+        owner_id,
+        GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES,
+    )
+    .await;
+    add_guild_member(&app.pool, guild_id, member_id).await;
+    let channel_id = create_channel(&app.pool, guild_id, "votes").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(owner_id);
+    guard.delete_user(member_id);
+
+    let path = format!("/api/messages/channel/{channel_id}");
+
+    // Invalid polls are rejected
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::POST,
+        &path,
+        Some(json!({ "content": "", "poll": { "question": "Map?", "options": ["Dust"] } })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::POST,
+        &path,
+        Some(json!({
+            "content": "",
+            "poll": {
+                "question": "Which map tonight?",
+                "options": ["Dust", "Nuke", "Inferno"],
+                "duration_secs": 3600
+            }
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let message = body_to_json(resp).await;
+    let message_id = message["id"].as_str().unwrap().to_string();
+    assert_eq!(message["poll"]["question"], "Which map tonight?");
+    assert_eq!(message["poll"]["closed"], false);
+    assert_eq!(votes(&message["poll"]), vec![0, 0, 0]);
+
+    let votes_path = format!("/api/messages/{message_id}/poll/votes");
+
+    // Single-choice polls take exactly one option
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::PUT,
+        &votes_path,
+        Some(json!({ "options": [0, 1] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::PUT,
+        &votes_path,
+        Some(json!({ "options": [0] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Voting again replaces the earlier ballot
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::PUT,
+        &votes_path,
+        Some(json!({ "options": [2] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let poll = body_to_json(resp).await;
+    assert_eq!(votes(&poll), vec![0, 0, 1]);
+    assert_eq!(poll["total_voters"], 1);
+    assert_eq!(poll["my_votes"], json!([2]));
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::PUT,
+        &votes_path,
+        Some(json!({ "options": [2] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // Message history shows tallies and the viewer's own vote
+    let resp = send_json(&app, member_id, Method::GET, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let list = body_to_json(resp).await;
+    let listed = &list["items"][0]["poll"];
+    assert_eq!(votes(listed), vec![0, 0, 2]);
+    assert_eq!(listed["my_votes"], json!([2]));
+
+    // Voters are visible because the poll isn't anonymous
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::GET,
+        &format!("/api/messages/{message_id}/poll/voters"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let voters = body_to_json(resp).await;
+    assert_eq!(voters[2]["user_ids"].as_array().unwrap().len(), 2);
+
+    // Withdrawing the vote
+    let resp = send_json(&app, member_id, Method::DELETE, &votes_path, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let poll = body_to_json(resp).await;
+    assert_eq!(votes(&poll), vec![0, 0, 1]);
+    assert_eq!(poll["my_votes"], json!([]));
+
+    // Expired polls no longer accept votes
+    sqlx::query("UPDATE message_polls SET expires_at = NOW() WHERE message_id = $1")
+        .bind(Uuid::parse_str(&message_id).unwrap())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::PUT,
+        &votes_path,
+        Some(json!({ "options": [1] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn anonymous_polls_hide_voters() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let guild_id = create_guild_with_default_role(
+        &app.pool,
+        owner_id,
+        GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES,
+    )
+    .await;
+    let channel_id = create_channel(&app.pool, guild_id, "secret-ballot").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(owner_id);
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::POST,
+        &format!("/api/messages/channel/{channel_id}"),
+        Some(json!({
+            "content": "Vote please",
+            "poll": {
+                "question": "What time?",
+                "options": ["18:00", "20:00"],
+                "allow_multiple": true,
+                "anonymous": true
+            }
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let message = body_to_json(resp).await;
+    let message_id = message["id"].as_str().unwrap();
+    assert_eq!(message["poll"]["expires_at"], serde_json::Value::Null);
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::PUT,
+        &format!("/api/messages/{message_id}/poll/votes"),
+        Some(json!({ "options": [1, 0] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let poll = body_to_json(resp).await;
+    assert_eq!(votes(&poll), vec![1, 1]);
+    assert_eq!(poll["my_votes"], json!([0, 1]));
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::GET,
+        &format!("/api/messages/{message_id}/poll/voters"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,222 @@
+//! Push Registration Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::helpers::*;
+
+/// Insert a session for `user_id` and return its raw refresh token.
+async fn create_session(app: &TestApp, user_id: Uuid) -> String {
+    let token = format!("push-test-{}", Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO sessions (user_id, token_hash, expires_at) VALUES ($1, $2, NOW() + INTERVAL '1 day')",
+    )
+    .bind(user_id)
+    .bind(vc_server::auth::hash_token(&token))
+    .execute(&app.pool)
+    .await
+    .expect("Session insert should succeed");
+    token
+}
+
+#[tokio::test]
+async fn registration_is_scoped_to_the_session() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(user_id);
+    let phone = create_session(&app, user_id).await;
+    let laptop = create_session(&app, user_id).await;
+
+    let register = |token: &'static str| {
+        json!({
+            "provider": "unified_push",
+            "endpoint": format!("https://ntfy.example.com/up{token}"),
+            "device_name": "Pixel",
+        })
+    };
+
+    // Without a refresh token the session can't be identified
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::PUT,
+        "/api/me/push/registration",
+        Some(register("a")),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json_in_session(
+        &app,
+        user_id,
+        &phone,
+        Method::PUT,
+        "/api/me/push/registration",
+        Some(register("a")),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let first = body_to_json(resp).await;
+    assert_eq!(first["provider"], "unified_push");
+    assert_eq!(first["device_name"], "Pixel");
+
+    // Re-registering from the same session replaces the endpoint
+    let resp = send_json_in_session(
+        &app,
+        user_id,
+        &phone,
+        Method::PUT,
+        "/api/me/push/registration",
+        Some(register("b")),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let replaced = body_to_json(resp).await;
+    assert_eq!(replaced["id"], first["id"]);
+    assert_eq!(replaced["endpoint"], "https://ntfy.example.com/upb");
+
+    let resp = send_json_in_session(
+        &app,
+        user_id,
+        &laptop,
+        Method::PUT,
+        "/api/me/push/registration",
+        Some(register("c")),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::GET,
+        "/api/me/push/registrations",
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await.as_array().unwrap().len(), 2);
+
+    // Unregistering only removes the calling session's device
+    let resp = send_json_in_session(
+        &app,
+        user_id,
+        &laptop,
+        Method::DELETE,
+        "/api/me/push/registration",
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let resp = send_json_in_session(
+        &app,
+        user_id,
+        &laptop,
+        Method::DELETE,
+        "/api/me/push/registration",
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // Another user can't remove someone else's registration
+    let (other_id, _) = create_test_user(&app.pool).await;
+    guard.delete_user(other_id);
+    let path = format!(
+        "/api/me/push/registrations/{}",
+        first["id"].as_str().unwrap()
+    );
+    let resp = send_json(&app, other_id, Method::DELETE, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = send_json(&app, user_id, Method::DELETE, &path, None).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    // Logging out a session drops its registration with it
+    send_json_in_session(
+        &app,
+        user_id,
+        &laptop,
+        Method::PUT,
+        "/api/me/push/registration",
+        Some(register("d")),
+    )
+    .await;
+    sqlx::query("DELETE FROM sessions WHERE token_hash = $1")
+        .bind(vc_server::auth::hash_token(&laptop))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let resp = send_json(
+        &app,
+        user_id,
+        Method::GET,
+        "/api/me/push/registrations",
+        None,
+    )
+    .await;
+    assert_eq!(body_to_json(resp).await.as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn registration_rejects_unsafe_or_incomplete_endpoints() {
+    let app = TestApp::new().await;
+    let (user_id, _) = create_test_user(&app.pool).await;
+    let mut guard = app.cleanup_guard();
+    guard.delete_user(user_id);
+    let session = create_session(&app, user_id).await;
+
+    for endpoint in [
+        "http://ntfy.example.com/up",
+        "https://localhost/up",
+        "https://10.0.0.5/up",
+        "not a url",
+    ] {
+        let resp = send_json_in_session(
+            &app,
+            user_id,
+            &session,
+            Method::PUT,
+            "/api/me/push/registration",
+            Some(json!({ "provider": "unified_push", "endpoint": endpoint })),
+        )
+        .await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST, "{endpoint}");
+    }
+
+    // The test config has no VAPID key, so Web Push is unavailable
+    let resp = send_json_in_session(
+        &app,
+        user_id,
+        &session,
+        Method::PUT,
+        "/api/me/push/registration",
+        Some(json!({
+            "provider": "web_push",
+            "endpoint": "https://fcm.googleapis.com/fcm/send/abc",
+            "keys": { "p256dh": "x", "auth": "y" },
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let resp = send_json(&app, user_id, Method::GET, "/api/me/push/vapid-key", None).await;
+    assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // Malformed keys are rejected even for UnifiedPush
+    let resp = send_json_in_session(
+        &app,
+        user_id,
+        &session,
+        Method::PUT,
+        "/api/me/push/registration",
+        Some(json!({
+            "provider": "unified_push",
+            "endpoint": "https://ntfy.example.com/up",
+            "keys": { "p256dh": "not-a-key", "auth": "BTBZMqHH6r4Tts7J_aSIgg" },
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+}
@@ -78,6 +78,50 @@ async fn create_test_limiter(redis: fred::clients::Client) -> RateLimiter {
                 requests: 2,
                 window_secs: 60,
             },
+            poll_vote: LimitConfig {
+                requests: 20,
+                window_secs: 60,
+            },
+            bulk_delete: LimitConfig {
+                requests: 5,
+                window_secs: 60,
+            },
+            interaction: LimitConfig {
+                requests: 30,
+                window_secs: 60,
+            },
+            autocomplete: LimitConfig {
+                requests: 60,
+                window_secs: 60,
+            },
+            bot_auth: LimitConfig {
+                requests: 600,
+                window_secs: 60,
+            },
+            bot_message_read: LimitConfig {
+                requests: 120,
+                window_secs: 60,
+            },
+            bot_message_write: LimitConfig {
+                requests: 30,
+                window_secs: 60,
+            },
+            bot_reaction: LimitConfig {
+                requests: 60,
+                window_secs: 60,
+            },
+            bot_member_read: LimitConfig {
+                requests: 30,
+                window_secs: 60,
+            },
+            bot_role_manage: LimitConfig {
+                requests: 20,
+                window_secs: 60,
+            },
+            bot_upload: LimitConfig {
+                requests: 10,
+                window_secs: 60,
+            },
             failed_auth: FailedAuthConfig {
                 max_failures: 3,
                 block_duration_secs: 60,
@@ -0,0 +1,166 @@
+//! Slow Mode Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+#[tokio::test]
+async fn slow_mode_throttles_members_but_not_moderators() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (member_id, _) = create_test_user(&app.pool).await;
+    let guild_id = create_guild_with_default_role(
+        &app.pool,
+        owner_id,
+        GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES,
+    )
+    .await;
+    add_guild_member(&app.pool, guild_id, member_id).await;
+    let channel_id = create_channel(&app.pool, guild_id, "busy").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(owner_id);
+    guard.delete_user(member_id);
+
+    let channel_path = format!("/api/channels/{channel_id}");
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::PATCH,
+        &channel_path,
+        Some(json!({ "slowmode_seconds": 21601 })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::PATCH,
+        &channel_path,
+        Some(json!({ "slowmode_seconds": 60 })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_to_json(resp).await["slowmode_seconds"], 60);
+
+    let messages_path = format!("/api/messages/channel/{channel_id}");
+    let post = |user_id, content: &str| {
+        send_json(
+            &app,
+            user_id,
+            Method::POST,
+            &messages_path,
+            Some(json!({ "content": content })),
+        )
+    };
+
+    let resp = post(member_id, "first").await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let parent_id = body_to_json(resp).await["id"].as_str().unwrap().to_string();
+
+    let resp = post(member_id, "second").await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("retry-after"));
+    let error = body_to_json(resp).await;
+    assert_eq!(error["error"], "SLOW_MODE");
+    let retry_after = error["retry_after"].as_u64().unwrap();
+    assert!((1..=60).contains(&retry_after));
+
+    // MANAGE_MESSAGES bypasses the cooldown
+    assert_eq!(post(owner_id, "one").await.status(), StatusCode::CREATED);
+    assert_eq!(post(owner_id, "two").await.status(), StatusCode::CREATED);
+
+    // Replies share the channel cooldown until the thread overrides it
+    let reply = json!({ "content": "reply", "parent_id": parent_id });
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::POST,
+        &messages_path,
+        Some(reply.clone()),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+
+    let thread_path = format!("/api/messages/{parent_id}/thread/slowmode");
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::PUT,
+        &thread_path,
+        Some(json!({ "slowmode_seconds": 0 })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    let resp = send_json(
+        &app,
+        owner_id,
+        Method::PUT,
+        &thread_path,
+        Some(json!({ "slowmode_seconds": 0 })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = send_json(&app, member_id, Method::POST, &messages_path, Some(reply)).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn failed_sends_do_not_start_the_cooldown() {
+    let app = TestApp::new().await;
+    let (owner_id, _) = create_test_user(&app.pool).await;
+    let (member_id, _) = create_test_user(&app.pool).await;
+    let guild_id = create_guild_with_default_role(
+        &app.pool,
+        owner_id,
+        GuildPermissions::VIEW_CHANNEL | GuildPermissions::SEND_MESSAGES,
+    )
+    .await;
+    add_guild_member(&app.pool, guild_id, member_id).await;
+    let channel_id = create_channel(&app.pool, guild_id, "busy").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, guild_id).await });
+    guard.delete_user(owner_id);
+    guard.delete_user(member_id);
+
+    sqlx::query("UPDATE channels SET slowmode_seconds = 60 WHERE id = $1")
+        .bind(channel_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    // Rejected after the cooldown check: a type the server doesn't accept
+    // (or no storage configured at all)
+    let boundary = "----SlowmodeBoundary";
+    let body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"tool.exe\"\r\nContent-Type: application/x-msdownload\r\n\r\nMZ\r\n--{boundary}--\r\n"
+    );
+    let token = generate_access_token(&app.config, member_id);
+    let req = TestApp::request(
+        Method::POST,
+        &format!("/api/messages/channel/{channel_id}/upload"),
+    )
+    .header("Authorization", format!("Bearer {token}"))
+    .header(
+        "Content-Type",
+        format!("multipart/form-data; boundary={boundary}"),
+    )
+    .body(axum::body::Body::from(body))
+    .unwrap();
+    assert_ne!(app.oneshot(req).await.status(), StatusCode::CREATED);
+
+    let resp = send_json(
+        &app,
+        member_id,
+        Method::POST,
+        &format!("/api/messages/channel/{channel_id}"),
+        Some(json!({ "content": "still allowed" })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+}
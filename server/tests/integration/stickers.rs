@@ -0,0 +1,112 @@
+//! Guild Sticker Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+use vc_server::permissions::GuildPermissions;
+
+use super::helpers::*;
+
+async fn insert_sticker(pool: &sqlx::PgPool, guild_id: Uuid, uploader: Uuid) -> Uuid {
+    let sticker_id = Uuid::now_v7();
+    sqlx::query(
+        r"
+        INSERT INTO guild_stickers
+            (id, guild_id, name, tags, format, animated, s3_key, preview_key, width, height, uploaded_by)
+        VALUES ($1, $2, 'wave', '{hello}', 'apng', TRUE, $3, $4, 320, 320, $5)
+        ",
+    )
+    .bind(sticker_id)
+    .bind(guild_id)
+    .bind(format!("stickers/{guild_id}/{sticker_id}.png"))
+    .bind(format!("stickers/{guild_id}/{sticker_id}_preview.webp"))
+    .bind(uploader)
+    .execute(pool)
+    .await
+    .unwrap();
+    sticker_id
+}
+
+#[tokio::test]
+async fn stickers_travel_with_their_guild_members() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let perms = GuildPermissions::VIEW_CHANNEL
+        | GuildPermissions::SEND_MESSAGES
+        | GuildPermissions::USE_EMOJI;
+    // Alice's sticker guild, and a shared guild Bob is also in
+    let sticker_guild = create_guild_with_default_role(&app.pool, alice, perms).await;
+    let shared_guild = create_guild_with_default_role(&app.pool, alice, perms).await;
+    add_guild_member(&app.pool, shared_guild, bob).await;
+    let channel_id = create_channel(&app.pool, shared_guild, "general").await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_guild(&pool, sticker_guild).await });
+    guard.add(move |pool| async move { delete_guild(&pool, shared_guild).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+
+    let sticker_id = insert_sticker(&app.pool, sticker_guild, alice).await;
+
+    // Listed per guild for members, and across guilds for the user
+    let resp = send_json(
+        &app,
+        alice,
+        Method::GET,
+        &format!("/api/guilds/{sticker_guild}/stickers"),
+        None,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let listed = body_to_json(resp).await;
+    assert_eq!(listed[0]["name"], "wave");
+    assert_eq!(listed[0]["tags"], json!(["hello"]));
+    assert_eq!(listed[0]["animated"], true);
+
+    let resp = send_json(&app, bob, Method::GET, "/api/me/stickers", None).await;
+    assert_eq!(body_to_json(resp).await, json!([]));
+
+    // Alice can use it in a guild that doesn't own it, without any text
+    let messages_path = format!("/api/messages/channel/{channel_id}");
+    let resp = send_json(
+        &app,
+        alice,
+        Method::POST,
+        &messages_path,
+        Some(json!({ "content": "", "sticker_id": sticker_id })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let message = body_to_json(resp).await;
+    let sticker = &message["components"][0]["components"][0];
+    assert_eq!(sticker["type"], "sticker");
+    assert_eq!(sticker["sticker_id"], sticker_id.to_string());
+    assert_eq!(sticker["format"], "apng");
+    assert_eq!(message["attachments"].as_array().unwrap().len(), 0);
+
+    // Bob isn't in the sticker's guild
+    let resp = send_json(
+        &app,
+        bob,
+        Method::POST,
+        &messages_path,
+        Some(json!({ "content": "", "sticker_id": sticker_id })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+    // Only the uploader or MANAGE_GUILD can edit it
+    let sticker_path = format!("/api/guilds/{sticker_guild}/stickers/{sticker_id}");
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PATCH,
+        &sticker_path,
+        Some(json!({ "name": "big_wave", "tags": ["Hi", "wave"] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let updated = body_to_json(resp).await;
+    assert_eq!(updated["name"], "big_wave");
+    assert_eq!(updated["tags"], json!(["hi", "wave"]));
+}
@@ -0,0 +1,133 @@
+//! To-Device Messaging Integration Tests
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use uuid::Uuid;
+
+use super::helpers::*;
+
+async fn insert_device(pool: &sqlx::PgPool, user_id: Uuid) -> Uuid {
+    sqlx::query_scalar(
+        r"
+        INSERT INTO user_devices (user_id, identity_key_ed25519, identity_key_curve25519)
+        VALUES ($1, 'ed25519', $2)
+        RETURNING id
+        ",
+    )
+    .bind(user_id)
+    .bind(Uuid::now_v7().simple().to_string())
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn to_device_messages_wait_for_their_device() {
+    let app = TestApp::new().await;
+    let (alice, _) = create_test_user(&app.pool).await;
+    let (bob, _) = create_test_user(&app.pool).await;
+    let (carol, _) = create_test_user(&app.pool).await;
+    let dm = create_dm_channel(&app.pool, alice, bob).await;
+    let mut guard = app.cleanup_guard();
+    guard.add(move |pool| async move { delete_dm_channel(&pool, dm).await });
+    guard.delete_user(alice);
+    guard.delete_user(bob);
+    guard.delete_user(carol);
+
+    let alice_device = insert_device(&app.pool, alice).await;
+    let bob_laptop = insert_device(&app.pool, bob).await;
+    let bob_phone = insert_device(&app.pool, bob).await;
+    let carol_device = insert_device(&app.pool, carol).await;
+
+    // Users who share no guild or DM can't reach each other's devices
+    let resp = send_json(
+        &app,
+        carol,
+        Method::PUT,
+        "/api/keys/send-to-device",
+        Some(json!({
+            "sender_device_id": carol_device,
+            "event_type": "m.room_key",
+            "messages": { bob.to_string(): { bob_laptop.to_string(): "AwogZ" } },
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let sent = body_to_json(resp).await;
+    assert_eq!(sent["queued"], 0);
+    assert_eq!(sent["skipped"], 1);
+
+    // Senders can only send from their own devices
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PUT,
+        "/api/keys/send-to-device",
+        Some(json!({
+            "sender_device_id": bob_phone,
+            "event_type": "m.room_key",
+            "messages": { bob.to_string(): { bob_laptop.to_string(): "AwogX" } },
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // Devices that don't belong to the addressed user are skipped
+    let resp = send_json(
+        &app,
+        alice,
+        Method::PUT,
+        "/api/keys/send-to-device",
+        Some(json!({
+            "sender_device_id": alice_device,
+            "event_type": "m.room_key",
+            "messages": {
+                bob.to_string(): { bob_laptop.to_string(): "AwogX" },
+                alice.to_string(): { bob_phone.to_string(): "AwogY" },
+            },
+        })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let sent = body_to_json(resp).await;
+    assert_eq!(sent["queued"], 1);
+    assert_eq!(sent["skipped"], 1);
+
+    // Only the addressed device sees the message
+    let resp = send_json(
+        &app,
+        bob,
+        Method::GET,
+        &format!("/api/keys/to-device/{bob_phone}"),
+        None,
+    )
+    .await;
+    assert_eq!(body_to_json(resp).await, json!([]));
+
+    let laptop_inbox = format!("/api/keys/to-device/{bob_laptop}");
+    let resp = send_json(&app, alice, Method::GET, &laptop_inbox, None).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = send_json(&app, bob, Method::GET, &laptop_inbox, None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let pending = body_to_json(resp).await;
+    assert_eq!(pending.as_array().unwrap().len(), 1);
+    assert_eq!(pending[0]["sender_user_id"], alice.to_string());
+    assert_eq!(pending[0]["sender_device_id"], alice_device.to_string());
+    assert_eq!(pending[0]["event_type"], "m.room_key");
+    assert_eq!(pending[0]["ciphertext"], "AwogX");
+
+    // Acknowledging removes it from the inbox
+    let resp = send_json(
+        &app,
+        bob,
+        Method::POST,
+        &format!("{laptop_inbox}/ack"),
+        Some(json!({ "message_ids": [pending[0]["id"]] })),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let resp = send_json(&app, bob, Method::GET, &laptop_inbox, None).await;
+    assert_eq!(body_to_json(resp).await, json!([]));
+}
@@ -0,0 +1,157 @@
+//! Encrypted Attachments
+//!
+//! Files shared in end-to-end encrypted conversations are encrypted on the
+//! client with a fresh AES-256-GCM key before upload. The server only ever
+//! sees the ciphertext; the key, nonce and ciphertext hash travel inside the
+//! encrypted message body as an [`EncryptedFile`], so only the people who can
+//! read the message can open the file.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use vodozemac::{base64_decode, base64_encode};
+use zeroize::Zeroizing;
+
+use crate::{CryptoError, Result};
+
+/// Algorithm identifier of encrypted attachments.
+pub const ATTACHMENT_ALGORITHM: &str = "kaiku.attachment.v1";
+
+/// Key material needed to download and decrypt one attachment.
+///
+/// Embedded in the encrypted message body; never sent to the server on its
+/// own.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedFile {
+    /// Algorithm identifier, [`ATTACHMENT_ALGORITHM`].
+    pub v: String,
+    /// AES-256-GCM key (base64).
+    pub key: String,
+    /// AES-GCM nonce (base64).
+    pub iv: String,
+    /// SHA-256 of the ciphertext (base64), checked before decrypting.
+    pub sha256: String,
+}
+
+/// Encrypt a file for upload under a freshly generated key.
+///
+/// Returns the ciphertext to upload and the key material to put in the
+/// message body.
+///
+/// # Panics
+///
+/// Panics if the system CSPRNG fails to generate random bytes.
+#[must_use]
+pub fn encrypt_attachment(data: &[u8]) -> (Vec<u8>, EncryptedFile) {
+    let mut key = Zeroizing::new([0u8; 32]);
+    getrandom::getrandom(key.as_mut()).expect("Failed to generate key");
+    let mut nonce_bytes = [0u8; 12];
+    getrandom::getrandom(&mut nonce_bytes).expect("Failed to generate nonce");
+
+    let ciphertext = Aes256Gcm::new_from_slice(key.as_ref())
+        .expect("Invalid key length")
+        .encrypt(Nonce::from_slice(&nonce_bytes), data)
+        .expect("Encryption failed");
+
+    let info = EncryptedFile {
+        v: ATTACHMENT_ALGORITHM.to_string(),
+        key: base64_encode(key.as_ref()),
+        iv: base64_encode(nonce_bytes),
+        sha256: base64_encode(Sha256::digest(&ciphertext)),
+    };
+    (ciphertext, info)
+}
+
+/// Verify and decrypt a downloaded attachment.
+///
+/// # Errors
+///
+/// Returns `CryptoError::InvalidKey` if the key material is malformed or uses
+/// an unknown algorithm, and `CryptoError::DecryptionFailed` if the
+/// ciphertext doesn't match its hash or fails authentication.
+pub fn decrypt_attachment(ciphertext: &[u8], info: &EncryptedFile) -> Result<Zeroizing<Vec<u8>>> {
+    if info.v != ATTACHMENT_ALGORITHM {
+        return Err(CryptoError::InvalidKey(format!(
+            "Unsupported attachment algorithm: {}",
+            info.v
+        )));
+    }
+    let key = Zeroizing::new(
+        base64_decode(&info.key).map_err(|_| CryptoError::InvalidKey("Malformed key".into()))?,
+    );
+    let nonce =
+        base64_decode(&info.iv).map_err(|_| CryptoError::InvalidKey("Malformed iv".into()))?;
+    if nonce.len() != 12 {
+        return Err(CryptoError::InvalidKey("Malformed iv".into()));
+    }
+    let cipher = Aes256Gcm::new_from_slice(&key)
+        .map_err(|_| CryptoError::InvalidKey("Invalid attachment key".into()))?;
+
+    if base64_encode(Sha256::digest(ciphertext)) != info.sha256 {
+        return Err(CryptoError::DecryptionFailed(
+            "Attachment hash mismatch".into(),
+        ));
+    }
+
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext)
+        .map(Zeroizing::new)
+        .map_err(|_| CryptoError::DecryptionFailed("Attachment decryption failed".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attachment_roundtrip() {
+        let data = b"\x89PNG\r\n\x1a\n not really an image";
+        let (ciphertext, info) = encrypt_attachment(data);
+
+        assert_ne!(&ciphertext[..data.len()], data.as_slice());
+        assert_eq!(info.v, ATTACHMENT_ALGORITHM);
+        let plaintext = decrypt_attachment(&ciphertext, &info).unwrap();
+        assert_eq!(plaintext.as_slice(), data.as_slice());
+    }
+
+    #[test]
+    fn test_attachment_keys_are_unique() {
+        let (first, first_info) = encrypt_attachment(b"same file");
+        let (second, second_info) = encrypt_attachment(b"same file");
+
+        assert_ne!(first, second);
+        assert_ne!(first_info.key, second_info.key);
+        assert!(decrypt_attachment(&first, &second_info).is_err());
+    }
+
+    #[test]
+    fn test_tampered_attachment_fails() {
+        let (mut ciphertext, info) = encrypt_attachment(b"secret");
+        ciphertext[0] ^= 1;
+
+        assert!(matches!(
+            decrypt_attachment(&ciphertext, &info),
+            Err(CryptoError::DecryptionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_attachment_info_serialization() {
+        let (ciphertext, info) = encrypt_attachment(b"file");
+        let json = serde_json::to_string(&info).unwrap();
+        let parsed: EncryptedFile = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, info);
+        assert!(decrypt_attachment(&ciphertext, &parsed).is_ok());
+
+        let unknown = EncryptedFile {
+            v: "unknown".into(),
+            ..parsed
+        };
+        assert!(matches!(
+            decrypt_attachment(&ciphertext, &unknown),
+            Err(CryptoError::InvalidKey(_))
+        ));
+    }
+}
@@ -0,0 +1,355 @@
+//! Cross-Signing Identities
+//!
+//! Each user has three Ed25519 keys on top of their per-device identity keys:
+//!
+//! - **Master key**: the user's long-term identity. It only signs the two keys below.
+//! - **Self-signing key**: signs the user's own devices, so other users trust a
+//!   new device once it is cross-signed instead of verifying it separately.
+//! - **User-signing key**: signs other users' master keys after verifying them.
+//!
+//! Signatures cover a canonical string of the signed data, built by the
+//! `canonical_*` functions below, so the server and clients agree on what was
+//! signed without a shared JSON canonicalisation.
+
+use serde::{Deserialize, Serialize};
+use vodozemac::{Ed25519PublicKey, Ed25519SecretKey, Ed25519Signature};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::{CryptoError, Result};
+
+/// Which cross-signing key a sub-key signature is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossSigningUsage {
+    /// Signs the owner's devices.
+    SelfSigning,
+    /// Signs other users' master keys.
+    UserSigning,
+}
+
+impl CrossSigningUsage {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::SelfSigning => "self_signing",
+            Self::UserSigning => "user_signing",
+        }
+    }
+}
+
+/// Canonical data signed by the master key for a sub-key.
+#[must_use]
+pub fn canonical_sub_key(user_id: &str, usage: CrossSigningUsage, public_key: &str) -> String {
+    format!(
+        "kaiku.cross_signing.v1|{}|{user_id}|{public_key}",
+        usage.as_str()
+    )
+}
+
+/// Canonical data signed by a self-signing key for one of the owner's devices.
+#[must_use]
+pub fn canonical_device(
+    user_id: &str,
+    device_id: &str,
+    identity_key_ed25519: &str,
+    identity_key_curve25519: &str,
+) -> String {
+    format!(
+        "kaiku.device.v1|{user_id}|{device_id}|{identity_key_ed25519}|{identity_key_curve25519}"
+    )
+}
+
+/// Canonical data signed by a user-signing key for another user's master key.
+#[must_use]
+pub fn canonical_user(user_id: &str, master_key: &str) -> String {
+    format!("kaiku.user.v1|{user_id}|{master_key}")
+}
+
+/// Verify a base64 Ed25519 signature over `message`.
+///
+/// # Errors
+///
+/// Returns [`CryptoError::InvalidKey`] if the key can't be decoded, or
+/// [`CryptoError::SignatureInvalid`] if the signature is malformed or doesn't match.
+pub fn verify_signature(public_key: &str, message: &str, signature: &str) -> Result<()> {
+    let key = Ed25519PublicKey::from_base64(public_key)
+        .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+    let signature =
+        Ed25519Signature::from_base64(signature).map_err(|_| CryptoError::SignatureInvalid)?;
+    key.verify(message.as_bytes(), &signature)
+        .map_err(|_| CryptoError::SignatureInvalid)
+}
+
+/// Public half of a user's cross-signing identity, as published to the server.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CrossSigningPublicKeys {
+    /// Master key (base64).
+    pub master_key: String,
+    /// Self-signing key (base64).
+    pub self_signing_key: String,
+    /// User-signing key (base64).
+    pub user_signing_key: String,
+    /// Master key signature over the self-signing key.
+    pub self_signing_signature: String,
+    /// Master key signature over the user-signing key.
+    pub user_signing_signature: String,
+}
+
+impl CrossSigningPublicKeys {
+    /// Check that both sub-keys are signed by the master key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any key is malformed or a signature doesn't match.
+    pub fn verify(&self, user_id: &str) -> Result<()> {
+        verify_signature(
+            &self.master_key,
+            &canonical_sub_key(
+                user_id,
+                CrossSigningUsage::SelfSigning,
+                &self.self_signing_key,
+            ),
+            &self.self_signing_signature,
+        )?;
+        verify_signature(
+            &self.master_key,
+            &canonical_sub_key(
+                user_id,
+                CrossSigningUsage::UserSigning,
+                &self.user_signing_key,
+            ),
+            &self.user_signing_signature,
+        )
+    }
+
+    /// Check a device's self-signature against these keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CryptoError::SignatureInvalid`] if the device isn't signed by
+    /// this identity's self-signing key.
+    pub fn verify_device(
+        &self,
+        user_id: &str,
+        device_id: &str,
+        identity_key_ed25519: &str,
+        identity_key_curve25519: &str,
+        signature: &str,
+    ) -> Result<()> {
+        verify_signature(
+            &self.self_signing_key,
+            &canonical_device(
+                user_id,
+                device_id,
+                identity_key_ed25519,
+                identity_key_curve25519,
+            ),
+            signature,
+        )
+    }
+
+    /// Check that this identity's user-signing key signed another user's master key.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CryptoError::SignatureInvalid`] if the signature doesn't match.
+    pub fn verify_user(&self, user_id: &str, master_key: &str, signature: &str) -> Result<()> {
+        verify_signature(
+            &self.user_signing_key,
+            &canonical_user(user_id, master_key),
+            signature,
+        )
+    }
+}
+
+/// Private cross-signing keys, exported for encrypted local storage or backup.
+#[derive(Clone, Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+pub struct CrossSigningSecrets {
+    /// Master secret key (base64).
+    pub master: String,
+    /// Self-signing secret key (base64).
+    pub self_signing: String,
+    /// User-signing secret key (base64).
+    pub user_signing: String,
+}
+
+/// A user's cross-signing identity with its private keys.
+pub struct CrossSigningIdentity {
+    user_id: String,
+    master: Ed25519SecretKey,
+    self_signing: Ed25519SecretKey,
+    user_signing: Ed25519SecretKey,
+}
+
+impl CrossSigningIdentity {
+    /// Generate a fresh identity for `user_id`.
+    #[must_use]
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            master: Ed25519SecretKey::new(),
+            self_signing: Ed25519SecretKey::new(),
+            user_signing: Ed25519SecretKey::new(),
+        }
+    }
+
+    /// Restore an identity from exported secrets.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CryptoError::InvalidKey`] if any secret can't be decoded.
+    pub fn from_secrets(user_id: &str, secrets: &CrossSigningSecrets) -> Result<Self> {
+        let decode = |secret: &str| {
+            Ed25519SecretKey::from_base64(secret)
+                .map_err(|e| CryptoError::InvalidKey(e.to_string()))
+        };
+        Ok(Self {
+            user_id: user_id.to_string(),
+            master: decode(&secrets.master)?,
+            self_signing: decode(&secrets.self_signing)?,
+            user_signing: decode(&secrets.user_signing)?,
+        })
+    }
+
+    /// Export the private keys.
+    #[must_use]
+    pub fn secrets(&self) -> CrossSigningSecrets {
+        CrossSigningSecrets {
+            master: self.master.to_base64(),
+            self_signing: self.self_signing.to_base64(),
+            user_signing: self.user_signing.to_base64(),
+        }
+    }
+
+    /// The master public key (base64).
+    #[must_use]
+    pub fn master_key(&self) -> String {
+        self.master.public_key().to_base64()
+    }
+
+    /// Public keys with the master key's sub-key signatures, ready to publish.
+    #[must_use]
+    pub fn public_keys(&self) -> CrossSigningPublicKeys {
+        let self_signing_key = self.self_signing.public_key().to_base64();
+        let user_signing_key = self.user_signing.public_key().to_base64();
+        let sign_sub_key = |usage, key: &str| {
+            self.master
+                .sign(canonical_sub_key(&self.user_id, usage, key).as_bytes())
+                .to_base64()
+        };
+
+        CrossSigningPublicKeys {
+            master_key: self.master_key(),
+            self_signing_signature: sign_sub_key(CrossSigningUsage::SelfSigning, &self_signing_key),
+            user_signing_signature: sign_sub_key(CrossSigningUsage::UserSigning, &user_signing_key),
+            self_signing_key,
+            user_signing_key,
+        }
+    }
+
+    /// Sign one of our own devices with the self-signing key.
+    #[must_use]
+    pub fn sign_device(
+        &self,
+        device_id: &str,
+        identity_key_ed25519: &str,
+        identity_key_curve25519: &str,
+    ) -> String {
+        let message = canonical_device(
+            &self.user_id,
+            device_id,
+            identity_key_ed25519,
+            identity_key_curve25519,
+        );
+        self.self_signing.sign(message.as_bytes()).to_base64()
+    }
+
+    /// Sign another user's master key with the user-signing key.
+    #[must_use]
+    pub fn sign_user(&self, user_id: &str, master_key: &str) -> String {
+        self.user_signing
+            .sign(canonical_user(user_id, master_key).as_bytes())
+            .to_base64()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_public_keys_verify() {
+        let identity = CrossSigningIdentity::new("alice");
+        let keys = identity.public_keys();
+
+        assert!(keys.verify("alice").is_ok());
+        // Signatures are bound to the owner
+        assert!(matches!(
+            keys.verify("mallory"),
+            Err(CryptoError::SignatureInvalid)
+        ));
+    }
+
+    #[test]
+    fn test_swapped_sub_key_is_rejected() {
+        let keys = CrossSigningIdentity::new("alice").public_keys();
+        let forged = CrossSigningPublicKeys {
+            self_signing_key: CrossSigningIdentity::new("alice")
+                .public_keys()
+                .self_signing_key,
+            ..keys
+        };
+        assert!(forged.verify("alice").is_err());
+    }
+
+    #[test]
+    fn test_device_signature() {
+        let identity = CrossSigningIdentity::new("alice");
+        let keys = identity.public_keys();
+        let signature = identity.sign_device("laptop", "ed-key", "curve-key");
+
+        assert!(keys
+            .verify_device("alice", "laptop", "ed-key", "curve-key", &signature)
+            .is_ok());
+        assert!(keys
+            .verify_device("alice", "laptop", "ed-key", "other-curve", &signature)
+            .is_err());
+        assert!(keys
+            .verify_device("alice", "laptop", "ed-key", "curve-key", "not-a-signature")
+            .is_err());
+    }
+
+    #[test]
+    fn test_user_signature() {
+        let alice = CrossSigningIdentity::new("alice");
+        let bob = CrossSigningIdentity::new("bob");
+        let signature = alice.sign_user("bob", &bob.master_key());
+
+        let keys = alice.public_keys();
+        assert!(keys
+            .verify_user("bob", &bob.master_key(), &signature)
+            .is_ok());
+        assert!(keys
+            .verify_user("bob", &alice.master_key(), &signature)
+            .is_err());
+    }
+
+    #[test]
+    fn test_secrets_roundtrip() {
+        let identity = CrossSigningIdentity::new("alice");
+        let restored = CrossSigningIdentity::from_secrets("alice", &identity.secrets()).unwrap();
+
+        assert_eq!(restored.public_keys().master_key, identity.master_key());
+        assert_eq!(
+            restored.public_keys().self_signing_key,
+            identity.public_keys().self_signing_key
+        );
+        assert!(CrossSigningIdentity::from_secrets(
+            "alice",
+            &CrossSigningSecrets {
+                master: "short".to_string(),
+                self_signing: String::new(),
+                user_signing: String::new(),
+            }
+        )
+        .is_err());
+    }
+}
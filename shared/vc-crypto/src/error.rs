@@ -21,6 +21,10 @@ pub enum CryptoError {
     #[error("Signature verification failed")]
     SignatureInvalid,
 
+    /// Interactive verification used out of order
+    #[error("Verification error: {0}")]
+    Verification(String),
+
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(String),
@@ -4,15 +4,26 @@
 //!
 //! - **Olm**: Double Ratchet for 1:1 encrypted sessions (DMs)
 //! - **Megolm**: Efficient group encryption for channels
+//! - **Attachments**: AES-256-GCM file encryption with keys carried in encrypted messages
+//! - **Cross-signing**: Master/self-signing/user-signing keys vouching for devices and users
+//! - **Verification**: Interactive SAS (emoji/decimal) and QR device verification
+//! - **Room key requests**: Signed requests for missing Megolm sessions and forwarded answers
 
+pub mod attachment;
+pub mod cross_signing;
 pub mod error;
 #[cfg(feature = "megolm")]
 pub mod megolm;
 pub mod olm;
 pub mod recovery;
+pub mod room_key;
+pub mod verification;
 
+pub use attachment::{decrypt_attachment, encrypt_attachment, EncryptedFile};
+pub use cross_signing::{CrossSigningIdentity, CrossSigningPublicKeys};
 pub use error::{CryptoError, Result};
-pub use recovery::{EncryptedBackup, RecoveryKey};
+pub use recovery::{EncryptedBackup, EncryptedRoomKey, KeyBackupAuthData, RecoveryKey};
+pub use room_key::{ForwardedRoomKey, RoomKeyRequest};
 
 /// Re-export vodozemac types that are commonly needed.
 pub mod types {
@@ -111,6 +111,26 @@ impl MegolmInboundSession {
         self.session.first_known_index()
     }
 
+    /// Export the session at its first known index (base64), for key backup.
+    #[must_use]
+    pub fn export(&self) -> String {
+        self.session.export_at_first_known_index().to_base64()
+    }
+
+    /// Import a session exported with [`Self::export`].
+    pub fn import(exported_b64: &str) -> Result<Self> {
+        let exported =
+            vodozemac::megolm::ExportedSessionKey::from_base64(exported_b64).map_err(|e| {
+                CryptoError::InvalidKey(format!("Invalid exported megolm session: {e}"))
+            })?;
+
+        let session = vodozemac::megolm::InboundGroupSession::import(
+            &exported,
+            vodozemac::megolm::SessionConfig::version_1(),
+        );
+        Ok(Self { session })
+    }
+
     /// Decrypt a base64-encoded ciphertext message.
     pub fn decrypt(&mut self, ciphertext_b64: &str) -> Result<String> {
         let message = vodozemac::megolm::MegolmMessage::from_base64(ciphertext_b64)
@@ -212,4 +232,19 @@ mod tests {
             MegolmInboundSession::deserialize(&serialized_inbound, &encryption_key).unwrap();
         assert_eq!(deserialized_inbound.session_id(), inbound.session_id());
     }
+
+    #[test]
+    fn test_megolm_export_import() {
+        let mut outbound = MegolmOutboundSession::new();
+        let inbound = MegolmInboundSession::new(&outbound.session_key()).unwrap();
+        let ciphertext = outbound.encrypt("Hello group chat!");
+
+        // The import can decrypt everything the exported session could
+        let mut imported = MegolmInboundSession::import(&inbound.export()).unwrap();
+        assert_eq!(imported.session_id(), inbound.session_id());
+        assert_eq!(imported.first_known_index(), inbound.first_known_index());
+        assert_eq!(imported.decrypt(&ciphertext).unwrap(), "Hello group chat!");
+
+        assert!(MegolmInboundSession::import("not a session").is_err());
+    }
 }
@@ -95,6 +95,49 @@ pub struct IdentityKeyPair {
 /// A one-time key with its ID.
 pub type OneTimeKey = (vodozemac::KeyId, String);
 
+/// Canonical data signed by a device's Ed25519 identity key for its fallback key.
+#[must_use]
+pub fn canonical_fallback_key(
+    identity_key_curve25519: &str,
+    key_id: &str,
+    public_key: &str,
+) -> String {
+    format!("kaiku.fallback_key.v1|{identity_key_curve25519}|{key_id}|{public_key}")
+}
+
+/// A fallback key signed by its device, published alongside one-time prekeys.
+///
+/// Handed out instead of a one-time prekey once a device's pool is empty, so
+/// sessions can still be established with offline devices. Unlike one-time
+/// prekeys it can be used several times, so the device replaces it once used.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SignedFallbackKey {
+    /// Key ID (base64).
+    pub key_id: String,
+    /// Curve25519 public key (base64).
+    pub public_key: String,
+    /// Ed25519 signature by the device's identity key (base64).
+    pub signature: String,
+}
+
+impl SignedFallbackKey {
+    /// Check that the key was signed by the device owning the given identity keys.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CryptoError::InvalidKey`] if a key can't be decoded, or
+    /// [`CryptoError::SignatureInvalid`] if the signature doesn't match.
+    pub fn verify(&self, identity_key_ed25519: &str, identity_key_curve25519: &str) -> Result<()> {
+        Curve25519PublicKey::from_base64(&self.public_key)
+            .map_err(|e| CryptoError::InvalidKey(e.to_string()))?;
+        crate::cross_signing::verify_signature(
+            identity_key_ed25519,
+            &canonical_fallback_key(identity_key_curve25519, &self.key_id, &self.public_key),
+            &self.signature,
+        )
+    }
+}
+
 /// User's Olm account containing identity keys.
 ///
 /// This wraps vodozemac's Account and provides secure key management
@@ -151,7 +194,49 @@ impl OlmAccount {
             .collect()
     }
 
-    /// Mark one-time keys as published.
+    /// Generate a new fallback key, replacing the current one.
+    ///
+    /// The replaced key stays usable for inbound sessions until the next
+    /// call to [`Self::forget_fallback_key`], so messages already in flight
+    /// can still be decrypted.
+    pub fn generate_fallback_key(&mut self) {
+        self.inner.generate_fallback_key();
+    }
+
+    /// Get the unpublished fallback key, signed with our identity key.
+    #[must_use]
+    pub fn fallback_key(&self) -> Option<SignedFallbackKey> {
+        let (key_id, public_key) = self.inner.fallback_key().into_iter().next()?;
+        let key_id = key_id.to_base64();
+        let public_key = public_key.to_base64();
+        let canonical = canonical_fallback_key(
+            &self.inner.curve25519_key().to_base64(),
+            &key_id,
+            &public_key,
+        );
+        Some(SignedFallbackKey {
+            key_id,
+            public_key,
+            signature: self.inner.sign(canonical).to_base64(),
+        })
+    }
+
+    /// Sign `message` with the account's Ed25519 identity key.
+    ///
+    /// Returns the signature (base64).
+    #[must_use]
+    pub fn sign(&self, message: &str) -> String {
+        self.inner.sign(message).to_base64()
+    }
+
+    /// Forget the fallback key replaced by the last [`Self::generate_fallback_key`].
+    ///
+    /// Returns whether there was one to forget.
+    pub fn forget_fallback_key(&mut self) -> bool {
+        self.inner.forget_fallback_key()
+    }
+
+    /// Mark one-time keys (and the fallback key) as published.
     ///
     /// Call this after uploading keys to the server to prevent
     /// them from being returned again.
@@ -323,6 +408,62 @@ mod tests {
         assert_eq!(account.one_time_keys().len(), 0);
     }
 
+    #[test]
+    fn test_fallback_key_is_signed_and_published_once() {
+        let mut account = OlmAccount::new();
+        assert!(account.fallback_key().is_none());
+
+        account.generate_fallback_key();
+        let identity = account.identity_keys();
+        let key = account.fallback_key().unwrap();
+        assert!(key.verify(&identity.ed25519, &identity.curve25519).is_ok());
+
+        // Bound to the device that signed it
+        let other = OlmAccount::new().identity_keys();
+        assert!(key.verify(&identity.ed25519, &other.curve25519).is_err());
+        let mut tampered = key.clone();
+        tampered.key_id = "AAAAAQ".to_string();
+        assert!(tampered
+            .verify(&identity.ed25519, &identity.curve25519)
+            .is_err());
+
+        account.mark_keys_as_published();
+        assert!(account.fallback_key().is_none());
+
+        account.generate_fallback_key();
+        assert_ne!(account.fallback_key().unwrap().public_key, key.public_key);
+        assert!(account.forget_fallback_key());
+        assert!(!account.forget_fallback_key());
+    }
+
+    #[test]
+    fn test_session_with_fallback_key() {
+        let mut alice = OlmAccount::new();
+        let mut bob = OlmAccount::new();
+        bob.generate_fallback_key();
+        let fallback = bob.fallback_key().unwrap();
+        bob.mark_keys_as_published();
+        let fallback_key = Curve25519PublicKey::from_base64(&fallback.public_key).unwrap();
+
+        // The same fallback key serves several senders
+        for _ in 0..2 {
+            let mut session = alice.create_outbound_session(&bob.curve25519_key(), &fallback_key);
+            let message = session.encrypt("hi").into_prekey_message().unwrap();
+            let (_, plaintext) = bob
+                .create_inbound_session(&alice.curve25519_key(), &message)
+                .unwrap();
+            assert_eq!(plaintext, "hi");
+        }
+
+        // Still usable after rotation, until forgotten
+        bob.generate_fallback_key();
+        let mut session = alice.create_outbound_session(&bob.curve25519_key(), &fallback_key);
+        let message = session.encrypt("late").into_prekey_message().unwrap();
+        assert!(bob
+            .create_inbound_session(&alice.curve25519_key(), &message)
+            .is_ok());
+    }
+
     #[test]
     fn test_account_serialization() {
         let account = OlmAccount::new();
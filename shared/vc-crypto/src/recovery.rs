@@ -3,11 +3,17 @@
 //! Provides a user-friendly recovery key for backing up E2EE identity keys.
 //! The recovery key is a 256-bit random value displayed as Base58 for easy
 //! user storage and entry.
+//!
+//! Megolm sessions are backed up one by one: each backup version publishes
+//! [`KeyBackupAuthData`] (the salt for deriving its [`BackupKey`] and a check
+//! value), and every session is encrypted separately into an
+//! [`EncryptedRoomKey`] bound to its room and session ID.
 
-use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
 use aes_gcm::{Aes256Gcm, Nonce};
 use argon2::{Argon2, Params};
 use serde::{Deserialize, Serialize};
+use vodozemac::{base64_decode, base64_encode};
 use zeroize::{ZeroizeOnDrop, Zeroizing};
 
 use crate::{CryptoError, Result};
@@ -15,6 +21,12 @@ use crate::{CryptoError, Result};
 /// Magic bytes for backup verification
 const BACKUP_MAGIC: &[u8] = b"CANIS_KEYS_V1";
 
+/// Algorithm identifier of per-session Megolm key backups.
+pub const MEGOLM_BACKUP_ALGORITHM: &str = "kaiku.megolm_backup.v1";
+
+/// Plaintext encrypted into [`KeyBackupAuthData::key_check`].
+const KEY_CHECK_MAGIC: &[u8] = b"kaiku.megolm_backup.v1|key_check";
+
 /// Recovery key for backing up E2EE identity keys.
 ///
 /// A 256-bit random value, displayed as Base58 for user storage.
@@ -37,6 +49,165 @@ impl AsRef<[u8]> for BackupKey {
     }
 }
 
+impl BackupKey {
+    /// Restore a backup key cached on this device.
+    #[must_use]
+    pub const fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Encrypt an exported Megolm session for the key backup.
+    ///
+    /// The room and session IDs are authenticated with the ciphertext, so a
+    /// server can't swap entries between sessions.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system CSPRNG fails to generate random bytes.
+    #[must_use]
+    pub fn encrypt_room_key(
+        &self,
+        room_id: &str,
+        session_id: &str,
+        data: &[u8],
+    ) -> EncryptedRoomKey {
+        let mut nonce_bytes = [0u8; 12];
+        getrandom::getrandom(&mut nonce_bytes).expect("Failed to generate nonce");
+        let aad = room_key_aad(room_id, session_id);
+        let ciphertext = self
+            .cipher()
+            .encrypt(
+                Nonce::from_slice(&nonce_bytes),
+                Payload {
+                    msg: data,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .expect("Encryption failed");
+
+        EncryptedRoomKey {
+            nonce: base64_encode(nonce_bytes),
+            ciphertext: base64_encode(ciphertext),
+        }
+    }
+
+    /// Decrypt a backed-up Megolm session.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError::DecryptionFailed` if the entry is malformed, was
+    /// encrypted under another key, or belongs to a different room or session.
+    pub fn decrypt_room_key(
+        &self,
+        room_id: &str,
+        session_id: &str,
+        key: &EncryptedRoomKey,
+    ) -> Result<Zeroizing<Vec<u8>>> {
+        let malformed = || CryptoError::DecryptionFailed("Malformed backup entry".into());
+        let nonce = base64_decode(&key.nonce).map_err(|_| malformed())?;
+        if nonce.len() != 12 {
+            return Err(malformed());
+        }
+        let ciphertext = base64_decode(&key.ciphertext).map_err(|_| malformed())?;
+        let aad = room_key_aad(room_id, session_id);
+
+        self.cipher()
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: aad.as_bytes(),
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| {
+                CryptoError::DecryptionFailed(
+                    "Backup entry decryption failed - wrong recovery key?".into(),
+                )
+            })
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new_from_slice(&self.0).expect("Invalid key length")
+    }
+}
+
+fn room_key_aad(room_id: &str, session_id: &str) -> String {
+    format!("{MEGOLM_BACKUP_ALGORITHM}|{room_id}|{session_id}")
+}
+
+/// Public parameters of a Megolm key backup version.
+///
+/// Stored by the server alongside the version so that any device holding the
+/// recovery key can derive the version's [`BackupKey`] and check it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyBackupAuthData {
+    /// Argon2id salt for deriving the backup key (base64).
+    pub salt: String,
+    /// A fixed value encrypted under the backup key (base64 nonce + ciphertext).
+    pub key_check: String,
+}
+
+impl KeyBackupAuthData {
+    /// Set up a new backup version for the recovery key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system CSPRNG fails to generate random bytes.
+    #[must_use]
+    pub fn create(recovery_key: &RecoveryKey) -> (Self, BackupKey) {
+        let mut salt = [0u8; 16];
+        getrandom::getrandom(&mut salt).expect("Failed to generate salt");
+        let backup_key = recovery_key.derive_backup_key(&salt);
+        let check = backup_key.encrypt_room_key("", "", KEY_CHECK_MAGIC);
+
+        let auth_data = Self {
+            salt: base64_encode(salt),
+            key_check: format!("{}.{}", check.nonce, check.ciphertext),
+        };
+        (auth_data, backup_key)
+    }
+
+    /// Derive this version's backup key from the recovery key.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError::DecryptionFailed` if the recovery key doesn't
+    /// match the version, or `CryptoError::InvalidKey` if the auth data is
+    /// malformed.
+    pub fn backup_key(&self, recovery_key: &RecoveryKey) -> Result<BackupKey> {
+        let salt: [u8; 16] = base64_decode(&self.salt)
+            .ok()
+            .and_then(|salt| salt.try_into().ok())
+            .ok_or_else(|| CryptoError::InvalidKey("Invalid backup salt".into()))?;
+        let (nonce, ciphertext) = self
+            .key_check
+            .split_once('.')
+            .ok_or_else(|| CryptoError::InvalidKey("Invalid backup key check".into()))?;
+
+        let backup_key = recovery_key.derive_backup_key(&salt);
+        let check = EncryptedRoomKey {
+            nonce: nonce.to_string(),
+            ciphertext: ciphertext.to_string(),
+        };
+        match backup_key.decrypt_room_key("", "", &check) {
+            Ok(plaintext) if plaintext.as_slice() == KEY_CHECK_MAGIC => Ok(backup_key),
+            _ => Err(CryptoError::DecryptionFailed(
+                "Recovery key does not match this backup".into(),
+            )),
+        }
+    }
+}
+
+/// A Megolm session encrypted under a [`BackupKey`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedRoomKey {
+    /// AES-GCM nonce (base64).
+    pub nonce: String,
+    /// Encrypted exported session (base64).
+    pub ciphertext: String,
+}
+
 impl RecoveryKey {
     /// Generate a new random recovery key using the system CSPRNG.
     pub fn generate() -> Self {
@@ -364,6 +535,35 @@ mod tests {
         assert_eq!(backup.version, 1);
     }
 
+    #[test]
+    fn test_room_key_backup_roundtrip() {
+        let recovery_key = RecoveryKey::generate();
+        let (auth_data, backup_key) = KeyBackupAuthData::create(&recovery_key);
+
+        let entry = backup_key.encrypt_room_key("room", "session", b"exported session");
+        let restored_key = auth_data.backup_key(&recovery_key).unwrap();
+        let decrypted = restored_key
+            .decrypt_room_key("room", "session", &entry)
+            .unwrap();
+        assert_eq!(decrypted.as_slice(), b"exported session");
+
+        // Entries are bound to their room and session
+        assert!(restored_key
+            .decrypt_room_key("other room", "session", &entry)
+            .is_err());
+        assert!(restored_key
+            .decrypt_room_key("room", "other session", &entry)
+            .is_err());
+    }
+
+    #[test]
+    fn test_room_key_backup_wrong_recovery_key() {
+        let (auth_data, _) = KeyBackupAuthData::create(&RecoveryKey::generate());
+
+        let result = auth_data.backup_key(&RecoveryKey::generate());
+        assert!(matches!(result, Err(CryptoError::DecryptionFailed(_))));
+    }
+
     #[test]
     fn test_backup_unique_salts_and_nonces() {
         let recovery_key = RecoveryKey::generate();
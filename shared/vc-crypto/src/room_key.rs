@@ -0,0 +1,189 @@
+//! Room Key Requests
+//!
+//! A device that can't decrypt a Megolm message, because it was added after
+//! the session was shared or missed the key share, asks for the session with
+//! a [`RoomKeyRequest`] sent over the to-device channel. The request is
+//! signed by the requesting device's Ed25519 identity key, so answering
+//! devices can tie it to a device they know. The user's other verified
+//! devices or the session's original sender reply with a
+//! [`ForwardedRoomKey`].
+//!
+//! Both payloads are Olm-encrypted before they are sent; the server only sees
+//! the event type.
+
+use serde::{Deserialize, Serialize};
+use vodozemac::base64_encode;
+
+use crate::olm::OlmAccount;
+use crate::Result;
+
+/// To-device event type of [`RoomKeyRequest`].
+pub const ROOM_KEY_REQUEST_EVENT_TYPE: &str = "kaiku.room_key_request";
+
+/// To-device event type of [`ForwardedRoomKey`].
+pub const FORWARDED_ROOM_KEY_EVENT_TYPE: &str = "kaiku.forwarded_room_key";
+
+/// Canonical data signed by the requesting device for a room key request.
+#[must_use]
+pub fn canonical_room_key_request(
+    request_id: &str,
+    requesting_user_id: &str,
+    requesting_device_id: &str,
+    room_id: &str,
+    sender_key: &str,
+    session_id: &str,
+) -> String {
+    format!(
+        "kaiku.room_key_request.v1|{request_id}|{requesting_user_id}|{requesting_device_id}|{room_id}|{sender_key}|{session_id}"
+    )
+}
+
+/// Request for a Megolm session this device is missing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoomKeyRequest {
+    /// Random ID, echoed in the answer.
+    pub request_id: String,
+    /// The channel or group room ID.
+    pub room_id: String,
+    /// Curve25519 key of the device that created the session (base64).
+    pub sender_key: String,
+    /// Megolm session ID.
+    pub session_id: String,
+    /// User asking for the session.
+    pub requesting_user_id: String,
+    /// Server device ID of the device asking for the session.
+    pub requesting_device_id: String,
+    /// Ed25519 signature by the requesting device's identity key (base64).
+    pub signature: String,
+}
+
+impl RoomKeyRequest {
+    /// Create a request for `session_id`, signed with the account's identity key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the system CSPRNG fails to generate random bytes.
+    #[must_use]
+    pub fn new(
+        account: &OlmAccount,
+        requesting_user_id: &str,
+        requesting_device_id: &str,
+        room_id: &str,
+        sender_key: &str,
+        session_id: &str,
+    ) -> Self {
+        let mut id = [0u8; 16];
+        getrandom::getrandom(&mut id).expect("Failed to generate request ID");
+        let request_id = base64_encode(id);
+
+        let signature = account.sign(&canonical_room_key_request(
+            &request_id,
+            requesting_user_id,
+            requesting_device_id,
+            room_id,
+            sender_key,
+            session_id,
+        ));
+        Self {
+            request_id,
+            room_id: room_id.to_string(),
+            sender_key: sender_key.to_string(),
+            session_id: session_id.to_string(),
+            requesting_user_id: requesting_user_id.to_string(),
+            requesting_device_id: requesting_device_id.to_string(),
+            signature,
+        }
+    }
+
+    /// Check that the request was signed by the device owning `identity_key_ed25519`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`crate::CryptoError::InvalidKey`] if the key can't be decoded,
+    /// or [`crate::CryptoError::SignatureInvalid`] if the signature doesn't match.
+    pub fn verify(&self, identity_key_ed25519: &str) -> Result<()> {
+        crate::cross_signing::verify_signature(
+            identity_key_ed25519,
+            &canonical_room_key_request(
+                &self.request_id,
+                &self.requesting_user_id,
+                &self.requesting_device_id,
+                &self.room_id,
+                &self.sender_key,
+                &self.session_id,
+            ),
+            &self.signature,
+        )
+    }
+}
+
+/// A Megolm session shared in answer to a [`RoomKeyRequest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForwardedRoomKey {
+    /// ID of the request this answers.
+    pub request_id: String,
+    /// The channel or group room ID.
+    pub room_id: String,
+    /// Curve25519 key of the device that created the session (base64).
+    pub sender_key: String,
+    /// Megolm session ID.
+    pub session_id: String,
+    /// Session exported at its first known index (base64).
+    pub session_key: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CryptoError;
+
+    fn request(account: &OlmAccount) -> RoomKeyRequest {
+        RoomKeyRequest::new(account, "user", "device", "room", "sender", "session")
+    }
+
+    #[test]
+    fn test_room_key_request_signature() {
+        let account = OlmAccount::new();
+        let request = request(&account);
+
+        assert_eq!(request.session_id, "session");
+        assert!(request.verify(&account.identity_keys().ed25519).is_ok());
+
+        let other = OlmAccount::new();
+        assert!(matches!(
+            request.verify(&other.identity_keys().ed25519),
+            Err(CryptoError::SignatureInvalid)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_room_key_request_fails() {
+        let account = OlmAccount::new();
+        let key = account.identity_keys().ed25519;
+
+        let mut tampered = request(&account);
+        tampered.session_id = "other-session".into();
+        assert!(tampered.verify(&key).is_err());
+
+        let mut tampered = request(&account);
+        tampered.requesting_device_id = "other-device".into();
+        assert!(tampered.verify(&key).is_err());
+    }
+
+    #[test]
+    fn test_room_key_request_ids_are_unique() {
+        let account = OlmAccount::new();
+        assert_ne!(request(&account).request_id, request(&account).request_id);
+    }
+
+    #[test]
+    fn test_room_key_request_serialization() {
+        let account = OlmAccount::new();
+        let request = request(&account);
+
+        let json = serde_json::to_string(&request).unwrap();
+        let parsed: RoomKeyRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, request);
+        assert!(parsed.verify(&account.identity_keys().ed25519).is_ok());
+    }
+}
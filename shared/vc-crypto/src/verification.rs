@@ -0,0 +1,1019 @@
+//! Interactive Device Verification
+//!
+//! Short Authentication String (SAS) and QR code verification between two
+//! devices, exchanged as [`VerificationMessage`]s over Olm-encrypted
+//! to-device messages.
+//!
+//! SAS flow (Alice starts):
+//!
+//! 1. Alice sends `request`, Bob answers `ready`.
+//! 2. Alice sends `start`; Bob commits to his ephemeral key in `accept`.
+//! 3. Alice sends her `key`, Bob replies with his; Alice checks the commitment.
+//! 4. Both show the same emoji (or decimals); once the users compare them,
+//!    each side sends a `mac` over its identity keys and finishes with `done`.
+//!
+//! QR flow: after `ready`, one device shows [`Verification::qr_code`] and the
+//! other scans it with [`Verification::scan_qr`], replying `reciprocate` with
+//! the code's shared secret.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use vodozemac::sas::{EstablishedSas, Mac, Sas};
+use vodozemac::{base64_decode, base64_encode, Curve25519PublicKey};
+
+use crate::{CryptoError, Result};
+
+/// To-device event type carrying verification messages.
+pub const VERIFICATION_EVENT_TYPE: &str = "m.key.verification";
+
+/// Emoji/decimal short authentication string.
+pub const METHOD_SAS: &str = "sas.v1";
+/// Show a QR code for the other device to scan.
+pub const METHOD_QR_SHOW: &str = "qr.show.v1";
+/// Scan the other device's QR code.
+pub const METHOD_QR_SCAN: &str = "qr.scan.v1";
+
+/// Prefix of encoded QR code payloads.
+const QR_PREFIX: &str = "KAIKU-VERIFY:";
+
+/// Key ID under which a party's master key is MAC'd.
+const MASTER_KEY_ID: &str = "master";
+
+/// The 64 SAS emoji, indexed by [`vodozemac::sas::SasBytes::emoji_indices`].
+const SAS_EMOJI: [(&str, &str); 64] = [
+    ("🐶", "Dog"),
+    ("🐱", "Cat"),
+    ("🦁", "Lion"),
+    ("🐎", "Horse"),
+    ("🦄", "Unicorn"),
+    ("🐷", "Pig"),
+    ("🐘", "Elephant"),
+    ("🐰", "Rabbit"),
+    ("🐼", "Panda"),
+    ("🐓", "Rooster"),
+    ("🐧", "Penguin"),
+    ("🐢", "Turtle"),
+    ("🐟", "Fish"),
+    ("🐙", "Octopus"),
+    ("🦋", "Butterfly"),
+    ("🌷", "Flower"),
+    ("🌳", "Tree"),
+    ("🌵", "Cactus"),
+    ("🍄", "Mushroom"),
+    ("🌏", "Globe"),
+    ("🌙", "Moon"),
+    ("☁️", "Cloud"),
+    ("🔥", "Fire"),
+    ("🍌", "Banana"),
+    ("🍎", "Apple"),
+    ("🍓", "Strawberry"),
+    ("🌽", "Corn"),
+    ("🍕", "Pizza"),
+    ("🎂", "Cake"),
+    ("❤️", "Heart"),
+    ("😀", "Smiley"),
+    ("🤖", "Robot"),
+    ("🎩", "Hat"),
+    ("👓", "Glasses"),
+    ("🔧", "Spanner"),
+    ("🎅", "Santa"),
+    ("👍", "Thumbs Up"),
+    ("☂️", "Umbrella"),
+    ("⌛", "Hourglass"),
+    ("⏰", "Clock"),
+    ("🎁", "Gift"),
+    ("💡", "Light Bulb"),
+    ("📕", "Book"),
+    ("✏️", "Pencil"),
+    ("📎", "Paperclip"),
+    ("✂️", "Scissors"),
+    ("🔒", "Lock"),
+    ("🔑", "Key"),
+    ("🔨", "Hammer"),
+    ("☎️", "Telephone"),
+    ("🏁", "Flag"),
+    ("🚂", "Train"),
+    ("🚲", "Bicycle"),
+    ("✈️", "Aeroplane"),
+    ("🚀", "Rocket"),
+    ("🏆", "Trophy"),
+    ("⚽", "Ball"),
+    ("🎸", "Guitar"),
+    ("🎺", "Trumpet"),
+    ("🔔", "Bell"),
+    ("⚓", "Anchor"),
+    ("🎧", "Headphones"),
+    ("📁", "Folder"),
+    ("📌", "Pin"),
+];
+
+/// One side of a verification.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VerificationParty {
+    /// User ID.
+    pub user_id: String,
+    /// Device ID.
+    pub device_id: String,
+    /// Device Ed25519 identity key (base64).
+    pub device_key: String,
+    /// Cross-signing master key (base64), if the user has one.
+    pub master_key: Option<String>,
+}
+
+impl VerificationParty {
+    /// Key IDs and keys this party proves ownership of.
+    fn keys(&self) -> BTreeMap<String, String> {
+        let mut keys = BTreeMap::from([(
+            format!("ed25519:{}", self.device_id),
+            self.device_key.clone(),
+        )]);
+        if let Some(master_key) = &self.master_key {
+            keys.insert(MASTER_KEY_ID.to_string(), master_key.clone());
+        }
+        keys
+    }
+
+    /// The key a QR code vouches for: the master key if there is one.
+    fn qr_key(&self) -> &str {
+        self.master_key.as_deref().unwrap_or(&self.device_key)
+    }
+
+    fn qr_key_id(&self) -> String {
+        if self.master_key.is_some() {
+            MASTER_KEY_ID.to_string()
+        } else {
+            format!("ed25519:{}", self.device_id)
+        }
+    }
+}
+
+/// Why a verification was cancelled.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CancelCode {
+    /// A user cancelled or said the emoji didn't match.
+    User,
+    /// The other side took too long.
+    Timeout,
+    /// No common verification method.
+    UnknownMethod,
+    /// A message arrived out of order.
+    UnexpectedMessage,
+    /// The other side's key didn't match its commitment.
+    MismatchedCommitment,
+    /// A MAC or QR code didn't match the expected keys.
+    KeyMismatch,
+    /// A message was malformed.
+    InvalidMessage,
+}
+
+/// A verification protocol message.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VerificationMessage {
+    /// Ask another device to verify.
+    Request {
+        transaction_id: String,
+        methods: Vec<String>,
+    },
+    /// Agree to verify with the given methods.
+    Ready {
+        transaction_id: String,
+        methods: Vec<String>,
+    },
+    /// Begin SAS verification.
+    Start {
+        transaction_id: String,
+        method: String,
+    },
+    /// Accept SAS, committing to the ephemeral key sent later.
+    Accept {
+        transaction_id: String,
+        commitment: String,
+    },
+    /// Ephemeral Curve25519 key for the SAS key agreement.
+    Key { transaction_id: String, key: String },
+    /// MACs of the sender's identity keys, keyed by key ID, plus a MAC of the key ID list.
+    Mac {
+        transaction_id: String,
+        mac: BTreeMap<String, String>,
+        keys: String,
+    },
+    /// Shared secret read from the other device's QR code.
+    Reciprocate {
+        transaction_id: String,
+        secret: String,
+    },
+    /// Verification finished on the sender's side.
+    Done { transaction_id: String },
+    /// Verification abandoned.
+    Cancel {
+        transaction_id: String,
+        code: CancelCode,
+        reason: String,
+    },
+}
+
+impl VerificationMessage {
+    /// The transaction this message belongs to.
+    #[must_use]
+    pub fn transaction_id(&self) -> &str {
+        match self {
+            Self::Request { transaction_id, .. }
+            | Self::Ready { transaction_id, .. }
+            | Self::Start { transaction_id, .. }
+            | Self::Accept { transaction_id, .. }
+            | Self::Key { transaction_id, .. }
+            | Self::Mac { transaction_id, .. }
+            | Self::Reciprocate { transaction_id, .. }
+            | Self::Done { transaction_id }
+            | Self::Cancel { transaction_id, .. } => transaction_id,
+        }
+    }
+}
+
+/// Progress of a verification.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum VerificationState {
+    /// Request sent or received, waiting for `ready`.
+    Requested,
+    /// Both sides are ready; SAS can start or a QR code can be shown.
+    Ready,
+    /// SAS started, ephemeral keys not yet exchanged.
+    Started,
+    /// SAS established; emoji can be compared.
+    KeysExchanged,
+    /// We confirmed the emoji match and are waiting for the other MAC.
+    Confirmed,
+    /// The other device is verified.
+    Done,
+    /// Verification was cancelled by either side.
+    Cancelled { code: CancelCode },
+}
+
+/// A single SAS emoji.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SasEmoji {
+    /// The emoji itself.
+    pub symbol: String,
+    /// English description, for accessibility and comparison by voice.
+    pub description: String,
+}
+
+/// Payload of a verification QR code.
+#[derive(Debug, Serialize, Deserialize)]
+struct QrCodeData {
+    transaction_id: String,
+    /// The displaying user's key.
+    key: String,
+    /// The key the displaying device expects the scanner to have.
+    other_key: String,
+    secret: String,
+}
+
+/// An in-progress verification with one other device.
+pub struct Verification {
+    transaction_id: String,
+    own: VerificationParty,
+    other: VerificationParty,
+    we_requested: bool,
+    methods: Vec<String>,
+    state: VerificationState,
+    sas: Option<Sas>,
+    sas_started_by_us: bool,
+    our_sas_key: Option<String>,
+    their_sas_key: Option<String>,
+    their_commitment: Option<String>,
+    established: Option<EstablishedSas>,
+    their_mac: Option<(BTreeMap<String, String>, String)>,
+    qr_secret: Option<String>,
+    verified_keys: BTreeMap<String, String>,
+}
+
+impl Verification {
+    fn new(
+        transaction_id: String,
+        own: VerificationParty,
+        other: VerificationParty,
+        we_requested: bool,
+    ) -> Self {
+        Self {
+            transaction_id,
+            own,
+            other,
+            we_requested,
+            methods: supported_methods(),
+            state: VerificationState::Requested,
+            sas: None,
+            sas_started_by_us: false,
+            our_sas_key: None,
+            their_sas_key: None,
+            their_commitment: None,
+            established: None,
+            their_mac: None,
+            qr_secret: None,
+            verified_keys: BTreeMap::new(),
+        }
+    }
+
+    /// Start a verification with another device.
+    ///
+    /// Returns the verification and the `request` message to send.
+    pub fn request(
+        own: VerificationParty,
+        other: VerificationParty,
+    ) -> Result<(Self, VerificationMessage)> {
+        let transaction_id = random_base64::<16>()?;
+        let message = VerificationMessage::Request {
+            transaction_id: transaction_id.clone(),
+            methods: supported_methods(),
+        };
+        Ok((Self::new(transaction_id, own, other, true), message))
+    }
+
+    /// Create the receiving side of a verification from a `request` message.
+    pub fn from_request(
+        own: VerificationParty,
+        other: VerificationParty,
+        message: &VerificationMessage,
+    ) -> Result<Self> {
+        let VerificationMessage::Request {
+            transaction_id,
+            methods,
+        } = message
+        else {
+            return Err(CryptoError::Verification(
+                "Expected a verification request".to_string(),
+            ));
+        };
+        let mut verification = Self::new(transaction_id.clone(), own, other, false);
+        verification.methods.retain(|m| methods.contains(m));
+        Ok(verification)
+    }
+
+    /// Transaction ID shared by both devices.
+    #[must_use]
+    pub fn transaction_id(&self) -> &str {
+        &self.transaction_id
+    }
+
+    /// The device being verified.
+    #[must_use]
+    pub const fn other(&self) -> &VerificationParty {
+        &self.other
+    }
+
+    /// Current progress.
+    #[must_use]
+    pub const fn state(&self) -> VerificationState {
+        self.state
+    }
+
+    /// Methods both devices support.
+    #[must_use]
+    pub fn methods(&self) -> &[String] {
+        &self.methods
+    }
+
+    /// Whether the verification has finished or been cancelled.
+    #[must_use]
+    pub const fn is_finished(&self) -> bool {
+        matches!(
+            self.state,
+            VerificationState::Done | VerificationState::Cancelled { .. }
+        )
+    }
+
+    /// The other party's keys proven by a successful verification, keyed by
+    /// key ID (`ed25519:<device_id>` or `master`). Empty until [`VerificationState::Done`].
+    #[must_use]
+    pub const fn verified_keys(&self) -> &BTreeMap<String, String> {
+        &self.verified_keys
+    }
+
+    /// Accept an incoming request.
+    pub fn accept(&mut self) -> Result<VerificationMessage> {
+        if self.we_requested || self.state != VerificationState::Requested {
+            return Err(CryptoError::Verification(
+                "Only a pending incoming request can be accepted".to_string(),
+            ));
+        }
+        if self.methods.is_empty() {
+            return Ok(self.cancel(CancelCode::UnknownMethod));
+        }
+        self.state = VerificationState::Ready;
+        Ok(VerificationMessage::Ready {
+            transaction_id: self.transaction_id.clone(),
+            methods: self.methods.clone(),
+        })
+    }
+
+    /// Begin SAS verification once both sides are ready.
+    pub fn start_sas(&mut self) -> Result<VerificationMessage> {
+        if self.state != VerificationState::Ready || !self.methods.iter().any(|m| m == METHOD_SAS) {
+            return Err(CryptoError::Verification(
+                "SAS can only start once both devices are ready".to_string(),
+            ));
+        }
+        self.begin_sas(true);
+        Ok(VerificationMessage::Start {
+            transaction_id: self.transaction_id.clone(),
+            method: METHOD_SAS.to_string(),
+        })
+    }
+
+    /// Cancel the verification, returning the `cancel` message to send.
+    pub fn cancel(&mut self, code: CancelCode) -> VerificationMessage {
+        self.state = VerificationState::Cancelled { code };
+        self.verified_keys.clear();
+        VerificationMessage::Cancel {
+            transaction_id: self.transaction_id.clone(),
+            code,
+            reason: cancel_reason(code).to_string(),
+        }
+    }
+
+    /// Process a message from the other device.
+    ///
+    /// Returns the reply to send, if any. Protocol violations cancel the
+    /// verification and return the `cancel` message.
+    pub fn receive(
+        &mut self,
+        message: &VerificationMessage,
+    ) -> Result<Option<VerificationMessage>> {
+        if message.transaction_id() != self.transaction_id {
+            return Err(CryptoError::Verification(
+                "Message belongs to a different verification".to_string(),
+            ));
+        }
+        if let VerificationMessage::Cancel { code, .. } = message {
+            self.state = VerificationState::Cancelled { code: *code };
+            self.verified_keys.clear();
+            return Ok(None);
+        }
+        if self.is_finished() {
+            return Ok(None);
+        }
+
+        let reply = match (self.state, message) {
+            (VerificationState::Requested, VerificationMessage::Ready { methods, .. })
+                if self.we_requested =>
+            {
+                self.methods.retain(|m| methods.contains(m));
+                if self.methods.is_empty() {
+                    return Ok(Some(self.cancel(CancelCode::UnknownMethod)));
+                }
+                self.state = VerificationState::Ready;
+                None
+            }
+            (VerificationState::Ready, VerificationMessage::Start { method, .. }) => {
+                if method != METHOD_SAS || !self.methods.iter().any(|m| m == METHOD_SAS) {
+                    return Ok(Some(self.cancel(CancelCode::UnknownMethod)));
+                }
+                self.begin_sas(false);
+                Some(VerificationMessage::Accept {
+                    transaction_id: self.transaction_id.clone(),
+                    commitment: commitment(self.our_sas_key(), &self.transaction_id),
+                })
+            }
+            (VerificationState::Started, VerificationMessage::Accept { commitment, .. })
+                if self.sas_started_by_us && self.their_commitment.is_none() =>
+            {
+                self.their_commitment = Some(commitment.clone());
+                Some(self.key_message())
+            }
+            (VerificationState::Started, VerificationMessage::Key { key, .. }) => {
+                if self.sas_started_by_us {
+                    let Some(expected) = &self.their_commitment else {
+                        return Ok(Some(self.cancel(CancelCode::UnexpectedMessage)));
+                    };
+                    if *expected != commitment(key, &self.transaction_id) {
+                        return Ok(Some(self.cancel(CancelCode::MismatchedCommitment)));
+                    }
+                }
+                if !self.establish(key) {
+                    return Ok(Some(self.cancel(CancelCode::InvalidMessage)));
+                }
+                // The accepting side sends its key only after receiving the starter's
+                (!self.sas_started_by_us).then(|| self.key_message())
+            }
+            (
+                VerificationState::KeysExchanged | VerificationState::Confirmed,
+                VerificationMessage::Mac { mac, keys, .. },
+            ) => {
+                self.their_mac = Some((mac.clone(), keys.clone()));
+                if self.state == VerificationState::Confirmed {
+                    Some(self.check_their_mac())
+                } else {
+                    None
+                }
+            }
+            (VerificationState::Ready, VerificationMessage::Reciprocate { secret, .. }) => {
+                let Some(expected) = &self.qr_secret else {
+                    return Ok(Some(self.cancel(CancelCode::UnexpectedMessage)));
+                };
+                if expected != secret {
+                    return Ok(Some(self.cancel(CancelCode::KeyMismatch)));
+                }
+                // The scanner proved it saw the key we expect it to have
+                self.verified_keys =
+                    BTreeMap::from([(self.other.qr_key_id(), self.other.qr_key().to_string())]);
+                self.state = VerificationState::Done;
+                Some(self.done_message())
+            }
+            // The other side finishing first is informational
+            (_, VerificationMessage::Done { .. }) => None,
+            _ => Some(self.cancel(CancelCode::UnexpectedMessage)),
+        };
+        Ok(reply)
+    }
+
+    /// The seven SAS emoji, once the key agreement has completed.
+    #[must_use]
+    pub fn emojis(&self) -> Option<Vec<SasEmoji>> {
+        let bytes = self.established.as_ref()?.bytes(&self.sas_info());
+        Some(
+            bytes
+                .emoji_indices()
+                .iter()
+                .map(|&i| {
+                    let (symbol, description) = SAS_EMOJI[usize::from(i)];
+                    SasEmoji {
+                        symbol: symbol.to_string(),
+                        description: description.to_string(),
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// The three SAS decimals (1000-9191), for devices that can't show emoji.
+    #[must_use]
+    pub fn decimals(&self) -> Option<(u16, u16, u16)> {
+        Some(
+            self.established
+                .as_ref()?
+                .bytes(&self.sas_info())
+                .decimals(),
+        )
+    }
+
+    /// Confirm that the emoji match on both devices.
+    ///
+    /// Returns our `mac`, followed by `done` (or `cancel`) if the other
+    /// side's MAC has already arrived.
+    pub fn confirm(&mut self) -> Result<Vec<VerificationMessage>> {
+        let Some(established) = &self.established else {
+            return Err(CryptoError::Verification(
+                "Nothing to confirm before the SAS keys are exchanged".to_string(),
+            ));
+        };
+        if self.state != VerificationState::KeysExchanged {
+            return Err(CryptoError::Verification(
+                "Verification was already confirmed or finished".to_string(),
+            ));
+        }
+
+        let keys = self.own.keys();
+        let mac = keys
+            .iter()
+            .map(|(key_id, key)| {
+                let info = mac_info(&self.own, &self.other, &self.transaction_id, key_id);
+                (
+                    key_id.clone(),
+                    established.calculate_mac(key, &info).to_base64(),
+                )
+            })
+            .collect();
+        let key_ids = keys.keys().cloned().collect::<Vec<_>>().join(",");
+        let keys_mac = established
+            .calculate_mac(
+                &key_ids,
+                &mac_info(&self.own, &self.other, &self.transaction_id, "KEY_IDS"),
+            )
+            .to_base64();
+
+        self.state = VerificationState::Confirmed;
+        let mut messages = vec![VerificationMessage::Mac {
+            transaction_id: self.transaction_id.clone(),
+            mac,
+            keys: keys_mac,
+        }];
+        if self.their_mac.is_some() {
+            messages.push(self.check_their_mac());
+        }
+        Ok(messages)
+    }
+
+    /// Encode a QR code for the other device to scan.
+    pub fn qr_code(&mut self) -> Result<String> {
+        if self.state != VerificationState::Ready
+            || !self.methods.iter().any(|m| m == METHOD_QR_SHOW)
+        {
+            return Err(CryptoError::Verification(
+                "A QR code can only be shown once both devices are ready".to_string(),
+            ));
+        }
+        let secret = random_base64::<16>()?;
+        let data = QrCodeData {
+            transaction_id: self.transaction_id.clone(),
+            key: self.own.qr_key().to_string(),
+            other_key: self.other.qr_key().to_string(),
+            secret: secret.clone(),
+        };
+        self.qr_secret = Some(secret);
+        let json =
+            serde_json::to_vec(&data).map_err(|e| CryptoError::Serialization(e.to_string()))?;
+        Ok(format!("{QR_PREFIX}{}", base64_encode(json)))
+    }
+
+    /// Check a QR code shown by the other device.
+    ///
+    /// On success the other device is verified and the `reciprocate` message
+    /// is returned; otherwise the verification is cancelled.
+    pub fn scan_qr(&mut self, code: &str) -> Result<VerificationMessage> {
+        if self.state != VerificationState::Ready {
+            return Err(CryptoError::Verification(
+                "A QR code can only be scanned once both devices are ready".to_string(),
+            ));
+        }
+        let Some(data) = code
+            .strip_prefix(QR_PREFIX)
+            .and_then(|encoded| base64_decode(encoded).ok())
+            .and_then(|json| serde_json::from_slice::<QrCodeData>(&json).ok())
+        else {
+            return Ok(self.cancel(CancelCode::InvalidMessage));
+        };
+        if data.transaction_id != self.transaction_id
+            || data.key != self.other.qr_key()
+            || data.other_key != self.own.qr_key()
+        {
+            return Ok(self.cancel(CancelCode::KeyMismatch));
+        }
+
+        self.verified_keys =
+            BTreeMap::from([(self.other.qr_key_id(), self.other.qr_key().to_string())]);
+        self.state = VerificationState::Done;
+        Ok(VerificationMessage::Reciprocate {
+            transaction_id: self.transaction_id.clone(),
+            secret: data.secret,
+        })
+    }
+
+    fn begin_sas(&mut self, started_by_us: bool) {
+        let sas = Sas::new();
+        self.our_sas_key = Some(sas.public_key().to_base64());
+        self.sas = Some(sas);
+        self.sas_started_by_us = started_by_us;
+        self.state = VerificationState::Started;
+    }
+
+    fn our_sas_key(&self) -> &str {
+        self.our_sas_key.as_deref().unwrap_or_default()
+    }
+
+    fn key_message(&self) -> VerificationMessage {
+        VerificationMessage::Key {
+            transaction_id: self.transaction_id.clone(),
+            key: self.our_sas_key().to_string(),
+        }
+    }
+
+    fn done_message(&self) -> VerificationMessage {
+        VerificationMessage::Done {
+            transaction_id: self.transaction_id.clone(),
+        }
+    }
+
+    /// Complete the key agreement. Returns `false` if the key is invalid.
+    fn establish(&mut self, their_key: &str) -> bool {
+        let Some(sas) = self.sas.take() else {
+            return false;
+        };
+        let Ok(key) = Curve25519PublicKey::from_base64(their_key) else {
+            return false;
+        };
+        let Ok(established) = sas.diffie_hellman(key) else {
+            return false;
+        };
+        self.their_sas_key = Some(their_key.to_string());
+        self.established = Some(established);
+        self.state = VerificationState::KeysExchanged;
+        true
+    }
+
+    /// SAS info string, ordered starter first so both sides derive the same bytes.
+    fn sas_info(&self) -> String {
+        let our_key = self.our_sas_key();
+        let their_key = self.their_sas_key.as_deref().unwrap_or_default();
+        let ((starter, starter_key), (accepter, accepter_key)) = if self.sas_started_by_us {
+            ((&self.own, our_key), (&self.other, their_key))
+        } else {
+            ((&self.other, their_key), (&self.own, our_key))
+        };
+        format!(
+            "KAIKU_SAS_V1|{}|{}|{starter_key}|{}|{}|{accepter_key}|{}",
+            starter.user_id,
+            starter.device_id,
+            accepter.user_id,
+            accepter.device_id,
+            self.transaction_id
+        )
+    }
+
+    /// Verify the other side's MAC after we've confirmed, finishing or cancelling.
+    fn check_their_mac(&mut self) -> VerificationMessage {
+        let (Some(established), Some((mac, keys_mac))) = (&self.established, &self.their_mac)
+        else {
+            return self.cancel(CancelCode::UnexpectedMessage);
+        };
+
+        let expected_keys = self.other.keys();
+        let device_key_id = format!("ed25519:{}", self.other.device_id);
+        let key_ids = mac.keys().cloned().collect::<Vec<_>>().join(",");
+        let info = |key_id: &str| mac_info(&self.other, &self.own, &self.transaction_id, key_id);
+        let verify = |input: &str, key_id: &str, tag: &str| {
+            Mac::from_base64(tag)
+                .ok()
+                .is_some_and(|tag| established.verify_mac(input, &info(key_id), &tag).is_ok())
+        };
+
+        let valid = mac.contains_key(&device_key_id)
+            && verify(&key_ids, "KEY_IDS", keys_mac)
+            && mac.iter().all(|(key_id, tag)| {
+                expected_keys
+                    .get(key_id)
+                    .is_some_and(|key| verify(key, key_id, tag))
+            });
+        if !valid {
+            return self.cancel(CancelCode::KeyMismatch);
+        }
+
+        self.verified_keys = mac
+            .keys()
+            .filter_map(|key_id| Some((key_id.clone(), expected_keys.get(key_id)?.clone())))
+            .collect();
+        self.state = VerificationState::Done;
+        self.done_message()
+    }
+}
+
+fn supported_methods() -> Vec<String> {
+    [METHOD_SAS, METHOD_QR_SHOW, METHOD_QR_SCAN]
+        .map(String::from)
+        .to_vec()
+}
+
+const fn cancel_reason(code: CancelCode) -> &'static str {
+    match code {
+        CancelCode::User => "The user cancelled the verification",
+        CancelCode::Timeout => "The verification timed out",
+        CancelCode::UnknownMethod => "No common verification method",
+        CancelCode::UnexpectedMessage => "Received an unexpected message",
+        CancelCode::MismatchedCommitment => "The key did not match the commitment",
+        CancelCode::KeyMismatch => "The keys did not match",
+        CancelCode::InvalidMessage => "Received an invalid message",
+    }
+}
+
+fn commitment(sas_key: &str, transaction_id: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(sas_key.as_bytes());
+    hasher.update(b"|");
+    hasher.update(transaction_id.as_bytes());
+    base64_encode(hasher.finalize())
+}
+
+fn mac_info(
+    sender: &VerificationParty,
+    receiver: &VerificationParty,
+    transaction_id: &str,
+    key_id: &str,
+) -> String {
+    format!(
+        "KAIKU_SAS_MAC_V1|{}|{}|{}|{}|{transaction_id}|{key_id}",
+        sender.user_id, sender.device_id, receiver.user_id, receiver.device_id
+    )
+}
+
+fn random_base64<const N: usize>() -> Result<String> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| CryptoError::Verification(format!("Random generation failed: {e}")))?;
+    Ok(base64_encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn party(user_id: &str, device_id: &str, with_master: bool) -> VerificationParty {
+        VerificationParty {
+            user_id: user_id.to_string(),
+            device_id: device_id.to_string(),
+            device_key: format!("{device_id}-ed25519"),
+            master_key: with_master.then(|| format!("{user_id}-master")),
+        }
+    }
+
+    fn ready_pair() -> (Verification, Verification) {
+        let alice = party("alice", "laptop", true);
+        let bob = party("bob", "phone", false);
+        let (mut alice_side, request) = Verification::request(alice.clone(), bob.clone()).unwrap();
+        let mut bob_side = Verification::from_request(bob, alice, &request).unwrap();
+        let ready = bob_side.accept().unwrap();
+        assert_eq!(alice_side.receive(&ready).unwrap(), None);
+        assert_eq!(alice_side.state(), VerificationState::Ready);
+        (alice_side, bob_side)
+    }
+
+    /// Deliver `message` and return the single reply.
+    fn exchange(to: &mut Verification, message: &VerificationMessage) -> VerificationMessage {
+        to.receive(message).unwrap().expect("reply")
+    }
+
+    fn sas_pair() -> (Verification, Verification) {
+        let (mut alice, mut bob) = ready_pair();
+        let start = alice.start_sas().unwrap();
+        let accept = exchange(&mut bob, &start);
+        let alice_key = exchange(&mut alice, &accept);
+        let bob_key = exchange(&mut bob, &alice_key);
+        assert_eq!(alice.receive(&bob_key).unwrap(), None);
+        (alice, bob)
+    }
+
+    #[test]
+    fn test_sas_emoji_and_decimals_match() {
+        let (alice, bob) = sas_pair();
+        assert_eq!(alice.state(), VerificationState::KeysExchanged);
+        assert_eq!(bob.state(), VerificationState::KeysExchanged);
+
+        let emojis = alice.emojis().unwrap();
+        assert_eq!(emojis.len(), 7);
+        assert_eq!(emojis, bob.emojis().unwrap());
+        assert_eq!(alice.decimals(), bob.decimals());
+    }
+
+    #[test]
+    fn test_sas_confirmation_verifies_both_sides() {
+        let (mut alice, mut bob) = sas_pair();
+
+        let alice_messages = alice.confirm().unwrap();
+        assert_eq!(alice_messages.len(), 1);
+        // Bob hasn't confirmed yet, so Alice's MAC is held
+        assert_eq!(bob.receive(&alice_messages[0]).unwrap(), None);
+
+        let bob_messages = bob.confirm().unwrap();
+        assert!(matches!(
+            bob_messages.as_slice(),
+            [
+                VerificationMessage::Mac { .. },
+                VerificationMessage::Done { .. }
+            ]
+        ));
+        assert_eq!(bob.state(), VerificationState::Done);
+        assert_eq!(
+            bob.verified_keys().get("master").map(String::as_str),
+            Some("alice-master")
+        );
+        assert!(bob.verified_keys().contains_key("ed25519:laptop"));
+
+        let done = exchange(&mut alice, &bob_messages[0]);
+        assert!(matches!(done, VerificationMessage::Done { .. }));
+        assert_eq!(alice.state(), VerificationState::Done);
+        assert_eq!(
+            alice.verified_keys().keys().collect::<Vec<_>>(),
+            ["ed25519:phone"]
+        );
+        assert_eq!(alice.receive(&bob_messages[1]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_mac_for_unexpected_key_cancels() {
+        let (mut alice, mut bob) = sas_pair();
+        // Bob knows a different master key for Alice than the one she proves
+        bob.other.master_key = Some("someone-else".to_string());
+
+        let alice_mac = alice.confirm().unwrap().remove(0);
+        bob.receive(&alice_mac).unwrap();
+        let bob_messages = bob.confirm().unwrap();
+        assert!(matches!(
+            bob_messages.last(),
+            Some(VerificationMessage::Cancel {
+                code: CancelCode::KeyMismatch,
+                ..
+            })
+        ));
+        assert!(bob.verified_keys().is_empty());
+    }
+
+    #[test]
+    fn test_commitment_mismatch_cancels() {
+        let (mut alice, mut bob) = ready_pair();
+        let start = alice.start_sas().unwrap();
+        exchange(&mut bob, &start);
+        let forged_accept = VerificationMessage::Accept {
+            transaction_id: alice.transaction_id().to_string(),
+            commitment: commitment("other-key", alice.transaction_id()),
+        };
+        let alice_key = exchange(&mut alice, &forged_accept);
+        let bob_key = exchange(&mut bob, &alice_key);
+
+        let reply = exchange(&mut alice, &bob_key);
+        assert!(matches!(
+            reply,
+            VerificationMessage::Cancel {
+                code: CancelCode::MismatchedCommitment,
+                ..
+            }
+        ));
+        assert!(alice.is_finished());
+    }
+
+    #[test]
+    fn test_qr_verification() {
+        let (mut alice, mut bob) = ready_pair();
+        let code = alice.qr_code().unwrap();
+
+        let reciprocate = bob.scan_qr(&code).unwrap();
+        assert_eq!(bob.state(), VerificationState::Done);
+        assert_eq!(
+            bob.verified_keys().get("master").map(String::as_str),
+            Some("alice-master")
+        );
+
+        let done = exchange(&mut alice, &reciprocate);
+        assert!(matches!(done, VerificationMessage::Done { .. }));
+        assert_eq!(
+            alice
+                .verified_keys()
+                .get("ed25519:phone")
+                .map(String::as_str),
+            Some("phone-ed25519")
+        );
+    }
+
+    #[test]
+    fn test_qr_for_wrong_key_cancels() {
+        let (mut alice, mut bob) = ready_pair();
+        alice.own.master_key = Some("impostor-master".to_string());
+        let code = alice.qr_code().unwrap();
+
+        let reply = bob.scan_qr(&code).unwrap();
+        assert!(matches!(
+            reply,
+            VerificationMessage::Cancel {
+                code: CancelCode::KeyMismatch,
+                ..
+            }
+        ));
+        assert!(matches!(
+            bob.scan_qr("garbage"),
+            Err(CryptoError::Verification(_))
+        ));
+    }
+
+    #[test]
+    fn test_out_of_order_and_foreign_messages() {
+        let (mut alice, _) = ready_pair();
+        let foreign = VerificationMessage::Done {
+            transaction_id: "other".to_string(),
+        };
+        assert!(alice.receive(&foreign).is_err());
+
+        let early_key = VerificationMessage::Key {
+            transaction_id: alice.transaction_id().to_string(),
+            key: "key".to_string(),
+        };
+        let reply = exchange(&mut alice, &early_key);
+        assert!(matches!(
+            reply,
+            VerificationMessage::Cancel {
+                code: CancelCode::UnexpectedMessage,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_message_wire_format() {
+        let message = VerificationMessage::Cancel {
+            transaction_id: "tx".to_string(),
+            code: CancelCode::User,
+            reason: "no".to_string(),
+        };
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["type"], "cancel");
+        assert_eq!(json["code"], "user");
+        assert_eq!(
+            serde_json::to_value(VerificationState::Cancelled {
+                code: CancelCode::Timeout
+            })
+            .unwrap(),
+            serde_json::json!({ "state": "cancelled", "code": "timeout" })
+        );
+    }
+}